@@ -0,0 +1,134 @@
+//! Scanner and parser benchmarks on large generated programs.
+//!
+//! Run with `cargo bench`; pass a substring as argument to only run matching benchmarks, e.g.:
+//!
+//! ```text
+//! cargo bench -- parse
+//! ```
+
+use nexus_rs::{parser::Parser, scanner::Scanner, source_line::SourceLine, token::Tokens};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/// Minimal duration to spend on measuring a single benchmark.
+const MEASUREMENT_TIME: Duration = Duration::from_secs(2);
+
+/// Number of samples per benchmark (the median sample is reported).
+const SAMPLES: usize = 20;
+
+/// Generate a program of many simple declarations and statements.
+fn gen_statements(count: usize) -> Vec<String> {
+    (0..count)
+        .map(|i| match i % 4 {
+            0 => format!("let mut x{i}: Number = {i} * 2 + (3 - {i}) / 4;"),
+            1 => format!("x{} = x{} + 1 == 3 && !true;", i - 1, i - 1),
+            2 => format!("print x{} % 7;", i - 2),
+            _ => format!("fn f{i}(a: Number, b: String) {{ return a <= 3; }}"),
+        })
+        .collect()
+}
+
+/// Generate a program of string variables holding long literals.
+fn gen_long_strings(count: usize, length: usize) -> Vec<String> {
+    (0..count)
+        .map(|i| format!("let s{i} = \"{}\";", "abcdéfgh ".repeat(length / 9)))
+        .collect()
+}
+
+/// Generate a program of deeply nested blocks and group expressions.
+fn gen_deep_nesting(depth: usize) -> Vec<String> {
+    vec![format!(
+        "{}let x = {}1{};{}",
+        "{ ".repeat(depth),
+        "(".repeat(depth),
+        ")".repeat(depth),
+        " }".repeat(depth)
+    )]
+}
+
+fn scan(lines: &[String]) -> Tokens {
+    let mut scanner = Scanner::new();
+
+    lines
+        .iter()
+        .enumerate()
+        .flat_map(|(n, line)| {
+            let (tokens, errors) = scanner.scan(SourceLine {
+                line: line.clone(),
+                number: Some(n + 1),
+            });
+            assert!(errors.is_empty(), "scan error in generated program");
+            tokens
+        })
+        .collect()
+}
+
+/// Run a benchmark and report the median time per iteration, and the throughput in bytes of source code.
+fn bench<T>(filter: &Option<String>, name: &str, bytes: usize, mut f: impl FnMut() -> T) {
+    if filter.as_ref().is_some_and(|p| !name.contains(p.as_str())) {
+        return;
+    }
+
+    // Warm up, and determine the number of iterations per sample.
+    let start = Instant::now();
+    let mut warmup_iters = 0u32;
+    while start.elapsed() < MEASUREMENT_TIME / 10 {
+        black_box(f());
+        warmup_iters += 1;
+    }
+    let iters = ((MEASUREMENT_TIME / SAMPLES as u32).as_secs_f64()
+        / (start.elapsed().as_secs_f64() / warmup_iters as f64))
+        .ceil()
+        .max(1.0) as u32;
+
+    let mut samples = (0..SAMPLES)
+        .map(|_| {
+            let start = Instant::now();
+            (0..iters).for_each(|_| {
+                black_box(f());
+            });
+            start.elapsed() / iters
+        })
+        .collect::<Vec<_>>();
+    samples.sort();
+
+    let median = samples[SAMPLES / 2];
+    println!(
+        "{name:<32} {:>12.3?}/iter  [{:.3?} .. {:.3?}]  {:>8.1} MiB/s",
+        median,
+        samples[0],
+        samples[SAMPLES - 1],
+        bytes as f64 / median.as_secs_f64() / (1024.0 * 1024.0)
+    );
+}
+
+fn main() {
+    // Skip over arguments passed by cargo (e.g. '--bench').
+    let filter = std::env::args().skip(1).find(|a| !a.starts_with("--"));
+
+    let programs = [
+        ("statements_10k", gen_statements(10_000)),
+        ("long_strings_1k", gen_long_strings(1_000, 4096)),
+        // NOTE: The parser limits the nesting depth, so this is about the deepest nesting accepted.
+        ("deep_nesting_50", gen_deep_nesting(50)),
+    ];
+
+    for (name, lines) in &programs {
+        let bytes = lines.iter().map(|l| l.len()).sum();
+
+        bench(&filter, &format!("scan/{name}"), bytes, || scan(lines));
+
+        let tokens = scan(lines);
+        bench(&filter, &format!("parse/{name}"), bytes, || {
+            Parser::new(tokens.clone())
+                .parse()
+                .expect("parse error in generated program")
+        });
+
+        bench(&filter, &format!("scan+parse/{name}"), bytes, || {
+            Parser::new(scan(lines))
+                .parse()
+                .expect("parse error in generated program")
+        });
+    }
+}
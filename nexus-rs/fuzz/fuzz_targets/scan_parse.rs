@@ -0,0 +1,16 @@
+//! Fuzz target feeding arbitrary bytes into the scanner and parser, run with:
+//!
+//! ```text
+//! cargo +nightly fuzz run scan_parse
+//! ```
+//!
+//! Minimized crashing inputs (`cargo fuzz tmin`) should be added to `REGRESSIONS` in `tests/fuzz_tests.rs`.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nexus_rs::front::parse_source;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = parse_source(&String::from_utf8_lossy(data));
+});
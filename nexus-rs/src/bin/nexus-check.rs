@@ -0,0 +1,108 @@
+use clap::Parser;
+use colored::Colorize;
+use nexus_rs::{
+    call_graph::{CallGraph, ImportGraph},
+    cli::{self, GlobalArgs},
+    driver::{Driver, DriverOptions, ErrorFormat, Severity, Stage},
+};
+use std::process::exit;
+
+/// Nexus programming language checker, with call graph and import graph analysis.
+#[derive(Parser)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    /// Input source filename ('-' reads from standard input).
+    #[arg(short, long)]
+    filename: String,
+
+    /// Output the function call graph (DOT), reporting unreachable functions and recursive cycles.
+    #[arg(long)]
+    call_graph: bool,
+
+    /// Output the module import graph (DOT), reporting cyclic imports.
+    #[arg(long)]
+    import_graph: bool,
+
+    /// Diagnostics output format.
+    #[arg(long, value_enum, default_value_t = ErrorFormat::Human)]
+    error_format: ErrorFormat,
+
+    #[command(flatten)]
+    global: GlobalArgs,
+}
+
+fn main() {
+    let args = Args::parse();
+    args.global.init(None);
+
+    let mut driver = Driver::new(DriverOptions {
+        stop_after: Stage::Check,
+        ..Default::default()
+    });
+
+    let session = driver.run_file(&args.filename).unwrap_or_else(|e| {
+        eprintln!("Failed to read file: {e}");
+        exit(1);
+    });
+
+    for diagnostic in session
+        .diagnostics
+        .iter()
+        .filter(|d| !cli::is_quiet() || session.severity(d) == Severity::Error)
+    {
+        eprintln!("{}", session.render_as(diagnostic, args.error_format));
+    }
+
+    if session.has_errors() {
+        exit(1);
+    }
+
+    if args.call_graph {
+        let graph = CallGraph::new(&session.loader);
+
+        let quiet = cli::is_quiet();
+        for node in graph
+            .unreachable()
+            .into_iter()
+            .map(|i| &graph.nodes[i])
+            .filter(|_| !quiet)
+        {
+            eprintln!(
+                "{}: function '{}' is never called ({}:{})",
+                "Warning".yellow().bold(),
+                node.name,
+                graph.modules[node.module],
+                node.line.map_or("?".to_owned(), |l| l.to_string())
+            );
+        }
+
+        for cycle in graph.cycles().into_iter().filter(|_| !quiet) {
+            let names: Vec<_> = cycle
+                .iter()
+                .map(|&i| graph.nodes[i].name.as_str())
+                .collect();
+            eprintln!(
+                "{}: recursive cycle: {}",
+                "Note".cyan().bold(),
+                names.join(", ")
+            );
+        }
+
+        print!("{}", graph.to_dot());
+    }
+
+    if args.import_graph {
+        let graph = ImportGraph::new(&session.loader);
+
+        for cycle in graph.cycles().into_iter().filter(|_| !cli::is_quiet()) {
+            let names: Vec<_> = cycle.iter().map(|&i| graph.modules[i].as_str()).collect();
+            eprintln!(
+                "{}: cyclic imports: {}",
+                "Note".cyan().bold(),
+                names.join(", ")
+            );
+        }
+
+        print!("{}", graph.to_dot());
+    }
+}
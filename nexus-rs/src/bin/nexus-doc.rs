@@ -0,0 +1,121 @@
+use clap::Parser;
+use nexus_rs::{
+    cli::{self, GlobalArgs},
+    docgen::{items, render, DocFormat, DocModule},
+    driver::{Driver, DriverOptions, ErrorFormat, Severity, Stage},
+    manifest::{Manifest, ManifestError},
+};
+use std::{fs, path::PathBuf, process::exit};
+
+/// Nexus documentation generator, from doc comments ('///') on functions, constants and variables.
+#[derive(Parser)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    /// Input source filenames (default: all source files of the project, see 'nexus.toml').
+    files: Vec<PathBuf>,
+
+    /// Path of the project manifest (default: 'nexus.toml' in the current or any parent directory).
+    #[arg(long, conflicts_with = "files")]
+    manifest_path: Option<PathBuf>,
+
+    /// Documentation output format.
+    #[arg(long, value_enum, default_value_t = DocFormat::Markdown)]
+    format: DocFormat,
+
+    /// Output filename (default: standard output).
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Documentation title (default: the project name, or 'Documentation').
+    #[arg(long)]
+    title: Option<String>,
+
+    /// Diagnostics output format.
+    #[arg(long, value_enum, default_value_t = ErrorFormat::Human)]
+    error_format: ErrorFormat,
+
+    #[command(flatten)]
+    global: GlobalArgs,
+}
+
+fn main() {
+    let args = Args::parse();
+    args.global.init(None);
+
+    let (files, root, name) = if args.files.is_empty() {
+        let manifest = match args.manifest_path {
+            Some(path) => Manifest::load(path),
+            None => std::env::current_dir()
+                .map_err(|e| ManifestError::Io(PathBuf::from("."), e))
+                .and_then(Manifest::find),
+        }
+        .unwrap_or_else(|e| {
+            eprintln!("Error: {e}");
+            exit(1);
+        });
+
+        let files = manifest.source_files().unwrap_or_else(|e| {
+            eprintln!("Error: failed to read source directories: {e}");
+            exit(1);
+        });
+
+        (files, Some(manifest.root), manifest.name)
+    } else {
+        (args.files, None, None)
+    };
+
+    let mut modules = Vec::new();
+    let mut success = true;
+
+    for file in files {
+        let filename = file.display().to_string();
+        let mut driver = Driver::new(DriverOptions {
+            stop_after: Stage::Parse,
+            ..Default::default()
+        });
+
+        let session = driver.run_file(&filename).unwrap_or_else(|e| {
+            eprintln!("Failed to read file '{filename}': {e}");
+            exit(1);
+        });
+
+        for diagnostic in session
+            .diagnostics
+            .iter()
+            .filter(|d| !cli::is_quiet() || session.severity(d) == Severity::Error)
+        {
+            eprintln!("{}", session.render_as(diagnostic, args.error_format));
+        }
+
+        match session.ast() {
+            Some(ast) if !session.has_errors() => modules.push(DocModule {
+                name: root
+                    .as_ref()
+                    .and_then(|r| file.strip_prefix(r).ok())
+                    .unwrap_or(&file)
+                    .display()
+                    .to_string(),
+                items: items(ast, &session.lines),
+            }),
+            _ => success = false,
+        }
+    }
+
+    if !success {
+        exit(1);
+    }
+
+    let title = args
+        .title
+        .or(name)
+        .unwrap_or_else(|| "Documentation".to_owned());
+    let output = render(&title, &modules, args.format);
+
+    match args.output {
+        Some(path) => fs::write(&path, output).unwrap_or_else(|e| {
+            eprintln!("Failed to write file '{}': {e}", path.display());
+            exit(1);
+        }),
+        None => print!("{output}"),
+    }
+}
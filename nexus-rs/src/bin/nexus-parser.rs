@@ -1,54 +1,76 @@
 use clap::Parser;
 use colored::Colorize;
-use nexus_rs::{filereader::FileReader, parser, scanner, source_line::SourceLine, token::Tokens};
+use nexus_rs::{
+    ast_dump::{dump, DumpFormat},
+    cli::{self, GlobalArgs},
+    driver::{Driver, DriverOptions, ErrorFormat, Severity, Stage},
+    semantic_tokens::{semantic_tokens, to_json},
+    token_cursor,
+};
 use std::process::exit;
 
 /// Nexus programming language scanner/lexer tester.
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 struct Args {
-    /// Input source filename.
+    /// Input source filename ('-' reads from standard input).
     #[arg(short, long)]
     filename: String,
+
+    /// Trace parser rule entry/exit with the current token and position to stderr (for debugging the grammar).
+    #[arg(long, alias = "trace-parse")]
+    trace_parser: bool,
+
+    /// Diagnostics output format.
+    #[arg(long, value_enum, default_value_t = ErrorFormat::Human)]
+    error_format: ErrorFormat,
+
+    /// AST output format.
+    #[arg(long, value_enum, default_value_t = DumpFormat::Flat)]
+    format: DumpFormat,
+
+    /// Output LSP semantic tokens (JSON) instead of the AST, classifying the source code for syntax highlighting.
+    #[arg(long)]
+    semantic_tokens: bool,
+
+    #[command(flatten)]
+    global: GlobalArgs,
 }
 
 fn main() {
     let args = Args::parse();
 
-    let file = FileReader::try_new(args.filename).unwrap_or_else(|e| {
-        eprintln!("Failed to open file: {e}");
+    args.global
+        .init(args.trace_parser.then_some(token_cursor::TRACE_TARGET));
+
+    let mut driver = Driver::new(DriverOptions {
+        stop_after: Stage::Parse,
+        ..Default::default()
+    });
+
+    let session = driver.run_file(&args.filename).unwrap_or_else(|e| {
+        eprintln!("Failed to read file: {e}");
         exit(1);
     });
 
-    let mut scanner = scanner::Scanner::new();
-    let mut scan_error = false;
-
-    let mut parser = parser::Parser::new(file.into_iter().enumerate().fold(
-        Tokens::new(),
-        |mut acc, line| {
-            let (number, line) = line;
-            match scanner.scan(SourceLine {
-                line,
-                number: Some(number + 1),
-            }) {
-                Ok(mut result) => acc.append(&mut result),
-                Err(error) => {
-                    scan_error = true;
-                    eprintln!("line {}: {error:?}", number + 1)
-                }
-            }
-
-            acc
-        },
-    ));
-
-    if scan_error {
-        eprintln!("scanning failed, aborting");
+    for diagnostic in session
+        .diagnostics
+        .iter()
+        .filter(|d| !cli::is_quiet() || session.severity(d) == Severity::Error)
+    {
+        eprintln!("{}", session.render_as(diagnostic, args.error_format));
+    }
+
+    if args.semantic_tokens {
+        println!(
+            "{}",
+            to_json(&semantic_tokens(&session.lines), &session.lines)
+        );
         return;
     }
 
-    match parser.parse() {
-        Ok(ast) => ast.iter().for_each(|n| {
+    match session.ast() {
+        Some(ast) if args.format == DumpFormat::Flat => ast.iter().for_each(|n| {
             println!(
                 "{} {}: {}",
                 "==".yellow().bold(),
@@ -56,6 +78,12 @@ fn main() {
                 n.to_string().bright_red().dimmed()
             )
         }),
-        Err(e) => eprintln!("{e:?}"),
+        Some(ast) => println!("{}", dump(ast, args.format)),
+        None if args.error_format == ErrorFormat::Human
+            && session.diagnostics.iter().any(|d| d.stage() == Stage::Scan) =>
+        {
+            eprintln!("scanning failed, aborting")
+        }
+        None => (),
     }
 }
@@ -1,40 +1,55 @@
 use clap::Parser;
 use colored::Colorize;
-use nexus_rs::{filereader::FileReader, scanner::Scanner, source_line::SourceLine};
+use nexus_rs::{
+    cli::GlobalArgs,
+    driver::{Diagnostic, Driver, DriverOptions, Stage},
+};
 use std::process::exit;
 
 /// Nexus programming language scanner/lexer tester.
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 struct Args {
-    /// Input source filename.
+    /// Input source filename ('-' reads from standard input).
     #[arg(short, long)]
     filename: String,
+
+    #[command(flatten)]
+    global: GlobalArgs,
 }
 
 fn main() {
     let args = Args::parse();
+    args.global.init(None);
+
+    let mut driver = Driver::new(DriverOptions {
+        stop_after: Stage::Scan,
+        ..Default::default()
+    });
 
-    let file = FileReader::try_new(args.filename).unwrap_or_else(|e| {
-        eprintln!("Failed to open file: {e}");
+    let session = driver.run_file(&args.filename).unwrap_or_else(|e| {
+        eprintln!("Failed to read file: {e}");
         exit(1);
     });
 
-    let mut s = Scanner::new();
+    let mut tokens = session.tokens.iter().zip(&session.spans).peekable();
 
-    for (number, line) in file.into_iter().enumerate() {
+    for line in &session.lines {
         println!(
             "{} {}: '{}'",
             "==".yellow().bold(),
             "Scan line".bold(),
-            line.to_string().bright_red().dimmed()
+            line.line.to_string().bright_red().dimmed()
         );
-        match s.scan(SourceLine {
-            line,
-            number: Some(number + 1),
-        }) {
-            Ok(tokens) => tokens.into_iter().for_each(|t| print!("{t:?} ")),
-            Err(error) => eprint!("{error}"),
+
+        while let Some((token, _)) = tokens.next_if(|(_, span)| span.line == line.number) {
+            print!("{token:?} ");
+        }
+
+        for diagnostic in &session.diagnostics {
+            if matches!(diagnostic, Diagnostic::Scan(e) if e.line() == line.number) {
+                eprint!("{diagnostic}");
+            }
         }
         println!();
     }
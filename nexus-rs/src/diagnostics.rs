@@ -0,0 +1,589 @@
+//! Diagnostic codes registry: the stable code of every scanning, parsing, checking and runtime error, with a longer
+//! explanation (see `nexus-rs --explain <CODE>`).
+//!
+//! Codes are grouped by stage: `NXS00xx` for scanning, `NXS01xx` for parsing, `NXS02xx` for checking and `NXS03xx`
+//! for running. Codes are never reused, so they can be looked up across versions.
+
+use std::fmt;
+
+#[cfg(test)]
+use crate::driver::{Driver, DriverOptions};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+/// Explanation of a diagnostic code.
+#[derive(Debug)]
+pub struct Explanation {
+    pub code: &'static str,
+    pub title: &'static str,
+    pub description: &'static str,
+    pub example: Option<&'static str>, // Code producing the diagnostic (if reproducible in a single file).
+    pub fix: Option<&'static str>,     // The example, fixed.
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let indent = |code: &str| {
+            code.lines()
+                .map(|l| format!("    {l}"))
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+
+        write!(f, "{}: {}\n\n{}", self.code, self.title, self.description)?;
+
+        if let Some(example) = self.example {
+            write!(f, "\n\nErroneous code example:\n\n{}", indent(example))?;
+        }
+
+        if let Some(fix) = self.fix {
+            write!(f, "\n\nFixed:\n\n{}", indent(fix))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Look up the explanation of a diagnostic code (case insensitive).
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::diagnostics::explain;
+///
+/// assert_eq!(explain("NXS0105").map(|e| e.title), Some("missing semicolon"));
+/// assert!(explain("nxs0105").is_some());
+/// assert!(explain("NXS9999").is_none());
+/// ```
+pub fn explain(code: &str) -> Option<&'static Explanation> {
+    REGISTRY.iter().find(|e| e.code.eq_ignore_ascii_case(code))
+}
+
+/// All diagnostic codes with their explanations, ordered by code.
+pub const REGISTRY: &[Explanation] = &[
+    Explanation {
+        code: "NXS0001",
+        title: "malformed string literal",
+        description: "A string literal ends with an incomplete escape sequence.",
+        example: None,
+        fix: None,
+    },
+    Explanation {
+        code: "NXS0002",
+        title: "invalid number literal",
+        description: "A number literal could not be converted to a number.",
+        example: None,
+        fix: None,
+    },
+    Explanation {
+        code: "NXS0003",
+        title: "invalid word",
+        description: "An identifier or keyword could not be scanned.",
+        example: None,
+        fix: None,
+    },
+    Explanation {
+        code: "NXS0004",
+        title: "unexpected character",
+        description: "The source code contains a character that is not part of the language (outside of string \
+                      literals and comments). A number literal directly followed by a '.' that is not part of a \
+                      range or fraction is also reported.",
+        example: Some("let x = 1 # 2;"),
+        fix: Some("let x = 1 + 2;"),
+    },
+    Explanation {
+        code: "NXS0005",
+        title: "unterminated string",
+        description: "A string literal is not closed by a '\"' on the same line. To include a '\"' in a string, \
+                      escape it as '\\\"'.",
+        example: Some("let s = \"abc;"),
+        fix: Some("let s = \"abc\";"),
+    },
+    Explanation {
+        code: "NXS0100",
+        title: "syntax error",
+        description: "The code does not match the grammar, e.g. a type annotation is not a type.",
+        example: Some("let x: 1 = 2;"),
+        fix: Some("let x: Number = 2;"),
+    },
+    Explanation {
+        code: "NXS0101",
+        title: "invalid deferred statement",
+        description: "Only statements without effect on the enclosing scope can be deferred: a declaration would \
+                      go out of scope right away, and a deferred 'return' would overrule the result of the scope.",
+        example: Some("fn f() { defer let x = 1; }"),
+        fix: Some("fn f() { defer print \"done\"; }"),
+    },
+    Explanation {
+        code: "NXS0102",
+        title: "expected token",
+        description: "A specific token (e.g. a closing parenthesis) is required at this point.",
+        example: Some("let x = (1 + 2;"),
+        fix: Some("let x = (1 + 2);"),
+    },
+    Explanation {
+        code: "NXS0103",
+        title: "missing operator",
+        description: "An operand directly follows an expression, on the same line. Most likely an operator is \
+                      missing in between, or the expression should end with ';'.",
+        example: Some("print 1 2;"),
+        fix: Some("print 1 + 2;"),
+    },
+    Explanation {
+        code: "NXS0104",
+        title: "keyword as identifier",
+        description: "Keywords are reserved, and cannot be used to name variables or functions, unless written as \
+                      raw identifier (e.g. 'r#while').",
+        example: Some("fn while() {}"),
+        fix: Some("fn repeat() {}"),
+    },
+    Explanation {
+        code: "NXS0105",
+        title: "missing semicolon",
+        description: "A statement is not terminated by ';', while a new statement follows.",
+        example: Some("let x = 1\nprint x;"),
+        fix: Some("let x = 1;\nprint x;"),
+    },
+    Explanation {
+        code: "NXS0106",
+        title: "nesting too deep",
+        description: "Expressions and blocks are nested deeper than the parser supports (over a thousand levels). \
+                      Split up the expression, e.g. by introducing variables or functions.",
+        example: None,
+        fix: None,
+    },
+    Explanation {
+        code: "NXS0107",
+        title: "invalid range delimiter",
+        description: "The start and end of a range must be literals, variables or parenthesized expressions.",
+        example: Some("for i in 0..-3 { print i; }"),
+        fix: Some("for i in 0..(-3) { print i; }\nprint \"done\";"),
+    },
+    Explanation {
+        code: "NXS0108",
+        title: "invalid reference target",
+        description: "A reference must refer to a variable (or a member of it), values cannot be referenced.",
+        example: Some("let r = &1;"),
+        fix: Some("let x = 1;\nlet r = &x;"),
+    },
+    Explanation {
+        code: "NXS0109",
+        title: "split inclusive range",
+        description: "An inclusive range is written as '..=', without whitespace in between. A '..' followed by \
+                      '=' is most likely intended as inclusive range.",
+        example: Some("for i in 0.. = 3 { print i; }"),
+        fix: Some("for i in 0..=3 { print i; }\nprint \"done\";"),
+    },
+    Explanation {
+        code: "NXS0110",
+        title: "reserved identifier",
+        description: "An identifier is reserved as keyword in the edition of the source code (see the \
+                      '// edition: <year>' header), and cannot be used as name.",
+        example: Some("// edition: 2024\nlet match = 1;"),
+        fix: Some("// edition: 2024\nlet matched = 1;"),
+    },
+    Explanation {
+        code: "NXS0111",
+        title: "unexpected token",
+        description: "A token appears where the grammar does not allow it.",
+        example: Some("let x = );"),
+        fix: Some("let x = 1;"),
+    },
+    Explanation {
+        code: "NXS0112",
+        title: "misplaced reference",
+        description: "A reference ('&') is only allowed as initializer of a variable declaration.",
+        example: Some("let x = 1;\nprint &x;"),
+        fix: Some("let x = 1;\nlet r = &x;\nprint r;"),
+    },
+    Explanation {
+        code: "NXS0113",
+        title: "unexpected end of source",
+        description: "The source code ends in the middle of a statement or expression.",
+        example: Some("let x ="),
+        fix: Some("let x = 1;"),
+    },
+    Explanation {
+        code: "NXS0114",
+        title: "unknown edition",
+        description: "The edition header comment names an unsupported edition. Supported editions are 2023 and \
+                      2024.",
+        example: Some("// edition: 2099\nlet x = 1;"),
+        fix: Some("// edition: 2024\nlet x = 1;"),
+    },
+    Explanation {
+        code: "NXS0115",
+        title: "unknown type",
+        description: "A type annotation names an unknown type. The types are 'bool', 'Number', 'String', 'Node' \
+                      and 'Group'.",
+        example: Some("let x: Numbr = 1;"),
+        fix: Some("let x: Number = 1;"),
+    },
+    Explanation {
+        code: "NXS0116",
+        title: "invalid pipeline target",
+        description: "The right-hand side of a pipeline ('|>') must be a function name or call: 'x |> f' is \
+                      'f(x)' and 'x |> f(a)' is 'f(x, a)'. The pipeline binds weaker than arithmetic and \
+                      comparison operators, use parentheses to apply an operator to the result.",
+        example: Some("let n = \"abc\" |> len + 1;"),
+        fix: Some("let n = (\"abc\" |> len) + 1;"),
+    },
+    Explanation {
+        code: "NXS0117",
+        title: "duplicate port",
+        description: "A node declaration declares the same port name twice. Port names must be unique within a \
+                      node, regardless of their direction.",
+        example: Some("node Gain { in x: Number; out x: Number; }"),
+        fix: Some("node Gain { in x: Number; out y: Number; }"),
+    },
+    Explanation {
+        code: "NXS0118",
+        title: "unknown connection option",
+        description: "The buffering policy of a connect statement ('a -> b [capacity: 8];') only accepts the \
+                      options 'capacity' (the maximum number of queued values) and 'overflow' (the policy for a \
+                      value sent into a full buffer).",
+        example: Some("a -> b [size: 8];"),
+        fix: None,
+    },
+    Explanation {
+        code: "NXS0119",
+        title: "unknown overflow policy",
+        description: "The 'overflow' option of a connection is one of 'block' (wait until the sink takes a value, \
+                      the default), 'drop_oldest' (drop the oldest queued value) or 'drop_newest' (drop the sent \
+                      value).",
+        example: Some("a -> b [capacity: 8, overflow: drop];"),
+        fix: None,
+    },
+    Explanation {
+        code: "NXS0120",
+        title: "invalid assignment target",
+        description: "The left-hand side of an assignment must be a variable ('x = 1;'), a member ('x.y = 1;') or an \
+                      indexed value ('x[0] = 1;'). Other expressions, like literals or operations, have no storage \
+                      to assign to.",
+        example: Some("let mut x = 1;\nx + 1 = 2;"),
+        fix: Some("let mut x = 1;\nx = 2 - 1;"),
+    },
+    Explanation {
+        code: "NXS0121",
+        title: "chained comparison",
+        description: "Comparisons do not chain like in mathematics: 'a < b < c' would compare the bool result of \
+                      'a < b' with 'c'. Combine the comparisons with '&&' instead, or group one explicitly if a \
+                      bool comparison is intended.",
+        example: Some("let x = 5;\nprint 0 < x < 10;"),
+        fix: Some("let x = 5;\nprint 0 < x && x < 10;"),
+    },
+    Explanation {
+        code: "NXS0122",
+        title: "assignment in condition",
+        description: "The condition of an 'if' or 'while' expression contains '=', which assigns a value. To \
+                      compare values, use '=='.",
+        example: Some("let x = 5;\nif x = 5 { print x; };"),
+        fix: Some("let x = 5;\nif x == 5 { print x; };"),
+    },
+    Explanation {
+        code: "NXS0123",
+        title: "reversed range",
+        description: "A range counts up from its start, so a range with a start greater than its end is empty. To \
+                      count down, give a negative step.",
+        example: Some("for i in 3..0 { print i; };"),
+        fix: Some("for i in 3..0 step -1 { print i; };"),
+    },
+    Explanation {
+        code: "NXS0124",
+        title: "invalid cast type",
+        description: "Only values can be converted with 'as': a value can be cast to 'Number', 'String' or 'bool', \
+                      but not to a node or group.",
+        example: Some("let n = \"1\" as Node;"),
+        fix: Some("let n = \"1\" as Number;"),
+    },
+    Explanation {
+        code: "NXS0125",
+        title: "duplicate enum variant",
+        description: "Each variant of an enum declaration must have a unique name.",
+        example: Some("enum Mode { Idle, Run, Idle }"),
+        fix: Some("enum Mode { Idle, Run, Stop }"),
+    },
+    Explanation {
+        code: "NXS0201",
+        title: "used file not found",
+        description: "A file named in a 'use' declaration cannot be read. Paths are relative to the using file.",
+        example: Some("use \"nonexistent.nxs\";"),
+        fix: None,
+    },
+    Explanation {
+        code: "NXS0202",
+        title: "invalid use path",
+        description: "The path of a 'use' declaration must be a string literal, like 'use \"lib.nxs\";'.",
+        example: Some("use lib;"),
+        fix: None,
+    },
+    Explanation {
+        code: "NXS0203",
+        title: "redundant use",
+        description: "A file is used more than once from the same file (warning). Remove all but the first 'use'.",
+        example: None,
+        fix: None,
+    },
+    Explanation {
+        code: "NXS0204",
+        title: "unknown package",
+        description: "A 'use' declaration names a package (with '@' prefix) that is not a dependency of the project. \
+                      Declare the package in the '[dependencies]' table of 'nexus.toml'.",
+        example: Some("use \"@geometry/vec.nxs\";"),
+        fix: None,
+    },
+    Explanation {
+        code: "NXS0205",
+        title: "unknown port",
+        description: "A connection refers to a port that is not declared by the kind of the node, like 'a.total' \
+                      for a node of kind 'node Adder { in a: Number; in b: Number; out sum: Number; }'.",
+        example: Some("node Gain { in x: Number; out y: Number; }\nlet a = node \"Gain\";\nlet b = node \"Gain\";\n\
+                       a.out -> b.x;"),
+        fix: None,
+    },
+    Explanation {
+        code: "NXS0206",
+        title: "port direction mismatch",
+        description: "A connection flows from an output port ('out') to an input port ('in'): the source of '->' \
+                      must be an output port, and the sink an input port.",
+        example: Some("node Gain { in x: Number; out y: Number; }\nlet a = node \"Gain\";\nlet b = node \"Gain\";\n\
+                       a.y -> b.y;"),
+        fix: None,
+    },
+    Explanation {
+        code: "NXS0207",
+        title: "port type mismatch",
+        description: "A connection connects ports of different types. The type of the output port must be the type \
+                      of the input port.",
+        example: Some("node Gain { in x: Number; out y: Number; }\nnode Log { in text: String; }\n\
+                       let a = node \"Gain\";\nlet b = node \"Log\";\na.y -> b.text;"),
+        fix: None,
+    },
+    Explanation {
+        code: "NXS0208",
+        title: "missing port",
+        description: "A connection refers to a node without naming a port, while the kind of the node declares \
+                      ports. Name the connected port, like 'a.y'.",
+        example: Some("node Gain { in x: Number; out y: Number; }\nlet a = node \"Gain\";\nlet b = node \"Gain\";\n\
+                       a -> b.x;"),
+        fix: None,
+    },
+    Explanation {
+        code: "NXS0209",
+        title: "input port already connected",
+        description: "An input port accepts a single connection, so it cannot be the sink of multiple connections \
+                      (fan-in). An output port can be connected to any number of sinks (fan-out), which all receive \
+                      the same value.",
+        example: Some("node Gain { in x: Number; out y: Number; }\nlet a = node \"Gain\";\nlet b = node \"Gain\";\n\
+                       [a.y, b.y] -> b.x;"),
+        fix: None,
+    },
+    Explanation {
+        code: "NXS0301",
+        title: "argument count mismatch",
+        description: "A function is called with a different number of arguments than it has parameters.",
+        example: Some("fn f(a: Number) {}\nf();"),
+        fix: Some("fn f(a: Number) {}\nf(1);"),
+    },
+    Explanation {
+        code: "NXS0302",
+        title: "assignment to immutable variable",
+        description: "An immutable variable can only be initialized once. Declare it with 'let mut' to allow \
+                      assignment.",
+        example: Some("let x = 1;\nx = 2;"),
+        fix: Some("let mut x = 1;\nx = 2;"),
+    },
+    Explanation {
+        code: "NXS0303",
+        title: "program exit",
+        description: "The program requested to exit by calling 'exit(code)'. This is not reported as error, the \
+                      code becomes the exit code of the interpreter.",
+        example: None,
+        fix: None,
+    },
+    Explanation {
+        code: "NXS0304",
+        title: "invalid argument",
+        description: "A built-in function is called with an argument outside of its domain.",
+        example: Some("print substring(\"abc\", 5, 1);"),
+        fix: Some("print substring(\"abc\", 1, 2);"),
+    },
+    Explanation {
+        code: "NXS0305",
+        title: "invalid assignment target",
+        description: "Only variables can be assigned to: assigning to members and indexed values is not supported \
+                      (yet).",
+        example: Some("let x = \"abc\";\nx[0] = \"b\";"),
+        fix: Some("let mut x = 1;\nx = 2;"),
+    },
+    Explanation {
+        code: "NXS0306",
+        title: "input/output error",
+        description: "Reading or writing a file or input failed, e.g. 'read_file(\"missing.txt\")' for a file \
+                      that does not exist. Use 'try' to handle the error.",
+        example: None,
+        fix: None,
+    },
+    Explanation {
+        code: "NXS0307",
+        title: "input/output denied",
+        description: "The program uses a file or input function while file and input access is denied (see \
+                      '--deny-io').",
+        example: Some("let line = read_line();"),
+        fix: None,
+    },
+    Explanation {
+        code: "NXS0308",
+        title: "resource limit exceeded",
+        description: "The program exceeds a resource limit: evaluation steps, function call depth, expression \
+                      nesting depth or heap size. This is often caused by unbounded recursion or loops.",
+        example: Some("fn f() { f(); }\nf();"),
+        fix: Some("fn f(n: Number) { if n > 0 { f(n - 1); } }\nf(10);"),
+    },
+    Explanation {
+        code: "NXS0309",
+        title: "mutable reference to immutable variable",
+        description: "A mutable reference ('let mut r = &x;') requires the referenced variable to be mutable.",
+        example: Some("let x = 1;\nlet mut r = &x;"),
+        fix: Some("let mut x = 1;\nlet mut r = &x;"),
+    },
+    Explanation {
+        code: "NXS0310",
+        title: "output failure",
+        description: "Writing program output failed, e.g. because the output stream was closed.",
+        example: None,
+        fix: None,
+    },
+    Explanation {
+        code: "NXS0311",
+        title: "panic",
+        description: "The program called 'panic(message)', which ends the program (and cannot be handled by \
+                      'try').",
+        example: Some("panic(\"oops\");"),
+        fix: None,
+    },
+    Explanation {
+        code: "NXS0312",
+        title: "type mismatch",
+        description: "An operator or function is applied to values of unsupported types. Use the conversion \
+                      functions (e.g. 'to_number') to convert values. Conditions (of 'if' and 'while') and the \
+                      operands of '&&' and '||' must be bool: other values are not implicitly converted (e.g. \
+                      'if n { ... }' is an error), so compare them explicitly (e.g. 'if n != 0 { ... }').",
+        example: Some("print \"1\" + 1;"),
+        fix: Some("print to_number(\"1\") + 1;"),
+    },
+    Explanation {
+        code: "NXS0313",
+        title: "undefined function",
+        description: "A function is called that is neither declared in a visible scope, nor built in.",
+        example: Some("print sqr(4);"),
+        fix: Some("print sqrt(4);"),
+    },
+    Explanation {
+        code: "NXS0314",
+        title: "undefined variable",
+        description: "A variable is used that is not declared in a visible scope. Note that functions cannot \
+                      access the variables of their caller.",
+        example: Some("let total = 1;\nprint totl;"),
+        fix: Some("let total = 1;\nprint total;"),
+    },
+    Explanation {
+        code: "NXS0315",
+        title: "uninitialized variable",
+        description: "A variable is used before a value is assigned to it.",
+        example: Some("let x;\nprint x;"),
+        fix: Some("let x;\nx = 1;\nprint x;"),
+    },
+    Explanation {
+        code: "NXS0316",
+        title: "unsupported feature",
+        description: "The program uses a language feature that is not supported by the interpreter (yet).",
+        example: Some("node \"N\";"),
+        fix: None,
+    },
+    Explanation {
+        code: "NXS0317",
+        title: "numeric error",
+        description: "In strict numeric mode ('--numeric-mode strict'), an operation on finite numbers has an \
+                      undefined or infinite result, e.g. 'x / 0', '0 % 0', 'sqrt(-1)' or an overflow. Check the \
+                      operands first, or use the (default) IEEE mode and test results with 'is_nan' and \
+                      'is_finite'.",
+        example: None, // Not reproducible with the default options.
+        fix: None,
+    },
+    Explanation {
+        code: "NXS0318",
+        title: "invalid index",
+        description: "A string is indexed (e.g. 's[i]') or sliced (e.g. 's[a..b]') out of its range, or with an \
+                      index that is not an integer. Indices count characters from 0, and a slice 'a..b' requires \
+                      'a <= b <= len(s)'.",
+        example: Some("let s = \"abc\";\nprint s[3];"),
+        fix: Some("let s = \"abc\";\nprint s[len(s) - 1];"),
+    },
+    Explanation {
+        code: "NXS0319",
+        title: "program interrupted",
+        description: "The program was interrupted (e.g. by Ctrl-C) and stopped, after running its deferred \
+                      statements. The process exits with code 130. Interrupt again to exit immediately.",
+        example: None, // Not reproducible without a signal.
+        fix: None,
+    },
+    Explanation {
+        code: "NXS0320",
+        title: "unknown enum variant",
+        description: "A variant accessed with the dot operator (e.g. `Mode.Run`) is not declared in the enum.",
+        example: Some("enum Mode { Idle, Run }\nprint Mode.Stop;"),
+        fix: Some("enum Mode { Idle, Run, Stop }\nprint Mode.Stop;"),
+    },
+];
+
+#[test]
+fn registry_test() {
+    assert!(REGISTRY.windows(2).all(|w| w[0].code < w[1].code));
+    assert!(REGISTRY.iter().all(|e| e.code.len() == 7
+        && e.code.starts_with("NXS")
+        && e.code[3..].chars().all(|c| c.is_ascii_digit())));
+    assert!(REGISTRY
+        .iter()
+        .all(|e| e.fix.is_none() || e.example.is_some()));
+}
+
+/// Check that each example produces its diagnostic, and that the fix resolves it.
+#[test]
+fn registry_example_test() {
+    let codes = |code: &str| {
+        let options = DriverOptions {
+            deny_io: true,
+            ..Default::default()
+        };
+
+        Driver::with_output(options, Vec::new())
+            .run_source("example.nxs", code)
+            .diagnostics
+            .iter()
+            .map(|d| d.code())
+            .collect::<Vec<_>>()
+    };
+
+    for e in REGISTRY {
+        if let Some(example) = e.example {
+            assert_eq!(codes(example), [e.code], "{example}");
+        }
+
+        if let Some(fix) = e.fix {
+            assert_eq!(codes(fix), [] as [&str; 0], "{fix}");
+        }
+    }
+}
+
+#[test]
+fn explanation_test() {
+    assert_eq!(
+        explain("NXS0302").unwrap().to_string(),
+        "NXS0302: assignment to immutable variable\n\n\
+         An immutable variable can only be initialized once. Declare it with 'let mut' to allow assignment.\n\n\
+         Erroneous code example:\n\n    let x = 1;\n    x = 2;\n\n\
+         Fixed:\n\n    let mut x = 1;\n    x = 2;"
+    );
+}
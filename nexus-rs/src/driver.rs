@@ -0,0 +1,1023 @@
+//! Program driver: runs the complete pipeline (scanning → parsing → checking → running) over a source file.
+//!
+//! The driver collects the diagnostics of all stages into a [`Session`](crate::driver::Session), which also keeps the intermediate artifacts
+//! (tokens, AST, loaded modules and result value) for inspection. All binaries use the driver, stopping after the
+//! stage they need.
+
+use crate::clock::Clock;
+use crate::edition::Edition;
+use crate::front::{
+    scan_lines, ParseError, ScanError, SourceLine, Span, Spans, Stmts, Tokens, BOM,
+};
+use crate::interpreter::{ExecEvent, Interpreter};
+use crate::json::Json;
+use crate::limits::Limits;
+use crate::loader::{LoadError, Loader, RedundantUse};
+use crate::manifest::Manifest;
+use crate::numeric::NumericMode;
+use crate::parser::Parser;
+use crate::ports::{check_connections, PortError};
+use crate::profile::HeapProfile;
+use crate::runtime_error::RuntimeError;
+use crate::value::Value;
+use log::{debug, info};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::{atomic::AtomicBool, Arc};
+use std::time::Instant;
+use thiserror::Error;
+
+#[cfg(feature = "cli")]
+use crate::filereader::FileReader;
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+/// Log target for pipeline progress (stages, with timing and counts at debug level).
+pub const LOG_TARGET: &str = "nexus_rs::driver";
+
+/// Exit code of an interrupted program (128 + SIGINT, as reported by shells).
+pub const INTERRUPTED: i32 = 130;
+
+/// Pipeline stage, in order of execution.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Stage {
+    /// Scanning source lines into tokens.
+    Scan,
+
+    /// Parsing tokens into an AST.
+    Parse,
+
+    /// Loading used files and linting.
+    Check,
+
+    /// Running the program.
+    #[default]
+    Run,
+}
+
+/// Diagnostic of a pipeline stage.
+#[derive(Error, Debug)]
+pub enum Diagnostic {
+    #[error("{0}")]
+    Scan(ScanError),
+
+    #[error("{0}")]
+    Parse(ParseError),
+
+    #[error("{0}")]
+    Load(LoadError),
+
+    #[error("{0}")]
+    RedundantUse(RedundantUse),
+
+    #[error("{0}")]
+    Port(PortError),
+
+    #[error("{0}")]
+    Runtime(RuntimeError, Option<Span>), // With the span of the statement raising the error (if known).
+}
+
+/// Diagnostic severity, in increasing order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// Reported, but never fails the run.
+    Note,
+
+    /// Reported, but only fails the run if denied (see [`LintLevels`]).
+    Warning,
+
+    /// Fails the run.
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Note => write!(f, "note"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// Names of the lints, i.e. the diagnostics with an adjustable severity (see [`LintLevels`]).
+pub const LINTS: &[&str] = &["redundant-use"];
+
+/// Lint group name, covering all lints reported as warning.
+pub const WARNINGS: &str = "warnings";
+
+/// Lint levels, overriding the (warning) severity of lints.
+///
+/// A level set for the [`WARNINGS`] group applies to all lints that are still a warning after applying the levels of
+///  the individual lints, e.g. denying warnings turns all warnings into errors.
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::driver::{LintLevels, Severity, WARNINGS};
+///
+/// let mut levels = LintLevels::default();
+/// assert_eq!(levels.level("redundant-use"), Severity::Warning);
+///
+/// levels.set(WARNINGS, Severity::Error);
+/// assert_eq!(levels.level("redundant-use"), Severity::Error);
+///
+/// levels.set("redundant-use", Severity::Note);
+/// assert_eq!(levels.level("redundant-use"), Severity::Note);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct LintLevels {
+    levels: Vec<(String, Severity)>, // In order of setting, later levels override earlier ones.
+}
+
+impl LintLevels {
+    /// Check if a name is a lint (or the [`WARNINGS`] group).
+    pub fn is_lint(name: &str) -> bool {
+        name == WARNINGS || LINTS.contains(&name)
+    }
+
+    /// Set the level of a lint (or the [`WARNINGS`] group).
+    pub fn set(&mut self, lint: &str, severity: Severity) {
+        self.levels.push((lint.to_owned(), severity));
+    }
+
+    /// Get the resulting level of a lint.
+    pub fn level(&self, lint: &str) -> Severity {
+        let last = |name: &str| {
+            self.levels
+                .iter()
+                .rev()
+                .find(|(n, _)| n == name)
+                .map(|(_, s)| *s)
+        };
+
+        match last(lint).unwrap_or(Severity::Warning) {
+            Severity::Warning => last(WARNINGS).unwrap_or(Severity::Warning),
+            severity => severity,
+        }
+    }
+}
+
+/// Diagnostic output format.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum ErrorFormat {
+    /// Source snippets with the offending code underlined.
+    #[default]
+    Human,
+
+    /// One JSON object per diagnostic line (severity, stage, code, file, span, message and notes).
+    Json,
+
+    /// One line per diagnostic: 'file:line:column: severity[code]: message'.
+    Short,
+}
+
+impl Diagnostic {
+    /// Get the stage producing the diagnostic.
+    pub fn stage(&self) -> Stage {
+        match self {
+            Diagnostic::Scan(_) => Stage::Scan,
+            Diagnostic::Parse(_) => Stage::Parse,
+            Diagnostic::Load(_) | Diagnostic::RedundantUse(_) | Diagnostic::Port(_) => Stage::Check,
+            Diagnostic::Runtime(..) => Stage::Run,
+        }
+    }
+
+    /// Get the diagnostic code (e.g. `NXS0105`), see [`diagnostics`](crate::diagnostics) for their explanations.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Diagnostic::Scan(e) => e.kind().code(),
+            Diagnostic::Parse(e) => e.kind().code(),
+            Diagnostic::Load(e) => e.code(),
+            Diagnostic::RedundantUse(r) => r.code(),
+            Diagnostic::Port(e) => e.code(),
+            Diagnostic::Runtime(e, _) => e.code(),
+        }
+    }
+
+    /// Get the diagnostic message, without source snippet.
+    pub fn message(&self) -> String {
+        match self {
+            Diagnostic::Scan(e) => e.kind().to_string(),
+            d => d.to_string(),
+        }
+    }
+
+    /// Get the source span of the diagnostic (if known).
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Diagnostic::Scan(e) => Some(e.span()),
+            Diagnostic::Parse(e) => e.span(),
+            Diagnostic::Runtime(_, span) => *span,
+            _ => None,
+        }
+    }
+
+    /// Get the notes attached to the diagnostic, e.g. "did you mean" suggestions.
+    pub fn notes(&self) -> Vec<String> {
+        match self {
+            Diagnostic::Parse(e) => e.notes().to_vec(),
+            Diagnostic::Runtime(e, _) => e.notes(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Get the lint name of the diagnostic, if its severity is adjustable (see [`LintLevels`]).
+    pub fn lint(&self) -> Option<&'static str> {
+        match self {
+            Diagnostic::RedundantUse(_) => Some("redundant-use"),
+            _ => None,
+        }
+    }
+}
+
+/// Driver options.
+#[derive(Clone, Debug, Default)]
+pub struct DriverOptions {
+    pub stop_after: Stage,                    // Last stage to run.
+    pub edition: Option<Edition>, // Overrides the edition header of the source (if any).
+    pub limits: Limits,           // Interpreter resource limits.
+    pub numeric_mode: NumericMode, // Numeric error policy.
+    pub clock: Clock,             // Clock of the time built-in functions (e.g. simulated).
+    pub seed: Option<u64>, // Random number generator seed (seeded from the system time by default).
+    pub interrupt: Option<Arc<AtomicBool>>, // Interrupt flag, set to stop the program (e.g. on SIGINT).
+    pub args: Vec<String>,                  // Program arguments.
+    pub deny_io: bool,                      // Deny the program file and input access.
+    pub lints: LintLevels,                  // Lint severity overrides.
+    pub search_dirs: Vec<PathBuf>, // Directories to resolve used files in (e.g. project source directories).
+    pub packages: BTreeMap<String, Manifest>, // Packages to resolve package uses in (see `package::resolve`).
+}
+
+/// Result of a driver run: the diagnostics and artifacts of all stages that ran.
+#[derive(Debug)]
+pub struct Session {
+    pub name: String, // Source name, used to resolve 'use' declarations and in diagnostics.
+    pub lines: Vec<SourceLine>,
+    pub edition: Edition,
+    pub tokens: Tokens,
+    pub spans: Spans,
+    pub loader: Loader,
+    pub diagnostics: Vec<Diagnostic>,
+    pub value: Option<Value>,         // Result of running the program.
+    pub exit: Option<i32>,            // Exit code requested by the program (see `exit()`).
+    pub profile: Option<HeapProfile>, // Heap profile of running the program.
+    pub lints: LintLevels,            // Lint severity overrides.
+    ast: Option<Stmts>,               // Parsed AST, until handed over to the loader.
+    module: Option<usize>,            // Index of the loaded module.
+}
+
+impl Session {
+    fn new(name: &str, lines: Vec<SourceLine>) -> Self {
+        Session {
+            name: name.to_owned(),
+            lines,
+            edition: Edition::default(),
+            tokens: Tokens::new(),
+            spans: Spans::new(),
+            loader: Loader::new(),
+            diagnostics: Vec::new(),
+            value: None,
+            exit: None,
+            profile: None,
+            lints: LintLevels::default(),
+            ast: None,
+            module: None,
+        }
+    }
+
+    /// Get the process exit code: as requested by the program, or else 1 on errors and 0 on success.
+    pub fn exit_code(&self) -> i32 {
+        match self.exit {
+            Some(code) => code,
+            None if self.has_errors() => 1,
+            None => 0,
+        }
+    }
+
+    /// Get the AST of the source (if parsed).
+    pub fn ast(&self) -> Option<&Stmts> {
+        match self.module {
+            Some(index) => Some(&self.loader.module(index).ast),
+            None => self.ast.as_ref(),
+        }
+    }
+
+    /// Take the AST of the source (if parsed and not loaded).
+    pub fn take_ast(&mut self) -> Option<Stmts> {
+        self.ast.take()
+    }
+
+    /// Get the severity of a diagnostic: the level of a lint, or else an error.
+    pub fn severity(&self, diagnostic: &Diagnostic) -> Severity {
+        match diagnostic.lint() {
+            Some(lint) => self.lints.level(lint),
+            None => Severity::Error,
+        }
+    }
+
+    /// Check if any stage reported an error.
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics
+            .iter()
+            .any(|d| self.severity(d) == Severity::Error)
+    }
+
+    /// Render a diagnostic, showing the offending source line (if known) and the attached notes.
+    pub fn render(&self, diagnostic: &Diagnostic) -> String {
+        let mut result = match diagnostic {
+            Diagnostic::Scan(e) => match e.line() {
+                Some(line) => format!("  ---> {}:{line}:{}\n{e}", self.name, e.column() + 1),
+                None => e.to_string(),
+            },
+            Diagnostic::Parse(e) => e.render(&self.lines),
+            Diagnostic::Runtime(e, Some(span)) if span.line.is_some() => {
+                format!("{e}\n  ---> {}", self.location(span))
+            }
+            d => d.to_string(),
+        };
+
+        for note in diagnostic.notes() {
+            result += &format!("\n  = note: {note}");
+        }
+
+        result
+    }
+
+    /// Render a diagnostic in an output format (see [`ErrorFormat`]).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nexus_rs::driver::{Driver, DriverOptions, ErrorFormat};
+    ///
+    /// let session = Driver::new(DriverOptions::default()).run_source("t.nxs", "let x = 1 $;");
+    ///
+    /// assert_eq!(
+    ///     session.render_as(&session.diagnostics[0], ErrorFormat::Short),
+    ///     "t.nxs:1:11: error[NXS0004]: unexpected character"
+    /// );
+    /// ```
+    pub fn render_as(&self, diagnostic: &Diagnostic, format: ErrorFormat) -> String {
+        let severity = self.severity(diagnostic);
+
+        match format {
+            ErrorFormat::Human => self.render(diagnostic),
+            ErrorFormat::Json => Json::Object(vec![
+                ("severity".to_owned(), severity.to_string().into()),
+                (
+                    "stage".to_owned(),
+                    format!("{:?}", diagnostic.stage()).to_lowercase().into(),
+                ),
+                ("code".to_owned(), diagnostic.code().into()),
+                ("file".to_owned(), self.file_name(diagnostic.span()).into()),
+                (
+                    "span".to_owned(),
+                    diagnostic
+                        .span()
+                        .map(|s| {
+                            Json::Object(vec![
+                                ("line".to_owned(), s.line.into()),
+                                ("start".to_owned(), s.start.into()),
+                                ("end".to_owned(), s.end.into()),
+                            ])
+                        })
+                        .into(),
+                ),
+                ("message".to_owned(), diagnostic.message().into()),
+                (
+                    "notes".to_owned(),
+                    Json::Array(diagnostic.notes().into_iter().map(Json::from).collect()),
+                ),
+            ])
+            .to_string(),
+            ErrorFormat::Short => {
+                let location = match diagnostic.span() {
+                    Some(span) if span.line.is_some() => self.location(&span),
+                    span => self.file_name(span),
+                };
+
+                format!(
+                    "{location}: {severity}[{}]: {}",
+                    diagnostic.code(),
+                    diagnostic.message()
+                )
+            }
+        }
+    }
+
+    /// Get the name of the source file of a span: the name of the source for the main file, or else the path of the
+    ///  used file.
+    fn file_name(&self, span: Option<Span>) -> String {
+        match span.and_then(|s| self.loader.file(s.file).filter(|_| s.file > 0)) {
+            Some(path) => path.display().to_string(),
+            None => self.name.clone(),
+        }
+    }
+
+    /// Get the location of a span, as `file:line:column`.
+    fn location(&self, span: &Span) -> String {
+        format!(
+            "{}:{}:{}",
+            self.file_name(Some(*span)),
+            span.line.unwrap_or_default(),
+            span.start + 1
+        )
+    }
+
+    fn fail(&mut self, diagnostic: Diagnostic) -> bool {
+        self.diagnostics.push(diagnostic);
+        false
+    }
+}
+
+/// Hook called after each completed stage.
+type StageHook<'o> = Box<dyn FnMut(Stage, &Session) + 'o>;
+
+/// Hook called on interpreter execution events.
+type ExecHook<'o> = Box<dyn for<'a> FnMut(&mut Interpreter<'a>, ExecEvent<'a>) + 'o>;
+
+/// Program driver, orchestrating the scanner, parser, loader and interpreter.
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::{driver::*, value::Value};
+///
+/// let mut output = Vec::new();
+/// let session = Driver::with_output(DriverOptions::default(), &mut output)
+///     .run_source("main.nxs", "let x = 6 * 7;\nprintln x;\nx;");
+///
+/// assert!(!session.has_errors());
+/// assert_eq!(session.value, Some(Value::Number(42.0)));
+/// assert_eq!(output, b"42\n");
+/// ```
+pub struct Driver<'o> {
+    options: DriverOptions,
+    output: Box<dyn Write + 'o>,
+    hook: Option<StageHook<'o>>,
+    exec_hook: Option<ExecHook<'o>>,
+}
+
+impl<'o> Driver<'o> {
+    /// Create a new driver, with program output to the standard output.
+    pub fn new(options: DriverOptions) -> Self {
+        Self::with_output(options, io::stdout())
+    }
+
+    /// Create a new driver, with program output to the given output.
+    pub fn with_output(options: DriverOptions, output: impl Write + 'o) -> Self {
+        Driver {
+            options,
+            output: Box::new(output),
+            hook: None,
+            exec_hook: None,
+        }
+    }
+
+    /// Set a hook, called after each successfully completed stage (e.g. to emit intermediate artifacts).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nexus_rs::driver::*;
+    ///
+    /// let mut stages = Vec::new();
+    /// let options = DriverOptions { stop_after: Stage::Parse, ..Default::default() };
+    ///
+    /// Driver::new(options)
+    ///     .on_stage(|stage, _| stages.push(stage))
+    ///     .run_source("main.nxs", "let x = 1;");
+    ///
+    /// assert_eq!(stages, [Stage::Scan, Stage::Parse]);
+    /// ```
+    pub fn on_stage(mut self, hook: impl FnMut(Stage, &Session) + 'o) -> Self {
+        self.hook = Some(Box::new(hook));
+        self
+    }
+
+    /// Set a hook, called on interpreter execution events (e.g. for debugging or profiling, see
+    ///  [`Interpreter::on_exec`]).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nexus_rs::driver::*;
+    ///
+    /// let mut events = 0;
+    ///
+    /// Driver::with_output(DriverOptions::default(), Vec::new())
+    ///     .on_exec(|_, _| events += 1)
+    ///     .run_source("main.nxs", "let x = 1;\nprintln abs(x);");
+    ///
+    /// assert_eq!(events, 4); // Two statements, a call and a return.
+    /// ```
+    pub fn on_exec(
+        mut self,
+        hook: impl for<'a> FnMut(&mut Interpreter<'a>, ExecEvent<'a>) + 'o,
+    ) -> Self {
+        self.exec_hook = Some(Box::new(hook));
+        self
+    }
+
+    /// Run the pipeline on a source file (`-` reads from standard input).
+    #[cfg(feature = "cli")]
+    pub fn run_file(&mut self, filename: &str) -> io::Result<Session> {
+        let lines = FileReader::try_new(filename)?
+            .into_iter()
+            .enumerate()
+            .map(|(number, line)| {
+                line.map(|line| SourceLine {
+                    line,
+                    number: Some(number + 1),
+                })
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+
+        Ok(self.run_lines(filename, lines))
+    }
+
+    /// Run the pipeline on a source text, with lines numbered from 1.
+    pub fn run_source(&mut self, name: &str, source: &str) -> Session {
+        let source = source.strip_prefix(BOM).unwrap_or(source);
+
+        self.run_lines(
+            name,
+            source
+                .lines()
+                .enumerate()
+                .map(|(number, line)| SourceLine {
+                    line: line.to_owned(),
+                    number: Some(number + 1),
+                })
+                .collect(),
+        )
+    }
+
+    /// Run the pipeline on source lines.
+    pub fn run_lines(&mut self, name: &str, lines: Vec<SourceLine>) -> Session {
+        let mut session = Session::new(name, lines);
+        session.lints = self.options.lints.clone();
+        session.loader = Loader::new()
+            .with_search_dirs(self.options.search_dirs.clone())
+            .with_packages(self.options.packages.clone());
+
+        for stage in [Stage::Scan, Stage::Parse, Stage::Check, Stage::Run] {
+            info!(target: LOG_TARGET, "{stage:?} '{name}'");
+            let start = Instant::now();
+
+            let completed = match stage {
+                Stage::Scan => Self::scan(&mut session),
+                Stage::Parse => self.parse(&mut session),
+                Stage::Check => Self::check(&mut session),
+                Stage::Run => self.run(&mut session),
+            };
+
+            debug!(
+                target: LOG_TARGET,
+                "{stage:?} {} in {:.2?} ({} token(s), {} module(s), {} diagnostic(s))",
+                if completed { "completed" } else { "failed" },
+                start.elapsed(),
+                session.tokens.len(),
+                session.loader.modules().len(),
+                session.diagnostics.len()
+            );
+
+            if !completed {
+                break;
+            }
+
+            if let Some(hook) = &mut self.hook {
+                hook(stage, &session);
+            }
+
+            if stage == self.options.stop_after {
+                break;
+            }
+        }
+
+        session
+    }
+
+    fn scan(session: &mut Session) -> bool {
+        let (tokens, spans, errors) = scan_lines(&session.lines);
+
+        session.tokens = tokens;
+        session.spans = spans;
+        session
+            .diagnostics
+            .extend(errors.into_iter().map(Diagnostic::Scan));
+
+        !session.has_errors()
+    }
+
+    fn parse(&mut self, session: &mut Session) -> bool {
+        let header = Edition::header(session.lines.iter().map(|l| l.line.as_str()));
+        session.edition = match (self.options.edition, Edition::detect(header)) {
+            (Some(edition), _) => edition,
+            (None, Ok(edition)) => edition.unwrap_or_default(),
+            (None, Err(e)) => return session.fail(Diagnostic::Parse(e)),
+        };
+
+        let parser = Parser::with_spans(session.tokens.clone(), session.spans.clone());
+        match parser.with_edition(session.edition).parse() {
+            Ok(ast) => session.ast = Some(ast),
+            Err(e) => return session.fail(Diagnostic::Parse(e)),
+        }
+
+        true
+    }
+
+    fn check(session: &mut Session) -> bool {
+        let ast = session.ast.take().expect("parsed AST");
+
+        match session.loader.add_module(&session.name, ast) {
+            Ok(index) => session.module = Some(index),
+            Err(e) => return session.fail(Diagnostic::Load(e)),
+        }
+
+        let lints = session.loader.redundant_uses();
+        session
+            .diagnostics
+            .extend(lints.into_iter().map(Diagnostic::RedundantUse));
+
+        let errors = check_connections(session.loader.modules());
+        session
+            .diagnostics
+            .extend(errors.into_iter().map(Diagnostic::Port));
+
+        !session.has_errors()
+    }
+
+    fn run(&mut self, session: &mut Session) -> bool {
+        let mut interpreter = Interpreter::with_output(&mut self.output)
+            .with_limits(self.options.limits)
+            .with_numeric_mode(self.options.numeric_mode)
+            .with_args(self.options.args.clone())
+            .with_io(!self.options.deny_io)
+            .with_clock(self.options.clock);
+        let mut result = Ok(());
+
+        if let Some(seed) = self.options.seed {
+            interpreter = interpreter.with_seed(seed);
+        }
+
+        if let Some(flag) = &self.options.interrupt {
+            interpreter = interpreter.with_interrupt(flag.clone());
+        }
+
+        if let Some(hook) = &mut self.exec_hook {
+            interpreter = interpreter.on_exec(|interpreter, event| hook(interpreter, event));
+        }
+
+        // Used files are loaded (and so run) before the files using them.
+        for module in session.loader.modules() {
+            result = interpreter
+                .run(&module.ast)
+                .map(|value| session.value = Some(value));
+            if result.is_err() {
+                break;
+            }
+        }
+
+        // The `main` function (if any) is the entry point, after all top-level statements ran.
+        if result.is_ok() {
+            if let Some(main) = interpreter.run_main() {
+                result = main.map(|value| session.value = Some(value));
+            }
+        }
+
+        session.profile = Some(interpreter.profile().clone());
+        let span = interpreter.error_span();
+        drop(interpreter);
+        let _ = self.output.flush();
+
+        match result {
+            Ok(()) => true,
+            Err(RuntimeError::Exit(code)) => {
+                session.exit = Some(code);
+                true
+            }
+            Err(RuntimeError::Interrupted) => {
+                session.exit = Some(INTERRUPTED);
+                true
+            }
+            Err(e) => session.fail(Diagnostic::Runtime(e, span)),
+        }
+    }
+}
+
+#[test]
+fn stop_after_test() {
+    let run = |stop_after: Stage| {
+        let options = DriverOptions {
+            stop_after,
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        let session = Driver::with_output(options, &mut output).run_source("t.nxs", "print 1;");
+
+        (session, output)
+    };
+
+    let (session, output) = run(Stage::Scan);
+    assert_eq!(session.tokens.len(), 3);
+    assert!(session.ast().is_none());
+    assert!(output.is_empty());
+
+    let (session, output) = run(Stage::Check);
+    assert_eq!(session.ast().map(|a| a.len()), Some(1));
+    assert_eq!(session.loader.modules().len(), 1);
+    assert!(output.is_empty());
+
+    let (session, output) = run(Stage::Run);
+    assert_eq!(session.value, Some(Value::Unit));
+    assert!(session.profile.is_some());
+    assert_eq!(output, b"1");
+}
+
+#[test]
+fn diagnostics_test() {
+    let run = |source: &str| {
+        let mut output = Vec::new();
+        let session =
+            Driver::with_output(DriverOptions::default(), &mut output).run_source("t.nxs", source);
+        session
+    };
+
+    let session = run("let x = @;\nlet y = 1;\nprint $;");
+    assert_eq!(
+        session
+            .diagnostics
+            .iter()
+            .map(|d| (d.stage(), session.severity(d)))
+            .collect::<Vec<_>>(),
+        [
+            (Stage::Scan, Severity::Error),
+            (Stage::Scan, Severity::Error)
+        ]
+    );
+    assert!(session
+        .render(&session.diagnostics[1])
+        .starts_with("  ---> t.nxs:3:7\n"));
+
+    let session = run("let x = 1;\nlet = 2;");
+    assert!(matches!(session.diagnostics[..], [Diagnostic::Parse(_)]));
+    assert!(session
+        .render(&session.diagnostics[0])
+        .contains(" 2 | let = 2;"));
+
+    let session = run("// edition: 2024\nlet match = 1;");
+    assert!(matches!(session.diagnostics[..], [Diagnostic::Parse(_)]));
+
+    let session = run("use \"nonexistent_file.nxs\";");
+    assert!(matches!(session.diagnostics[..], [Diagnostic::Load(_)]));
+
+    let session = run("let x = 1;\nx = 2;");
+    assert!(matches!(session.diagnostics[..], [Diagnostic::Runtime(..)]));
+    assert_eq!(session.value, None);
+
+    let session = run("fn main() { panic(\"oops\"); }");
+    assert_eq!(
+        session.render(&session.diagnostics[0]),
+        "panic: oops\n  ---> t.nxs:1:13"
+    );
+    assert_eq!(session.exit_code(), 1);
+
+    let session = run("fn main(x: Number) {}");
+    assert!(matches!(
+        session.diagnostics[..],
+        [Diagnostic::Runtime(
+            RuntimeError::ArgumentCount(_, 1, 0),
+            None
+        )]
+    ));
+
+    let session = run("let x: Numbr = 1;");
+    assert!(session.render(&session.diagnostics[0]).ends_with(
+        "| error[NXS0115]: unknown type 'Numbr'\n   |\n  = note: did you mean 'Number'?"
+    ));
+
+    let session = run("let total = 1;\nprint totl;");
+    assert_eq!(
+        session.render(&session.diagnostics[0]),
+        "undefined variable 'totl'\n  ---> t.nxs:2:1\n  = note: did you mean 'total'?"
+    );
+}
+
+#[test]
+fn lint_levels_test() {
+    let dir = std::env::temp_dir();
+    std::fs::write(dir.join("nexus_driver_lint_lib.nxs"), "").unwrap();
+
+    let run = |levels: &[(&str, Severity)]| {
+        let mut lints = LintLevels::default();
+        levels.iter().for_each(|(l, s)| lints.set(l, *s));
+
+        let options = DriverOptions {
+            lints,
+            ..Default::default()
+        };
+        let source =
+            "use \"nexus_driver_lint_lib.nxs\";\nuse \"./nexus_driver_lint_lib.nxs\";\nprint 1;";
+        let name = dir.join("nexus_driver_lint_main.nxs");
+
+        let mut output = Vec::new();
+        let session =
+            Driver::with_output(options, &mut output).run_source(&name.to_string_lossy(), source);
+        let severity = session.severity(&session.diagnostics[0]);
+
+        (severity, session.has_errors(), output)
+    };
+
+    assert_eq!(run(&[]), (Severity::Warning, false, b"1".to_vec()));
+    assert_eq!(
+        run(&[(WARNINGS, Severity::Error)]),
+        (Severity::Error, true, Vec::new())
+    );
+    assert_eq!(
+        run(&[
+            ("redundant-use", Severity::Note),
+            (WARNINGS, Severity::Error)
+        ]),
+        (Severity::Note, false, b"1".to_vec())
+    );
+    assert_eq!(
+        run(&[
+            ("redundant-use", Severity::Error),
+            ("redundant-use", Severity::Warning)
+        ]),
+        (Severity::Warning, false, b"1".to_vec())
+    );
+
+    assert!(LintLevels::is_lint("redundant-use"));
+    assert!(LintLevels::is_lint(WARNINGS));
+    assert!(!LintLevels::is_lint("unknown"));
+}
+
+#[test]
+fn error_format_test() {
+    let session = Driver::new(DriverOptions::default()).run_source("t.nxs", "let x: Numbr = 1;");
+    let diagnostic = &session.diagnostics[0];
+
+    assert_eq!(
+        Json::parse(&session.render_as(diagnostic, ErrorFormat::Json)).unwrap(),
+        Json::parse(
+            r#"{"severity": "error", "stage": "parse", "code": "NXS0115", "file": "t.nxs",
+                "span": {"line": 1, "start": 7, "end": 12}, "message": "unknown type 'Numbr'",
+                "notes": ["did you mean 'Number'?"]}"#
+        )
+        .unwrap()
+    );
+    assert_eq!(
+        session.render_as(diagnostic, ErrorFormat::Short),
+        "t.nxs:1:8: error[NXS0115]: unknown type 'Numbr'"
+    );
+    assert_eq!(
+        session.render_as(diagnostic, ErrorFormat::Human),
+        session.render(diagnostic)
+    );
+
+    let session = Driver::new(DriverOptions::default())
+        .run_source("t.nxs", "fn f() -> Number {\n    1 + x\n}\nprint f();");
+    let diagnostic = &session.diagnostics[0];
+
+    assert_eq!(
+        Json::parse(&session.render_as(diagnostic, ErrorFormat::Json)).unwrap(),
+        Json::parse(
+            r#"{"severity": "error", "stage": "run", "code": "NXS0314", "file": "t.nxs",
+                "span": {"line": 2, "start": 4, "end": 9}, "message": "undefined variable 'x'",
+                "notes": []}"#
+        )
+        .unwrap()
+    );
+    assert_eq!(
+        session.render_as(diagnostic, ErrorFormat::Short),
+        "t.nxs:2:5: error[NXS0314]: undefined variable 'x'"
+    );
+    assert_eq!(
+        session.render(diagnostic),
+        "undefined variable 'x'\n  ---> t.nxs:2:5"
+    );
+
+    // Errors in used files refer to the used file.
+    let dir = std::env::temp_dir();
+    let lib = dir.join("nexus_driver_format_lib.nxs");
+    std::fs::write(&lib, "fn f() -> Number {\n    1 + x\n}").unwrap();
+
+    let session = Driver::new(DriverOptions::default()).run_source(
+        &dir.join("nexus_driver_format_main.nxs").to_string_lossy(),
+        "use \"nexus_driver_format_lib.nxs\";\nprint f();",
+    );
+    assert_eq!(
+        session.render_as(&session.diagnostics[0], ErrorFormat::Short),
+        format!(
+            "{}:2:5: error[NXS0314]: undefined variable 'x'",
+            std::fs::canonicalize(lib).unwrap().display()
+        )
+    );
+}
+
+#[test]
+fn main_test() {
+    let run = |source: &str, args: &[&str]| {
+        let options = DriverOptions {
+            args: args.iter().map(|a| a.to_string()).collect(),
+            ..Default::default()
+        };
+
+        let mut output = Vec::new();
+        let session = Driver::with_output(options, &mut output).run_source("t.nxs", source);
+        assert!(!session.has_errors(), "{source}");
+
+        (session.value, String::from_utf8(output).unwrap())
+    };
+
+    assert_eq!(
+        run(
+            "fn main() -> Number { print arg(0), N; arg_count() }\nprint \"top\";\nconst N: Number = 1;",
+            &["a", "b"]
+        ),
+        (Some(Value::Number(2.0)), "topa 1".to_owned())
+    );
+    assert_eq!(
+        run("{ fn main() { print 1; } }\n2;", &[]),
+        (Some(Value::Number(2.0)), String::new())
+    );
+    assert_eq!(
+        run("print 1; fn main() { print 2; exit(0); print 3; }", &[]),
+        (Some(Value::Unit), "12".to_owned())
+    );
+
+    let session = Driver::with_output(DriverOptions::default(), io::sink())
+        .run_source("t.nxs", "fn f() { exit(3); } f(); panic(\"unreachable\");");
+    assert!(!session.has_errors());
+    assert_eq!(session.exit_code(), 3);
+
+    let options = DriverOptions {
+        deny_io: true,
+        ..Default::default()
+    };
+    let session = Driver::with_output(options, io::sink()).run_source("t.nxs", "read_line();");
+    assert!(matches!(
+        session.diagnostics[..],
+        [Diagnostic::Runtime(RuntimeError::IoDenied(_), _)]
+    ));
+}
+
+#[test]
+fn simulate_test() {
+    let run = |until: Option<u64>| {
+        let options = DriverOptions {
+            clock: Clock::simulated(until.map(std::time::Duration::from_millis)),
+            seed: Some(7),
+            ..Default::default()
+        };
+
+        let mut output = Vec::new();
+        let source = "let mut tick = 0;\n\
+                      while tick < 5 { sleep(100); tick = tick + 1; print now(), random() < 2, \";\"; };";
+        let session = Driver::with_output(options, &mut output).run_source("t.nxs", source);
+        assert!(!session.has_errors());
+
+        (session.exit_code(), String::from_utf8(output).unwrap())
+    };
+
+    assert_eq!(run(None), run(None));
+    assert_eq!(
+        run(None).1,
+        "100 true ;200 true ;300 true ;400 true ;500 true ;"
+    );
+
+    // The simulation ends (successfully) when the virtual clock passes the end time.
+    assert_eq!(run(Some(250)), (0, "100 true ;200 true ;".to_owned()));
+}
+
+#[test]
+fn interrupt_test() {
+    let flag = Arc::new(AtomicBool::new(false));
+    let options = DriverOptions {
+        interrupt: Some(flag.clone()),
+        ..Default::default()
+    };
+
+    let interrupt = std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        flag.store(true, std::sync::atomic::Ordering::SeqCst);
+    });
+
+    // Interrupting a long sleep stops the program soon, after running its deferred statements.
+    let start = Instant::now();
+    let mut output = Vec::new();
+    let source = "defer print \"stopped\"; while true { sleep(60000); };";
+    let session = Driver::with_output(options, &mut output).run_source("t.nxs", source);
+    interrupt.join().unwrap();
+
+    assert!(start.elapsed().as_secs() < 10);
+    assert!(!session.has_errors());
+    assert_eq!(session.exit_code(), INTERRUPTED);
+    assert_eq!(output, b"stopped");
+}
@@ -0,0 +1,128 @@
+//! Front-end facade: the canonical public API for turning Nexus source code into an AST.
+//!
+//! All scanner/parser types live in the `nxs_*` module groups; this module re-exports the ones needed to drive the
+//! front-end, so downstream code does not have to depend on the module layout.
+
+use thiserror::Error;
+
+pub use crate::ast::{Stmt, StmtKind, Stmts};
+pub use crate::edition::Edition;
+pub use crate::parse_error::{ParseError, ParseErrorKind, ParseResult};
+pub use crate::parser::Parser;
+pub use crate::scan_error::{ScanError, ScanErrorKind};
+pub use crate::scanner::{ScanOptions, Scanner};
+pub use crate::source_line::{SourceLine, BOM};
+pub use crate::span::{Span, Spans};
+pub use crate::symbol::Symbol;
+pub use crate::token::{Token, Tokens};
+
+/// Front-end error: either a scanning or a parsing error.
+#[derive(Error, Debug)]
+pub enum FrontError {
+    #[error("{0}")]
+    Scan(#[from] ScanError),
+
+    #[error("{0}")]
+    Parse(#[from] ParseError),
+}
+
+impl FrontError {
+    /// Get the diagnostic code of the error (see [`diagnostics`](crate::diagnostics)).
+    pub fn code(&self) -> &'static str {
+        match self {
+            FrontError::Scan(e) => e.kind().code(),
+            FrontError::Parse(e) => e.kind().code(),
+        }
+    }
+}
+
+/// Scan and parse a complete source text, with lines numbered from 1.
+///
+/// A leading UTF-8 byte order mark is stripped, and both LF and CRLF line endings are accepted. A shebang line (e.g.
+///  `#!/usr/bin/env nexus`) is skipped. The edition is taken from the header comment (see [`Edition::detect`]).
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::front::*;
+///
+/// let ast = parse_source("let x = 1;\nprint x;").unwrap();
+/// assert_eq!(ast.len(), 2);
+///
+/// assert!(matches!(parse_source("let x = 1"), Err(FrontError::Parse(_))));
+/// assert!(matches!(parse_source("let x = \"1;"), Err(FrontError::Scan(_))));
+///
+/// assert_eq!(parse_source("\u{feff}let x = 1;\r\nprint x;\r\n").unwrap().len(), 2);
+/// assert_eq!(parse_source("#!/usr/bin/env nexus\nprint 1;").unwrap().len(), 1);
+///
+/// assert!(parse_source("let match = 1;").is_ok());
+/// assert!(parse_source("// edition: 2024\nlet match = 1;").is_err());
+/// ```
+pub fn parse_source(source: &str) -> Result<Stmts, FrontError> {
+    parse_source_file(source, 0)
+}
+
+/// Scan and parse a complete source text (see [`parse_source`]) of a source file, with spans referring to the given
+///  file index (see [`Span`]).
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::front::*;
+///
+/// let ast = parse_source_file("let x = 1;", 2).unwrap();
+/// assert_eq!(ast[0].span.unwrap().file, 2);
+/// ```
+pub fn parse_source_file(source: &str, file: usize) -> Result<Stmts, FrontError> {
+    let source = source.strip_prefix(BOM).unwrap_or(source);
+    let edition = Edition::detect(Edition::header(source.lines()))?.unwrap_or_default();
+
+    let lines = source
+        .lines()
+        .enumerate()
+        .map(|(number, line)| SourceLine {
+            line: line.to_string(),
+            number: Some(number + 1),
+        })
+        .collect::<Vec<_>>();
+
+    let (tokens, mut spans, mut errors) = scan_lines(&lines);
+    if !errors.is_empty() {
+        return Err(errors.swap_remove(0).into());
+    }
+
+    spans.iter_mut().for_each(|s| s.file = file);
+
+    Ok(Parser::with_spans(tokens, spans)
+        .with_edition(edition)
+        .parse()?)
+}
+
+/// Scan source lines into a token stream (with spans), continuing after scanning errors to report them all.
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::front::*;
+///
+/// let lines = ["let x = @;", "let y = 1;", "print $ #;"].map(|l| SourceLine { line: l.to_string(), number: None });
+/// let (tokens, spans, errors) = scan_lines(&lines);
+///
+/// assert_eq!(errors.len(), 3);
+/// assert_eq!(tokens.len(), spans.len());
+/// ```
+pub fn scan_lines(lines: &[SourceLine]) -> (Tokens, Spans, Vec<ScanError>) {
+    let mut scanner = Scanner::new();
+    let mut tokens = Tokens::new();
+    let mut spans = Spans::new();
+    let mut errors = Vec::new();
+
+    for line in lines {
+        let (mut t, mut s, mut e) = scanner.scan_with_spans(line.clone());
+        tokens.append(&mut t);
+        spans.append(&mut s);
+        errors.append(&mut e);
+    }
+
+    (tokens, spans, errors)
+}
@@ -1,25 +1,96 @@
+//! Implementation of the Nexus programming language.
+//!
+//! The [`prelude`] is the stable API, meant for embedding Nexus:
+//!
+//! ```
+//! use nexus_rs::prelude::*;
+//!
+//! let mut output = Vec::new();
+//! let session = Driver::with_output(DriverOptions::default(), &mut output).run_source("main", "println 6 * 7;");
+//!
+//! assert!(!session.has_errors());
+//! assert_eq!(output, b"42\n");
+//! ```
+//!
+//! The other (re-exported) modules are public for tooling, but may change between minor versions. The module groups
+//!  (`nxs_*`) are an implementation detail, use the re-exported modules instead (e.g. `nexus_rs::parser`).
+
 /// Module group for utilities.
+#[doc(hidden)]
 pub mod utils {
+    /// Command-line options shared by all binaries (color, quiet and verbosity).
+    #[cfg(feature = "cli")]
+    pub mod cli;
+
     /// File reader based on a buffered, line-by-line file reader.
+    #[cfg(feature = "cli")]
     pub mod filereader;
+
+    /// SIGINT (Ctrl-C) handling, to stop running programs gracefully.
+    #[cfg(feature = "cli")]
+    pub mod interrupt;
+
+    /// Minimal JSON value representation, parser and writer.
+    pub mod json;
+
+    /// Edit distance based "did you mean" suggestions for misspelled names.
+    pub mod suggest;
+
+    /// Minimal parsing of the TOML subset used by configuration files.
+    pub mod toml;
+
+    /// Minimal stderr logger for developer tracing output.
+    #[cfg(feature = "cli")]
+    pub mod trace_logger;
+
+    /// Polling file watcher, detecting changes by modification time.
+    #[cfg(feature = "cli")]
+    pub mod watcher;
 }
 
 /// Module group for lexing token-related items.
+#[doc(hidden)]
 pub mod nxs_token {
     /// Scanning/lexing token representations.
     pub mod token;
+
+    /// Source code spans of tokens.
+    pub mod span;
+
+    /// Interned strings for identifiers.
+    pub mod symbol;
 }
 
 /// Module group for AST (Abstract Syntax Tree)-related items.
+#[doc(hidden)]
 pub mod nxs_ast {
     /// AST definitions for Nexus.
     pub mod ast;
 
+    /// Structural diffing of ASTs.
+    pub mod ast_diff;
+
+    /// Tree, S-expression and JSON dumps of ASTs.
+    pub mod ast_dump;
+
+    /// Documentation generation from doc comments (see the 'nexus-doc' binary).
+    pub mod docgen;
+
+    /// Minified source code output of ASTs.
+    pub mod minify;
+
     /// Pointer-wrapper used in the AST.
     pub mod ptr;
+
+    /// Name resolution with symbol tables, for finding references and renaming.
+    pub mod resolver;
+
+    /// Semantic token classification of source code, for editor highlighting (e.g. LSP semantic tokens).
+    pub mod semantic_tokens;
 }
 
 /// Module group for lexing/scanner-related items.
+#[doc(hidden)]
 pub mod nxs_scanner {
     /// Scanner/lexer for Nexus.
     pub mod scanner;
@@ -35,6 +106,7 @@ pub mod nxs_scanner {
 }
 
 /// Module group for parsing-related items.
+#[doc(hidden)]
 pub mod nxs_parser {
     /// Parser for Nexus.
     pub mod parser;
@@ -44,10 +116,161 @@ pub mod nxs_parser {
 
     /// Parser error representation.
     pub mod parse_error;
+
+    /// Language editions.
+    pub mod edition;
+}
+
+/// Module group for source file loading-related items.
+#[doc(hidden)]
+pub mod nxs_loader {
+    /// Function call graph and module import graph analysis, exported as DOT.
+    pub mod call_graph;
+
+    /// Source file loader, resolving 'use' declarations.
+    pub mod loader;
+
+    /// Project manifest ('nexus.toml'), declaring the main file, source directories and dependencies.
+    pub mod manifest;
+
+    /// Package dependency resolution, with lockfile ('nexus.lock') and git package cache.
+    pub mod package;
+}
+
+/// Module group for node graph-related items.
+#[doc(hidden)]
+pub mod nxs_graph {
+    /// Connection buffers, queueing values between nodes according to a buffering policy.
+    pub mod buffer;
+
+    /// Node graph topology representation.
+    pub mod graph;
+
+    /// Diffing of node graphs by node paths, for reloading a running graph.
+    pub mod graph_diff;
+
+    /// Built-in node library: node kinds predefined in all programs.
+    pub mod library;
+
+    /// Metrics of graph runs: per-node counters, with observers for embedders.
+    pub mod metrics;
+
+    /// Native node interface, for implementing node kinds in host (Rust) code.
+    pub mod native;
+
+    /// Port resolution and validation of connections against node declarations.
+    pub mod ports;
+}
+
+/// Module group for interpreter-related items.
+#[doc(hidden)]
+pub mod nxs_interp {
+    /// Built-in functions.
+    pub mod builtins;
+
+    /// System and virtual (simulated) clocks, for the time built-in functions.
+    pub mod clock;
+
+    /// Interactive debugger (breakpoints and stepping), driven by an interpreter execution hook.
+    pub mod debugger;
+
+    /// Tree-walking interpreter for Nexus.
+    pub mod interpreter;
+
+    /// Interpreter resource limits.
+    pub mod limits;
+
+    /// Numeric error policy (IEEE semantics or strict domain errors).
+    pub mod numeric;
+
+    /// Heap and execution profiling of programs.
+    pub mod profile;
+
+    /// Pseudo-random number generation.
+    pub mod random;
+
+    /// Runtime error representation.
+    pub mod runtime_error;
+
+    /// Runtime value representation.
+    pub mod value;
+}
+
+/// Module group for REPL-related items.
+#[doc(hidden)]
+#[cfg(feature = "cli")]
+pub mod nxs_repl {
+    /// REPL configuration.
+    pub mod repl_config;
+
+    /// Line editor helper for the REPL.
+    pub mod repl_helper;
+
+    /// Syntax highlighting and bracket matching of REPL input.
+    pub mod repl_highlight;
+
+    /// REPL input buffering.
+    pub mod repl_input;
+
+    /// REPL prompt rendering.
+    pub mod repl_prompt;
+}
+
+/// Front-end facade (scanning + parsing) over the module groups above.
+pub mod front;
+
+/// Program driver (scanning, parsing, checking and running) over the module groups above.
+pub mod driver;
+
+/// Diagnostic codes registry, with explanations.
+pub mod diagnostics;
+
+/// Stable API, guarded by semantic versioning.
+pub mod prelude {
+    pub use crate::ast;
+    pub use crate::clock::Clock;
+    pub use crate::driver::{
+        Diagnostic, Driver, DriverOptions, ErrorFormat, LintLevels, Session, Severity, Stage,
+    };
+    pub use crate::edition::Edition;
+    pub use crate::front::{parse_source, FrontError};
+    pub use crate::interpreter::Interpreter;
+    pub use crate::limits::{Limit, Limits};
+    pub use crate::numeric::{DomainError, NumericMode};
+    pub use crate::parse_error::{ParseError, ParseErrorKind};
+    pub use crate::parser::Parser;
+    pub use crate::runtime_error::RuntimeError;
+    pub use crate::scan_error::{ScanError, ScanErrorKind};
+    pub use crate::scanner::{ScanOptions, Scanner};
+    pub use crate::source_line::SourceLine;
+    pub use crate::span::{Span, Spans};
+    pub use crate::symbol::Symbol;
+    pub use crate::token::{Token, Tokens};
+    pub use crate::value::Value;
 }
 
+#[doc(inline)]
 pub use nxs_ast::*;
-pub use nxs_parser::*;
-pub use nxs_scanner::*;
+#[doc(inline)]
+pub use nxs_graph::*;
+#[doc(inline)]
+pub use nxs_interp::*;
+#[doc(inline)]
+pub use nxs_loader::*;
+#[doc(hidden)]
+pub use nxs_parser::token_cursor;
+#[doc(inline)]
+pub use nxs_parser::{edition, parse_error, parser};
+#[cfg(feature = "cli")]
+#[doc(inline)]
+pub use nxs_repl::*;
+#[doc(hidden)]
+pub use nxs_scanner::cursor;
+#[doc(inline)]
+pub use nxs_scanner::{scan_error, scanner, source_line};
+#[doc(inline)]
 pub use nxs_token::*;
+#[doc(inline)]
+pub use scanner::keywords;
+#[doc(inline)]
 pub use utils::*;
@@ -1,97 +1,880 @@
-use clap::Parser;
+use clap::{Parser, Subcommand, ValueEnum};
 use colored::Colorize;
-use nexus_rs::{filereader::*, *};
-use rustyline::{error::ReadlineError, DefaultEditor};
-use std::process::exit;
+use nexus_rs::{
+    cli::{self, GlobalArgs},
+    clock::Clock,
+    driver::{
+        Diagnostic, Driver, DriverOptions, ErrorFormat, LintLevels, Session, Severity, Stage,
+        LINTS, WARNINGS,
+    },
+    manifest::{Manifest, ManifestError},
+    package::Lockfile,
+    repl_config::ReplConfig,
+    repl_helper::ReplHelper,
+    repl_input::InputBuffer,
+    repl_prompt::{PromptState, ReplPrompt},
+    watcher::Watcher,
+    *,
+};
+use rustyline::{error::ReadlineError, history::DefaultHistory, Config, Editor};
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    io::{self, IsTerminal, Write},
+    path::{Path, PathBuf},
+    process::exit,
+    time::Duration,
+};
 
 /// Nexus programming language interpreter.
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 struct Args {
-    /// Input source filename (omit for REPL).
+    /// Input source filename, '-' reads from standard input (omit for REPL).
     #[arg(short, long)]
     filename: Option<String>,
+
+    /// Run the given source code instead of a source file (e.g. -e 'print 1 + 2;').
+    #[arg(short, long, value_name = "CODE", conflicts_with_all = ["filename", "debug", "watch"])]
+    eval: Option<String>,
+
+    /// Trace parser rule entry/exit with the current token and position to stderr (for debugging the grammar).
+    #[arg(long, alias = "trace-parse")]
+    trace_parser: bool,
+
+    /// Output emitted instead of running the program from file.
+    #[arg(long, value_enum)]
+    emit: Option<Emit>,
+
+    /// Consistently rename declared identifiers to short names (for '--emit min').
+    #[arg(long)]
+    rename: bool,
+
+    /// Language edition, overriding the edition header comment of the source file (e.g. '// edition: 2024').
+    #[arg(long)]
+    edition: Option<edition::Edition>,
+
+    /// Maximum number of evaluation steps when running (unlimited by default).
+    #[arg(long)]
+    max_steps: Option<u64>,
+
+    /// Maximum function call depth when running (at most the expression nesting limit, as each call nests an
+    ///  expression).
+    #[arg(long, default_value_t = limits::DEFAULT_MAX_CALL_DEPTH, value_parser = parse_call_depth)]
+    max_call_depth: usize,
+
+    /// Maximum number of bytes held by live values when running (unlimited by default).
+    #[arg(long)]
+    max_heap: Option<usize>,
+
+    /// Numeric error policy: IEEE semantics (e.g. '1 / 0' is 'inf'), or a runtime error for undefined and infinite
+    ///  results.
+    #[arg(long, value_enum, value_name = "MODE", default_value_t = numeric::NumericMode::Ieee)]
+    numeric_mode: numeric::NumericMode,
+
+    /// Simulate time: 'sleep(ms)' advances a virtual clock (starting at zero) instantly, and the random number
+    ///  generator is seeded (with zero, unless given), so runs are deterministic.
+    #[arg(long)]
+    simulate: bool,
+
+    /// End the simulation when the virtual clock passes the given time (e.g. '1000ms' or '2s').
+    #[arg(long, value_name = "TIME", requires = "simulate", value_parser = clock::parse_duration)]
+    until: Option<Duration>,
+
+    /// Seed the random number generator (seeded from the system time by default), for reproducible runs.
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Report the execution profile (statement counts, function calls and time) and the heap profile of runtime values
+    ///  to stderr after running.
+    #[arg(long)]
+    profile: bool,
+
+    /// Program arguments (after '--'), available through the 'arg_count()' and 'arg(i)' built-in functions.
+    #[arg(last = true)]
+    args: Vec<String>,
+
+    /// Deny the program file and input access (for running untrusted programs).
+    #[arg(long)]
+    deny_io: bool,
+
+    /// Diagnostics output format.
+    #[arg(long, value_enum, default_value_t = ErrorFormat::Human)]
+    error_format: ErrorFormat,
+
+    /// Report a lint as warning (e.g. '-W redundant-use').
+    #[arg(short = 'W', value_name = "LINT", value_parser = parse_lint)]
+    warn: Vec<String>,
+
+    /// Report a lint as error, failing the run ('-D warnings' denies all warnings).
+    #[arg(short = 'D', value_name = "LINT", value_parser = parse_lint)]
+    deny: Vec<String>,
+
+    /// Report a lint as note, never failing the run.
+    #[arg(short = 'A', value_name = "LINT", value_parser = parse_lint)]
+    allow: Vec<String>,
+
+    /// Debug the program interactively: pause at breakpoints and step through statements ('help' lists the commands).
+    #[arg(long, conflicts_with = "emit")]
+    debug: bool,
+
+    /// Re-check (or re-run) the program whenever the source file or any file it uses changes, until interrupted.
+    #[arg(
+        long,
+        value_enum,
+        value_name = "MODE",
+        num_args = 0..=1,
+        default_missing_value = "check",
+        conflicts_with = "debug"
+    )]
+    watch: Option<Watch>,
+
+    /// Explain a diagnostic code (e.g. 'NXS0105').
+    #[arg(long, value_name = "CODE")]
+    explain: Option<String>,
+
+    #[command(flatten)]
+    global: GlobalArgs,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Emit {
+    /// AST representation.
+    Ast,
+
+    /// Minified source code.
+    Min,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum Watch {
+    /// Scan, parse and check the program.
+    Check,
+
+    /// Also run the program.
+    Run,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Show the structural differences (declarations, function bodies) between two source files.
+    AstDiff {
+        /// Old source filename.
+        old: String,
+
+        /// New source filename.
+        new: String,
+    },
+
+    /// Check all source files of a project (see 'nexus.toml'), reporting the diagnostics of all files.
+    Build {
+        /// Project manifest path (default: 'nexus.toml' in the current directory or any parent directory).
+        #[arg(long)]
+        manifest_path: Option<PathBuf>,
+    },
+
+    /// Run the main file of a project (see 'nexus.toml').
+    Run {
+        /// Project manifest path (default: 'nexus.toml' in the current directory or any parent directory).
+        #[arg(long)]
+        manifest_path: Option<PathBuf>,
+
+        /// Program arguments (after '--').
+        #[arg(last = true)]
+        args: Vec<String>,
+    },
 }
 
 fn main() {
     let args = Args::parse();
 
-    if let Some(filename) = args.filename {
-        run_from_file(filename);
-    } else {
-        run_repl();
+    args.global
+        .init(args.trace_parser.then_some(token_cursor::TRACE_TARGET));
+
+    if let Some(code) = args.explain {
+        match diagnostics::explain(&code) {
+            Some(explanation) => println!("{explanation}"),
+            None => {
+                eprintln!("{}: unknown diagnostic code '{code}'", "Error".red().bold());
+                exit(1);
+            }
+        }
+        return;
+    }
+
+    let mut options = DriverOptions {
+        stop_after: if args.emit.is_some() {
+            Stage::Check
+        } else {
+            Stage::Run
+        },
+        edition: args.edition,
+        limits: limits::Limits {
+            max_steps: args.max_steps,
+            max_call_depth: Some(args.max_call_depth),
+            max_heap: args.max_heap,
+        },
+        numeric_mode: args.numeric_mode,
+        clock: match args.simulate {
+            true => Clock::simulated(args.until),
+            false => Clock::System,
+        },
+        seed: args.seed.or(args.simulate.then_some(0)),
+        interrupt: None,
+        args: args.args,
+        deny_io: args.deny_io,
+        lints: lint_levels(&args.allow, &args.warn, &args.deny),
+        search_dirs: Vec::new(),
+        packages: BTreeMap::new(),
+    };
+
+    let output = Output {
+        emit: args.emit,
+        rename: args.rename,
+        profile: args.profile,
+        error_format: args.error_format,
+    };
+
+    match (args.command, args.filename, args.eval) {
+        (Some(Command::AstDiff { old, new }), ..) => run_ast_diff(&old, &new, args.error_format),
+        (Some(Command::Build { manifest_path }), ..) => {
+            let manifest = load_manifest(manifest_path);
+            options.packages = resolve_packages(&manifest);
+
+            if !run_build(&manifest, options, args.error_format) {
+                exit(1);
+            }
+        }
+        (
+            Some(Command::Run {
+                manifest_path,
+                args: program_args,
+            }),
+            ..,
+        ) => {
+            let manifest = load_manifest(manifest_path);
+            options.args = program_args;
+            options.search_dirs = manifest.source_dirs();
+            options.packages = resolve_packages(&manifest);
+
+            let main = manifest.main_file().display().to_string();
+            run_file(&main, options, output, args.debug, args.watch);
+        }
+        (None, _, Some(code)) => run_code(&code, options, output),
+        (None, Some(filename), None) => {
+            run_file(&filename, options, output, args.debug, args.watch)
+        }
+        (None, None, None) => run_repl(),
+    }
+}
+
+/// Run (or emit, debug or watch) a source file, exiting with the exit code of the program on failure.
+fn run_file(
+    filename: &str,
+    mut options: DriverOptions,
+    output: Output,
+    debug: bool,
+    watch: Option<Watch>,
+) {
+    if (debug || watch.is_some()) && filename == "-" {
+        let action = if debug { "debug" } else { "watch" };
+        eprintln!(
+            "{}: cannot {action} a program read from standard input",
+            "Error".red().bold()
+        );
+        exit(1);
+    }
+
+    if let Some(watch) = watch {
+        run_watch(filename, options, output, watch);
+    }
+
+    // Ctrl-C stops the program gracefully (watching stops on Ctrl-C instead).
+    options.interrupt = Some(interrupt::install());
+
+    match run_from_file(filename, options, output, debug) {
+        Ok(session) => match session.exit_code() {
+            0 => (),
+            code => exit(code),
+        },
+        Err(e) => {
+            eprintln!("{}: failed to read '{filename}': {e}", "Error".red().bold());
+            exit(1);
+        }
     }
 }
 
-fn run_from_file(filename: String) {
-    let file = FileReader::try_new(&filename).unwrap_or_else(|e| {
-        eprintln!("Failed to open file: {e}");
+/// Run (or emit) inline source code ('--eval'), exiting with the exit code of the program on failure.
+fn run_code(code: &str, mut options: DriverOptions, output: Output) {
+    options.interrupt = Some(interrupt::install());
+    let session = run_program(Input::Code(code), options, output, false)
+        .expect("inline code is not read from a file");
+
+    match session.exit_code() {
+        0 => (),
+        code => exit(code),
+    }
+}
+
+/// Load the given project manifest, or else find it from the current directory, exiting on failure.
+fn load_manifest(path: Option<PathBuf>) -> Manifest {
+    let result = match path {
+        Some(path) => Manifest::load(path),
+        None => std::env::current_dir()
+            .map_err(|e| ManifestError::Io(PathBuf::from("."), e))
+            .and_then(Manifest::find),
+    };
+
+    result.unwrap_or_else(|e| {
+        eprintln!("{}: {e}", "Error".red().bold());
         exit(1);
+    })
+}
+
+/// Resolve the packages of a project (see 'nexus.toml'), updating its lockfile, exiting on failure.
+fn resolve_packages(manifest: &Manifest) -> BTreeMap<String, Manifest> {
+    let cache_dir = dirs_next::cache_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("nexus");
+
+    let result = Lockfile::load(&manifest.root).and_then(|lockfile| {
+        let resolution = package::resolve(manifest, &lockfile, &cache_dir)?;
+
+        if resolution.lockfile != lockfile {
+            resolution.lockfile.save(&manifest.root)?;
+        }
+
+        Ok(resolution.packages)
     });
 
-    let mut scanner = scanner::Scanner::new();
-    let mut scan_error = false;
-
-    let mut parser = parser::Parser::new(file.into_iter().enumerate().fold(
-        token::Tokens::new(),
-        |mut acc, line| {
-            let (number, line) = line;
-            match scanner.scan(source_line::SourceLine {
-                line,
-                number: Some(number + 1),
-            }) {
-                Ok(mut result) => acc.append(&mut result),
-                Err(error) => {
-                    scan_error = true;
-
-                    eprintln!("  ---> {filename}:{number}");
-                    eprintln!("{error}");
-                }
+    result.unwrap_or_else(|e| {
+        eprintln!("{}: {e}", "Error".red().bold());
+        exit(1);
+    })
+}
+
+/// Check all source files of a project, starting with the main file, returning whether it succeeded.
+///
+/// Files loaded (or failed to load) through a 'use' declaration of a file checked earlier are not checked again, so
+///  their diagnostics are only reported once.
+fn run_build(manifest: &Manifest, options: DriverOptions, format: ErrorFormat) -> bool {
+    let options = DriverOptions {
+        stop_after: Stage::Check,
+        search_dirs: manifest.source_dirs(),
+        ..options
+    };
+
+    let files = manifest.source_files().unwrap_or_else(|e| {
+        eprintln!(
+            "{}: failed to read source directories: {e}",
+            "Error".red().bold()
+        );
+        exit(1);
+    });
+
+    let canonical = |path: &Path| fs::canonicalize(path).unwrap_or(path.to_owned());
+    let mut checked = HashSet::new();
+    let mut success = true;
+
+    for file in std::iter::once(manifest.main_file()).chain(files) {
+        if !checked.insert(canonical(&file)) {
+            continue;
+        }
+
+        let filename = file.display().to_string();
+        let session = match Driver::new(options.clone()).run_file(&filename) {
+            Ok(session) => session,
+            Err(e) => {
+                eprintln!("{}: failed to read '{filename}': {e}", "Error".red().bold());
+                success = false;
+                continue;
             }
+        };
 
-            acc
-        },
-    ));
+        checked.extend(session.loader.modules().iter().map(|m| m.path.clone()));
+        checked.extend(session.diagnostics.iter().filter_map(|d| match d {
+            Diagnostic::Load(e) => Some(canonical(e.path())),
+            _ => None,
+        }));
+
+        success &= report(&session, format);
+    }
+
+    let name = manifest
+        .name
+        .clone()
+        .or_else(|| {
+            canonical(&manifest.root)
+                .file_name()
+                .map(|n| n.to_string_lossy().into())
+        })
+        .unwrap_or_default();
+
+    if success {
+        eprintln!(
+            "{} project '{name}' ({} file(s) checked)",
+            "Finished".green().bold(),
+            checked.len()
+        );
+    } else {
+        eprintln!("{}: project '{name}' has errors", "Error".red().bold());
+    }
+
+    success
+}
+
+/// Parse a call depth argument, which cannot exceed the expression nesting limit (the stack budget).
+fn parse_call_depth(depth: &str) -> Result<usize, String> {
+    match depth.parse::<usize>() {
+        Ok(depth) if depth <= limits::MAX_EXPR_DEPTH => Ok(depth),
+        Ok(_) => Err(format!(
+            "exceeds the expression nesting limit of {}",
+            limits::MAX_EXPR_DEPTH
+        )),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Parse a lint name argument.
+fn parse_lint(name: &str) -> Result<String, String> {
+    if LintLevels::is_lint(name) {
+        Ok(name.to_owned())
+    } else {
+        Err(format!(
+            "unknown lint (lints: {WARNINGS}, {})",
+            LINTS.join(", ")
+        ))
+    }
+}
+
+/// Collect the lint levels, where denying takes precedence over warning, and warning over allowing.
+fn lint_levels(allow: &[String], warn: &[String], deny: &[String]) -> LintLevels {
+    let mut levels = LintLevels::default();
+
+    for (lints, severity) in [
+        (allow, Severity::Note),
+        (warn, Severity::Warning),
+        (deny, Severity::Error),
+    ] {
+        lints.iter().for_each(|lint| levels.set(lint, severity));
+    }
+
+    levels
+}
+
+fn run_ast_diff(old: &str, new: &str, format: ErrorFormat) {
+    let parse_file = |filename: &str| {
+        let mut session = Driver::new(DriverOptions {
+            stop_after: Stage::Parse,
+            ..Default::default()
+        })
+        .run_file(filename)
+        .unwrap_or_else(|e| {
+            eprintln!("{}: failed to read '{filename}': {e}", "Error".red().bold());
+            exit(1);
+        });
+
+        if !report(&session, format) {
+            exit(1);
+        }
+
+        session.take_ast().expect("parsed AST")
+    };
+
+    let changes = ast_diff::diff(&parse_file(old), &parse_file(new));
+
+    if changes.is_empty() {
+        println!("No structural changes");
+    }
 
-    if scan_error {
+    for change in changes {
+        let line = change.to_string();
+        match change {
+            ast_diff::AstChange::Added(_) => println!("{}", line.green()),
+            ast_diff::AstChange::Removed(_) => println!("{}", line.red()),
+            ast_diff::AstChange::Modified(..) => println!("{}", line.yellow()),
+        }
+    }
+}
+
+/// Output options of running a source file.
+#[derive(Clone, Copy)]
+struct Output {
+    emit: Option<Emit>,
+    rename: bool,
+    profile: bool,
+    error_format: ErrorFormat,
+}
+
+/// Program input: a source file ('-' reads from standard input) or inline source code.
+#[derive(Clone, Copy)]
+enum Input<'a> {
+    File(&'a str),
+    Code(&'a str),
+}
+
+/// Name of inline source code in diagnostics (relative 'use' paths resolve from the current directory).
+const EVAL_NAME: &str = "<eval>";
+
+/// Run (or emit) a source file, optionally under the debugger, returning the driver session.
+fn run_from_file(
+    filename: &str,
+    options: DriverOptions,
+    output: Output,
+    debug: bool,
+) -> io::Result<Session> {
+    run_program(Input::File(filename), options, output, debug)
+}
+
+/// Run (or emit) a program, optionally under the debugger, returning the driver session.
+fn run_program(
+    input: Input,
+    options: DriverOptions,
+    output: Output,
+    debug: bool,
+) -> io::Result<Session> {
+    let Output {
+        emit,
+        rename,
+        profile,
+        error_format,
+    } = output;
+
+    let mut debugger = if debug {
+        Some(debugger::Debugger::new(match input {
+            Input::File(filename) => read_lines(filename)?,
+            Input::Code(code) => source_lines(code),
+        }))
+    } else {
+        None
+    };
+    let mut exec_profile = profile.then(profile::ExecProfile::new);
+
+    let mut driver = Driver::new(options);
+
+    if debugger.is_some() || exec_profile.is_some() {
+        driver = driver.on_exec(|interpreter, event| {
+            if let Some(exec_profile) = &mut exec_profile {
+                exec_profile.record(event);
+            }
+            if let Some(debugger) = &mut debugger {
+                debugger.on_exec(interpreter, event);
+            }
+        });
+    }
+
+    let session = match input {
+        Input::File(filename) => driver.run_file(filename)?,
+        Input::Code(code) => driver.run_source(EVAL_NAME, code),
+    };
+    drop(driver);
+
+    if let Some(exec_profile) = exec_profile {
+        eprintln!("{}\n{exec_profile}", "Execution profile".bold());
+    }
+
+    if let (true, Some(profile)) = (profile, &session.profile) {
+        eprintln!("{}\n{profile}", "Heap profile".bold());
+    }
+
+    if !report(&session, error_format) {
+        return Ok(session);
+    }
+
+    if let (Some(emit), Some(ast)) = (emit, session.ast()) {
+        match emit {
+            Emit::Ast => println!("{ast}"),
+            Emit::Min => println!("{}", minify::minify(ast, minify::MinifyOptions { rename })),
+        }
+    }
+
+    Ok(session)
+}
+
+/// Re-check (or re-run) a source file whenever it or any file it uses changes, clearing the screen in between.
+fn run_watch(filename: &str, mut options: DriverOptions, output: Output, watch: Watch) -> ! {
+    if watch == Watch::Check {
+        options.stop_after = Stage::Check;
+    }
+
+    // Clearing the screen is terminal output, so it is left out where colors are (e.g. '--color never').
+    let clear = io::stdout().is_terminal() && colored::control::SHOULD_COLORIZE.should_colorize();
+
+    // The main file is always watched, also if it cannot be read (yet).
+    let main = fs::canonicalize(filename).unwrap_or(PathBuf::from(filename));
+    let mut paths = vec![main.clone()];
+
+    loop {
+        if clear {
+            print!("\x1b[2J\x1b[H");
+            let _ = io::stdout().flush();
+        }
+
+        // Snapshot the files of the previous run before running, so changes made during the run are not missed.
+        let mut watcher = Watcher::new(std::mem::replace(&mut paths, vec![main.clone()]));
+
+        match run_from_file(filename, options.clone(), output, false) {
+            Ok(session) => {
+                paths.extend(session.loader.modules().iter().map(|m| m.path.clone()));
+                paths.extend(session.diagnostics.iter().filter_map(|d| match d {
+                    Diagnostic::Load(e) => Some(e.path().to_owned()),
+                    _ => None,
+                }));
+
+                let status = match (session.has_errors(), watch) {
+                    (true, _) => "failed".red().bold(),
+                    (false, Watch::Check) => "checked".green().bold(),
+                    (false, Watch::Run) => "finished".green().bold(),
+                };
+                eprintln!("{status}");
+            }
+            Err(e) => eprintln!("{}: failed to read '{filename}': {e}", "Error".red().bold()),
+        }
+
+        paths.sort();
+        paths.dedup();
+        watcher.add(paths.iter().cloned());
+
+        eprintln!(
+            "{}",
+            format!(
+                "watching {} file(s) for changes (press Ctrl-C to stop)",
+                watcher.paths().count()
+            )
+            .dimmed()
+        );
+
+        watcher.wait();
+    }
+}
+
+/// Read the numbered lines of a source file (e.g. to show source in the debugger).
+fn read_lines(filename: &str) -> io::Result<Vec<source_line::SourceLine>> {
+    Ok(source_lines(&fs::read_to_string(filename)?))
+}
+
+/// Get the numbered lines of source code.
+fn source_lines(source: &str) -> Vec<source_line::SourceLine> {
+    source
+        .strip_prefix(source_line::BOM)
+        .unwrap_or(source)
+        .lines()
+        .enumerate()
+        .map(|(number, line)| source_line::SourceLine {
+            line: line.to_owned(),
+            number: Some(number + 1),
+        })
+        .collect()
+}
+
+/// Report the diagnostics of a driver session, returning whether it succeeded.
+///
+/// With `--quiet`, only errors are reported.
+fn report(session: &Session, format: ErrorFormat) -> bool {
+    let diagnostics: Vec<_> = session
+        .diagnostics
+        .iter()
+        .filter(|d| !cli::is_quiet() || session.severity(d) == Severity::Error)
+        .collect();
+
+    if format != ErrorFormat::Human {
+        for diagnostic in diagnostics {
+            eprintln!("{}", session.render_as(diagnostic, format));
+        }
+
+        return !session.has_errors();
+    }
+
+    for &diagnostic in &diagnostics {
+        match diagnostic {
+            Diagnostic::Scan(_) | Diagnostic::Parse(_) => {
+                eprintln!("{}", session.render(diagnostic))
+            }
+            d => {
+                let severity = match session.severity(d) {
+                    Severity::Error => "Error".red().bold(),
+                    Severity::Warning => "Warning".yellow().bold(),
+                    Severity::Note => "Note".cyan().bold(),
+                };
+
+                eprintln!("{severity}[{}]: {}", d.code(), session.render(d));
+            }
+        }
+    }
+
+    if !diagnostics.is_empty() && !cli::is_quiet() {
+        eprintln!("For more information about an error, try 'nexus-rs --explain <CODE>'.");
+    }
+
+    if session.diagnostics.iter().any(|d| d.stage() == Stage::Scan) {
         eprintln!("scanning failed, aborting");
-        return;
     }
 
-    match parser.parse() {
-        Ok(ast) => println!("{ast}"),
-        Err(e) => eprintln!("{}: {e:?}", "Error".red().bold()),
+    !session.has_errors()
+}
+
+/// Run the REPL startup script, adding its definitions to the session.
+fn load_startup_script(path: &Path, session: &mut ast::Stmts) {
+    let filename = path.display().to_string();
+
+    let report = |result: &Session| {
+        for diagnostic in &result.diagnostics {
+            eprintln!(
+                "{} (in '{filename}'):\n{}",
+                "Error".red().bold(),
+                result.render(diagnostic)
+            );
+        }
+    };
+
+    let mut parser = Driver::new(DriverOptions {
+        stop_after: Stage::Parse,
+        ..Default::default()
+    });
+
+    let mut parsed = match parser.run_file(&filename) {
+        Ok(parsed) => parsed,
+        Err(e) => return eprintln!("failed to read startup script '{filename}': {e}"),
+    };
+
+    report(&parsed);
+
+    // The run consumes its AST, so the parsed one is kept for the session.
+    if let Some(mut ast) = parsed.take_ast() {
+        report(&Driver::new(DriverOptions::default()).run_lines(&filename, parsed.lines));
+        session.append(&mut ast);
     }
 }
 
 fn run_repl() {
-    let Ok(mut rl) = DefaultEditor::new() else {
+    let config = ReplConfig::load().unwrap_or_else(|e| {
+        eprintln!("failed to load REPL configuration: {e}");
+        ReplConfig::default()
+    });
+
+    if !config.color {
+        colored::control::set_override(false);
+    }
+
+    let prompt = ReplPrompt::new(&config.prompt, config.continuation_prompt.as_deref());
+
+    // Bracketed paste makes sure pasted multiline input arrives as a single unit.
+    let Ok(mut rl) = Config::builder()
+        .bracketed_paste(true)
+        .max_history_size(config.history_size)
+        .and_then(|builder| Editor::<ReplHelper, DefaultHistory>::with_config(builder.build()))
+    else {
         eprintln!("failed to create REPL interface");
         exit(1);
     };
 
+    rl.set_helper(Some(ReplHelper::new(prompt.clone())));
+
+    let history = ReplConfig::history_file().filter(|_| config.save_history);
+
+    // A missing history file is expected on first use.
+    if let Some(path) = history.as_ref().filter(|p| p.is_file()) {
+        if let Err(e) = rl.load_history(path) {
+            eprintln!("failed to load REPL history '{}': {e}", path.display());
+        }
+    }
+
+    // All definitions made in this session (including those from the startup script).
+    let mut session = ast::Stmts::new();
+
+    if let Some(path) = ReplConfig::startup_script() {
+        load_startup_script(&path, &mut session);
+    }
+
+    if let Some(helper) = rl.helper_mut() {
+        helper.set_bindings(&session);
+    }
+
+    let mut input = InputBuffer::new();
+    let mut state = PromptState::Ready;
+    let mut failed = false;
+
     loop {
-        match rl.readline("> ") {
+        if let Some(helper) = rl.helper() {
+            helper.set_state(state);
+        }
+
+        match rl.readline(&prompt.render(state)) {
             Ok(line) => {
-                rl.add_history_entry(line.clone())
+                if input.is_empty() && line.trim().is_empty() {
+                    continue;
+                }
+
+                input.push(line);
+
+                if !input.is_complete() {
+                    state = PromptState::Continuation;
+                    continue;
+                }
+
+                rl.add_history_entry(input.text())
                     .expect("failed to store line to history");
-                match scanner::Scanner::new().scan(source_line::SourceLine { line, number: None }) {
-                    Ok(tokens) => match parser::Parser::new(tokens).parse() {
-                        Ok(ast) => println!("{ast}"),
-                        Err(e) => eprintln!("{}: {e:?}", "Error".red().bold()),
-                    },
-                    Err(error) => eprintln!("{error}"),
+
+                state = if run_input(input.take(), &mut session) {
+                    PromptState::Ready
+                } else {
+                    PromptState::Error
+                };
+
+                if let Some(helper) = rl.helper_mut() {
+                    helper.set_bindings(&session);
                 }
             }
             Err(ReadlineError::Eof) => break,
+            Err(ReadlineError::Interrupted) if !input.is_empty() => {
+                input.clear();
+                state = PromptState::Ready;
+            }
             Err(ReadlineError::Interrupted) => {
                 eprintln!("interrupted");
                 break; // TODO: Do something else?
             }
             _ => {
                 eprintln!("failed to parse input");
-                exit(1);
+                failed = true;
+                break;
             }
         }
     }
+
+    if let Some(path) = history {
+        if let Err(e) = rl.save_history(&path) {
+            eprintln!("failed to save REPL history '{}': {e}", path.display());
+        }
+    }
+
+    if failed {
+        exit(1);
+    }
+}
+
+/// Run a unit of REPL input, returning whether it succeeded.
+fn run_input(lines: Vec<source_line::SourceLine>, session: &mut ast::Stmts) -> bool {
+    let mut driver = Driver::new(DriverOptions {
+        stop_after: Stage::Parse,
+        ..Default::default()
+    });
+
+    let mut result = driver.run_lines("<repl>", lines);
+
+    for diagnostic in &result.diagnostics {
+        eprintln!("{}", result.render(diagnostic));
+    }
+
+    match result.take_ast() {
+        Some(mut ast) => {
+            println!("{ast}");
+            session.append(&mut ast);
+            true
+        }
+        None => false,
+    }
 }
@@ -2,6 +2,8 @@ use std::{fmt, ops};
 use strum_macros::Display;
 
 use super::ptr::Ptr;
+use crate::span::Span;
+use crate::symbol::Symbol;
 
 /// AST node evaluation trait, used for simple evaluation.
 ///
@@ -17,6 +19,7 @@ pub trait Eval<R, E> {
 #[derive(Debug)]
 pub struct Stmt {
     pub kind: StmtKind,
+    pub span: Option<Span>, // Source span of the statement (if known), on its first line.
 }
 
 impl fmt::Display for Stmt {
@@ -32,9 +35,15 @@ pub enum StmtKind {
     Block(Stmts),
     Connect(Ptr<Connect>),
     ConstDecl(Ptr<ConstDecl>),
+    Defer(Ptr<Stmt>),
+    EnumDecl(Ptr<EnumDecl>),
+    Disconnect(Ptr<Connect>),
     Expr(Ptr<Expr>),
     FunctionDecl(Ptr<FunctionDecl>),
+    GroupDecl(Ptr<GroupDecl>),
+    NodeDecl(Ptr<NodeDecl>),
     Print(Ptr<Print>),
+    Remove(Ptr<Remove>),
     Return(Ptr<Return>),
     UseDecl(Ptr<UseDecl>),
     VarDecl(Ptr<VarDecl>),
@@ -48,9 +57,15 @@ impl fmt::Display for StmtKind {
             StmtKind::Block(x) => write!(f, "BlockStmt {{ {x} }}"),
             StmtKind::Connect(x) => write!(f, "ConnectStmt {{ {x} }}"),
             StmtKind::ConstDecl(x) => write!(f, "ConstDeclStmt {{ {x} }}"),
+            StmtKind::Defer(x) => write!(f, "DeferStmt {{ {x} }}"),
+            StmtKind::Disconnect(x) => write!(f, "DisconnectStmt {{ {x} }}"),
+            StmtKind::EnumDecl(x) => write!(f, "EnumDeclStmt {{ {x} }}"),
             StmtKind::Expr(x) => write!(f, "ExprStmt {{ {x} }}"),
             StmtKind::FunctionDecl(x) => write!(f, "FunctionDeclStmt {{ {x} }}"),
+            StmtKind::GroupDecl(x) => write!(f, "GroupDeclStmt {{ {x} }}"),
+            StmtKind::NodeDecl(x) => write!(f, "NodeDeclStmt {{ {x} }}"),
             StmtKind::Print(x) => write!(f, "PrintStmt {{ {x} }}"),
+            StmtKind::Remove(x) => write!(f, "RemoveStmt {{ {x} }}"),
             StmtKind::Return(x) => write!(f, "ReturnStmt {{ {x} }}"),
             StmtKind::UseDecl(x) => write!(f, "UseDeclStmt {{ {x} }}"),
             StmtKind::VarDecl(x) => write!(f, "VarDeclStmt {{ {x} }}"),
@@ -122,9 +137,10 @@ pub enum TypeKind {
 /// Constant declaration.
 #[derive(Debug)]
 pub struct ConstDecl {
-    pub id: String,
+    pub id: Symbol,
     pub typeid: TypeKind,
     pub value: Expr,
+    pub doc: Option<String>, // Documentation text from doc comments ('///').
 }
 
 impl fmt::Display for ConstDecl {
@@ -136,10 +152,11 @@ impl fmt::Display for ConstDecl {
 /// Function declaration.
 #[derive(Debug)]
 pub struct FunctionDecl {
-    pub id: String,
+    pub id: Symbol,
     pub args: Option<FunctionArgs>,
     pub ret_type: Option<TypeKind>,
-    pub body: Stmt, // A block statement.
+    pub body: Stmt,          // A block statement.
+    pub doc: Option<String>, // Documentation text from doc comments ('///').
 }
 
 impl fmt::Display for FunctionDecl {
@@ -164,7 +181,7 @@ impl fmt::Display for FunctionDecl {
 /// Function argument.
 #[derive(Debug)]
 pub struct FunctionArg {
-    pub id: String,
+    pub id: Symbol,
     pub typeid: TypeKind,
 }
 
@@ -225,6 +242,119 @@ impl fmt::Display for FunctionArgs {
     }
 }
 
+/// Enum declaration, declaring the variants of an enum type (e.g. `enum Mode { Idle, Run }`).
+#[derive(Debug)]
+pub struct EnumDecl {
+    pub id: Symbol,
+    pub variants: Vec<Symbol>,
+    pub doc: Option<String>, // Documentation text from doc comments ('///').
+}
+
+impl fmt::Display for EnumDecl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let variants: Vec<_> = self.variants.iter().map(|v| v.as_str()).collect();
+        write!(
+            f,
+            "EnumDecl {{ {} {{ {} }} }}",
+            self.id,
+            variants.join(", ")
+        )
+    }
+}
+
+/// Group declaration, a template of the nodes and connections of a group, optionally with parameters (e.g.
+///  `group Chain(n: Number) { .. }`, building `n` nodes).
+#[derive(Debug)]
+pub struct GroupDecl {
+    pub id: Symbol,
+    pub args: Option<FunctionArgs>,
+    pub body: Stmt,          // A block statement.
+    pub doc: Option<String>, // Documentation text from doc comments ('///').
+}
+
+impl fmt::Display for GroupDecl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "GroupDecl {{ {} ({}) {{ {} }} }}",
+            self.id,
+            match &self.args {
+                Some(a) => format!("{a}"),
+                None => "".to_owned(),
+            },
+            self.body
+        )
+    }
+}
+
+/// Node declaration, declaring the typed input and output ports of a node kind.
+#[derive(Debug)]
+pub struct NodeDecl {
+    pub id: Symbol,
+    pub ports: Vec<PortDecl>,
+    pub doc: Option<String>, // Documentation text from doc comments ('///').
+}
+
+impl NodeDecl {
+    /// Get a port by name.
+    pub fn port(&self, id: &str) -> Option<&PortDecl> {
+        self.ports.iter().find(|p| p.id.as_str() == id)
+    }
+}
+
+impl fmt::Display for NodeDecl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "NodeDecl {{ {} {{ {} }} }}",
+            self.id,
+            if self.ports.is_empty() {
+                "(empty)".to_owned()
+            } else {
+                self.ports
+                    .iter()
+                    .map(|p| format!("{p}"))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            }
+        )
+    }
+}
+
+/// Port direction, as seen from the node declaring the port.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PortDirection {
+    Input,
+    Output,
+}
+
+impl fmt::Display for PortDirection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PortDirection::Input => write!(f, "in"),
+            PortDirection::Output => write!(f, "out"),
+        }
+    }
+}
+
+/// Port declaration of a node.
+#[derive(Debug)]
+pub struct PortDecl {
+    pub id: Symbol,
+    pub direction: PortDirection,
+    pub typeid: TypeKind,
+}
+
+impl fmt::Display for PortDecl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Port {{ {} {} : {} }}",
+            self.direction, self.id, self.typeid
+        )
+    }
+}
+
 /// Variable declaration.
 #[derive(Debug)]
 pub struct VarDecl {
@@ -232,6 +362,7 @@ pub struct VarDecl {
     pub mutable: bool,
     pub typeid: Option<TypeKind>,
     pub value: Option<Expr>,
+    pub doc: Option<String>, // Documentation text from doc comments ('///').
 }
 
 impl fmt::Display for VarDecl {
@@ -257,6 +388,7 @@ impl fmt::Display for VarDecl {
 #[derive(Debug)]
 pub struct UseDecl {
     pub filename: Expr,
+    pub span: Option<Span>, // Source span of the 'use' keyword (if known).
 }
 
 impl fmt::Display for UseDecl {
@@ -282,14 +414,20 @@ impl fmt::Display for Expr {
 pub enum ExprKind {
     Binary(Ptr<BinaryExpr>),
     Block(Ptr<BlockExpr>),
+    Cast(Ptr<Cast>),
     Empty(),
     For(Ptr<For>),
     FuncCall(Ptr<FuncCall>),
     Group(Ptr<Expr>),
     If(Ptr<If>),
+    Index(Ptr<Index>),
     Literal(Ptr<Literal>),
+    Loop(Ptr<Loop>),
+    Propagate(Ptr<Expr>),
     Range(Ptr<Range>),
     Ref(Ptr<Ref>),
+    Time(Ptr<Time>),
+    Try(Ptr<Expr>),
     Unary(Ptr<UnaryExpr>),
     Var(Ptr<Var>),
     While(Ptr<While>),
@@ -300,14 +438,20 @@ impl fmt::Display for ExprKind {
         match self {
             ExprKind::Binary(x) => write!(f, "BinaryExpr {{ {x} }}"),
             ExprKind::Block(x) => write!(f, "BlockExpr {{ {x} }}"),
+            ExprKind::Cast(x) => write!(f, "CastExpr {{ {x} }}"),
             ExprKind::Empty() => write!(f, "EmptyExpr"),
             ExprKind::For(x) => write!(f, "ForExpr {{ {x} }}"),
             ExprKind::FuncCall(x) => write!(f, "FuncCallExpr {{ {x} }}"),
             ExprKind::Group(x) => write!(f, "GroupExpr {{ ( {x} ) }}"),
             ExprKind::If(x) => write!(f, "IfExpr {{ {x} }}"),
+            ExprKind::Index(x) => write!(f, "IndexExpr {{ {x} }}"),
             ExprKind::Literal(x) => write!(f, "LiteralExpr {{ {x} }}"),
+            ExprKind::Loop(x) => write!(f, "LoopExpr {{ {x} }}"),
+            ExprKind::Propagate(x) => write!(f, "PropagateExpr {{ {x} ? }}"),
             ExprKind::Range(x) => write!(f, "RangeExpr {{ {x} }}"),
             ExprKind::Ref(x) => write!(f, "RefExpr {{ {x} }}"),
+            ExprKind::Time(x) => write!(f, "TimeExpr {{ {x} }}"),
+            ExprKind::Try(x) => write!(f, "TryExpr {{ try {x} }}"),
             ExprKind::Unary(x) => write!(f, "UnaryExpr {{ {x} }}"),
             ExprKind::Var(x) => write!(f, "VarExpr {{ {x} }}"),
             ExprKind::While(x) => write!(f, "WhileExpr {{ {x} }}"),
@@ -344,6 +488,7 @@ pub enum BinaryOp {
     NotEq,
     Or,
     Plus,
+    Power,
     Remainder,
     Subtract,
 }
@@ -363,7 +508,7 @@ impl fmt::Display for BlockExpr {
 /// For expression.
 #[derive(Debug)]
 pub struct For {
-    pub id: String,
+    pub id: Symbol,
     pub expr: Expr,
     pub body: Expr,
 }
@@ -381,7 +526,7 @@ impl fmt::Display for For {
 /// Function call expression.
 #[derive(Debug)]
 pub struct FuncCall {
-    pub id: String,
+    pub id: Symbol,
     pub args: Vec<Expr>,
 }
 
@@ -436,31 +581,139 @@ impl fmt::Display for Assignment {
     }
 }
 
-/// Connect statement.
+/// Connect (`->`) or disconnect (`-/>`) statement: a chain of two or more stages (`a -> b -> c`), each stage being a
+///  single endpoint or a list of endpoints (`a -> [b, c]`). Every endpoint of a stage is connected to every endpoint
+///  of the next stage.
+///
+/// A connect statement can end with a buffering policy for all its connections (`a -> b [capacity: 8];`).
 #[derive(Debug)]
 pub struct Connect {
-    pub source: Expr,
-    pub sink: Expr,
+    pub stages: Vec<Vec<Expr>>,
+    pub buffer: Option<Buffer>,
+}
+
+impl Connect {
+    /// Get all connections as (source, sink) pairs, in order.
+    pub fn pairs(&self) -> impl Iterator<Item = (&Expr, &Expr)> {
+        self.stages.windows(2).flat_map(|w| {
+            w[0].iter()
+                .flat_map(move |source| w[1].iter().map(move |sink| (source, sink)))
+        })
+    }
 }
 
 impl fmt::Display for Connect {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Connect {{ {} -> {} }}", self.source, self.sink)
+        let stages: Vec<_> = self
+            .stages
+            .iter()
+            .map(|s| match &s[..] {
+                [e] => format!("{e}"),
+                _ => format!(
+                    "[{}]",
+                    s.iter()
+                        .map(|e| format!("{e}"))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            })
+            .collect();
+
+        match &self.buffer {
+            Some(buffer) => write!(f, "Connect {{ {} {buffer} }}", stages.join(" -> ")),
+            None => write!(f, "Connect {{ {} }}", stages.join(" -> ")),
+        }
+    }
+}
+
+/// Buffering policy of connections (`[capacity: 8, overflow: drop_oldest]`), unbounded and blocking by default.
+#[derive(Debug, Default)]
+pub struct Buffer {
+    pub capacity: Option<Expr>, // Maximum number of queued values.
+    pub overflow: Overflow,
+}
+
+impl fmt::Display for Buffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.capacity {
+            Some(capacity) => write!(f, "[capacity: {capacity}, overflow: {}]", self.overflow),
+            None => write!(f, "[overflow: {}]", self.overflow),
+        }
+    }
+}
+
+/// Policy for a value sent into a full connection buffer.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Overflow {
+    #[default]
+    Block, // Wait until the sink takes a value.
+    DropOldest, // Drop the oldest queued value.
+    DropNewest, // Drop the sent value.
+}
+
+impl Overflow {
+    /// All policies, in order.
+    pub const ALL: [Overflow; 3] = [Overflow::Block, Overflow::DropOldest, Overflow::DropNewest];
+}
+
+impl fmt::Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Overflow::Block => write!(f, "block"),
+            Overflow::DropOldest => write!(f, "drop_oldest"),
+            Overflow::DropNewest => write!(f, "drop_newest"),
+        }
+    }
+}
+
+/// Remove statement, removing a node or group from the graph (`remove a;`).
+///
+/// A node or group that still has connections (or members) is only removed in the force form (`remove force a;`).
+#[derive(Debug)]
+pub struct Remove {
+    pub target: Expr,
+    pub force: bool,
+}
+
+impl fmt::Display for Remove {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.force {
+            write!(f, "Remove {{ force {} }}", self.target)
+        } else {
+            write!(f, "Remove {{ {} }}", self.target)
+        }
     }
 }
 
 /// Print statement.
 #[derive(Debug)]
 pub struct Print {
-    pub expr: Expr,
+    pub kind: PrintKind,
+    pub args: Vec<Expr>, // Printed separated by spaces.
 }
 
 impl fmt::Display for Print {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Print {{ {} }}", self.expr)
+        write!(
+            f,
+            "{} {{ {} }}",
+            self.kind,
+            self.args
+                .iter()
+                .map(|a| format!("{a}"))
+                .collect::<Vec<String>>()
+                .join(", ")
+        )
     }
 }
 
+/// Print statement kind.
+#[derive(Debug, Display, PartialEq)]
+pub enum PrintKind {
+    Print,   // Print without trailing newline.
+    Println, // Print with trailing newline.
+}
+
 /// Return statement.
 #[derive(Debug)]
 pub struct Return {
@@ -513,6 +766,7 @@ impl fmt::Display for Literal {
 #[derive(Debug)]
 pub enum LiteralKind {
     Bool(bool),
+    Nil,
     Number(f64),
     String(String),
 }
@@ -521,23 +775,69 @@ impl fmt::Display for LiteralKind {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             LiteralKind::Bool(x) => write!(f, "Bool {{ {x} }}"),
+            LiteralKind::Nil => write!(f, "Nil"),
             LiteralKind::Number(x) => write!(f, "Number {{ {x} }}"),
             LiteralKind::String(x) => write!(f, "String {{ \"{x}\" }}"),
         }
     }
 }
 
+/// Index expression: an element (`x[i]`) or a slice (`x[a..b]`).
+#[derive(Debug)]
+pub struct Index {
+    pub expr: Expr,
+    pub index: IndexKind,
+}
+
+impl fmt::Display for Index {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Index {{ {} [ {} ] }}", self.expr, self.index)
+    }
+}
+
+/// Index kind.
+#[derive(Debug)]
+pub enum IndexKind {
+    Element(Expr), // Also a slice, if the index evaluates to a range value.
+    Slice(RangeKind, Option<Expr>, Option<Expr>), // Either bound may be omitted, e.g. `x[..n]`.
+}
+
+impl fmt::Display for IndexKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IndexKind::Element(x) => write!(f, "{x}"),
+            IndexKind::Slice(kind, start, end) => {
+                if let Some(start) = start {
+                    write!(f, "{start} ")?;
+                }
+                write!(f, "{kind}")?;
+                if let Some(end) = end {
+                    write!(f, " {end}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
 /// Range expression.
 #[derive(Debug)]
 pub struct Range {
     pub kind: RangeKind,
     pub start: Expr,
     pub end: Expr,
+    pub step: Option<Expr>, // Explicit step (`0..10 step 2`), one by default.
 }
 
 impl fmt::Display for Range {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Range {{ {} {} {} }}", self.start, self.kind, self.end)
+        write!(f, "Range {{ {} {} {}", self.start, self.kind, self.end)?;
+
+        if let Some(step) = &self.step {
+            write!(f, " step {step}")?;
+        }
+
+        write!(f, " }}")
     }
 }
 
@@ -576,7 +876,7 @@ impl fmt::Display for Ref {
 /// Variable expression.
 #[derive(Debug)]
 pub struct Var {
-    pub id: String,
+    pub id: Symbol,
 }
 
 impl fmt::Display for Var {
@@ -585,6 +885,43 @@ impl fmt::Display for Var {
     }
 }
 
+/// Cast expression, converting a value to another type (`x as String`).
+#[derive(Debug)]
+pub struct Cast {
+    pub expr: Expr,
+    pub typeid: TypeKind,
+}
+
+impl fmt::Display for Cast {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} as {}", self.expr, self.typeid)
+    }
+}
+
+/// Loop expression, running its body until it returns or fails.
+#[derive(Debug)]
+pub struct Loop {
+    pub body: Expr,
+}
+
+impl fmt::Display for Loop {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "loop {{ {} }}", self.body)
+    }
+}
+
+/// Time expression, evaluating to the time taken to run its body (in milliseconds).
+#[derive(Debug)]
+pub struct Time {
+    pub body: Expr,
+}
+
+impl fmt::Display for Time {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "time {{ {} }}", self.body)
+    }
+}
+
 /// While expression.
 #[derive(Debug)]
 pub struct While {
@@ -599,7 +936,7 @@ impl fmt::Display for While {
 }
 
 /// Trait to enable self-evaluation.
-trait Evaluate: Sized {
+pub trait Evaluate: Sized {
     fn evaluate<R>(&self) -> R
     where
         Self: Eval<R, Self>;
@@ -0,0 +1,266 @@
+use super::ast::{ExprKind, LiteralKind, Stmt, StmtKind, Stmts};
+use std::{
+    collections::hash_map::DefaultHasher,
+    fmt,
+    hash::{Hash, Hasher},
+};
+
+#[cfg(test)]
+use crate::{parser::Parser, scanner::Scanner, source_line::SourceLine};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+/// Structural change of a top-level declaration/statement between two ASTs.
+#[derive(Debug, PartialEq)]
+pub enum AstChange {
+    Added(String),            // Declaration label.
+    Removed(String),          // Declaration label.
+    Modified(String, String), // Declaration label, change description.
+}
+
+impl fmt::Display for AstChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AstChange::Added(label) => write!(f, "+ {label}"),
+            AstChange::Removed(label) => write!(f, "- {label}"),
+            AstChange::Modified(label, change) => write!(f, "~ {label}: {change}"),
+        }
+    }
+}
+
+/// Compute the structural difference between the top-level declarations/statements of two ASTs.
+///
+/// Named declarations (functions, constants, variables) are matched by name, so reordering them is not a change.
+/// Other statements are matched by their content, so they can only be added or removed.
+/// Changes are listed for the old AST first (removed/modified), followed by the additions in the new AST.
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::{ast_diff::*, parser::Parser, scanner::Scanner, source_line::SourceLine};
+///
+/// let parse = |code: &str| {
+///     let line = SourceLine { line: code.to_string(), number: None };
+///     Parser::new(Scanner::new().scan(line).0).parse().unwrap()
+/// };
+///
+/// let changes = diff(&parse("const x: Number = 1; let y = 2;"), &parse("let y = 3; let z;"));
+///
+/// assert_eq!(
+///     changes,
+///     vec![
+///         AstChange::Removed("const x".to_string()),
+///         AstChange::Modified("let y".to_string(), "changed".to_string()),
+///         AstChange::Added("let z".to_string()),
+///     ]
+/// );
+/// ```
+pub fn diff(old: &Stmts, new: &Stmts) -> Vec<AstChange> {
+    let old = keyed(old);
+    let new = keyed(new);
+
+    let mut changes = Vec::new();
+
+    for (key, stmt) in &old {
+        match new.iter().find(|(k, _)| k == key) {
+            None => changes.push(AstChange::Removed(key.label())),
+            Some((_, new_stmt)) => {
+                if let Some(change) = compare(stmt, new_stmt) {
+                    changes.push(AstChange::Modified(key.label(), change));
+                }
+            }
+        }
+    }
+
+    for (key, _) in &new {
+        if !old.iter().any(|(k, _)| k == key) {
+            changes.push(AstChange::Added(key.label()));
+        }
+    }
+
+    changes
+}
+
+/// Matching key of a top-level declaration/statement.
+#[derive(Debug, PartialEq)]
+struct DeclKey {
+    kind: &'static str,
+    name: String,
+    occurrence: usize, // To match repeated declarations of the same name in order.
+}
+
+impl DeclKey {
+    fn label(&self) -> String {
+        match self.occurrence {
+            0 => format!("{} {}", self.kind, self.name),
+            n => format!("{} {} ({})", self.kind, self.name, n + 1),
+        }
+    }
+}
+
+fn keyed(stmts: &Stmts) -> Vec<(DeclKey, &Stmt)> {
+    let mut result: Vec<(DeclKey, &Stmt)> = Vec::new();
+
+    for stmt in stmts.iter() {
+        let (kind, name) = match &stmt.kind {
+            StmtKind::FunctionDecl(f) => ("fn", f.id.to_string()),
+            StmtKind::ConstDecl(c) => ("const", c.id.to_string()),
+            StmtKind::NodeDecl(n) => ("node", n.id.to_string()),
+            StmtKind::EnumDecl(e) => ("enum", e.id.to_string()),
+            StmtKind::GroupDecl(g) => ("group", g.id.to_string()),
+            StmtKind::VarDecl(v) => (
+                "let",
+                match &v.id.kind {
+                    ExprKind::Var(var) => var.id.to_string(),
+                    _ => format!("#{}", hash(&v.id)),
+                },
+            ),
+            StmtKind::UseDecl(u) => (
+                "use",
+                match &u.filename.kind {
+                    ExprKind::Literal(l) => match &l.kind {
+                        LiteralKind::String(s) => format!("\"{s}\""),
+                        _ => format!("#{}", hash(&u.filename)),
+                    },
+                    _ => format!("#{}", hash(&u.filename)),
+                },
+            ),
+            StmtKind::Assignment(_) => ("assignment", format!("#{}", hash(stmt))),
+            StmtKind::Block(_) => ("block", format!("#{}", hash(stmt))),
+            StmtKind::Connect(_) => ("connect", format!("#{}", hash(stmt))),
+            StmtKind::Defer(_) => ("defer", format!("#{}", hash(stmt))),
+            StmtKind::Disconnect(_) => ("disconnect", format!("#{}", hash(stmt))),
+            StmtKind::Expr(_) => ("expression", format!("#{}", hash(stmt))),
+            StmtKind::Print(_) => ("print", format!("#{}", hash(stmt))),
+            StmtKind::Remove(_) => ("remove", format!("#{}", hash(stmt))),
+            StmtKind::Return(_) => ("return", format!("#{}", hash(stmt))),
+        };
+
+        let occurrence = result
+            .iter()
+            .filter(|(k, _)| k.kind == kind && k.name == name)
+            .count();
+
+        result.push((
+            DeclKey {
+                kind,
+                name,
+                occurrence,
+            },
+            stmt,
+        ));
+    }
+
+    result
+}
+
+/// Compare two matched declarations, returning a description of the change (if any).
+fn compare(old: &Stmt, new: &Stmt) -> Option<String> {
+    match (&old.kind, &new.kind) {
+        (StmtKind::FunctionDecl(o), StmtKind::FunctionDecl(n)) => {
+            let signature = |f: &super::ast::FunctionDecl| {
+                format!(
+                    "{:?} -> {:?}",
+                    f.args.as_ref().map(|a| a.to_string()),
+                    f.ret_type.as_ref().map(|t| t.to_string())
+                )
+            };
+
+            let (old_body, new_body) = (hash(&o.body), hash(&n.body));
+
+            match (signature(o) != signature(n), old_body != new_body) {
+                (false, false) => None,
+                (true, false) => Some("signature changed".to_owned()),
+                (false, true) => Some(format!("body changed (#{old_body} -> #{new_body})")),
+                (true, true) => Some(format!(
+                    "signature and body changed (#{old_body} -> #{new_body})"
+                )),
+            }
+        }
+        _ => (hash(old) != hash(new)).then(|| "changed".to_owned()),
+    }
+}
+
+/// Short structural hash of an AST node.
+fn hash(node: &impl fmt::Display) -> String {
+    let mut hasher = DefaultHasher::new();
+    node.to_string().hash(&mut hasher);
+    format!("{:08x}", hasher.finish() as u32)
+}
+
+#[cfg(test)]
+fn parse(code: &str) -> Stmts {
+    let line = SourceLine {
+        line: code.to_string(),
+        number: None,
+    };
+    Parser::new(Scanner::new().scan(line).0).parse().unwrap()
+}
+
+#[test]
+fn diff_unchanged_test() {
+    let code = "use \"lib.nxs\"; fn f(a: Number) { print a; } let x = 1; x -> y; print x;";
+
+    assert!(diff(&parse(code), &parse(code)).is_empty());
+    let removed = diff(&parse(code), &parse(""));
+    assert_eq!(removed.len(), 5);
+    assert_eq!(
+        removed[..3],
+        [
+            AstChange::Removed("use \"lib.nxs\"".to_string()),
+            AstChange::Removed("fn f".to_string()),
+            AstChange::Removed("let x".to_string())
+        ]
+    );
+    assert!(diff(
+        &parse("fn f() { 1 } fn g() { 2 }"),
+        &parse("fn g() { 2 } fn f() { 1 }")
+    )
+    .is_empty());
+}
+
+#[test]
+fn diff_function_test() {
+    let old = parse("fn f(a: Number) { print a; } fn g() { 1 }");
+
+    let changes = diff(
+        &old,
+        &parse("fn f(a: Number) { print a + 1; } fn g() { 1 }"),
+    );
+    assert_eq!(changes.len(), 1);
+    assert!(changes[0]
+        .to_string()
+        .starts_with("~ fn f: body changed (#"));
+
+    let changes = diff(&old, &parse("fn f(a: String) { print a; } fn g() { 1 }"));
+    assert_eq!(
+        changes,
+        vec![AstChange::Modified(
+            "fn f".to_string(),
+            "signature changed".to_string()
+        )]
+    );
+
+    let changes = diff(&old, &parse("fn f(a: Number) { print a; } fn h() { 1 }"));
+    assert_eq!(
+        changes,
+        vec![
+            AstChange::Removed("fn g".to_string()),
+            AstChange::Added("fn h".to_string())
+        ]
+    );
+}
+
+#[test]
+fn diff_statements_test() {
+    let changes = diff(
+        &parse("let x = 1; let x = 2; print x;"),
+        &parse("let x = 1; print y;"),
+    );
+
+    assert_eq!(changes.len(), 3);
+    assert_eq!(changes[0].to_string(), "- let x (2)");
+    assert!(changes[1].to_string().starts_with("- print #"));
+    assert!(changes[2].to_string().starts_with("+ print #"));
+}
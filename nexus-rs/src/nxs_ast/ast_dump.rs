@@ -0,0 +1,405 @@
+use super::ast::{
+    Connect, Expr, ExprKind, FunctionArgs, FunctionDecl, IndexKind, LiteralKind, PrintKind,
+    RangeKind, Stmt, StmtKind, Stmts, TypeKind,
+};
+use crate::json::Json;
+
+#[cfg(test)]
+use crate::{parser::Parser, scanner::Scanner, source_line::SourceLine};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+/// AST dump output format.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum DumpFormat {
+    #[default]
+    Flat, // One display line per top-level statement.
+    Tree, // Indented tree view with box-drawing characters.
+    Sexp, // One S-expression per top-level statement.
+    Json, // Array of nested node objects.
+}
+
+/// Generic AST dump node: a node kind, its attributes (names, operators, values) and its child nodes.
+#[derive(Debug, PartialEq)]
+pub struct DumpNode {
+    pub kind: &'static str,
+    pub attrs: Vec<String>,
+    pub children: Vec<DumpNode>,
+}
+
+impl DumpNode {
+    fn new(kind: &'static str) -> Self {
+        DumpNode {
+            kind,
+            attrs: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+
+    fn attr(mut self, attr: impl Into<String>) -> Self {
+        self.attrs.push(attr.into());
+        self
+    }
+
+    fn attrs(mut self, attrs: impl IntoIterator<Item = String>) -> Self {
+        self.attrs.extend(attrs);
+        self
+    }
+
+    fn child(mut self, child: DumpNode) -> Self {
+        self.children.push(child);
+        self
+    }
+
+    fn children(mut self, children: impl IntoIterator<Item = DumpNode>) -> Self {
+        self.children.extend(children);
+        self
+    }
+
+    /// Header line of the node: its kind followed by its attributes.
+    fn header(&self) -> String {
+        std::iter::once(self.kind.to_owned())
+            .chain(self.attrs.iter().cloned())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Render the node as a single-line S-expression.
+    pub fn to_sexp(&self) -> String {
+        let mut out = format!("({}", self.header());
+
+        for child in &self.children {
+            out.push(' ');
+            out.push_str(&child.to_sexp());
+        }
+
+        out.push(')');
+        out
+    }
+
+    /// Render the node as a JSON object.
+    pub fn to_json(&self) -> Json {
+        Json::Object(vec![
+            ("kind".to_owned(), self.kind.into()),
+            (
+                "attrs".to_owned(),
+                Json::Array(self.attrs.iter().map(|a| a.as_str().into()).collect()),
+            ),
+            (
+                "children".to_owned(),
+                Json::Array(self.children.iter().map(DumpNode::to_json).collect()),
+            ),
+        ])
+    }
+
+    /// Render the node as an indented tree, one node per line.
+    pub fn to_tree(&self) -> String {
+        let mut out = self.header();
+        self.tree_children(&mut out, "");
+        out
+    }
+
+    fn tree_children(&self, out: &mut String, indent: &str) {
+        for (i, child) in self.children.iter().enumerate() {
+            let last = i + 1 == self.children.len();
+
+            out.push('\n');
+            out.push_str(indent);
+            out.push_str(if last { "└── " } else { "├── " });
+            out.push_str(&child.header());
+
+            child.tree_children(
+                out,
+                &format!("{indent}{}", if last { "    " } else { "│   " }),
+            );
+        }
+    }
+}
+
+/// Convert a statement into a generic dump node.
+pub fn stmt_node(stmt: &Stmt) -> DumpNode {
+    match &stmt.kind {
+        StmtKind::Assignment(x) => DumpNode::new("Assign")
+            .child(expr_node(&x.lhs))
+            .child(expr_node(&x.rhs)),
+        StmtKind::Block(x) => DumpNode::new("Block").children(x.iter().map(stmt_node)),
+        StmtKind::Connect(x) => connect_node("Connect", x),
+        StmtKind::ConstDecl(x) => DumpNode::new("Const")
+            .attr(x.id.to_string())
+            .attr(typed("", &x.typeid))
+            .child(expr_node(&x.value)),
+        StmtKind::Defer(x) => DumpNode::new("Defer").child(stmt_node(x)),
+        StmtKind::Disconnect(x) => connect_node("Disconnect", x),
+        StmtKind::EnumDecl(x) => DumpNode::new("EnumDecl")
+            .attr(x.id.to_string())
+            .attrs(x.variants.iter().map(|v| v.to_string())),
+        StmtKind::Expr(x) => DumpNode::new("ExprStmt").child(expr_node(x)),
+        StmtKind::FunctionDecl(x) => function_node(x),
+        StmtKind::GroupDecl(x) => DumpNode::new("GroupDecl")
+            .attr(x.id.to_string())
+            .attrs(args_attrs(&x.args))
+            .child(stmt_node(&x.body)),
+        StmtKind::NodeDecl(x) => {
+            DumpNode::new("NodeDecl")
+                .attr(x.id.to_string())
+                .children(x.ports.iter().map(|p| {
+                    DumpNode::new("Port")
+                        .attr(p.direction.to_string())
+                        .attr(typed(p.id.as_str(), &p.typeid))
+                }))
+        }
+        StmtKind::Print(x) => DumpNode {
+            kind: if x.kind == PrintKind::Print {
+                "Print"
+            } else {
+                "Println"
+            },
+            attrs: Vec::new(),
+            children: x.args.iter().map(expr_node).collect(),
+        },
+        StmtKind::Remove(x) => {
+            let node = DumpNode::new("Remove");
+            if x.force { node.attr("force") } else { node }.child(expr_node(&x.target))
+        }
+        StmtKind::Return(x) => DumpNode::new("Return").child(expr_node(&x.expr)),
+        StmtKind::UseDecl(x) => DumpNode::new("Use").child(expr_node(&x.filename)),
+        StmtKind::VarDecl(x) => {
+            let mut node = DumpNode::new("Let");
+
+            if x.mutable {
+                node = node.attr("mut");
+            }
+            if let Some(t) = &x.typeid {
+                node = node.attr(typed("", t));
+            }
+
+            node = node.child(expr_node(&x.id));
+            if let Some(v) = &x.value {
+                node = node.child(expr_node(v));
+            }
+
+            node
+        }
+    }
+}
+
+/// Convert an expression into a generic dump node.
+pub fn expr_node(expr: &Expr) -> DumpNode {
+    match &expr.kind {
+        ExprKind::Binary(x) => DumpNode::new("Binary")
+            .attr(x.op.to_string())
+            .child(expr_node(&x.lhs))
+            .child(expr_node(&x.rhs)),
+        ExprKind::Block(x) => DumpNode::new("BlockExpr").child(stmt_node(&x.body)),
+        ExprKind::Cast(x) => DumpNode::new("Cast")
+            .attr(x.typeid.to_string())
+            .child(expr_node(&x.expr)),
+        ExprKind::Empty() => DumpNode::new("Empty"),
+        ExprKind::For(x) => DumpNode::new("For")
+            .attr(x.id.to_string())
+            .child(expr_node(&x.expr))
+            .child(expr_node(&x.body)),
+        ExprKind::FuncCall(x) => DumpNode::new("Call")
+            .attr(x.id.to_string())
+            .children(x.args.iter().map(expr_node)),
+        ExprKind::Group(x) => DumpNode::new("Group").child(expr_node(x)),
+        ExprKind::If(x) => {
+            let node = DumpNode::new("If")
+                .child(expr_node(&x.expr))
+                .child(expr_node(&x.body_then));
+
+            match &x.body_else {
+                Some(e) => node.child(expr_node(e)),
+                None => node,
+            }
+        }
+        ExprKind::Index(x) => {
+            let node = DumpNode::new("Index").child(expr_node(&x.expr));
+            match &x.index {
+                IndexKind::Element(e) => node.child(expr_node(e)),
+                IndexKind::Slice(kind, start, end) => {
+                    node.child(DumpNode::new("Slice").attr(kind.to_string()).children(
+                        [start, end].map(|e| e.as_ref().map_or(DumpNode::new("Empty"), expr_node)),
+                    ))
+                }
+            }
+        }
+        ExprKind::Literal(x) => match &x.kind {
+            LiteralKind::Bool(b) => DumpNode::new("Bool").attr(b.to_string()),
+            LiteralKind::Nil => DumpNode::new("Nil"),
+            LiteralKind::Number(n) => DumpNode::new("Number").attr(n.to_string()),
+            LiteralKind::String(s) => DumpNode::new("String").attr(format!("{s:?}")),
+        },
+        ExprKind::Propagate(x) => DumpNode::new("Propagate").child(expr_node(x)),
+        ExprKind::Range(x) => DumpNode::new("Range")
+            .attr(match x.kind {
+                RangeKind::Exclusive => "..",
+                RangeKind::Inclusive => "..=",
+            })
+            .child(expr_node(&x.start))
+            .child(expr_node(&x.end))
+            .children(x.step.iter().map(expr_node)),
+        ExprKind::Loop(x) => DumpNode::new("Loop").child(expr_node(&x.body)),
+        ExprKind::Ref(x) => DumpNode::new("Ref").child(expr_node(&x.expr)),
+        ExprKind::Time(x) => DumpNode::new("Time").child(expr_node(&x.body)),
+        ExprKind::Try(x) => DumpNode::new("Try").child(expr_node(x)),
+        ExprKind::Unary(x) => DumpNode::new("Unary")
+            .attr(x.op.to_string())
+            .child(expr_node(&x.expr)),
+        ExprKind::Var(x) => DumpNode::new("Var").attr(x.id.to_string()),
+        ExprKind::While(x) => DumpNode::new("While")
+            .child(expr_node(&x.expr))
+            .child(expr_node(&x.body)),
+    }
+}
+
+fn connect_node(kind: &'static str, connect: &Connect) -> DumpNode {
+    let node = DumpNode::new(kind).children(connect.stages.iter().map(|s| match &s[..] {
+        [e] => expr_node(e),
+        _ => DumpNode::new("List").children(s.iter().map(expr_node)),
+    }));
+
+    match &connect.buffer {
+        Some(buffer) => node.child(
+            DumpNode::new("Buffer")
+                .attr(buffer.overflow.to_string())
+                .children(buffer.capacity.iter().map(expr_node)),
+        ),
+        None => node,
+    }
+}
+
+fn function_node(decl: &FunctionDecl) -> DumpNode {
+    let mut node = DumpNode::new("Function")
+        .attr(decl.id.to_string())
+        .attrs(args_attrs(&decl.args));
+
+    if let Some(t) = &decl.ret_type {
+        node = node.attr(format!("->{t}"));
+    }
+
+    node.child(stmt_node(&decl.body))
+}
+
+/// Typed argument attributes (see [`typed`]).
+fn args_attrs(args: &Option<FunctionArgs>) -> impl Iterator<Item = String> + '_ {
+    args.iter()
+        .flat_map(|a| a.iter())
+        .map(|a| typed(a.id.as_str(), &a.typeid))
+}
+
+/// Type annotation attribute, without spaces so it stays a single S-expression atom.
+fn typed(id: &str, typeid: &TypeKind) -> String {
+    format!("{id}:{typeid}")
+}
+
+/// Dump an AST in the given format.
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::{ast_dump::*, parser::Parser, scanner::Scanner, source_line::SourceLine};
+///
+/// let line = SourceLine { line: "let x = 1 + 2;".to_string(), number: None };
+/// let ast = Parser::new(Scanner::new().scan(line).0).parse().unwrap();
+///
+/// assert_eq!(dump(&ast, DumpFormat::Sexp), "(Let (Var x) (Binary Plus (Number 1) (Number 2)))");
+/// assert_eq!(
+///     dump(&ast, DumpFormat::Tree),
+///     "Program\n└── Let\n    ├── Var x\n    └── Binary Plus\n        ├── Number 1\n        └── Number 2"
+/// );
+/// ```
+pub fn dump(ast: &Stmts, format: DumpFormat) -> String {
+    match format {
+        DumpFormat::Flat => ast
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>()
+            .join("\n"),
+        DumpFormat::Tree => DumpNode::new("Program")
+            .children(ast.iter().map(stmt_node))
+            .to_tree(),
+        DumpFormat::Sexp => ast
+            .iter()
+            .map(|s| stmt_node(s).to_sexp())
+            .collect::<Vec<_>>()
+            .join("\n"),
+        DumpFormat::Json => {
+            Json::Array(ast.iter().map(|s| stmt_node(s).to_json()).collect()).pretty()
+        }
+    }
+}
+
+#[cfg(test)]
+fn parse(code: &str) -> Stmts {
+    let line = SourceLine {
+        line: code.to_string(),
+        number: None,
+    };
+
+    Parser::new(Scanner::new().scan(line).0).parse().unwrap()
+}
+
+#[test]
+fn sexp_test() {
+    let ast = parse(
+        r#"fn f(a: Number, b: String) -> Number { return -a; } let mut x: Number = f(1, "y"); if x > 1 { println x..=3; }; "#,
+    );
+
+    assert_eq!(
+        dump(&ast, DumpFormat::Sexp),
+        [
+            "(Function f a:Number b:String ->Number (Block (Return (Unary Minus (Var a)))))",
+            r#"(Let mut :Number (Var x) (Call f (Number 1) (String "y")))"#,
+            "(ExprStmt (If (Binary Gt (Var x) (Number 1)) (BlockExpr (Block (Println (Range ..= (Var x) (Number 3)))))))",
+        ]
+        .join("\n")
+    );
+}
+
+#[test]
+fn tree_test() {
+    let ast = parse("let x; x = !true; print x, nil;");
+
+    assert_eq!(
+        dump(&ast, DumpFormat::Tree),
+        "Program
+├── Let
+│   └── Var x
+├── Assign
+│   ├── Var x
+│   └── Unary Bang
+│       └── Bool true
+└── Print
+    ├── Var x
+    └── Nil"
+    );
+
+    assert_eq!(dump(&Stmts::new(), DumpFormat::Tree), "Program");
+}
+
+#[test]
+fn json_test() {
+    let ast = parse("x -> y;");
+    let json = Json::parse(&dump(&ast, DumpFormat::Json)).unwrap();
+
+    assert_eq!(
+        json.to_string(),
+        r#"[{"kind":"Connect","attrs":[],"children":[{"kind":"Var","attrs":["x"],"children":[]},{"kind":"Var","attrs":["y"],"children":[]}]}]"#
+    );
+}
+
+#[test]
+fn flat_test() {
+    let ast = parse("let x = 1; println x;");
+
+    assert_eq!(
+        dump(&ast, DumpFormat::Flat),
+        ast.iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>()
+            .join("\n")
+    );
+}
@@ -0,0 +1,459 @@
+use super::ast::{
+    EnumDecl, ExprKind, FunctionArgs, FunctionDecl, GroupDecl, NodeDecl, StmtKind, Stmts, TypeKind,
+    UnaryOp,
+};
+use crate::source_line::SourceLine;
+use crate::span::Span;
+use std::fmt::Write;
+
+#[cfg(test)]
+use crate::front::parse_source;
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+/// Documentation output format.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum DocFormat {
+    #[default]
+    Markdown,
+    Html, // Standalone HTML page.
+}
+
+/// Kind of documented item.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ItemKind {
+    Function,
+    Const,
+    Enum,     // Enum declaration, e.g. `enum Mode { Idle, Run }`.
+    Group,    // Group declaration, e.g. `group Chain(n: Number) { .. }`.
+    Node,     // Variable declared as node, e.g. `let c = node "Converter";`.
+    NodeKind, // Node declaration with ports, e.g. `node Adder { in a: Number; .. }`.
+    Variable, // Other variable, only if documented.
+}
+
+impl ItemKind {
+    /// Get the keyword of the item kind, as shown in the documentation.
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            ItemKind::Function => "fn",
+            ItemKind::Const => "const",
+            ItemKind::Enum => "enum",
+            ItemKind::Group => "group",
+            ItemKind::Node | ItemKind::NodeKind => "node",
+            ItemKind::Variable => "let",
+        }
+    }
+}
+
+/// Documented item: a top-level declaration with its signature and documentation text (from doc comments).
+#[derive(Clone, Debug, PartialEq)]
+pub struct DocItem {
+    pub kind: ItemKind,
+    pub name: String,
+    pub signature: String,
+    pub doc: Option<String>,
+}
+
+/// Documented source file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DocModule {
+    pub name: String, // E.g. the path of the source file, relative to the project root.
+    pub items: Vec<DocItem>,
+}
+
+/// Collect the documented items of a source file: all top-level functions, constants, node declarations and nodes,
+///  and the documented top-level variables, in order of declaration.
+///
+/// Signatures of functions and node declarations are derived from the AST, others are taken from the source lines (a declaration spanning
+///  multiple lines shows its first line).
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::{docgen::*, front::parse_source, source_line::SourceLine};
+///
+/// let source = "/// Doubles a number.\nfn twice(x: Number) -> Number { x * 2 }\nlet y = 1;";
+/// let lines: Vec<_> = source
+///     .lines()
+///     .enumerate()
+///     .map(|(i, l)| SourceLine { line: l.to_owned(), number: Some(i + 1) })
+///     .collect();
+///
+/// let items = items(&parse_source(source).unwrap(), &lines);
+///
+/// assert_eq!(items.len(), 1);
+/// assert_eq!(items[0].signature, "fn twice(x: Number) -> Number");
+/// assert_eq!(items[0].doc.as_deref(), Some("Doubles a number."));
+/// ```
+pub fn items(ast: &Stmts, lines: &[SourceLine]) -> Vec<DocItem> {
+    let mut items = Vec::new();
+
+    for stmt in ast.iter() {
+        let item = match &stmt.kind {
+            StmtKind::FunctionDecl(f) => DocItem {
+                kind: ItemKind::Function,
+                name: f.id.to_string(),
+                signature: function_signature(f),
+                doc: f.doc.clone(),
+            },
+            StmtKind::EnumDecl(e) => DocItem {
+                kind: ItemKind::Enum,
+                name: e.id.to_string(),
+                signature: enum_signature(e),
+                doc: e.doc.clone(),
+            },
+            StmtKind::GroupDecl(g) => DocItem {
+                kind: ItemKind::Group,
+                name: g.id.to_string(),
+                signature: group_signature(g),
+                doc: g.doc.clone(),
+            },
+            StmtKind::NodeDecl(n) => DocItem {
+                kind: ItemKind::NodeKind,
+                name: n.id.to_string(),
+                signature: node_signature(n),
+                doc: n.doc.clone(),
+            },
+            StmtKind::ConstDecl(c) => DocItem {
+                kind: ItemKind::Const,
+                name: c.id.to_string(),
+                signature: source_signature(stmt.span, lines),
+                doc: c.doc.clone(),
+            },
+            StmtKind::VarDecl(v) => {
+                let ExprKind::Var(id) = &v.id.kind else {
+                    continue; // Member definitions (e.g. node properties) are not declarations.
+                };
+
+                let node = matches!(
+                    v.value.as_ref().map(|e| &e.kind),
+                    Some(ExprKind::Unary(u)) if matches!(u.op, UnaryOp::Node)
+                );
+
+                if !node && v.doc.is_none() {
+                    continue;
+                }
+
+                DocItem {
+                    kind: if node {
+                        ItemKind::Node
+                    } else {
+                        ItemKind::Variable
+                    },
+                    name: id.id.to_string(),
+                    signature: source_signature(stmt.span, lines),
+                    doc: v.doc.clone(),
+                }
+            }
+            _ => continue,
+        };
+
+        items.push(item);
+    }
+
+    items
+}
+
+/// Render the documentation of modules in the given format. Modules without items are left out.
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::docgen::*;
+///
+/// let modules = [DocModule {
+///     name: "main.nxs".to_owned(),
+///     items: vec![DocItem {
+///         kind: ItemKind::Const,
+///         name: "PI".to_owned(),
+///         signature: "const PI: Number = 3.14".to_owned(),
+///         doc: Some("Approximately.".to_owned()),
+///     }],
+/// }];
+///
+/// assert!(render("Demo", &modules, DocFormat::Markdown).contains("### const `PI`"));
+/// assert!(render("Demo", &modules, DocFormat::Html).contains("<h3 id=\"main.nxs-PI\">const <code>PI</code></h3>"));
+/// ```
+pub fn render(title: &str, modules: &[DocModule], format: DocFormat) -> String {
+    let modules = modules.iter().filter(|m| !m.items.is_empty());
+
+    match format {
+        DocFormat::Markdown => {
+            let mut out = format!("# {title}\n");
+
+            for module in modules {
+                let _ = write!(out, "\n## `{}`\n", module.name);
+
+                for item in &module.items {
+                    let _ = write!(
+                        out,
+                        "\n### {} `{}`\n\n```nexus\n{}\n```\n",
+                        item.kind.keyword(),
+                        item.name,
+                        item.signature
+                    );
+
+                    if let Some(doc) = &item.doc {
+                        let _ = write!(out, "\n{doc}\n");
+                    }
+                }
+            }
+
+            out
+        }
+        DocFormat::Html => {
+            let mut out = format!(
+                "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{0}</title>\n</head>\n<body>\n\
+                 <h1>{0}</h1>\n",
+                escape(title)
+            );
+
+            for module in modules {
+                let _ = writeln!(out, "<h2><code>{}</code></h2>", escape(&module.name));
+
+                for item in &module.items {
+                    let _ = write!(
+                        out,
+                        "<h3 id=\"{}-{}\">{} <code>{}</code></h3>\n<pre><code>{}</code></pre>\n",
+                        escape(&module.name),
+                        escape(&item.name),
+                        item.kind.keyword(),
+                        escape(&item.name),
+                        escape(&item.signature)
+                    );
+
+                    // Paragraphs are separated by empty doc comment lines.
+                    for paragraph in item.doc.iter().flat_map(|d| d.split("\n\n")) {
+                        let _ = writeln!(out, "<p>{}</p>", escape(paragraph.trim()));
+                    }
+                }
+            }
+
+            out.push_str("</body>\n</html>\n");
+            out
+        }
+    }
+}
+
+fn function_signature(f: &FunctionDecl) -> String {
+    let mut signature = format!("fn {}({})", f.id, args_signature(&f.args));
+    if let Some(t) = &f.ret_type {
+        let _ = write!(signature, " -> {}", type_name(t));
+    }
+
+    signature
+}
+
+fn enum_signature(e: &EnumDecl) -> String {
+    let variants: Vec<_> = e.variants.iter().map(|v| v.as_str()).collect();
+
+    format!("enum {} {{ {} }}", e.id, variants.join(", "))
+}
+
+fn group_signature(g: &GroupDecl) -> String {
+    match &g.args {
+        Some(_) => format!("group {}({})", g.id, args_signature(&g.args)),
+        None => format!("group {}", g.id),
+    }
+}
+
+fn args_signature(args: &Option<FunctionArgs>) -> String {
+    let args: Vec<_> = args
+        .iter()
+        .flat_map(|a| a.0.iter())
+        .map(|a| format!("{}: {}", a.id, type_name(&a.typeid)))
+        .collect();
+
+    args.join(", ")
+}
+
+fn node_signature(n: &NodeDecl) -> String {
+    let ports: Vec<_> = n
+        .ports
+        .iter()
+        .map(|p| format!("{} {}: {};", p.direction, p.id, type_name(&p.typeid)))
+        .collect();
+
+    format!("node {} {{ {} }}", n.id, ports.join(" "))
+}
+
+/// Type name as written in source code.
+fn type_name(t: &TypeKind) -> &'static str {
+    match t {
+        TypeKind::Bool => "bool",
+        TypeKind::Group => "Group",
+        TypeKind::Node => "Node",
+        TypeKind::Number => "Number",
+        TypeKind::String => "String",
+    }
+}
+
+/// Signature from the source text of a declaration: its span, or else the rest of its first line.
+fn source_signature(span: Option<Span>, lines: &[SourceLine]) -> String {
+    let Some(span) = span else {
+        return String::new();
+    };
+
+    let Some(line) = lines
+        .iter()
+        .find(|l| l.number.is_some() && l.number == span.line)
+    else {
+        return String::new();
+    };
+
+    // The span covers the whole declaration only if it ends on this line (at the terminating ';').
+    let text: String = line.line.chars().skip(span.start).collect();
+    let text: String = match span.end.checked_sub(span.start + 1) {
+        Some(n) if text.chars().nth(n) == Some(';') => text.chars().take(n).collect(),
+        _ => text,
+    };
+
+    text.trim_end().trim_end_matches(';').trim_end().to_owned()
+}
+
+/// Escape text for HTML.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+fn lines(source: &str) -> Vec<SourceLine> {
+    source
+        .lines()
+        .enumerate()
+        .map(|(i, l)| SourceLine {
+            line: l.to_owned(),
+            number: Some(i + 1),
+        })
+        .collect()
+}
+
+#[test]
+fn items_test() {
+    let source = r#"/// Scale factor.
+///
+/// Applied twice.
+const SCALE: Number = 2.5; // Not part of the doc.
+//// Regular comment.
+fn scale(x: Number, flag: bool) -> Number {
+    /// Not attached (no declaration follows).
+    x * SCALE
+}
+/// Input.
+let source = node "Source";
+let sink = node "Sink";
+let undocumented = 1;
+/// Documented.
+let mut documented: Number =
+    2;
+source.rate = 3;
+/// Adds two numbers.
+node Adder {
+    in a: Number;
+    in b: Number;
+    out sum: Number;
+}
+/// Chain of adders.
+group Chain(n: Number) {}
+/// Operating mode.
+enum Mode { Idle, Run }"#;
+
+    let ast = parse_source(source).unwrap();
+    let items: Vec<_> = items(&ast, &lines(source))
+        .into_iter()
+        .map(|i| (i.kind, i.name, i.signature, i.doc))
+        .collect();
+
+    let some = |s: &str| Some(s.to_owned());
+    assert_eq!(
+        items,
+        [
+            (
+                ItemKind::Const,
+                "SCALE".to_owned(),
+                "const SCALE: Number = 2.5".to_owned(),
+                some("Scale factor.\n\nApplied twice.")
+            ),
+            (
+                ItemKind::Function,
+                "scale".to_owned(),
+                "fn scale(x: Number, flag: bool) -> Number".to_owned(),
+                None
+            ),
+            (
+                ItemKind::Node,
+                "source".to_owned(),
+                "let source = node \"Source\"".to_owned(),
+                some("Input.")
+            ),
+            (
+                ItemKind::Node,
+                "sink".to_owned(),
+                "let sink = node \"Sink\"".to_owned(),
+                None
+            ),
+            (
+                ItemKind::Variable,
+                "documented".to_owned(),
+                "let mut documented: Number =".to_owned(),
+                some("Documented.")
+            ),
+            (
+                ItemKind::NodeKind,
+                "Adder".to_owned(),
+                "node Adder { in a: Number; in b: Number; out sum: Number; }".to_owned(),
+                some("Adds two numbers.")
+            ),
+            (
+                ItemKind::Group,
+                "Chain".to_owned(),
+                "group Chain(n: Number)".to_owned(),
+                some("Chain of adders.")
+            ),
+            (
+                ItemKind::Enum,
+                "Mode".to_owned(),
+                "enum Mode { Idle, Run }".to_owned(),
+                some("Operating mode.")
+            ),
+        ]
+    );
+}
+
+#[test]
+fn render_test() {
+    let source = "/// Says <hi> & more.\n///\n/// Second paragraph.\nfn hi() {}";
+    let modules = [
+        DocModule {
+            name: "main.nxs".to_owned(),
+            items: items(&parse_source(source).unwrap(), &lines(source)),
+        },
+        DocModule {
+            name: "empty.nxs".to_owned(),
+            items: Vec::new(),
+        },
+    ];
+
+    assert_eq!(
+        render("Demo", &modules, DocFormat::Markdown),
+        "# Demo\n\n## `main.nxs`\n\n### fn `hi`\n\n```nexus\nfn hi()\n```\n\n\
+         Says <hi> & more.\n\nSecond paragraph.\n"
+    );
+
+    assert_eq!(
+        render("A & B", &modules, DocFormat::Html),
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>A &amp; B</title>\n</head>\n<body>\n\
+         <h1>A &amp; B</h1>\n\
+         <h2><code>main.nxs</code></h2>\n\
+         <h3 id=\"main.nxs-hi\">fn <code>hi</code></h3>\n\
+         <pre><code>fn hi()</code></pre>\n\
+         <p>Says &lt;hi&gt; &amp; more.</p>\n\
+         <p>Second paragraph.</p>\n\
+         </body>\n</html>\n"
+    );
+}
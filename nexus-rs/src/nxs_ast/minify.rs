@@ -0,0 +1,628 @@
+use super::ast::*;
+use crate::builtins::{builtin, BUILTINS};
+use crate::scanner::{is_keyword, source_identifier};
+use crate::symbol::Symbol;
+use std::collections::{HashMap, HashSet};
+
+#[cfg(test)]
+use crate::{parser::Parser, scanner::Scanner, source_line::SourceLine};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+/// Minified source code output options.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MinifyOptions {
+    pub rename: bool, // Consistently rename declared identifiers to the shortest available names.
+}
+
+/// Output an AST as minified (canonical) source code.
+///
+/// The output is semicolon-tight and comment-free, only whitespace required to separate words is kept. Empty statements
+///  are dropped. Minifying the AST of minified source code reproduces the same output.
+///
+/// With identifier renaming enabled, identifiers are renamed in order of appearance (`a`, `b`, .., `aa`, `ab`, ..),
+///  skipping keywords, built-in function names and kept names. Only names declared in the module are renamed: `main`,
+///  built-in function names, names declared elsewhere (e.g. in used files) and member names (the right-hand side of
+///  `.`) are kept as-is.
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::{minify::*, parser::Parser, scanner::Scanner, source_line::SourceLine};
+///
+/// let line = SourceLine { line: "let value = 1 + 2; /* ... */ print value;".to_string(), number: None };
+/// let ast = Parser::new(Scanner::new().scan(line).0).parse().unwrap();
+///
+/// assert_eq!(minify(&ast, MinifyOptions::default()), "let value=1+2;print value;");
+/// assert_eq!(minify(&ast, MinifyOptions { rename: true }), "let a=1+2;print a;");
+/// ```
+pub fn minify(ast: &Stmts, options: MinifyOptions) -> String {
+    let mut m = Minifier {
+        out: String::new(),
+        last_number: false,
+        names: None,
+        next_name: 0,
+        declared: HashSet::new(),
+        used: HashSet::new(),
+        kept: HashSet::new(),
+    };
+
+    m.stmts(ast, false);
+    if !options.rename {
+        return m.out;
+    }
+
+    // Rename in a second pass, once all declared and kept names are known.
+    m.kept = m
+        .used
+        .iter()
+        .filter(|id| !m.renamed(**id))
+        .map(|id| id.as_str())
+        .collect();
+    m.out.clear();
+    m.last_number = false;
+    m.names = Some(HashMap::new());
+
+    m.stmts(ast, false);
+    m.out
+}
+
+struct Minifier {
+    out: String,
+    last_number: bool, // Last emitted token was a number literal.
+    names: Option<HashMap<Symbol, String>>, // Renamed identifiers (if renaming).
+    next_name: usize,  // Index of the next short name to try.
+    declared: HashSet<Symbol>, // Names declared in the module.
+    used: HashSet<Symbol>, // All names, declared or not.
+    kept: HashSet<&'static str>, // Names that are not renamed (if renaming).
+}
+
+impl Minifier {
+    /// Emit a token, separated from the previous token only if required.
+    fn emit(&mut self, text: &str) {
+        let is_word = |c: char| c.is_alphanumeric() || c == '_';
+
+        if let (Some(l), Some(n)) = (self.out.chars().last(), text.chars().next()) {
+            // A number literal followed by a dot would be scanned as a malformed number.
+            if (is_word(l) && is_word(n)) || (self.last_number && text == ".") {
+                self.out.push(' ');
+            }
+        }
+
+        self.out.push_str(text);
+        self.last_number = false;
+    }
+
+    /// Check if a name is renamed (if renaming): names declared in the module, except `main` and built-in functions.
+    fn renamed(&self, id: Symbol) -> bool {
+        self.declared.contains(&id) && id.as_str() != "main" && builtin(id).is_none()
+    }
+
+    fn id(&mut self, id: Symbol) {
+        self.used.insert(id);
+
+        let renamed = self.renamed(id);
+        let name = match &mut self.names {
+            Some(names) if renamed => names
+                .entry(id)
+                .or_insert_with(|| loop {
+                    let name = short_name(self.next_name);
+                    self.next_name += 1;
+                    if !is_keyword(&name)
+                        && !self.kept.contains(name.as_str())
+                        && BUILTINS.binary_search_by(|b| b.name.cmp(&name)).is_err()
+                    {
+                        break name;
+                    }
+                })
+                .clone(),
+            _ => source_identifier(id.as_str()),
+        };
+
+        self.emit(&name);
+    }
+
+    /// Emit the name of a declaration.
+    fn declare(&mut self, id: Symbol) {
+        self.declared.insert(id);
+        self.id(id);
+    }
+
+    fn stmts(&mut self, stmts: &Stmts, in_block: bool) {
+        let stmts = stmts
+            .iter()
+            .filter(
+                |s| !matches!(&s.kind, StmtKind::Expr(e) if matches!(e.kind, ExprKind::Empty())),
+            )
+            .collect::<Vec<_>>();
+
+        for (i, stmt) in stmts.iter().enumerate() {
+            // The semicolon may be omitted for the last expression statement in a block (its result value).
+            let last = in_block && i + 1 == stmts.len();
+            self.stmt(stmt, last);
+        }
+    }
+
+    fn args(&mut self, args: &Option<FunctionArgs>) {
+        self.emit("(");
+        for (i, arg) in args.iter().flat_map(|a| a.iter()).enumerate() {
+            if i > 0 {
+                self.emit(",");
+            }
+            self.declare(arg.id);
+            self.emit(":");
+            self.emit(type_name(&arg.typeid));
+        }
+        self.emit(")");
+    }
+
+    fn block(&mut self, stmt: &Stmt) {
+        match &stmt.kind {
+            StmtKind::Block(body) => {
+                self.emit("{");
+                self.stmts(body, true);
+                self.emit("}");
+            }
+            _ => self.stmt(stmt, false),
+        }
+    }
+
+    fn stmt(&mut self, stmt: &Stmt, last: bool) {
+        match &stmt.kind {
+            StmtKind::Assignment(a) => {
+                self.expr(&a.lhs);
+                self.emit("=");
+                self.expr(&a.rhs);
+                self.emit(";");
+            }
+            StmtKind::Block(_) => self.block(stmt),
+            StmtKind::Connect(c) => self.connect(c, "->"),
+            StmtKind::ConstDecl(c) => {
+                self.emit("const");
+                self.declare(c.id);
+                self.emit(":");
+                self.emit(type_name(&c.typeid));
+                self.emit("=");
+                self.expr(&c.value);
+                self.emit(";");
+            }
+            StmtKind::Disconnect(c) => self.connect(c, "-/>"),
+            StmtKind::EnumDecl(e) => {
+                // Variants are not renamed, as member names (see `Mode.Run`).
+                self.emit("enum");
+                self.declare(e.id);
+                self.emit("{");
+                for (i, variant) in e.variants.iter().enumerate() {
+                    if i > 0 {
+                        self.emit(",");
+                    }
+                    self.emit(&source_identifier(variant.as_str()));
+                }
+                self.emit("}");
+            }
+            StmtKind::Defer(s) => {
+                self.emit("defer");
+                self.stmt(s, false);
+            }
+            StmtKind::Expr(e) => {
+                self.expr(e);
+                if !last {
+                    self.emit(";");
+                }
+            }
+            StmtKind::FunctionDecl(f) => {
+                self.emit("fn");
+                self.declare(f.id);
+                self.args(&f.args);
+                if let Some(t) = &f.ret_type {
+                    self.emit("->");
+                    self.emit(type_name(t));
+                }
+                self.block(&f.body);
+            }
+            StmtKind::GroupDecl(g) => {
+                // Group names are not renamed, as they are referred to by name in the graph.
+                self.emit("group");
+                self.emit(&source_identifier(g.id.as_str()));
+                if g.args.is_some() {
+                    self.args(&g.args);
+                }
+                self.block(&g.body);
+            }
+            StmtKind::NodeDecl(n) => {
+                // Node and port names are not renamed, as they are referred to by name in the graph.
+                self.emit("node");
+                self.emit(&source_identifier(n.id.as_str()));
+                self.emit("{");
+                for port in &n.ports {
+                    self.emit(&port.direction.to_string());
+                    self.emit(&source_identifier(port.id.as_str()));
+                    self.emit(":");
+                    self.emit(type_name(&port.typeid));
+                    self.emit(";");
+                }
+                self.emit("}");
+            }
+            StmtKind::Remove(r) => {
+                self.emit("remove");
+                if r.force {
+                    self.emit("force");
+                }
+                self.expr(&r.target);
+                self.emit(";");
+            }
+            StmtKind::Print(p) => {
+                self.emit(match p.kind {
+                    PrintKind::Print => "print",
+                    PrintKind::Println => "println",
+                });
+                self.exprs(&p.args);
+                self.emit(";");
+            }
+            StmtKind::Return(r) => {
+                self.emit("return");
+                self.expr(&r.expr);
+                self.emit(";");
+            }
+            StmtKind::UseDecl(u) => {
+                self.emit("use");
+                self.expr(&u.filename);
+                self.emit(";");
+            }
+            StmtKind::VarDecl(v) => {
+                self.emit("let");
+                if v.mutable {
+                    self.emit("mut");
+                }
+                match &v.id.kind {
+                    ExprKind::Var(var) => self.declare(var.id),
+                    _ => self.expr(&v.id),
+                }
+                if let Some(t) = &v.typeid {
+                    self.emit(":");
+                    self.emit(type_name(t));
+                }
+                if let Some(value) = &v.value {
+                    self.emit("=");
+                    self.expr(value);
+                }
+                self.emit(";");
+            }
+        }
+    }
+
+    fn connect(&mut self, connect: &Connect, arrow: &str) {
+        for (i, stage) in connect.stages.iter().enumerate() {
+            if i > 0 {
+                self.emit(arrow);
+            }
+            match &stage[..] {
+                [e] => self.expr(e),
+                _ => {
+                    self.emit("[");
+                    self.exprs(stage);
+                    self.emit("]");
+                }
+            }
+        }
+        if let Some(buffer) = &connect.buffer {
+            self.emit("[");
+            if let Some(capacity) = &buffer.capacity {
+                self.emit("capacity:");
+                self.expr(capacity);
+                self.emit(",");
+            }
+            self.emit("overflow:");
+            self.emit(&buffer.overflow.to_string());
+            self.emit("]");
+        }
+        self.emit(";");
+    }
+
+    fn exprs(&mut self, exprs: &[Expr]) {
+        for (i, expr) in exprs.iter().enumerate() {
+            if i > 0 {
+                self.emit(",");
+            }
+            self.expr(expr);
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match &expr.kind {
+            ExprKind::Binary(b) => {
+                self.expr(&b.lhs);
+                self.emit(binary_op(&b.op));
+                match (&b.op, &b.rhs.kind) {
+                    // Member names are not renamed.
+                    (BinaryOp::Dot, ExprKind::Var(v)) => {
+                        self.emit(&source_identifier(v.id.as_str()))
+                    }
+                    (BinaryOp::Dot, ExprKind::FuncCall(f)) => {
+                        self.call(&source_identifier(f.id.as_str()), &f.args)
+                    }
+                    _ => self.expr(&b.rhs),
+                }
+            }
+            ExprKind::Block(b) => self.block(&b.body),
+            ExprKind::Cast(x) => {
+                self.expr(&x.expr);
+                self.emit("as");
+                self.emit(type_name(&x.typeid));
+            }
+            ExprKind::Empty() => (),
+            ExprKind::For(f) => {
+                self.emit("for");
+                self.declare(f.id);
+                self.emit("in");
+                self.expr(&f.expr);
+                self.expr(&f.body);
+            }
+            ExprKind::FuncCall(f) => {
+                self.id(f.id);
+                self.call("", &f.args);
+            }
+            ExprKind::Group(e) => {
+                self.emit("(");
+                self.expr(e);
+                self.emit(")");
+            }
+            ExprKind::If(i) => {
+                self.emit("if");
+                self.expr(&i.expr);
+                self.expr(&i.body_then);
+                if let Some(e) = &i.body_else {
+                    self.emit("else");
+                    self.expr(e);
+                }
+            }
+            ExprKind::Index(x) => {
+                self.expr(&x.expr);
+                self.emit("[");
+                match &x.index {
+                    IndexKind::Element(e) => self.expr(e),
+                    IndexKind::Slice(kind, start, end) => {
+                        start.iter().for_each(|e| self.expr(e));
+                        self.emit(&kind.to_string());
+                        end.iter().for_each(|e| self.expr(e));
+                    }
+                }
+                self.emit("]");
+            }
+            ExprKind::Literal(l) => match &l.kind {
+                LiteralKind::Bool(b) => self.emit(if *b { "true" } else { "false" }),
+                LiteralKind::Nil => self.emit("nil"),
+                LiteralKind::Number(n) => {
+                    self.emit(&n.to_string());
+                    self.last_number = true;
+                }
+                LiteralKind::String(s) => self.emit(&format!(
+                    "\"{}\"",
+                    s.replace('\\', "\\\\").replace('"', "\\\"")
+                )),
+            },
+            ExprKind::Propagate(e) => {
+                self.expr(e);
+                self.emit("?");
+            }
+            ExprKind::Range(r) => {
+                self.expr(&r.start);
+                self.emit(&r.kind.to_string());
+                self.expr(&r.end);
+
+                if let Some(step) = &r.step {
+                    self.emit("step");
+                    self.expr(step);
+                }
+            }
+            ExprKind::Loop(l) => {
+                self.emit("loop");
+                self.expr(&l.body);
+            }
+            ExprKind::Ref(r) => {
+                self.emit("&");
+                self.expr(&r.expr);
+            }
+            ExprKind::Time(t) => {
+                self.emit("time");
+                self.expr(&t.body);
+            }
+            ExprKind::Try(e) => {
+                self.emit("try");
+                self.expr(e);
+            }
+            ExprKind::Unary(u) => {
+                self.emit(unary_op(&u.op));
+                self.expr(&u.expr);
+            }
+            ExprKind::Var(v) => self.id(v.id),
+            ExprKind::While(w) => {
+                self.emit("while");
+                self.expr(&w.expr);
+                self.expr(&w.body);
+            }
+        }
+    }
+
+    fn call(&mut self, name: &str, args: &[Expr]) {
+        self.emit(name);
+        self.emit("(");
+        self.exprs(args);
+        self.emit(")");
+    }
+}
+
+/// Get the n-th short identifier name: `a`..`z`, `aa`..`zz`, `aaa`.. (keywords are *not* skipped).
+fn short_name(mut n: usize) -> String {
+    let mut name = Vec::new();
+
+    loop {
+        name.push(b'a' + (n % 26) as u8);
+        if n < 26 {
+            break;
+        }
+        n = n / 26 - 1;
+    }
+
+    name.reverse();
+    String::from_utf8(name).expect("ASCII name")
+}
+
+pub(crate) fn type_name(t: &TypeKind) -> &'static str {
+    match t {
+        TypeKind::Bool => "bool",
+        TypeKind::Group => "Group",
+        TypeKind::Node => "Node",
+        TypeKind::Number => "Number",
+        TypeKind::String => "String",
+    }
+}
+
+pub(crate) fn binary_op(op: &BinaryOp) -> &'static str {
+    match op {
+        BinaryOp::And => "&&",
+        BinaryOp::Divide => "/",
+        BinaryOp::Dot => ".",
+        BinaryOp::Eq => "==",
+        BinaryOp::Gt => ">",
+        BinaryOp::GtEq => ">=",
+        BinaryOp::Lt => "<",
+        BinaryOp::LtEq => "<=",
+        BinaryOp::Multiply => "*",
+        BinaryOp::Power => "**",
+        BinaryOp::NotEq => "!=",
+        BinaryOp::Or => "||",
+        BinaryOp::Plus => "+",
+        BinaryOp::Remainder => "%",
+        BinaryOp::Subtract => "-",
+    }
+}
+
+pub(crate) fn unary_op(op: &UnaryOp) -> &'static str {
+    match op {
+        UnaryOp::Bang => "!",
+        UnaryOp::Group => "group",
+        UnaryOp::Minus => "-",
+        UnaryOp::Node => "node",
+        UnaryOp::Plus => "+",
+    }
+}
+
+#[cfg(test)]
+fn parse(code: &str) -> Stmts {
+    let mut scanner = Scanner::new();
+    let tokens = code
+        .lines()
+        .flat_map(|line| {
+            scanner
+                .scan(SourceLine {
+                    line: line.to_string(),
+                    number: None,
+                })
+                .0
+        })
+        .collect();
+
+    Parser::new(tokens).parse().unwrap()
+}
+
+#[test]
+fn minify_test() {
+    let test = |code: &str, expected: &str| {
+        assert_eq!(minify(&parse(code), MinifyOptions::default()), expected);
+    };
+
+    test("let   mut x : Number = 1.5 ;", "let mut x:Number=1.5;");
+    test("const C: bool = true; // Comment.", "const C:bool=true;");
+    test(
+        "fn f(a: Number, b: String) -> Number { a }",
+        "fn f(a:Number,b:String)->Number{a}",
+    );
+    test(
+        "fn f() { print 1, \"a\"; println; return 2; }",
+        "fn f(){print 1,\"a\";println;return 2;}",
+    );
+    test(
+        "if x < 1 { y = -x; } else if !x { ; } else { 3 };",
+        "if x<1{y=-x;}else if!x{}else{3};",
+    );
+    test(
+        "for i in 0..=(n - 1) { n.size(); n . x; };",
+        "for i in 0..=(n-1){n.size();n.x};",
+    );
+    test("while a && b || c {};", "while a&&b||c{};");
+    test(
+        "let n = node x; let g = group y; n -> g;",
+        "let n=node x;let g=group y;n->g;",
+    );
+    test("use \"lib.nxs\"; let r = &x;", "use\"lib.nxs\";let r=&x;");
+    test(
+        "print \"\\\"quoted\\\" \\\\\";",
+        "print\"\\\"quoted\\\" \\\\\";",
+    );
+    test("print 1 . x;", "print 1 .x;");
+    test("; ;", "");
+}
+
+#[test]
+fn minify_roundtrip_test() {
+    let code = "
+        use \"lib.nxs\";
+        /* A multiline
+           comment. */
+        const LIMIT: Number = 10;
+        fn square(x: Number) -> Number { x * x }
+        let mut total = 0;
+        for i in 1..LIMIT {
+            if i % 2 == 0 { total = total + square(i); }
+        }
+        let n = node a;
+        n.value = (total - 1) / 2;
+        println total, n.value;
+    ";
+
+    let ast = parse(code);
+
+    for options in [MinifyOptions::default(), MinifyOptions { rename: true }] {
+        let min = minify(&ast, options);
+        let reparsed = parse(&min);
+
+        assert_eq!(minify(&reparsed, options), min);
+        if !options.rename {
+            assert_eq!(reparsed.to_string(), ast.to_string());
+        }
+    }
+}
+
+#[test]
+fn minify_rename_test() {
+    let test = |code: &str, expected: &str| {
+        assert_eq!(
+            minify(&parse(code), MinifyOptions { rename: true }),
+            expected
+        );
+    };
+
+    test(
+        "fn square(x: Number) -> Number { x * x } let x = square(2); x.value = x;",
+        "fn a(b:Number)->Number{b*b}let b=a(2);b.value=b;",
+    );
+    test(
+        "fn main() -> Number { let x = abs(-1); x + a + lib(x) }",
+        "fn main()->Number{let b=abs(-1);b+a+lib(b)}",
+    );
+    test(
+        "fn len(s: String) -> Number { 0 } let x = len(\"\");",
+        "fn len(a:String)->Number{0}let b=len(\"\");",
+    );
+}
+
+#[test]
+fn short_name_test() {
+    assert_eq!(short_name(0), "a");
+    assert_eq!(short_name(25), "z");
+    assert_eq!(short_name(26), "aa");
+    assert_eq!(short_name(26 + 26 * 26 - 1), "zz");
+    assert_eq!(short_name(26 + 26 * 26), "aaa");
+}
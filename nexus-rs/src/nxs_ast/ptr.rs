@@ -1,4 +1,4 @@
-use core::fmt;
+use core::{fmt, ops};
 
 /// Immovable pointer type, able to take DSTs.
 ///
@@ -50,6 +50,24 @@ impl<T: 'static> Ptr<T> {
     }
 }
 
+/// Read access to the pointee (the pointer is immovable, but not opaque).
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::ptr::Ptr;
+///
+/// let p = Ptr::new((1, 2));
+/// assert_eq!(p.0, 1);
+/// ```
+impl<T: ?Sized> ops::Deref for Ptr<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.ptr
+    }
+}
+
 #[test]
 fn test_new() {
     let p0 = Ptr::new(42);
@@ -59,3 +77,10 @@ fn test_new() {
     let p1 = Ptr::new(x);
     assert_eq!(p1.into_inner(), 42);
 }
+
+#[test]
+fn test_deref() {
+    let p = Ptr::new("abc".to_string());
+    assert_eq!(p.len(), 3);
+    assert_eq!(*p, "abc");
+}
@@ -0,0 +1,658 @@
+use crate::{scanner::Scanner, source_line::SourceLine, symbol::Symbol, token::Token};
+use thiserror::Error;
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+/// Position in a source file.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Position {
+    pub line: usize,      // Index of the source line, starting from 0.
+    pub character: usize, // Character index in the line.
+}
+
+/// Location of a name in a source file of a project.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Location {
+    pub file: usize,  // Index of the file in the project.
+    pub line: usize,  // Index of the source line, starting from 0.
+    pub start: usize, // Start character index (inclusive).
+    pub end: usize,   // End character index (exclusive).
+}
+
+impl Location {
+    /// Check if a position is on the name, including the position directly after it.
+    fn contains(&self, file: usize, position: Position) -> bool {
+        self.file == file
+            && self.line == position.line
+            && (self.start..=self.end).contains(&position.character)
+    }
+}
+
+/// Text edit: replace the text at a location with new text.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextEdit {
+    pub location: Location,
+    pub new_text: String,
+}
+
+/// Kind of declared name.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DefinitionKind {
+    Const,
+    Variable, // Variable, including loop variables.
+    Argument, // Function argument.
+    Function,
+}
+
+/// Declared name with its use sites.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Definition {
+    pub name: Symbol,
+    pub kind: DefinitionKind,
+    pub location: Location,        // Location of the name in the declaration.
+    pub references: Vec<Location>, // Use sites, in order of appearance.
+    scope: usize, // Scope of the declaration, scope 0 being the top level of the file.
+    from: usize,  // Index of the token from which the declaration is visible.
+}
+
+impl Definition {
+    /// Check if the name is declared at the top level of a file.
+    pub fn is_top_level(&self) -> bool {
+        self.scope == 0
+    }
+}
+
+/// Symbol table of a source file: the declared names, and the use sites of names resolved to them.
+///
+/// Names are resolved lexically from the token stream, so a file that does not parse (e.g. while editing) is still
+///  resolved on a best-effort basis. Scopes are delimited by braces. A use resolves to the innermost declaration
+///  visible at that point: functions are visible throughout their scope, variables and constants after their
+///  declaration statement (so `let x = x + 1;` refers to an earlier `x`). A use without visible declaration resolves
+///  to a later declaration in an enclosing scope, e.g. a top-level constant used in a function declared before it.
+///  Members (e.g. `rate` in `n.rate`) are not resolved.
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::{resolver::*, source_line::SourceLine};
+///
+/// let lines = ["let x = 1;", "{ let x = x + 1; print x; }", "print x;"]
+///     .map(|l| SourceLine { line: l.to_owned(), number: None });
+///
+/// let table = SymbolTable::new(0, &lines);
+/// let (outer, inner) = (&table.definitions[0], &table.definitions[1]);
+///
+/// assert_eq!(outer.references.iter().map(|l| l.line).collect::<Vec<_>>(), [1, 2]);
+/// assert_eq!(inner.references.iter().map(|l| (l.line, l.start)).collect::<Vec<_>>(), [(1, 23)]);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SymbolTable {
+    pub definitions: Vec<Definition>,        // In order of declaration.
+    pub unresolved: Vec<(Symbol, Location)>, // Uses of undeclared names, e.g. built-in functions.
+    parents: Vec<Option<usize>>,             // Parent of each scope.
+    uses: Vec<Use>,                          // Uses of names, in order of appearance.
+}
+
+/// Use of a name.
+#[derive(Clone, Debug, PartialEq)]
+struct Use {
+    name: Symbol,
+    location: Location,
+    scope: usize, // Innermost scope containing the use.
+    token: usize, // Index of the token.
+}
+
+impl SymbolTable {
+    /// Resolve the names of a source file, with the given file index for the locations.
+    pub fn new(file: usize, lines: &[SourceLine]) -> Self {
+        let mut scanner = Scanner::new();
+        let (mut tokens, mut locations) = (Vec::new(), Vec::new());
+
+        for (index, line) in lines.iter().enumerate() {
+            let (t, s, _) = scanner.scan_with_spans(line.clone());
+
+            tokens.extend(t);
+            locations.extend(s.iter().map(|s| Location {
+                file,
+                line: index,
+                start: s.start,
+                end: s.end,
+            }));
+        }
+
+        resolve(&tokens, &locations)
+    }
+
+    /// Resolve a use to the index of a definition accepted by a filter (typically matching the name), without
+    ///  looking at other files.
+    ///
+    /// The latest visible declaration in the innermost scope is taken, or else any declaration in the innermost scope.
+    fn resolve_local(&self, u: &Use, accept: impl Fn(usize) -> bool) -> Option<usize> {
+        let chain: Vec<_> = std::iter::successors(Some(u.scope), |s| self.parents[*s]).collect();
+        let accept = &accept;
+        let candidates = |scope: usize| {
+            self.definitions
+                .iter()
+                .enumerate()
+                .filter(move |(index, d)| d.scope == scope && accept(*index))
+        };
+
+        chain
+            .iter()
+            .find_map(|s| {
+                candidates(*s)
+                    .filter(|(_, d)| d.from <= u.token)
+                    .max_by_key(|(_, d)| d.from)
+                    .map(|(index, _)| index)
+            })
+            .or_else(|| {
+                chain
+                    .iter()
+                    .find_map(|s| candidates(*s).next().map(|(index, _)| index))
+            })
+    }
+
+    /// Get the index of the definition with its declaration or a use site at a position.
+    pub fn definition_at(&self, file: usize, position: Position) -> Option<usize> {
+        self.definitions.iter().position(|d| {
+            d.location.contains(file, position)
+                || d.references.iter().any(|r| r.contains(file, position))
+        })
+    }
+}
+
+/// Rename error representation.
+#[derive(Error, Debug, PartialEq)]
+pub enum RenameError {
+    #[error("no declared name at the given position")]
+    NotFound,
+
+    #[error("'{0}' is not a valid identifier")]
+    InvalidName(String),
+
+    #[error("'{0}' is already declared in the same scope")]
+    Conflict(String),
+
+    #[error("renaming to '{0}' changes what names refer to, through shadowing")]
+    Shadowed(String),
+}
+
+/// Project of source files, with cross-file name resolution.
+///
+/// Top-level declarations are shared between the files of a project (as with 'use' declarations): a name that is not
+///  declared in a file resolves to a top-level declaration in another file, the first in order of the files.
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::{resolver::*, source_line::SourceLine};
+///
+/// let lines = |l: &[&str]| l.iter().map(|l| SourceLine { line: l.to_string(), number: None }).collect();
+/// let project = Project::new(&[lines(&["use \"lib.nxs\";", "print twice(2);"]), lines(&["fn twice(x: Number) { x * 2 }"])]);
+///
+/// let edits = project.rename(0, Position { line: 1, character: 8 }, "double").unwrap();
+///
+/// assert_eq!(edits.iter().map(|e| (e.location.file, e.location.line)).collect::<Vec<_>>(), [(1, 0), (0, 1)]);
+/// assert!(edits.iter().all(|e| e.new_text == "double"));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Project {
+    pub tables: Vec<SymbolTable>, // Symbol table of each file, in order of the files.
+}
+
+impl Project {
+    /// Resolve the names of the source files of a project.
+    pub fn new(files: &[Vec<SourceLine>]) -> Self {
+        let mut tables: Vec<_> = files
+            .iter()
+            .enumerate()
+            .map(|(file, lines)| SymbolTable::new(file, lines))
+            .collect();
+
+        for file in 0..tables.len() {
+            for (name, location) in std::mem::take(&mut tables[file].unresolved) {
+                let target = tables.iter().enumerate().find_map(|(other, t)| {
+                    t.definitions
+                        .iter()
+                        .position(|d| other != file && d.is_top_level() && d.name == name)
+                        .map(|index| (other, index))
+                });
+
+                match target {
+                    Some((other, index)) => {
+                        tables[other].definitions[index].references.push(location)
+                    }
+                    None => tables[file].unresolved.push((name, location)),
+                }
+            }
+        }
+
+        for definition in tables.iter_mut().flat_map(|t| t.definitions.iter_mut()) {
+            definition.references.sort();
+        }
+
+        Project { tables }
+    }
+
+    /// Get the definition with its declaration or a use site at a position in a file.
+    pub fn definition_at(&self, file: usize, position: Position) -> Option<&Definition> {
+        self.tables.iter().find_map(|t| {
+            t.definition_at(file, position)
+                .map(|index| &t.definitions[index])
+        })
+    }
+
+    /// Find all references of the name at a position in a file: the location of its declaration, followed by its use
+    ///  sites. Returns no locations if there is no declared name at the position.
+    pub fn find_references(&self, file: usize, position: Position) -> Vec<Location> {
+        self.definition_at(file, position)
+            .map(|d| {
+                std::iter::once(d.location)
+                    .chain(d.references.iter().copied())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Get the edits renaming the name at a position in a file, in its declaration and all of its use sites.
+    pub fn rename(
+        &self,
+        file: usize,
+        position: Position,
+        new_name: &str,
+    ) -> Result<Vec<TextEdit>, RenameError> {
+        let definition = self
+            .definition_at(file, position)
+            .ok_or(RenameError::NotFound)?;
+
+        let (tokens, _) = Scanner::new().scan(SourceLine {
+            line: new_name.to_owned(),
+            number: None,
+        });
+
+        let name = match tokens.as_slice() {
+            [Token::Identifier(name)] if name.to_string() == new_name => *name,
+            _ => return Err(RenameError::InvalidName(new_name.to_owned())),
+        };
+
+        let conflict = self
+            .tables
+            .iter()
+            .flat_map(|t| t.definitions.iter())
+            .any(|d| {
+                d.name == name
+                    && d.scope == definition.scope
+                    && (d.location.file == definition.location.file || d.is_top_level())
+            });
+
+        if name == definition.name {
+            return Ok(self.rename_edits(file, position, new_name));
+        }
+
+        if conflict {
+            return Err(RenameError::Conflict(new_name.to_owned()));
+        }
+
+        // All uses of both names must resolve to the same declarations after renaming, so no use is captured by (or
+        //  escapes from) the renamed declaration through shadowing.
+        let renamed = self.definition_id_at(file, position);
+        let name_of = |id: DefinitionId| match Some(id) == renamed {
+            true => name,
+            false => self.tables[id.0].definitions[id.1].name,
+        };
+
+        for (index, table) in self.tables.iter().enumerate() {
+            for u in table
+                .uses
+                .iter()
+                .filter(|u| u.name == definition.name || u.name == name)
+            {
+                let before = self.resolve_use(index, u, |id| {
+                    self.tables[id.0].definitions[id.1].name == u.name
+                });
+
+                let use_name = if before == renamed { name } else { u.name };
+                let after = self.resolve_use(index, u, |id| name_of(id) == use_name);
+
+                if after != before {
+                    return Err(RenameError::Shadowed(new_name.to_owned()));
+                }
+            }
+        }
+
+        Ok(self.rename_edits(file, position, new_name))
+    }
+
+    fn rename_edits(&self, file: usize, position: Position, new_name: &str) -> Vec<TextEdit> {
+        self.find_references(file, position)
+            .into_iter()
+            .map(|location| TextEdit {
+                location,
+                new_text: new_name.to_owned(),
+            })
+            .collect()
+    }
+
+    /// Get the file and index of the definition with its declaration or a use site at a position in a file.
+    fn definition_id_at(&self, file: usize, position: Position) -> Option<DefinitionId> {
+        self.tables
+            .iter()
+            .enumerate()
+            .find_map(|(index, t)| t.definition_at(file, position).map(|d| (index, d)))
+    }
+
+    /// Resolve a use in a file to a definition accepted by a filter: a definition in the file itself, or else the first
+    ///  top-level definition in another file (see [`Project::new`]).
+    fn resolve_use(
+        &self,
+        file: usize,
+        u: &Use,
+        accept: impl Fn(DefinitionId) -> bool,
+    ) -> Option<DefinitionId> {
+        self.tables[file]
+            .resolve_local(u, |index| accept((file, index)))
+            .map(|index| (file, index))
+            .or_else(|| {
+                self.tables.iter().enumerate().find_map(|(other, t)| {
+                    t.definitions
+                        .iter()
+                        .enumerate()
+                        .position(|(index, d)| {
+                            other != file && d.is_top_level() && accept((other, index))
+                        })
+                        .map(|index| (other, index))
+                })
+            })
+    }
+}
+
+/// Definition identifier in a project: the index of the file and the index of the definition in its symbol table.
+type DefinitionId = (usize, usize);
+
+/// Resolve the names in a token stream, with the location of each token.
+fn resolve(tokens: &[Token], locations: &[Location]) -> SymbolTable {
+    let mut parents = vec![None]; // Parent of each scope.
+    let mut stack = vec![0]; // Open scopes.
+    let mut pending = Vec::new(); // Declarations in the next scope (function arguments, loop variables).
+    let mut declarations = Vec::new(); // Declarations, with the index of the token from which they are visible.
+    let mut uses = Vec::new();
+    let mut arguments = false; // Inside a function argument list.
+
+    for (i, token) in tokens.iter().enumerate() {
+        let scope = *stack.last().unwrap_or(&0);
+
+        match token {
+            Token::LeftBrace => {
+                let inner = parents.len();
+                parents.push(Some(scope));
+                stack.push(inner);
+
+                declarations.extend(pending.drain(..).map(|(name, kind, location)| {
+                    (new_definition(name, kind, location, inner), i)
+                }));
+            }
+            Token::RightBrace if stack.len() > 1 => {
+                stack.pop();
+            }
+            Token::LeftParen if i >= 2 && tokens[i - 2] == Token::Function => arguments = true,
+            Token::RightParen => arguments = false,
+            Token::Identifier(name) => {
+                let (name, location) = (*name, locations[i]);
+                let next = tokens.get(i + 1);
+
+                match i.checked_sub(1).map(|p| &tokens[p]) {
+                    Some(Token::Dot) => (), // Member.
+                    Some(Token::Function) => declarations.push((
+                        new_definition(name, DefinitionKind::Function, location, scope),
+                        0,
+                    )),
+                    Some(Token::Let | Token::Mut | Token::Const) if next != Some(&Token::Dot) => {
+                        let kind = match tokens[i - 1] {
+                            Token::Const => DefinitionKind::Const,
+                            _ => DefinitionKind::Variable,
+                        };
+
+                        declarations.push((
+                            new_definition(name, kind, location, scope),
+                            statement_end(tokens, i),
+                        ));
+                    }
+                    Some(Token::For) => pending.push((name, DefinitionKind::Variable, location)),
+                    _ if arguments && next == Some(&Token::Colon) => {
+                        pending.push((name, DefinitionKind::Argument, location))
+                    }
+                    _ => uses.push((i, name, location, scope)),
+                }
+            }
+            _ => (),
+        }
+    }
+
+    let mut table = SymbolTable {
+        definitions: declarations
+            .into_iter()
+            .map(|(d, from)| Definition { from, ..d })
+            .collect(),
+        unresolved: Vec::new(),
+        parents,
+        uses: uses
+            .into_iter()
+            .map(|(token, name, location, scope)| Use {
+                name,
+                location,
+                scope,
+                token,
+            })
+            .collect(),
+    };
+
+    for u in &table.uses {
+        match table.resolve_local(u, |index| table.definitions[index].name == u.name) {
+            Some(index) => table.definitions[index].references.push(u.location),
+            None => table.unresolved.push((u.name, u.location)),
+        }
+    }
+
+    table
+}
+
+fn new_definition(
+    name: Symbol,
+    kind: DefinitionKind,
+    location: Location,
+    scope: usize,
+) -> Definition {
+    Definition {
+        name,
+        kind,
+        location,
+        references: Vec::new(),
+        scope,
+        from: 0,
+    }
+}
+
+/// Get the index of the ';' ending the statement containing a token (or the end of the enclosing block).
+fn statement_end(tokens: &[Token], start: usize) -> usize {
+    let mut depth = 0;
+
+    for (i, token) in tokens.iter().enumerate().skip(start) {
+        match token {
+            Token::LeftBrace | Token::LeftParen | Token::LeftBracket => depth += 1,
+            Token::RightBrace | Token::RightParen | Token::RightBracket if depth == 0 => return i,
+            Token::RightBrace | Token::RightParen | Token::RightBracket => depth -= 1,
+            Token::SemiColon if depth == 0 => return i,
+            _ => (),
+        }
+    }
+
+    tokens.len()
+}
+
+#[cfg(test)]
+fn lines(lines: &[&str]) -> Vec<SourceLine> {
+    lines
+        .iter()
+        .map(|l| SourceLine {
+            line: l.to_string(),
+            number: None,
+        })
+        .collect()
+}
+
+#[test]
+fn resolve_test() {
+    let source = lines(&[
+        "fn f(x: Number) -> Number {",    // 0
+        "    for i in 0..x { print i; }", // 1
+        "    let x = x + N;",             // 2
+        "    g(x)",                       // 3
+        "}",                              // 4
+        "fn g(n: Number) { n }",          // 5
+        "const N: Number = 1;",           // 6
+        "let c = node \"C\";",            // 7
+        "c.x = f(N);",                    // 8
+        "print sqrt(c.x);",               // 9
+    ]);
+
+    let table = SymbolTable::new(0, &source);
+    let references: Vec<_> = table
+        .definitions
+        .iter()
+        .map(|d| {
+            let refs: Vec<_> = d.references.iter().map(|l| (l.line, l.start)).collect();
+            (d.name.to_string(), d.kind, d.location.line, refs)
+        })
+        .collect();
+
+    assert_eq!(
+        references,
+        [
+            ("f".to_owned(), DefinitionKind::Function, 0, vec![(8, 6)]),
+            (
+                "x".to_owned(),
+                DefinitionKind::Argument,
+                0,
+                vec![(1, 16), (2, 12)]
+            ),
+            ("i".to_owned(), DefinitionKind::Variable, 1, vec![(1, 26)]),
+            ("x".to_owned(), DefinitionKind::Variable, 2, vec![(3, 6)]),
+            ("g".to_owned(), DefinitionKind::Function, 5, vec![(3, 4)]),
+            ("n".to_owned(), DefinitionKind::Argument, 5, vec![(5, 18)]),
+            (
+                "N".to_owned(),
+                DefinitionKind::Const,
+                6,
+                vec![(2, 16), (8, 8)]
+            ),
+            (
+                "c".to_owned(),
+                DefinitionKind::Variable,
+                7,
+                vec![(8, 0), (9, 11)]
+            ),
+        ]
+    );
+
+    assert_eq!(
+        table
+            .unresolved
+            .iter()
+            .map(|(n, l)| (n.to_string(), l.line))
+            .collect::<Vec<_>>(),
+        [("sqrt".to_owned(), 9)]
+    );
+
+    assert_eq!(
+        table.definition_at(
+            0,
+            Position {
+                line: 3,
+                character: 7
+            }
+        ),
+        Some(3)
+    );
+    assert_eq!(
+        table.definition_at(
+            0,
+            Position {
+                line: 8,
+                character: 3
+            }
+        ),
+        None
+    );
+}
+
+#[test]
+fn rename_test() {
+    let project = Project::new(&[
+        lines(&["use \"lib.nxs\";", "let y = 1;", "print twice(y);"]),
+        lines(&["fn twice(x: Number) -> Number { x * 2 }", "let y = 2;"]),
+    ]);
+
+    let position = |line, character| Position { line, character };
+
+    assert_eq!(
+        project.find_references(1, position(0, 9)),
+        [
+            Location {
+                file: 1,
+                line: 0,
+                start: 9,
+                end: 10
+            },
+            Location {
+                file: 1,
+                line: 0,
+                start: 32,
+                end: 33
+            },
+        ]
+    );
+
+    let edits = project.rename(0, position(1, 4), "z").unwrap();
+    assert_eq!(
+        edits
+            .iter()
+            .map(|e| (e.location.file, e.location.line, e.location.start))
+            .collect::<Vec<_>>(),
+        [(0, 1, 4), (0, 2, 12)]
+    );
+
+    assert_eq!(
+        project.rename(0, position(0, 0), "z"),
+        Err(RenameError::NotFound)
+    );
+    assert_eq!(
+        project.rename(0, position(1, 4), "let"),
+        Err(RenameError::InvalidName("let".to_owned()))
+    );
+    assert_eq!(
+        project.rename(0, position(1, 4), "a b"),
+        Err(RenameError::InvalidName("a b".to_owned()))
+    );
+    assert!(project.rename(1, position(0, 9), "y").is_ok()); // Shadows the top-level 'y'.
+    assert_eq!(
+        project.rename(0, position(2, 6), "y"),
+        Err(RenameError::Conflict("y".to_owned()))
+    );
+    assert!(project.rename(1, position(0, 9), "twice").is_ok());
+
+    let project = Project::new(&[lines(&[
+        "let a = 1;",
+        "fn f() -> Number { let b = 2; a + b }",
+    ])]);
+
+    assert_eq!(
+        project.rename(0, position(0, 4), "b"),
+        Err(RenameError::Shadowed("b".to_owned()))
+    ); // 'a + b' would become 'b + b'.
+    assert_eq!(
+        project.rename(0, position(1, 23), "a"),
+        Err(RenameError::Shadowed("a".to_owned()))
+    ); // 'a + b' would become 'a + a'.
+    assert!(project.rename(0, position(1, 23), "c").is_ok());
+}
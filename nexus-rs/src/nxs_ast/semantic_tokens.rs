@@ -0,0 +1,620 @@
+use super::ast::{Expr, ExprKind, IndexKind, Stmt, StmtKind, Stmts};
+use crate::{
+    builtins::builtin,
+    edition::Edition,
+    json::Json,
+    parser::Parser,
+    scanner::{keyword, Scanner},
+    source_line::SourceLine,
+    span::Spans,
+    symbol::Symbol,
+    token::{Token, Tokens},
+};
+use std::collections::HashSet;
+use strum_macros::Display;
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+/// Semantic token kind, the classification of a piece of source code for highlighting.
+///
+/// Displayed as the standard token type name of the Language Server Protocol (see [`LEGEND`]).
+#[derive(Clone, Copy, Debug, Display, PartialEq)]
+#[strum(serialize_all = "lowercase")]
+pub enum SemanticKind {
+    Keyword,
+    Variable, // Constant, variable or function argument.
+    Property, // Member, e.g. `p` in `n.p`.
+    Function, // Declared or built-in function.
+    Type,
+    Number,
+    String,
+    Comment,
+}
+
+/// Semantic token kinds in order of their LSP token type index (see [`encode`]).
+pub const LEGEND: &[SemanticKind] = &[
+    SemanticKind::Keyword,
+    SemanticKind::Variable,
+    SemanticKind::Property,
+    SemanticKind::Function,
+    SemanticKind::Type,
+    SemanticKind::Number,
+    SemanticKind::String,
+    SemanticKind::Comment,
+];
+
+/// Semantic token: a classified piece of a single source line.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SemanticToken {
+    pub line: usize,  // Index of the source line, starting from 0.
+    pub start: usize, // Start character index (inclusive).
+    pub end: usize,   // End character index (exclusive).
+    pub kind: SemanticKind,
+}
+
+/// Classify the source code, in order of appearance.
+///
+/// Identifiers are classified using the parse result: names declared as function (and built-in functions) are
+///  functions, other names are variables, and a name used as both is a function only where it is called. Punctuation
+///  and operators are not classified. If the source fails to parse, identifiers are classified on a best-effort basis
+///  (a name followed by '(' is a function).
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::{semantic_tokens::*, source_line::SourceLine};
+///
+/// let lines = ["fn twice(x: Number) -> Number { x * 2 } // Doubles.", "let f = twice;"]
+///     .map(|l| SourceLine { line: l.to_owned(), number: None });
+///
+/// let kinds: Vec<_> = semantic_tokens(&lines).iter().map(|t| t.kind.to_string()).collect();
+///
+/// assert_eq!(
+///     kinds,
+///     [
+///         "keyword", "function", "variable", "type", "type", "variable", "number", "comment", // Line 1.
+///         "keyword", "variable", "function", // Line 2.
+///     ]
+/// );
+/// ```
+pub fn semantic_tokens(lines: &[SourceLine]) -> Vec<SemanticToken> {
+    let mut scanner = Scanner::new();
+    let mut result = Vec::new();
+    let (mut tokens, mut spans) = (Tokens::new(), Spans::new());
+    let mut lexemes = Vec::new(); // Index of the line of each token.
+
+    for (index, line) in lines.iter().enumerate() {
+        let in_comment = scanner.in_comment();
+        let (t, s, _) = scanner.scan_with_spans(line.clone());
+        let chars: Vec<char> = line.line.chars().collect();
+
+        let mut covered = vec![false; chars.len()];
+        for span in &s {
+            covered[span.start.min(chars.len())..span.end.min(chars.len())].fill(true);
+        }
+
+        let comments = match line.is_shebang() {
+            true => vec![(0, chars.len())],
+            false => comments(&chars, &covered, in_comment),
+        };
+
+        result.extend(comments.into_iter().map(|(start, end)| SemanticToken {
+            line: index,
+            start,
+            end,
+            kind: SemanticKind::Comment,
+        }));
+
+        lexemes.extend(std::iter::repeat_n(index, t.len()));
+        tokens.extend(t);
+        spans.extend(s);
+    }
+
+    let edition = Edition::detect(Edition::header(lines.iter().map(|l| l.line.as_str())))
+        .ok()
+        .flatten()
+        .unwrap_or_default();
+
+    let names = Parser::with_spans(tokens.clone(), spans.clone())
+        .with_edition(edition)
+        .parse()
+        .ok()
+        .map(|ast| {
+            let mut names = Names::default();
+            names.stmts(&ast);
+            names
+        });
+
+    for (i, token) in tokens.iter().enumerate() {
+        let kind = match token {
+            Token::Number(..) => SemanticKind::Number,
+            Token::String(_) => SemanticKind::String,
+            Token::DocComment(_) => SemanticKind::Comment,
+            Token::BoolId | Token::GroupId | Token::NodeId | Token::NumberId | Token::StringId => {
+                SemanticKind::Type
+            }
+            Token::Identifier(_) if is_contextual_keyword(&tokens, i) => SemanticKind::Keyword,
+            Token::Identifier(id) => {
+                let previous = i.checked_sub(1).map(|p| &tokens[p]);
+                let next = tokens.get(i + 1);
+                let called = next == Some(&Token::LeftParen);
+
+                match (previous, &names) {
+                    (Some(Token::Dot), _) => SemanticKind::Property,
+                    (Some(Token::Function), _) => SemanticKind::Function,
+                    (Some(Token::Node), _) if next == Some(&Token::LeftBrace) => SemanticKind::Type,
+                    (Some(Token::In), _) if next == Some(&Token::Colon) => SemanticKind::Property,
+                    (Some(Token::LeftBracket | Token::Comma), _)
+                        if matches!(id.as_str(), "capacity" | "overflow")
+                            && next == Some(&Token::Colon) =>
+                    {
+                        SemanticKind::Property // Connection option.
+                    }
+                    (Some(Token::Identifier(out)), _)
+                        if out.as_str() == "out" && next == Some(&Token::Colon) =>
+                    {
+                        SemanticKind::Property
+                    }
+                    (_, Some(names)) => names.kind(*id, called),
+                    (_, None) if called || builtin(*id).is_some() => SemanticKind::Function,
+                    (_, None) => SemanticKind::Variable,
+                }
+            }
+            t if keyword(t).is_some() => SemanticKind::Keyword,
+            _ => continue,
+        };
+
+        result.push(SemanticToken {
+            line: lexemes[i],
+            start: spans[i].start,
+            end: spans[i].end,
+            kind,
+        });
+    }
+
+    result.sort_by_key(|t| (t.line, t.start));
+    result
+}
+
+/// Check if the identifier at index `i` is used as contextual keyword, which is not reserved elsewhere: the port
+///  direction 'out' in port declarations (`out y: Number;`), 'remove' and 'force' in remove statements
+///  (`remove force a;`), overflow policies in connection options (`[overflow: drop_oldest]`), 'step' in ranges
+///  (`0..10 step 2`), 'as' in casts (`x as String`), and 'enum' in enum declarations (`enum Mode { .. }`).
+fn is_contextual_keyword(tokens: &Tokens, i: usize) -> bool {
+    let word = |i: usize, w: &str| matches!(tokens.get(i), Some(Token::Identifier(id)) if id.as_str() == w);
+    let identifier = |i: usize| matches!(tokens.get(i), Some(Token::Identifier(_)));
+    let stmt_start = |i: usize| {
+        matches!(
+            i.checked_sub(1).map(|p| &tokens[p]),
+            None | Some(Token::SemiColon | Token::LeftBrace | Token::RightBrace)
+        )
+    };
+
+    (word(i, "out") && identifier(i + 1) && tokens.get(i + 2) == Some(&Token::Colon))
+        || (word(i, "remove") && identifier(i + 1) && stmt_start(i))
+        || (word(i, "force")
+            && identifier(i + 1)
+            && i > 0
+            && word(i - 1, "remove")
+            && stmt_start(i - 1))
+        || (i > 1 && word(i - 2, "overflow") && tokens[i - 1] == Token::Colon)
+        || (word(i, "as") && tokens.get(i + 1).is_some_and(Token::is_type))
+        || (word(i, "enum") && identifier(i + 1) && tokens.get(i + 2) == Some(&Token::LeftBrace))
+        || (word(i, "step")
+            && i > 1
+            && (matches!(tokens[i - 2], Token::Range | Token::RangeInclusive)
+                || tokens[i - 1] == Token::RightParen))
+}
+
+/// Encode semantic tokens (in order of appearance) as LSP semantic tokens data.
+///
+/// Each token is encoded as five numbers: the line delta, the start delta (relative to the previous token start if on
+///  the same line), the length, the token type (index in [`LEGEND`]) and the token modifiers (none). Positions and
+///  lengths are in UTF-16 code units, as required by LSP.
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::{semantic_tokens::*, source_line::SourceLine};
+///
+/// let lines = ["let x = \"😀\";", "", "print x;"].map(|l| SourceLine { line: l.to_owned(), number: None });
+///
+/// assert_eq!(
+///     encode(&semantic_tokens(&lines), &lines),
+///     [0, 0, 3, 0, 0, 0, 4, 1, 1, 0, 0, 4, 4, 6, 0, 2, 0, 5, 0, 0, 0, 6, 1, 1, 0]
+/// );
+/// ```
+pub fn encode(tokens: &[SemanticToken], lines: &[SourceLine]) -> Vec<u32> {
+    let mut data = Vec::with_capacity(tokens.len() * 5);
+    let (mut line, mut start) = (0, 0);
+
+    for token in tokens {
+        let utf16 = |index: usize| -> usize {
+            lines.get(token.line).map_or(index, |l| {
+                l.line.chars().take(index).map(char::len_utf16).sum()
+            })
+        };
+
+        let (token_start, token_end) = (utf16(token.start), utf16(token.end));
+        if token.line != line {
+            start = 0;
+        }
+
+        data.extend([
+            (token.line - line) as u32,
+            (token_start - start) as u32,
+            (token_end - token_start) as u32,
+            LEGEND
+                .iter()
+                .position(|k| *k == token.kind)
+                .unwrap_or_default() as u32,
+            0,
+        ]);
+
+        (line, start) = (token.line, token_start);
+    }
+
+    data
+}
+
+/// Get the LSP semantic tokens legend and data as JSON, i.e. the `semanticTokensProvider.legend` server capability
+///  and the `SemanticTokens` result of a `textDocument/semanticTokens/full` request, combined in one object.
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::{semantic_tokens::*, source_line::SourceLine};
+///
+/// let lines = [SourceLine { line: "let x;".to_owned(), number: Some(1) }];
+///
+/// assert_eq!(
+///     to_json(&semantic_tokens(&lines), &lines).to_string(),
+///     r#"{"legend":{"tokenTypes":["keyword","variable","property","function","type","number","string","comment"],"tokenModifiers":[]},"data":[0,0,3,0,0,0,4,1,1,0]}"#
+/// );
+/// ```
+pub fn to_json(tokens: &[SemanticToken], lines: &[SourceLine]) -> Json {
+    Json::Object(vec![
+        (
+            "legend".to_owned(),
+            Json::Object(vec![
+                (
+                    "tokenTypes".to_owned(),
+                    Json::Array(LEGEND.iter().map(|k| Json::String(k.to_string())).collect()),
+                ),
+                ("tokenModifiers".to_owned(), Json::Array(Vec::new())),
+            ]),
+        ),
+        (
+            "data".to_owned(),
+            Json::Array(
+                encode(tokens, lines)
+                    .into_iter()
+                    .map(|n| Json::Number(n as f64))
+                    .collect(),
+            ),
+        ),
+    ])
+}
+
+/// Get the character ranges of the comments of a line, i.e. the text not covered by any token starting with '//' or
+///  '/*', or the start of the line if it starts inside a multiline comment.
+fn comments(chars: &[char], covered: &[bool], in_comment: bool) -> Vec<(usize, usize)> {
+    let mut comments = Vec::new();
+    let mut open = in_comment.then_some((0, 0)); // Start of the open comment, and where its end may start.
+    let mut i = 0;
+
+    while i < chars.len() {
+        let next = chars.get(i + 1).copied();
+
+        match open {
+            Some((start, from)) if i >= from && chars[i] == '*' && next == Some('/') => {
+                comments.push((start, i + 2));
+                open = None;
+                i += 1;
+            }
+            None if !covered[i] && chars[i] == '/' && next == Some('/') => {
+                comments.push((i, chars.len()));
+                return comments;
+            }
+            None if !covered[i] && chars[i] == '/' && next == Some('*') => open = Some((i, i + 2)),
+            _ => (),
+        }
+
+        i += 1;
+    }
+
+    if let Some((start, _)) = open {
+        comments.push((start, chars.len()));
+    }
+
+    comments
+}
+
+/// Declared names, collected from the AST.
+#[derive(Default)]
+struct Names {
+    functions: HashSet<Symbol>, // Declared functions.
+    values: HashSet<Symbol>,    // Declared constants, variables and function arguments.
+}
+
+impl Names {
+    fn kind(&self, id: Symbol, called: bool) -> SemanticKind {
+        let function = self.functions.contains(&id) || builtin(id).is_some();
+
+        match (function, self.values.contains(&id)) {
+            (true, false) => SemanticKind::Function,
+            (true, true) if called => SemanticKind::Function,
+            _ => SemanticKind::Variable,
+        }
+    }
+
+    fn stmts(&mut self, stmts: &Stmts) {
+        stmts.iter().for_each(|s| self.stmt(s));
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        match &stmt.kind {
+            StmtKind::Assignment(a) => {
+                self.expr(&a.lhs);
+                self.expr(&a.rhs);
+            }
+            StmtKind::Block(b) => self.stmts(b),
+            StmtKind::Connect(c) | StmtKind::Disconnect(c) => {
+                c.stages.iter().flatten().for_each(|e| self.expr(e));
+                if let Some(capacity) = c.buffer.as_ref().and_then(|b| b.capacity.as_ref()) {
+                    self.expr(capacity);
+                }
+            }
+            StmtKind::ConstDecl(c) => {
+                self.values.insert(c.id);
+                self.expr(&c.value);
+            }
+            StmtKind::Defer(s) => self.stmt(s),
+            StmtKind::Expr(e) => self.expr(e),
+            StmtKind::FunctionDecl(f) => {
+                self.functions.insert(f.id);
+                self.values
+                    .extend(f.args.iter().flat_map(|a| a.0.iter()).map(|a| a.id));
+                self.stmt(&f.body);
+            }
+            StmtKind::GroupDecl(g) => {
+                self.values
+                    .extend(g.args.iter().flat_map(|a| a.0.iter()).map(|a| a.id));
+                self.stmt(&g.body);
+            }
+            StmtKind::EnumDecl(_) | StmtKind::NodeDecl(_) => (),
+            StmtKind::Print(p) => p.args.iter().for_each(|e| self.expr(e)),
+            StmtKind::Remove(r) => self.expr(&r.target),
+            StmtKind::Return(r) => self.expr(&r.expr),
+            StmtKind::UseDecl(_) => (),
+            StmtKind::VarDecl(v) => {
+                if let ExprKind::Var(var) = &v.id.kind {
+                    self.values.insert(var.id);
+                }
+                v.value.iter().for_each(|e| self.expr(e));
+            }
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match &expr.kind {
+            ExprKind::Binary(b) => {
+                self.expr(&b.lhs);
+                self.expr(&b.rhs);
+            }
+            ExprKind::Block(b) => self.stmt(&b.body),
+            ExprKind::Cast(x) => self.expr(&x.expr),
+            ExprKind::For(f) => {
+                self.values.insert(f.id);
+                self.expr(&f.expr);
+                self.expr(&f.body);
+            }
+            ExprKind::FuncCall(c) => c.args.iter().for_each(|e| self.expr(e)),
+            ExprKind::Group(e) | ExprKind::Propagate(e) | ExprKind::Try(e) => self.expr(e),
+            ExprKind::If(i) => {
+                self.expr(&i.expr);
+                self.expr(&i.body_then);
+                i.body_else.iter().for_each(|e| self.expr(e));
+            }
+            ExprKind::Index(x) => {
+                self.expr(&x.expr);
+                match &x.index {
+                    IndexKind::Element(e) => self.expr(e),
+                    IndexKind::Slice(_, start, end) => {
+                        start.iter().chain(end).for_each(|e| self.expr(e))
+                    }
+                }
+            }
+            ExprKind::Range(r) => {
+                self.expr(&r.start);
+                self.expr(&r.end);
+                r.step.iter().for_each(|e| self.expr(e));
+            }
+            ExprKind::Loop(l) => self.expr(&l.body),
+            ExprKind::Ref(r) => self.expr(&r.expr),
+            ExprKind::Time(t) => self.expr(&t.body),
+            ExprKind::Unary(u) => self.expr(&u.expr),
+            ExprKind::While(w) => {
+                self.expr(&w.expr);
+                self.expr(&w.body);
+            }
+            ExprKind::Empty() | ExprKind::Literal(_) | ExprKind::Var(_) => (),
+        }
+    }
+}
+
+#[cfg(test)]
+fn kinds(lines: &[&str]) -> Vec<(usize, String, SemanticKind)> {
+    let lines: Vec<_> = lines
+        .iter()
+        .map(|l| SourceLine {
+            line: l.to_string(),
+            number: None,
+        })
+        .collect();
+
+    semantic_tokens(&lines)
+        .into_iter()
+        .map(|t| {
+            let text = lines[t.line]
+                .line
+                .chars()
+                .skip(t.start)
+                .take(t.end - t.start);
+            (t.line, text.collect(), t.kind)
+        })
+        .collect()
+}
+
+#[test]
+fn comments_test() {
+    use SemanticKind::*;
+
+    assert_eq!(
+        kinds(&[
+            "/// Doc.",
+            "let s = \"// /* \"; /* a */ let /*/ b",
+            "c */ // d",
+            "/* e"
+        ]),
+        [
+            (0, "/// Doc.".to_owned(), Comment),
+            (1, "let".to_owned(), Keyword),
+            (1, "s".to_owned(), Variable),
+            (1, "\"// /* \"".to_owned(), String),
+            (1, "/* a */".to_owned(), Comment),
+            (1, "let".to_owned(), Keyword),
+            (1, "/*/ b".to_owned(), Comment),
+            (2, "c */".to_owned(), Comment),
+            (2, "// d".to_owned(), Comment),
+            (3, "/* e".to_owned(), Comment),
+        ]
+    );
+}
+
+#[test]
+fn identifiers_test() {
+    use SemanticKind::{Function, Property, Variable};
+
+    let identifiers = |lines: &[&str]| -> Vec<(String, SemanticKind)> {
+        kinds(lines)
+            .into_iter()
+            .filter(|(_, _, k)| matches!(k, Variable | Property | Function))
+            .map(|(_, text, kind)| (text, kind))
+            .collect()
+    };
+
+    // Functions used as value, shadowed names, built-in functions and members.
+    assert_eq!(
+        identifiers(&[
+            "fn f(g: Number) -> Number { g + f(g) }",
+            "fn g() {}",
+            "let h = f;",
+            "let n = node \"N\";",
+            "n.rate = sqrt(h(2));",
+        ]),
+        [
+            ("f".to_owned(), Function),
+            ("g".to_owned(), Variable),
+            ("g".to_owned(), Variable),
+            ("f".to_owned(), Function),
+            ("g".to_owned(), Variable),
+            ("g".to_owned(), Function),
+            ("h".to_owned(), Variable),
+            ("f".to_owned(), Function),
+            ("n".to_owned(), Variable),
+            ("n".to_owned(), Variable),
+            ("rate".to_owned(), Property),
+            ("sqrt".to_owned(), Function),
+            ("h".to_owned(), Variable),
+        ]
+    );
+
+    // Node declarations: the node kind is a type, ports are members.
+    assert_eq!(
+        kinds(&["node Gain { in x: Number; out y: Number; }"])
+            .into_iter()
+            .map(|(_, text, kind)| (text, kind))
+            .collect::<Vec<_>>(),
+        [
+            ("node".to_owned(), SemanticKind::Keyword),
+            ("Gain".to_owned(), SemanticKind::Type),
+            ("in".to_owned(), SemanticKind::Keyword),
+            ("x".to_owned(), Property),
+            ("Number".to_owned(), SemanticKind::Type),
+            ("out".to_owned(), SemanticKind::Keyword),
+            ("y".to_owned(), Property),
+            ("Number".to_owned(), SemanticKind::Type),
+        ]
+    );
+
+    // Remove statements, where 'remove' and 'force' are only keywords at the start of a statement.
+    assert_eq!(
+        kinds(&["remove force a; remove(b);"])
+            .into_iter()
+            .map(|(_, text, kind)| (text, kind))
+            .collect::<Vec<_>>(),
+        [
+            ("remove".to_owned(), SemanticKind::Keyword),
+            ("force".to_owned(), SemanticKind::Keyword),
+            ("a".to_owned(), Variable),
+            ("remove".to_owned(), Variable),
+            ("b".to_owned(), Variable),
+        ]
+    );
+
+    // Range steps, where 'step' is only a keyword following a range.
+    assert_eq!(
+        kinds(&["0..step step (step);"])
+            .into_iter()
+            .map(|(_, text, kind)| (text, kind))
+            .collect::<Vec<_>>(),
+        [
+            ("0".to_owned(), SemanticKind::Number),
+            ("step".to_owned(), Variable),
+            ("step".to_owned(), SemanticKind::Keyword),
+            ("step".to_owned(), Variable),
+        ]
+    );
+
+    // Casts, where 'as' is only a keyword followed by a type.
+    assert_eq!(
+        kinds(&["as as String;"])
+            .into_iter()
+            .map(|(_, text, kind)| (text, kind))
+            .collect::<Vec<_>>(),
+        [
+            ("as".to_owned(), Variable),
+            ("as".to_owned(), SemanticKind::Keyword),
+            ("String".to_owned(), SemanticKind::Type),
+        ]
+    );
+
+    // Connection options are members, overflow policies keywords.
+    assert_eq!(
+        kinds(&["a -> b [capacity: n, overflow: drop_oldest];"])
+            .into_iter()
+            .map(|(_, text, kind)| (text, kind))
+            .collect::<Vec<_>>(),
+        [
+            ("a".to_owned(), Variable),
+            ("b".to_owned(), Variable),
+            ("capacity".to_owned(), Property),
+            ("n".to_owned(), Variable),
+            ("overflow".to_owned(), Property),
+            ("drop_oldest".to_owned(), SemanticKind::Keyword),
+        ]
+    );
+
+    // Best effort without parse result.
+    assert_eq!(
+        identifiers(&["let x = f(y) +;", "fn g() {"]),
+        [
+            ("x".to_owned(), Variable),
+            ("f".to_owned(), Function),
+            ("y".to_owned(), Variable),
+            ("g".to_owned(), Function),
+        ]
+    );
+}
@@ -0,0 +1,145 @@
+use crate::ast::Overflow;
+use crate::value::Value;
+use std::collections::VecDeque;
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+/// Buffering policy of a connection: the maximum number of queued values (unbounded if `None`), and what happens to a
+///  value sent into a full buffer.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct BufferPolicy {
+    pub capacity: Option<usize>,
+    pub overflow: Overflow,
+}
+
+/// Outcome of sending a value into a connection buffer.
+#[derive(Debug, PartialEq)]
+pub enum Sent {
+    Queued,
+    Dropped(Value), // The value dropped to make room (the oldest or the sent value).
+    Blocked(Value), // The sent value, to send again once the sink took a value.
+}
+
+/// Queue of values sent over a connection, applying its buffering policy.
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::{ast::Overflow, buffer::*, value::Value};
+///
+/// let policy = BufferPolicy { capacity: Some(2), overflow: Overflow::DropOldest };
+/// let mut buffer = ConnectionBuffer::new(policy);
+///
+/// for i in 0..3 {
+///     buffer.send(Value::Number(i as f64));
+/// }
+///
+/// assert_eq!(buffer.receive(), Some(Value::Number(1.0)));
+/// assert_eq!(buffer.dropped(), 1);
+/// ```
+#[derive(Debug, Default)]
+pub struct ConnectionBuffer {
+    policy: BufferPolicy,
+    queue: VecDeque<Value>,
+    dropped: u64,
+}
+
+impl ConnectionBuffer {
+    /// Create an empty buffer with the given policy.
+    pub fn new(policy: BufferPolicy) -> Self {
+        Self {
+            policy,
+            ..Self::default()
+        }
+    }
+
+    /// Get the buffering policy.
+    pub fn policy(&self) -> BufferPolicy {
+        self.policy
+    }
+
+    /// Send a value into the buffer. If the buffer is full, the overflow policy decides which value is dropped, or
+    ///  whether the sender has to wait (block) until the sink takes a value.
+    pub fn send(&mut self, value: Value) -> Sent {
+        if self.policy.capacity.is_none_or(|c| self.queue.len() < c) {
+            self.queue.push_back(value);
+            return Sent::Queued;
+        }
+
+        match self.policy.overflow {
+            Overflow::Block => Sent::Blocked(value),
+            Overflow::DropNewest => {
+                self.dropped += 1;
+                Sent::Dropped(value)
+            }
+            Overflow::DropOldest => {
+                self.dropped += 1;
+                self.queue.push_back(value);
+                Sent::Dropped(self.queue.pop_front().expect("queued value"))
+            }
+        }
+    }
+
+    /// Take the oldest value from the buffer.
+    pub fn receive(&mut self) -> Option<Value> {
+        self.queue.pop_front()
+    }
+
+    /// Get the number of queued values.
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Check if there are no queued values.
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Get the number of values dropped because the buffer was full (a metric of the connection).
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+}
+
+#[test]
+fn buffer_test() {
+    let n = Value::Number;
+    let bounded = |overflow| {
+        ConnectionBuffer::new(BufferPolicy {
+            capacity: Some(1),
+            overflow,
+        })
+    };
+
+    let mut b = ConnectionBuffer::new(BufferPolicy::default());
+    for i in 0..100 {
+        assert_eq!(b.send(n(i as f64)), Sent::Queued);
+    }
+    assert_eq!((b.len(), b.dropped()), (100, 0));
+
+    let mut b = bounded(Overflow::Block);
+    assert_eq!(b.send(n(1.0)), Sent::Queued);
+    assert_eq!(b.send(n(2.0)), Sent::Blocked(n(2.0)));
+    assert_eq!(b.receive(), Some(n(1.0)));
+    assert_eq!(b.send(n(2.0)), Sent::Queued);
+    assert_eq!(b.dropped(), 0);
+
+    let mut b = bounded(Overflow::DropNewest);
+    b.send(n(1.0));
+    assert_eq!(b.send(n(2.0)), Sent::Dropped(n(2.0)));
+    assert_eq!((b.receive(), b.dropped()), (Some(n(1.0)), 1));
+
+    let mut b = bounded(Overflow::DropOldest);
+    b.send(n(1.0));
+    assert_eq!(b.send(n(2.0)), Sent::Dropped(n(1.0)));
+    assert_eq!((b.receive(), b.dropped()), (Some(n(2.0)), 1));
+    assert!(b.is_empty());
+
+    // A zero capacity buffer queues nothing.
+    let mut b = ConnectionBuffer::new(BufferPolicy {
+        capacity: Some(0),
+        overflow: Overflow::DropOldest,
+    });
+    assert_eq!(b.send(n(1.0)), Sent::Dropped(n(1.0)));
+}
@@ -0,0 +1,1196 @@
+use crate::ast::{NodeDecl, Overflow, PortDirection};
+use crate::buffer::BufferPolicy;
+use crate::json::{Json, JsonError};
+use crate::library;
+use thiserror::Error;
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+/// Name of the graph exchange format schema.
+pub const GRAPH_SCHEMA: &str = "nexus-graph";
+
+/// Version of the graph exchange format schema.
+///
+/// Bump this whenever the exchange format changes in an incompatible way.
+pub const GRAPH_SCHEMA_VERSION: usize = 1;
+
+/// Graph error representation.
+#[derive(Error, Debug, PartialEq)]
+pub enum GraphError {
+    #[error("invalid JSON ({0})")]
+    Json(#[from] JsonError),
+
+    #[error("not a Nexus graph document")]
+    InvalidSchema,
+
+    #[error("unsupported graph schema version {0} (supported: {GRAPH_SCHEMA_VERSION})")]
+    UnsupportedVersion(usize),
+
+    #[error("missing or invalid field '{0}'")]
+    InvalidField(&'static str),
+
+    #[error("unknown node ID {0}")]
+    UnknownNode(usize),
+
+    #[error("unknown group ID {0}")]
+    UnknownGroup(usize),
+
+    #[error("node kind '{0}' is already declared")]
+    DuplicateKind(String),
+
+    #[error("missing port for node '{0}', its kind declares ports")]
+    MissingPort(String),
+
+    #[error("node kind '{0}' has no port '{1}'")]
+    UnknownPort(String, String),
+
+    #[error("port '{0}.{1}' cannot be a connection {2}")]
+    WrongPortDirection(String, String, &'static str),
+
+    #[error("connected port types do not match ({0} -> {1})")]
+    PortTypeMismatch(String, String),
+
+    #[error("input port '{0}.{1}' is already connected")]
+    InputConnected(String, String),
+
+    #[error("no such connection")]
+    NotConnected,
+
+    #[error(
+        "node '{0}' still has {1} connection(s), remove it by force to also remove its connections"
+    )]
+    NodeConnected(String, usize),
+
+    #[error("group '{0}' is not empty, remove it by force to also remove its members")]
+    GroupNotEmpty(String),
+
+    #[error("nodes {0} form a cycle, so they cannot be scheduled")]
+    Cycle(String),
+}
+
+/// Node identifier (index into the node list of a graph).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(pub usize);
+
+/// Group identifier (index into the group list of a graph).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct GroupId(pub usize);
+
+/// Graph node.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Node {
+    pub name: String,
+    pub kind: String,
+    pub group: Option<GroupId>,
+}
+
+/// Graph group, containing nodes and (nested) groups.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Group {
+    pub name: String,
+    pub parent: Option<GroupId>,
+}
+
+/// Port of a node kind.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Port {
+    pub name: String,
+    pub direction: PortDirection,
+    pub typeid: String, // Type name, e.g. "Number".
+}
+
+/// Node kind, declaring the ports of all nodes of that kind (see [`Graph::declare_kind`]).
+#[derive(Clone, Debug, PartialEq)]
+pub struct NodeKind {
+    pub name: String,
+    pub ports: Vec<Port>,
+}
+
+impl Port {
+    /// Create an input port descriptor.
+    pub fn input(name: &str, typeid: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            direction: PortDirection::Input,
+            typeid: typeid.to_owned(),
+        }
+    }
+
+    /// Create an output port descriptor.
+    pub fn output(name: &str, typeid: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            direction: PortDirection::Output,
+            typeid: typeid.to_owned(),
+        }
+    }
+}
+
+impl NodeKind {
+    /// Get a port by name.
+    pub fn port(&self, name: &str) -> Option<&Port> {
+        self.ports.iter().find(|p| p.name == name)
+    }
+}
+
+impl From<&NodeDecl> for NodeKind {
+    fn from(decl: &NodeDecl) -> Self {
+        Self {
+            name: decl.id.to_string(),
+            ports: decl
+                .ports
+                .iter()
+                .map(|p| Port {
+                    name: p.id.to_string(),
+                    direction: p.direction,
+                    typeid: p.typeid.to_string(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Connection endpoint: a node with an optional port name.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Endpoint {
+    pub node: NodeId,
+    pub port: Option<String>,
+}
+
+/// Directed connection between two node endpoints.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Connection {
+    pub source: Endpoint,
+    pub sink: Endpoint,
+    pub buffer: BufferPolicy,
+}
+
+/// Node graph topology, as constructed by a Nexus program.
+///
+/// Connections between nodes of a declared kind (see [`Graph::declare_kind`]) are validated: the source must be an
+///  output port and the sink an input port of the same type. Nodes of undeclared kinds are connected unchecked.
+///
+/// An output port can fan out to any number of sinks, which all share the same value (values are immutable, so this
+///  is indistinguishable from copying). An input port of a declared kind accepts a single connection.
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::graph::{Endpoint, Graph};
+///
+/// let mut g = Graph::new();
+///
+/// let main = g.add_group("Main", None).unwrap();
+/// let source = g.add_node("source", "Reader", Some(main)).unwrap();
+/// let sink = g.add_node("sink", "Writer", Some(main)).unwrap();
+///
+/// g.connect(Endpoint { node: source, port: None }, Endpoint { node: sink, port: None }).unwrap();
+///
+/// assert_eq!(Graph::from_json(&g.to_json()).unwrap(), g);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Graph {
+    kinds: Vec<NodeKind>,
+    nodes: Vec<Node>,
+    groups: Vec<Group>,
+    connections: Vec<Connection>,
+}
+
+impl Graph {
+    /// Create a new, empty graph.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new, empty graph with the node kinds of the built-in node library declared (see [`crate::library`]).
+    pub fn with_library() -> Self {
+        Self {
+            kinds: library::kinds(),
+            ..Self::default()
+        }
+    }
+
+    /// Get all declared node kinds.
+    pub fn kinds(&self) -> &[NodeKind] {
+        &self.kinds
+    }
+
+    /// Get a declared node kind by name.
+    pub fn kind(&self, name: &str) -> Option<&NodeKind> {
+        self.kinds.iter().find(|k| k.name == name)
+    }
+
+    /// Get all nodes in the graph.
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
+    /// Get all groups in the graph.
+    pub fn groups(&self) -> &[Group] {
+        &self.groups
+    }
+
+    /// Get all connections in the graph.
+    pub fn connections(&self) -> &[Connection] {
+        &self.connections
+    }
+
+    /// Get a node by ID.
+    pub fn node(&self, id: NodeId) -> Option<&Node> {
+        self.nodes.get(id.0)
+    }
+
+    /// Get a group by ID.
+    pub fn group(&self, id: GroupId) -> Option<&Group> {
+        self.groups.get(id.0)
+    }
+
+    /// Get the nodes directly connected to the inputs of a node, in connection order (without duplicates).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nexus_rs::graph::*;
+    ///
+    /// let mut g = Graph::new();
+    /// let a = g.add_node("a", "Reader", None).unwrap();
+    /// let b = g.add_node("b", "Filter", None).unwrap();
+    /// let c = g.add_node("c", "Writer", None).unwrap();
+    /// let any = |node| Endpoint { node, port: None };
+    /// g.connect(any(a), any(b)).unwrap();
+    /// g.connect(any(b), any(c)).unwrap();
+    ///
+    /// assert_eq!(g.sources(b), [a]);
+    /// assert_eq!(g.sinks(b), [c]);
+    /// assert!(g.is_connected(a, b) && !g.is_connected(b, a) && !g.is_connected(a, c));
+    /// ```
+    pub fn sources(&self, id: NodeId) -> Vec<NodeId> {
+        self.neighbours(|c| (c.sink.node == id).then_some(c.source.node))
+    }
+
+    /// Get the nodes directly connected to the outputs of a node, in connection order (without duplicates).
+    pub fn sinks(&self, id: NodeId) -> Vec<NodeId> {
+        self.neighbours(|c| (c.source.node == id).then_some(c.sink.node))
+    }
+
+    /// Check if there is a (direct) connection from a source node to a sink node, on any ports.
+    pub fn is_connected(&self, source: NodeId, sink: NodeId) -> bool {
+        self.connections
+            .iter()
+            .any(|c| c.source.node == source && c.sink.node == sink)
+    }
+
+    /// Schedule the nodes for execution, in layers: every node is in a later layer than all of its sources.
+    ///
+    /// Nodes in the same layer have no data dependencies between them, so they can run concurrently. Within a layer,
+    ///  nodes are ordered by ID. A graph with cycles cannot be scheduled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nexus_rs::graph::*;
+    ///
+    /// let mut g = Graph::new();
+    /// let a = g.add_node("a", "Reader", None).unwrap();
+    /// let b = g.add_node("b", "Reader", None).unwrap();
+    /// let c = g.add_node("c", "Mixer", None).unwrap();
+    /// let any = |node| Endpoint { node, port: None };
+    /// g.connect(any(a), any(c)).unwrap();
+    /// g.connect(any(b), any(c)).unwrap();
+    ///
+    /// assert_eq!(g.schedule(), Ok(vec![vec![a, b], vec![c]]));
+    ///
+    /// g.connect(any(c), any(a)).unwrap();
+    /// assert_eq!(g.schedule(), Err(GraphError::Cycle("'a', 'c'".to_owned())));
+    /// ```
+    pub fn schedule(&self) -> Result<Vec<Vec<NodeId>>, GraphError> {
+        let mut pending: Vec<_> = (0..self.nodes.len()).map(NodeId).collect();
+        let mut layers: Vec<Vec<NodeId>> = Vec::new();
+
+        while !pending.is_empty() {
+            let (ready, blocked): (Vec<NodeId>, Vec<NodeId>) = pending.iter().partition(|&&n| {
+                self.sources(n)
+                    .iter()
+                    .all(|s| *s != n && !pending.contains(s))
+            });
+
+            if ready.is_empty() {
+                let names: Vec<_> = blocked
+                    .iter()
+                    .map(|n| format!("'{}'", self.nodes[n.0].name))
+                    .collect();
+                return Err(GraphError::Cycle(names.join(", ")));
+            }
+
+            layers.push(ready);
+            pending = blocked;
+        }
+
+        Ok(layers)
+    }
+
+    /// Declare a node kind, so connections to its nodes are validated.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nexus_rs::{ast::StmtKind, front::parse_source, graph::*};
+    ///
+    /// let ast = parse_source("node Gain { in x: Number; out y: Number; }").unwrap();
+    /// let StmtKind::NodeDecl(decl) = &ast[0].kind else { unreachable!() };
+    ///
+    /// let mut g = Graph::new();
+    /// g.declare_kind(NodeKind::from(&**decl)).unwrap();
+    ///
+    /// let a = g.add_node("a", "Gain", None).unwrap();
+    /// let b = g.add_node("b", "Gain", None).unwrap();
+    /// let port = |node, name: &str| Endpoint { node, port: Some(name.to_owned()) };
+    ///
+    /// assert!(g.connect(port(a, "y"), port(b, "x")).is_ok());
+    /// assert_eq!(
+    ///     g.connect(port(a, "x"), port(b, "x")),
+    ///     Err(GraphError::WrongPortDirection("Gain".to_owned(), "x".to_owned(), "source"))
+    /// );
+    /// ```
+    pub fn declare_kind(&mut self, kind: NodeKind) -> Result<(), GraphError> {
+        if self.kind(&kind.name).is_some() {
+            return Err(GraphError::DuplicateKind(kind.name));
+        }
+
+        self.kinds.push(kind);
+
+        Ok(())
+    }
+
+    /// Replace all declared node kinds (without validating the existing connections).
+    pub(crate) fn replace_kinds(&mut self, kinds: Vec<NodeKind>) {
+        self.kinds = kinds;
+    }
+
+    /// Add a group, optionally nested in a parent group.
+    pub fn add_group(
+        &mut self,
+        name: &str,
+        parent: Option<GroupId>,
+    ) -> Result<GroupId, GraphError> {
+        self.check_group(parent)?;
+
+        self.groups.push(Group {
+            name: name.to_owned(),
+            parent,
+        });
+
+        Ok(GroupId(self.groups.len() - 1))
+    }
+
+    /// Add a node of a specific kind, optionally as member of a group.
+    pub fn add_node(
+        &mut self,
+        name: &str,
+        kind: &str,
+        group: Option<GroupId>,
+    ) -> Result<NodeId, GraphError> {
+        self.check_group(group)?;
+
+        self.nodes.push(Node {
+            name: name.to_owned(),
+            kind: kind.to_owned(),
+            group,
+        });
+
+        Ok(NodeId(self.nodes.len() - 1))
+    }
+
+    /// Add a group of `count` nodes of a kind wired in sequence, as built by a parametric group declaration like
+    ///  `group Chain(n: Number) { .. }`. Nodes are named by their position in the chain (e.g. `chain.0`), and the
+    ///  `link` ports connect each node (source) to the next (sink). The graph is left unchanged on failure.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nexus_rs::graph::*;
+    ///
+    /// let mut g = Graph::new();
+    /// g.declare_kind(NodeKind {
+    ///     name: "Gain".to_owned(),
+    ///     ports: vec![Port::input("x", "Number"), Port::output("y", "Number")],
+    /// })
+    /// .unwrap();
+    ///
+    /// let chain = g.add_chain("chain", "Gain", 3, (Some("y"), Some("x")), None).unwrap();
+    ///
+    /// assert_eq!(g.group(chain).unwrap().name, "chain");
+    /// assert_eq!(g.nodes().iter().map(|n| n.name.as_str()).collect::<Vec<_>>(), ["0", "1", "2"]);
+    /// assert_eq!(g.sinks(NodeId(0)), [NodeId(1)]);
+    /// assert!(g.add_chain("bad", "Gain", 2, (Some("x"), Some("x")), None).is_err());
+    /// assert_eq!(g.groups().len(), 1);
+    /// ```
+    pub fn add_chain(
+        &mut self,
+        name: &str,
+        kind: &str,
+        count: usize,
+        link: (Option<&str>, Option<&str>),
+        parent: Option<GroupId>,
+    ) -> Result<GroupId, GraphError> {
+        let mut graph = self.clone();
+        let group = graph.add_group(name, parent)?;
+        let endpoint = |node, port: Option<&str>| Endpoint {
+            node,
+            port: port.map(str::to_owned),
+        };
+
+        let mut prev = None;
+        for i in 0..count {
+            let node = graph.add_node(&i.to_string(), kind, Some(group))?;
+
+            if let Some(prev) = prev {
+                graph.connect(endpoint(prev, link.0), endpoint(node, link.1))?;
+            }
+
+            prev = Some(node);
+        }
+
+        *self = graph;
+
+        Ok(group)
+    }
+
+    /// Connect a source endpoint to a sink endpoint, with an unbounded buffer.
+    pub fn connect(&mut self, source: Endpoint, sink: Endpoint) -> Result<(), GraphError> {
+        self.connect_buffered(source, sink, BufferPolicy::default())
+    }
+
+    /// Connect a source endpoint to a sink endpoint, with the given buffering policy.
+    pub fn connect_buffered(
+        &mut self,
+        source: Endpoint,
+        sink: Endpoint,
+        buffer: BufferPolicy,
+    ) -> Result<(), GraphError> {
+        self.check_node(source.node)?;
+        self.check_node(sink.node)?;
+
+        let source_port = self.check_port(&source, PortDirection::Output)?;
+        let sink_port = self.check_port(&sink, PortDirection::Input)?;
+
+        if let (Some(s), Some(t)) = (source_port, sink_port) {
+            if s.typeid != t.typeid {
+                return Err(GraphError::PortTypeMismatch(
+                    s.typeid.clone(),
+                    t.typeid.clone(),
+                ));
+            }
+        }
+
+        if sink_port.is_some() && self.connections.iter().any(|c| c.sink == sink) {
+            return Err(GraphError::InputConnected(
+                self.nodes[sink.node.0].name.clone(),
+                sink.port.unwrap_or_default(),
+            ));
+        }
+
+        self.connections.push(Connection {
+            source,
+            sink,
+            buffer,
+        });
+
+        Ok(())
+    }
+
+    /// Disconnect a source endpoint from a sink endpoint.
+    pub fn disconnect(&mut self, source: &Endpoint, sink: &Endpoint) -> Result<(), GraphError> {
+        let index = self
+            .connections
+            .iter()
+            .position(|c| c.source == *source && c.sink == *sink)
+            .ok_or(GraphError::NotConnected)?;
+
+        self.connections.remove(index);
+
+        Ok(())
+    }
+
+    /// Remove a node. A node that still has connections is only removed by force, which also removes its connections.
+    ///
+    /// Like [`Vec::remove`], the IDs of the nodes following the removed node shift down by one.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nexus_rs::graph::*;
+    ///
+    /// let mut g = Graph::new();
+    /// let a = g.add_node("a", "Reader", None).unwrap();
+    /// let b = g.add_node("b", "Writer", None).unwrap();
+    /// g.connect(Endpoint { node: a, port: None }, Endpoint { node: b, port: None }).unwrap();
+    ///
+    /// assert_eq!(g.remove_node(a, false), Err(GraphError::NodeConnected("a".to_owned(), 1)));
+    /// assert!(g.remove_node(a, true).is_ok());
+    /// assert_eq!(g.node(NodeId(0)).map(|n| n.name.as_str()), Some("b"));
+    /// assert!(g.connections().is_empty());
+    /// ```
+    pub fn remove_node(&mut self, id: NodeId, force: bool) -> Result<Node, GraphError> {
+        self.check_node(id)?;
+
+        let connected = |c: &Connection| c.source.node == id || c.sink.node == id;
+        let count = self.connections.iter().filter(|c| connected(c)).count();
+        if count > 0 && !force {
+            return Err(GraphError::NodeConnected(
+                self.nodes[id.0].name.clone(),
+                count,
+            ));
+        }
+
+        self.connections.retain(|c| !connected(c));
+        for c in &mut self.connections {
+            for endpoint in [&mut c.source, &mut c.sink] {
+                if endpoint.node > id {
+                    endpoint.node.0 -= 1;
+                }
+            }
+        }
+
+        Ok(self.nodes.remove(id.0))
+    }
+
+    /// Remove a group. A group that still has members (nodes or groups) is only removed by force, which also removes
+    ///  its members (recursively) and their connections.
+    ///
+    /// Like [`Vec::remove`], the IDs of the groups (and nodes) following the removed ones shift down.
+    pub fn remove_group(&mut self, id: GroupId, force: bool) -> Result<Group, GraphError> {
+        self.check_group(Some(id))?;
+
+        let mut groups = vec![id];
+        let mut i = 0;
+        while i < groups.len() {
+            let parent = Some(groups[i]);
+            groups.extend(
+                (0..self.groups.len())
+                    .map(GroupId)
+                    .filter(|g| self.groups[g.0].parent == parent),
+            );
+            i += 1;
+        }
+
+        let nodes: Vec<_> = (0..self.nodes.len())
+            .map(NodeId)
+            .filter(|n| self.nodes[n.0].group.is_some_and(|g| groups.contains(&g)))
+            .collect();
+
+        if (groups.len() > 1 || !nodes.is_empty()) && !force {
+            return Err(GraphError::GroupNotEmpty(self.groups[id.0].name.clone()));
+        }
+
+        // Remove from the back, so the IDs still to remove are not shifted.
+        for node in nodes.into_iter().rev() {
+            self.remove_node(node, true)?;
+        }
+
+        groups.sort();
+        let mut removed = None;
+        for group in groups.into_iter().rev() {
+            let shift = |g: &mut Option<GroupId>| {
+                if let Some(g) = g.as_mut().filter(|g| **g > group) {
+                    g.0 -= 1;
+                }
+            };
+            self.nodes.iter_mut().for_each(|n| shift(&mut n.group));
+            self.groups.iter_mut().for_each(|g| shift(&mut g.parent));
+
+            removed = Some(self.groups.remove(group.0));
+        }
+
+        Ok(removed.expect("removed group"))
+    }
+
+    /// Serialize the graph into (pretty-printed) JSON.
+    ///
+    /// The output is deterministic: identical graphs always produce identical output, so it can be diffed.
+    pub fn to_json(&self) -> String {
+        let endpoint = |e: &Endpoint| {
+            Json::Object(vec![
+                ("node".to_owned(), e.node.0.into()),
+                ("port".to_owned(), e.port.clone().into()),
+            ])
+        };
+
+        Json::Object(vec![
+            ("schema".to_owned(), GRAPH_SCHEMA.into()),
+            ("version".to_owned(), GRAPH_SCHEMA_VERSION.into()),
+            (
+                "kinds".to_owned(),
+                Json::Array(
+                    self.kinds
+                        .iter()
+                        .map(|k| {
+                            Json::Object(vec![
+                                ("name".to_owned(), k.name.as_str().into()),
+                                (
+                                    "ports".to_owned(),
+                                    Json::Array(
+                                        k.ports
+                                            .iter()
+                                            .map(|p| {
+                                                Json::Object(vec![
+                                                    ("name".to_owned(), p.name.as_str().into()),
+                                                    (
+                                                        "direction".to_owned(),
+                                                        p.direction.to_string().into(),
+                                                    ),
+                                                    ("type".to_owned(), p.typeid.as_str().into()),
+                                                ])
+                                            })
+                                            .collect(),
+                                    ),
+                                ),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+            (
+                "groups".to_owned(),
+                Json::Array(
+                    self.groups
+                        .iter()
+                        .enumerate()
+                        .map(|(id, g)| {
+                            Json::Object(vec![
+                                ("id".to_owned(), id.into()),
+                                ("name".to_owned(), g.name.as_str().into()),
+                                ("parent".to_owned(), g.parent.map(|p| p.0).into()),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+            (
+                "nodes".to_owned(),
+                Json::Array(
+                    self.nodes
+                        .iter()
+                        .enumerate()
+                        .map(|(id, n)| {
+                            Json::Object(vec![
+                                ("id".to_owned(), id.into()),
+                                ("name".to_owned(), n.name.as_str().into()),
+                                ("kind".to_owned(), n.kind.as_str().into()),
+                                ("group".to_owned(), n.group.map(|g| g.0).into()),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+            (
+                "connections".to_owned(),
+                Json::Array(
+                    self.connections
+                        .iter()
+                        .map(|c| {
+                            let mut fields = vec![
+                                ("source".to_owned(), endpoint(&c.source)),
+                                ("sink".to_owned(), endpoint(&c.sink)),
+                            ];
+                            // The default (unbounded, blocking) policy is omitted.
+                            if let Some(capacity) = c.buffer.capacity {
+                                fields.push(("capacity".to_owned(), capacity.into()));
+                            }
+                            if c.buffer.overflow != Overflow::default() {
+                                fields.push((
+                                    "overflow".to_owned(),
+                                    c.buffer.overflow.to_string().into(),
+                                ));
+                            }
+                            Json::Object(fields)
+                        })
+                        .collect(),
+                ),
+            ),
+        ])
+        .pretty()
+    }
+
+    /// Deserialize a graph from JSON, as produced by [Graph::to_json].
+    pub fn from_json(input: &str) -> Result<Self, GraphError> {
+        let json = Json::parse(input)?;
+
+        if json.get("schema").and_then(Json::as_str) != Some(GRAPH_SCHEMA) {
+            return Err(GraphError::InvalidSchema);
+        }
+
+        match json.get("version").and_then(Json::as_usize) {
+            Some(GRAPH_SCHEMA_VERSION) => (),
+            Some(v) => return Err(GraphError::UnsupportedVersion(v)),
+            None => return Err(GraphError::InvalidField("version")),
+        }
+
+        let mut graph = Graph::new();
+
+        // Node kinds were added later on, and are optional for compatibility.
+        for k in json
+            .get("kinds")
+            .and_then(Json::as_array)
+            .into_iter()
+            .flatten()
+        {
+            let mut ports = Vec::new();
+            for p in array_field(k, "ports")? {
+                ports.push(Port {
+                    name: str_field(p, "name")?.to_owned(),
+                    direction: match str_field(p, "direction")? {
+                        "in" => PortDirection::Input,
+                        "out" => PortDirection::Output,
+                        _ => return Err(GraphError::InvalidField("direction")),
+                    },
+                    typeid: str_field(p, "type")?.to_owned(),
+                });
+            }
+
+            graph.declare_kind(NodeKind {
+                name: str_field(k, "name")?.to_owned(),
+                ports,
+            })?;
+        }
+
+        for (id, g) in array_field(&json, "groups")?.iter().enumerate() {
+            check_id(g, id, "groups")?;
+            graph.add_group(
+                str_field(g, "name")?,
+                optional_usize_field(g, "parent")?.map(GroupId),
+            )?;
+        }
+
+        for (id, n) in array_field(&json, "nodes")?.iter().enumerate() {
+            check_id(n, id, "nodes")?;
+            graph.add_node(
+                str_field(n, "name")?,
+                str_field(n, "kind")?,
+                optional_usize_field(n, "group")?.map(GroupId),
+            )?;
+        }
+
+        for c in array_field(&json, "connections")? {
+            let endpoint = |field: &'static str| -> Result<Endpoint, GraphError> {
+                let e = c.get(field).ok_or(GraphError::InvalidField(field))?;
+                Ok(Endpoint {
+                    node: NodeId(
+                        e.get("node")
+                            .and_then(Json::as_usize)
+                            .ok_or(GraphError::InvalidField("node"))?,
+                    ),
+                    port: match e.get("port") {
+                        None | Some(Json::Null) => None,
+                        Some(Json::String(p)) => Some(p.clone()),
+                        Some(_) => return Err(GraphError::InvalidField("port")),
+                    },
+                })
+            };
+
+            let buffer = BufferPolicy {
+                capacity: optional_usize_field(c, "capacity")?,
+                overflow: match c.get("overflow") {
+                    None | Some(Json::Null) => Overflow::default(),
+                    Some(o) => Overflow::ALL
+                        .into_iter()
+                        .find(|p| o.as_str() == Some(&p.to_string()))
+                        .ok_or(GraphError::InvalidField("overflow"))?,
+                },
+            };
+
+            graph.connect_buffered(endpoint("source")?, endpoint("sink")?, buffer)?;
+        }
+
+        Ok(graph)
+    }
+
+    fn neighbours(&self, select: impl Fn(&Connection) -> Option<NodeId>) -> Vec<NodeId> {
+        let mut nodes = Vec::new();
+        for node in self.connections.iter().filter_map(select) {
+            if !nodes.contains(&node) {
+                nodes.push(node);
+            }
+        }
+        nodes
+    }
+
+    fn check_node(&self, id: NodeId) -> Result<(), GraphError> {
+        if id.0 < self.nodes.len() {
+            Ok(())
+        } else {
+            Err(GraphError::UnknownNode(id.0))
+        }
+    }
+
+    /// Check a connection endpoint of a node of a declared kind, returning its port (if declared).
+    fn check_port(
+        &self,
+        endpoint: &Endpoint,
+        direction: PortDirection,
+    ) -> Result<Option<&Port>, GraphError> {
+        let node = &self.nodes[endpoint.node.0];
+        let Some(kind) = self.kind(&node.kind) else {
+            return Ok(None);
+        };
+
+        let Some(name) = &endpoint.port else {
+            return Err(GraphError::MissingPort(node.name.clone()));
+        };
+
+        let port = kind
+            .port(name)
+            .ok_or_else(|| GraphError::UnknownPort(kind.name.clone(), name.clone()))?;
+
+        if port.direction != direction {
+            return Err(GraphError::WrongPortDirection(
+                kind.name.clone(),
+                name.clone(),
+                match direction {
+                    PortDirection::Input => "sink",
+                    PortDirection::Output => "source",
+                },
+            ));
+        }
+
+        Ok(Some(port))
+    }
+
+    fn check_group(&self, id: Option<GroupId>) -> Result<(), GraphError> {
+        match id {
+            Some(GroupId(g)) if g >= self.groups.len() => Err(GraphError::UnknownGroup(g)),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn array_field<'a>(json: &'a Json, field: &'static str) -> Result<&'a Vec<Json>, GraphError> {
+    json.get(field)
+        .and_then(Json::as_array)
+        .ok_or(GraphError::InvalidField(field))
+}
+
+fn str_field<'a>(json: &'a Json, field: &'static str) -> Result<&'a str, GraphError> {
+    json.get(field)
+        .and_then(Json::as_str)
+        .ok_or(GraphError::InvalidField(field))
+}
+
+fn optional_usize_field(json: &Json, field: &'static str) -> Result<Option<usize>, GraphError> {
+    match json.get(field) {
+        None | Some(Json::Null) => Ok(None),
+        Some(v) => v
+            .as_usize()
+            .map(Some)
+            .ok_or(GraphError::InvalidField(field)),
+    }
+}
+
+/// IDs are implicit (list indices), but are stored for readability; make sure they are consistent.
+fn check_id(json: &Json, expected: usize, field: &'static str) -> Result<(), GraphError> {
+    match json.get("id").and_then(Json::as_usize) {
+        Some(id) if id == expected => Ok(()),
+        _ => Err(GraphError::InvalidField(field)),
+    }
+}
+
+#[cfg(test)]
+fn test_graph() -> Graph {
+    let mut g = Graph::new();
+
+    g.declare_kind(NodeKind {
+        name: "Reader".to_owned(),
+        ports: vec![Port {
+            name: "Output".to_owned(),
+            direction: PortDirection::Output,
+            typeid: "String".to_owned(),
+        }],
+    })
+    .unwrap();
+
+    let app = g.add_group("app", None).unwrap();
+    let sys = g.add_group("Sys0", Some(app)).unwrap();
+    let source = g.add_node("source", "Reader", Some(sys)).unwrap();
+    let sink = g.add_node("sink", "Writer", Some(sys)).unwrap();
+    let log = g.add_node("log", "Logger", None).unwrap();
+
+    g.connect(
+        Endpoint {
+            node: source,
+            port: Some("Output".to_owned()),
+        },
+        Endpoint {
+            node: sink,
+            port: Some("Input".to_owned()),
+        },
+    )
+    .unwrap();
+    g.connect(
+        Endpoint {
+            node: sink,
+            port: None,
+        },
+        Endpoint {
+            node: log,
+            port: None,
+        },
+    )
+    .unwrap();
+
+    g
+}
+
+#[test]
+fn json_roundtrip_test() {
+    let g = test_graph();
+    let json = g.to_json();
+
+    assert_eq!(Graph::from_json(&json).unwrap(), g);
+    assert_eq!(Graph::from_json(&json).unwrap().to_json(), json);
+    assert_eq!(
+        Graph::from_json(&Graph::new().to_json()).unwrap(),
+        Graph::new()
+    );
+
+    // Buffering policies.
+    let mut g = test_graph();
+    let any = |node| Endpoint { node, port: None };
+    let buffer = BufferPolicy {
+        capacity: Some(8),
+        overflow: Overflow::DropOldest,
+    };
+    g.connect_buffered(any(NodeId(1)), any(NodeId(2)), buffer)
+        .unwrap();
+    let json = g.to_json();
+    assert!(json.contains(r#""capacity": 8"#) && json.contains(r#""overflow": "drop_oldest""#));
+    assert_eq!(Graph::from_json(&json).unwrap(), g);
+    assert_eq!(
+        Graph::from_json(&json.replace("drop_oldest", "drop")),
+        Err(GraphError::InvalidField("overflow"))
+    );
+}
+
+#[test]
+fn json_schema_test() {
+    let json = Json::parse(&test_graph().to_json()).unwrap();
+
+    assert_eq!(
+        json.get("schema").and_then(Json::as_str),
+        Some(GRAPH_SCHEMA)
+    );
+    assert_eq!(
+        json.get("version").and_then(Json::as_usize),
+        Some(GRAPH_SCHEMA_VERSION)
+    );
+    assert_eq!(
+        json.get("nodes").and_then(Json::as_array).map(Vec::len),
+        Some(3)
+    );
+}
+
+#[test]
+fn json_error_test() {
+    assert_eq!(Graph::from_json("{}"), Err(GraphError::InvalidSchema));
+    assert_eq!(
+        Graph::from_json(r#"{"schema": "nexus-graph", "version": 99}"#),
+        Err(GraphError::UnsupportedVersion(99))
+    );
+    assert_eq!(
+        Graph::from_json(r#"{"schema": "nexus-graph", "version": 1, "groups": []}"#),
+        Err(GraphError::InvalidField("nodes"))
+    );
+    assert_eq!(
+        Graph::from_json(
+            r#"{"schema": "nexus-graph", "version": 1, "groups": [], "nodes": [],
+                "connections": [{"source": {"node": 0}, "sink": {"node": 1}}]}"#
+        ),
+        Err(GraphError::UnknownNode(0))
+    );
+    assert!(matches!(Graph::from_json("{"), Err(GraphError::Json(_))));
+}
+
+#[test]
+fn port_test() {
+    let mut g = Graph::new();
+
+    let port = |name: &str, direction, typeid: &str| Port {
+        name: name.to_owned(),
+        direction,
+        typeid: typeid.to_owned(),
+    };
+    let gain = NodeKind {
+        name: "Gain".to_owned(),
+        ports: vec![
+            port("x", PortDirection::Input, "Number"),
+            port("y", PortDirection::Output, "Number"),
+            port("label", PortDirection::Output, "String"),
+        ],
+    };
+
+    g.declare_kind(gain.clone()).unwrap();
+    assert_eq!(
+        g.declare_kind(gain),
+        Err(GraphError::DuplicateKind("Gain".to_owned()))
+    );
+
+    let a = g.add_node("a", "Gain", None).unwrap();
+    let b = g.add_node("b", "Gain", None).unwrap();
+    let log = g.add_node("log", "Logger", None).unwrap();
+    let endpoint = |node, port: Option<&str>| Endpoint {
+        node,
+        port: port.map(str::to_owned),
+    };
+
+    assert_eq!(
+        g.connect(endpoint(a, Some("y")), endpoint(b, Some("x"))),
+        Ok(())
+    );
+    assert_eq!(
+        g.connect(endpoint(a, Some("y")), endpoint(b, Some("y"))),
+        Err(GraphError::WrongPortDirection(
+            "Gain".to_owned(),
+            "y".to_owned(),
+            "sink"
+        ))
+    );
+    assert_eq!(
+        g.connect(endpoint(a, Some("z")), endpoint(b, Some("x"))),
+        Err(GraphError::UnknownPort("Gain".to_owned(), "z".to_owned()))
+    );
+    assert_eq!(
+        g.connect(endpoint(a, None), endpoint(b, Some("x"))),
+        Err(GraphError::MissingPort("a".to_owned()))
+    );
+    assert_eq!(
+        g.connect(endpoint(a, Some("label")), endpoint(b, Some("x"))),
+        Err(GraphError::PortTypeMismatch(
+            "String".to_owned(),
+            "Number".to_owned()
+        ))
+    );
+
+    // Outputs fan out, inputs accept a single connection.
+    let c = g.add_node("c", "Gain", None).unwrap();
+    assert_eq!(
+        g.connect(endpoint(a, Some("y")), endpoint(c, Some("x"))),
+        Ok(())
+    );
+    assert_eq!(
+        g.connect(endpoint(c, Some("y")), endpoint(b, Some("x"))),
+        Err(GraphError::InputConnected("b".to_owned(), "x".to_owned()))
+    );
+
+    // Nodes of undeclared kinds are not checked.
+    assert_eq!(
+        g.connect(endpoint(a, Some("label")), endpoint(log, None)),
+        Ok(())
+    );
+    assert_eq!(
+        g.connect(endpoint(b, Some("label")), endpoint(log, None)),
+        Ok(())
+    );
+    assert_eq!(g.connections().len(), 4);
+}
+
+#[test]
+fn mutation_test() {
+    let mut g = test_graph();
+    let endpoint = |node, port: Option<&str>| Endpoint {
+        node: NodeId(node),
+        port: port.map(str::to_owned),
+    };
+
+    // Nodes 'source' (0) and 'sink' (1) in group 'Sys0' (1) in group 'app' (0), 'log' (2) without group.
+    assert_eq!(
+        g.disconnect(&endpoint(1, None), &endpoint(0, None)),
+        Err(GraphError::NotConnected)
+    );
+    assert_eq!(g.disconnect(&endpoint(1, None), &endpoint(2, None)), Ok(()));
+    assert_eq!(g.connections().len(), 1);
+
+    assert_eq!(
+        g.remove_node(NodeId(1), false),
+        Err(GraphError::NodeConnected("sink".to_owned(), 1))
+    );
+    assert_eq!(
+        g.remove_node(NodeId(5), false),
+        Err(GraphError::UnknownNode(5))
+    );
+
+    assert_eq!(
+        g.remove_group(GroupId(0), false),
+        Err(GraphError::GroupNotEmpty("app".to_owned()))
+    );
+    assert_eq!(
+        g.remove_group(GroupId(0), true).map(|g| g.name),
+        Ok("app".to_owned())
+    );
+    assert!(g.groups().is_empty());
+    assert!(g.connections().is_empty());
+    assert_eq!(
+        g.nodes()
+            .iter()
+            .map(|n| n.name.as_str())
+            .collect::<Vec<_>>(),
+        ["log"]
+    );
+
+    // IDs shift down.
+    let mut g = test_graph();
+    g.remove_node(NodeId(0), true).unwrap();
+    assert_eq!(g.connections()[0].source.node, NodeId(0));
+    assert_eq!(g.connections()[0].sink.node, NodeId(1));
+    g.remove_group(GroupId(1), true).unwrap();
+    assert_eq!(g.groups().len(), 1);
+    assert_eq!(g.nodes().len(), 1);
+}
+
+#[test]
+fn topology_test() {
+    let mut g = test_graph();
+    let (source, sink, log) = (NodeId(0), NodeId(1), NodeId(2));
+
+    assert_eq!(g.sources(source), []);
+    assert_eq!(g.sinks(source), [sink]);
+    assert_eq!(g.sources(log), [sink]);
+    assert!(g.is_connected(source, sink));
+    assert!(!g.is_connected(sink, source));
+    assert!(!g.is_connected(source, log));
+
+    // Multiple connections between the same nodes are reported once.
+    let any = |node| Endpoint { node, port: None };
+    g.connect(any(sink), any(log)).unwrap();
+    let output = Endpoint {
+        node: source,
+        port: Some("Output".to_owned()),
+    };
+    g.connect(output, any(log)).unwrap();
+    assert_eq!(g.sources(log), [sink, source]);
+    assert_eq!(g.sinks(sink), [log]);
+
+    g.remove_node(sink, true).unwrap();
+    assert_eq!(g.sources(NodeId(1)), [source]);
+    assert!(g.sinks(NodeId(1)).is_empty());
+}
+
+#[test]
+fn schedule_test() {
+    let mut g = test_graph();
+    let (source, sink, log) = (NodeId(0), NodeId(1), NodeId(2));
+
+    assert_eq!(g.schedule(), Ok(vec![vec![source], vec![sink], vec![log]]));
+    assert_eq!(Graph::new().schedule(), Ok(vec![]));
+
+    // Unconnected nodes are scheduled first.
+    let idle = g.add_node("idle", "Logger", None).unwrap();
+    assert_eq!(
+        g.schedule(),
+        Ok(vec![vec![source, idle], vec![sink], vec![log]])
+    );
+
+    // Only the nodes on (or after) a cycle are reported.
+    let any = |node| Endpoint { node, port: None };
+    g.connect(any(log), any(log)).unwrap();
+    assert_eq!(g.schedule(), Err(GraphError::Cycle("'log'".to_owned())));
+}
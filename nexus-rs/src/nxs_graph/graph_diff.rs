@@ -0,0 +1,514 @@
+use crate::buffer::BufferPolicy;
+use crate::graph::{Connection, Endpoint, Graph, GraphError, GroupId, Node, NodeId};
+use std::fmt;
+
+#[cfg(test)]
+use crate::graph::{NodeKind, Port};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+/// Change between two graphs, with nodes and groups identified by their path (e.g. `sys.a`).
+#[derive(Debug, PartialEq)]
+pub enum GraphChange {
+    RemovedGroup(String),                 // Group path.
+    AddedGroup(String),                   // Group path.
+    RemovedNode(String, String),          // Node path, kind.
+    AddedNode(String, String),            // Node path, kind.
+    ReplacedNode(String, String, String), // Node path, old kind, new kind (equal if its declaration changed).
+    Disconnected(String),                 // Connection label, e.g. `a.x -> b.y`.
+    Connected(String),                    // Connection label, e.g. `a.x -> b.y`.
+}
+
+impl fmt::Display for GraphChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GraphChange::RemovedGroup(path) => write!(f, "- group {path}"),
+            GraphChange::AddedGroup(path) => write!(f, "+ group {path}"),
+            GraphChange::RemovedNode(path, kind) => write!(f, "- node {path} ({kind})"),
+            GraphChange::AddedNode(path, kind) => write!(f, "+ node {path} ({kind})"),
+            GraphChange::ReplacedNode(path, old, new) if old == new => {
+                write!(f, "~ node {path} ({old}, ports changed)")
+            }
+            GraphChange::ReplacedNode(path, old, new) => {
+                write!(f, "~ node {path} ({old} -> {new})")
+            }
+            GraphChange::Disconnected(label) => write!(f, "- {label}"),
+            GraphChange::Connected(label) => write!(f, "+ {label}"),
+        }
+    }
+}
+
+/// Compute the changes to turn one graph into another.
+///
+/// Nodes and groups are identified by their path, so their IDs (and order) do not matter. A node is replaced if its
+///  kind, or the declaration of its kind, changed. Changes are listed in the order they are applied by [`reload`]:
+///  disconnections, removals, additions and connections.
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::{graph::*, graph_diff::*};
+///
+/// let mut old = Graph::new();
+/// let a = old.add_node("a", "Reader", None).unwrap();
+/// let b = old.add_node("b", "Writer", None).unwrap();
+/// old.connect(Endpoint { node: a, port: None }, Endpoint { node: b, port: None }).unwrap();
+///
+/// let mut new = Graph::new();
+/// new.add_node("b", "Writer", None).unwrap();
+/// new.add_node("a", "Parser", None).unwrap();
+///
+/// let changes: Vec<_> = diff(&old, &new).iter().map(|c| c.to_string()).collect();
+///
+/// assert_eq!(changes, ["- a -> b", "~ node a (Reader -> Parser)"]);
+/// ```
+pub fn diff(old: &Graph, new: &Graph) -> Vec<GraphChange> {
+    let old_nodes = node_keys(old);
+    let new_nodes = node_keys(new);
+    let old_groups = group_keys(old);
+    let new_groups = group_keys(new);
+    let old_connections = connection_keys(old, &old_nodes);
+    let new_connections = connection_keys(new, &new_nodes);
+
+    let mut changes = Vec::new();
+
+    for (key, _) in &old_connections {
+        if !new_connections.iter().any(|(k, _)| k == key) {
+            changes.push(GraphChange::Disconnected(key.clone()));
+        }
+    }
+
+    for (key, node) in &old_nodes {
+        if !new_nodes.iter().any(|(k, _)| k == key) {
+            changes.push(GraphChange::RemovedNode(key.clone(), node.kind.clone()));
+        }
+    }
+
+    for key in old_groups.iter().rev() {
+        if !new_groups.contains(key) {
+            changes.push(GraphChange::RemovedGroup(key.clone()));
+        }
+    }
+
+    for key in &new_groups {
+        if !old_groups.contains(key) {
+            changes.push(GraphChange::AddedGroup(key.clone()));
+        }
+    }
+
+    for (key, node) in &new_nodes {
+        match old_nodes.iter().find(|(k, _)| k == key) {
+            None => changes.push(GraphChange::AddedNode(key.clone(), node.kind.clone())),
+            Some((_, old_node)) => {
+                if old_node.kind != node.kind || old.kind(&node.kind) != new.kind(&node.kind) {
+                    changes.push(GraphChange::ReplacedNode(
+                        key.clone(),
+                        old_node.kind.clone(),
+                        node.kind.clone(),
+                    ));
+                }
+            }
+        }
+    }
+
+    for (key, _) in &new_connections {
+        if !old_connections.iter().any(|(k, _)| k == key) {
+            changes.push(GraphChange::Connected(key.clone()));
+        }
+    }
+
+    changes
+}
+
+/// Reload a (running) graph into another graph, by applying only the changes between them (see [`diff`]).
+///
+/// Nodes that are not removed or replaced are kept, with their connections, so they need not be restarted. The node
+///  kinds are taken over from the new graph. Afterwards, both graphs are equal, up to the order (and IDs) of their
+///  nodes, groups and connections.
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::{graph::*, graph_diff::*};
+///
+/// let mut running = Graph::new();
+/// running.add_node("a", "Reader", None).unwrap();
+///
+/// let mut new = Graph::new();
+/// let a = new.add_node("a", "Reader", None).unwrap();
+/// let b = new.add_node("b", "Writer", None).unwrap();
+/// new.connect(Endpoint { node: a, port: None }, Endpoint { node: b, port: None }).unwrap();
+///
+/// let changes = reload(&mut running, &new).unwrap();
+///
+/// assert_eq!(summary(&changes), "1 node added, 1 connection added");
+/// assert!(diff(&running, &new).is_empty());
+/// ```
+pub fn reload(running: &mut Graph, new: &Graph) -> Result<Vec<GraphChange>, GraphError> {
+    let changes = diff(running, new);
+    let new_nodes = node_keys(new);
+    let new_groups = group_keys(new);
+
+    let nodes = node_keys(running);
+    let disconnected: Vec<_> = connection_keys(running, &nodes)
+        .into_iter()
+        .filter(|(k, _)| changes.contains(&GraphChange::Disconnected(k.clone())))
+        .map(|(_, c)| c.clone())
+        .collect();
+    for c in disconnected {
+        running.disconnect(&c.source, &c.sink)?;
+    }
+
+    // Remove from the back, so the IDs (and numbering of repeated paths) still to remove are not shifted.
+    let mut removed_nodes = Vec::new();
+    let mut removed_groups = Vec::new();
+    for change in &changes {
+        match change {
+            GraphChange::RemovedNode(path, _) | GraphChange::ReplacedNode(path, ..) => {
+                removed_nodes.push(find_node(running, path));
+            }
+            GraphChange::RemovedGroup(path) => removed_groups.push(find_group(running, path)),
+            _ => {}
+        }
+    }
+
+    removed_nodes.sort();
+    for id in removed_nodes.into_iter().rev() {
+        running.remove_node(id, true)?;
+    }
+
+    removed_groups.sort();
+    for id in removed_groups.into_iter().rev() {
+        running.remove_group(id, true)?;
+    }
+
+    running.replace_kinds(new.kinds().to_vec());
+
+    for change in &changes {
+        match change {
+            GraphChange::AddedGroup(path) => {
+                let id = new_groups.iter().position(|k| k == path);
+                let group = &new.groups()[id.expect("group of new graph")];
+                let parent = group.parent.map(|p| find_group(running, &new_groups[p.0]));
+                running.add_group(&group.name, parent)?;
+            }
+            GraphChange::AddedNode(path, kind) | GraphChange::ReplacedNode(path, _, kind) => {
+                let node = new_nodes
+                    .iter()
+                    .find(|(k, _)| k == path)
+                    .map(|(_, n)| n)
+                    .expect("node of new graph");
+                let group = node.group.map(|g| find_group(running, &new_groups[g.0]));
+                running.add_node(&node.name, kind, group)?;
+            }
+            _ => {}
+        }
+    }
+
+    // Reconnect the new connections, as well as the connections of replaced nodes.
+    let nodes = node_keys(running);
+    let connected: Vec<_> = connection_keys(running, &nodes)
+        .into_iter()
+        .map(|(k, _)| k)
+        .collect();
+    for (key, c) in connection_keys(new, &new_nodes) {
+        if !connected.contains(&key) {
+            let endpoint = |e: &Endpoint| Endpoint {
+                node: find_node(running, &new_nodes[e.node.0].0),
+                port: e.port.clone(),
+            };
+            let (source, sink) = (endpoint(&c.source), endpoint(&c.sink));
+            running.connect_buffered(source, sink, c.buffer)?;
+        }
+    }
+
+    Ok(changes)
+}
+
+/// Summarize changes for a reload report, e.g. `1 node added, 2 connections removed`.
+pub fn summary(changes: &[GraphChange]) -> String {
+    let count = |f: fn(&GraphChange) -> bool| changes.iter().filter(|c| f(c)).count();
+    let counts = [
+        (
+            count(|c| matches!(c, GraphChange::AddedGroup(_))),
+            "group",
+            "added",
+        ),
+        (
+            count(|c| matches!(c, GraphChange::RemovedGroup(_))),
+            "group",
+            "removed",
+        ),
+        (
+            count(|c| matches!(c, GraphChange::AddedNode(..))),
+            "node",
+            "added",
+        ),
+        (
+            count(|c| matches!(c, GraphChange::RemovedNode(..))),
+            "node",
+            "removed",
+        ),
+        (
+            count(|c| matches!(c, GraphChange::ReplacedNode(..))),
+            "node",
+            "replaced",
+        ),
+        (
+            count(|c| matches!(c, GraphChange::Connected(_))),
+            "connection",
+            "added",
+        ),
+        (
+            count(|c| matches!(c, GraphChange::Disconnected(_))),
+            "connection",
+            "removed",
+        ),
+    ];
+
+    let parts: Vec<_> = counts
+        .iter()
+        .filter(|(n, ..)| *n > 0)
+        .map(|(n, what, how)| format!("{n} {what}{} {how}", if *n == 1 { "" } else { "s" }))
+        .collect();
+
+    match parts.is_empty() {
+        true => "no changes".to_owned(),
+        false => parts.join(", "),
+    }
+}
+
+/// Get the path of a group, e.g. `app.sys`.
+fn group_path(graph: &Graph, id: GroupId) -> String {
+    let group = &graph.groups()[id.0];
+    match group.parent {
+        Some(parent) => format!("{}.{}", group_path(graph, parent), group.name),
+        None => group.name.clone(),
+    }
+}
+
+/// Get the identities of all groups (by path, numbered if repeated), in graph order.
+fn group_keys(graph: &Graph) -> Vec<String> {
+    let mut keys = Vec::new();
+
+    for id in 0..graph.groups().len() {
+        let key = numbered(&keys, group_path(graph, GroupId(id)));
+        keys.push(key);
+    }
+
+    keys
+}
+
+/// Get the identities of all nodes (by path, numbered if repeated), in graph (ID) order.
+fn node_keys(graph: &Graph) -> Vec<(String, &Node)> {
+    let mut keys: Vec<(String, &Node)> = Vec::new();
+
+    for node in graph.nodes() {
+        let path = match node.group {
+            Some(g) => format!("{}.{}", group_path(graph, g), node.name),
+            None => node.name.clone(),
+        };
+        let existing: Vec<_> = keys.iter().map(|(k, _)| k.clone()).collect();
+        keys.push((numbered(&existing, path), node));
+    }
+
+    keys
+}
+
+/// Get the identities of all connections (by label, numbered if repeated), in graph order.
+fn connection_keys<'g>(
+    graph: &'g Graph,
+    nodes: &[(String, &Node)],
+) -> Vec<(String, &'g Connection)> {
+    let mut keys: Vec<(String, &Connection)> = Vec::new();
+
+    for c in graph.connections() {
+        let endpoint = |e: &Endpoint| match &e.port {
+            Some(port) => format!("{}.{port}", nodes[e.node.0].0),
+            None => nodes[e.node.0].0.clone(),
+        };
+        let mut label = format!("{} -> {}", endpoint(&c.source), endpoint(&c.sink));
+        if c.buffer != BufferPolicy::default() {
+            let capacity = c.buffer.capacity.map(|n| format!("capacity: {n}, "));
+            label += &format!(
+                " [{}overflow: {}]",
+                capacity.unwrap_or_default(),
+                c.buffer.overflow
+            );
+        }
+        let existing: Vec<_> = keys.iter().map(|(k, _)| k.clone()).collect();
+        keys.push((numbered(&existing, label), c));
+    }
+
+    keys
+}
+
+/// Number a repeated key, e.g. `a (2)` for the second `a`.
+fn numbered(keys: &[String], key: String) -> String {
+    let count = keys
+        .iter()
+        .filter(|k| {
+            **k == key
+                || k.strip_prefix(key.as_str())
+                    .is_some_and(|s| s.starts_with(" ("))
+        })
+        .count();
+
+    match count {
+        0 => key,
+        n => format!("{key} ({})", n + 1),
+    }
+}
+
+fn find_node(graph: &Graph, path: &str) -> NodeId {
+    node_keys(graph)
+        .iter()
+        .position(|(k, _)| k == path)
+        .map(NodeId)
+        .expect("node path")
+}
+
+fn find_group(graph: &Graph, path: &str) -> GroupId {
+    group_keys(graph)
+        .iter()
+        .position(|k| k == path)
+        .map(GroupId)
+        .expect("group path")
+}
+
+#[cfg(test)]
+fn test_graph(sink_kind: &str) -> Graph {
+    let mut g = Graph::new();
+    g.declare_kind(NodeKind {
+        name: "Reader".to_owned(),
+        ports: vec![Port::output("out", "Number")],
+    })
+    .unwrap();
+
+    let sys = g.add_group("sys", None).unwrap();
+    let a = g.add_node("a", "Reader", Some(sys)).unwrap();
+    let b = g.add_node("b", sink_kind, Some(sys)).unwrap();
+    let c = g.add_node("c", "Writer", None).unwrap();
+
+    let out = Endpoint {
+        node: a,
+        port: Some("out".to_owned()),
+    };
+    let any = |node| Endpoint { node, port: None };
+    g.connect(out.clone(), any(b)).unwrap();
+    g.connect(out, any(c)).unwrap();
+
+    g
+}
+
+#[test]
+fn diff_test() {
+    let old = test_graph("Writer");
+
+    assert_eq!(diff(&old, &old), []);
+    assert_eq!(diff(&old, &old.clone()), []);
+
+    let changes: Vec<_> = diff(&old, &test_graph("Filter"))
+        .iter()
+        .map(|c| c.to_string())
+        .collect();
+    assert_eq!(changes, ["~ node sys.b (Writer -> Filter)"]);
+
+    let changes: Vec<_> = diff(&old, &Graph::new())
+        .iter()
+        .map(|c| c.to_string())
+        .collect();
+    assert_eq!(
+        changes,
+        [
+            "- sys.a.out -> sys.b",
+            "- sys.a.out -> c",
+            "- node sys.a (Reader)",
+            "- node sys.b (Writer)",
+            "- node c (Writer)",
+            "- group sys"
+        ]
+    );
+
+    // Repeated names are matched in order.
+    let mut g = Graph::new();
+    g.add_node("x", "Reader", None).unwrap();
+    let mut h = g.clone();
+    h.add_node("x", "Writer", None).unwrap();
+    assert_eq!(
+        diff(&g, &h),
+        [GraphChange::AddedNode(
+            "x (2)".to_owned(),
+            "Writer".to_owned()
+        )]
+    );
+}
+
+#[test]
+fn reload_test() {
+    let mut running = test_graph("Writer");
+
+    // Replacing a node restores its connections, other nodes are kept.
+    let new = test_graph("Filter");
+    let changes = reload(&mut running, &new).unwrap();
+    assert_eq!(summary(&changes), "1 node replaced");
+    assert_eq!(diff(&running, &new), []);
+    assert_eq!(running.connections().len(), 2);
+    assert_eq!(running.nodes()[0].name, "a");
+
+    // Moving nodes between groups, and changing kind declarations.
+    let mut new = Graph::new();
+    new.declare_kind(NodeKind {
+        name: "Reader".to_owned(),
+        ports: vec![Port::output("value", "Number")],
+    })
+    .unwrap();
+    let app = new.add_group("app", None).unwrap();
+    let a = new.add_node("a", "Reader", Some(app)).unwrap();
+    let c = new.add_node("c", "Writer", None).unwrap();
+    new.connect(
+        Endpoint {
+            node: a,
+            port: Some("value".to_owned()),
+        },
+        Endpoint {
+            node: c,
+            port: None,
+        },
+    )
+    .unwrap();
+
+    let changes = reload(&mut running, &new).unwrap();
+    assert_eq!(
+        summary(&changes),
+        "1 group added, 1 group removed, 1 node added, 2 nodes removed, 1 connection added, 2 connections removed"
+    );
+    assert_eq!(diff(&running, &new), []);
+    assert_eq!(running.kinds(), new.kinds());
+
+    assert_eq!(summary(&reload(&mut running, &new).unwrap()), "no changes");
+
+    // Changing the buffering policy reconnects.
+    let mut buffered = new.clone();
+    let c = buffered.connections()[0].clone();
+    buffered.disconnect(&c.source, &c.sink).unwrap();
+    let buffer = BufferPolicy {
+        capacity: Some(4),
+        overflow: crate::ast::Overflow::DropNewest,
+    };
+    buffered.connect_buffered(c.source, c.sink, buffer).unwrap();
+    let changes: Vec<_> = reload(&mut running, &buffered)
+        .unwrap()
+        .iter()
+        .map(|c| c.to_string())
+        .collect();
+    assert_eq!(
+        changes,
+        [
+            "- app.a.value -> c",
+            "+ app.a.value -> c [capacity: 4, overflow: drop_newest]"
+        ]
+    );
+    assert_eq!(running.connections()[0].buffer, buffer);
+}
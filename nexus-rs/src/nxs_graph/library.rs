@@ -0,0 +1,103 @@
+use crate::ast::{StmtKind, Stmts};
+use crate::front::parse_source;
+use crate::graph::NodeKind;
+
+#[cfg(test)]
+use crate::graph::{Endpoint, Graph, GraphError};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+/// Node declarations of the built-in node library, available in all programs.
+///
+/// A node declaration in a program takes precedence over a built-in node kind of the same name.
+pub const LIBRARY: &str = "\
+/// Source of a constant number.
+node Constant { out value: Number; }
+
+/// Source of a tick count, increasing at a fixed interval.
+node Timer { out tick: Number; }
+
+/// Sum of two numbers.
+node Add { in a: Number; in b: Number; out result: Number; }
+
+/// Difference of two numbers.
+node Subtract { in a: Number; in b: Number; out result: Number; }
+
+/// Product of two numbers.
+node Multiply { in a: Number; in b: Number; out result: Number; }
+
+/// Quotient of two numbers.
+node Divide { in a: Number; in b: Number; out result: Number; }
+
+/// Sink printing numbers to the console.
+node Logger { in value: Number; }
+
+/// Sink printing strings to the console.
+node TextLogger { in value: String; }
+";
+
+/// Parse the node declarations of the built-in node library.
+pub fn declarations() -> Stmts {
+    parse_source(LIBRARY).expect("valid built-in node library")
+}
+
+/// Get the node kinds of the built-in node library.
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::library;
+///
+/// let kinds = library::kinds();
+/// let adder = kinds.iter().find(|k| k.name == "Add").unwrap();
+///
+/// assert_eq!(adder.ports.len(), 3);
+/// assert!(adder.port("result").is_some());
+/// ```
+pub fn kinds() -> Vec<NodeKind> {
+    declarations()
+        .iter()
+        .filter_map(|stmt| match &stmt.kind {
+            StmtKind::NodeDecl(n) => Some(NodeKind::from(&**n)),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn library_test() {
+    let kinds = kinds();
+    let names: Vec<_> = kinds.iter().map(|k| k.name.as_str()).collect();
+
+    assert_eq!(
+        names,
+        [
+            "Constant",
+            "Timer",
+            "Add",
+            "Subtract",
+            "Multiply",
+            "Divide",
+            "Logger",
+            "TextLogger"
+        ]
+    );
+    assert_eq!(declarations().len(), kinds.len());
+
+    let mut g = Graph::with_library();
+    let c = g.add_node("c", "Constant", None).unwrap();
+    let log = g.add_node("log", "TextLogger", None).unwrap();
+    let port = |node, name: &str| Endpoint {
+        node,
+        port: Some(name.to_owned()),
+    };
+
+    assert_eq!(
+        g.connect(port(c, "value"), port(log, "value")),
+        Err(GraphError::PortTypeMismatch(
+            "Number".to_owned(),
+            "String".to_owned()
+        ))
+    );
+}
@@ -0,0 +1,196 @@
+use crate::json::Json;
+use crate::native::{self, NativeNode, NodeError, PortValues};
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+#[cfg(test)]
+use crate::{graph::Port, value::Value};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+/// Counters of a node, accumulated over the values it processed.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct NodeMetrics {
+    pub items_in: u64,  // Input values.
+    pub items_out: u64, // Output values.
+    pub errors: u64,    // Failed processing calls.
+    pub processing_time: Duration,
+}
+
+impl NodeMetrics {
+    fn add(&mut self, other: &NodeMetrics) {
+        self.items_in += other.items_in;
+        self.items_out += other.items_out;
+        self.errors += other.errors;
+        self.processing_time += other.processing_time;
+    }
+}
+
+/// Observer of node metrics, e.g. to export them to a metrics collector.
+pub trait MetricsObserver {
+    /// Called after every processing call of a node, with the counters of that call.
+    fn record(&mut self, node: &str, sample: &NodeMetrics);
+}
+
+/// Metrics of the nodes of a graph run, by node name.
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::metrics::*;
+///
+/// struct Errors(u64);
+///
+/// impl MetricsObserver for Errors {
+///     fn record(&mut self, _: &str, sample: &NodeMetrics) {
+///         self.0 += sample.errors;
+///     }
+/// }
+///
+/// let mut metrics = Metrics::new();
+/// metrics.observe(Box::new(Errors(0)));
+/// metrics.record("reader", NodeMetrics { items_out: 2, ..Default::default() });
+/// metrics.record("reader", NodeMetrics { items_out: 1, ..Default::default() });
+///
+/// assert_eq!(metrics.node("reader").unwrap().items_out, 3);
+/// assert!(metrics.to_json().contains(r#""items_out": 3"#));
+/// ```
+#[derive(Default)]
+pub struct Metrics {
+    nodes: BTreeMap<String, NodeMetrics>,
+    observers: Vec<Box<dyn MetricsObserver>>,
+}
+
+impl Metrics {
+    /// Create empty metrics, without observers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an observer, called for every recorded sample.
+    pub fn observe(&mut self, observer: Box<dyn MetricsObserver>) {
+        self.observers.push(observer);
+    }
+
+    /// Record a sample of a node, adding it to the node counters.
+    pub fn record(&mut self, node: &str, sample: NodeMetrics) {
+        self.nodes.entry(node.to_owned()).or_default().add(&sample);
+
+        for observer in &mut self.observers {
+            observer.record(node, &sample);
+        }
+    }
+
+    /// Process values with a node (see [`native::process`]), recording its metrics.
+    pub fn process(
+        &mut self,
+        name: &str,
+        node: &mut dyn NativeNode,
+        inputs: &PortValues,
+    ) -> Result<PortValues, NodeError> {
+        let start = Instant::now();
+        let result = native::process(node, inputs);
+
+        self.record(
+            name,
+            NodeMetrics {
+                items_in: inputs.len() as u64,
+                items_out: result.as_ref().map_or(0, |o| o.len() as u64),
+                errors: result.is_err().into(),
+                processing_time: start.elapsed(),
+            },
+        );
+
+        result
+    }
+
+    /// Get the counters of a node.
+    pub fn node(&self, name: &str) -> Option<&NodeMetrics> {
+        self.nodes.get(name)
+    }
+
+    /// Get the counters of all nodes, sorted by node name.
+    pub fn nodes(&self) -> impl Iterator<Item = (&str, &NodeMetrics)> {
+        self.nodes.iter().map(|(name, m)| (name.as_str(), m))
+    }
+
+    /// Serialize the metrics into (pretty-printed) JSON, with processing times in milliseconds.
+    pub fn to_json(&self) -> String {
+        Json::Object(vec![(
+            "nodes".to_owned(),
+            Json::Array(
+                self.nodes()
+                    .map(|(name, m)| {
+                        Json::Object(vec![
+                            ("name".to_owned(), name.into()),
+                            ("items_in".to_owned(), (m.items_in as usize).into()),
+                            ("items_out".to_owned(), (m.items_out as usize).into()),
+                            ("errors".to_owned(), (m.errors as usize).into()),
+                            (
+                                "processing_ms".to_owned(),
+                                (m.processing_time.as_secs_f64() * 1000.0).into(),
+                            ),
+                        ])
+                    })
+                    .collect(),
+            ),
+        )])
+        .pretty()
+    }
+}
+
+#[cfg(test)]
+struct Negate;
+
+#[cfg(test)]
+impl NativeNode for Negate {
+    fn ports(&self) -> Vec<Port> {
+        vec![Port::input("x", "Number"), Port::output("y", "Number")]
+    }
+
+    fn process(&mut self, inputs: &PortValues) -> Result<PortValues, NodeError> {
+        match inputs["x"] {
+            Value::Number(x) => Ok(PortValues::from([("y".to_owned(), Value::Number(-x))])),
+            _ => unreachable!("checked input type"),
+        }
+    }
+}
+
+#[test]
+fn metrics_test() {
+    use std::{cell::RefCell, rc::Rc};
+
+    struct Log(Rc<RefCell<Vec<String>>>);
+
+    impl MetricsObserver for Log {
+        fn record(&mut self, node: &str, sample: &NodeMetrics) {
+            self.0.borrow_mut().push(format!(
+                "{node} {} {} {}",
+                sample.items_in, sample.items_out, sample.errors
+            ));
+        }
+    }
+
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let mut metrics = Metrics::new();
+    metrics.observe(Box::new(Log(log.clone())));
+
+    let x = |v: Value| PortValues::from([("x".to_owned(), v)]);
+    assert!(metrics
+        .process("neg", &mut Negate, &x(Value::Number(1.0)))
+        .is_ok());
+    assert!(metrics
+        .process("neg", &mut Negate, &x(Value::from("1")))
+        .is_err());
+
+    let neg = metrics.node("neg").unwrap();
+    assert_eq!((neg.items_in, neg.items_out, neg.errors), (2, 1, 1));
+    assert_eq!(*log.borrow(), ["neg 1 1 0", "neg 1 0 1"]);
+    assert!(metrics.node("pos").is_none());
+
+    let json = Json::parse(&metrics.to_json()).unwrap();
+    let nodes = json.get("nodes").and_then(Json::as_array).unwrap();
+    assert_eq!(nodes.len(), 1);
+    assert_eq!(nodes[0].get("errors").and_then(Json::as_usize), Some(1));
+}
@@ -0,0 +1,271 @@
+use crate::ast::PortDirection;
+use crate::graph::{GraphError, NodeKind, Port};
+use crate::value::Value;
+use std::collections::{BTreeMap, HashMap};
+use thiserror::Error;
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+/// Native node error, reported by a node implementation or when checking its port values.
+#[derive(Error, Debug, PartialEq)]
+pub enum NodeError {
+    #[error("node setup failed: {0}")]
+    Setup(String),
+
+    #[error("node processing failed: {0}")]
+    Process(String),
+
+    #[error("no value for input port '{0}'")]
+    MissingInput(String),
+
+    #[error("node has no output port '{0}'")]
+    UnknownOutput(String),
+
+    #[error("value of port '{0}' is of type {1}, expected {2}")]
+    PortType(String, &'static str, String),
+}
+
+/// Values of node ports, by port name.
+pub type PortValues = HashMap<String, Value>;
+
+/// Native node factory, creating a node instance (e.g. `MyNode::factory`).
+pub type NodeFactory = fn() -> Box<dyn NativeNode>;
+
+/// Node implemented in host (Rust) code.
+///
+/// A node is set up once, then processes the values of its input ports into values of its output ports any number of
+///  times, and is torn down once.
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::{graph::Port, native::*, value::Value};
+///
+/// struct Double;
+///
+/// impl NativeNode for Double {
+///     fn ports(&self) -> Vec<Port> {
+///         vec![Port::input("x", "Number"), Port::output("y", "Number")]
+///     }
+///
+///     fn process(&mut self, inputs: &PortValues) -> Result<PortValues, NodeError> {
+///         match inputs["x"] {
+///             Value::Number(x) => Ok(PortValues::from([("y".to_owned(), Value::Number(2.0 * x))])),
+///             _ => Err(NodeError::Process("not a number".to_owned())),
+///         }
+///     }
+/// }
+///
+/// let mut node = Double;
+/// let outputs = process(&mut node, &PortValues::from([("x".to_owned(), Value::Number(3.0))])).unwrap();
+///
+/// assert_eq!(outputs["y"], Value::Number(6.0));
+/// assert_eq!(process(&mut node, &PortValues::new()), Err(NodeError::MissingInput("x".to_owned())));
+/// ```
+pub trait NativeNode {
+    /// Get the port descriptors, identical for all nodes created by the same factory.
+    fn ports(&self) -> Vec<Port>;
+
+    /// Prepare the node, before processing any values.
+    fn setup(&mut self) -> Result<(), NodeError> {
+        Ok(())
+    }
+
+    /// Process the values of the input ports into values of (some of) the output ports.
+    fn process(&mut self, inputs: &PortValues) -> Result<PortValues, NodeError>;
+
+    /// Release the resources of the node, after processing all values.
+    fn teardown(&mut self) {}
+}
+
+/// Process values with a node, checking the values of all ports against the port descriptors: every input port must
+///  have a value, and every value must be of the port type.
+pub fn process(node: &mut dyn NativeNode, inputs: &PortValues) -> Result<PortValues, NodeError> {
+    let ports = node.ports();
+
+    for p in ports.iter().filter(|p| p.direction == PortDirection::Input) {
+        let value = inputs
+            .get(&p.name)
+            .ok_or_else(|| NodeError::MissingInput(p.name.clone()))?;
+        check_type(p, value)?;
+    }
+
+    let outputs = node.process(inputs)?;
+
+    for (name, value) in &outputs {
+        let p = ports
+            .iter()
+            .find(|p| p.name == *name && p.direction == PortDirection::Output)
+            .ok_or_else(|| NodeError::UnknownOutput(name.clone()))?;
+        check_type(p, value)?;
+    }
+
+    Ok(outputs)
+}
+
+fn check_type(port: &Port, value: &Value) -> Result<(), NodeError> {
+    // Port types are declared type names (e.g. `Bool`), value types are language names (e.g. `bool`).
+    match value.type_name() {
+        name if name.eq_ignore_ascii_case(&port.typeid) => Ok(()),
+        name => Err(NodeError::PortType(
+            port.name.clone(),
+            name,
+            port.typeid.clone(),
+        )),
+    }
+}
+
+/// Registry of native node kinds, by name.
+#[derive(Debug, Default)]
+pub struct NodeRegistry {
+    factories: BTreeMap<String, NodeFactory>,
+}
+
+impl NodeRegistry {
+    /// Create a new, empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a node kind, created by the given factory.
+    pub fn register(&mut self, name: &str, factory: NodeFactory) -> Result<(), GraphError> {
+        if self.factories.contains_key(name) {
+            return Err(GraphError::DuplicateKind(name.to_owned()));
+        }
+
+        self.factories.insert(name.to_owned(), factory);
+
+        Ok(())
+    }
+
+    /// Check if a node kind is registered.
+    pub fn contains(&self, name: &str) -> bool {
+        self.factories.contains_key(name)
+    }
+
+    /// Create a node of a registered kind.
+    pub fn create(&self, name: &str) -> Option<Box<dyn NativeNode>> {
+        self.factories.get(name).map(|factory| factory())
+    }
+
+    /// Get the node kinds (with their ports) of all registered kinds, sorted by name.
+    ///
+    /// The kinds can be declared in a graph (see [`crate::graph::Graph::declare_kind`]), to validate connections.
+    pub fn kinds(&self) -> Vec<NodeKind> {
+        self.factories
+            .iter()
+            .map(|(name, factory)| NodeKind {
+                name: name.clone(),
+                ports: factory().ports(),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+struct Counter {
+    count: f64,
+    active: bool,
+}
+
+#[cfg(test)]
+impl NativeNode for Counter {
+    fn ports(&self) -> Vec<Port> {
+        vec![
+            Port::input("step", "Number"),
+            Port::output("count", "Number"),
+        ]
+    }
+
+    fn setup(&mut self) -> Result<(), NodeError> {
+        self.active = true;
+        Ok(())
+    }
+
+    fn process(&mut self, inputs: &PortValues) -> Result<PortValues, NodeError> {
+        if !self.active {
+            return Err(NodeError::Process("not set up".to_owned()));
+        }
+
+        if let Value::Number(step) = inputs["step"] {
+            self.count += step;
+        }
+
+        Ok(PortValues::from([(
+            "count".to_owned(),
+            if self.count < 0.0 {
+                Value::from("negative")
+            } else {
+                Value::Number(self.count)
+            },
+        )]))
+    }
+
+    fn teardown(&mut self) {
+        self.active = false;
+    }
+}
+
+#[cfg(test)]
+impl Counter {
+    fn factory() -> Box<dyn NativeNode> {
+        Box::new(Counter {
+            count: 0.0,
+            active: false,
+        })
+    }
+}
+
+#[test]
+fn native_node_test() {
+    let mut registry = NodeRegistry::new();
+    registry.register("Counter", Counter::factory).unwrap();
+
+    assert_eq!(
+        registry.register("Counter", Counter::factory),
+        Err(GraphError::DuplicateKind("Counter".to_owned()))
+    );
+    assert!(registry.contains("Counter") && registry.create("Kafka").is_none());
+
+    let kinds = registry.kinds();
+    assert_eq!(kinds.len(), 1);
+    assert_eq!(
+        kinds[0].port("count"),
+        Some(&Port::output("count", "Number"))
+    );
+
+    let mut node = registry.create("Counter").unwrap();
+    let step = |x: Value| PortValues::from([("step".to_owned(), x)]);
+
+    assert_eq!(
+        process(&mut *node, &step(Value::Number(1.0))),
+        Err(NodeError::Process("not set up".to_owned()))
+    );
+
+    node.setup().unwrap();
+    process(&mut *node, &step(Value::Number(1.0))).unwrap();
+    assert_eq!(
+        process(&mut *node, &step(Value::Number(2.0))).unwrap()["count"],
+        Value::Number(3.0)
+    );
+    assert_eq!(
+        process(&mut *node, &step(Value::Bool(true))),
+        Err(NodeError::PortType(
+            "step".to_owned(),
+            "bool",
+            "Number".to_owned()
+        ))
+    );
+    assert_eq!(
+        process(&mut *node, &step(Value::Number(-4.0))),
+        Err(NodeError::PortType(
+            "count".to_owned(),
+            "String",
+            "Number".to_owned()
+        ))
+    );
+
+    node.teardown();
+    assert!(node.process(&step(Value::Number(1.0))).is_err());
+}
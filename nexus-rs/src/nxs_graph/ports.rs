@@ -0,0 +1,484 @@
+use crate::ast::{
+    BinaryOp, Expr, ExprKind, LiteralKind, NodeDecl, PortDecl, PortDirection, Stmt, StmtKind,
+    Stmts, UnaryOp,
+};
+use crate::library;
+use crate::loader::Module;
+use std::{collections::HashMap, fmt, path::PathBuf};
+use thiserror::Error;
+
+#[cfg(test)]
+use crate::front::parse_source;
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+/// Connection port error, found by resolving the port references of connections against node declarations.
+#[derive(Error, Debug, PartialEq)]
+pub enum PortError {
+    #[error("{0}: node '{1}' of kind '{2}' has no port '{3}' (ports: {4})")]
+    UnknownPort(ConnectSite, String, String, String, String),
+
+    #[error("{0}: '{1}' is an {2} port, it cannot be a connection {3}")]
+    Direction(ConnectSite, String, &'static str, &'static str),
+
+    #[error("{0}: cannot connect '{1}' ({2}) to '{3}' ({4}), the port types differ")]
+    TypeMismatch(ConnectSite, String, String, String, String),
+
+    #[error("{0}: connection of node '{1}' must name a port, as kind '{2}' declares ports (like '{1}.port')")]
+    MissingPort(ConnectSite, String, String),
+
+    #[error(
+        "{0}: input port '{1}' is already connected (at {2}), an input accepts a single connection"
+    )]
+    InputConnected(ConnectSite, String, ConnectSite),
+}
+
+impl PortError {
+    /// Get the diagnostic code of the error (see [`diagnostics`](crate::diagnostics)).
+    pub fn code(&self) -> &'static str {
+        match self {
+            PortError::UnknownPort(..) => "NXS0205",
+            PortError::Direction(..) => "NXS0206",
+            PortError::TypeMismatch(..) => "NXS0207",
+            PortError::MissingPort(..) => "NXS0208",
+            PortError::InputConnected(..) => "NXS0209",
+        }
+    }
+}
+
+/// Location of a connect statement.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConnectSite {
+    pub file: PathBuf,
+    pub line: Option<usize>,
+}
+
+impl fmt::Display for ConnectSite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{line}", self.file.display()),
+            None => write!(f, "{}", self.file.display()),
+        }
+    }
+}
+
+/// Check the connections of the loaded modules against the node declarations (see [`NodeDecl`]).
+///
+/// An endpoint like `a.x` is resolved if `a` holds a node of a declared kind (e.g. `let a = node "Adder";`): `x` must
+///  be a declared port, an output port for the source and an input port for the sink. If both endpoints resolve, the
+///  port types must match. Endpoints of nodes of undeclared kinds, or of unknown values, are not checked.
+///
+/// An output port can fan out to any number of sinks (`a.y -> [b.x, c.x];`), but an input port accepts a single
+///  connection.
+///
+/// Node declarations are visible in all modules, as are the kinds of the built-in node library (see
+///  [`library::LIBRARY`]), unless declared by the program. Node variables are tracked by name in statement order (nested
+///  blocks included), without scoping.
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::{front::parse_source, loader::Module, ports::*};
+///
+/// let code = "node Gain { in x: Number; out y: Number; }\n\
+///             let a = node \"Gain\";\n\
+///             let b = node \"Gain\";\n\
+///             a.y -> b.x;\n\
+///             b.x -> a.x;";
+/// let modules = [Module { path: "main.nxs".into(), ast: parse_source(code).unwrap() }];
+///
+/// let errors = check_connections(&modules);
+///
+/// assert_eq!(errors.len(), 1);
+/// assert_eq!(errors[0].to_string(), "main.nxs:5: 'b.x' is an input port, it cannot be a connection source");
+/// ```
+pub fn check_connections(modules: &[Module]) -> Vec<PortError> {
+    let builtins = library::declarations();
+    let mut checker = Checker::default();
+
+    checker.declare(&builtins);
+    for module in modules {
+        checker.declare(&module.ast);
+    }
+
+    for module in modules {
+        checker.file = module.path.clone();
+        checker.stmts(&module.ast);
+    }
+
+    checker.errors
+}
+
+#[derive(Default)]
+struct Checker<'a> {
+    kinds: HashMap<String, &'a NodeDecl>, // Declared node kinds.
+    nodes: HashMap<String, String>, // Kind of node variables (or members), by path (e.g. `sys.a`).
+    inputs: HashMap<String, ConnectSite>, // Connected input ports, by path (e.g. `sys.a.x`).
+    file: PathBuf,
+    errors: Vec<PortError>,
+}
+
+/// Resolved connection endpoint.
+struct Endpoint<'a> {
+    name: String, // E.g. `a.x`.
+    port: &'a PortDecl,
+}
+
+impl<'a> Checker<'a> {
+    fn declare(&mut self, stmts: &'a Stmts) {
+        for stmt in stmts.iter() {
+            if let StmtKind::NodeDecl(n) = &stmt.kind {
+                self.kinds.insert(n.id.to_string(), n);
+            }
+        }
+    }
+
+    fn stmts(&mut self, stmts: &Stmts) {
+        stmts.iter().for_each(|s| self.stmt(s));
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        match &stmt.kind {
+            StmtKind::Assignment(a) => self.bind(&a.lhs, Some(&a.rhs)),
+            StmtKind::Block(b) => self.stmts(b),
+            StmtKind::Connect(c) => {
+                let site = ConnectSite {
+                    file: self.file.clone(),
+                    line: stmt.span.and_then(|s| s.line),
+                };
+
+                // Each endpoint is resolved once per role: as sink of the previous stage, and as source of the next.
+                let last = c.stages.len() - 1;
+                let resolve =
+                    |checker: &mut Self, i: usize, direction| -> Vec<Option<Endpoint<'a>>> {
+                        c.stages[i]
+                            .iter()
+                            .map(|e| checker.endpoint(e, direction, &site))
+                            .collect()
+                    };
+
+                let mut sources = resolve(self, 0, PortDirection::Output);
+                for i in 1..=last {
+                    let sinks = resolve(self, i, PortDirection::Input);
+
+                    for sink in sinks.iter().flatten() {
+                        self.connect_input(sink, sources.len(), &site);
+
+                        for source in sources.iter().flatten() {
+                            if source.port.typeid.to_string() != sink.port.typeid.to_string() {
+                                self.errors.push(PortError::TypeMismatch(
+                                    site.clone(),
+                                    source.name.clone(),
+                                    source.port.typeid.to_string(),
+                                    sink.name.clone(),
+                                    sink.port.typeid.to_string(),
+                                ));
+                            }
+                        }
+                    }
+
+                    if i < last {
+                        sources = resolve(self, i, PortDirection::Output);
+                    }
+                }
+            }
+            StmtKind::Disconnect(c) => {
+                for (_, sink) in c.pairs() {
+                    if let Some(path) = path(sink) {
+                        self.inputs.remove(&path);
+                    }
+                }
+            }
+            StmtKind::Remove(r) => self.bind(&r.target, None),
+            StmtKind::Defer(s) => self.stmt(s),
+            StmtKind::Expr(e) => self.expr(e),
+            StmtKind::FunctionDecl(f) => self.stmt(&f.body),
+            StmtKind::GroupDecl(g) => self.stmt(&g.body),
+            StmtKind::VarDecl(v) => self.bind(&v.id, v.value.as_ref()),
+            StmtKind::ConstDecl(_)
+            | StmtKind::EnumDecl(_)
+            | StmtKind::NodeDecl(_)
+            | StmtKind::Print(_)
+            | StmtKind::Return(_)
+            | StmtKind::UseDecl(_) => (),
+        }
+    }
+
+    /// Visit the statements nested in an expression (e.g. loop bodies).
+    fn expr(&mut self, expr: &Expr) {
+        match &expr.kind {
+            ExprKind::Block(b) => self.stmt(&b.body),
+            ExprKind::For(f) => self.expr(&f.body),
+            ExprKind::If(i) => {
+                self.expr(&i.body_then);
+                i.body_else.iter().for_each(|e| self.expr(e));
+            }
+            ExprKind::Loop(l) => self.expr(&l.body),
+            ExprKind::Time(t) => self.expr(&t.body),
+            ExprKind::While(w) => self.expr(&w.body),
+            _ => (),
+        }
+    }
+
+    /// Track the kind of a (re)defined node variable or member.
+    fn bind(&mut self, target: &Expr, value: Option<&Expr>) {
+        let Some(path) = path(target) else {
+            return;
+        };
+
+        // A (re)defined node has no connected inputs yet.
+        let prefix = format!("{path}.");
+        self.inputs.retain(|input, _| !input.starts_with(&prefix));
+
+        match value.and_then(node_kind) {
+            Some(kind) => self.nodes.insert(path, kind),
+            None => self.nodes.remove(&path),
+        };
+    }
+
+    /// Register `count` connections to an input port, which accepts a single connection.
+    fn connect_input(&mut self, sink: &Endpoint, count: usize, site: &ConnectSite) {
+        match self.inputs.get(&sink.name) {
+            Some(first) => self.errors.push(PortError::InputConnected(
+                site.clone(),
+                sink.name.clone(),
+                first.clone(),
+            )),
+            None if count > 1 => self.errors.push(PortError::InputConnected(
+                site.clone(),
+                sink.name.clone(),
+                site.clone(),
+            )),
+            None => (),
+        }
+
+        self.inputs.insert(sink.name.clone(), site.clone());
+    }
+
+    /// Resolve a connection endpoint of a node of a declared kind, reporting invalid endpoints.
+    fn endpoint(
+        &mut self,
+        expr: &Expr,
+        direction: PortDirection,
+        site: &ConnectSite,
+    ) -> Option<Endpoint<'a>> {
+        let name = path(expr)?;
+
+        if let Some(kind) = self.nodes.get(&name).and_then(|k| self.kinds.get(k)) {
+            let kind = kind.id.to_string();
+            self.errors
+                .push(PortError::MissingPort(site.clone(), name, kind));
+            return None;
+        }
+
+        let (node, port) = name.rsplit_once('.')?;
+        let kind = *self.nodes.get(node).and_then(|k| self.kinds.get(k))?;
+
+        let Some(decl) = kind.port(port) else {
+            let ports: Vec<_> = kind.ports.iter().map(|p| p.id.to_string()).collect();
+            self.errors.push(PortError::UnknownPort(
+                site.clone(),
+                node.to_owned(),
+                kind.id.to_string(),
+                port.to_owned(),
+                ports.join(", "),
+            ));
+            return None;
+        };
+
+        if decl.direction != direction {
+            self.errors.push(PortError::Direction(
+                site.clone(),
+                name,
+                match decl.direction {
+                    PortDirection::Input => "input",
+                    PortDirection::Output => "output",
+                },
+                match direction {
+                    PortDirection::Input => "sink",
+                    PortDirection::Output => "source",
+                },
+            ));
+            return None;
+        }
+
+        Some(Endpoint { name, port: decl })
+    }
+}
+
+/// Get the path of a variable or (nested) member, e.g. `sys.a.x`.
+fn path(expr: &Expr) -> Option<String> {
+    match &expr.kind {
+        ExprKind::Var(v) => Some(v.id.to_string()),
+        ExprKind::Binary(b) if matches!(b.op, BinaryOp::Dot) => match &b.rhs.kind {
+            ExprKind::Var(member) => Some(format!("{}.{}", path(&b.lhs)?, member.id)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Get the kind of a node expression, e.g. `Adder` for `node "Adder"`.
+fn node_kind(expr: &Expr) -> Option<String> {
+    let ExprKind::Unary(u) = &expr.kind else {
+        return None;
+    };
+
+    match (&u.op, &u.expr.kind) {
+        (UnaryOp::Node, ExprKind::Literal(l)) => match &l.kind {
+            LiteralKind::String(kind) => Some(kind.to_string()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+fn check(code: &str) -> Vec<String> {
+    let modules = [Module {
+        path: "t.nxs".into(),
+        ast: parse_source(code).unwrap(),
+    }];
+
+    check_connections(&modules)
+        .iter()
+        .map(|e| format!("{} {e}", e.code()))
+        .collect()
+}
+
+#[test]
+fn check_connections_test() {
+    let decls = "node Adder { in a: Number; in b: Number; out sum: Number; }\n\
+                 node Label { in text: String; out done: bool; }\n\
+                 let x = node \"Adder\";\n\
+                 let y = node \"Adder\";\n\
+                 let z = node \"Label\";\n\
+                 let sys.u = node \"Unknown\";\n";
+
+    assert_eq!(
+        check(&format!(
+            "{decls}x.sum -> y.a; x.sum -> sys.u.input; sys.u -> y.b;"
+        )),
+        [] as [String; 0]
+    );
+    assert_eq!(
+        check(&format!("{decls}x.total -> y.a;")),
+        ["NXS0205 t.nxs:7: node 'x' of kind 'Adder' has no port 'total' (ports: a, b, sum)"]
+    );
+    assert_eq!(
+        check(&format!("{decls}x.sum -> y.sum;")),
+        ["NXS0206 t.nxs:7: 'y.sum' is an output port, it cannot be a connection sink"]
+    );
+    assert_eq!(
+        check(&format!("{decls}x.sum -> z.text;")),
+        ["NXS0207 t.nxs:7: cannot connect 'x.sum' (Number) to 'z.text' (String), the port types differ"]
+    );
+    assert_eq!(
+        check(&format!("{decls}x -> y.a;")),
+        ["NXS0208 t.nxs:7: connection of node 'x' must name a port, as kind 'Adder' declares ports (like 'x.port')"]
+    );
+}
+
+#[test]
+fn node_tracking_test() {
+    let decls = "node Gain { in x: Number; out y: Number; }\n";
+
+    // Members, nested blocks and redefinitions.
+    assert_eq!(
+        check(&format!(
+            "{decls}let s.g = node \"Gain\"; if true {{ s.g.x -> s.g.x; }};"
+        ))
+        .len(),
+        1
+    );
+    assert_eq!(
+        check(&format!(
+            "{decls}fn f() {{ let g = node \"Gain\"; g.y -> g.y; }}"
+        ))
+        .len(),
+        1
+    );
+    assert_eq!(
+        check(&format!(
+            "{decls}let mut g = node \"Gain\"; g = 1; g.y -> g.y;"
+        ))
+        .len(),
+        0
+    );
+    assert_eq!(
+        check(&format!(
+            "{decls}let mut g = 1; g = node \"Gain\"; g.y -> g.y;"
+        ))
+        .len(),
+        1
+    );
+}
+
+#[test]
+fn fan_out_fan_in_test() {
+    let decls = "node Gain { in x: Number; out y: Number; }\n\
+                 let a = node \"Gain\";\n\
+                 let b = node \"Gain\";\n\
+                 let c = node \"Gain\";\n";
+
+    assert_eq!(
+        check(&format!("{decls}a.y -> [b.x, c.x]; b.y -> a.x;")),
+        [] as [String; 0]
+    );
+    assert_eq!(
+        check(&format!("{decls}[a.y, b.y] -> c.x;")),
+        ["NXS0209 t.nxs:5: input port 'c.x' is already connected (at t.nxs:5), an input accepts a single connection"]
+    );
+    assert_eq!(
+        check(&format!("{decls}a.y -> c.x;\nb.y -> c.x;")),
+        ["NXS0209 t.nxs:6: input port 'c.x' is already connected (at t.nxs:5), an input accepts a single connection"]
+    );
+
+    // Chained stages are both sink and source, which a declared port cannot be.
+    assert_eq!(
+        check(&format!("{decls}a.y -> b.x -> c.x;")),
+        ["NXS0206 t.nxs:5: 'b.x' is an input port, it cannot be a connection source"]
+    );
+
+    // A disconnected input can be connected again, and a removed node has no connected inputs.
+    assert_eq!(
+        check(&format!("{decls}a.y -> c.x; a.y -/> c.x; b.y -> c.x;")).len(),
+        0
+    );
+    assert_eq!(
+        check(&format!("{decls}a.y -> c.x; remove force c; c.x -> c.x;")).len(),
+        0
+    );
+
+    // A redefined node has no connected inputs.
+    assert_eq!(
+        check(&format!(
+            "{decls}a.y -> c.x; let c = node \"Gain\"; b.y -> c.x;"
+        ))
+        .len(),
+        0
+    );
+}
+
+#[test]
+fn library_kinds_test() {
+    let nodes = "let c = node \"Constant\";\nlet add = node \"Add\";\n";
+
+    assert_eq!(
+        check(&format!("{nodes}c.value -> add.a;")),
+        [] as [String; 0]
+    );
+    assert_eq!(
+        check(&format!("{nodes}c.value -> add.result;")),
+        ["NXS0206 t.nxs:3: 'add.result' is an output port, it cannot be a connection sink"]
+    );
+
+    // Declarations in the program take precedence.
+    assert_eq!(
+        check(&format!(
+            "node Add {{ in x: Number; out y: Number; }}\n{nodes}c.value -> add.x;"
+        )),
+        [] as [String; 0]
+    );
+}
@@ -0,0 +1,901 @@
+use crate::ast::TypeKind;
+use crate::clock::Clock;
+use crate::interpreter::Interpreter;
+use crate::minify::type_name;
+use crate::random::Random;
+use crate::runtime_error::RuntimeError;
+use crate::symbol::Symbol;
+use crate::value::{RangeValue, Value};
+use lazy_static::lazy_static;
+use std::{collections::HashMap, time::Duration};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+/// Built-in function implementation, called with the interpreter and the (arity-checked) argument values.
+pub type BuiltinFn = fn(&mut Interpreter, &[Value]) -> Result<Value, RuntimeError>;
+
+/// Built-in function, callable like any user-defined function (which take precedence).
+///
+/// Like user-defined functions, built-in functions can be called as methods on their first argument, e.g.
+///  `s.trim()` is `trim(s)`.
+#[derive(Debug)]
+pub struct Builtin {
+    pub name: &'static str,
+    pub arity: usize,
+    pub variadic: bool, // Accepts more arguments than its arity.
+    pub func: BuiltinFn,
+}
+
+/// Define a numeric built-in function from a function on `f64`.
+macro_rules! math {
+    ($name:literal, |$($arg:ident),*| $body:expr) => {
+        Builtin {
+            name: $name,
+            arity: [$(stringify!($arg)),*].len(),
+            variadic: false,
+            func: |interpreter, args| {
+                let mut i = 0..;
+                $(let $arg = number($name, args, i.next().unwrap())?;)*
+                interpreter
+                    .numeric_mode()
+                    .check(&[$($arg),*], $body, None)
+                    .map(Value::Number)
+                    .map_err(RuntimeError::Numeric)
+            },
+        }
+    };
+}
+
+/// Longest sleep on the system clock between checks for an interrupt.
+const SLEEP_SLICE: Duration = Duration::from_millis(50);
+
+/// All built-in functions, sorted by name.
+pub const BUILTINS: &[Builtin] = &[
+    math!("abs", |x| x.abs()),
+    math!("acos", |x| x.acos()),
+    Builtin {
+        name: "arg",
+        arity: 1,
+        variadic: false,
+        func: |interpreter, args| {
+            let i = number("arg", args, 0)?;
+            let count = interpreter.args().len();
+
+            match interpreter.args().get(i as usize) {
+                Some(arg) if i.fract() == 0.0 && i >= 0.0 => Ok(Value::from(arg.as_str())),
+                _ => Err(RuntimeError::InvalidArgument(format!(
+                    "program argument index {i} out of range ({count} arguments)"
+                ))),
+            }
+        },
+    },
+    Builtin {
+        name: "arg_count",
+        arity: 0,
+        variadic: false,
+        func: |interpreter, _| Ok(Value::Number(interpreter.args().len() as f64)),
+    },
+    math!("asin", |x| x.asin()),
+    math!("atan", |x| x.atan()),
+    math!("atan2", |y, x| y.atan2(x)),
+    math!("ceil", |x| x.ceil()),
+    Builtin {
+        name: "contains",
+        arity: 2,
+        variadic: false,
+        func: |_, args| match &args[0] {
+            Value::Range(r) => Ok(Value::Bool(r.contains(number("contains", args, 1)?))),
+            _ => {
+                let (s, pattern) = (string("contains", args, 0)?, string("contains", args, 1)?);
+                Ok(Value::Bool(s.contains(pattern)))
+            }
+        },
+    },
+    math!("cos", |x| x.cos()),
+    Builtin {
+        name: "ends_with",
+        arity: 2,
+        variadic: false,
+        func: |_, args| {
+            let (s, suffix) = (string("ends_with", args, 0)?, string("ends_with", args, 1)?);
+            Ok(Value::Bool(s.ends_with(suffix)))
+        },
+    },
+    Builtin {
+        name: "env",
+        arity: 1,
+        variadic: false,
+        func: |_, args| {
+            let value = std::env::var(string("env", args, 0)?).unwrap_or_default();
+            Ok(Value::from(value.as_str()))
+        },
+    },
+    Builtin {
+        name: "exit",
+        arity: 1,
+        variadic: false,
+        func: |_, args| {
+            let code = number("exit", args, 0)?;
+            if code.fract() != 0.0 || code < i32::MIN as f64 || code > i32::MAX as f64 {
+                return Err(RuntimeError::InvalidArgument(format!(
+                    "exit code must be an integer, found {code}"
+                )));
+            }
+
+            Err(RuntimeError::Exit(code as i32))
+        },
+    },
+    math!("exp", |x| x.exp()),
+    math!("floor", |x| x.floor()),
+    Builtin {
+        name: "format",
+        arity: 1,
+        variadic: true,
+        func: |_, args| format(string("format", args, 0)?, &args[1..]),
+    },
+    Builtin {
+        name: "is_finite",
+        arity: 1,
+        variadic: false,
+        func: |_, args| Ok(Value::Bool(number("is_finite", args, 0)?.is_finite())),
+    },
+    Builtin {
+        name: "is_nan",
+        arity: 1,
+        variadic: false,
+        func: |_, args| Ok(Value::Bool(number("is_nan", args, 0)?.is_nan())),
+    },
+    Builtin {
+        name: "len",
+        arity: 1,
+        variadic: false,
+        func: |_, args| match &args[0] {
+            Value::Range(r) => Ok(Value::Number(r.len() as f64)),
+            _ => Ok(Value::Number(string("len", args, 0)?.chars().count() as f64)),
+        },
+    },
+    math!("log", |x| x.ln()),
+    math!("log10", |x| x.log10()),
+    math!("log2", |x| x.log2()),
+    math!("max", |x, y| x.max(y)),
+    math!("min", |x, y| x.min(y)),
+    Builtin {
+        name: "now",
+        arity: 0,
+        variadic: false,
+        func: |interpreter, _| {
+            let now = interpreter.clock().now();
+            Ok(Value::Number(now.as_secs_f64() * 1000.0))
+        },
+    },
+    Builtin {
+        name: "panic",
+        arity: 1,
+        variadic: false,
+        func: |_, args| Err(RuntimeError::Panic(args[0].to_string())),
+    },
+    Builtin {
+        name: "pi",
+        arity: 0,
+        variadic: false,
+        func: |_, _| Ok(Value::Number(std::f64::consts::PI)),
+    },
+    math!("pow", |x, y| x.powf(y)),
+    Builtin {
+        name: "random",
+        arity: 0,
+        variadic: false,
+        func: |interpreter, _| Ok(Value::Number(interpreter.random().next_f64())),
+    },
+    Builtin {
+        name: "random_range",
+        arity: 2,
+        variadic: false,
+        func: |interpreter, args| {
+            let (a, b) = (
+                number("random_range", args, 0)?,
+                number("random_range", args, 1)?,
+            );
+            if !(a.is_finite() && b.is_finite() && a < b) {
+                return Err(RuntimeError::InvalidArgument(format!(
+                    "random range [{a}, {b}) is empty or unbounded"
+                )));
+            }
+
+            // Rounding may end up at the (exclusive) upper bound.
+            let x = a + (b - a) * interpreter.random().next_f64();
+            Ok(Value::Number(if x < b { x } else { a }))
+        },
+    },
+    Builtin {
+        name: "read_file",
+        arity: 1,
+        variadic: false,
+        func: |interpreter, args| {
+            let path = string("read_file", args, 0)?;
+            allow_io(interpreter, "read_file")?;
+
+            std::fs::read_to_string(path)
+                .map(|text| Value::from(text.as_str()))
+                .map_err(|e| RuntimeError::Io(format!("failed to read '{path}': {e}")))
+        },
+    },
+    Builtin {
+        name: "read_line",
+        arity: 0,
+        variadic: false,
+        func: |interpreter, _| {
+            allow_io(interpreter, "read_line")?;
+
+            let mut line = String::new();
+            match interpreter.input().read_line(&mut line) {
+                Ok(0) => Ok(Value::Nil), // End of input.
+                Ok(_) => {
+                    let end = line.trim_end_matches(['\n', '\r']).len();
+                    line.truncate(end);
+                    Ok(Value::from(line.as_str()))
+                }
+                Err(e) => Err(RuntimeError::Io(format!("failed to read input: {e}"))),
+            }
+        },
+    },
+    Builtin {
+        name: "replace",
+        arity: 3,
+        variadic: false,
+        func: |_, args| {
+            let s = string("replace", args, 0)?;
+            let (from, to) = (string("replace", args, 1)?, string("replace", args, 2)?);
+            Ok(Value::from(s.replace(from, to).as_str()))
+        },
+    },
+    Builtin {
+        name: "rev",
+        arity: 1,
+        variadic: false,
+        func: |_, args| Ok(Value::Range(range("rev", args, 0)?.rev())),
+    },
+    math!("round", |x| x.round()),
+    Builtin {
+        name: "seed",
+        arity: 1,
+        variadic: false,
+        func: |interpreter, args| {
+            let seed = number("seed", args, 0)?;
+            if !seed.is_finite() || seed.fract() != 0.0 {
+                return Err(RuntimeError::InvalidArgument(format!(
+                    "seed must be an integer, found {seed}"
+                )));
+            }
+
+            *interpreter.random() = Random::new(seed as i64 as u64);
+            Ok(Value::Unit)
+        },
+    },
+    math!("sin", |x| x.sin()),
+    Builtin {
+        name: "sleep",
+        arity: 1,
+        variadic: false,
+        func: |interpreter, args| {
+            let ms = number("sleep", args, 0)?;
+            let duration = Duration::try_from_secs_f64(ms / 1000.0).map_err(|_| {
+                RuntimeError::InvalidArgument(format!("cannot sleep for {ms} milliseconds"))
+            })?;
+
+            // The system clock sleeps in slices, to stop soon when interrupted. The end of a simulation ends the
+            //  program.
+            let mut remaining = duration;
+            while !remaining.is_zero() {
+                let slice = match interpreter.clock() {
+                    Clock::System => remaining.min(SLEEP_SLICE),
+                    Clock::Virtual { .. } => remaining,
+                };
+                interpreter
+                    .clock()
+                    .sleep(slice)
+                    .map_err(|_| RuntimeError::Exit(0))?;
+                interpreter.check_interrupt()?;
+                remaining -= slice;
+            }
+            Ok(Value::Unit)
+        },
+    },
+    math!("sqrt", |x| x.sqrt()),
+    Builtin {
+        name: "starts_with",
+        arity: 2,
+        variadic: false,
+        func: |_, args| {
+            let (s, prefix) = (
+                string("starts_with", args, 0)?,
+                string("starts_with", args, 1)?,
+            );
+            Ok(Value::Bool(s.starts_with(prefix)))
+        },
+    },
+    Builtin {
+        name: "step_by",
+        arity: 2,
+        variadic: false,
+        func: |_, args| {
+            let (r, n) = (range("step_by", args, 0)?, number("step_by", args, 1)?);
+            r.step_by(n).map(Value::Range).ok_or_else(|| {
+                RuntimeError::InvalidArgument(format!("range step must be positive, found {n}"))
+            })
+        },
+    },
+    Builtin {
+        name: "substring",
+        arity: 3,
+        variadic: false,
+        func: |_, args| {
+            let s = string("substring", args, 0)?;
+            let (start, end) = (index("substring", args, 1)?, index("substring", args, 2)?);
+            let len = s.chars().count();
+            if start > end || end > len {
+                return Err(RuntimeError::InvalidArgument(format!(
+                    "substring [{start}, {end}) out of range for string of length {len}"
+                )));
+            }
+
+            let sub = s.chars().skip(start).take(end - start).collect::<String>();
+            Ok(Value::from(sub.as_str()))
+        },
+    },
+    math!("tan", |x| x.tan()),
+    Builtin {
+        name: "to_bool",
+        arity: 1,
+        variadic: false,
+        func: |_, args| match &args[0] {
+            Value::Bool(b) => Ok(Value::Bool(*b)),
+            Value::String(s) => match s.trim() {
+                "true" => Ok(Value::Bool(true)),
+                "false" => Ok(Value::Bool(false)),
+                _ => Err(RuntimeError::InvalidArgument(format!(
+                    "cannot convert \"{s}\" to bool"
+                ))),
+            },
+            value => Err(RuntimeError::TypeMismatch(format!(
+                "cannot convert {} to bool",
+                value.type_name()
+            ))),
+        },
+    },
+    Builtin {
+        name: "to_lower",
+        arity: 1,
+        variadic: false,
+        func: |_, args| {
+            Ok(Value::from(
+                string("to_lower", args, 0)?.to_lowercase().as_str(),
+            ))
+        },
+    },
+    Builtin {
+        name: "to_number",
+        arity: 1,
+        variadic: false,
+        func: |_, args| convert(&args[0], &TypeKind::Number),
+    },
+    Builtin {
+        name: "to_string",
+        arity: 1,
+        variadic: false,
+        func: |_, args| convert(&args[0], &TypeKind::String),
+    },
+    Builtin {
+        name: "to_upper",
+        arity: 1,
+        variadic: false,
+        func: |_, args| {
+            Ok(Value::from(
+                string("to_upper", args, 0)?.to_uppercase().as_str(),
+            ))
+        },
+    },
+    Builtin {
+        name: "trim",
+        arity: 1,
+        variadic: false,
+        func: |_, args| Ok(Value::from(string("trim", args, 0)?.trim())),
+    },
+    Builtin {
+        name: "type_of",
+        arity: 1,
+        variadic: false,
+        func: |_, args| Ok(Value::from(args[0].type_name())),
+    },
+    Builtin {
+        name: "write_file",
+        arity: 2,
+        variadic: false,
+        func: |interpreter, args| {
+            let path = string("write_file", args, 0)?;
+            let text = string("write_file", args, 1)?;
+            allow_io(interpreter, "write_file")?;
+
+            std::fs::write(path, text)
+                .map(|_| Value::Unit)
+                .map_err(|e| RuntimeError::Io(format!("failed to write '{path}': {e}")))
+        },
+    },
+];
+
+/// Names of the built-in functions on strings and ranges, typically called as methods (e.g. `s.trim()`), sorted.
+pub const METHODS: &[&str] = &[
+    "contains",
+    "ends_with",
+    "len",
+    "replace",
+    "rev",
+    "starts_with",
+    "step_by",
+    "substring",
+    "to_lower",
+    "to_upper",
+    "trim",
+];
+
+lazy_static! {
+    /// Built-in functions by (interned) name, so finding a built-in function does not look up symbol strings.
+    static ref BUILTIN_SYMBOLS: HashMap<Symbol, &'static Builtin> =
+        BUILTINS.iter().map(|b| (Symbol::intern(b.name), b)).collect();
+}
+
+/// Find a built-in function by name.
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::{builtins::builtin, interpreter::Interpreter, symbol::Symbol, value::Value};
+///
+/// let sqrt = builtin(Symbol::intern("sqrt")).unwrap();
+///
+/// assert_eq!(sqrt.arity, 1);
+/// assert_eq!((sqrt.func)(&mut Interpreter::new(), &[Value::Number(9.0)]), Ok(Value::Number(3.0)));
+/// assert!(builtin(Symbol::intern("nope")).is_none());
+/// ```
+pub fn builtin(id: Symbol) -> Option<&'static Builtin> {
+    BUILTIN_SYMBOLS.get(&id).copied()
+}
+
+/// Check if file and input access is allowed.
+fn allow_io(interpreter: &Interpreter, name: &str) -> Result<(), RuntimeError> {
+    match interpreter.has_io() {
+        true => Ok(()),
+        false => Err(RuntimeError::IoDenied(Symbol::intern(name))),
+    }
+}
+
+/// Get a numeric argument.
+fn number(name: &str, args: &[Value], i: usize) -> Result<f64, RuntimeError> {
+    match &args[i] {
+        Value::Number(n) => Ok(*n),
+        value => Err(RuntimeError::TypeMismatch(format!(
+            "argument {} of '{name}' must be Number, found {}",
+            i + 1,
+            value.type_name()
+        ))),
+    }
+}
+
+/// Get a range argument.
+/// Convert a value to another type (see `to_number` and `to_string`, and cast expressions like `x as Number`).
+///
+/// Any value converts to its printed form as `String`. A `String` converts to a (finite) `Number` or `bool` if it
+///  holds one (ignoring surrounding whitespace). Numbers and bools do not convert into each other: there is no
+///  truthiness.
+pub(crate) fn convert(value: &Value, typeid: &TypeKind) -> Result<Value, RuntimeError> {
+    let failed = || {
+        RuntimeError::InvalidArgument(format!(
+            "cannot convert \"{value}\" to {}",
+            type_name(typeid)
+        ))
+    };
+
+    match (value, typeid) {
+        (Value::Number(_), TypeKind::Number) | (Value::Bool(_), TypeKind::Bool) => {
+            Ok(value.clone())
+        }
+        (Value::String(_), TypeKind::String) => Ok(value.clone()),
+        (value, TypeKind::String) => Ok(Value::from(value.to_string().as_str())),
+        (Value::String(s), TypeKind::Number) => match s.trim().parse::<f64>() {
+            Ok(n) if n.is_finite() => Ok(Value::Number(n)),
+            _ => Err(failed()),
+        },
+        (Value::String(s), TypeKind::Bool) => match s.trim() {
+            "true" => Ok(Value::Bool(true)),
+            "false" => Ok(Value::Bool(false)),
+            _ => Err(failed()),
+        },
+        (value, typeid) => Err(RuntimeError::TypeMismatch(format!(
+            "cannot convert {} to {}",
+            value.type_name(),
+            type_name(typeid)
+        ))),
+    }
+}
+
+fn range(name: &str, args: &[Value], i: usize) -> Result<RangeValue, RuntimeError> {
+    match &args[i] {
+        Value::Range(r) => Ok(*r),
+        value => Err(RuntimeError::TypeMismatch(format!(
+            "argument {} of '{name}' must be Range, found {}",
+            i + 1,
+            value.type_name()
+        ))),
+    }
+}
+
+/// Format a string, replacing each `{}` in the template by the next value (`{{` and `}}` are escaped braces).
+fn format(template: &str, values: &[Value]) -> Result<Value, RuntimeError> {
+    let error = |reason: &str| {
+        Err(RuntimeError::InvalidArgument(format!(
+            "format string {reason}"
+        )))
+    };
+
+    let mut result = String::with_capacity(template.len());
+    let mut values = values.iter();
+    let mut chars = template.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('{', Some('}')) => match values.next() {
+                Some(value) => result.push_str(&value.to_string()),
+                None => return error("has more placeholders than values"),
+            },
+            ('{', Some('{')) | ('}', Some('}')) => result.push(c),
+            ('{' | '}', _) => return error(&format!("has an unmatched '{c}'")),
+            _ => {
+                result.push(c);
+                continue;
+            }
+        }
+        chars.next();
+    }
+
+    match values.next() {
+        Some(_) => error("has fewer placeholders than values"),
+        None => Ok(Value::from(result.as_str())),
+    }
+}
+
+/// Get a (character) index argument.
+fn index(name: &str, args: &[Value], i: usize) -> Result<usize, RuntimeError> {
+    match number(name, args, i)? {
+        n if n >= 0.0 && n.fract() == 0.0 && n.is_finite() => Ok(n as usize),
+        n => Err(RuntimeError::InvalidArgument(format!(
+            "argument {} of '{name}' must be an index, found {n}",
+            i + 1
+        ))),
+    }
+}
+
+/// Get a string argument.
+fn string<'v>(name: &str, args: &'v [Value], i: usize) -> Result<&'v str, RuntimeError> {
+    match &args[i] {
+        Value::String(s) => Ok(s),
+        value => Err(RuntimeError::TypeMismatch(format!(
+            "argument {} of '{name}' must be String, found {}",
+            i + 1,
+            value.type_name()
+        ))),
+    }
+}
+
+#[test]
+fn builtins_test() {
+    assert!(BUILTINS.windows(2).all(|w| w[0].name < w[1].name));
+    assert!(METHODS.windows(2).all(|w| w[0] < w[1]));
+    assert!(METHODS
+        .iter()
+        .all(|m| builtin(Symbol::intern(m)).is_some_and(|b| b.arity >= 1)));
+
+    let mut interpreter = Interpreter::new()
+        .with_args(vec!["a".to_owned(), "b".to_owned()])
+        .with_input(std::io::empty());
+    let mut call = |name: &str, args: &[f64]| {
+        let b = builtin(Symbol::intern(name)).unwrap();
+        assert_eq!(b.arity, args.len(), "{name}");
+
+        let args = args.iter().map(|n| Value::Number(*n)).collect::<Vec<_>>();
+        match (b.func)(&mut interpreter, &args) {
+            Ok(Value::Number(n)) => n,
+            result => panic!("{name}: {result:?}"),
+        }
+    };
+
+    assert_eq!(call("abs", &[-2.0]), 2.0);
+    assert_eq!(call("atan2", &[1.0, 0.0]), std::f64::consts::FRAC_PI_2);
+    assert_eq!(call("floor", &[1.5]), 1.0);
+    assert_eq!(call("log", &[1.0]), 0.0);
+    assert_eq!(call("log2", &[8.0]), 3.0);
+    assert_eq!(call("max", &[1.0, 2.0]), 2.0);
+    assert_eq!(call("pi", &[]), std::f64::consts::PI);
+    assert_eq!(call("pow", &[2.0, 10.0]), 1024.0);
+    assert!(call("sqrt", &[-1.0]).is_nan());
+    assert_eq!(call("arg_count", &[]), 2.0);
+    assert!(call("now", &[]) > 1.7e12);
+
+    let mut call = |name: &str, args: &[Value]| {
+        (builtin(Symbol::intern(name)).unwrap().func)(&mut interpreter, args)
+    };
+
+    assert_eq!(call("arg", &[Value::Number(1.0)]), Ok(Value::from("b")));
+    assert_eq!(
+        call("exit", &[Value::Number(3.0)]),
+        Err(RuntimeError::Exit(3))
+    );
+    assert!(matches!(
+        call("exit", &[Value::Number(1.5)]),
+        Err(RuntimeError::InvalidArgument(_))
+    ));
+    assert_eq!(
+        call("panic", &[Value::Number(1.0)]),
+        Err(RuntimeError::Panic("1".to_owned()))
+    );
+    assert_eq!(
+        call("env", &[Value::from("NEXUS_SURELY_UNSET_VARIABLE")]),
+        Ok(Value::from(""))
+    );
+    assert_eq!(call("read_line", &[]), Ok(Value::Nil));
+    assert_eq!(call("sleep", &[Value::Number(0.0)]), Ok(Value::Unit));
+    for ms in [-1.0, f64::NAN] {
+        assert!(matches!(
+            call("sleep", &[Value::Number(ms)]),
+            Err(RuntimeError::InvalidArgument(_))
+        ));
+    }
+    for i in [2.0, -1.0, 0.5] {
+        assert!(
+            matches!(
+                call("arg", &[Value::Number(i)]),
+                Err(RuntimeError::InvalidArgument(_))
+            ),
+            "{i}"
+        );
+    }
+    assert_eq!(
+        call("pow", &[Value::Number(1.0), Value::from("2")]),
+        Err(RuntimeError::TypeMismatch(
+            "argument 2 of 'pow' must be Number, found String".to_owned()
+        ))
+    );
+}
+
+#[test]
+fn io_test() {
+    let path = std::env::temp_dir().join(format!("nexus-io-test-{}.txt", std::process::id()));
+    let path = Value::from(path.to_str().unwrap());
+
+    let mut interpreter = Interpreter::new().with_input("first\r\nsecond".as_bytes());
+    let mut call = |name: &str, args: &[Value]| {
+        (builtin(Symbol::intern(name)).unwrap().func)(&mut interpreter, args)
+    };
+
+    assert_eq!(call("read_line", &[]), Ok(Value::from("first")));
+    assert_eq!(call("read_line", &[]), Ok(Value::from("second")));
+    assert_eq!(call("read_line", &[]), Ok(Value::Nil));
+    let args = [path.clone(), Value::from("text")];
+    assert_eq!(call("write_file", &args), Ok(Value::Unit));
+    assert_eq!(call("read_file", &args[..1]), Ok(Value::from("text")));
+
+    std::fs::remove_file(path.to_string()).unwrap();
+    assert!(matches!(
+        call("read_file", &args[..1]),
+        Err(RuntimeError::Io(_))
+    ));
+
+    let mut interpreter = Interpreter::new().with_io(false);
+    let mut call = |name: &str, args: &[Value]| {
+        (builtin(Symbol::intern(name)).unwrap().func)(&mut interpreter, args)
+    };
+
+    for (name, args) in [
+        ("read_line", vec![]),
+        ("read_file", vec![path.clone()]),
+        ("write_file", vec![path.clone(), Value::from("text")]),
+    ] {
+        assert_eq!(
+            call(name, &args),
+            Err(RuntimeError::IoDenied(Symbol::intern(name)))
+        );
+    }
+    assert!(!std::path::Path::new(&path.to_string()).exists());
+}
+
+#[test]
+fn random_test() {
+    let mut interpreter = Interpreter::new();
+    let mut call = |name: &str, args: &[Value]| {
+        (builtin(Symbol::intern(name)).unwrap().func)(&mut interpreter, args)
+    };
+    let mut draw = |seed: f64| {
+        assert_eq!(call("seed", &[Value::Number(seed)]), Ok(Value::Unit));
+        [call("random", &[]), call("random", &[])]
+    };
+
+    // Seeding makes the sequence reproducible.
+    assert_eq!(draw(42.0), draw(42.0));
+    assert_ne!(draw(42.0), draw(-42.0));
+
+    for (a, b) in [
+        (0.0, 1.0),
+        (-2.5, -2.0),
+        (1.0, 1.0 + f64::EPSILON),
+        (0.0, 1e300),
+    ] {
+        for _ in 0..100 {
+            match call("random_range", &[Value::Number(a), Value::Number(b)]) {
+                Ok(Value::Number(x)) => assert!(a <= x && x < b, "{x} in [{a}, {b})"),
+                result => panic!("{result:?}"),
+            }
+        }
+    }
+
+    for (a, b) in [
+        (1.0, 1.0),
+        (2.0, 1.0),
+        (0.0, f64::INFINITY),
+        (f64::NAN, 1.0),
+    ] {
+        assert!(matches!(
+            call("random_range", &[Value::Number(a), Value::Number(b)]),
+            Err(RuntimeError::InvalidArgument(_))
+        ));
+    }
+    for seed in [0.5, f64::NAN, f64::INFINITY] {
+        assert!(matches!(
+            call("seed", &[Value::Number(seed)]),
+            Err(RuntimeError::InvalidArgument(_))
+        ));
+    }
+    assert!(matches!(
+        call("random_range", &[Value::Number(0.0), Value::Bool(true)]),
+        Err(RuntimeError::TypeMismatch(_))
+    ));
+    assert!(matches!(
+        call("seed", &[Value::from("1")]),
+        Err(RuntimeError::TypeMismatch(_))
+    ));
+}
+
+#[test]
+fn string_test() {
+    let mut interpreter = Interpreter::new();
+    let mut call = |name: &str, args: &[&str]| {
+        let args = args.iter().map(|a| Value::from(*a)).collect::<Vec<_>>();
+        (builtin(Symbol::intern(name)).unwrap().func)(&mut interpreter, &args)
+    };
+
+    assert_eq!(call("len", &["héllo ✓"]), Ok(Value::Number(7.0)));
+    assert_eq!(call("trim", &[" \tab \n"]), Ok(Value::from("ab")));
+    assert_eq!(call("to_upper", &["straße"]), Ok(Value::from("STRASSE")));
+    assert_eq!(call("to_lower", &["ÀB"]), Ok(Value::from("àb")));
+    assert_eq!(call("contains", &["abc", "bc"]), Ok(Value::Bool(true)));
+    assert_eq!(call("starts_with", &["abc", "b"]), Ok(Value::Bool(false)));
+    assert_eq!(call("ends_with", &["abc", ""]), Ok(Value::Bool(true)));
+    assert_eq!(
+        call("replace", &["a-b-c", "-", "→"]),
+        Ok(Value::from("a→b→c"))
+    );
+    assert_eq!(call("format", &["{{{}}}", "x"]), Ok(Value::from("{x}")));
+
+    let mut call = |name: &str, args: &[Value]| {
+        (builtin(Symbol::intern(name)).unwrap().func)(&mut interpreter, args)
+    };
+    let substring =
+        |s: &str, start: f64, end: f64| [Value::from(s), Value::Number(start), Value::Number(end)];
+
+    assert_eq!(
+        call("substring", &substring("héllo", 1.0, 3.0)),
+        Ok(Value::from("él"))
+    );
+    assert_eq!(
+        call("substring", &substring("héllo", 5.0, 5.0)),
+        Ok(Value::from(""))
+    );
+    for (start, end) in [(2.0, 1.0), (0.0, 6.0), (-1.0, 1.0), (0.5, 1.0)] {
+        assert!(matches!(
+            call("substring", &substring("héllo", start, end)),
+            Err(RuntimeError::InvalidArgument(_))
+        ));
+    }
+
+    let format = |template: &str, values: &[Value]| format(template, values);
+    assert_eq!(
+        format(
+            "{} + {} = {}{{}}",
+            &[Value::Number(1.0), Value::from("x"), Value::Bool(true)]
+        ),
+        Ok(Value::from("1 + x = true{}"))
+    );
+    for template in ["{}{}", "", "{", "a}b"] {
+        assert!(matches!(
+            format(template, &[Value::Unit]),
+            Err(RuntimeError::InvalidArgument(_))
+        ));
+    }
+    assert!(matches!(
+        call("len", &[Value::Number(1.0)]),
+        Err(RuntimeError::TypeMismatch(_))
+    ));
+}
+
+#[test]
+fn conversion_test() {
+    let mut interpreter = Interpreter::new();
+    let mut call = |name: &str, arg: Value| {
+        (builtin(Symbol::intern(name)).unwrap().func)(&mut interpreter, &[arg])
+    };
+
+    assert_eq!(call("type_of", Value::Unit), Ok(Value::from("()")));
+    assert_eq!(call("type_of", Value::Bool(true)), Ok(Value::from("bool")));
+    assert_eq!(
+        call("type_of", Value::Number(1.0)),
+        Ok(Value::from("Number"))
+    );
+    assert_eq!(call("type_of", Value::from("")), Ok(Value::from("String")));
+    assert_eq!(
+        call("is_nan", Value::Number(f64::NAN)),
+        Ok(Value::Bool(true))
+    );
+    assert_eq!(
+        call("is_finite", Value::Number(f64::NEG_INFINITY)),
+        Ok(Value::Bool(false))
+    );
+    assert!(matches!(
+        call("is_nan", Value::from("NaN")),
+        Err(RuntimeError::TypeMismatch(_))
+    ));
+
+    assert_eq!(
+        call("to_string", Value::Number(1.5)),
+        Ok(Value::from("1.5"))
+    );
+    assert_eq!(
+        call("to_string", Value::Bool(false)),
+        Ok(Value::from("false"))
+    );
+    assert_eq!(call("to_string", Value::from("x")), Ok(Value::from("x")));
+
+    assert_eq!(
+        call("to_number", Value::from(" -1.5e3\n")),
+        Ok(Value::Number(-1500.0))
+    );
+    assert_eq!(
+        call("to_number", Value::Number(2.0)),
+        Ok(Value::Number(2.0))
+    );
+    for s in ["", "1x", "1 2", "inf", "NaN", "1e999"] {
+        assert!(
+            matches!(
+                call("to_number", Value::from(s)),
+                Err(RuntimeError::InvalidArgument(_))
+            ),
+            "{s}"
+        );
+    }
+
+    assert_eq!(call("to_bool", Value::from("true ")), Ok(Value::Bool(true)));
+    assert_eq!(call("to_bool", Value::Bool(false)), Ok(Value::Bool(false)));
+    assert!(matches!(
+        call("to_bool", Value::from("yes")),
+        Err(RuntimeError::InvalidArgument(_))
+    ));
+
+    for name in ["to_number", "to_bool"] {
+        assert!(matches!(
+            call(name, Value::Unit),
+            Err(RuntimeError::TypeMismatch(_))
+        ));
+    }
+    assert!(matches!(
+        call("to_bool", Value::Number(1.0)),
+        Err(RuntimeError::TypeMismatch(_))
+    ));
+}
@@ -0,0 +1,135 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+/// Clock of the time built-in functions (`now()` and `sleep(ms)`).
+///
+/// A virtual clock simulates time: sleeping advances the clock instantly, so a program (e.g. polling on a timer) runs
+///  deterministically, and as fast as possible. The simulation ends when the clock would pass its end time.
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::clock::Clock;
+/// use std::time::Duration;
+///
+/// let mut clock = Clock::simulated(Some(Duration::from_millis(100)));
+///
+/// assert_eq!(clock.sleep(Duration::from_millis(60)), Ok(()));
+/// assert_eq!(clock.now(), Duration::from_millis(60));
+/// assert_eq!(clock.sleep(Duration::from_millis(60)), Err(Duration::from_millis(100)));
+/// assert_eq!(clock.now(), Duration::from_millis(100));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Clock {
+    #[default]
+    System,
+    Virtual {
+        now: Duration,           // Time since the start of the simulation.
+        until: Option<Duration>, // End time of the simulation.
+    },
+}
+
+impl Clock {
+    /// Create a virtual clock, starting at zero and ending at the given time (if any).
+    pub fn simulated(until: Option<Duration>) -> Self {
+        Clock::Virtual {
+            now: Duration::ZERO,
+            until,
+        }
+    }
+
+    /// Get the current time: since the UNIX epoch for the system clock, or since the start of the simulation.
+    pub fn now(&self) -> Duration {
+        match self {
+            Clock::System => SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default(),
+            Clock::Virtual { now, .. } => *now,
+        }
+    }
+
+    /// Sleep for a duration, or advance the virtual clock.
+    ///
+    /// Returns the end time of the simulation if sleeping would pass it (advancing the clock to the end time).
+    pub fn sleep(&mut self, duration: Duration) -> Result<(), Duration> {
+        match self {
+            Clock::System => std::thread::sleep(duration),
+            Clock::Virtual { now, until } => {
+                *now = now.saturating_add(duration);
+                if let Some(end) = until.filter(|end| *now > *end) {
+                    *now = end;
+                    return Err(end);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Parse a duration with a unit (`ms`, `s`, `min` or `h`, e.g. `1000ms` or `1.5s`), or in milliseconds without unit.
+pub fn parse_duration(input: &str) -> Result<Duration, String> {
+    let input = input.trim();
+    let split = input
+        .find(|c: char| c.is_ascii_alphabetic())
+        .unwrap_or(input.len());
+    let (value, unit) = input.split_at(split);
+
+    let scale = match unit {
+        "" | "ms" => 0.001,
+        "s" => 1.0,
+        "min" => 60.0,
+        "h" => 3600.0,
+        _ => return Err(format!("unknown time unit '{unit}' (use ms, s, min or h)")),
+    };
+
+    value
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .and_then(|v| Duration::try_from_secs_f64(v * scale).ok())
+        .ok_or_else(|| format!("invalid duration '{input}'"))
+}
+
+#[test]
+fn clock_test() {
+    let mut clock = Clock::simulated(None);
+
+    assert_eq!(clock.now(), Duration::ZERO);
+    assert_eq!(clock.sleep(Duration::from_secs(3600)), Ok(()));
+    assert_eq!(clock.sleep(Duration::MAX), Ok(()));
+    assert_eq!(clock.now(), Duration::MAX);
+
+    // Reaching the end time exactly does not end the simulation, passing it does.
+    let mut clock = Clock::simulated(Some(Duration::from_millis(10)));
+    assert_eq!(clock.sleep(Duration::from_millis(10)), Ok(()));
+    assert_eq!(
+        clock.sleep(Duration::from_nanos(1)),
+        Err(Duration::from_millis(10))
+    );
+
+    assert!(Clock::System.now() > Duration::ZERO);
+}
+
+#[test]
+fn parse_duration_test() {
+    assert_eq!(parse_duration("1000ms"), Ok(Duration::from_secs(1)));
+    assert_eq!(parse_duration("250"), Ok(Duration::from_millis(250)));
+    assert_eq!(parse_duration("1.5s"), Ok(Duration::from_millis(1500)));
+    assert_eq!(parse_duration("2 min"), Ok(Duration::from_secs(120)));
+    assert_eq!(parse_duration("1h"), Ok(Duration::from_secs(3600)));
+    assert_eq!(
+        parse_duration("5d"),
+        Err("unknown time unit 'd' (use ms, s, min or h)".to_owned())
+    );
+    assert_eq!(
+        parse_duration("-1s"),
+        Err("invalid duration '-1s'".to_owned())
+    );
+    assert_eq!(
+        parse_duration("ms"),
+        Err("invalid duration 'ms'".to_owned())
+    );
+}
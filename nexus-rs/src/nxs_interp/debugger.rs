@@ -0,0 +1,451 @@
+use crate::ast::{ExprKind, Stmt, StmtKind, Stmts};
+use crate::front::parse_source;
+use crate::interpreter::{ExecEvent, Interpreter};
+use crate::source_line::SourceLine;
+use std::{
+    collections::BTreeSet,
+    io::{self, BufRead, Write},
+    str::FromStr,
+};
+use thiserror::Error;
+
+#[cfg(test)]
+use crate::driver::{Driver, DriverOptions};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+/// Debugger prompt.
+const PROMPT: &str = "(nxdb) ";
+
+/// Debugger command help.
+const HELP: &str = "\
+break <line>    set a breakpoint (without line: list the breakpoints)
+delete <line>   remove a breakpoint
+step            run to the next statement, entering function calls
+next            run to the next statement, stepping over function calls
+continue        run to the next breakpoint
+print <expr>    evaluate an expression in the current scope
+help            show this help";
+
+/// Debugger command.
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Break(Option<usize>), // Set a breakpoint at a line, or list the breakpoints.
+    Delete(usize),
+    Step,
+    Next,
+    Continue,
+    Print(String),
+    Help,
+}
+
+/// Debugger command error representation.
+#[derive(Error, Debug, PartialEq)]
+pub enum CommandError {
+    #[error("unknown command '{0}' (try 'help')")]
+    Unknown(String),
+
+    #[error("invalid line number '{0}'")]
+    InvalidLine(String),
+
+    #[error("missing {0}")]
+    Missing(&'static str),
+}
+
+impl FromStr for Command {
+    type Err = CommandError;
+
+    /// Parse a command, which may be abbreviated to its first letter (e.g. `b 12` for `break 12`).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, arg) = match s.trim().split_once(char::is_whitespace) {
+            Some((name, arg)) => (name, arg.trim()),
+            None => (s.trim(), ""),
+        };
+
+        let line = || match arg {
+            "" => Err(CommandError::Missing("line number")),
+            _ => arg
+                .parse()
+                .map_err(|_| CommandError::InvalidLine(arg.to_owned())),
+        };
+
+        match name {
+            "break" | "b" if arg.is_empty() => Ok(Command::Break(None)),
+            "break" | "b" => line().map(|l| Command::Break(Some(l))),
+            "delete" | "d" => line().map(Command::Delete),
+            "step" | "s" => Ok(Command::Step),
+            "next" | "n" => Ok(Command::Next),
+            "continue" | "c" => Ok(Command::Continue),
+            "print" | "p" if arg.is_empty() => Err(CommandError::Missing("expression")),
+            "print" | "p" => Ok(Command::Print(arg.to_owned())),
+            "help" | "h" => Ok(Command::Help),
+            _ => Err(CommandError::Unknown(name.to_owned())),
+        }
+    }
+}
+
+/// Execution mode, deciding where to pause next.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Mode {
+    Step,
+    Next(usize), // Pause at a call depth up to the given depth.
+    Continue,
+}
+
+/// Interactive debugger, pausing the interpreter at breakpoints and stepping through statements.
+///
+/// The debugger is driven by an execution hook (see [`Interpreter::on_exec`]), and pauses before the first statement.
+///  While paused, it shows the current statement in the source and reads commands (see [`Command`]) until execution
+///  is resumed. At the end of the input, the debugger detaches and the program runs to completion.
+///
+/// Line numbers refer to the given source lines, i.e. the main source file. Statements in used files (see
+///  [`Span::file`](crate::span::Span)) are run without pausing.
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::{debugger::Debugger, driver::*, source_line::SourceLine};
+///
+/// let source = "let x = 6 * 7;\nprintln x;";
+/// let lines = source
+///     .lines()
+///     .enumerate()
+///     .map(|(i, l)| SourceLine { line: l.to_owned(), number: Some(i + 1) })
+///     .collect();
+///
+/// let mut output = Vec::new();
+/// let mut debugger = Debugger::with_io(lines, "step\nprint x\ncontinue\n".as_bytes(), &mut output);
+///
+/// Driver::with_output(DriverOptions::default(), Vec::new())
+///     .on_exec(|interpreter, event| debugger.on_exec(interpreter, event))
+///     .run_source("main.nxs", source);
+///
+/// drop(debugger);
+/// assert!(String::from_utf8(output).unwrap().contains("(nxdb) = 42"));
+/// ```
+pub struct Debugger<'d> {
+    lines: Vec<SourceLine>,
+    breakpoints: BTreeSet<usize>,
+    mode: Mode,
+    input: Box<dyn BufRead + 'd>,
+    output: Box<dyn Write + 'd>,
+}
+
+impl Debugger<'static> {
+    /// Create a new debugger for a source, reading commands from the standard input and writing to standard error.
+    pub fn new(lines: Vec<SourceLine>) -> Self {
+        Self::with_io(lines, io::BufReader::new(io::stdin()), io::stderr())
+    }
+}
+
+impl<'d> Debugger<'d> {
+    /// Create a new debugger for a source, reading commands from the given input and writing to the given output.
+    pub fn with_io(
+        lines: Vec<SourceLine>,
+        input: impl BufRead + 'd,
+        output: impl Write + 'd,
+    ) -> Self {
+        Debugger {
+            lines,
+            breakpoints: BTreeSet::new(),
+            mode: Mode::Step,
+            input: Box::new(input),
+            output: Box::new(output),
+        }
+    }
+
+    /// Execution hook: pause before a statement if stepping onto it or at a breakpoint.
+    ///
+    /// Declarations and blocks are not paused at, as there is nothing to step over.
+    pub fn on_exec<'a>(&mut self, interpreter: &mut Interpreter<'a>, event: ExecEvent<'a>) {
+        let ExecEvent::Stmt(stmt) = event else {
+            return;
+        };
+
+        if matches!(
+            stmt.kind,
+            StmtKind::Block(_)
+                | StmtKind::EnumDecl(_)
+                | StmtKind::FunctionDecl(_)
+                | StmtKind::GroupDecl(_)
+                | StmtKind::NodeDecl(_)
+                | StmtKind::UseDecl(_)
+        ) {
+            return;
+        }
+
+        let Some(span) = stmt.span.filter(|s| s.file == 0) else {
+            return;
+        };
+
+        let at_breakpoint = span.line.is_some_and(|l| self.breakpoints.contains(&l));
+        let pause = at_breakpoint
+            || match self.mode {
+                Mode::Step => true,
+                Mode::Next(depth) => interpreter.call_depth() <= depth,
+                Mode::Continue => false,
+            };
+
+        if !pause {
+            return;
+        }
+
+        match self.lines.iter().find(|l| l.number == span.line) {
+            Some(line) => self.write(&line.render_snippet(span.start, span.end, "paused")),
+            None => self.write("paused"),
+        }
+
+        // Output errors are ignored, as they cannot be reported anywhere else.
+        while let Some(input) = self.read() {
+            match input.parse::<Command>() {
+                Ok(Command::Break(Some(line))) => {
+                    self.breakpoints.insert(line);
+                    self.write(&format!("breakpoint set at line {line}"));
+                }
+                Ok(Command::Break(None)) if self.breakpoints.is_empty() => {
+                    self.write("no breakpoints")
+                }
+                Ok(Command::Break(None)) => {
+                    let lines: Vec<_> = self.breakpoints.iter().map(|l| l.to_string()).collect();
+                    self.write(&format!("breakpoints at line {}", lines.join(", ")));
+                }
+                Ok(Command::Delete(line)) => match self.breakpoints.remove(&line) {
+                    true => self.write(&format!("breakpoint removed at line {line}")),
+                    false => self.write(&format!("no breakpoint at line {line}")),
+                },
+                Ok(Command::Step) => {
+                    self.mode = Mode::Step;
+                    return;
+                }
+                Ok(Command::Next) => {
+                    self.mode = Mode::Next(interpreter.call_depth());
+                    return;
+                }
+                Ok(Command::Continue) => {
+                    self.mode = Mode::Continue;
+                    return;
+                }
+                Ok(Command::Print(expr)) => {
+                    let result = Self::evaluate(interpreter, &expr);
+                    self.write(&result);
+                }
+                Ok(Command::Help) => self.write(HELP),
+                Err(e) => self.write(&e.to_string()),
+            }
+        }
+
+        self.write("detached");
+        self.breakpoints.clear();
+        self.mode = Mode::Continue;
+    }
+
+    /// Evaluate an expression in the current scope of the interpreter, rendering its value or error.
+    fn evaluate(interpreter: &mut Interpreter, expr: &str) -> String {
+        let ast = match parse_source(&format!("{expr};")) {
+            Ok(ast) => ast,
+            Err(e) => return e.to_string(),
+        };
+
+        // The AST is leaked, as the interpreter may keep references into evaluated code (e.g. functions declared in a
+        //  block). This is fine for the occasional expression typed at the debugger prompt.
+        let ast: &'static Stmts = Box::leak(Box::new(ast));
+
+        match ast.as_slice() {
+            [Stmt {
+                kind: StmtKind::Expr(e),
+                ..
+            }] if !matches!(e.kind, ExprKind::Empty()) => match interpreter.evaluate(e) {
+                Ok(value) => format!("= {value}"),
+                Err(e) => format!("error[{}]: {e}", e.code()),
+            },
+            _ => "expected an expression".to_owned(),
+        }
+    }
+
+    /// Prompt for and read a line of input, returning `None` at the end of the input.
+    fn read(&mut self) -> Option<String> {
+        let _ = write!(self.output, "{PROMPT}");
+        let _ = self.output.flush();
+
+        let mut input = String::new();
+        match self.input.read_line(&mut input) {
+            Ok(0) | Err(_) => {
+                let _ = writeln!(self.output);
+                None
+            }
+            Ok(_) => Some(input),
+        }
+    }
+
+    fn write(&mut self, text: &str) {
+        let _ = writeln!(self.output, "{text}");
+    }
+}
+
+#[test]
+fn command_test() {
+    assert_eq!("break 12".parse(), Ok(Command::Break(Some(12))));
+    assert_eq!(" b  3 ".parse(), Ok(Command::Break(Some(3))));
+    assert_eq!("break".parse(), Ok(Command::Break(None)));
+    assert_eq!("d 3".parse(), Ok(Command::Delete(3)));
+    assert_eq!("s".parse(), Ok(Command::Step));
+    assert_eq!("next".parse(), Ok(Command::Next));
+    assert_eq!("c\n".parse(), Ok(Command::Continue));
+    assert_eq!(
+        "print x + 1".parse(),
+        Ok(Command::Print("x + 1".to_owned()))
+    );
+    assert_eq!("help".parse(), Ok(Command::Help));
+
+    assert_eq!(
+        "break x".parse::<Command>(),
+        Err(CommandError::InvalidLine("x".to_owned()))
+    );
+    assert_eq!(
+        "delete".parse::<Command>(),
+        Err(CommandError::Missing("line number"))
+    );
+    assert_eq!(
+        "p".parse::<Command>(),
+        Err(CommandError::Missing("expression"))
+    );
+    assert_eq!(
+        "run".parse::<Command>(),
+        Err(CommandError::Unknown("run".to_owned()))
+    );
+}
+
+#[test]
+fn debugger_test() {
+    let source = "\
+fn sq(x: Number) -> Number {
+    let y = x * x;
+    y
+}
+let a = sq(3);
+let b = sq(a);
+println a + b;";
+
+    let lines = source
+        .lines()
+        .enumerate()
+        .map(|(i, l)| SourceLine {
+            line: l.to_owned(),
+            number: Some(i + 1),
+        })
+        .collect();
+
+    let commands = "\
+break 2
+continue
+print x + 1
+next
+print y
+step
+break
+delete 2
+break
+print undefined
+print let
+next
+print a + b
+";
+
+    let (mut output, mut program_output) = (Vec::new(), Vec::new());
+    let mut debugger = Debugger::with_io(lines, commands.as_bytes(), &mut output);
+
+    let session = Driver::with_output(DriverOptions::default(), &mut program_output)
+        .on_exec(|interpreter, event| debugger.on_exec(interpreter, event))
+        .run_source("main.nxs", source);
+
+    drop(debugger);
+    assert!(!session.has_errors());
+    assert_eq!(program_output, b"90\n");
+
+    // Keep the source line of each pause, and the command output.
+    let output = String::from_utf8(output).unwrap();
+    let transcript: Vec<_> = output
+        .lines()
+        .map(|l| l.trim_start_matches(PROMPT))
+        .filter(|l| !l.is_empty() && !l.starts_with("   |"))
+        .collect();
+
+    assert_eq!(
+        transcript,
+        [
+            " 5 | let a = sq(3);",
+            "breakpoint set at line 2",
+            " 2 |     let y = x * x;",
+            "= 4",
+            " 3 |     y",
+            "= 9",
+            " 6 | let b = sq(a);",
+            "breakpoints at line 2",
+            "breakpoint removed at line 2",
+            "no breakpoints",
+            "error[NXS0314]: undefined variable 'undefined'",
+            "expected an expression",
+            " 7 | println a + b;",
+            "= 90",
+            "detached",
+        ]
+    );
+}
+
+#[test]
+fn debugger_used_file_test() {
+    let dir = std::env::temp_dir().join(format!("nexus_debugger_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("lib.nxs"),
+        "fn sq(x: Number) -> Number {\n\n\n    x * x\n}",
+    )
+    .unwrap();
+
+    let source = "\
+use \"lib.nxs\";
+let a = sq(3);
+let b = sq(a);
+println a + b;";
+
+    let lines = source
+        .lines()
+        .enumerate()
+        .map(|(i, l)| SourceLine {
+            line: l.to_owned(),
+            number: Some(i + 1),
+        })
+        .collect();
+
+    // Line 4 of the used file is not paused at (stepping into or at a breakpoint).
+    let commands = "step\nbreak 4\ncontinue\n";
+
+    let mut output = Vec::new();
+    let mut debugger = Debugger::with_io(lines, commands.as_bytes(), &mut output);
+
+    let session = Driver::with_output(DriverOptions::default(), Vec::new())
+        .on_exec(|interpreter, event| debugger.on_exec(interpreter, event))
+        .run_source(dir.join("main.nxs").to_str().unwrap(), source);
+
+    drop(debugger);
+    assert!(!session.has_errors());
+
+    let output = String::from_utf8(output).unwrap();
+    let transcript: Vec<_> = output
+        .lines()
+        .map(|l| l.trim_start_matches(PROMPT))
+        .filter(|l| !l.is_empty() && !l.starts_with("   |"))
+        .collect();
+
+    assert_eq!(
+        transcript,
+        [
+            " 2 | let a = sq(3);",
+            " 3 | let b = sq(a);",
+            "breakpoint set at line 4",
+            " 4 | println a + b;",
+            "detached",
+        ]
+    );
+}
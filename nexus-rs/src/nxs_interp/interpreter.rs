@@ -0,0 +1,2104 @@
+use crate::ast::*;
+use crate::builtins::{builtin, convert, BUILTINS};
+use crate::clock::Clock;
+use crate::graph::GraphError;
+use crate::limits::{Limit, Limits, MAX_EXPR_DEPTH};
+use crate::minify::{binary_op, unary_op};
+use crate::native::{NodeFactory, NodeRegistry};
+use crate::numeric::{DomainError, NumericMode};
+use crate::profile::HeapProfile;
+use crate::random::Random;
+use crate::runtime_error::RuntimeError;
+use crate::scanner::keywords;
+use crate::span::Span;
+use crate::suggest::suggest;
+use crate::symbol::Symbol;
+use crate::value::{EnumValue, RangeValue, Value};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    io::{self, BufRead, Write},
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Instant,
+};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+/// Storage of a variable, shared between the variable and its references.
+type Slot = Rc<RefCell<Option<Value>>>; // Uninitialized if `None`.
+
+/// Variable binding.
+#[derive(Debug)]
+struct Binding {
+    slot: Slot,
+    mutable: bool,
+}
+
+/// Lexical scope.
+#[derive(Debug, Default)]
+struct Scope<'a> {
+    vars: HashMap<Symbol, Binding>,
+    functions: HashMap<Symbol, &'a FunctionDecl>,
+    enums: HashMap<Symbol, &'a EnumDecl>,
+    deferred: Vec<&'a Stmt>, // Executed (last first) when leaving the scope.
+}
+
+/// Non-local control flow, unwinding the evaluation.
+enum Unwind {
+    Return(Value),
+    Error(RuntimeError),
+}
+
+impl From<RuntimeError> for Unwind {
+    fn from(e: RuntimeError) -> Self {
+        Unwind::Error(e)
+    }
+}
+
+type EvalResult = Result<Value, Unwind>;
+
+/// Execution event, reported to the execution hook (see [`Interpreter::on_exec`]).
+#[derive(Clone, Copy, Debug)]
+pub enum ExecEvent<'a> {
+    Stmt(&'a Stmt), // Before executing a statement.
+    Call(Symbol), // Entering a (user-defined or built-in) function, after evaluating its arguments.
+    Return(Symbol), // Leaving a function, also when failing.
+}
+
+/// Hook called on execution events (e.g. for debugging or profiling).
+type ExecHook<'a> = Box<dyn FnMut(&mut Interpreter<'a>, ExecEvent<'a>) + 'a>;
+
+/// Check for an empty statement (a stray `;`), which is skipped.
+fn is_empty(stmt: &Stmt) -> bool {
+    matches!(&stmt.kind, StmtKind::Expr(e) if matches!(e.kind, ExprKind::Empty()))
+}
+
+/// Kind, heap size and heap data address (if any) of a value.
+type Footprint = (&'static str, usize, Option<usize>);
+
+/// Check if two values can be compared for equality: values of the same type, or any value with `nil`.
+fn comparable(lhs: &Value, rhs: &Value) -> bool {
+    lhs.type_name() == rhs.type_name() || matches!(lhs, Value::Nil) || matches!(rhs, Value::Nil)
+}
+
+/// Get a character index from a number, which must be a non-negative integer.
+fn char_index(n: f64) -> Result<usize, RuntimeError> {
+    match n {
+        n if n >= 0.0 && n.fract() == 0.0 && n.is_finite() => Ok(n as usize),
+        n => Err(RuntimeError::InvalidIndex(format!(
+            "index must be a non-negative integer, found {n}"
+        ))),
+    }
+}
+
+/// Combine the result of a scope with that of its deferred statements: errors of the scope take precedence.
+fn settle(result: EvalResult, deferred: Result<(), RuntimeError>) -> EvalResult {
+    match (result, deferred) {
+        (Err(Unwind::Error(e)), _) => Err(Unwind::Error(e)),
+        (_, Err(e)) => Err(e.into()),
+        (result, Ok(())) => result,
+    }
+}
+
+fn footprint(value: &Value) -> Footprint {
+    (value.type_name(), value.heap_size(), value.heap_id())
+}
+
+/// Tree-walking interpreter for Nexus.
+///
+/// Functions only see their own (local) scopes and the global scope. Function declarations are hoisted to the start of
+///  their block, so they can be called before being declared.
+///
+/// A reference (`let r = &x;`) aliases the referenced variable: reading `r` reads `x`. Assigning to `r` assigns to `x`,
+///  which requires a mutable reference (`let mut r = &x;`) to a mutable variable. References cannot outlive their
+///  variable, as they are bound in the same or a nested scope, and passing or returning them copies the referenced
+///  value.
+///
+/// Values are reference counted, and freed as soon as their last binding goes out of scope. Values cannot refer to
+///  variables (only references can, and they are not values), so no reference cycles can be formed and no cycle
+///  collection is needed.
+///
+/// File and standard input access (through the `read_line()`, `read_file(path)` and `write_file(path, text)` built-in
+///  functions) can be disabled to run untrusted programs, see [`Interpreter::with_io`].
+///
+/// Deferred statements (`defer stmt;`) are executed when leaving their scope, last deferred first, including when
+///  returning or failing. Statements deferred at the top level are executed at the end of the program.
+///
+/// Failures can be handled in the language: `try expr` evaluates to `nil` if `expr` fails with a recoverable runtime
+///  error (see [`RuntimeError::is_recoverable`]), and `expr?` returns `nil` from the current function if `expr` is
+///  `nil` or fails with a recoverable runtime error.
+///
+/// Execution is bounded by resource limits (see [`Limits`]), exceeding them results in a
+///  [`RuntimeError::LimitExceeded`] error.
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::{front::parse_source, interpreter::Interpreter, value::Value};
+///
+/// let ast = parse_source("fn sq(x: Number) -> Number { x * x }\nlet mut y = sq(3);\ny = y + 1;\ny;").unwrap();
+/// let mut output = Vec::new();
+///
+/// assert_eq!(Interpreter::with_output(&mut output).run(&ast), Ok(Value::Number(10.0)));
+/// ```
+pub struct Interpreter<'a> {
+    scopes: Vec<Scope<'a>>, // Global scope first.
+    frames: Vec<usize>,     // Index of the first scope of each function call.
+    output: Box<dyn Write + 'a>,
+    input: Box<dyn BufRead + 'a>,
+    io: bool, // File and input access allowed.
+    limits: Limits,
+    numeric: NumericMode,
+    steps: u64,                    // Evaluation steps taken (over all runs).
+    depth: usize,                  // Current expression nesting depth (over all function calls).
+    error_span: Option<Span>, // Span of the statement raising the current runtime error (if known).
+    profile: HeapProfile,     // Bound values.
+    shared: HashMap<usize, usize>, // Number of bindings per (shared) heap data address.
+    args: Vec<String>,        // Program arguments.
+    random: Random,
+    clock: Clock,
+    hook: Option<ExecHook<'a>>,
+    node_types: NodeRegistry, // Native node kinds, registered by the host.
+    interrupt: Option<Arc<AtomicBool>>, // Set (e.g. on SIGINT) to stop the program.
+}
+
+impl<'a> Interpreter<'a> {
+    /// Create a new interpreter, printing to the standard output.
+    pub fn new() -> Self {
+        Self::with_output(io::stdout())
+    }
+
+    /// Create a new interpreter, printing to the given output (and reading from the standard input).
+    pub fn with_output(output: impl Write + 'a) -> Self {
+        Interpreter {
+            scopes: vec![Scope::default()],
+            frames: Vec::new(),
+            output: Box::new(output),
+            input: Box::new(io::BufReader::new(io::stdin())),
+            io: true,
+            limits: Limits::default(),
+            numeric: NumericMode::default(),
+            steps: 0,
+            depth: 0,
+            error_span: None,
+            profile: HeapProfile::new(),
+            shared: HashMap::new(),
+            args: Vec::new(),
+            random: Random::from_time(),
+            clock: Clock::default(),
+            hook: None,
+            node_types: NodeRegistry::new(),
+            interrupt: None,
+        }
+    }
+
+    /// Set the resource limits.
+    ///
+    /// The step limit applies to all runs combined.
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Set the numeric error policy (IEEE semantics by default).
+    pub fn with_numeric_mode(mut self, mode: NumericMode) -> Self {
+        self.numeric = mode;
+        self
+    }
+
+    /// Get the numeric error policy.
+    pub fn numeric_mode(&self) -> NumericMode {
+        self.numeric
+    }
+
+    /// Set the clock of the `now()` and `sleep(ms)` built-in functions (the system clock by default).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nexus_rs::{clock::Clock, front::parse_source, interpreter::Interpreter, runtime_error::RuntimeError, value::Value};
+    /// use std::time::Duration;
+    ///
+    /// let ast = parse_source("sleep(1500); now();").unwrap();
+    /// let clock = Clock::simulated(Some(Duration::from_secs(1)));
+    ///
+    /// assert_eq!(Interpreter::new().with_clock(Clock::simulated(None)).run(&ast), Ok(Value::Number(1500.0)));
+    /// assert_eq!(Interpreter::new().with_clock(clock).run(&ast), Err(RuntimeError::Exit(0)));
+    /// ```
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Get the clock.
+    pub(crate) fn clock(&mut self) -> &mut Clock {
+        &mut self.clock
+    }
+
+    /// Set the interrupt flag, e.g. set by a SIGINT handler (see [`crate::interrupt::install`]).
+    ///
+    /// When the flag is set, the program stops at the next evaluation step (or while sleeping) with a
+    ///  [`RuntimeError::Interrupted`] error, running its deferred statements first. The flag is cleared when the
+    ///  program stops, so the deferred statements can be interrupted again.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nexus_rs::{front::parse_source, interpreter::Interpreter, runtime_error::RuntimeError};
+    /// use std::sync::{atomic::AtomicBool, Arc};
+    ///
+    /// let ast = parse_source("while true {};").unwrap();
+    /// let flag = Arc::new(AtomicBool::new(true)); // E.g. set by another thread.
+    ///
+    /// assert_eq!(Interpreter::new().with_interrupt(flag).run(&ast), Err(RuntimeError::Interrupted));
+    /// ```
+    pub fn with_interrupt(mut self, flag: Arc<AtomicBool>) -> Self {
+        self.interrupt = Some(flag);
+        self
+    }
+
+    /// Stop the program if it is interrupted (clearing the interrupt flag).
+    pub(crate) fn check_interrupt(&mut self) -> Result<(), RuntimeError> {
+        match &self.interrupt {
+            Some(flag) if flag.swap(false, Ordering::SeqCst) => Err(RuntimeError::Interrupted),
+            _ => Ok(()),
+        }
+    }
+
+    /// Set the input, read by the `read_line()` built-in function.
+    pub fn with_input(mut self, input: impl BufRead + 'a) -> Self {
+        self.input = Box::new(input);
+        self
+    }
+
+    /// Allow or deny file and input access (allowed by default).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nexus_rs::{front::parse_source, interpreter::Interpreter, runtime_error::RuntimeError, symbol::Symbol};
+    ///
+    /// let ast = parse_source("read_file(\"secret.txt\");").unwrap();
+    ///
+    /// assert_eq!(Interpreter::new().with_io(false).run(&ast),
+    ///            Err(RuntimeError::IoDenied(Symbol::intern("read_file"))));
+    /// ```
+    pub fn with_io(mut self, io: bool) -> Self {
+        self.io = io;
+        self
+    }
+
+    /// Check if file and input access is allowed.
+    pub fn has_io(&self) -> bool {
+        self.io
+    }
+
+    /// Get the input.
+    pub(crate) fn input(&mut self) -> &mut dyn BufRead {
+        &mut self.input
+    }
+
+    /// Set the program arguments, available through the `arg_count()` and `arg(i)` built-in functions.
+    pub fn with_args(mut self, args: Vec<String>) -> Self {
+        self.args = args;
+        self
+    }
+
+    /// Seed the random number generator (seeded from the system time by default), for reproducible runs.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nexus_rs::{front::parse_source, interpreter::Interpreter};
+    ///
+    /// let ast = parse_source("random();").unwrap();
+    ///
+    /// assert_eq!(Interpreter::new().with_seed(1).run(&ast), Interpreter::new().with_seed(1).run(&ast));
+    /// ```
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.random = Random::new(seed);
+        self
+    }
+
+    /// Set a hook, called on execution events (e.g. to pause at breakpoints, or to count function calls).
+    ///
+    /// The hook is not called for code executed by the hook itself (see [`Interpreter::evaluate`]).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nexus_rs::{front::parse_source, interpreter::{ExecEvent, Interpreter}};
+    ///
+    /// let ast = parse_source("fn f() { print 1; }\nf();").unwrap();
+    /// let mut events = Vec::new();
+    ///
+    /// Interpreter::with_output(Vec::new())
+    ///     .on_exec(|_, event| {
+    ///         events.push(match event {
+    ///             ExecEvent::Stmt(s) => match s.span.and_then(|s| s.line) {
+    ///                 Some(line) => format!("line {line}"),
+    ///                 None => "block".to_owned(), // Function body.
+    ///             },
+    ///             ExecEvent::Call(id) => format!("call {id}"),
+    ///             ExecEvent::Return(id) => format!("return {id}"),
+    ///         })
+    ///     })
+    ///     .run(&ast)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(events, ["line 1", "line 2", "call f", "block", "line 1", "return f"]);
+    /// ```
+    pub fn on_exec(mut self, hook: impl FnMut(&mut Interpreter<'a>, ExecEvent<'a>) + 'a) -> Self {
+        self.hook = Some(Box::new(hook));
+        self
+    }
+
+    /// Register a native node kind, implemented in host code (see [`crate::native::NativeNode`]).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nexus_rs::{graph::Port, interpreter::Interpreter, native::*};
+    ///
+    /// struct Sink;
+    ///
+    /// impl NativeNode for Sink {
+    ///     fn ports(&self) -> Vec<Port> {
+    ///         vec![Port::input("value", "String")]
+    ///     }
+    ///
+    ///     fn process(&mut self, _: &PortValues) -> Result<PortValues, NodeError> {
+    ///         Ok(PortValues::new())
+    ///     }
+    /// }
+    ///
+    /// impl Sink {
+    ///     fn factory() -> Box<dyn NativeNode> {
+    ///         Box::new(Sink)
+    ///     }
+    /// }
+    ///
+    /// let mut interpreter = Interpreter::new();
+    /// interpreter.register_node_type("Kafka", Sink::factory).unwrap();
+    ///
+    /// assert!(interpreter.register_node_type("Kafka", Sink::factory).is_err());
+    /// assert!(interpreter.node_types().contains("Kafka"));
+    /// ```
+    pub fn register_node_type(
+        &mut self,
+        name: &str,
+        factory: NodeFactory,
+    ) -> Result<(), GraphError> {
+        self.node_types.register(name, factory)
+    }
+
+    /// Get the registered native node kinds.
+    pub fn node_types(&self) -> &NodeRegistry {
+        &self.node_types
+    }
+
+    /// Get the function call depth (zero at the top level).
+    pub fn call_depth(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Get the span of the (innermost) statement raising the last runtime error returned, if known.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nexus_rs::{front::parse_source, interpreter::Interpreter};
+    ///
+    /// let ast = parse_source("fn f() -> Number {\n    x\n}\nlet y = f();").unwrap();
+    /// let mut interpreter = Interpreter::new();
+    ///
+    /// assert!(interpreter.run(&ast).is_err());
+    /// assert_eq!(interpreter.error_span().and_then(|s| s.line), Some(2));
+    /// ```
+    pub fn error_span(&self) -> Option<Span> {
+        self.error_span
+    }
+
+    /// Evaluate an expression in the current scope, e.g. to inspect variables from an execution hook.
+    pub fn evaluate(&mut self, expr: &'a Expr) -> Result<Value, RuntimeError> {
+        self.error_span = None;
+
+        match self.eval(expr) {
+            Ok(value) | Err(Unwind::Return(value)) => Ok(value),
+            Err(Unwind::Error(e)) => Err(e),
+        }
+    }
+
+    /// Get the random number generator.
+    pub(crate) fn random(&mut self) -> &mut Random {
+        &mut self.random
+    }
+
+    /// Get the program arguments.
+    pub fn args(&self) -> &[String] {
+        &self.args
+    }
+
+    /// Get the heap profile of the values bound to variables (over all runs).
+    pub fn profile(&self) -> &HeapProfile {
+        &self.profile
+    }
+
+    /// Run a program, returning the value of the last statement (or of a top-level `return`).
+    ///
+    /// Global definitions are kept between runs, so multiple modules can be run in succession.
+    pub fn run(&mut self, ast: &'a Stmts) -> Result<Value, RuntimeError> {
+        self.error_span = None;
+        self.declare_items(ast);
+
+        let mut result = Ok(Value::Unit);
+        for stmt in ast.iter().filter(|s| !is_empty(s)) {
+            result = self.exec(stmt);
+            if result.is_err() {
+                break;
+            }
+        }
+
+        // Statements deferred at the top level are executed at the end of the program.
+        let deferred = self.exec_deferred();
+
+        match settle(result, deferred) {
+            Ok(value) | Err(Unwind::Return(value)) => Ok(value),
+            Err(Unwind::Error(e)) => {
+                self.unwind();
+                Err(e)
+            }
+        }
+    }
+
+    /// Run the `main` function (if declared globally, without parameters), returning its value.
+    ///
+    /// This is to be called after running all modules of a program, so `main` can use all global definitions.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nexus_rs::{front::parse_source, interpreter::Interpreter, value::Value};
+    ///
+    /// let ast = parse_source("fn main() -> Number { arg_count() + X } const X: Number = 1;").unwrap();
+    /// let mut interpreter = Interpreter::new().with_args(vec!["a".to_owned(), "b".to_owned()]);
+    ///
+    /// assert_eq!(interpreter.run(&ast), Ok(Value::Unit));
+    /// assert_eq!(interpreter.run_main(), Some(Ok(Value::Number(3.0))));
+    /// ```
+    pub fn run_main(&mut self) -> Option<Result<Value, RuntimeError>> {
+        self.error_span = None;
+
+        let id = Symbol::intern("main");
+        let main = *self.scopes[0].functions.get(&id)?;
+
+        let params = main.args.as_ref().map_or(0, |a| a.len());
+        if params > 0 {
+            return Some(Err(RuntimeError::ArgumentCount(id, params, 0)));
+        }
+
+        Some(
+            self.call_function(main, Vec::new())
+                .inspect_err(|_| self.unwind()),
+        )
+    }
+
+    /// Unwind all local scopes, e.g. after an error.
+    fn unwind(&mut self) {
+        while self.scopes.len() > 1 {
+            let _ = self.pop_scope();
+        }
+        self.frames.clear();
+    }
+
+    fn exec(&mut self, stmt: &'a Stmt) -> EvalResult {
+        let result = self.exec_kind(stmt);
+
+        // The innermost statement raising an error is its location.
+        if matches!(result, Err(Unwind::Error(_))) && self.error_span.is_none() {
+            self.error_span = stmt.span;
+        }
+
+        result
+    }
+
+    fn exec_kind(&mut self, stmt: &'a Stmt) -> EvalResult {
+        self.step()?;
+
+        self.notify(ExecEvent::Stmt(stmt));
+
+        match &stmt.kind {
+            StmtKind::Assignment(a) => self.assign(a).map(|_| Value::Unit),
+            StmtKind::Block(stmts) => self.exec_block(stmts),
+            StmtKind::Connect(_) => Err(RuntimeError::Unsupported("connecting nodes").into()),
+            StmtKind::Disconnect(_) => Err(RuntimeError::Unsupported("disconnecting nodes").into()),
+            StmtKind::ConstDecl(c) => {
+                let value = self.eval(&c.value)?;
+                self.define(c.id, Some(value), false)?;
+                Ok(Value::Unit)
+            }
+            StmtKind::Defer(s) => {
+                self.current_scope().deferred.push(s);
+                Ok(Value::Unit)
+            }
+            StmtKind::Expr(e) => self.eval(e),
+            StmtKind::EnumDecl(_) => Ok(Value::Unit), // Declared when entering the block.
+            StmtKind::FunctionDecl(_) => Ok(Value::Unit), // Declared when entering the block.
+            StmtKind::GroupDecl(_) => Ok(Value::Unit), // Only used by the graph (see `Graph::add_chain`).
+            StmtKind::NodeDecl(_) => Ok(Value::Unit), // Only used by the graph (see `graph::NodeKind`).
+            StmtKind::Print(p) => self.print(p).map(|_| Value::Unit),
+            StmtKind::Remove(_) => Err(RuntimeError::Unsupported("removing nodes").into()),
+            StmtKind::Return(r) => Err(Unwind::Return(self.eval(&r.expr)?)),
+            StmtKind::UseDecl(_) => Ok(Value::Unit), // Resolved by the loader.
+            StmtKind::VarDecl(v) => {
+                let ExprKind::Var(var) = &v.id.kind else {
+                    return Err(RuntimeError::Unsupported("member declarations").into());
+                };
+
+                if let Some(Expr {
+                    kind: ExprKind::Ref(r),
+                }) = &v.value
+                {
+                    self.define_ref(var.id, r, v.mutable)?;
+                    return Ok(Value::Unit);
+                }
+
+                let value = v.value.as_ref().map(|e| self.eval(e)).transpose()?;
+                self.define(var.id, value, v.mutable)?;
+                Ok(Value::Unit)
+            }
+        }
+    }
+
+    fn exec_block(&mut self, stmts: &'a Stmts) -> EvalResult {
+        self.scopes.push(Scope::default());
+        self.declare_items(stmts);
+
+        let mut result = Ok(Value::Unit);
+        for stmt in stmts.iter().filter(|s| !is_empty(s)) {
+            result = self.exec(stmt);
+            if result.is_err() {
+                break;
+            }
+        }
+
+        let result = settle(result, self.pop_scope());
+
+        // The value of a block is the value of its last expression (if any).
+        match stmts.iter().rfind(|s| !is_empty(s)).map(|s| &s.kind) {
+            Some(StmtKind::Expr(_)) => result,
+            _ => result.map(|_| Value::Unit),
+        }
+    }
+
+    fn eval(&mut self, expr: &'a Expr) -> EvalResult {
+        self.step()?;
+
+        if self.depth >= MAX_EXPR_DEPTH {
+            return Err(RuntimeError::LimitExceeded(Limit::ExprDepth(MAX_EXPR_DEPTH)).into());
+        }
+
+        self.depth += 1;
+        let result = self.eval_kind(expr);
+        self.depth -= 1;
+
+        result
+    }
+
+    fn eval_kind(&mut self, expr: &'a Expr) -> EvalResult {
+        match &expr.kind {
+            ExprKind::Binary(b) => self.eval_binary(b),
+            ExprKind::Block(b) => self.exec(&b.body),
+            ExprKind::Cast(x) => Ok(convert(&self.eval(&x.expr)?, &x.typeid)?),
+            ExprKind::Empty() => Ok(Value::Unit),
+            ExprKind::For(f) => self.eval_for(f),
+            ExprKind::FuncCall(f) => self.call(f, None),
+            ExprKind::Group(e) => self.eval(e),
+            ExprKind::If(i) => match self.eval_bool(&i.expr, "if condition")? {
+                true => self.eval(&i.body_then),
+                false => match &i.body_else {
+                    Some(body) => self.eval(body),
+                    None => Ok(Value::Unit),
+                },
+            },
+            ExprKind::Literal(l) => Ok(match &l.kind {
+                LiteralKind::Bool(b) => Value::Bool(*b),
+                LiteralKind::Nil => Value::Nil,
+                LiteralKind::Number(n) => Value::Number(*n),
+                LiteralKind::String(s) => Value::from(s.as_str()),
+            }),
+            ExprKind::Propagate(e) => match self.eval(e) {
+                Ok(Value::Nil) => Err(Unwind::Return(Value::Nil)),
+                Err(Unwind::Error(e)) if e.is_recoverable() => {
+                    self.error_span = None;
+                    Err(Unwind::Return(Value::Nil))
+                }
+                result => result,
+            },
+            ExprKind::Index(x) => self.eval_index(x),
+            ExprKind::Range(r) => self.eval_range(r),
+            ExprKind::Loop(l) => loop {
+                self.eval(&l.body)?;
+            },
+            ExprKind::Ref(_) => {
+                Err(RuntimeError::Unsupported("references outside declarations").into())
+            }
+            ExprKind::Time(t) => {
+                let start = Instant::now();
+                self.eval(&t.body)?;
+
+                Ok(Value::Number(start.elapsed().as_secs_f64() * 1000.0))
+            }
+            ExprKind::Try(e) => match self.eval(e) {
+                Err(Unwind::Error(e)) if e.is_recoverable() => {
+                    self.error_span = None;
+                    Ok(Value::Nil)
+                }
+                result => result,
+            },
+            ExprKind::Unary(u) => self.eval_unary(u),
+            ExprKind::Var(v) => self.lookup(v.id),
+            ExprKind::While(w) => {
+                while self.eval_bool(&w.expr, "while condition")? {
+                    self.eval(&w.body)?;
+                }
+
+                Ok(Value::Unit)
+            }
+        }
+    }
+
+    fn eval_binary(&mut self, b: &'a BinaryExpr) -> EvalResult {
+        match b.op {
+            BinaryOp::And => {
+                return Ok(Value::Bool(
+                    self.eval_bool(&b.lhs, "'&&' operand")?
+                        && self.eval_bool(&b.rhs, "'&&' operand")?,
+                ))
+            }
+            BinaryOp::Or => {
+                return Ok(Value::Bool(
+                    self.eval_bool(&b.lhs, "'||' operand")?
+                        || self.eval_bool(&b.rhs, "'||' operand")?,
+                ))
+            }
+            BinaryOp::Dot => {
+                if let Some(value) = self.eval_variant(b) {
+                    return value;
+                }
+
+                let ExprKind::FuncCall(f) = &b.rhs.kind else {
+                    return Err(RuntimeError::Unsupported("member access").into());
+                };
+
+                let receiver = self.eval(&b.lhs)?;
+                return self.call(f, Some(receiver));
+            }
+            _ => (),
+        }
+
+        let (lhs, rhs) = (self.eval(&b.lhs)?, self.eval(&b.rhs)?);
+
+        let result = match (&b.op, &lhs, &rhs) {
+            (BinaryOp::Eq, l, r) if comparable(l, r) => Value::Bool(l == r),
+            (BinaryOp::NotEq, l, r) if comparable(l, r) => Value::Bool(l != r),
+            (
+                op @ (BinaryOp::Divide
+                | BinaryOp::Multiply
+                | BinaryOp::Plus
+                | BinaryOp::Power
+                | BinaryOp::Remainder
+                | BinaryOp::Subtract),
+                Value::Number(l),
+                Value::Number(r),
+            ) => {
+                let (result, divisor) = match op {
+                    BinaryOp::Divide => (l / r, Some((*r, DomainError::DivisionByZero))),
+                    BinaryOp::Multiply => (l * r, None),
+                    BinaryOp::Plus => (l + r, None),
+                    BinaryOp::Power => (l.powf(*r), None),
+                    BinaryOp::Remainder => (l % r, Some((*r, DomainError::RemainderByZero))),
+                    BinaryOp::Subtract => (l - r, None),
+                    _ => unreachable!("matched above"),
+                };
+                Value::Number(
+                    self.numeric
+                        .check(&[*l, *r], result, divisor)
+                        .map_err(RuntimeError::Numeric)?,
+                )
+            }
+            (op, Value::Number(l), Value::Number(r)) => match op {
+                BinaryOp::Gt => Value::Bool(l > r),
+                BinaryOp::GtEq => Value::Bool(l >= r),
+                BinaryOp::Lt => Value::Bool(l < r),
+                BinaryOp::LtEq => Value::Bool(l <= r),
+                _ => unreachable!("handled above"),
+            },
+            (
+                op @ (BinaryOp::Plus
+                | BinaryOp::Gt
+                | BinaryOp::GtEq
+                | BinaryOp::Lt
+                | BinaryOp::LtEq),
+                Value::String(l),
+                Value::String(r),
+            ) => match op {
+                BinaryOp::Plus => Value::String(Rc::new(format!("{l}{r}"))),
+                BinaryOp::Gt => Value::Bool(l > r),
+                BinaryOp::GtEq => Value::Bool(l >= r),
+                BinaryOp::Lt => Value::Bool(l < r),
+                BinaryOp::LtEq => Value::Bool(l <= r),
+                _ => unreachable!("matched above"),
+            },
+            (BinaryOp::Multiply, Value::String(s), Value::Number(n))
+            | (BinaryOp::Multiply, Value::Number(n), Value::String(s)) => self.repeat(s, *n)?,
+            (op, l, r) => {
+                return Err(RuntimeError::TypeMismatch(format!(
+                    "cannot apply '{}' to {} and {}",
+                    binary_op(op),
+                    l.type_name(),
+                    r.type_name()
+                ))
+                .into())
+            }
+        };
+
+        Ok(result)
+    }
+
+    /// Repeat a string (`s * n`), where the count must be a non-negative integer.
+    ///
+    /// The result size is checked against the heap limit (if any) before allocating it, and a failing allocation is
+    ///  an error (rather than aborting the host).
+    fn repeat(&self, s: &str, count: f64) -> Result<Value, RuntimeError> {
+        if count.fract() != 0.0 || count < 0.0 {
+            return Err(RuntimeError::InvalidArgument(format!(
+                "string repetition count must be a non-negative integer, found {count}"
+            )));
+        }
+
+        let too_large = || {
+            RuntimeError::InvalidArgument(format!("string repetition count {count} is too large"))
+        };
+
+        let bytes = (count <= usize::MAX as f64)
+            .then(|| s.len().checked_mul(count as usize))
+            .flatten()
+            .filter(|&bytes| bytes <= isize::MAX as usize)
+            .ok_or_else(too_large)?;
+
+        if let Some(max) = self.limits.max_heap {
+            if bytes > max {
+                return Err(RuntimeError::LimitExceeded(Limit::Heap(max)));
+            }
+        }
+
+        let mut result = String::new();
+        result.try_reserve_exact(bytes).map_err(|_| too_large())?;
+        if !s.is_empty() {
+            (0..count as usize).for_each(|_| result.push_str(s));
+        }
+
+        Ok(Value::String(Rc::new(result)))
+    }
+
+    fn eval_unary(&mut self, u: &'a UnaryExpr) -> EvalResult {
+        match (&u.op, self.eval(&u.expr)?) {
+            (UnaryOp::Bang, Value::Bool(b)) => Ok(Value::Bool(!b)),
+            (UnaryOp::Minus, Value::Number(n)) => Ok(Value::Number(-n)),
+            (UnaryOp::Plus, Value::Number(n)) => Ok(Value::Number(n)),
+            (UnaryOp::Group | UnaryOp::Node, _) => {
+                Err(RuntimeError::Unsupported("node/group instantiation").into())
+            }
+            (op, value) => Err(RuntimeError::TypeMismatch(format!(
+                "cannot apply '{}' to {}",
+                unary_op(op),
+                value.type_name()
+            ))
+            .into()),
+        }
+    }
+
+    fn eval_bool(&mut self, expr: &'a Expr, what: &str) -> Result<bool, Unwind> {
+        match self.eval(expr)? {
+            Value::Bool(b) => Ok(b),
+            value => Err(RuntimeError::NotBool(what.to_owned(), value.type_name()).into()),
+        }
+    }
+
+    fn eval_number(&mut self, expr: &'a Expr, what: &str) -> Result<f64, Unwind> {
+        match self.eval(expr)? {
+            Value::Number(n) => Ok(n),
+            value => Err(RuntimeError::TypeMismatch(format!(
+                "{what} must be Number, found {}",
+                value.type_name()
+            ))
+            .into()),
+        }
+    }
+
+    /// Evaluate an index expression: a character (`s[i]`) or a substring (`s[a..b]`) of a string.
+    ///
+    /// Indices count characters. A slice with a range value of another step than one (e.g. `s[(0..n).rev()]`) takes
+    ///  the characters at the indices of the range, in order.
+    fn eval_index(&mut self, x: &'a Index) -> EvalResult {
+        let s = match self.eval(&x.expr)? {
+            Value::String(s) => s,
+            value => {
+                return Err(RuntimeError::TypeMismatch(format!(
+                    "cannot index {}",
+                    value.type_name()
+                ))
+                .into())
+            }
+        };
+        let chars: Vec<char> = s.chars().collect();
+        let len = chars.len();
+
+        let mut bound = |expr: &'a Option<Expr>, default: usize| -> Result<f64, Unwind> {
+            match expr {
+                Some(e) => Ok(self.eval_number(e, "slice bound")?),
+                None => Ok(default as f64),
+            }
+        };
+
+        let range = match &x.index {
+            IndexKind::Slice(kind, start, end) => RangeValue::new(
+                bound(start, 0)?,
+                bound(end, len)?,
+                matches!(kind, RangeKind::Inclusive),
+            ),
+            IndexKind::Element(e) => match self.eval(e)? {
+                Value::Range(range) => range,
+                Value::Number(i) => {
+                    let i = char_index(i)?;
+                    return match chars.get(i) {
+                        Some(c) => Ok(Value::from(c.to_string().as_str())),
+                        None => Err(RuntimeError::InvalidIndex(format!(
+                            "index {i} out of range for string of length {len}"
+                        ))
+                        .into()),
+                    };
+                }
+                value => {
+                    return Err(RuntimeError::TypeMismatch(format!(
+                        "index must be Number or Range, found {}",
+                        value.type_name()
+                    ))
+                    .into())
+                }
+            },
+        };
+
+        // Slices (in steps of one) are checked like substrings, other ranges by their elements.
+        let indices = if range.step == 1.0 {
+            let (start, end) = (char_index(range.start)?, char_index(range.end)?);
+            let end = end + usize::from(range.inclusive);
+            if start > end || end > len {
+                return Err(RuntimeError::InvalidIndex(format!(
+                    "slice [{start}, {end}) out of range for string of length {len}"
+                ))
+                .into());
+            }
+            (start..end).collect()
+        } else {
+            range
+                .iter()
+                .map(|i| match char_index(i)? {
+                    i if i < len => Ok(i),
+                    i => Err(RuntimeError::InvalidIndex(format!(
+                        "index {i} out of range for string of length {len}"
+                    ))),
+                })
+                .collect::<Result<Vec<_>, _>>()?
+        };
+
+        Ok(Value::from(
+            indices
+                .into_iter()
+                .map(|i| chars[i])
+                .collect::<String>()
+                .as_str(),
+        ))
+    }
+
+    /// Evaluate an enum variant (`Mode.Run`), if the left-hand side of the dot names a declared enum.
+    fn eval_variant(&self, b: &BinaryExpr) -> Option<EvalResult> {
+        let (ExprKind::Var(e), ExprKind::Var(v)) = (&b.lhs.kind, &b.rhs.kind) else {
+            return None;
+        };
+        let decl = self.visible_scopes().find_map(|s| s.enums.get(&e.id))?;
+
+        Some(match decl.variants.contains(&v.id) {
+            true => Ok(Value::Enum(EnumValue {
+                kind: decl.id,
+                variant: v.id,
+            })),
+            false => Err(RuntimeError::UnknownVariant(decl.id, v.id).into()),
+        })
+    }
+
+    fn eval_range(&mut self, r: &'a Range) -> EvalResult {
+        let range = RangeValue::new(
+            self.eval_number(&r.start, "range start")?,
+            self.eval_number(&r.end, "range end")?,
+            matches!(r.kind, RangeKind::Inclusive),
+        );
+
+        let Some(step) = &r.step else {
+            return Ok(Value::Range(range));
+        };
+
+        let step = self.eval_number(step, "range step")?;
+        match range.with_step(step) {
+            Some(range) => Ok(Value::Range(range)),
+            None => Err(RuntimeError::InvalidArgument(format!(
+                "range step must be a nonzero number, found {step}"
+            ))
+            .into()),
+        }
+    }
+
+    fn eval_for(&mut self, f: &'a For) -> EvalResult {
+        let range = match self.eval(&f.expr)? {
+            Value::Range(range) => range,
+            value => {
+                return Err(RuntimeError::TypeMismatch(format!(
+                    "for loop expects a range, found {}",
+                    value.type_name()
+                ))
+                .into())
+            }
+        };
+
+        for i in range.iter() {
+            self.scopes.push(Scope::default());
+            let result = self
+                .define(f.id, Some(Value::Number(i)), false)
+                .map_err(Unwind::from)
+                .and_then(|_| self.eval(&f.body));
+            let deferred = self.pop_scope();
+
+            settle(result, deferred)?;
+        }
+
+        Ok(Value::Unit)
+    }
+
+    /// Call a function, with the receiver (if any) of a method call (`x.f(a)`) as first argument (`f(x, a)`).
+    fn call(&mut self, call: &'a FuncCall, receiver: Option<Value>) -> EvalResult {
+        let Some(function) = self
+            .visible_scopes()
+            .find_map(|s| s.functions.get(&call.id).copied())
+        else {
+            return self.call_builtin(call, receiver);
+        };
+
+        let params = function.args.as_ref().map_or(&[][..], |a| &a[..]);
+        let count = call.args.len() + usize::from(receiver.is_some());
+        if params.len() != count {
+            return Err(RuntimeError::ArgumentCount(call.id, params.len(), count).into());
+        }
+
+        let args = self.eval_args(&call.args, receiver)?;
+
+        Ok(self.call_function(function, args)?)
+    }
+
+    fn eval_args(
+        &mut self,
+        args: &'a [Expr],
+        receiver: Option<Value>,
+    ) -> Result<Vec<Value>, Unwind> {
+        receiver
+            .into_iter()
+            .map(Ok)
+            .chain(args.iter().map(|a| self.eval(a)))
+            .collect()
+    }
+
+    /// Call a function with (count-checked) arguments.
+    fn call_function(
+        &mut self,
+        function: &'a FunctionDecl,
+        args: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        let params = function.args.as_ref().map_or(&[][..], |a| &a[..]);
+
+        if let Some(max) = self.limits.max_call_depth {
+            if self.frames.len() >= max {
+                return Err(RuntimeError::LimitExceeded(Limit::CallDepth(max)));
+            }
+        }
+
+        self.notify(ExecEvent::Call(function.id));
+        self.frames.push(self.scopes.len());
+        self.scopes.push(Scope::default());
+
+        let result = params
+            .iter()
+            .zip(args)
+            .try_for_each(|(param, arg)| self.define(param.id, Some(arg), false))
+            .map_err(Unwind::from)
+            .and_then(|_| self.exec(&function.body));
+
+        let result = settle(result, self.pop_scope());
+        self.frames.pop();
+        self.notify(ExecEvent::Return(function.id));
+
+        match result {
+            Ok(value) | Err(Unwind::Return(value)) => Ok(value),
+            Err(Unwind::Error(e)) => Err(e),
+        }
+    }
+
+    fn call_builtin(&mut self, call: &'a FuncCall, receiver: Option<Value>) -> EvalResult {
+        let Some(builtin) = builtin(call.id) else {
+            return Err(
+                RuntimeError::UndefinedFunction(call.id, self.suggest_function(call.id)).into(),
+            );
+        };
+        let count = call.args.len() + usize::from(receiver.is_some());
+        if count != builtin.arity && !(builtin.variadic && count > builtin.arity) {
+            return Err(RuntimeError::ArgumentCount(call.id, builtin.arity, count).into());
+        }
+
+        let args = self.eval_args(&call.args, receiver)?;
+
+        self.notify(ExecEvent::Call(call.id));
+        let result = (builtin.func)(self, &args);
+        self.notify(ExecEvent::Return(call.id));
+
+        Ok(result?)
+    }
+
+    fn assign(&mut self, a: &'a Assignment) -> Result<(), Unwind> {
+        let ExprKind::Var(var) = &a.lhs.kind else {
+            return Err(RuntimeError::InvalidAssignment(a.lhs.to_string()).into());
+        };
+
+        let value = self.eval(&a.rhs)?;
+        let binding = self.binding(var.id)?;
+
+        // Immutable variables can be initialized once, after declaration.
+        if !binding.mutable && binding.slot.borrow().is_some() {
+            return Err(RuntimeError::AssignImmutable(var.id).into());
+        }
+
+        let slot = binding.slot.clone();
+        let old = slot.borrow().as_ref().map(footprint);
+        self.track_heap(old, Some(footprint(&value)))?;
+
+        *slot.borrow_mut() = Some(value);
+        Ok(())
+    }
+
+    fn print(&mut self, p: &'a Print) -> Result<(), Unwind> {
+        let args = p
+            .args
+            .iter()
+            .map(|a| self.eval(a).map(|v| v.to_string()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let end = if p.kind == PrintKind::Println {
+            "\n"
+        } else {
+            ""
+        };
+        write!(self.output, "{}{end}", args.join(" "))
+            .map_err(|e| RuntimeError::Output(e.to_string()).into())
+    }
+
+    /// Declare the functions and enums of a block, so they can be used before their declaration.
+    fn declare_items(&mut self, stmts: &'a Stmts) {
+        for stmt in stmts.iter() {
+            match &stmt.kind {
+                StmtKind::FunctionDecl(f) => {
+                    self.current_scope().functions.insert(f.id, f);
+                }
+                StmtKind::EnumDecl(e) => {
+                    self.current_scope().enums.insert(e.id, e);
+                }
+                _ => (),
+            }
+        }
+    }
+
+    fn define(
+        &mut self,
+        id: Symbol,
+        value: Option<Value>,
+        mutable: bool,
+    ) -> Result<(), RuntimeError> {
+        // A shadowed value remains alive if it is still referenced.
+        let old = self
+            .current_scope()
+            .vars
+            .get(&id)
+            .filter(|b| Rc::strong_count(&b.slot) == 1)
+            .and_then(|b| b.slot.borrow().as_ref().map(footprint));
+        self.track_heap(old, value.as_ref().map(footprint))?;
+
+        self.current_scope().vars.insert(
+            id,
+            Binding {
+                slot: Rc::new(RefCell::new(value)),
+                mutable,
+            },
+        );
+        Ok(())
+    }
+
+    fn define_ref(&mut self, id: Symbol, r: &'a Ref, mutable: bool) -> Result<(), RuntimeError> {
+        let ExprKind::Var(var) = &r.expr.kind else {
+            return Err(RuntimeError::Unsupported("member references"));
+        };
+
+        let target = self.binding(var.id)?;
+        if target.slot.borrow().is_none() {
+            return Err(RuntimeError::Uninitialized(var.id));
+        }
+
+        if mutable && !target.mutable {
+            return Err(RuntimeError::MutableRefImmutable(var.id));
+        }
+
+        let binding = Binding {
+            slot: target.slot.clone(),
+            mutable,
+        };
+
+        // Free the shadowed value (if not referenced), the referenced value is accounted for by its variable.
+        if let Some(old) = self.current_scope().vars.insert(id, binding) {
+            self.free(old);
+        }
+
+        Ok(())
+    }
+
+    fn lookup(&mut self, id: Symbol) -> EvalResult {
+        self.binding(id)?
+            .slot
+            .borrow()
+            .clone()
+            .ok_or(RuntimeError::Uninitialized(id).into())
+    }
+
+    fn binding(&self, id: Symbol) -> Result<&Binding, RuntimeError> {
+        match self.visible_scopes().find_map(|s| s.vars.get(&id)) {
+            Some(binding) => Ok(binding),
+            None => Err(RuntimeError::UndefinedVariable(
+                id,
+                self.suggest_variable(id),
+            )),
+        }
+    }
+
+    /// Find the visible variable (or keyword) closest to an undefined variable name.
+    fn suggest_variable(&self, id: Symbol) -> Option<&'static str> {
+        let mut names: Vec<_> = self
+            .visible_scopes()
+            .flat_map(|s| s.vars.keys().map(Symbol::as_str))
+            .collect();
+        names.sort_unstable();
+
+        suggest(
+            id.as_str(),
+            names.into_iter().chain(keywords().iter().map(|(k, _)| *k)),
+        )
+    }
+
+    /// Find the visible (or built-in) function closest to an undefined function name.
+    fn suggest_function(&self, id: Symbol) -> Option<&'static str> {
+        let mut names: Vec<_> = self
+            .visible_scopes()
+            .flat_map(|s| s.functions.keys().map(Symbol::as_str))
+            .collect();
+        names.sort_unstable();
+
+        suggest(
+            id.as_str(),
+            names.into_iter().chain(BUILTINS.iter().map(|b| b.name)),
+        )
+    }
+
+    fn visible_scopes(&self) -> impl Iterator<Item = &Scope<'a>> {
+        let base = self.frames.last().copied().unwrap_or(0);
+
+        self.scopes[base.max(1)..]
+            .iter()
+            .rev()
+            .chain(self.scopes.iter().take(1))
+    }
+
+    /// Leave the current scope, executing its deferred statements first.
+    fn pop_scope(&mut self) -> Result<(), RuntimeError> {
+        let result = self.exec_deferred();
+        let scope = self.scopes.pop().expect("local scope");
+
+        for binding in scope.vars.into_values() {
+            self.free(binding);
+        }
+
+        result
+    }
+
+    /// Execute the deferred statements of the current scope (last first), returning the first error (if any).
+    ///
+    /// All deferred statements are executed, even if one fails. Their values are discarded, so a `?` in a deferred
+    ///  statement only ends that statement.
+    fn exec_deferred(&mut self) -> Result<(), RuntimeError> {
+        let deferred = std::mem::take(&mut self.current_scope().deferred);
+
+        let mut result = Ok(());
+        for stmt in deferred.into_iter().rev() {
+            if let Err(Unwind::Error(e)) = self.exec(stmt) {
+                result = result.and(Err(e));
+            }
+        }
+
+        result
+    }
+
+    /// Free the value of a binding, if it is the last binding of its slot.
+    fn free(&mut self, binding: Binding) {
+        if let Ok(slot) = Rc::try_unwrap(binding.slot) {
+            if let Some(old) = slot.into_inner().as_ref().map(footprint) {
+                self.release(old, self.refund(&old));
+            }
+        }
+    }
+
+    /// Report an execution event to the hook (if any).
+    fn notify(&mut self, event: ExecEvent<'a>) {
+        // The hook is taken out while it runs, so it can use the interpreter.
+        if let Some(mut hook) = self.hook.take() {
+            hook(self, event);
+            self.hook = Some(hook);
+        }
+    }
+
+    /// Count an evaluation step.
+    fn step(&mut self) -> Result<(), RuntimeError> {
+        self.steps += 1;
+        self.check_interrupt()?;
+
+        match self.limits.max_steps {
+            Some(max) if self.steps > max => Err(RuntimeError::LimitExceeded(Limit::Steps(max))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Track heap usage of a bound value replacing another one (if any).
+    fn track_heap(
+        &mut self,
+        old: Option<Footprint>,
+        new: Option<Footprint>,
+    ) -> Result<(), RuntimeError> {
+        let (old_bytes, new_bytes) = match (old, new) {
+            (Some((_, _, Some(o))), Some((_, _, Some(n)))) if o == n => (0, 0),
+            _ => (
+                old.map_or(0, |o| self.refund(&o)),
+                new.map_or(0, |n| self.charge(&n)),
+            ),
+        };
+
+        if let Some(max) = self.limits.max_heap {
+            if new_bytes > old_bytes && self.profile.live_bytes() - old_bytes + new_bytes > max {
+                return Err(RuntimeError::LimitExceeded(Limit::Heap(max)));
+            }
+        }
+
+        if let Some(old) = old {
+            self.release(old, old_bytes);
+        }
+
+        if let Some((kind, _, id)) = new {
+            self.profile.alloc(kind, new_bytes);
+            if let Some(id) = id {
+                *self.shared.entry(id).or_default() += 1;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get the heap bytes to account for when binding a value (shared data is accounted for once).
+    fn charge(&self, (_, bytes, id): &Footprint) -> usize {
+        match id {
+            Some(id) if self.shared.contains_key(id) => 0,
+            _ => *bytes,
+        }
+    }
+
+    /// Get the heap bytes freed when unbinding a value (shared data is freed with its last binding).
+    fn refund(&self, (_, bytes, id): &Footprint) -> usize {
+        match id.and_then(|id| self.shared.get(&id)) {
+            Some(&count) if count > 1 => 0,
+            _ => *bytes,
+        }
+    }
+
+    /// Record unbinding a value.
+    fn release(&mut self, (kind, _, id): Footprint, bytes: usize) {
+        self.profile.free(kind, bytes);
+
+        if let Some(id) = id {
+            match self.shared.get_mut(&id) {
+                Some(count) if *count > 1 => *count -= 1,
+                _ => {
+                    self.shared.remove(&id);
+                }
+            }
+        }
+    }
+
+    fn current_scope(&mut self) -> &mut Scope<'a> {
+        self.scopes.last_mut().expect("global scope")
+    }
+}
+
+impl Default for Interpreter<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+fn run(code: &str) -> (Result<Value, RuntimeError>, String) {
+    let ast = crate::front::parse_source(code).unwrap();
+    let mut output = Vec::new();
+    let result = Interpreter::with_output(&mut output).run(&ast);
+
+    (result, String::from_utf8(output).unwrap())
+}
+
+#[test]
+fn eval_test() {
+    let test = |code: &str, expected: Value| {
+        assert_eq!(run(code).0, Ok(expected), "{code}");
+    };
+
+    test("1 + 2 * 3;", Value::Number(7.0));
+    test("(1 + 2) * 3 % 5;", Value::Number(4.0));
+    test("-(2 - 5) / 2;", Value::Number(1.5));
+    test("1 < 2 && !(2 <= 1) || false;", Value::Bool(true));
+    test("\"a\" == \"a\";", Value::Bool(true));
+    test("let x = { let a = 42; a + 1 }; x;", Value::Number(43.0));
+    test("if 1 > 2 { \"yes\" } else { \"no\" };", Value::from("no"));
+    test("if false { 1 };", Value::Unit);
+    test(
+        "let mut n = 0; while n < 5 { n = n + 1; }; n;",
+        Value::Number(5.0),
+    );
+    test(
+        "let mut s = 0; for i in 1..=4 { s = s + i; }; s;",
+        Value::Number(10.0),
+    );
+    test(
+        "let mut s = 0; for i in 0..0 { s = 1; }; s;",
+        Value::Number(0.0),
+    );
+    test("let x; x = 3; x;", Value::Number(3.0));
+    test("return 1; 2;", Value::Number(1.0));
+    test("{ 1 };;", Value::Number(1.0));
+    test("let x = { 2;; }; x;", Value::Number(2.0));
+    test("2 ** 3 ** 2;", Value::Number(512.0));
+    test("-2 ** 2 * 3;", Value::Number(-12.0));
+    test("\" ab \" |> trim |> len;", Value::Number(2.0));
+    test(
+        "fn f(a: Number, b: Number) -> Number { a - b } 5 |> f(2);",
+        Value::Number(3.0),
+    );
+    test(
+        "fn f(a: Number, b: Number) -> Number { a * 10 + b } 2 |> f(1) |> f(3);",
+        Value::Number(213.0),
+    );
+    test("\"ab\" + \"c\" + \"\";", Value::from("abc"));
+    test("\"ab\" * 3;", Value::from("ababab"));
+    test("2 * \"-\" + \">\";", Value::from("-->"));
+    test("\"x\" * 0;", Value::from(""));
+    test("\"\" * (2 ** 62);", Value::from(""));
+    test("try (\"ab\" * (2 ** 62));", Value::Nil);
+    test(
+        "\"abc\" < \"abd\" && \"b\" > \"abc\" && \"a\" <= \"a\";",
+        Value::Bool(true),
+    );
+    test("\"Z\" >= \"a\";", Value::Bool(false));
+    test("2 * 3 ** 2;", Value::Number(18.0));
+    test(
+        "fn f() -> Number { let mut n = 0; loop { n = n + 1; if n == 3 { return n * 2; } } } f();",
+        Value::Number(6.0),
+    );
+
+    test("\" 42 \" as Number + 1;", Value::Number(43.0));
+    test("\"n = \" + 1.5 as String;", Value::from("n = 1.5"));
+    test(
+        "(1 < 2) as String + \"false\" as bool as String;",
+        Value::from("truefalse"),
+    );
+    test("try \"x\" as Number;", Value::Nil);
+
+    let (result, output) = run("let t = time { print 1; sleep(2); }; t >= 2;");
+    assert_eq!(result, Ok(Value::Bool(true)));
+    assert_eq!(output, "1");
+}
+
+#[test]
+fn function_test() {
+    let test = |code: &str, expected: Value| {
+        assert_eq!(run(code).0, Ok(expected), "{code}");
+    };
+
+    test(
+        "let y = f(2); fn f(x: Number) -> Number { x * 2 } y;",
+        Value::Number(4.0),
+    );
+    test(
+        "fn fac(n: Number) -> Number { if n <= 1 { return 1; }; n * fac(n - 1) } fac(5);",
+        Value::Number(120.0),
+    );
+    test(
+        "const G: Number = 1; fn f() -> Number { fn g() -> Number { G + 1 } g() } f();",
+        Value::Number(2.0),
+    );
+    test(
+        "let mut c = 0; fn inc() { c = c + 1; } inc(); inc(); c;",
+        Value::Number(2.0),
+    );
+    test("sqrt(pow(3, 2) + 4 ** 2);", Value::Number(5.0));
+    test(
+        "fn abs(x: Number) -> Number { x } abs(-1);",
+        Value::Number(-1.0),
+    );
+
+    // Method calls pass the receiver as first argument.
+    test("\" ab \".trim().len();", Value::Number(2.0));
+    test(
+        "fn add(x: Number, y: Number) -> Number { x + y } let one = 1; one.add(2).add(3);",
+        Value::Number(6.0),
+    );
+    test("let x = 2; x.pow(3).max(9);", Value::Number(9.0));
+    assert_eq!(
+        run("\"ab\".len(1);").0,
+        Err(RuntimeError::ArgumentCount(Symbol::intern("len"), 1, 2))
+    );
+}
+
+#[test]
+fn try_test() {
+    let test = |code: &str, expected: Value| {
+        assert_eq!(run(code).0, Ok(expected), "{code}");
+    };
+
+    test("try to_number(\"x\");", Value::Nil);
+    test("try to_number(\" 1\") + 1;", Value::Number(2.0));
+    test("(try 1 + \"a\") == nil;", Value::Bool(true));
+    test("try { let x = 1; x + true };", Value::Nil);
+    test("nil != 1;", Value::Bool(true));
+
+    // Propagation returns `nil` from the current function only.
+    test(
+        "fn f(s: String) -> Number { (try to_number(s))? + 1 } let a = f(\"x\"); let b = f(\"1\"); a == nil && b == 2;",
+        Value::Bool(true),
+    );
+    test("let x = nil; x?; 1;", Value::Nil);
+
+    // Propagation also returns `nil` on recoverable errors (as `try`), but not on other errors.
+    test(
+        "fn f(s: String) -> Number { to_number(s)? + 1 } let a = f(\"x\"); let b = f(\"1\"); a == nil && b == 2;",
+        Value::Bool(true),
+    );
+    test(
+        "fn f() -> Number { let x = (1 + \"a\")?; 1 } f();",
+        Value::Nil,
+    );
+    assert_eq!(
+        run("fn f() { exit(3)?; } f();").0,
+        Err(RuntimeError::Exit(3))
+    );
+
+    // Exiting, panicking and exceeding resource limits cannot be handled.
+    assert_eq!(run("try exit(2);").0, Err(RuntimeError::Exit(2)));
+    assert_eq!(
+        run("try panic(\"p\");").0,
+        Err(RuntimeError::Panic("p".to_owned()))
+    );
+    assert!(matches!(
+        run("fn f() { f(); } try f();").0,
+        Err(RuntimeError::LimitExceeded(_))
+    ));
+
+    // Scopes are unwound when recovering.
+    let ast =
+        crate::front::parse_source("fn f() { let s = \"abc\"; { let t = s; t.x(); } } try f();")
+            .unwrap();
+    let mut interpreter = Interpreter::with_output(io::sink());
+    assert_eq!(interpreter.run(&ast), Ok(Value::Nil));
+    assert_eq!(interpreter.profile().live_bytes(), 0);
+    assert_eq!((interpreter.scopes.len(), interpreter.frames.len()), (1, 0));
+}
+
+#[test]
+fn defer_test() {
+    let test = |code: &str, expected: (Result<Value, RuntimeError>, &str)| {
+        assert_eq!(run(code), (expected.0, expected.1.to_owned()), "{code}");
+    };
+
+    test(
+        "{ let x = 1; defer print x; defer { print 2; } print 3; } print 4;",
+        (Ok(Value::Unit), "3214"),
+    );
+    test(
+        "fn f() -> Number { defer print \"d\"; return 1; print \"x\"; } f();",
+        (Ok(Value::Number(1.0)), "d"),
+    );
+    test(
+        "defer print \"end\"; for i in 0..2 { defer print i; } { 5 };",
+        (Ok(Value::Number(5.0)), "01end"),
+    );
+    test(
+        "fn f() { defer print \"d\"; 1 + true; } try f();",
+        (Ok(Value::Nil), "d"),
+    );
+
+    // The first error is reported, after executing all deferred statements.
+    test(
+        "{ defer print 1; defer 1 + true; defer print 2; undefined; }",
+        (
+            Err(RuntimeError::UndefinedVariable(
+                Symbol::intern("undefined"),
+                None,
+            )),
+            "21",
+        ),
+    );
+    assert!(matches!(
+        run("fn f() -> Number { defer 1 + true; 1 } f();").0,
+        Err(RuntimeError::TypeMismatch(_))
+    ));
+    test(
+        "let x = nil; { defer x?; 1 };",
+        (Ok(Value::Number(1.0)), ""),
+    );
+}
+
+#[test]
+fn range_test() {
+    let test = |code: &str, expected: &str| {
+        assert_eq!(run(code).1, expected, "{code}");
+    };
+
+    test("let r = 0..3; for i in r { print i; }; print r;", "0120..3");
+    test("for i in (1..=3).rev() { print i; };", "321");
+    test("for i in (0..10).step_by(4) { print i; };", "048");
+    test("for i in (0..10).rev().step_by(4) { print i; };", "951");
+    test(
+        "let a = 3; for i in a..1 { print i; }; print (a..1).rev();",
+        "3..3 (step -1)",
+    );
+    test("for i in 10..0 step -3 { print i; };", "10741");
+    test("for i in 0..=1 step 0.5 { print i; };", "00.51");
+    test(
+        "let step = 2; for i in 0..(2 * step) step step { print i; };",
+        "02",
+    );
+    test(
+        "print 4..=0 step -2, len(0..2 step -1);",
+        "4..=0 (step -2) 0",
+    );
+    test(
+        "let n = 2; print len(0..=n), (0..=n).contains(2), (0..n).contains(2);",
+        "3 true false",
+    );
+    test(
+        "print (0..10).step_by(3).contains(6), (0..10).step_by(3).contains(5);",
+        "true false",
+    );
+    test(
+        "print (0..2) == (0..2), (0..2) == (0..=2), type_of(1..2);",
+        "true false Range",
+    );
+
+    assert_eq!(
+        run("for i in 3 {};").0,
+        Err(RuntimeError::TypeMismatch(
+            "for loop expects a range, found Number".to_owned()
+        ))
+    );
+    assert_eq!(
+        run("let s = 0; 0..3 step s;").0,
+        Err(RuntimeError::InvalidArgument(
+            "range step must be a nonzero number, found 0".to_owned()
+        ))
+    );
+    assert_eq!(
+        run("(0..3).step_by(0);").0,
+        Err(RuntimeError::InvalidArgument(
+            "range step must be positive, found 0".to_owned()
+        ))
+    );
+}
+
+#[test]
+fn index_test() {
+    let test = |code: &str, expected: Result<Value, RuntimeError>| {
+        assert_eq!(run(code).0, expected, "{code}");
+    };
+    let ok = |s: &str| Ok(Value::from(s));
+    let invalid = |e: &str| Err(RuntimeError::InvalidIndex(e.to_owned()));
+
+    test("\"héllo\"[1];", ok("é"));
+    test(
+        "let s = \"héllo\"; s[1..3] + s[..1] + s[4..] + s[..];",
+        ok("élhohéllo"),
+    );
+    test("\"abc\"[1..=1];", ok("b"));
+    test("\"abc\"[3..];", ok(""));
+    test("let n = 3; \"abc\"[(0..n).rev()];", ok("cba"));
+    test("\"abcde\"[(0..5).step_by(2)];", ok("ace"));
+    test("let n = 1; \"abcd\"[n * 2..][n - 1];", ok("c"));
+
+    test(
+        "\"abc\"[3];",
+        invalid("index 3 out of range for string of length 3"),
+    );
+    test(
+        "\"abc\"[2..4];",
+        invalid("slice [2, 4) out of range for string of length 3"),
+    );
+    test(
+        "\"abc\"[2..1];",
+        invalid("slice [2, 1) out of range for string of length 3"),
+    );
+    test(
+        "\"abc\"[(0..4).rev()];",
+        invalid("index 3 out of range for string of length 3"),
+    );
+    test(
+        "\"abc\"[-1];",
+        invalid("index must be a non-negative integer, found -1"),
+    );
+    test(
+        "1[0];",
+        Err(RuntimeError::TypeMismatch("cannot index Number".to_owned())),
+    );
+    test(
+        "\"abc\"[true];",
+        Err(RuntimeError::TypeMismatch(
+            "index must be Number or Range, found bool".to_owned(),
+        )),
+    );
+}
+
+#[test]
+fn enum_test() {
+    let test = |code: &str, expected: Result<Value, RuntimeError>| {
+        assert_eq!(run(code).0, expected, "{code}");
+    };
+
+    let sym = Symbol::intern;
+    let decl = "enum Mode { Idle, Run } enum Level { Low, Run } ";
+
+    test(
+        &format!("{decl}Mode.Run == Mode.Run;"),
+        Ok(Value::Bool(true)),
+    );
+    test(
+        &format!("{decl}let m = Mode.Idle; m != Mode.Run;"),
+        Ok(Value::Bool(true)),
+    );
+    test(
+        &format!("{decl}Mode.Run == Level.Run;"),
+        Err(RuntimeError::TypeMismatch(
+            "cannot apply '==' to Mode and Level".to_owned(),
+        )),
+    );
+    test(
+        &format!("{decl}Mode.Low;"),
+        Err(RuntimeError::UnknownVariant(sym("Mode"), sym("Low"))),
+    );
+
+    // Enums are declared when entering the block, like functions.
+    test(
+        "fn f() -> bool { Mode.Idle == Mode.Idle } enum Mode { Idle } f();",
+        Ok(Value::Bool(true)),
+    );
+    assert_eq!(
+        run("enum Mode { Idle, Run } print Mode.Run, Mode.Idle as String;").1,
+        "Mode.Run Mode.Idle"
+    );
+}
+
+#[test]
+fn print_test() {
+    assert_eq!(
+        run("print 1, \"two\", true; println; println 1.5, 2 > 3;").1,
+        "1 two true\n1.5 false\n"
+    );
+}
+
+#[test]
+fn runtime_error_test() {
+    let test = |code: &str, expected: RuntimeError| {
+        assert_eq!(run(code).0, Err(expected), "{code}");
+    };
+
+    let sym = Symbol::intern;
+
+    test("x;", RuntimeError::UndefinedVariable(sym("x"), None));
+    test("let x; x + 1;", RuntimeError::Uninitialized(sym("x")));
+    test("let x = 1; x = 2;", RuntimeError::AssignImmutable(sym("x")));
+    test("f();", RuntimeError::UndefinedFunction(sym("f"), None));
+    test(
+        "sqrt(1, 2);",
+        RuntimeError::ArgumentCount(sym("sqrt"), 1, 2),
+    );
+    test(
+        "fn f(a: Number) {} f();",
+        RuntimeError::ArgumentCount(sym("f"), 1, 0),
+    );
+    test(
+        "fn f() { let l = 1; } fn g() { l; } g();",
+        RuntimeError::UndefinedVariable(sym("l"), None),
+    );
+    test(
+        "fn g() -> Number { l } fn f() -> Number { let l = 1; g() } f();",
+        RuntimeError::UndefinedVariable(sym("l"), None),
+    );
+    test(
+        "let counter = 1; countr;",
+        RuntimeError::UndefinedVariable(sym("countr"), Some("counter")),
+    );
+    test(
+        "fn f() { let counter = 1; } fn g() { countr; } g();",
+        RuntimeError::UndefinedVariable(sym("countr"), None),
+    );
+    test(
+        "whle;",
+        RuntimeError::UndefinedVariable(sym("whle"), Some("while")),
+    );
+    test(
+        "fn total() {} totl();",
+        RuntimeError::UndefinedFunction(sym("totl"), Some("total")),
+    );
+    test(
+        "sqr(4);",
+        RuntimeError::UndefinedFunction(sym("sqr"), Some("sqrt")),
+    );
+    test(
+        "1 + \"a\";",
+        RuntimeError::TypeMismatch("cannot apply '+' to Number and String".to_owned()),
+    );
+    test(
+        "\"a\" - \"b\";",
+        RuntimeError::TypeMismatch("cannot apply '-' to String and String".to_owned()),
+    );
+    test(
+        "\"a\" < 1;",
+        RuntimeError::TypeMismatch("cannot apply '<' to String and Number".to_owned()),
+    );
+    test(
+        "\"a\" * \"b\";",
+        RuntimeError::TypeMismatch("cannot apply '*' to String and String".to_owned()),
+    );
+    test(
+        "1 as bool;",
+        RuntimeError::TypeMismatch("cannot convert Number to bool".to_owned()),
+    );
+    test(
+        "\"yes\" as bool;",
+        RuntimeError::InvalidArgument("cannot convert \"yes\" to bool".to_owned()),
+    );
+    test(
+        "\"a\" * 1.5;",
+        RuntimeError::InvalidArgument(
+            "string repetition count must be a non-negative integer, found 1.5".to_owned(),
+        ),
+    );
+    test(
+        "\"ab\" * (2 ** 62);",
+        RuntimeError::InvalidArgument(
+            "string repetition count 4611686018427388000 is too large".to_owned(),
+        ),
+    );
+    test(
+        "if 1 { 2 };",
+        RuntimeError::NotBool("if condition".to_owned(), "Number"),
+    );
+    test(
+        "while \"\" {};",
+        RuntimeError::NotBool("while condition".to_owned(), "String"),
+    );
+    test(
+        "true && nil;",
+        RuntimeError::NotBool("'&&' operand".to_owned(), "nil"),
+    );
+    test(
+        "node \"N\";",
+        RuntimeError::Unsupported("node/group instantiation"),
+    );
+}
+
+#[test]
+fn reference_test() {
+    let sym = Symbol::intern;
+
+    assert_eq!(
+        run("let x = 1; let r = &x; r + 1;").0,
+        Ok(Value::Number(2.0))
+    );
+    assert_eq!(
+        run("let mut x = 1; let r = &x; x = 2; r;").0,
+        Ok(Value::Number(2.0))
+    );
+    assert_eq!(
+        run("let mut x = 1; { let mut r = &x; r = r + 1; }; x;").0,
+        Ok(Value::Number(2.0))
+    );
+    assert_eq!(
+        run("let mut x = 1; let mut r = &x; let mut rr = &r; rr = 3; x;").0,
+        Ok(Value::Number(3.0))
+    );
+    assert_eq!(
+        run("let mut x = 1; let r = &x; let x = 5; x + r;").0,
+        Ok(Value::Number(6.0))
+    );
+    assert_eq!(
+        run("fn f(a: Number) -> Number { a } let x = 1; let r = &x; f(r);").0,
+        Ok(Value::Number(1.0))
+    );
+
+    assert_eq!(
+        run("let mut x = 1; let r = &x; r = 2;").0,
+        Err(RuntimeError::AssignImmutable(sym("r")))
+    );
+    assert_eq!(
+        run("let x = 1; let mut r = &x;").0,
+        Err(RuntimeError::MutableRefImmutable(sym("x")))
+    );
+    assert_eq!(
+        run("let x; let r = &x;").0,
+        Err(RuntimeError::Uninitialized(sym("x")))
+    );
+    assert_eq!(
+        run("let r = &x;").0,
+        Err(RuntimeError::UndefinedVariable(sym("x"), None))
+    );
+    assert_eq!(
+        run("let x = 1; let r = &x.y;").0,
+        Err(RuntimeError::Unsupported("member references"))
+    );
+
+    // The referenced value is allocated (and freed) once.
+    let code = "let s = \"abc\"; let r = &s; let s = 1; { let t = &r; };";
+    let (ast, shadow) = (
+        crate::front::parse_source(code).unwrap(),
+        crate::front::parse_source("let r = 2;").unwrap(),
+    );
+    let mut interpreter = Interpreter::with_output(io::sink());
+    interpreter.run(&ast).unwrap();
+    assert_eq!(interpreter.profile().live_bytes(), 3);
+    interpreter.run(&shadow).unwrap();
+    assert_eq!(interpreter.profile().live_bytes(), 0);
+}
+
+#[test]
+fn shared_value_test() {
+    let parse = |code: &str| crate::front::parse_source(code).unwrap();
+    let asts = [
+        parse(
+            "let a = \"abcd\"; let b = a; fn f(s: String) -> String { let t = s; t } let c = f(b);",
+        ),
+        parse("let a = 1; let b = 2;"),
+        parse("let c = \"xy\";"),
+    ];
+    let mut interpreter = Interpreter::with_output(io::sink());
+
+    // Copies share the string data, which is accounted for once.
+    interpreter.run(&asts[0]).unwrap();
+    assert_eq!(interpreter.profile().kind("String").live, 3);
+    assert_eq!(interpreter.profile().live_bytes(), 4);
+    assert_eq!(interpreter.profile().peak_bytes(), 4);
+
+    // The data is freed with its last binding.
+    interpreter.run(&asts[1]).unwrap();
+    assert_eq!(interpreter.profile().live_bytes(), 4);
+    interpreter.run(&asts[2]).unwrap();
+    assert_eq!(interpreter.profile().live_bytes(), 2);
+
+    let heap = Limits {
+        max_heap: Some(6),
+        ..Limits::default()
+    };
+    let ast = parse("let a = \"abcd\"; let b = a; let c = b; let d = \"efgh\";");
+    assert_eq!(
+        Interpreter::with_output(io::sink())
+            .with_limits(heap)
+            .run(&ast),
+        Err(RuntimeError::LimitExceeded(Limit::Heap(6)))
+    );
+}
+
+#[test]
+fn release_test() {
+    let code = "{
+        let s = \"abc\";
+        fn f(a: String) -> String { let mut t = a; let mut r = &t; r = \"def\"; t }
+        let mut u = f(s);
+        { let v = &u; let w = &v; u = s; };
+    };";
+    let ast = crate::front::parse_source(code).unwrap();
+    let mut interpreter = Interpreter::with_output(io::sink());
+    interpreter.run(&ast).unwrap();
+
+    // All values are freed when leaving their scope, nothing is kept alive by references.
+    assert_eq!(interpreter.profile().peak_bytes(), 6);
+    assert_eq!(interpreter.profile().live_bytes(), 0);
+    assert_eq!(interpreter.profile().kind("String").live, 0);
+    assert!(interpreter.shared.is_empty());
+}
+
+#[test]
+fn limits_test() {
+    let test = |code: &str, limits: Limits| {
+        let ast = crate::front::parse_source(code).unwrap();
+        let ok = crate::front::parse_source("let ok = 1; ok;").unwrap();
+        let mut interpreter = Interpreter::with_output(io::sink()).with_limits(limits);
+        let result = interpreter.run(&ast);
+
+        // The interpreter remains usable after exceeding a (non-cumulative) limit.
+        if !matches!(result, Err(RuntimeError::LimitExceeded(Limit::Steps(_)))) {
+            assert_eq!(interpreter.run(&ok), Ok(Value::Number(1.0)));
+        }
+
+        result
+    };
+
+    let unlimited = Limits {
+        max_steps: None,
+        max_call_depth: None,
+        max_heap: None,
+    };
+
+    assert_eq!(
+        test(
+            "let mut n = 0; while true { n = n + 1; };",
+            Limits {
+                max_steps: Some(10_000),
+                ..unlimited
+            }
+        ),
+        Err(RuntimeError::LimitExceeded(Limit::Steps(10_000)))
+    );
+    assert_eq!(
+        test("fn f() { f(); } f();", Limits::default()),
+        Err(RuntimeError::LimitExceeded(Limit::CallDepth(
+            crate::limits::DEFAULT_MAX_CALL_DEPTH
+        )))
+    );
+    assert_eq!(
+        test(
+            "fn f(n: Number) -> Number { if n > 0 { f(n - 1) } else { 0 } } f(10);",
+            Limits {
+                max_call_depth: Some(11),
+                ..unlimited
+            }
+        ),
+        Ok(Value::Number(0.0))
+    );
+
+    let heap = Limits {
+        max_heap: Some(10),
+        ..unlimited
+    };
+
+    assert_eq!(
+        test("let a = \"12345\"; let b = \"67890\"; let c = \"x\";", heap),
+        Err(RuntimeError::LimitExceeded(Limit::Heap(10)))
+    );
+    assert_eq!(
+        test("\"ab\" * 1000000;", heap),
+        Err(RuntimeError::LimitExceeded(Limit::Heap(10)))
+    );
+    assert_eq!(
+        test(
+            "let mut a = \"12345\"; a = \"67890\"; { let b = \"12345\"; }; let c = \"12345\";",
+            heap
+        ),
+        Ok(Value::Unit)
+    );
+}
+
+#[test]
+fn expr_depth_test() {
+    // Unlimited recursion is stopped by the expression nesting limit, on a stack the size of the main thread stack.
+    std::thread::Builder::new()
+        .stack_size(8 * 1024 * 1024)
+        .spawn(|| {
+            let ast =
+                crate::front::parse_source("fn f(n: Number) -> Number { f(n + 1) } f(0);").unwrap();
+            let limits = Limits {
+                max_call_depth: None,
+                ..Default::default()
+            };
+
+            let mut interpreter = Interpreter::with_output(io::sink()).with_limits(limits);
+
+            assert_eq!(
+                interpreter.run(&ast),
+                Err(RuntimeError::LimitExceeded(Limit::ExprDepth(
+                    MAX_EXPR_DEPTH
+                )))
+            );
+            assert_eq!(interpreter.depth, 0);
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+}
+
+#[test]
+fn numeric_mode_test() {
+    let test = |code: &str, mode: NumericMode| {
+        let ast = crate::front::parse_source(code).unwrap();
+        let result = Interpreter::with_output(io::sink())
+            .with_numeric_mode(mode)
+            .run(&ast);
+        result
+    };
+    let ieee = |code: &str| test(code, NumericMode::Ieee);
+    let strict = |code: &str| test(code, NumericMode::Strict);
+
+    // IEEE semantics: non-finite results, to be tested with `is_nan` and `is_finite`.
+    assert_eq!(ieee("1 / 0;"), Ok(Value::Number(f64::INFINITY)));
+    assert_eq!(ieee("-1 / 0;"), Ok(Value::Number(f64::NEG_INFINITY)));
+    assert_eq!(ieee("is_nan(0 % 0);"), Ok(Value::Bool(true)));
+    assert_eq!(ieee("is_nan(sqrt(-1));"), Ok(Value::Bool(true)));
+    assert_eq!(ieee("is_finite(10 ** 400);"), Ok(Value::Bool(false)));
+    assert_eq!(
+        ieee("is_finite(1 / 3) && !is_nan(1);"),
+        Ok(Value::Bool(true))
+    );
+
+    // Strict: domain errors, which can be handled by `try`.
+    let error = |e| Err(RuntimeError::Numeric(e));
+
+    assert_eq!(strict("1 / 0;"), error(DomainError::DivisionByZero));
+    assert_eq!(strict("0 / 0;"), error(DomainError::DivisionByZero));
+    assert_eq!(strict("0 % 0;"), error(DomainError::RemainderByZero));
+    assert_eq!(strict("sqrt(-1);"), error(DomainError::NotANumber));
+    assert_eq!(strict("log(0);"), error(DomainError::Overflow));
+    assert_eq!(strict("10 ** 400;"), error(DomainError::Overflow));
+    assert_eq!(
+        strict("10 ** 300 * 10 ** 10;"),
+        error(DomainError::Overflow)
+    );
+    assert_eq!(strict("try (1 / 0);"), Ok(Value::Nil));
+    assert_eq!(strict("7 % 4 + 1 / 4;"), Ok(Value::Number(3.25)));
+}
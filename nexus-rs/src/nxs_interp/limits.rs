@@ -0,0 +1,61 @@
+use std::fmt;
+
+/// Default maximum function call depth, keeping deep recursion from overflowing the host stack.
+pub const DEFAULT_MAX_CALL_DEPTH: usize = 100;
+
+/// Maximum expression nesting depth (over all function calls), the stack budget of the interpreter.
+///
+/// Every evaluated expression takes host stack space, so this bounds both deeply nested expressions and deep
+///  recursion (each call nests at least one expression), whatever the call depth limit is. It is not configurable:
+///  it is sized to fit the default (8 MiB) main thread stack in unoptimized builds.
+pub const MAX_EXPR_DEPTH: usize = 500;
+
+/// Interpreter resource limits, `None` meaning unlimited.
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::{front::parse_source, interpreter::Interpreter, limits::*, runtime_error::RuntimeError};
+///
+/// let ast = parse_source("while true {};").unwrap();
+/// let limits = Limits { max_steps: Some(1000), ..Default::default() };
+///
+/// assert_eq!(Interpreter::new().with_limits(limits).run(&ast),
+///            Err(RuntimeError::LimitExceeded(Limit::Steps(1000))));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Limits {
+    pub max_steps: Option<u64>, // Maximum number of evaluated statements/expressions.
+    pub max_call_depth: Option<usize>, // Maximum function call nesting depth.
+    pub max_heap: Option<usize>, // Maximum number of bytes held by live values.
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_steps: None,
+            max_call_depth: Some(DEFAULT_MAX_CALL_DEPTH),
+            max_heap: None,
+        }
+    }
+}
+
+/// Exceeded resource limit.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Limit {
+    Steps(u64),
+    CallDepth(usize),
+    ExprDepth(usize),
+    Heap(usize),
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Limit::Steps(n) => write!(f, "more than {n} evaluation steps"),
+            Limit::CallDepth(n) => write!(f, "function calls nested more than {n} deep"),
+            Limit::ExprDepth(n) => write!(f, "expressions nested more than {n} deep"),
+            Limit::Heap(n) => write!(f, "more than {n} bytes of live values"),
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use std::fmt;
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+/// Numeric error policy: how operations with an undefined or unbounded result (e.g. `1 / 0`) are handled.
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::{front::parse_source, interpreter::Interpreter, numeric::*, runtime_error::RuntimeError};
+///
+/// let ast = parse_source("1 / 0;").unwrap();
+///
+/// assert!(Interpreter::new().run(&ast).is_ok());
+/// assert_eq!(Interpreter::new().with_numeric_mode(NumericMode::Strict).run(&ast),
+///            Err(RuntimeError::Numeric(DomainError::DivisionByZero)));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum NumericMode {
+    /// IEEE 754 semantics: results may be infinite or NaN (e.g. `1 / 0` is `inf`, `0 % 0` is `NaN`).
+    #[default]
+    Ieee,
+
+    /// A non-finite result of finite operands is a runtime error.
+    Strict,
+}
+
+/// Numeric domain error, reported in strict mode (see [`NumericMode::Strict`]).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DomainError {
+    DivisionByZero,
+    RemainderByZero,
+    NotANumber,
+    Overflow,
+}
+
+impl NumericMode {
+    /// Check the result of a numeric operation on the given operands, where `divisor` is the right operand of a
+    ///  division or remainder (if any).
+    ///
+    /// Operations on non-finite operands are not checked: the error is reported where the first non-finite value
+    ///  arises.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nexus_rs::numeric::*;
+    ///
+    /// assert_eq!(NumericMode::Strict.check(&[2.0], 2f64.sqrt(), None), Ok(2f64.sqrt()));
+    /// assert_eq!(NumericMode::Strict.check(&[-1.0], (-1f64).sqrt(), None), Err(DomainError::NotANumber));
+    /// assert_eq!(NumericMode::Ieee.check(&[-1.0], (-1f64).sqrt(), None).map(f64::is_nan), Ok(true));
+    /// ```
+    pub fn check(
+        &self,
+        operands: &[f64],
+        result: f64,
+        divisor: Option<(f64, DomainError)>,
+    ) -> Result<f64, DomainError> {
+        if *self == NumericMode::Ieee
+            || result.is_finite()
+            || !operands.iter().all(|x| x.is_finite())
+        {
+            return Ok(result);
+        }
+
+        match divisor {
+            Some((0.0, error)) => Err(error),
+            _ if result.is_nan() => Err(DomainError::NotANumber),
+            _ => Err(DomainError::Overflow),
+        }
+    }
+}
+
+impl fmt::Display for DomainError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DomainError::DivisionByZero => write!(f, "division by zero"),
+            DomainError::RemainderByZero => write!(f, "remainder by zero"),
+            DomainError::NotANumber => write!(f, "result is not a number (NaN)"),
+            DomainError::Overflow => write!(f, "result is infinite (overflow)"),
+        }
+    }
+}
+
+#[test]
+fn check_test() {
+    let strict = |operands: &[f64], result: f64, divisor| {
+        NumericMode::Strict.check(operands, result, divisor)
+    };
+    let division = |d| Some((d, DomainError::DivisionByZero));
+
+    assert_eq!(
+        strict(&[1.0, 0.0], f64::INFINITY, division(0.0)),
+        Err(DomainError::DivisionByZero)
+    );
+    assert_eq!(
+        strict(&[0.0, 0.0], f64::NAN, division(0.0)),
+        Err(DomainError::DivisionByZero)
+    );
+    assert_eq!(
+        strict(
+            &[0.0, 0.0],
+            f64::NAN,
+            Some((0.0, DomainError::RemainderByZero))
+        ),
+        Err(DomainError::RemainderByZero)
+    );
+    assert_eq!(
+        strict(&[1e300, 1e300], 1e300 * 1e300, None),
+        Err(DomainError::Overflow)
+    );
+    assert_eq!(strict(&[0.0], 0f64.ln(), None), Err(DomainError::Overflow));
+    assert_eq!(strict(&[1.0, 2.0], 0.5, division(2.0)), Ok(0.5));
+
+    // Non-finite operands are passed on.
+    assert!(strict(&[f64::INFINITY, 1.0], f64::INFINITY, None).is_ok());
+    assert!(strict(&[f64::NAN], f64::NAN, None).is_ok());
+}
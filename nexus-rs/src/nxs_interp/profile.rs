@@ -0,0 +1,312 @@
+use crate::ast::StmtKind;
+use crate::interpreter::ExecEvent;
+use std::{
+    collections::BTreeMap,
+    fmt,
+    time::{Duration, Instant},
+};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+/// Allocation statistics of one kind of runtime value.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct KindStats {
+    pub allocated: usize, // Total number of values bound.
+    pub live: usize,      // Number of values currently bound.
+    pub peak: usize,      // Maximum number of values bound at once.
+}
+
+/// Heap profile of the runtime values bound to variables, by kind (type name).
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::{front::parse_source, interpreter::Interpreter};
+///
+/// let ast = parse_source("let mut s = \"a\"; for i in 0..3 { let t = \"b\"; s = t; };").unwrap();
+/// let mut interpreter = Interpreter::new();
+/// interpreter.run(&ast).unwrap();
+///
+/// let strings = interpreter.profile().kind("String");
+/// assert_eq!((strings.allocated, strings.live, strings.peak), (7, 1, 2));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HeapProfile {
+    kinds: BTreeMap<&'static str, KindStats>,
+    live_bytes: usize,
+    peak_bytes: usize,
+}
+
+impl HeapProfile {
+    /// Create a new, empty profile.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the allocation of a value.
+    pub fn alloc(&mut self, kind: &'static str, bytes: usize) {
+        let stats = self.kinds.entry(kind).or_default();
+        stats.allocated += 1;
+        stats.live += 1;
+        stats.peak = stats.peak.max(stats.live);
+
+        self.live_bytes += bytes;
+        self.peak_bytes = self.peak_bytes.max(self.live_bytes);
+    }
+
+    /// Record the release of a value.
+    pub fn free(&mut self, kind: &'static str, bytes: usize) {
+        if let Some(stats) = self.kinds.get_mut(kind) {
+            stats.live -= 1;
+        }
+
+        self.live_bytes -= bytes;
+    }
+
+    /// Get the statistics of a kind of value.
+    pub fn kind(&self, kind: &str) -> KindStats {
+        self.kinds.get(kind).copied().unwrap_or_default()
+    }
+
+    /// Get the statistics of all kinds of values allocated (ordered by kind).
+    pub fn kinds(&self) -> impl Iterator<Item = (&'static str, KindStats)> + '_ {
+        self.kinds.iter().map(|(k, s)| (*k, *s))
+    }
+
+    /// Get the number of heap bytes held by live values.
+    pub fn live_bytes(&self) -> usize {
+        self.live_bytes
+    }
+
+    /// Get the maximum number of heap bytes held by live values at once.
+    pub fn peak_bytes(&self) -> usize {
+        self.peak_bytes
+    }
+}
+
+impl fmt::Display for HeapProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<8} {:>10} {:>10} {:>10}",
+            "kind", "allocated", "live", "peak"
+        )?;
+
+        for (kind, stats) in self.kinds() {
+            writeln!(
+                f,
+                "{kind:<8} {:>10} {:>10} {:>10}",
+                stats.allocated, stats.live, stats.peak
+            )?;
+        }
+
+        write!(
+            f,
+            "heap: {} bytes live, {} bytes peak",
+            self.live_bytes, self.peak_bytes
+        )
+    }
+}
+
+/// Number of hottest source lines shown in the execution profile report.
+const REPORT_LINES: usize = 10;
+
+/// Call statistics of a function.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CallStats {
+    pub calls: u64,
+    pub time: Duration, // Cumulative time spent in the function (including the functions it calls).
+}
+
+/// Execution profile: statement executions (by source line, blocks excluded) and function calls, recorded from
+///  execution events.
+///
+/// The time of a recursive function is counted for its outermost call only, so it is not counted twice.
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::{front::parse_source, interpreter::Interpreter, profile::ExecProfile};
+///
+/// let ast = parse_source("fn f(n: Number) -> Number {\n  if n > 0 { f(n - 1) } else { 0 }\n}\nf(3);").unwrap();
+/// let mut profile = ExecProfile::new();
+///
+/// Interpreter::new().on_exec(|_, event| profile.record(event)).run(&ast).unwrap();
+///
+/// assert_eq!(profile.function("f").calls, 4);
+/// assert_eq!(profile.line(2), 8); // Four evaluations of the 'if', and of the branch taken.
+/// assert_eq!(profile.statements(), 10);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ExecProfile {
+    statements: u64,
+    lines: BTreeMap<usize, u64>,
+    functions: BTreeMap<&'static str, CallStats>,
+    stack: Vec<(&'static str, Instant)>, // Functions being executed, with their start time.
+}
+
+impl ExecProfile {
+    /// Create a new, empty profile.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record an execution event (see [`Interpreter::on_exec`](crate::interpreter::Interpreter::on_exec)).
+    pub fn record(&mut self, event: ExecEvent) {
+        match event {
+            ExecEvent::Stmt(stmt) if matches!(stmt.kind, StmtKind::Block(_)) => (), // Not counted, as its contents are.
+            ExecEvent::Stmt(stmt) => {
+                self.statements += 1;
+                if let Some(line) = stmt.span.and_then(|s| s.line) {
+                    *self.lines.entry(line).or_default() += 1;
+                }
+            }
+            ExecEvent::Call(id) => {
+                self.functions.entry(id.as_str()).or_default().calls += 1;
+                self.stack.push((id.as_str(), Instant::now()));
+            }
+            ExecEvent::Return(_) => {
+                if let Some((id, start)) = self.stack.pop() {
+                    if !self.stack.iter().any(|(f, _)| *f == id) {
+                        self.functions.entry(id).or_default().time += start.elapsed();
+                    }
+                }
+            }
+        }
+    }
+
+    /// Get the number of statements executed.
+    pub fn statements(&self) -> u64 {
+        self.statements
+    }
+
+    /// Get the number of statements executed (starting) on a source line.
+    pub fn line(&self, line: usize) -> u64 {
+        self.lines.get(&line).copied().unwrap_or_default()
+    }
+
+    /// Get the call statistics of a function.
+    pub fn function(&self, id: &str) -> CallStats {
+        self.functions.get(id).copied().unwrap_or_default()
+    }
+
+    /// Get the call statistics of all functions called, most time spent first.
+    pub fn functions(&self) -> Vec<(&'static str, CallStats)> {
+        let mut functions: Vec<_> = self.functions.iter().map(|(f, s)| (*f, *s)).collect();
+        functions.sort_by(|(f1, s1), (f2, s2)| s2.time.cmp(&s1.time).then(f1.cmp(f2)));
+        functions
+    }
+
+    /// Get the statement counts of all source lines executed, most executed first.
+    pub fn lines(&self) -> Vec<(usize, u64)> {
+        let mut lines: Vec<_> = self.lines.iter().map(|(l, c)| (*l, *c)).collect();
+        lines.sort_by(|(l1, c1), (l2, c2)| c2.cmp(c1).then(l1.cmp(l2)));
+        lines
+    }
+}
+
+impl fmt::Display for ExecProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:<16} {:>10} {:>12}", "function", "calls", "time")?;
+
+        for (function, stats) in self.functions() {
+            writeln!(
+                f,
+                "{function:<16} {:>10} {:>12}",
+                stats.calls,
+                format!("{:.3?}", stats.time)
+            )?;
+        }
+
+        writeln!(f, "{:<16} {:>10}", "line", "statements")?;
+
+        for (line, count) in self.lines().into_iter().take(REPORT_LINES) {
+            writeln!(f, "{line:<16} {count:>10}")?;
+        }
+
+        write!(f, "statements: {} executed", self.statements)
+    }
+}
+
+#[test]
+fn profile_test() {
+    let mut p = HeapProfile::new();
+
+    p.alloc("Number", 0);
+    p.alloc("String", 8);
+    p.alloc("String", 4);
+    p.free("String", 8);
+    p.alloc("String", 2);
+
+    assert_eq!(
+        p.kinds().collect::<Vec<_>>(),
+        [
+            (
+                "Number",
+                KindStats {
+                    allocated: 1,
+                    live: 1,
+                    peak: 1
+                }
+            ),
+            (
+                "String",
+                KindStats {
+                    allocated: 3,
+                    live: 2,
+                    peak: 2
+                }
+            )
+        ]
+    );
+    assert_eq!((p.live_bytes(), p.peak_bytes()), (6, 12));
+    assert_eq!(
+        p.to_string(),
+        "kind      allocated       live       peak\n\
+         Number            1          1          1\n\
+         String            3          2          2\n\
+         heap: 6 bytes live, 12 bytes peak"
+    );
+}
+
+#[test]
+fn exec_profile_test() {
+    use crate::{front::parse_source, interpreter::Interpreter};
+
+    let ast = parse_source(
+        "fn even(n: Number) -> bool { if n == 0 { true } else { odd(n - 1) } }\n\
+         fn odd(n: Number) -> bool { if n == 0 { false } else { even(n - 1) } }\n\
+         even(abs(-4));",
+    )
+    .unwrap();
+
+    let mut p = ExecProfile::new();
+    Interpreter::new()
+        .on_exec(|_, event| p.record(event))
+        .run(&ast)
+        .unwrap();
+
+    assert_eq!(p.function("even").calls, 3);
+    assert_eq!(p.function("odd").calls, 2);
+    assert_eq!(p.function("abs").calls, 1);
+    assert_eq!(p.function("none"), CallStats::default());
+    assert!(p.stack.is_empty());
+
+    // The outermost call of 'even' includes all other calls.
+    assert!(p.function("even").time >= p.function("odd").time);
+    assert_eq!(p.functions()[0].0, "even");
+
+    assert_eq!(p.lines(), [(1, 7), (2, 5), (3, 1)]); // Including the declarations and branches.
+    assert_eq!(p.statements(), 13);
+
+    let report = p.to_string();
+    let report: Vec<_> = report
+        .lines()
+        .map(|l| l.split_whitespace().next())
+        .collect();
+    assert_eq!(report.len(), 9);
+    assert_eq!(report[0], Some("function"));
+    assert_eq!(report[4], Some("line"));
+    assert_eq!(report[8], Some("statements:"));
+}
@@ -0,0 +1,65 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+/// Pseudo-random number generator (SplitMix64), seedable for reproducible runs.
+///
+/// Not suitable for cryptographic purposes.
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::random::Random;
+///
+/// let (mut a, mut b) = (Random::new(42), Random::new(42));
+/// let x = a.next_f64();
+///
+/// assert!((0.0..1.0).contains(&x));
+/// assert_eq!(x, b.next_f64());
+/// ```
+#[derive(Clone, Debug)]
+pub struct Random {
+    state: u64,
+}
+
+impl Random {
+    /// Create a new generator from a seed.
+    pub fn new(seed: u64) -> Self {
+        Random { state: seed }
+    }
+
+    /// Create a new generator, seeded from the system time.
+    pub fn from_time() -> Self {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        Self::new(now.as_nanos() as u64)
+    }
+
+    /// Get the next random 64-bit value.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Get the next random number, uniformly distributed in `[0, 1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[test]
+fn random_test() {
+    // Reference values of SplitMix64.
+    let mut random = Random::new(0);
+    assert_eq!(random.next_u64(), 0xe220_a839_7b1d_cdaf);
+    assert_eq!(random.next_u64(), 0x6e78_9e6a_a1b9_65f4);
+
+    let mut random = Random::new(7);
+    assert!((0..1000).all(|_| (0.0..1.0).contains(&random.next_f64())));
+}
@@ -0,0 +1,156 @@
+use crate::limits::Limit;
+use crate::numeric::DomainError;
+use crate::symbol::Symbol;
+use thiserror::Error;
+
+/// Runtime error representation.
+#[derive(Error, Debug, PartialEq)]
+pub enum RuntimeError {
+    #[error("function '{0}' expects {1} argument(s), found {2}")]
+    ArgumentCount(Symbol, usize, usize),
+
+    #[error("cannot assign twice to immutable variable '{0}'")]
+    AssignImmutable(Symbol),
+
+    #[error("program exited with code {0}")]
+    Exit(i32), // Not an error, but a request to exit the program.
+
+    #[error("invalid argument: {0}")]
+    InvalidArgument(String),
+
+    #[error("invalid assignment target '{0}'")]
+    InvalidAssignment(String),
+
+    #[error("{0}")]
+    InvalidIndex(String), // Out of range, or not an integer.
+
+    #[error("{0}")]
+    Io(String),
+
+    #[error("program interrupted")]
+    Interrupted, // E.g. by Ctrl-C (see `Interpreter::with_interrupt`).
+
+    #[error("function '{0}' requires file or input access, which is denied")]
+    IoDenied(Symbol),
+
+    #[error("resource limit exceeded: {0}")]
+    LimitExceeded(Limit),
+
+    #[error("cannot create a mutable reference to immutable variable '{0}'")]
+    MutableRefImmutable(Symbol),
+
+    #[error("{0} must be bool, found {1}")]
+    NotBool(String, &'static str), // A condition (or logic operand) of another type: values have no truthiness.
+
+    #[error("numeric error: {0}")]
+    Numeric(DomainError), // Only in strict numeric mode.
+
+    #[error("failed to write output: {0}")]
+    Output(String),
+
+    #[error("panic: {0}")]
+    Panic(String),
+
+    #[error("type mismatch: {0}")]
+    TypeMismatch(String),
+
+    #[error("undefined function '{0}'")]
+    UndefinedFunction(Symbol, Option<&'static str>), // With the closest known name, if any.
+
+    #[error("undefined variable '{0}'")]
+    UndefinedVariable(Symbol, Option<&'static str>), // With the closest known name, if any.
+
+    #[error("variable '{0}' is used uninitialized")]
+    Uninitialized(Symbol),
+
+    #[error("enum '{0}' has no variant '{1}'")]
+    UnknownVariant(Symbol, Symbol),
+
+    #[error("{0} not supported (yet)")]
+    Unsupported(&'static str),
+}
+
+impl RuntimeError {
+    /// Check if the error can be handled by a `try` expression.
+    ///
+    /// Exiting, interrupting, panicking and exceeding resource limits always end the program.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nexus_rs::runtime_error::RuntimeError;
+    ///
+    /// assert!(RuntimeError::InvalidArgument("x".to_owned()).is_recoverable());
+    /// assert!(!RuntimeError::Exit(0).is_recoverable());
+    /// ```
+    pub fn is_recoverable(&self) -> bool {
+        !matches!(
+            self,
+            RuntimeError::Exit(_)
+                | RuntimeError::Interrupted
+                | RuntimeError::LimitExceeded(_)
+                | RuntimeError::Panic(_)
+        )
+    }
+
+    /// Get the diagnostic code of the error (see [`diagnostics`](crate::diagnostics)).
+    pub fn code(&self) -> &'static str {
+        match self {
+            RuntimeError::ArgumentCount(..) => "NXS0301",
+            RuntimeError::AssignImmutable(_) => "NXS0302",
+            RuntimeError::Exit(_) => "NXS0303",
+            RuntimeError::InvalidArgument(_) => "NXS0304",
+            RuntimeError::InvalidAssignment(_) => "NXS0305",
+            RuntimeError::InvalidIndex(_) => "NXS0318",
+            RuntimeError::Interrupted => "NXS0319",
+            RuntimeError::Io(_) => "NXS0306",
+            RuntimeError::IoDenied(_) => "NXS0307",
+            RuntimeError::LimitExceeded(_) => "NXS0308",
+            RuntimeError::MutableRefImmutable(_) => "NXS0309",
+            RuntimeError::NotBool(..) => "NXS0312", // A type mismatch, with a fix-it note.
+            RuntimeError::Numeric(_) => "NXS0317",
+            RuntimeError::Output(_) => "NXS0310",
+            RuntimeError::Panic(_) => "NXS0311",
+            RuntimeError::TypeMismatch(_) => "NXS0312",
+            RuntimeError::UndefinedFunction(..) => "NXS0313",
+            RuntimeError::UndefinedVariable(..) => "NXS0314",
+            RuntimeError::Uninitialized(_) => "NXS0315",
+            RuntimeError::Unsupported(_) => "NXS0316",
+            RuntimeError::UnknownVariant(..) => "NXS0320",
+        }
+    }
+
+    /// Get the notes on the error, e.g. a suggestion on how to fix it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nexus_rs::{runtime_error::RuntimeError, symbol::Symbol};
+    ///
+    /// let e = RuntimeError::UndefinedVariable(Symbol::intern("whle"), Some("while"));
+    /// assert_eq!(e.notes(), ["did you mean 'while'?"]);
+    ///
+    /// let e = RuntimeError::NotBool("if condition".to_owned(), "Number");
+    /// assert_eq!(e.notes(), ["values are not implicitly converted to bool, compare explicitly: '... != 0'"]);
+    /// ```
+    pub fn notes(&self) -> Vec<String> {
+        match self {
+            RuntimeError::UndefinedFunction(_, Some(name))
+            | RuntimeError::UndefinedVariable(_, Some(name)) => {
+                vec![format!("did you mean '{name}'?")]
+            }
+            RuntimeError::NotBool(_, found) => {
+                let fix = match *found {
+                    "Number" => "'... != 0'",
+                    "String" => "'... != \"\"'",
+                    "nil" => "'... != nil'",
+                    _ => return Vec::new(),
+                };
+                vec![format!(
+                    "values are not implicitly converted to bool, compare explicitly: {fix}"
+                )]
+            }
+            _ => Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,240 @@
+use crate::symbol::Symbol;
+use std::{fmt, rc::Rc};
+
+/// Runtime value representation.
+///
+/// Heap values are shared: cloning a value (e.g. passing it to a function) does not copy its contents. A shared value
+///  is copied on write only (see [`Value::string_mut`]).
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::value::Value;
+///
+/// assert_eq!(Value::Number(42.0).to_string(), "42");
+/// assert_eq!(Value::Number(0.5).type_name(), "Number");
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum Value {
+    #[default]
+    Unit,
+    Nil, // Absent value, e.g. the result of a failed `try` expression.
+    Bool(bool),
+    Number(f64),
+    String(Rc<String>),
+    Range(RangeValue),
+    Enum(EnumValue),
+}
+
+/// Enum value: a variant of a declared enum (e.g. `Mode.Run`), equal only to the same variant.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EnumValue {
+    pub kind: Symbol, // Enum name.
+    pub variant: Symbol,
+}
+
+/// Range value: the numbers from `start` towards `end` (inclusive or exclusive), in steps of `step`.
+///
+/// A negative step counts down, e.g. for a reversed range (see [`RangeValue::rev`]).
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::value::RangeValue;
+///
+/// let r = RangeValue::new(0.0, 10.0, false).step_by(3.0).unwrap();
+///
+/// assert_eq!(r.iter().collect::<Vec<_>>(), [0.0, 3.0, 6.0, 9.0]);
+/// assert_eq!(r.rev().iter().collect::<Vec<_>>(), [9.0, 6.0, 3.0, 0.0]);
+/// assert!(r.contains(6.0) && !r.contains(5.0));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RangeValue {
+    pub start: f64,
+    pub end: f64,
+    pub step: f64,
+    pub inclusive: bool, // Includes `end` (if reached in steps).
+}
+
+impl RangeValue {
+    /// Create a range in steps of one (`start..end` or `start..=end`).
+    pub fn new(start: f64, end: f64, inclusive: bool) -> Self {
+        RangeValue {
+            start,
+            end,
+            step: 1.0,
+            inclusive,
+        }
+    }
+
+    /// Get the number of elements.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nexus_rs::value::RangeValue;
+    ///
+    /// assert_eq!(RangeValue::new(0.0, 3.0, false).len(), 3);
+    /// assert_eq!(RangeValue::new(0.0, 3.0, true).len(), 4);
+    /// assert_eq!(RangeValue::new(3.0, 0.0, true).len(), 0);
+    /// ```
+    pub fn len(&self) -> usize {
+        let steps = (self.end - self.start) / self.step;
+        let len = if self.inclusive {
+            steps.floor() + 1.0
+        } else {
+            steps.ceil()
+        };
+
+        len.max(0.0) as usize // NaN (e.g. a zero step to the start) is empty.
+    }
+
+    /// Check if the range is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the element at an index (unchecked).
+    pub fn get(&self, index: usize) -> f64 {
+        self.start + index as f64 * self.step
+    }
+
+    /// Check if a number is an element of the range (i.e. reached in steps, not just within bounds).
+    pub fn contains(&self, x: f64) -> bool {
+        let index = (x - self.start) / self.step;
+        index >= 0.0 && index.fract() == 0.0 && index < self.len() as f64
+    }
+
+    /// Get the range with the elements in reverse order.
+    pub fn rev(&self) -> Self {
+        match self.len() {
+            0 => RangeValue {
+                end: self.start,
+                step: -self.step,
+                inclusive: false,
+                ..*self
+            },
+            len => RangeValue {
+                start: self.get(len - 1),
+                end: self.start,
+                step: -self.step,
+                inclusive: true,
+            },
+        }
+    }
+
+    /// Get the range with another step, if `step` is a nonzero (finite) number. A negative step counts down from the
+    ///  start, e.g. `10..0 step -2` holds `10, 8, 6, 4, 2`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nexus_rs::value::RangeValue;
+    ///
+    /// let r = RangeValue::new(10.0, 0.0, true).with_step(-5.0).unwrap();
+    ///
+    /// assert_eq!(r.iter().collect::<Vec<_>>(), [10.0, 5.0, 0.0]);
+    /// assert!(RangeValue::new(0.0, 10.0, false).with_step(0.0).is_none());
+    /// ```
+    pub fn with_step(&self, step: f64) -> Option<Self> {
+        (step != 0.0 && step.is_finite()).then_some(RangeValue { step, ..*self })
+    }
+
+    /// Get the range taking every step of `n` elements, if `n` is a positive (finite) number.
+    pub fn step_by(&self, n: f64) -> Option<Self> {
+        (n > 0.0 && n.is_finite()).then_some(RangeValue {
+            step: self.step * n,
+            ..*self
+        })
+    }
+
+    /// Iterate the elements.
+    pub fn iter(&self) -> impl Iterator<Item = f64> {
+        let range = *self;
+        (0..range.len()).map(move |i| range.get(i))
+    }
+}
+
+impl fmt::Display for RangeValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = if self.inclusive { "..=" } else { ".." };
+        write!(f, "{}{op}{}", self.start, self.end)?;
+
+        if self.step != 1.0 {
+            write!(f, " (step {})", self.step)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Value {
+    /// Get the number of heap bytes held by the value.
+    pub fn heap_size(&self) -> usize {
+        match self {
+            Value::String(s) => s.capacity(),
+            _ => 0,
+        }
+    }
+
+    /// Get the address of the (shared) heap data of the value, if any.
+    pub(crate) fn heap_id(&self) -> Option<usize> {
+        match self {
+            Value::String(s) => Some(Rc::as_ptr(s) as usize),
+            _ => None,
+        }
+    }
+
+    /// Get mutable access to a string value, copying the string first if it is shared.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nexus_rs::value::Value;
+    ///
+    /// let a = Value::from("abc");
+    /// let mut b = a.clone();
+    /// b.string_mut().unwrap().push('d');
+    ///
+    /// assert_eq!((a.to_string(), b.to_string()), ("abc".to_owned(), "abcd".to_owned()));
+    /// ```
+    pub fn string_mut(&mut self) -> Option<&mut String> {
+        match self {
+            Value::String(s) => Some(Rc::make_mut(s)),
+            _ => None,
+        }
+    }
+
+    /// Get the (language) type name of the value.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Unit => "()",
+            Value::Nil => "nil",
+            Value::Bool(_) => "bool",
+            Value::Number(_) => "Number",
+            Value::String(_) => "String",
+            Value::Range(_) => "Range",
+            Value::Enum(e) => e.kind.as_str(),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Unit => write!(f, "()"),
+            Value::Nil => write!(f, "nil"),
+            Value::Bool(b) => write!(f, "{b}"),
+            Value::Number(n) => write!(f, "{n}"),
+            Value::String(s) => write!(f, "{s}"),
+            Value::Range(r) => write!(f, "{r}"),
+            Value::Enum(e) => write!(f, "{}.{}", e.kind, e.variant),
+        }
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::String(Rc::new(s.to_owned()))
+    }
+}
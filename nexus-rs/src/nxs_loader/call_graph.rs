@@ -0,0 +1,596 @@
+use super::loader::Loader;
+use crate::ast::{Expr, ExprKind, IndexKind, Stmt, StmtKind, Stmts};
+use std::{collections::BTreeSet, fmt::Write, path::Path};
+
+#[cfg(test)]
+use crate::front::parse_source;
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+/// Name of the function called by the driver after running the top-level code (if declared).
+const MAIN: &str = "main";
+
+/// Call graph node: the top-level code of a module, or a declared function.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CallNode {
+    pub name: String, // Function name, or the module name for top-level code.
+    pub module: usize,
+    pub line: Option<usize>, // Line of the function declaration.
+    pub function: bool,
+}
+
+/// Function call graph of loaded modules.
+///
+/// Calls are resolved by name: to a function declared in the calling function (or the enclosing functions), or else
+///  to a top-level function of any module. A function used as value (e.g. `let f = g;`) counts as called. The
+///  top-level code of all modules, and the `main` function (called by the driver), are the roots of the graph.
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::{call_graph::CallGraph, front::parse_source};
+///
+/// let ast = parse_source("fn f() { g(); }\nfn g() { f(); }\nfn h() {}\nf();").unwrap();
+/// let graph = CallGraph::from_modules(&[("main.nxs".to_owned(), &ast)]);
+///
+/// let name = |i: usize| graph.nodes[i].name.as_str();
+/// assert_eq!(graph.unreachable().into_iter().map(name).collect::<Vec<_>>(), ["h"]);
+/// assert_eq!(graph.cycles()[0].iter().map(|&i| name(i)).collect::<Vec<_>>(), ["f", "g"]);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CallGraph {
+    pub modules: Vec<String>,            // Module names.
+    pub nodes: Vec<CallNode>, // Top-level code of each module (in module order), followed by the functions.
+    pub calls: BTreeSet<(usize, usize)>, // Caller and callee node indices.
+}
+
+impl CallGraph {
+    /// Build the call graph of all modules loaded by a loader. Modules are named by their path, relative to the
+    ///  directory of the last loaded module (the main file) if possible.
+    pub fn new(loader: &Loader) -> Self {
+        let modules = loader.modules();
+        let names = module_names(modules.iter().map(|m| m.path.as_path()));
+
+        Self::from_modules(
+            &names
+                .into_iter()
+                .zip(modules.iter().map(|m| &m.ast))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    /// Build the call graph of named module ASTs.
+    pub fn from_modules(modules: &[(String, &Stmts)]) -> Self {
+        let mut graph = CallGraph {
+            modules: modules.iter().map(|(name, _)| name.clone()).collect(),
+            ..Default::default()
+        };
+
+        graph.nodes = modules
+            .iter()
+            .enumerate()
+            .map(|(module, (name, _))| CallNode {
+                name: name.clone(),
+                module,
+                line: None,
+                function: false,
+            })
+            .collect();
+
+        // Declarations first (with the parent of each function), so calls to later functions resolve.
+        let mut parents = vec![None; modules.len()];
+        let mut bodies = Vec::new();
+        for (module, (_, ast)) in modules.iter().enumerate() {
+            graph.declare_stmts(ast, module, module, &mut parents, &mut bodies);
+        }
+
+        for (module, (_, ast)) in modules.iter().enumerate() {
+            let mut visit = Visit::default();
+            visit.stmts(ast);
+            graph.connect(module, visit.names, &parents);
+        }
+
+        for (node, body) in bodies {
+            let mut visit = Visit::default();
+            visit.stmt(body);
+            graph.connect(node, visit.names, &parents);
+        }
+
+        graph
+    }
+
+    /// Get the functions not reachable from the top-level code (or the `main` function), in order of declaration.
+    pub fn unreachable(&self) -> Vec<usize> {
+        let mut reached = vec![false; self.nodes.len()];
+        let mut stack: Vec<_> = (0..self.nodes.len())
+            .filter(|&i| !self.nodes[i].function || self.is_main(i))
+            .collect();
+
+        while let Some(node) = stack.pop() {
+            if std::mem::replace(&mut reached[node], true) {
+                continue;
+            }
+
+            stack.extend(self.callees(node).filter(|&c| !reached[c]));
+        }
+
+        (0..self.nodes.len()).filter(|&i| !reached[i]).collect()
+    }
+
+    /// Get the recursive cycles: the groups of functions calling each other (directly or indirectly), and functions
+    ///  calling themselves. Each cycle is in order of declaration, the cycles in order of their first function.
+    pub fn cycles(&self) -> Vec<Vec<usize>> {
+        let mut cycles: Vec<_> = components(self.nodes.len(), |n| self.callees(n).collect())
+            .into_iter()
+            .filter(|c| c.len() > 1 || self.calls.contains(&(c[0], c[0])))
+            .collect();
+
+        cycles.sort();
+        cycles
+    }
+
+    /// Export the call graph as DOT (Graphviz) graph, with a cluster per module. Unreachable functions are dashed, and
+    ///  calls within recursive cycles are bold.
+    pub fn to_dot(&self) -> String {
+        let unreachable = self.unreachable();
+        let cycles = self.cycles();
+        let cycle = |n: usize| cycles.iter().position(|c| c.contains(&n));
+
+        let mut dot = "digraph calls {\n    node [shape=box];\n".to_owned();
+
+        for (module, name) in self.modules.iter().enumerate() {
+            let _ = writeln!(
+                dot,
+                "    subgraph cluster_{module} {{\n        label={};",
+                quote(name)
+            );
+
+            for (i, node) in self
+                .nodes
+                .iter()
+                .enumerate()
+                .filter(|(_, n)| n.module == module)
+            {
+                let mut attributes = vec![format!(
+                    "label={}",
+                    quote(if node.function {
+                        &node.name
+                    } else {
+                        "(top level)"
+                    })
+                )];
+
+                if !node.function {
+                    attributes.push("shape=ellipse".to_owned());
+                }
+                if unreachable.contains(&i) {
+                    attributes.push("style=dashed".to_owned());
+                }
+
+                let _ = writeln!(dot, "        n{i} [{}];", attributes.join(", "));
+            }
+
+            dot.push_str("    }\n");
+        }
+
+        for &(caller, callee) in &self.calls {
+            let bold = cycle(caller).is_some() && cycle(caller) == cycle(callee);
+            let _ = writeln!(
+                dot,
+                "    n{caller} -> n{callee}{};",
+                if bold { " [style=bold]" } else { "" }
+            );
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    fn is_main(&self, node: usize) -> bool {
+        self.nodes[node].function && self.nodes[node].name == MAIN
+    }
+
+    fn callees(&self, node: usize) -> impl Iterator<Item = usize> + '_ {
+        self.calls
+            .range((node, 0)..(node + 1, 0))
+            .map(|&(_, callee)| callee)
+    }
+
+    fn declare_stmts<'a>(
+        &mut self,
+        stmts: &'a Stmts,
+        module: usize,
+        parent: usize,
+        parents: &mut Vec<Option<usize>>,
+        bodies: &mut Vec<(usize, &'a Stmt)>,
+    ) {
+        for stmt in stmts.iter() {
+            self.declare(stmt, module, parent, parents, bodies);
+        }
+    }
+
+    /// Declare the functions in a statement (recursively), with the node declaring them as parent.
+    fn declare<'a>(
+        &mut self,
+        stmt: &'a Stmt,
+        module: usize,
+        parent: usize,
+        parents: &mut Vec<Option<usize>>,
+        bodies: &mut Vec<(usize, &'a Stmt)>,
+    ) {
+        match &stmt.kind {
+            StmtKind::FunctionDecl(f) => {
+                let node = self.nodes.len();
+                self.nodes.push(CallNode {
+                    name: f.id.to_string(),
+                    module,
+                    line: stmt.span.and_then(|s| s.line),
+                    function: true,
+                });
+                parents.push(Some(parent));
+                bodies.push((node, &f.body));
+
+                self.declare(&f.body, module, node, parents, bodies);
+            }
+            StmtKind::Block(stmts) => self.declare_stmts(stmts, module, parent, parents, bodies),
+            _ => (),
+        }
+    }
+
+    /// Connect a node to the functions with the given names (unknown names, e.g. built-in functions, are skipped).
+    fn connect(&mut self, node: usize, names: Vec<String>, parents: &[Option<usize>]) {
+        for name in names {
+            let count = self.nodes.len();
+            let top_level = |i: usize| matches!(parents[i], Some(p) if p < self.modules.len());
+
+            // Functions declared in the node or its ancestors, innermost first, then top-level functions.
+            let callee = std::iter::successors(Some(node), |&n| parents[n])
+                .flat_map(|scope| (0..count).filter(move |&i| parents[i] == Some(scope)))
+                .chain((0..count).filter(|&i| top_level(i)))
+                .find(|&i| self.nodes[i].name == name);
+
+            if let Some(callee) = callee {
+                self.calls.insert((node, callee));
+            }
+        }
+    }
+}
+
+/// Module import graph of loaded modules: which module uses which.
+///
+/// # Example
+///
+/// ```no_run
+/// use nexus_rs::{call_graph::ImportGraph, loader::Loader};
+///
+/// let mut loader = Loader::new();
+/// loader.load("main.nxs").unwrap();
+///
+/// print!("{}", ImportGraph::new(&loader).to_dot());
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ImportGraph {
+    pub modules: Vec<String>, // Module names (see `CallGraph::new`).
+    pub imports: BTreeSet<(usize, usize)>, // Using and used module indices.
+}
+
+impl ImportGraph {
+    /// Build the import graph of all modules loaded by a loader.
+    pub fn new(loader: &Loader) -> Self {
+        let modules = loader.modules();
+        let index = |path: &Path| modules.iter().position(|m| m.path == path);
+
+        ImportGraph {
+            modules: module_names(modules.iter().map(|m| m.path.as_path())),
+            imports: loader
+                .uses()
+                .iter()
+                .filter_map(|(site, target)| Some((index(&site.file)?, index(target)?)))
+                .collect(),
+        }
+    }
+
+    /// Get the cyclic imports: the groups of modules using each other (directly or indirectly).
+    pub fn cycles(&self) -> Vec<Vec<usize>> {
+        let mut cycles: Vec<_> = components(self.modules.len(), |n| {
+            self.imports
+                .range((n, 0)..(n + 1, 0))
+                .map(|&(_, used)| used)
+                .collect()
+        })
+        .into_iter()
+        .filter(|c| c.len() > 1)
+        .collect();
+
+        cycles.sort();
+        cycles
+    }
+
+    /// Export the import graph as DOT (Graphviz) graph.
+    pub fn to_dot(&self) -> String {
+        let mut dot = "digraph imports {\n    node [shape=note];\n".to_owned();
+
+        for (i, name) in self.modules.iter().enumerate() {
+            let _ = writeln!(dot, "    m{i} [label={}];", quote(name));
+        }
+        for (user, used) in &self.imports {
+            let _ = writeln!(dot, "    m{user} -> m{used};");
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// Name modules by their path, relative to the directory of the last module if possible.
+fn module_names<'a>(paths: impl Iterator<Item = &'a Path> + Clone) -> Vec<String> {
+    let dir = paths.clone().last().and_then(Path::parent);
+
+    paths
+        .map(|p| {
+            dir.and_then(|d| p.strip_prefix(d).ok())
+                .unwrap_or(p)
+                .display()
+                .to_string()
+        })
+        .collect()
+}
+
+/// Quote a DOT identifier.
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Get the strongly connected components of a graph (Tarjan's algorithm), each sorted by node index.
+fn components(count: usize, successors: impl Fn(usize) -> Vec<usize>) -> Vec<Vec<usize>> {
+    struct State {
+        index: Vec<Option<usize>>,
+        low: Vec<usize>,
+        stack: Vec<usize>,
+        on_stack: Vec<bool>,
+        next: usize,
+        components: Vec<Vec<usize>>,
+    }
+
+    fn visit(node: usize, state: &mut State, successors: &impl Fn(usize) -> Vec<usize>) {
+        state.index[node] = Some(state.next);
+        state.low[node] = state.next;
+        state.next += 1;
+        state.stack.push(node);
+        state.on_stack[node] = true;
+
+        for successor in successors(node) {
+            match state.index[successor] {
+                None => {
+                    visit(successor, state, successors);
+                    state.low[node] = state.low[node].min(state.low[successor]);
+                }
+                Some(index) if state.on_stack[successor] => {
+                    state.low[node] = state.low[node].min(index)
+                }
+                Some(_) => (),
+            }
+        }
+
+        if Some(state.low[node]) == state.index[node] {
+            let mut component = Vec::new();
+
+            while let Some(n) = state.stack.pop() {
+                state.on_stack[n] = false;
+                component.push(n);
+                if n == node {
+                    break;
+                }
+            }
+
+            component.sort();
+            state.components.push(component);
+        }
+    }
+
+    let mut state = State {
+        index: vec![None; count],
+        low: vec![0; count],
+        stack: Vec::new(),
+        on_stack: vec![false; count],
+        next: 0,
+        components: Vec::new(),
+    };
+
+    for node in 0..count {
+        if state.index[node].is_none() {
+            visit(node, &mut state, &successors);
+        }
+    }
+
+    state.components
+}
+
+/// Names called or used as value in statements, skipping nested function declarations.
+#[derive(Default)]
+struct Visit {
+    names: Vec<String>,
+}
+
+impl Visit {
+    fn stmts(&mut self, stmts: &Stmts) {
+        stmts.iter().for_each(|s| self.stmt(s));
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        match &stmt.kind {
+            StmtKind::Assignment(a) => {
+                self.expr(&a.lhs);
+                self.expr(&a.rhs);
+            }
+            StmtKind::Block(b) => self.stmts(b),
+            StmtKind::Connect(c) | StmtKind::Disconnect(c) => {
+                c.stages.iter().flatten().for_each(|e| self.expr(e));
+                if let Some(capacity) = c.buffer.as_ref().and_then(|b| b.capacity.as_ref()) {
+                    self.expr(capacity);
+                }
+            }
+            StmtKind::ConstDecl(c) => self.expr(&c.value),
+            StmtKind::Defer(s) => self.stmt(s),
+            StmtKind::Expr(e) => self.expr(e),
+            StmtKind::EnumDecl(_) | StmtKind::FunctionDecl(_) | StmtKind::NodeDecl(_) => (),
+            StmtKind::GroupDecl(g) => self.stmt(&g.body),
+            StmtKind::Print(p) => p.args.iter().for_each(|e| self.expr(e)),
+            StmtKind::Remove(r) => self.expr(&r.target),
+            StmtKind::Return(r) => self.expr(&r.expr),
+            StmtKind::UseDecl(_) => (),
+            StmtKind::VarDecl(v) => v.value.iter().for_each(|e| self.expr(e)),
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match &expr.kind {
+            ExprKind::Binary(b) => {
+                self.expr(&b.lhs);
+                self.expr(&b.rhs);
+            }
+            ExprKind::Block(b) => self.stmt(&b.body),
+            ExprKind::Cast(x) => self.expr(&x.expr),
+            ExprKind::For(f) => {
+                self.expr(&f.expr);
+                self.expr(&f.body);
+            }
+            ExprKind::FuncCall(c) => {
+                self.names.push(c.id.to_string());
+                c.args.iter().for_each(|e| self.expr(e));
+            }
+            ExprKind::Group(e) | ExprKind::Propagate(e) | ExprKind::Try(e) => self.expr(e),
+            ExprKind::If(i) => {
+                self.expr(&i.expr);
+                self.expr(&i.body_then);
+                i.body_else.iter().for_each(|e| self.expr(e));
+            }
+            ExprKind::Index(x) => {
+                self.expr(&x.expr);
+                match &x.index {
+                    IndexKind::Element(e) => self.expr(e),
+                    IndexKind::Slice(_, start, end) => {
+                        start.iter().chain(end).for_each(|e| self.expr(e))
+                    }
+                }
+            }
+            ExprKind::Range(r) => {
+                self.expr(&r.start);
+                self.expr(&r.end);
+                r.step.iter().for_each(|e| self.expr(e));
+            }
+            ExprKind::Loop(l) => self.expr(&l.body),
+            ExprKind::Ref(r) => self.expr(&r.expr),
+            ExprKind::Time(t) => self.expr(&t.body),
+            ExprKind::Unary(u) => self.expr(&u.expr),
+            ExprKind::Var(v) => self.names.push(v.id.to_string()),
+            ExprKind::While(w) => {
+                self.expr(&w.expr);
+                self.expr(&w.body);
+            }
+            ExprKind::Empty() | ExprKind::Literal(_) => (),
+        }
+    }
+}
+
+#[test]
+fn call_graph_test() {
+    let lib =
+        parse_source("fn twice(x: Number) -> Number { x * 2 }\nfn unused() { unused(); }").unwrap();
+    let main = parse_source(
+        r#"use "lib.nxs";
+fn main() { helper(); }
+fn helper() {
+    fn inner() { sqrt(twice(1)); }
+    let f = inner;
+}
+fn dead() { helper(); }"#,
+    )
+    .unwrap();
+
+    let graph =
+        CallGraph::from_modules(&[("lib.nxs".to_owned(), &lib), ("main.nxs".to_owned(), &main)]);
+    let name = |i: usize| graph.nodes[i].name.clone();
+
+    assert_eq!(
+        graph
+            .nodes
+            .iter()
+            .map(|n| (n.name.as_str(), n.module, n.line))
+            .collect::<Vec<_>>(),
+        [
+            ("lib.nxs", 0, None),
+            ("main.nxs", 1, None),
+            ("twice", 0, Some(1)),
+            ("unused", 0, Some(2)),
+            ("main", 1, Some(2)),
+            ("helper", 1, Some(3)),
+            ("inner", 1, Some(4)),
+            ("dead", 1, Some(7)),
+        ]
+    );
+
+    assert_eq!(
+        graph
+            .calls
+            .iter()
+            .map(|&(a, b)| (name(a), name(b)))
+            .collect::<Vec<_>>(),
+        [
+            ("unused".to_owned(), "unused".to_owned()),
+            ("main".to_owned(), "helper".to_owned()),
+            ("helper".to_owned(), "inner".to_owned()),
+            ("inner".to_owned(), "twice".to_owned()),
+            ("dead".to_owned(), "helper".to_owned()),
+        ]
+    );
+
+    assert_eq!(
+        graph
+            .unreachable()
+            .into_iter()
+            .map(name)
+            .collect::<Vec<_>>(),
+        ["unused", "dead"]
+    );
+    assert_eq!(graph.cycles(), [vec![3]]);
+}
+
+#[test]
+fn to_dot_test() {
+    let ast = parse_source("fn f() { f(); }\nfn g() {}\ng();").unwrap();
+    let graph = CallGraph::from_modules(&[("a \"b\".nxs".to_owned(), &ast)]);
+
+    assert_eq!(
+        graph.to_dot(),
+        r#"digraph calls {
+    node [shape=box];
+    subgraph cluster_0 {
+        label="a \"b\".nxs";
+        n0 [label="(top level)", shape=ellipse];
+        n1 [label="f", style=dashed];
+        n2 [label="g"];
+    }
+    n0 -> n2;
+    n1 -> n1 [style=bold];
+}
+"#
+    );
+
+    let imports = ImportGraph {
+        modules: vec![
+            "a.nxs".to_owned(),
+            "b.nxs".to_owned(),
+            "main.nxs".to_owned(),
+        ],
+        imports: [(0, 1), (1, 0), (2, 0)].into(),
+    };
+
+    assert_eq!(imports.cycles(), [vec![0, 1]]);
+    assert_eq!(
+        imports.to_dot(),
+        "digraph imports {\n    node [shape=note];\n    m0 [label=\"a.nxs\"];\n    m1 [label=\"b.nxs\"];\n    \
+         m2 [label=\"main.nxs\"];\n    m0 -> m1;\n    m1 -> m0;\n    m2 -> m0;\n}\n"
+    );
+}
@@ -0,0 +1,524 @@
+use crate::ast::{ExprKind, LiteralKind, StmtKind, Stmts};
+use crate::front::{parse_source_file, FrontError};
+use crate::manifest::Manifest;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+/// Module loading error.
+#[derive(Error, Debug)]
+pub enum LoadError {
+    #[error("failed to read '{0}': {1}")]
+    Io(PathBuf, io::Error),
+
+    #[error("in '{0}':\n{1}")]
+    Front(PathBuf, Box<FrontError>),
+
+    #[error("{0}: 'use' path must be a string literal")]
+    UsePath(UseSite),
+
+    #[error("{0}: unknown package '{1}'")]
+    UnknownPackage(UseSite, String),
+}
+
+impl LoadError {
+    /// Get the diagnostic code of the error (see [`diagnostics`](crate::diagnostics)).
+    ///
+    /// Errors in a used file have the code of the underlying scanning or parsing error.
+    pub fn code(&self) -> &'static str {
+        match self {
+            LoadError::Io(..) => "NXS0201",
+            LoadError::Front(_, e) => e.code(),
+            LoadError::UsePath(_) => "NXS0202",
+            LoadError::UnknownPackage(..) => "NXS0204",
+        }
+    }
+
+    /// Get the path of the file that failed to load, or of the file with the invalid `use` declaration.
+    pub fn path(&self) -> &Path {
+        match self {
+            LoadError::Io(path, _) | LoadError::Front(path, _) => path,
+            LoadError::UsePath(site) | LoadError::UnknownPackage(site, _) => &site.file,
+        }
+    }
+}
+
+/// Location of a `use` declaration.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UseSite {
+    pub file: PathBuf,
+    pub line: Option<usize>,
+}
+
+impl fmt::Display for UseSite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{line}", self.file.display()),
+            None => write!(f, "{}", self.file.display()),
+        }
+    }
+}
+
+/// Lint for a file that is used more than once from the same file.
+#[derive(Debug, PartialEq)]
+pub struct RedundantUse {
+    pub target: PathBuf,     // Canonical path of the used file.
+    pub sites: Vec<UseSite>, // All sites using the file (in source order).
+}
+
+impl RedundantUse {
+    /// Get the diagnostic code of the lint (see [`diagnostics`](crate::diagnostics)).
+    pub fn code(&self) -> &'static str {
+        "NXS0203"
+    }
+}
+
+impl fmt::Display for RedundantUse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "redundant 'use' of '{}' (used {} times: {})",
+            self.target.display(),
+            self.sites.len(),
+            self.sites
+                .iter()
+                .map(|s| s.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+}
+
+/// Loaded source file.
+#[derive(Debug)]
+pub struct Module {
+    pub path: PathBuf, // Canonical path (if the file exists on disk).
+    pub ast: Stmts,
+}
+
+/// Source file loader, resolving `use` declarations with include-once semantics.
+///
+/// Used files are resolved relative to the directory of the using file, or else relative to the search directories
+///  (e.g. the source directories of a project), and identified by their canonical path. Package files are used with
+///  a `@` prefix (see [`Manifest`]). So a file used via multiple
+///  (different) paths is only loaded once. Only top-level `use` declarations are resolved.
+///
+/// # Example
+///
+/// ```no_run
+/// use nexus_rs::loader::Loader;
+///
+/// let mut loader = Loader::new();
+/// loader.load("main.nxs").unwrap();
+///
+/// for lint in loader.redundant_uses() {
+///     eprintln!("warning: {lint}");
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct Loader {
+    modules: Vec<Module>, // In order of completion (used files before their users).
+    loaded: HashMap<PathBuf, usize>, // Canonical path to module index, or `usize::MAX` while loading.
+    files: Vec<PathBuf>,             // Canonical paths in order of loading (see `Span::file`).
+    uses: Vec<(UseSite, PathBuf)>,   // Use sites with the canonical path of the used file.
+    search_dirs: Vec<PathBuf>, // Directories to resolve used files in, after the directory of the using file.
+    packages: BTreeMap<String, Manifest>, // Packages to resolve `@name` uses in.
+}
+
+impl Loader {
+    /// Create a new loader.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the directories to resolve used files in, if not found relative to the using file.
+    pub fn with_search_dirs(mut self, dirs: Vec<PathBuf>) -> Self {
+        self.search_dirs = dirs;
+        self
+    }
+
+    /// Set the packages to resolve package uses in (e.g. `use "@name/file.nxs";`), see [`Manifest`].
+    pub fn with_packages(mut self, packages: BTreeMap<String, Manifest>) -> Self {
+        self.packages = packages;
+        self
+    }
+
+    /// Load a source file and all files it uses (recursively), returning the module index.
+    ///
+    /// Files that are already loaded (or being loaded, in case of cyclic uses) are not loaded again.
+    pub fn load(&mut self, path: impl AsRef<Path>) -> Result<usize, LoadError> {
+        let path = path.as_ref();
+        let canonical = fs::canonicalize(path).map_err(|e| LoadError::Io(path.to_owned(), e))?;
+
+        if let Some(&index) = self.loaded.get(&canonical) {
+            return Ok(index);
+        }
+
+        let source =
+            fs::read_to_string(&canonical).map_err(|e| LoadError::Io(path.to_owned(), e))?;
+        // Files are numbered in order of loading (see `Span::file`).
+        let ast = parse_source_file(&source, self.loaded.len())
+            .map_err(|e| LoadError::Front(path.to_owned(), Box::new(e)))?;
+
+        self.add(canonical, ast)
+    }
+
+    /// Add an already parsed source file (e.g. read from standard input) and load all files it uses (recursively),
+    ///  returning the module index.
+    ///
+    /// The path is used to resolve the used files, it does not have to exist.
+    pub fn add_module(&mut self, path: impl AsRef<Path>, ast: Stmts) -> Result<usize, LoadError> {
+        let path = path.as_ref();
+        self.add(fs::canonicalize(path).unwrap_or(path.to_owned()), ast)
+    }
+
+    /// Get a loaded module by index.
+    pub fn module(&self, index: usize) -> &Module {
+        &self.modules[index]
+    }
+
+    /// Get the canonical path of a source file by its index (see [`Span::file`](crate::span::Span)).
+    pub fn file(&self, index: usize) -> Option<&Path> {
+        self.files.get(index).map(PathBuf::as_path)
+    }
+
+    /// Get all loaded modules, used files before the files using them (except for cyclic uses).
+    pub fn modules(&self) -> &[Module] {
+        &self.modules
+    }
+
+    /// Get all use sites, with the canonical path of the used file, in order of loading.
+    pub fn uses(&self) -> &[(UseSite, PathBuf)] {
+        &self.uses
+    }
+
+    /// Get the lints for files used more than once from the same file.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nexus_rs::{front::parse_source, loader::Loader};
+    ///
+    /// let dir = std::env::temp_dir();
+    /// std::fs::write(dir.join("nexus_loader_doc_lib.nxs"), "let x = 1;").unwrap();
+    ///
+    /// let mut loader = Loader::new();
+    /// let ast = parse_source("use \"nexus_loader_doc_lib.nxs\";\nuse \"./nexus_loader_doc_lib.nxs\";").unwrap();
+    /// loader.add_module(dir.join("main.nxs"), ast).unwrap();
+    ///
+    /// assert_eq!(loader.modules().len(), 2);
+    /// assert_eq!(loader.redundant_uses()[0].sites.len(), 2);
+    /// ```
+    pub fn redundant_uses(&self) -> Vec<RedundantUse> {
+        let mut result: Vec<RedundantUse> = Vec::new();
+
+        for (site, target) in &self.uses {
+            let same = |(s, t): &&(UseSite, PathBuf)| t == target && s.file == site.file;
+            if self.uses.iter().filter(same).count() < 2 {
+                continue;
+            }
+
+            match result
+                .iter_mut()
+                .find(|r| r.target == *target && r.sites[0].file == site.file)
+            {
+                Some(lint) => lint.sites.push(site.clone()),
+                None => result.push(RedundantUse {
+                    target: target.clone(),
+                    sites: vec![site.clone()],
+                }),
+            }
+        }
+
+        result
+    }
+
+    fn add(&mut self, path: PathBuf, ast: Stmts) -> Result<usize, LoadError> {
+        self.loaded.insert(path.clone(), usize::MAX);
+        self.files.push(path.clone());
+
+        let dir = path.parent().unwrap_or(Path::new("")).to_owned();
+
+        for stmt in ast.iter() {
+            let StmtKind::UseDecl(u) = &stmt.kind else {
+                continue;
+            };
+
+            let site = UseSite {
+                file: path.clone(),
+                line: u.span.and_then(|s| s.line),
+            };
+
+            let filename = match &u.filename.kind {
+                ExprKind::Literal(l) => match &l.kind {
+                    LiteralKind::String(s) => s.as_str(),
+                    _ => return Err(LoadError::UsePath(site)),
+                },
+                _ => return Err(LoadError::UsePath(site)),
+            };
+
+            let target = match filename.strip_prefix('@') {
+                Some(package) => self.resolve_package(package, &site)?,
+                None => self.resolve(&dir, filename),
+            };
+            self.load(&target)?;
+
+            let target = fs::canonicalize(&target).map_err(|e| LoadError::Io(target, e))?;
+            self.uses.push((site, target));
+        }
+
+        self.modules.push(Module {
+            path: path.clone(),
+            ast,
+        });
+        self.loaded.insert(path, self.modules.len() - 1);
+
+        Ok(self.modules.len() - 1)
+    }
+
+    /// Resolve a used filename, falling back to the path relative to the using file if it is not found anywhere.
+    ///
+    /// Files in a package are also resolved in the source directories of the package.
+    fn resolve(&self, dir: &Path, filename: &str) -> PathBuf {
+        let package_dirs = self
+            .packages
+            .values()
+            .filter(|m| fs::canonicalize(&m.root).is_ok_and(|root| dir.starts_with(root)))
+            .flat_map(|m| m.source_dirs());
+
+        std::iter::once(dir.to_owned())
+            .chain(package_dirs)
+            .chain(self.search_dirs.iter().cloned())
+            .map(|d| d.join(filename))
+            .find(|p| p.is_file())
+            .unwrap_or_else(|| dir.join(filename))
+    }
+
+    /// Resolve a package use (without `@` prefix): the main file of a package, or a file in its source directories.
+    fn resolve_package(&self, package: &str, site: &UseSite) -> Result<PathBuf, LoadError> {
+        let (name, filename) = package.split_once('/').unwrap_or((package, ""));
+
+        let Some(manifest) = self.packages.get(name) else {
+            return Err(LoadError::UnknownPackage(site.clone(), name.to_owned()));
+        };
+
+        if filename.is_empty() {
+            return Ok(manifest.main_file());
+        }
+
+        let dirs = manifest.source_dirs();
+        Ok(dirs
+            .iter()
+            .map(|d| d.join(filename))
+            .find(|p| p.is_file())
+            .unwrap_or_else(|| dirs.first().unwrap_or(&manifest.root).join(filename)))
+    }
+}
+
+#[cfg(test)]
+fn test_dir(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("nexus_loader_{name}_{}", std::process::id()));
+
+    for (file, source) in files {
+        let path = dir.join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, source).unwrap();
+    }
+
+    dir
+}
+
+#[test]
+fn include_once_test() {
+    let dir = test_dir(
+        "once",
+        &[
+            ("main.nxs", "use \"a.nxs\";\nuse \"sub/b.nxs\";\nlet x = 1;"),
+            ("a.nxs", "use \"sub/../lib.nxs\";"),
+            ("sub/b.nxs", "use \"../lib.nxs\";"),
+            ("lib.nxs", "let y = 2;"),
+        ],
+    );
+
+    let mut loader = Loader::new();
+    let main = loader.load(dir.join("main.nxs")).unwrap();
+
+    let names = loader
+        .modules()
+        .iter()
+        .map(|m| m.path.file_name().unwrap().to_str().unwrap())
+        .collect::<Vec<_>>();
+
+    assert_eq!(names, ["lib.nxs", "a.nxs", "b.nxs", "main.nxs"]);
+    assert_eq!(main, 3);
+    assert!(loader.redundant_uses().is_empty());
+
+    // Files are numbered in order of loading, and spans refer to them.
+    let files = (0..4)
+        .map(|i| {
+            loader
+                .file(i)
+                .unwrap()
+                .file_name()
+                .unwrap()
+                .to_str()
+                .unwrap()
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(files, ["main.nxs", "a.nxs", "lib.nxs", "b.nxs"]);
+    assert_eq!(loader.module(0).ast[0].span.map(|s| s.file), Some(2));
+    assert_eq!(loader.file(4), None);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn cyclic_use_test() {
+    let dir = test_dir(
+        "cycle",
+        &[("a.nxs", "use \"b.nxs\";"), ("b.nxs", "use \"a.nxs\";")],
+    );
+
+    let mut loader = Loader::new();
+    loader.load(dir.join("a.nxs")).unwrap();
+
+    assert_eq!(loader.modules().len(), 2);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn redundant_use_test() {
+    let dir = test_dir(
+        "redundant",
+        &[
+            (
+                "main.nxs",
+                "use \"lib.nxs\";\nuse \"other.nxs\";\nuse \"./lib.nxs\";\n\nuse \"lib.nxs\";",
+            ),
+            ("other.nxs", "use \"lib.nxs\";"),
+            ("lib.nxs", ""),
+        ],
+    );
+
+    let mut loader = Loader::new();
+    loader.load(dir.join("main.nxs")).unwrap();
+
+    let lints = loader.redundant_uses();
+    let main = fs::canonicalize(dir.join("main.nxs")).unwrap();
+
+    assert_eq!(lints.len(), 1);
+    assert_eq!(
+        lints[0].target,
+        fs::canonicalize(dir.join("lib.nxs")).unwrap()
+    );
+    assert_eq!(
+        lints[0].sites.iter().map(|s| s.line).collect::<Vec<_>>(),
+        [Some(1), Some(3), Some(5)]
+    );
+    assert!(lints[0].sites.iter().all(|s| s.file == main));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn load_error_test() {
+    let dir = test_dir(
+        "error",
+        &[
+            ("missing.nxs", "use \"nonexistent.nxs\";"),
+            ("expr.nxs", "use \"a\" + \".nxs\";"),
+            ("invalid.nxs", "use \"broken.nxs\";"),
+            ("broken.nxs", "let = 1;"),
+        ],
+    );
+
+    let mut loader = Loader::new();
+
+    assert!(matches!(
+        loader.load(dir.join("missing.nxs")),
+        Err(LoadError::Io(p, _)) if p.ends_with("nonexistent.nxs")
+    ));
+    assert!(matches!(
+        loader.load(dir.join("expr.nxs")),
+        Err(LoadError::UsePath(UseSite { line: Some(1), .. }))
+    ));
+    assert!(matches!(
+        loader.load(dir.join("invalid.nxs")),
+        Err(LoadError::Front(p, e)) if p.ends_with("broken.nxs") && matches!(*e, FrontError::Parse(_))
+    ));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn search_dirs_test() {
+    let dir = test_dir(
+        "search",
+        &[
+            ("src/main.nxs", "use \"util.nxs\";\nuse \"shared.nxs\";"),
+            ("src/shared.nxs", "let a = 1;"),
+            ("lib/util.nxs", "let b = 2;"),
+            ("lib/shared.nxs", "let c = 3;"),
+        ],
+    );
+
+    let mut loader = Loader::new().with_search_dirs(vec![dir.join("lib")]);
+    loader.load(dir.join("src/main.nxs")).unwrap();
+
+    // The directory of the using file takes precedence over the search directories.
+    let paths: Vec<_> = loader
+        .modules()
+        .iter()
+        .map(|m| {
+            m.path
+                .strip_prefix(fs::canonicalize(&dir).unwrap())
+                .unwrap()
+        })
+        .collect();
+    assert_eq!(
+        paths,
+        [
+            Path::new("lib/util.nxs"),
+            Path::new("src/shared.nxs"),
+            Path::new("src/main.nxs")
+        ]
+    );
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn package_test() {
+    let dir = test_dir(
+        "package",
+        &[
+            ("main.nxs", "use \"@geo\";\nuse \"@geo/vec.nxs\";"),
+            ("geo/src/main.nxs", "use \"vec.nxs\";"),
+            ("geo/lib/vec.nxs", "let v = 1;"),
+            ("unknown.nxs", "use \"@net/io.nxs\";"),
+        ],
+    );
+
+    let geo = Manifest::from_toml(dir.join("geo"), "sources = [\"src\", \"lib\"]").unwrap();
+    let packages = BTreeMap::from([("geo".to_owned(), geo)]);
+
+    // Package files also resolve uses in the source directories of their package.
+    let mut loader = Loader::new().with_packages(packages);
+    loader.load(dir.join("main.nxs")).unwrap();
+    assert_eq!(loader.modules().len(), 3);
+
+    assert!(matches!(
+        loader.load(dir.join("unknown.nxs")),
+        Err(LoadError::UnknownPackage(UseSite { line: Some(1), .. }, name)) if name == "net"
+    ));
+
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,334 @@
+use crate::toml::{entries, parse_inline_table, parse_string, parse_string_array, Entry};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+/// Name of the project manifest file in the project root directory.
+pub const MANIFEST_FILENAME: &str = "nexus.toml";
+
+/// Project manifest error representation.
+#[derive(Error, Debug)]
+pub enum ManifestError {
+    #[error("no '{MANIFEST_FILENAME}' found in '{0}' or any parent directory")]
+    NotFound(PathBuf),
+
+    #[error("failed to read '{0}': {1}")]
+    Io(PathBuf, io::Error),
+
+    #[error("line {0}: expected 'key = value'")]
+    Syntax(usize),
+
+    #[error("line {0}: unknown key '{1}'")]
+    UnknownKey(usize, String),
+
+    #[error("line {0}: invalid value for '{1}'")]
+    InvalidValue(usize, String),
+}
+
+/// Project manifest, read from `nexus.toml` in the project root directory.
+///
+/// The manifest uses the same TOML subset as the REPL configuration (see [`toml`](crate::toml)), with the keys:
+///
+/// - `name`: project name (optional),
+/// - `version`: project version (optional, e.g. `"1.2.0"`),
+/// - `main`: main source file, relative to the project root (default `src/main.nxs`),
+/// - `sources`: source directories, relative to the project root (default `["src"]`),
+/// - `dependencies.<name>`: package dependency (e.g. in a `[dependencies]` table), see [`Dependency`].
+///
+/// Used files are resolved relative to the using file, or else relative to the source directories. Files of a
+///  package are used with a `@` prefix: `use "@name";` uses the main file of a package, `use "@name/file.nxs";` a
+///  file in its source directories.
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::manifest::Manifest;
+/// use std::path::Path;
+///
+/// let manifest = Manifest::from_toml(
+///     "/project",
+///     "name = \"demo\"\nsources = [\"src\", \"lib\"]\n\n[dependencies]\ngeo = { path = \"../geo\" }",
+/// )
+/// .unwrap();
+///
+/// assert_eq!(manifest.name.as_deref(), Some("demo"));
+/// assert_eq!(manifest.main_file(), Path::new("/project/src/main.nxs"));
+/// assert_eq!(manifest.source_dirs(), [Path::new("/project/src"), Path::new("/project/lib")]);
+/// assert_eq!(manifest.dependencies[0].name, "geo");
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Manifest {
+    pub root: PathBuf, // Project root directory, containing the manifest.
+    pub name: Option<String>,
+    pub version: Option<String>,
+    pub main: PathBuf,
+    pub sources: Vec<PathBuf>,
+    pub dependencies: Vec<Dependency>, // In order of declaration.
+}
+
+/// Package dependency, declared as inline table with either a `path` or a `git` key:
+///
+/// - `{ path = "../lib" }`: package directory, relative to the project root,
+/// - `{ git = "https://example.com/lib.git", rev = "v1.0" }`: git repository, pinned to a revision (branch, tag or
+///   commit; the default branch if omitted).
+///
+/// An optional `version` key requires the package version to start with the given version components (e.g. `"1.2"`
+///  matches `"1.2.5"`).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Dependency {
+    pub name: String,
+    pub source: Source,
+    pub version: Option<String>, // Required version.
+}
+
+/// Package source.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Source {
+    Path(PathBuf),
+    Git { url: String, rev: Option<String> },
+}
+
+impl Manifest {
+    /// Find the manifest in a directory or any of its parent directories, and load it.
+    pub fn find(dir: impl AsRef<Path>) -> Result<Self, ManifestError> {
+        let dir = dir.as_ref();
+
+        match dir
+            .ancestors()
+            .map(|d| d.join(MANIFEST_FILENAME))
+            .find(|p| p.is_file())
+        {
+            Some(path) => Self::load(path),
+            None => Err(ManifestError::NotFound(dir.to_owned())),
+        }
+    }
+
+    /// Load a manifest file. The project root is the directory of the manifest.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ManifestError> {
+        let path = path.as_ref();
+        let input = fs::read_to_string(path).map_err(|e| ManifestError::Io(path.to_owned(), e))?;
+
+        Self::from_toml(path.parent().unwrap_or(Path::new(".")), &input)
+    }
+
+    /// Parse a manifest from a string, for a project root directory. Keys that are not set keep their default value.
+    pub fn from_toml(root: impl AsRef<Path>, input: &str) -> Result<Self, ManifestError> {
+        let mut manifest = Manifest {
+            root: root.as_ref().to_owned(),
+            name: None,
+            version: None,
+            main: PathBuf::from("src/main.nxs"),
+            sources: vec![PathBuf::from("src")],
+            dependencies: Vec::new(),
+        };
+
+        for Entry { line, key, value } in entries(input).map_err(ManifestError::Syntax)? {
+            let invalid = || ManifestError::InvalidValue(line, key.clone());
+
+            match key.as_str() {
+                "name" => manifest.name = Some(parse_string(value).ok_or_else(invalid)?),
+                "version" => manifest.version = Some(parse_string(value).ok_or_else(invalid)?),
+                "main" => manifest.main = parse_string(value).ok_or_else(invalid)?.into(),
+                "sources" => {
+                    manifest.sources = parse_string_array(value)
+                        .ok_or_else(invalid)?
+                        .into_iter()
+                        .map(PathBuf::from)
+                        .collect()
+                }
+                _ => match key.strip_prefix("dependencies.") {
+                    Some(name) if is_package_name(name) => manifest
+                        .dependencies
+                        .push(parse_dependency(name, value).ok_or_else(invalid)?),
+                    _ => return Err(ManifestError::UnknownKey(line, key)),
+                },
+            }
+        }
+
+        Ok(manifest)
+    }
+
+    /// Get the path of the main source file.
+    pub fn main_file(&self) -> PathBuf {
+        self.root.join(&self.main)
+    }
+
+    /// Get the paths of the source directories.
+    pub fn source_dirs(&self) -> Vec<PathBuf> {
+        self.sources.iter().map(|d| self.root.join(d)).collect()
+    }
+
+    /// Get all source files (`*.nxs`) in the source directories (recursively), sorted by path.
+    pub fn source_files(&self) -> io::Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+
+        for dir in self.source_dirs() {
+            collect_sources(&dir, &mut files)?;
+        }
+
+        files.sort();
+        files.dedup();
+
+        Ok(files)
+    }
+}
+
+/// Check if a name is a valid package name: non-empty, with only alphanumeric characters, '-' and '_'.
+pub fn is_package_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Parse a dependency inline table.
+fn parse_dependency(name: &str, value: &str) -> Option<Dependency> {
+    let (mut path, mut git, mut rev, mut version) = (None, None, None, None);
+
+    for (key, value) in parse_inline_table(value)? {
+        let value = Some(parse_string(value)?);
+
+        match key.as_str() {
+            "path" => path = value,
+            "git" => git = value,
+            "rev" => rev = value,
+            "version" => version = value,
+            _ => return None,
+        }
+    }
+
+    let source = match (path, git) {
+        (Some(path), None) if rev.is_none() => Source::Path(path.into()),
+        (None, Some(url)) => Source::Git { url, rev },
+        _ => return None,
+    };
+
+    Some(Dependency {
+        name: name.to_owned(),
+        source,
+        version,
+    })
+}
+
+fn collect_sources(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+
+        if path.is_dir() {
+            collect_sources(&path, files)?;
+        } else if path.extension().is_some_and(|e| e == "nxs") {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn from_toml_test() {
+    let manifest = Manifest::from_toml(
+        "root",
+        r#"
+        # Nexus project.
+        name = "demo"
+        version = "0.1.0"
+        main = "app.nxs" # Main file.
+        sources = [".", "lib"]
+
+        [dependencies]
+        geo = { path = "../geo", version = "1.2" }
+        net = { git = "https://example.com/net.git", rev = "v2" }
+        "#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        manifest,
+        Manifest {
+            root: "root".into(),
+            name: Some("demo".to_owned()),
+            version: Some("0.1.0".to_owned()),
+            main: "app.nxs".into(),
+            sources: vec![".".into(), "lib".into()],
+            dependencies: vec![
+                Dependency {
+                    name: "geo".to_owned(),
+                    source: Source::Path("../geo".into()),
+                    version: Some("1.2".to_owned()),
+                },
+                Dependency {
+                    name: "net".to_owned(),
+                    source: Source::Git {
+                        url: "https://example.com/net.git".to_owned(),
+                        rev: Some("v2".to_owned())
+                    },
+                    version: None,
+                },
+            ],
+        }
+    );
+
+    let manifest = Manifest::from_toml("root", "").unwrap();
+    assert_eq!(manifest.main_file(), Path::new("root/src/main.nxs"));
+    assert_eq!(manifest.source_dirs(), [Path::new("root/src")]);
+}
+
+#[test]
+fn from_toml_error_test() {
+    assert!(matches!(
+        Manifest::from_toml("", "name"),
+        Err(ManifestError::Syntax(1))
+    ));
+    assert!(matches!(
+        Manifest::from_toml("", "\nedition = \"1\""),
+        Err(ManifestError::UnknownKey(2, _))
+    ));
+    assert!(matches!(
+        Manifest::from_toml("", "sources = \"src\""),
+        Err(ManifestError::InvalidValue(1, _))
+    ));
+    assert!(matches!(
+        Manifest::from_toml("", "[dependencies]\n\"a b\" = { path = \"x\" }"),
+        Err(ManifestError::UnknownKey(2, _))
+    ));
+
+    for dependency in [
+        "{}",
+        "{ path = \"a\", git = \"b\" }",
+        "{ path = \"a\", rev = \"b\" }",
+        "{ path = \"a\", branch = \"b\" }",
+        "\"1.0\"",
+    ] {
+        assert!(matches!(
+            Manifest::from_toml("", &format!("dependencies.a = {dependency}")),
+            Err(ManifestError::InvalidValue(1, _))
+        ));
+    }
+}
+
+#[test]
+fn find_test() {
+    let root = std::env::temp_dir().join(format!("nexus_manifest_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(root.join("src/sub")).unwrap();
+
+    fs::write(root.join(MANIFEST_FILENAME), "name = \"found\"").unwrap();
+    fs::write(root.join("src/main.nxs"), "").unwrap();
+    fs::write(root.join("src/sub/lib.nxs"), "").unwrap();
+    fs::write(root.join("src/notes.txt"), "").unwrap();
+
+    let manifest = Manifest::find(root.join("src/sub")).unwrap();
+    assert_eq!(manifest.name.as_deref(), Some("found"));
+    assert_eq!(manifest.root, root);
+    assert_eq!(
+        manifest.source_files().unwrap(),
+        [root.join("src/main.nxs"), root.join("src/sub/lib.nxs")]
+    );
+
+    fs::remove_dir_all(&root).unwrap();
+}
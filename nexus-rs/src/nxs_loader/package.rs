@@ -0,0 +1,549 @@
+use crate::manifest::{Manifest, ManifestError, Source, MANIFEST_FILENAME};
+use crate::toml::{entries, parse_string, Entry};
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    fs, io,
+    path::{Path, PathBuf},
+    process::Command,
+};
+use thiserror::Error;
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+/// Name of the lockfile in the project root directory.
+pub const LOCK_FILENAME: &str = "nexus.lock";
+
+/// Package resolution error representation.
+#[derive(Error, Debug)]
+pub enum PackageError {
+    #[error("package '{0}': {1}")]
+    Manifest(String, ManifestError),
+
+    #[error("package '{0}': 'git {1}' failed: {2}")]
+    Git(String, &'static str, String),
+
+    #[error("package '{0}': invalid git {1} '{2}' (must not start with '-')")]
+    GitArgument(String, &'static str, String),
+
+    #[error("package '{name}' is required from both {first} and {second}")]
+    Conflict {
+        name: String,
+        first: String,
+        second: String,
+    },
+
+    #[error("package '{name}' has version {found}, but version {required} is required")]
+    Version {
+        name: String,
+        required: String,
+        found: String,
+    },
+
+    #[error("package dependency cycle: {}", .0.join(" -> "))]
+    Cycle(Vec<String>),
+
+    #[error("invalid lockfile '{0}': {1}")]
+    Lockfile(PathBuf, ManifestError),
+
+    #[error("failed to write '{0}': {1}")]
+    Io(PathBuf, io::Error),
+}
+
+/// Locked package: its resolved source and version.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LockedPackage {
+    pub source: String, // `path+<path>`, or `git+<url>[?rev=<rev>]#<commit>`.
+    pub version: Option<String>,
+}
+
+/// Lockfile (`nexus.lock`), recording the resolved packages of a project.
+///
+/// Git packages are checked out at their locked commit, as long as their source (URL and revision) is unchanged. So
+///  a project builds with the same package code until its dependencies change (or the lockfile is removed).
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::package::{LockedPackage, Lockfile};
+///
+/// let mut lockfile = Lockfile::default();
+/// lockfile.packages.insert(
+///     "geo".to_owned(),
+///     LockedPackage { source: "path+../geo".to_owned(), version: Some("1.0.0".to_owned()) },
+/// );
+///
+/// assert_eq!(Lockfile::from_toml(&lockfile.to_toml()).unwrap(), lockfile);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Lockfile {
+    pub packages: BTreeMap<String, LockedPackage>,
+}
+
+impl Lockfile {
+    /// Load the lockfile of a project. A missing lockfile results in an empty lockfile.
+    pub fn load(root: impl AsRef<Path>) -> Result<Self, PackageError> {
+        let path = root.as_ref().join(LOCK_FILENAME);
+
+        match fs::read_to_string(&path) {
+            Ok(input) => Self::from_toml(&input).map_err(|e| PackageError::Lockfile(path, e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(PackageError::Lockfile(
+                path.clone(),
+                ManifestError::Io(path, e),
+            )),
+        }
+    }
+
+    /// Save the lockfile of a project.
+    pub fn save(&self, root: impl AsRef<Path>) -> Result<(), PackageError> {
+        let path = root.as_ref().join(LOCK_FILENAME);
+        fs::write(&path, self.to_toml()).map_err(|e| PackageError::Io(path, e))
+    }
+
+    /// Parse a lockfile from a string.
+    pub fn from_toml(input: &str) -> Result<Self, ManifestError> {
+        let mut packages: BTreeMap<String, LockedPackage> = BTreeMap::new();
+
+        for Entry { line, key, value } in entries(input).map_err(ManifestError::Syntax)? {
+            let invalid = || ManifestError::InvalidValue(line, key.clone());
+
+            let Some((name, field)) = key
+                .strip_prefix("package.")
+                .and_then(|k| k.rsplit_once('.'))
+            else {
+                return Err(ManifestError::UnknownKey(line, key));
+            };
+
+            let package = packages
+                .entry(name.to_owned())
+                .or_insert_with(|| LockedPackage {
+                    source: String::new(),
+                    version: None,
+                });
+
+            match field {
+                "source" => package.source = parse_string(value).ok_or_else(invalid)?,
+                "version" => package.version = Some(parse_string(value).ok_or_else(invalid)?),
+                _ => return Err(ManifestError::UnknownKey(line, key)),
+            }
+        }
+
+        Ok(Lockfile { packages })
+    }
+
+    /// Render the lockfile as string.
+    pub fn to_toml(&self) -> String {
+        let mut out = "# Generated by Nexus, do not edit.\n".to_owned();
+
+        for (name, package) in &self.packages {
+            let _ = write!(out, "\n[package.{name}]\nsource = \"{}\"\n", package.source);
+            if let Some(version) = &package.version {
+                let _ = writeln!(out, "version = \"{version}\"");
+            }
+        }
+
+        out
+    }
+}
+
+/// Resolved packages: the manifests of all (direct and indirect) dependencies by name, and the resulting lockfile.
+#[derive(Debug, Default)]
+pub struct Resolution {
+    pub packages: BTreeMap<String, Manifest>,
+    pub lockfile: Lockfile,
+}
+
+/// Resolve the dependencies of a project (recursively), fetching git packages into a cache directory.
+///
+/// Packages share a single namespace: a package required by multiple packages must resolve to the same source
+///  (path or git repository and revision), and satisfy all version requirements. Dependency cycles are reported as
+///  error. Git packages are checked out at their commit in the given lockfile (see [`Lockfile`]).
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::{manifest::Manifest, package::{resolve, Lockfile}};
+///
+/// let dir = std::env::temp_dir().join("nexus_package_doc");
+/// std::fs::create_dir_all(dir.join("geo")).unwrap();
+/// std::fs::write(dir.join("geo/nexus.toml"), "version = \"1.0.0\"").unwrap();
+///
+/// let manifest = Manifest::from_toml(&dir, "[dependencies]\ngeo = { path = \"geo\", version = \"1\" }").unwrap();
+/// let resolution = resolve(&manifest, &Lockfile::default(), &dir.join("cache")).unwrap();
+///
+/// assert_eq!(resolution.packages["geo"].root, dir.join("geo"));
+/// assert_eq!(resolution.lockfile.packages["geo"].source, "path+geo");
+/// ```
+pub fn resolve(
+    manifest: &Manifest,
+    lockfile: &Lockfile,
+    cache_dir: &Path,
+) -> Result<Resolution, PackageError> {
+    let mut resolver = Resolver {
+        locked: lockfile,
+        cache_dir,
+        resolution: Resolution::default(),
+        identities: BTreeMap::new(),
+        stack: manifest.name.iter().cloned().collect(),
+    };
+
+    resolver.resolve(manifest)?;
+    Ok(resolver.resolution)
+}
+
+struct Resolver<'r> {
+    locked: &'r Lockfile,
+    cache_dir: &'r Path,
+    resolution: Resolution,
+    identities: BTreeMap<String, String>, // Package source identities, to detect conflicts.
+    stack: Vec<String>,                   // Packages being resolved, to detect cycles.
+}
+
+impl Resolver<'_> {
+    fn resolve(&mut self, manifest: &Manifest) -> Result<(), PackageError> {
+        for dependency in &manifest.dependencies {
+            let name = &dependency.name;
+
+            if let Some(start) = self.stack.iter().position(|n| n == name) {
+                let mut cycle = self.stack[start..].to_vec();
+                cycle.push(name.clone());
+                return Err(PackageError::Cycle(cycle));
+            }
+
+            let (identity, locked_source) = match &dependency.source {
+                Source::Path(path) => {
+                    let root = manifest.root.join(path);
+                    let identity = fs::canonicalize(&root).unwrap_or(root);
+                    (
+                        format!("path '{}'", identity.display()),
+                        format!("path+{}", path.display()),
+                    )
+                }
+                Source::Git { url, rev } => {
+                    let source = match rev {
+                        Some(rev) => format!("git+{url}?rev={rev}"),
+                        None => format!("git+{url}"),
+                    };
+                    (format!("git '{}'", &source[4..]), source)
+                }
+            };
+
+            match self.identities.get(name) {
+                Some(first) if *first != identity => {
+                    return Err(PackageError::Conflict {
+                        name: name.clone(),
+                        first: first.clone(),
+                        second: identity,
+                    })
+                }
+                Some(_) => {
+                    let package = &self.resolution.packages[name];
+                    check_version(name, dependency.version.as_deref(), package)?;
+                    continue;
+                }
+                None => (),
+            }
+
+            let (root, locked_source) = match &dependency.source {
+                Source::Path(path) => (manifest.root.join(path), locked_source),
+                Source::Git { url, .. } => {
+                    // The locked commit is only used if the source is unchanged.
+                    let commit = self
+                        .locked
+                        .packages
+                        .get(name)
+                        .and_then(|p| p.source.strip_prefix(&format!("{locked_source}#")));
+
+                    let (root, commit) = self.fetch(name, url, &dependency.source, commit)?;
+                    (root, format!("{locked_source}#{commit}"))
+                }
+            };
+
+            let package = Manifest::load(root.join(MANIFEST_FILENAME))
+                .map_err(|e| PackageError::Manifest(name.clone(), e))?;
+            check_version(name, dependency.version.as_deref(), &package)?;
+
+            self.identities.insert(name.clone(), identity);
+            self.resolution.lockfile.packages.insert(
+                name.clone(),
+                LockedPackage {
+                    source: locked_source,
+                    version: package.version.clone(),
+                },
+            );
+            self.resolution
+                .packages
+                .insert(name.clone(), package.clone());
+
+            self.stack.push(name.clone());
+            self.resolve(&package)?;
+            self.stack.pop();
+        }
+
+        Ok(())
+    }
+
+    /// Fetch a git package into the cache directory and check out its (locked) revision, returning the checkout
+    ///  directory and commit.
+    fn fetch(
+        &self,
+        name: &str,
+        url: &str,
+        source: &Source,
+        commit: Option<&str>,
+    ) -> Result<(PathBuf, String), PackageError> {
+        let dir = self
+            .cache_dir
+            .join("git")
+            .join(format!("{name}-{:016x}", fnv1a(url)));
+
+        // Manifest (and lockfile) values must not be taken as git options.
+        let check = |what: &'static str, value: &str| match value.starts_with('-') {
+            true => Err(PackageError::GitArgument(
+                name.to_owned(),
+                what,
+                value.to_owned(),
+            )),
+            false => Ok(()),
+        };
+
+        let rev = match (commit, source) {
+            (Some(commit), _) => commit.to_owned(),
+            (None, Source::Git { rev: Some(rev), .. }) => rev.clone(),
+            _ => "origin/HEAD".to_owned(),
+        };
+
+        check("URL", url)?;
+        check("revision", &rev)?;
+
+        let git = |command: &'static str, args: &[&str]| {
+            let output = Command::new("git")
+                .arg("-C")
+                .arg(if command == "clone" {
+                    self.cache_dir
+                } else {
+                    dir.as_path()
+                })
+                .arg(command)
+                .args(args)
+                .output()
+                .map_err(|e| PackageError::Git(name.to_owned(), command, e.to_string()))?;
+
+            match output.status.success() {
+                true => Ok(String::from_utf8_lossy(&output.stdout).trim().to_owned()),
+                false => Err(PackageError::Git(
+                    name.to_owned(),
+                    command,
+                    String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+                )),
+            }
+        };
+
+        if !dir.join(".git").is_dir() {
+            fs::create_dir_all(self.cache_dir)
+                .map_err(|e| PackageError::Io(self.cache_dir.to_owned(), e))?;
+            git("clone", &["--quiet", "--", url, &dir.to_string_lossy()])?;
+        }
+
+        // The cached repository may not have the revision yet.
+        if git("checkout", &["--quiet", "--detach", &rev, "--"]).is_err() {
+            git("fetch", &["--quiet", "--tags", "origin"])?;
+            git("checkout", &["--quiet", "--detach", &rev, "--"]).or_else(|_| {
+                git(
+                    "checkout",
+                    &["--quiet", "--detach", &format!("origin/{rev}"), "--"],
+                )
+            })?;
+        }
+
+        Ok((dir.clone(), git("rev-parse", &["HEAD"])?))
+    }
+}
+
+/// Check if a package satisfies a version requirement: the package version must start with the required components.
+fn check_version(
+    name: &str,
+    required: Option<&str>,
+    package: &Manifest,
+) -> Result<(), PackageError> {
+    let Some(required) = required else {
+        return Ok(());
+    };
+
+    let found = package.version.as_deref().unwrap_or("(none)");
+    let mut components = found.split('.');
+
+    if required.split('.').all(|r| components.next() == Some(r)) {
+        Ok(())
+    } else {
+        Err(PackageError::Version {
+            name: name.to_owned(),
+            required: required.to_owned(),
+            found: found.to_owned(),
+        })
+    }
+}
+
+/// FNV-1a hash, naming the cache directories (stable across Rust versions, unlike the standard library hashers).
+fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+fn test_project(name: &str, packages: &[(&str, &str)]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("nexus_package_{name}_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+
+    for (package, manifest) in packages {
+        fs::create_dir_all(dir.join(package)).unwrap();
+        fs::write(dir.join(package).join(MANIFEST_FILENAME), manifest).unwrap();
+    }
+
+    dir
+}
+
+#[test]
+fn resolve_test() {
+    let dir = test_project(
+        "resolve",
+        &[
+            (
+                "app",
+                "[dependencies]\na = { path = \"../a\" }\nb = { path = \"../b\", version = \"2\" }",
+            ),
+            (
+                "a",
+                "version = \"1.0\"\ndependencies.b = { path = \"../b\" }",
+            ),
+            ("b", "version = \"2.1\""),
+        ],
+    );
+
+    let manifest = Manifest::load(dir.join("app").join(MANIFEST_FILENAME)).unwrap();
+    let resolution = resolve(&manifest, &Lockfile::default(), &dir.join("cache")).unwrap();
+
+    assert_eq!(resolution.packages.keys().collect::<Vec<_>>(), ["a", "b"]);
+    assert_eq!(
+        resolution.lockfile.to_toml(),
+        "# Generated by Nexus, do not edit.\n\n\
+         [package.a]\nsource = \"path+../a\"\nversion = \"1.0\"\n\n\
+         [package.b]\nsource = \"path+../b\"\nversion = \"2.1\"\n"
+    );
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn resolve_error_test() {
+    let dir = test_project(
+        "error",
+        &[
+            (
+                "conflict",
+                "[dependencies]\na = { path = \"../a\" }\nb = { path = \"../b\" }",
+            ),
+            ("a", "name = \"a\"\ndependencies.b = { path = \"../c\" }"),
+            ("b", ""),
+            ("c", ""),
+            ("cycle", "[dependencies]\nd = { path = \"../d\" }"),
+            ("d", "dependencies.e = { path = \"../e\" }"),
+            ("e", "dependencies.d = { path = \"../d\" }"),
+            (
+                "version",
+                "dependencies.b = { path = \"../b\", version = \"1\" }",
+            ),
+            ("missing", "dependencies.x = { path = \"../x\" }"),
+            (
+                "option_url",
+                "dependencies.x = { git = \"--upload-pack=touch pwned\" }",
+            ),
+            (
+                "option_rev",
+                "dependencies.x = { git = \"../b\", rev = \"--orphan=x\" }",
+            ),
+        ],
+    );
+
+    let resolve = |project: &str| {
+        let manifest = Manifest::load(dir.join(project).join(MANIFEST_FILENAME)).unwrap();
+        resolve(&manifest, &Lockfile::default(), &dir.join("cache")).unwrap_err()
+    };
+
+    assert!(matches!(resolve("conflict"), PackageError::Conflict { name, .. } if name == "b"));
+    assert_eq!(
+        resolve("cycle").to_string(),
+        "package dependency cycle: d -> e -> d"
+    );
+    assert_eq!(
+        resolve("version").to_string(),
+        "package 'b' has version (none), but version 1 is required"
+    );
+    assert!(matches!(resolve("missing"), PackageError::Manifest(name, _) if name == "x"));
+    assert_eq!(
+        resolve("option_url").to_string(),
+        "package 'x': invalid git URL '--upload-pack=touch pwned' (must not start with '-')"
+    );
+    assert_eq!(
+        resolve("option_rev").to_string(),
+        "package 'x': invalid git revision '--orphan=x' (must not start with '-')"
+    );
+    assert!(!dir.join("cache").join("git").exists());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn resolve_git_test() {
+    let dir = test_project("git", &[("repo", "version = \"0.1.0\"")]);
+    let repo = dir.join("repo");
+
+    let git = |args: &[&str]| {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(&repo)
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "git {args:?}");
+        String::from_utf8(output.stdout).unwrap().trim().to_owned()
+    };
+
+    git(&["init", "--quiet"]);
+    git(&["add", "."]);
+    git(&["commit", "--quiet", "-m", "First"]);
+    git(&["tag", "v1"]);
+    fs::write(repo.join(MANIFEST_FILENAME), "version = \"0.2.0\"").unwrap();
+    git(&["commit", "--quiet", "-am", "Second"]);
+
+    let url = repo.to_string_lossy();
+    let manifest = |rev: &str| {
+        Manifest::from_toml(
+            &dir,
+            &format!("dependencies.repo = {{ git = \"{url}\", rev = \"{rev}\" }}"),
+        )
+        .unwrap()
+    };
+
+    let cache = dir.join("cache");
+    let first = resolve(&manifest("v1"), &Lockfile::default(), &cache).unwrap();
+    assert_eq!(first.packages["repo"].version.as_deref(), Some("0.1.0"));
+
+    // The locked commit takes precedence over the (unchanged) revision.
+    let mut lockfile = first.lockfile.clone();
+    git(&["tag", "--force", "v1"]);
+    let second = resolve(&manifest("v1"), &lockfile, &cache).unwrap();
+    assert_eq!(second.lockfile, first.lockfile);
+
+    // A changed revision ignores the locked commit.
+    lockfile = second.lockfile;
+    let branch = git(&["symbolic-ref", "--short", "HEAD"]);
+    let third = resolve(&manifest(&branch), &lockfile, &cache).unwrap();
+    assert_eq!(third.packages["repo"].version.as_deref(), Some("0.2.0"));
+
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,124 @@
+use crate::parse_error::{ParseError, ParseErrorKind};
+use crate::source_line::SHEBANG;
+use std::{fmt, str::FromStr};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+/// Language edition, selecting the syntax accepted by the parser.
+///
+/// Source files select an edition with a header comment on their first line (e.g. `// edition: 2024`), or on their
+///  second line following a shebang line (e.g. `#!/usr/bin/env nexus`). Files without header use the first edition. So existing scripts keep parsing identically while the language evolves.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Edition {
+    /// Initial edition.
+    #[default]
+    E2023,
+
+    /// Reserves keywords for upcoming syntax.
+    E2024,
+}
+
+impl Edition {
+    /// All editions, oldest first.
+    pub const ALL: [Edition; 2] = [Edition::E2023, Edition::E2024];
+
+    /// Latest edition.
+    pub const LATEST: Edition = Edition::E2024;
+
+    /// Get the words that cannot be used as identifiers in this edition (other than the keywords).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nexus_rs::edition::Edition;
+    ///
+    /// assert!(Edition::E2023.reserved().is_empty());
+    /// assert!(Edition::E2024.reserved().contains(&"match"));
+    /// ```
+    pub fn reserved(&self) -> &'static [&'static str] {
+        match self {
+            Edition::E2023 => &[],
+            Edition::E2024 => &["as", "enum", "match", "module", "struct"],
+        }
+    }
+
+    /// Get the line of a source file that may hold the header comment: the first line, or the second line following a
+    ///  shebang line (see [`SHEBANG`]).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nexus_rs::edition::Edition;
+    ///
+    /// assert_eq!(Edition::header(["// edition: 2024", "let x = 1;"]), "// edition: 2024");
+    /// assert_eq!(Edition::header(["#!/usr/bin/env nexus", "// edition: 2024"]), "// edition: 2024");
+    /// assert_eq!(Edition::header([""; 0]), "");
+    /// ```
+    pub fn header<'a>(lines: impl IntoIterator<Item = &'a str>) -> &'a str {
+        let mut lines = lines.into_iter();
+
+        match lines.next() {
+            Some(first) if first.starts_with(SHEBANG) => lines.next().unwrap_or_default(),
+            first => first.unwrap_or_default(),
+        }
+    }
+
+    /// Detect the edition from the header comment of a source file (if any, see [`Edition::header`]).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nexus_rs::edition::Edition;
+    ///
+    /// assert_eq!(Edition::detect("// edition: 2024").unwrap(), Some(Edition::E2024));
+    /// assert_eq!(Edition::detect("let x = 1;").unwrap(), None);
+    /// assert!(Edition::detect("// edition: 1999").is_err());
+    /// ```
+    pub fn detect(first_line: &str) -> Result<Option<Edition>, ParseError> {
+        let Some(header) = first_line.trim().strip_prefix("//") else {
+            return Ok(None);
+        };
+
+        match header.trim().strip_prefix("edition:") {
+            Some(edition) => edition.trim().parse().map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+impl FromStr for Edition {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Edition::ALL
+            .into_iter()
+            .find(|e| e.to_string() == s)
+            .ok_or_else(|| ParseError::new(ParseErrorKind::UnknownEdition(s.to_owned())))
+    }
+}
+
+impl fmt::Display for Edition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Edition::E2023 => write!(f, "2023"),
+            Edition::E2024 => write!(f, "2024"),
+        }
+    }
+}
+
+#[test]
+fn detect_test() {
+    let detect = |line| Edition::detect(line).ok().flatten();
+
+    assert_eq!(detect("//edition:2023"), Some(Edition::E2023));
+    assert_eq!(detect("  //  edition:  2024  "), Some(Edition::E2024));
+    assert_eq!(detect("// Edition: 2024"), None);
+    assert_eq!(detect("/* edition: 2024 */"), None);
+    assert_eq!(detect(""), None);
+
+    assert!(matches!(
+        Edition::detect("// edition: next").unwrap_err().kind(),
+        ParseErrorKind::UnknownEdition(e) if e == "next"
+    ));
+}
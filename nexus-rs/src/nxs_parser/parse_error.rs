@@ -0,0 +1,223 @@
+use crate::edition::Edition;
+use crate::source_line::SourceLine;
+use crate::span::Span;
+use crate::symbol::Symbol;
+use crate::token::Token;
+use thiserror::Error;
+
+/// Parsing error kind.
+#[derive(Error, Debug)]
+pub enum ParseErrorKind {
+    #[error("'=' assigns, it cannot be used in a condition")]
+    AssignmentInCondition,
+
+    #[error("comparison operators cannot be chained")]
+    ChainedComparison,
+
+    #[error("a value can only be cast to 'Number', 'String' or 'bool'")]
+    CastType,
+
+    #[error("{0}")]
+    Custom(String),
+
+    #[error("declarations and 'return' cannot be deferred")]
+    DeferTarget,
+
+    #[error("duplicate port '{0}' in node declaration")]
+    DuplicatePort(Symbol),
+
+    #[error("duplicate variant '{0}' in enum declaration")]
+    DuplicateVariant(Symbol),
+
+    #[error("expected '{0}'")]
+    Expected(Token),
+
+    #[error("expected '{0}' ({1})")]
+    ExpectedReason(Token, String),
+
+    #[error("expected an operator or ';' before this operand")]
+    ExpectedOperator,
+
+    #[error(
+        "invalid assignment target, only a variable, member or indexed value can be assigned to"
+    )]
+    InvalidAssignmentTarget,
+
+    #[error("keyword '{0}' cannot be used as identifier")]
+    KeywordAsIdentifier(Token),
+
+    #[error("missing ';' after statement, add one here")]
+    MissingSemiColon,
+
+    #[error("nesting too deep (more than {0} grammar rule levels)")]
+    NestingTooDeep(usize),
+
+    #[error(
+        "the right-hand side of '|>' must be a function name or call, like 'x |> f' or 'x |> f(2)'"
+    )]
+    PipelineTarget,
+
+    #[error("range delimiter must be a literal, variable or group expression")]
+    RangeDelimiter,
+
+    #[error("range start is greater than its end, so the range is empty")]
+    ReversedRange,
+
+    #[error("a reference must refer to a variable or member, like '&x' or '&x.y'")]
+    RefTarget,
+
+    #[error("'..' followed by '=', write '..=' (without whitespace) for an inclusive range")]
+    SplitRangeInclusive,
+
+    #[error("'{0}' is reserved in edition {1} and cannot be used as identifier")]
+    ReservedIdentifier(Symbol, Edition),
+
+    #[error("unexpected token '{0}'")]
+    Unexpected(Token),
+
+    #[error("a reference ('&') is only allowed as variable initializer, like 'let r = &x;'")]
+    UnexpectedRef,
+
+    #[error("unexpected end of stream while parsing {0}")]
+    UnexpectedEos(String),
+
+    #[error("unknown connection option '{0}' (options: capacity, overflow)")]
+    UnknownConnectOption(Symbol),
+
+    #[error("unknown overflow policy '{0}' (policies: block, drop_oldest, drop_newest)")]
+    UnknownOverflow(Symbol),
+
+    #[error("unknown edition '{0}' (supported editions: 2023, 2024)")]
+    UnknownEdition(String),
+
+    #[error("unknown type '{0}'")]
+    UnknownType(Symbol),
+}
+
+impl ParseErrorKind {
+    /// Get the diagnostic code of the error (see [`diagnostics`](crate::diagnostics)).
+    pub fn code(&self) -> &'static str {
+        match self {
+            ParseErrorKind::Custom(_) => "NXS0100",
+            ParseErrorKind::DeferTarget => "NXS0101",
+            ParseErrorKind::Expected(_) | ParseErrorKind::ExpectedReason(..) => "NXS0102",
+            ParseErrorKind::ExpectedOperator => "NXS0103",
+            ParseErrorKind::KeywordAsIdentifier(_) => "NXS0104",
+            ParseErrorKind::MissingSemiColon => "NXS0105",
+            ParseErrorKind::NestingTooDeep(_) => "NXS0106",
+            ParseErrorKind::RangeDelimiter => "NXS0107",
+            ParseErrorKind::RefTarget => "NXS0108",
+            ParseErrorKind::SplitRangeInclusive => "NXS0109",
+            ParseErrorKind::ReservedIdentifier(..) => "NXS0110",
+            ParseErrorKind::Unexpected(_) => "NXS0111",
+            ParseErrorKind::UnexpectedRef => "NXS0112",
+            ParseErrorKind::UnexpectedEos(_) => "NXS0113",
+            ParseErrorKind::UnknownEdition(_) => "NXS0114",
+            ParseErrorKind::UnknownType(_) => "NXS0115",
+            ParseErrorKind::PipelineTarget => "NXS0116",
+            ParseErrorKind::DuplicatePort(_) => "NXS0117",
+            ParseErrorKind::UnknownConnectOption(_) => "NXS0118",
+            ParseErrorKind::UnknownOverflow(_) => "NXS0119",
+            ParseErrorKind::InvalidAssignmentTarget => "NXS0120",
+            ParseErrorKind::ChainedComparison => "NXS0121",
+            ParseErrorKind::AssignmentInCondition => "NXS0122",
+            ParseErrorKind::ReversedRange => "NXS0123",
+            ParseErrorKind::CastType => "NXS0124",
+            ParseErrorKind::DuplicateVariant(_) => "NXS0125",
+        }
+    }
+}
+
+/// Parsing error representation.
+#[derive(Error, Debug)]
+#[error("{kind}")]
+pub struct ParseError {
+    // Boxed to keep parse results small; they are returned at every rule level.
+    kind: Box<ParseErrorKind>,
+    span: Option<Span>,
+    notes: Vec<String>,
+}
+
+impl ParseError {
+    /// Create a new parse error from an error kind.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nexus_rs::parse_error::{ParseError, ParseErrorKind};
+    ///
+    /// let e = ParseError::new(ParseErrorKind::RangeDelimiter);
+    /// assert!(matches!(e.kind(), ParseErrorKind::RangeDelimiter));
+    /// ```
+    pub fn new(kind: ParseErrorKind) -> Self {
+        ParseError {
+            kind: Box::new(kind),
+            span: None,
+            notes: Vec::new(),
+        }
+    }
+
+    /// Attach a source span to the error (if any).
+    pub fn with_span(mut self, span: Option<Span>) -> Self {
+        self.span = span;
+        self
+    }
+
+    /// Attach a note to the error, e.g. a suggestion on how to fix it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nexus_rs::parse_error::{ParseError, ParseErrorKind};
+    ///
+    /// let e = ParseError::new(ParseErrorKind::RangeDelimiter).with_note("did you mean 'x'?");
+    /// assert_eq!(e.notes(), ["did you mean 'x'?"]);
+    /// ```
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    /// Get the error kind.
+    pub fn kind(&self) -> &ParseErrorKind {
+        &self.kind
+    }
+
+    /// Get the source span of the error (if known).
+    pub fn span(&self) -> Option<Span> {
+        self.span
+    }
+
+    /// Get the notes attached to the error.
+    pub fn notes(&self) -> &[String] {
+        &self.notes
+    }
+
+    /// Render the error as a diagnostic, underlining its span in the source lines (if known).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nexus_rs::{parse_error::*, source_line::SourceLine, span::Span};
+    ///
+    /// let lines = vec![SourceLine { line: "let = 1;".to_string(), number: None }];
+    /// let e = ParseError::new(ParseErrorKind::Custom("oops".to_string()))
+    ///     .with_span(Some(Span { file: 0, line: None, start: 4, end: 5 }));
+    ///
+    /// assert_eq!(e.render(&lines), "  |\n  | let = 1;\n  |     ^\n  | error[NXS0100]: oops\n  |");
+    /// ```
+    pub fn render(&self, lines: &[SourceLine]) -> String {
+        let message = format!("error[{}]: {}", self.kind.code(), self.kind);
+
+        match self
+            .span
+            .and_then(|s| lines.iter().find(|l| l.number == s.line).map(|l| (l, s)))
+        {
+            Some((line, span)) => line.render_snippet(span.start, span.end, &message),
+            None => message,
+        }
+    }
+}
+
+/// Parsing result type.
+pub type ParseResult<T> = Result<T, ParseError>;
@@ -1,34 +1,101 @@
+use crate::edition::Edition;
 use crate::parse_error::*;
+use crate::scanner::keyword;
+use crate::span::{Span, Spans};
+use crate::suggest::suggest;
+use crate::symbol::Symbol;
 use crate::token::{Token, Tokens};
 use crate::token_cursor::TokenCursor;
 use crate::{ast, ptr::Ptr};
-use lazy_static::lazy_static;
+
+/// Maximum grammar rule nesting depth, to prevent stack overflows on deeply nested input.
+const MAX_DEPTH: usize = 1024;
+
+/// Grammar rule nesting depth of a grouped operand (`expr`, `binary_expr`, `unary_expr`, `call_expr`,
+///  `primary_expr` and `group_expr`).
+const OPERAND_DEPTH: usize = 6;
 
 /// Parser for Nexus.
 pub struct Parser {
-    cursor: TokenCursor,
+    tokens: Tokens,
+    spans: Spans,
+    edition: Edition,
+}
+
+/// Check for identifiers that are reserved in an edition.
+fn check_reserved(tokens: &Tokens, spans: &Spans, edition: Edition) -> Result<(), ParseError> {
+    for (i, token) in tokens.iter().enumerate() {
+        if let Token::Identifier(s) = token {
+            // Casts and enum declarations are allowed, though 'as' and 'enum' are reserved.
+            let cast = s.as_str() == AS && tokens.get(i + 1).is_some_and(Token::is_type);
+            let decl = s.as_str() == ENUM
+                && matches!(
+                    (tokens.get(i + 1), tokens.get(i + 2)),
+                    (Some(Token::Identifier(_)), Some(Token::LeftBrace))
+                );
+
+            if !cast && !decl && edition.reserved().contains(&s.as_str()) {
+                return Err(
+                    ParseError::new(ParseErrorKind::ReservedIdentifier(*s, edition))
+                        .with_span(spans.get(i).copied()),
+                );
+            }
+        }
+    }
+
+    Ok(())
 }
 
-/// Preprocess token stream.
-fn preprocess(tokens: Tokens) -> Tokens {
+/// Preprocess token stream (and the accompanying spans, if any).
+fn preprocess(tokens: Tokens, spans: Spans) -> (Tokens, Spans) {
     let mut result = Tokens::new();
+    let mut result_spans = Spans::new();
+    let mut spans = spans.into_iter();
 
     // TODO: For now, ignore non-capturing closures and transform a '||' into 'Or':
-    let mut found_pipe = false;
+    let mut found_pipe: Option<Option<Span>> = None;
     tokens.into_iter().for_each(|t| {
-        if found_pipe && t == Token::Pipe {
-            result.push(Token::Or);
-            found_pipe = false;
-        } else {
-            found_pipe = t == Token::Pipe;
+        let span = spans.next();
 
-            if !found_pipe {
-                result.push(t);
+        if let (Some(pipe_span), Token::Pipe) = (found_pipe, &t) {
+            result.push(Token::Or);
+            if let (Some(p), Some(s)) = (pipe_span, span) {
+                result_spans.push(p.merge(&s));
             }
+            found_pipe = None;
+        } else if t == Token::Pipe {
+            found_pipe = Some(span);
+        } else if matches!(t, Token::Newline | Token::Eof | Token::Whitespace(_)) {
+            // Layout tokens are not (yet) used by the parser.
+        } else {
+            found_pipe = None;
+            result.push(t);
+            result_spans.extend(span);
         }
     });
 
-    result
+    // Doc comments are only kept if they precede a declaration, elsewhere they are just comments.
+    let attached = |i: usize| match result[i..]
+        .iter()
+        .find(|t| !matches!(t, Token::DocComment(_)))
+    {
+        Some(Token::Const | Token::Function | Token::Group | Token::Let | Token::Node) => true,
+        Some(Token::Identifier(id)) => id.as_str() == ENUM,
+        _ => false,
+    };
+
+    let keep: Vec<bool> = (0..result.len())
+        .map(|i| !matches!(result[i], Token::DocComment(_)) || attached(i))
+        .collect();
+
+    let mut keep_iter = keep.iter();
+    result.retain(|_| *keep_iter.next().expect("flag per token"));
+    if result_spans.len() == keep.len() {
+        let mut keep_iter = keep.iter();
+        result_spans.retain(|_| *keep_iter.next().expect("flag per span"));
+    }
+
+    (result, result_spans)
 }
 
 impl Parser {
@@ -44,17 +111,62 @@ impl Parser {
     /// let p = Parser::new(t);
     /// ```
     pub fn new(tokens: Tokens) -> Self {
+        Self::with_spans(tokens, Spans::new())
+    }
+
+    /// Create a new parser from a collection of tokens and their source spans.
+    ///
+    /// Parse errors will carry the span of the offending token.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nexus_rs::parser::Parser;
+    /// use nexus_rs::scanner::Scanner;
+    /// use nexus_rs::source_line::SourceLine;
+    ///
+    /// let (t, s, _) = Scanner::new()
+    ///     .scan_with_spans(SourceLine { line: "print (1;".to_string(), number: None });
+    /// let e = Parser::with_spans(t, s).parse().unwrap_err();
+    ///
+    /// assert_eq!(e.span().map(|s| s.start), Some(8));
+    /// ```
+    pub fn with_spans(tokens: Tokens, spans: Spans) -> Self {
+        let (tokens, spans) = preprocess(tokens, spans);
         Parser {
-            cursor: TokenCursor::new(preprocess(tokens)),
+            tokens,
+            spans,
+            edition: Edition::default(),
         }
     }
 
+    /// Select the language edition to parse (the first edition by default).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nexus_rs::{edition::Edition, parser::Parser, scanner::Scanner, source_line::SourceLine};
+    ///
+    /// let (tokens, _) = Scanner::new()
+    ///     .scan(SourceLine { line: "let match = 1;".to_string(), number: None });
+    ///
+    /// assert!(Parser::new(tokens.clone()).parse().is_ok());
+    /// assert!(Parser::new(tokens).with_edition(Edition::E2024).parse().is_err());
+    /// ```
+    pub fn with_edition(mut self, edition: Edition) -> Self {
+        self.edition = edition;
+        self
+    }
+
     /// Parse tokens into AST.
     pub fn parse(&mut self) -> Result<ast::Stmts, ParseError> {
         let mut ast = ast::Stmts::new();
+        let mut cursor = TokenCursor::with_spans(&self.tokens, &self.spans);
+
+        check_reserved(&self.tokens, &self.spans, self.edition)?;
 
-        while !self.cursor.eos() {
-            ast.push(parse_decl(&mut self.cursor)?);
+        while !cursor.eos() {
+            ast.push(parse_decl(&mut cursor)?);
         }
 
         Ok(ast)
@@ -62,23 +174,72 @@ impl Parser {
 }
 
 fn parse_decl(c: &mut TokenCursor) -> ParseResult<ast::Stmt> {
-    match c.peek() {
-        Some(Token::Const) => parse_const_decl(c),
-        Some(Token::Function) => parse_function_decl(c),
-        Some(Token::Let) => parse_var_decl(c),
+    let c = &mut c.trace("decl");
+
+    check_depth(c)?;
+
+    let doc = parse_doc(c);
+
+    spanned(c, |c| match c.peek() {
+        Some(Token::Const) => parse_const_decl(c, doc),
+        Some(Token::Function) => parse_function_decl(c, doc),
+        Some(Token::Let) => parse_var_decl(c, doc),
+        Some(Token::Group) if is_group_decl(c) => parse_group_decl(c, doc),
+        Some(Token::Identifier(id)) if id.as_str() == ENUM && is_enum_decl(c) => {
+            parse_enum_decl(c, doc)
+        }
+        Some(Token::Node) if is_node_decl(c) => parse_node_decl(c, doc),
         Some(Token::Use) => parse_use_decl(c),
         _ => parse_stmt(c),
+    })
+}
+
+/// Parse the doc comments preceding a declaration (if any) into its documentation text, one line per comment.
+fn parse_doc(c: &mut TokenCursor) -> Option<String> {
+    let mut lines = Vec::new();
+
+    while let Some(Token::DocComment(text)) = c.peek() {
+        lines.push(text.as_str());
+        c.advance();
+    }
+
+    (!lines.is_empty()).then(|| lines.join("\n"))
+}
+
+/// Parse a statement, attaching its source span: from its first token up to its last token on the same line.
+fn spanned(
+    c: &mut TokenCursor,
+    parse: impl FnOnce(&mut TokenCursor) -> ParseResult<ast::Stmt>,
+) -> ParseResult<ast::Stmt> {
+    let start = c.span();
+    let mut stmt = parse(c)?;
+
+    stmt.span = start.map(|start| match c.prev_span() {
+        Some(end) if end.line == start.line && end.end > start.start => start.merge(&end),
+        _ => start,
+    });
+
+    Ok(stmt)
+}
+
+fn check_depth(c: &TokenCursor) -> ParseResult<()> {
+    if c.depth() > MAX_DEPTH {
+        return Err(c.error(ParseErrorKind::NestingTooDeep(MAX_DEPTH)));
     }
+
+    Ok(())
 }
 
-fn parse_function_decl(c: &mut TokenCursor) -> ParseResult<ast::Stmt> {
+fn parse_function_decl(c: &mut TokenCursor, doc: Option<String>) -> ParseResult<ast::Stmt> {
+    let c = &mut c.trace("function_decl");
+
     c.consume(Token::Function)?;
 
     let id = parse_identifier(c)?;
 
     c.consume_msg(Token::LeftParen, "expected '(' after function identifier")?;
 
-    let args = if c.peek() != Some(Token::RightParen) {
+    let args = if c.peek() != Some(&Token::RightParen) {
         Some(parse_function_args(c)?)
     } else {
         None
@@ -89,7 +250,7 @@ fn parse_function_decl(c: &mut TokenCursor) -> ParseResult<ast::Stmt> {
         "expected ')' after function argument list",
     )?;
 
-    let ret_type = if c.peek() == Some(Token::Arrow) {
+    let ret_type = if c.peek() == Some(&Token::Arrow) {
         c.consume_msg(Token::Arrow, "expected '->' in function declaration")?;
         Some(parse_type(c)?)
     } else {
@@ -104,11 +265,15 @@ fn parse_function_decl(c: &mut TokenCursor) -> ParseResult<ast::Stmt> {
             args,
             ret_type,
             body,
+            doc,
         })),
+        span: None,
     })
 }
 
 fn parse_function_arg(c: &mut TokenCursor) -> ParseResult<ast::FunctionArg> {
+    let c = &mut c.trace("function_arg");
+
     let id = parse_identifier(c)?;
 
     c.consume_msg(
@@ -122,6 +287,8 @@ fn parse_function_arg(c: &mut TokenCursor) -> ParseResult<ast::FunctionArg> {
 }
 
 fn parse_function_args(c: &mut TokenCursor) -> ParseResult<ast::FunctionArgs> {
+    let c = &mut c.trace("function_args");
+
     let mut result = ast::FunctionArgs::new();
 
     loop {
@@ -135,7 +302,9 @@ fn parse_function_args(c: &mut TokenCursor) -> ParseResult<ast::FunctionArgs> {
     Ok(result)
 }
 
-fn parse_const_decl(c: &mut TokenCursor) -> ParseResult<ast::Stmt> {
+fn parse_const_decl(c: &mut TokenCursor, doc: Option<String>) -> ParseResult<ast::Stmt> {
+    let c = &mut c.trace("const_decl");
+
     c.consume(Token::Const)?;
 
     let id = parse_identifier(c)?;
@@ -157,12 +326,12 @@ fn parse_const_decl(c: &mut TokenCursor) -> ParseResult<ast::Stmt> {
     let value = match typeid {
         ast::TypeKind::Bool => parse_bool_literal(c)?,
         ast::TypeKind::Group => {
-            return Err(ParseError::new(ParseErrorKind::Custom(
+            return Err(c.error(ParseErrorKind::Custom(
                 "cannot create a Group type literal".to_owned(),
             )));
         }
         ast::TypeKind::Node => {
-            return Err(ParseError::new(ParseErrorKind::Custom(
+            return Err(c.error(ParseErrorKind::Custom(
                 "cannot create a Node type literal".to_owned(),
             )));
         }
@@ -170,18 +339,203 @@ fn parse_const_decl(c: &mut TokenCursor) -> ParseResult<ast::Stmt> {
         ast::TypeKind::String => parse_string_literal(c)?,
     };
 
-    c.consume(Token::SemiColon)?;
+    parse_stmt_end(c)?;
+
+    Ok(ast::Stmt {
+        kind: ast::StmtKind::ConstDecl(Ptr::new(ast::ConstDecl {
+            id,
+            typeid,
+            value,
+            doc,
+        })),
+        span: None,
+    })
+}
+
+/// Check if an 'enum' identifier starts an enum declaration (`enum Mode { .. }`).
+fn is_enum_decl(c: &TokenCursor) -> bool {
+    matches!(
+        (c.peek_nth(1), c.peek_nth(2)),
+        (Some(Token::Identifier(_)), Some(Token::LeftBrace))
+    )
+}
+
+fn parse_enum_decl(c: &mut TokenCursor, doc: Option<String>) -> ParseResult<ast::Stmt> {
+    let c = &mut c.trace("enum_decl");
+
+    c.advance(); // The 'enum' identifier.
+
+    let id = parse_identifier(c)?;
+
+    c.consume_msg(Token::LeftBrace, "expected '{' after enum identifier")?;
+
+    // Variants are separated by commas, with an optional trailing comma.
+    let mut variants = Vec::new();
+    while !c.advance_if(Token::RightBrace) {
+        let variant = parse_identifier(c)?;
+
+        if variants.contains(&variant) {
+            return Err(c.error_prev(ParseErrorKind::DuplicateVariant(variant)));
+        }
+
+        variants.push(variant);
+
+        if !c.advance_if(Token::Comma) {
+            c.consume_msg(Token::RightBrace, "expected ',' or '}' after enum variant")?;
+            break;
+        }
+    }
+
+    Ok(ast::Stmt {
+        kind: ast::StmtKind::EnumDecl(Ptr::new(ast::EnumDecl { id, variants, doc })),
+        span: None,
+    })
+}
+
+/// Check if a 'group' keyword starts a group declaration (`group Chain(n: Number) { .. }`), rather than a group
+///  expression (e.g. `group Chain(3)`).
+fn is_group_decl(c: &TokenCursor) -> bool {
+    match (c.peek_nth(1), c.peek_nth(2)) {
+        (Some(Token::Identifier(_)), Some(Token::LeftBrace)) => true,
+        (Some(Token::Identifier(_)), Some(Token::LeftParen)) => {
+            // Parameters are followed by the body, arguments are not.
+            let mut depth = 0;
+            for i in 2.. {
+                match c.peek_nth(i) {
+                    Some(Token::LeftParen) => depth += 1,
+                    Some(Token::RightParen) if depth == 1 => {
+                        return c.peek_nth(i + 1) == Some(&Token::LeftBrace)
+                    }
+                    Some(Token::RightParen) => depth -= 1,
+                    Some(_) => (),
+                    None => break,
+                }
+            }
+            false
+        }
+        _ => false,
+    }
+}
+
+fn parse_group_decl(c: &mut TokenCursor, doc: Option<String>) -> ParseResult<ast::Stmt> {
+    let c = &mut c.trace("group_decl");
+
+    c.consume(Token::Group)?;
+
+    let id = parse_identifier(c)?;
+
+    let args = if c.advance_if(Token::LeftParen) {
+        let args = if c.peek() != Some(&Token::RightParen) {
+            parse_function_args(c)?
+        } else {
+            ast::FunctionArgs::new()
+        };
+
+        c.consume_msg(Token::RightParen, "expected ')' after group parameter list")?;
+
+        Some(args)
+    } else {
+        None
+    };
+
+    let body = parse_block_stmt(c)?;
 
     Ok(ast::Stmt {
-        kind: ast::StmtKind::ConstDecl(Ptr::new(ast::ConstDecl { id, typeid, value })),
+        kind: ast::StmtKind::GroupDecl(Ptr::new(ast::GroupDecl {
+            id,
+            args,
+            body,
+            doc,
+        })),
+        span: None,
+    })
+}
+
+/// Check if a 'node' keyword starts a node declaration (`node Adder { .. }`), rather than a node expression.
+fn is_node_decl(c: &TokenCursor) -> bool {
+    matches!(
+        (c.peek_nth(1), c.peek_nth(2)),
+        (Some(Token::Identifier(_)), Some(Token::LeftBrace))
+    )
+}
+
+fn parse_node_decl(c: &mut TokenCursor, doc: Option<String>) -> ParseResult<ast::Stmt> {
+    let c = &mut c.trace("node_decl");
+
+    c.consume(Token::Node)?;
+
+    let id = parse_identifier(c)?;
+
+    c.consume_msg(Token::LeftBrace, "expected '{' after node identifier")?;
+
+    let mut ports = Vec::<ast::PortDecl>::new();
+    while !c.advance_if(Token::RightBrace) {
+        let port = parse_port_decl(c)?;
+
+        if ports.iter().any(|p| p.id == port.id) {
+            return Err(c.error_prev(ParseErrorKind::DuplicatePort(port.id)));
+        }
+
+        ports.push(port);
+    }
+
+    Ok(ast::Stmt {
+        kind: ast::StmtKind::NodeDecl(Ptr::new(ast::NodeDecl { id, ports, doc })),
+        span: None,
+    })
+}
+
+fn parse_port_decl(c: &mut TokenCursor) -> ParseResult<ast::PortDecl> {
+    let c = &mut c.trace("port_decl");
+
+    // NOTE: 'out' is not a keyword, it is only special in port declarations.
+    let direction = match c.peek() {
+        Some(Token::In) => ast::PortDirection::Input,
+        Some(Token::Identifier(id)) if id.as_str() == "out" => ast::PortDirection::Output,
+        Some(_) => {
+            return Err(c.error(ParseErrorKind::Custom(
+                "expected a port declaration, like 'in x: Number;' or 'out y: Number;'".to_owned(),
+            )));
+        }
+        None => {
+            return Err(c.error(ParseErrorKind::UnexpectedEos("node declaration".to_owned())));
+        }
+    };
+
+    c.advance();
+
+    let id = parse_identifier(c)?;
+
+    c.consume_msg(Token::Colon, "expected ':' for type annotation of port")?;
+
+    let typeid = parse_type(c)?;
+
+    parse_stmt_end(c)?;
+
+    Ok(ast::PortDecl {
+        id,
+        direction,
+        typeid,
     })
 }
 
-fn parse_var_decl(c: &mut TokenCursor) -> ParseResult<ast::Stmt> {
+fn parse_var_decl(c: &mut TokenCursor, doc: Option<String>) -> ParseResult<ast::Stmt> {
+    let c = &mut c.trace("var_decl");
+
     c.consume(Token::Let)?;
 
     let mutable = c.advance_if(Token::Mut);
 
+    // A keyword followed by a type or value is meant as name, rather than starting an expression.
+    if c.peek().and_then(keyword).is_some()
+        && matches!(
+            c.peek_nth(1),
+            Some(Token::Colon | Token::Is | Token::SemiColon)
+        )
+    {
+        parse_identifier(c)?;
+    }
+
     let id = parse_expr(c)?;
 
     let typeid = if c.advance_if(Token::Colon) {
@@ -192,7 +546,12 @@ fn parse_var_decl(c: &mut TokenCursor) -> ParseResult<ast::Stmt> {
 
     let value = if c.advance_if(Token::Is) {
         Some(if c.advance_if(Token::Amp) {
+            let span = c.prev_span();
             let expr = parse_expr(c)?;
+            if !is_ref_target(&expr) {
+                return Err(ParseError::new(ParseErrorKind::RefTarget).with_span(span));
+            }
+
             ast::Expr {
                 kind: ast::ExprKind::Ref(Ptr::new(ast::Ref { expr })),
             }
@@ -203,7 +562,7 @@ fn parse_var_decl(c: &mut TokenCursor) -> ParseResult<ast::Stmt> {
         None
     };
 
-    c.consume(Token::SemiColon)?;
+    parse_stmt_end(c)?;
 
     Ok(ast::Stmt {
         kind: ast::StmtKind::VarDecl(Ptr::new(ast::VarDecl {
@@ -211,34 +570,97 @@ fn parse_var_decl(c: &mut TokenCursor) -> ParseResult<ast::Stmt> {
             mutable,
             typeid,
             value,
+            doc,
         })),
+        span: None,
     })
 }
 
+/// Check if an expression can be referenced: a variable or (nested) member access.
+fn is_ref_target(expr: &ast::Expr) -> bool {
+    match &expr.kind {
+        ast::ExprKind::Var(_) => true,
+        ast::ExprKind::Binary(b) if matches!(b.op, ast::BinaryOp::Dot) => {
+            is_ref_target(&b.lhs) && is_ref_target(&b.rhs)
+        }
+        _ => false,
+    }
+}
+
+/// Check if an expression can be assigned to: a variable, member (e.g. `f().x`) or indexed value.
+fn is_assignment_target(expr: &ast::Expr) -> bool {
+    match &expr.kind {
+        ast::ExprKind::Var(_) | ast::ExprKind::Index(_) => true,
+        ast::ExprKind::Binary(b) => {
+            matches!(b.op, ast::BinaryOp::Dot) && matches!(b.rhs.kind, ast::ExprKind::Var(_))
+        }
+        _ => false,
+    }
+}
+
 fn parse_use_decl(c: &mut TokenCursor) -> ParseResult<ast::Stmt> {
+    let c = &mut c.trace("use_decl");
+
+    let span = c.span();
+
     c.consume(Token::Use)?;
 
     // TODO: Check for global scope?
 
     let filename = parse_expr(c)?;
 
-    c.consume_msg(Token::SemiColon, "expected semicolon after statement")?;
+    parse_stmt_end(c)?;
 
     Ok(ast::Stmt {
-        kind: ast::StmtKind::UseDecl(Ptr::new(ast::UseDecl { filename })),
+        kind: ast::StmtKind::UseDecl(Ptr::new(ast::UseDecl { filename, span })),
+        span: None,
     })
 }
 
 fn parse_stmt(c: &mut TokenCursor) -> ParseResult<ast::Stmt> {
-    match c.peek() {
+    let c = &mut c.trace("stmt");
+
+    spanned(c, |c| match c.peek() {
         Some(Token::LeftBrace) => parse_block_stmt(c),
-        Some(Token::Print) => parse_print_stmt(c),
+        Some(Token::Print | Token::Println) => parse_print_stmt(c),
         Some(Token::Return) => parse_return_stmt(c),
+        Some(Token::Defer) => parse_defer_stmt(c),
+        Some(Token::LeftBracket) => {
+            let sources = parse_connect_stage(c)?;
+            parse_connect_stmt(sources, c)
+        }
+        Some(Token::Identifier(id)) if id.as_str() == REMOVE && is_remove_stmt(c) => {
+            parse_remove_stmt(c)
+        }
         _ => parse_expr_stmt(c),
+    })
+}
+
+fn parse_defer_stmt(c: &mut TokenCursor) -> ParseResult<ast::Stmt> {
+    let c = &mut c.trace("defer_stmt");
+
+    c.consume(Token::Defer)?;
+
+    if matches!(
+        c.peek(),
+        Some(
+            Token::Const | Token::Function | Token::Let | Token::Use | Token::Return | Token::Defer
+        )
+    ) {
+        return Err(c.error(ParseErrorKind::DeferTarget));
     }
+
+    let stmt = parse_stmt(c)?;
+
+    Ok(ast::Stmt {
+        kind: ast::StmtKind::Defer(Ptr::new(stmt)),
+        span: None,
+    })
 }
 
 fn parse_block_stmt(c: &mut TokenCursor) -> ParseResult<ast::Stmt> {
+    let c = &mut c.trace("block_stmt");
+
     c.consume(Token::LeftBrace)?;
 
     let mut body = ast::Stmts::new();
@@ -246,9 +668,7 @@ fn parse_block_stmt(c: &mut TokenCursor) -> ParseResult<ast::Stmt> {
         match c.peek() {
             Some(Token::RightBrace) => break,
             None => {
-                return Err(ParseError::new(ParseErrorKind::UnexpectedEos(
-                    "block statement".to_owned(),
-                )))
+                return Err(c.error(ParseErrorKind::UnexpectedEos("block statement".to_owned())))
             }
             _ => body.push(parse_decl(c)?),
         }
@@ -258,118 +678,114 @@ fn parse_block_stmt(c: &mut TokenCursor) -> ParseResult<ast::Stmt> {
 
     Ok(ast::Stmt {
         kind: ast::StmtKind::Block(body),
+        span: None,
     })
 }
 
-fn parse_identifier(c: &mut TokenCursor) -> ParseResult<String> {
-    lazy_static! {
-        static ref KEYWORDS: Tokens = vec![
-            Token::BoolId,
-            Token::NodeId,
-            Token::Const,
-            Token::Else,
-            Token::False,
-            Token::For,
-            Token::Function,
-            Token::Group,
-            Token::GroupId,
-            Token::If,
-            Token::Let,
-            Token::Mut,
-            Token::Node,
-            Token::NumberId,
-            Token::Print,
-            Token::Return,
-            Token::StringId,
-            Token::True,
-            Token::Use,
-            Token::While,
-        ];
-    }
+fn parse_identifier(c: &mut TokenCursor) -> ParseResult<Symbol> {
+    let c = &mut c.trace("identifier");
 
     match c.value() {
-        Some(Token::Identifier(i)) => Ok(i),
-        Some(t) if KEYWORDS.contains(&t) => {
-            Err(ParseError::new(ParseErrorKind::KeywordAsIdentifier(t)))
-        }
-        Some(t) => Err(ParseError::new(ParseErrorKind::Unexpected(t))),
-        None => Err(ParseError::new(ParseErrorKind::UnexpectedEos(
-            "identifier".to_owned(),
-        ))),
+        Some(Token::Identifier(i)) => Ok(*i),
+        Some(t) => match keyword(t) {
+            Some(word) => Err(c
+                .error_prev(ParseErrorKind::KeywordAsIdentifier(t.clone()))
+                .with_note(format!("use the raw identifier 'r#{word}' as name"))),
+            None => Err(c.error_prev(ParseErrorKind::Unexpected(t.clone()))),
+        },
+        None => Err(c.error_prev(ParseErrorKind::UnexpectedEos("identifier".to_owned()))),
     }
 }
 
 fn parse_type(c: &mut TokenCursor) -> ParseResult<ast::TypeKind> {
+    let c = &mut c.trace("type");
+
     Ok(match c.value() {
         Some(Token::BoolId) => ast::TypeKind::Bool,
         Some(Token::NodeId) => ast::TypeKind::Node,
         Some(Token::GroupId) => ast::TypeKind::Group,
         Some(Token::NumberId) => ast::TypeKind::Number,
         Some(Token::StringId) => ast::TypeKind::String,
+        Some(Token::Identifier(id)) => {
+            const TYPE_IDS: [&str; 5] = ["Group", "Node", "Number", "String", "bool"];
+
+            let e = c.error_prev(ParseErrorKind::UnknownType(*id));
+            return Err(match suggest(id.as_str(), TYPE_IDS) {
+                Some(name) => e.with_note(format!("did you mean '{name}'?")),
+                None => e,
+            });
+        }
         Some(t) => {
-            return Err(ParseError::new(ParseErrorKind::Custom(format!(
-                "not a type ID '{:?}'",
-                t
-            ))));
+            return Err(c.error_prev(ParseErrorKind::Custom(format!("not a type ID '{t}'"))));
         }
         None => {
-            return Err(ParseError::new(ParseErrorKind::Custom(
-                "empty type ID".to_owned(),
-            )));
+            return Err(c.error_prev(ParseErrorKind::Custom("empty type ID".to_owned())));
         }
     })
 }
 
 fn parse_expr(c: &mut TokenCursor) -> ParseResult<ast::Expr> {
-    // NOTE: The recursion depth encodes the operator precedence.
-    parse_range_expr(c)
+    let c = &mut c.trace("expr");
+
+    check_depth(c)?;
+
+    // NOTE: The operator precedence is encoded in the token binding powers (see `Token::binding_power`).
+    parse_binary_expr(c, 0)
 }
 
-fn parse_range_expr(c: &mut TokenCursor) -> ParseResult<ast::Expr> {
-    let mut expr = parse_or_expr(c)?;
+/// Parse a (Pratt) binary expression, only consuming operators binding at least as strong as `min_power`.
+fn parse_binary_expr(c: &mut TokenCursor, min_power: u8) -> ParseResult<ast::Expr> {
+    let depth = c.depth();
+    parse_binary_expr_at(c, min_power, depth)
+}
 
-    if matches!(c.peek(), Some(Token::Range)) {
-        c.consume(Token::Range)?;
+/// Parse a (Pratt) binary expression nested at a depth (in grammar rule levels).
+///
+/// The loop builds operator chains without recursion, but each operator nests an operand one level deeper in the
+///  AST, which all AST consumers recurse into. So each operator counts as deep as a grouped operand.
+fn parse_binary_expr_at(
+    c: &mut TokenCursor,
+    min_power: u8,
+    mut depth: usize,
+) -> ParseResult<ast::Expr> {
+    let c = &mut c.trace("binary_expr");
 
-        let kind = if c.peek() == Some(Token::Is) {
-            c.consume(Token::Is)?;
-            ast::RangeKind::Inclusive
-        } else {
-            ast::RangeKind::Exclusive
-        };
+    check_depth(c)?;
 
-        let start = expr;
-        let end = parse_or_expr(c)?;
-
-        let check_range_expr_type = |e: &ast::Expr| -> ParseResult<()> {
-            if matches!(
-                e.kind,
-                ast::ExprKind::Literal(_) | ast::ExprKind::Var(_) | ast::ExprKind::Group(_)
-            ) {
-                Ok(())
-            } else {
-                Err(ParseError::new(ParseErrorKind::RangeDelimiter))
-            }
-        };
+    let mut expr = parse_unary_expr(c)?;
 
-        check_range_expr_type(&start)?;
-        check_range_expr_type(&end)?;
+    while let Some(power) = c.peek().and_then(Token::binding_power) {
+        if power < min_power {
+            break;
+        }
 
-        expr = ast::Expr {
-            kind: ast::ExprKind::Range(Ptr::new(ast::Range { kind, start, end })),
-        };
-    }
+        depth += OPERAND_DEPTH;
+        if depth > MAX_DEPTH {
+            return Err(c.error(ParseErrorKind::NestingTooDeep(MAX_DEPTH)));
+        }
 
-    Ok(expr)
-}
+        // Ranges do not associate, so a range ends the expression.
+        if matches!(c.peek(), Some(Token::Range | Token::RangeInclusive)) {
+            return parse_range_expr(expr, power, c);
+        }
 
-fn parse_or_expr(c: &mut TokenCursor) -> ParseResult<ast::Expr> {
-    let mut expr = parse_and_expr(c)?;
+        // A pipeline is not a binary expression, but desugars to a function call.
+        if c.advance_if(Token::Pipeline) {
+            expr = parse_pipeline_expr(expr, power, c)?;
+            continue;
+        }
 
-    while matches!(c.peek(), Some(Token::Or)) {
+        // Comparisons do not chain: `a < b < c` would compare the bool result of `a < b` with a number.
+        if is_comparison(c.peek()) && is_comparison_expr(&expr) {
+            return Err(c
+                .error(ParseErrorKind::ChainedComparison)
+                .with_note("combine comparisons with '&&', like 'a < b && b < c'"));
+        }
+
+        let right = c.peek().is_some_and(Token::is_right_associative);
         let op = parse_binary_op(c.value())?;
         let lhs = expr;
-        let rhs = parse_and_expr(c)?;
+        let rhs = parse_binary_expr_at(c, if right { power } else { power + 1 }, depth)?;
 
         expr = ast::Expr {
             kind: ast::ExprKind::Binary(Ptr::new(ast::BinaryExpr { op, lhs, rhs })),
@@ -379,93 +795,131 @@ fn parse_or_expr(c: &mut TokenCursor) -> ParseResult<ast::Expr> {
     Ok(expr)
 }
 
-fn parse_and_expr(c: &mut TokenCursor) -> ParseResult<ast::Expr> {
-    let mut expr = parse_equality_expr(c)?;
-
-    while matches!(c.peek(), Some(Token::And)) {
-        let op = parse_binary_op(c.value())?;
-        let lhs = expr;
-        let rhs = parse_equality_expr(c)?;
+fn is_comparison(token: Option<&Token>) -> bool {
+    matches!(
+        token,
+        Some(Token::Lt | Token::LtEq | Token::Gt | Token::GtEq)
+    )
+}
 
-        expr = ast::Expr {
-            kind: ast::ExprKind::Binary(Ptr::new(ast::BinaryExpr { op, lhs, rhs })),
-        };
-    }
+/// Check if an expression is an (ungrouped) comparison.
+fn is_comparison_expr(expr: &ast::Expr) -> bool {
+    use ast::BinaryOp::*;
 
-    Ok(expr)
+    matches!(&expr.kind, ast::ExprKind::Binary(b) if matches!(b.op, Lt | LtEq | Gt | GtEq))
 }
 
-fn parse_equality_expr(c: &mut TokenCursor) -> ParseResult<ast::Expr> {
-    let mut expr = parse_relational_expr(c)?;
-
-    while matches!(c.peek(), Some(Token::Eq) | Some(Token::NotEq)) {
-        let op = parse_binary_op(c.value())?;
-        let lhs = expr;
-        let rhs = parse_relational_expr(c)?;
+/// Parse the condition of an `if` or `while` expression.
+fn parse_condition(c: &mut TokenCursor) -> ParseResult<ast::Expr> {
+    let expr = parse_expr(c)?;
 
-        expr = ast::Expr {
-            kind: ast::ExprKind::Binary(Ptr::new(ast::BinaryExpr { op, lhs, rhs })),
-        };
+    if c.peek() == Some(&Token::Is) {
+        return Err(c
+            .error(ParseErrorKind::AssignmentInCondition)
+            .with_note("use '==' to compare values"));
     }
 
     Ok(expr)
 }
 
-fn parse_relational_expr(c: &mut TokenCursor) -> ParseResult<ast::Expr> {
-    let mut expr = parse_expr_term(c)?;
+/// Desugar a pipeline `x |> f` into `f(x)`, and `x |> f(a, b)` into `f(x, a, b)`.
+fn parse_pipeline_expr(arg: ast::Expr, power: u8, c: &mut TokenCursor) -> ParseResult<ast::Expr> {
+    let c = &mut c.trace("pipeline_expr");
+
+    let target = parse_binary_expr(c, power + 1)?;
+
+    let call = match target.kind {
+        ast::ExprKind::Var(var) => ast::FuncCall {
+            id: var.id,
+            args: vec![arg],
+        },
+        ast::ExprKind::FuncCall(call) => {
+            let mut call = call.into_inner();
+            call.args.insert(0, arg);
+            call
+        }
+        _ => return Err(c.error_prev(ParseErrorKind::PipelineTarget)),
+    };
 
-    while matches!(
-        c.peek(),
-        Some(Token::Lt) | Some(Token::Gt) | Some(Token::LtEq) | Some(Token::GtEq)
-    ) {
-        let op = parse_binary_op(c.value())?;
-        let lhs = expr;
-        let rhs = parse_expr_term(c)?;
+    Ok(ast::Expr {
+        kind: ast::ExprKind::FuncCall(Ptr::new(call)),
+    })
+}
 
-        expr = ast::Expr {
-            kind: ast::ExprKind::Binary(Ptr::new(ast::BinaryExpr { op, lhs, rhs })),
-        };
+fn parse_range_expr(start: ast::Expr, power: u8, c: &mut TokenCursor) -> ParseResult<ast::Expr> {
+    let c = &mut c.trace("range_expr");
+
+    let check_range_expr_type = |e: &ast::Expr| {
+        matches!(
+            e.kind,
+            ast::ExprKind::Literal(_) | ast::ExprKind::Var(_) | ast::ExprKind::Group(_)
+        )
+    };
+
+    if !check_range_expr_type(&start) {
+        return Err(c.error(ParseErrorKind::RangeDelimiter));
     }
 
-    Ok(expr)
-}
+    let kind = match c.value() {
+        Some(Token::RangeInclusive) => ast::RangeKind::Inclusive,
+        _ => ast::RangeKind::Exclusive,
+    };
 
-fn parse_expr_term(c: &mut TokenCursor) -> ParseResult<ast::Expr> {
-    let mut expr = parse_factor_expr(c)?;
+    if matches!(kind, ast::RangeKind::Exclusive) && c.peek() == Some(&Token::Is) {
+        let span = c.prev_span().zip(c.span()).map(|(r, i)| r.merge(&i));
+        return Err(ParseError::new(ParseErrorKind::SplitRangeInclusive).with_span(span));
+    }
 
-    while matches!(c.peek(), Some(Token::Plus) | Some(Token::Minus)) {
-        let op = parse_binary_op(c.value())?;
-        let lhs = expr;
-        let rhs = parse_factor_expr(c)?;
+    let end = parse_binary_expr(c, power + 1)?;
 
-        expr = ast::Expr {
-            kind: ast::ExprKind::Binary(Ptr::new(ast::BinaryExpr { op, lhs, rhs })),
-        };
+    if !check_range_expr_type(&end) {
+        return Err(c.error_prev(ParseErrorKind::RangeDelimiter));
     }
 
-    Ok(expr)
-}
+    // The step may also be negated, to count down (`10..0 step -1`).
+    let step = match c.peek() {
+        Some(Token::Identifier(id)) if id.as_str() == STEP => {
+            c.advance();
 
-fn parse_factor_expr(c: &mut TokenCursor) -> ParseResult<ast::Expr> {
-    let mut expr = parse_unary_expr(c)?;
+            let step = parse_binary_expr(c, power + 1)?;
+            let negated = matches!(&step.kind, ast::ExprKind::Unary(u) if matches!(u.op, ast::UnaryOp::Minus) && check_range_expr_type(&u.expr));
+            if !check_range_expr_type(&step) && !negated {
+                return Err(c.error_prev(ParseErrorKind::RangeDelimiter));
+            }
 
-    while matches!(
-        c.peek(),
-        Some(Token::Star) | Some(Token::Slash) | Some(Token::Percent)
-    ) {
-        let op = parse_binary_op(c.value())?;
-        let lhs = expr;
-        let rhs = parse_unary_expr(c)?;
+            Some(step)
+        }
+        _ => None,
+    };
 
-        expr = ast::Expr {
-            kind: ast::ExprKind::Binary(Ptr::new(ast::BinaryExpr { op, lhs, rhs })),
-        };
+    // Literal ranges that count up from a greater start are always empty, which is unlikely intended.
+    if let (ast::ExprKind::Literal(s), ast::ExprKind::Literal(e), None) =
+        (&start.kind, &end.kind, &step)
+    {
+        if let (ast::LiteralKind::Number(s), ast::LiteralKind::Number(e)) = (&s.kind, &e.kind) {
+            if s > e {
+                return Err(c
+                    .error_prev(ParseErrorKind::ReversedRange)
+                    .with_note(format!(
+                        "count down with a negative step, like '{s}{kind}{e} step -1'"
+                    )));
+            }
+        }
     }
 
-    Ok(expr)
+    Ok(ast::Expr {
+        kind: ast::ExprKind::Range(Ptr::new(ast::Range {
+            kind,
+            start,
+            end,
+            step,
+        })),
+    })
 }
 
 fn parse_unary_expr(c: &mut TokenCursor) -> ParseResult<ast::Expr> {
+    let c = &mut c.trace("unary_expr");
+
     if matches!(
         c.peek(),
         Some(Token::Bang)
@@ -486,30 +940,112 @@ fn parse_unary_expr(c: &mut TokenCursor) -> ParseResult<ast::Expr> {
 }
 
 fn parse_dot_expr(c: &mut TokenCursor) -> ParseResult<ast::Expr> {
+    let c = &mut c.trace("dot_expr");
+
     let mut expr = parse_call_expr(c)?;
 
-    while matches!(c.peek(), Some(Token::Dot)) {
-        let op = parse_binary_op(c.value())?;
-        let lhs = expr;
-        let rhs = parse_call_expr(c)?;
+    loop {
+        expr = match c.peek() {
+            Some(Token::Dot) => {
+                let op = parse_binary_op(c.value())?;
+                let lhs = expr;
+                let rhs = parse_call_expr(c)?;
+
+                ast::Expr {
+                    kind: ast::ExprKind::Binary(Ptr::new(ast::BinaryExpr { op, lhs, rhs })),
+                }
+            }
+            Some(Token::Question) => {
+                c.advance();
 
-        expr = ast::Expr {
-            kind: ast::ExprKind::Binary(Ptr::new(ast::BinaryExpr { op, lhs, rhs })),
+                ast::Expr {
+                    kind: ast::ExprKind::Propagate(Ptr::new(expr)),
+                }
+            }
+            Some(Token::Identifier(id)) if id.as_str() == AS && is_cast(c) => parse_cast(expr, c)?,
+            Some(Token::LeftBracket) if !is_buffer(c) => {
+                let index = parse_index(c)?;
+
+                ast::Expr {
+                    kind: ast::ExprKind::Index(Ptr::new(ast::Index { expr, index })),
+                }
+            }
+            _ => return Ok(expr),
         };
     }
+}
 
-    Ok(expr)
+/// Check if an 'as' identifier following an operand is a cast (`x as String`), i.e. followed by a type name. Other
+///  identifiers are accepted as well, to report unknown types.
+fn is_cast(c: &TokenCursor) -> bool {
+    matches!(c.peek_nth(1), Some(t) if t.is_type() || matches!(t, Token::Identifier(_)))
+}
+
+fn parse_cast(expr: ast::Expr, c: &mut TokenCursor) -> ParseResult<ast::Expr> {
+    let c = &mut c.trace("cast");
+
+    c.advance(); // The 'as' identifier.
+
+    let typeid = parse_type(c)?;
+
+    if matches!(typeid, ast::TypeKind::Group | ast::TypeKind::Node) {
+        return Err(c.error_prev(ParseErrorKind::CastType));
+    }
+
+    Ok(ast::Expr {
+        kind: ast::ExprKind::Cast(Ptr::new(ast::Cast { expr, typeid })),
+    })
+}
+
+/// Parse an index (`[i]`) or slice (`[a..b]`), where either slice bound may be omitted (e.g. `[..n]` or `[a..]`).
+fn parse_index(c: &mut TokenCursor) -> ParseResult<ast::IndexKind> {
+    let c = &mut c.trace("index");
+
+    c.consume(Token::LeftBracket)?;
+
+    // Bounds are parsed up to the range operator (which binds weakest).
+    let power = Token::Range.binding_power().expect("binary operator") + 1;
+    let is_range = |t: Option<&Token>| matches!(t, Some(Token::Range | Token::RangeInclusive));
+
+    let start = match is_range(c.peek()) {
+        true => None,
+        false => Some(parse_binary_expr(c, power)?),
+    };
+
+    let index = match (start, is_range(c.peek())) {
+        (Some(start), false) => ast::IndexKind::Element(start),
+        (start, _) => {
+            let kind = match c.value() {
+                Some(Token::RangeInclusive) => ast::RangeKind::Inclusive,
+                _ => ast::RangeKind::Exclusive,
+            };
+
+            // An inclusive slice requires an end bound.
+            let end = match (&kind, c.peek()) {
+                (ast::RangeKind::Exclusive, Some(Token::RightBracket)) => None,
+                _ => Some(parse_binary_expr(c, power)?),
+            };
+
+            ast::IndexKind::Slice(kind, start, end)
+        }
+    };
+
+    c.consume(Token::RightBracket)?;
+
+    Ok(index)
 }
 
 fn parse_call_expr(c: &mut TokenCursor) -> ParseResult<ast::Expr> {
-    match (c.peek(), c.peek_next()) {
+    let c = &mut c.trace("call_expr");
+
+    match (c.peek(), c.peek_nth(1)) {
         (Some(Token::Identifier(_)), Some(Token::LeftParen)) => {
             let id = parse_identifier(c)?;
 
             c.consume(Token::LeftParen)?;
 
             let mut args = Vec::new();
-            while c.peek() != Some(Token::RightParen) {
+            while c.peek() != Some(&Token::RightParen) {
                 args.push(parse_expr(c)?);
 
                 if !c.advance_if(Token::Comma) {
@@ -528,33 +1064,51 @@ fn parse_call_expr(c: &mut TokenCursor) -> ParseResult<ast::Expr> {
 }
 
 fn parse_primary_expr(c: &mut TokenCursor) -> ParseResult<ast::Expr> {
+    let c = &mut c.trace("primary_expr");
+
     match c.peek() {
-        Some(Token::Number(_)) => parse_number_literal(c),
+        Some(Token::Number(..)) => parse_number_literal(c),
         Some(Token::String(_)) => parse_string_literal(c),
         Some(Token::True | Token::False) => parse_bool_literal(c),
+        Some(Token::Nil) => parse_nil_literal(c),
         Some(Token::Identifier(_)) => parse_var_expr(c),
         Some(Token::If) => parse_if_expr(c),
         Some(Token::While) => parse_while_expr(c),
+        Some(Token::Loop) => parse_loop_expr(c),
         Some(Token::For) => parse_for_expr(c),
+        Some(Token::Time) => parse_time_expr(c),
+        Some(Token::Try) => parse_try_expr(c),
         Some(Token::LeftParen) => parse_group_expr(c),
         Some(Token::LeftBrace) => parse_block_expr(c),
         Some(Token::SemiColon) => Ok(ast::Expr {
             kind: ast::ExprKind::Empty(),
         }),
-        Some(t) => Err(ParseError::new(ParseErrorKind::Unexpected(t))),
-        None => Err(ParseError::new(ParseErrorKind::UnexpectedEos(
+        Some(Token::Amp) => Err(c.error(ParseErrorKind::UnexpectedRef)),
+        Some(t) => Err(c.error(ParseErrorKind::Unexpected(t.clone()))),
+        None => Err(c.error(ParseErrorKind::UnexpectedEos(
             "primary expression".to_owned(),
         ))),
     }
 }
 
 fn parse_expr_stmt(c: &mut TokenCursor) -> ParseResult<ast::Stmt> {
+    let c = &mut c.trace("expr_stmt");
+
+    let start = c.span();
     let expr = parse_expr(c)?;
 
     match c.peek() {
-        Some(Token::Arrow) => parse_connect_stmt(expr, c),
+        Some(Token::Arrow | Token::NotArrow) => parse_connect_stmt(vec![expr], c),
+        Some(Token::Is) if !is_assignment_target(&expr) => {
+            // The error covers the target, if it is on a single line.
+            let span = match (start, c.prev_span()) {
+                (Some(s), Some(e)) if s.line == e.line => Some(s.merge(&e)),
+                (s, _) => s,
+            };
+            Err(ParseError::new(ParseErrorKind::InvalidAssignmentTarget).with_span(span))
+        }
         Some(Token::Is) => parse_assignment_stmt(expr, c),
-        None => Err(ParseError::new(ParseErrorKind::UnexpectedEos(
+        None => Err(c.error(ParseErrorKind::UnexpectedEos(
             "expression statement".to_owned(),
         ))),
         _ => {
@@ -564,19 +1118,72 @@ fn parse_expr_stmt(c: &mut TokenCursor) -> ParseResult<ast::Stmt> {
 
             Ok(ast::Stmt {
                 kind: ast::StmtKind::Expr(Ptr::new(expr)),
+                span: None,
             })
         }
     }
 }
 
+fn parse_stmt_end(c: &mut TokenCursor) -> ParseResult<()> {
+    if c.advance_if(Token::SemiColon) {
+        return Ok(());
+    }
+
+    // If the statement is apparently complete (i.e. a new statement follows, possibly on a new line), the semicolon
+    //  was most likely forgotten: point to where it should be added.
+    let next_stmt = matches!(
+        c.peek(),
+        None | Some(
+            Token::Const
+                | Token::Function
+                | Token::Let
+                | Token::Use
+                | Token::Print
+                | Token::Println
+                | Token::Return
+                | Token::Defer
+                | Token::RightBrace
+        )
+    );
+    let next_line = matches!((c.prev_span(), c.span()), (Some(p), Some(n)) if p.line != n.line);
+
+    // An operand directly following an expression is most likely missing an operator.
+    let next_operand = matches!(
+        c.peek(),
+        Some(
+            Token::Number(..)
+                | Token::String(_)
+                | Token::Identifier(_)
+                | Token::True
+                | Token::False
+                | Token::Nil
+                | Token::LeftParen
+        )
+    );
+
+    if next_stmt || next_line {
+        Err(ParseError::new(ParseErrorKind::MissingSemiColon)
+            .with_span(c.prev_span().map(|s| s.after())))
+    } else if next_operand {
+        Err(c.error(ParseErrorKind::ExpectedOperator))
+    } else {
+        Err(c.error(ParseErrorKind::ExpectedReason(
+            Token::SemiColon,
+            "after statement".to_owned(),
+        )))
+    }
+}
+
 fn parse_if_expr(c: &mut TokenCursor) -> ParseResult<ast::Expr> {
+    let c = &mut c.trace("if_expr");
+
     c.consume(Token::If)?;
 
-    let expr = parse_expr(c)?;
+    let expr = parse_condition(c)?;
     let body_then = parse_block_expr(c)?;
 
     let body_else = if c.advance_if(Token::Else) {
-        if c.peek() == Some(Token::If) {
+        if c.peek() == Some(&Token::If) {
             Some(parse_if_expr(c)?)
         } else {
             Some(parse_block_expr(c)?)
@@ -595,9 +1202,11 @@ fn parse_if_expr(c: &mut TokenCursor) -> ParseResult<ast::Expr> {
 }
 
 fn parse_while_expr(c: &mut TokenCursor) -> ParseResult<ast::Expr> {
+    let c = &mut c.trace("while_expr");
+
     c.consume(Token::While)?;
 
-    let expr = parse_expr(c)?;
+    let expr = parse_condition(c)?;
     let body = parse_block_expr(c)?;
 
     Ok(ast::Expr {
@@ -605,7 +1214,45 @@ fn parse_while_expr(c: &mut TokenCursor) -> ParseResult<ast::Expr> {
     })
 }
 
+fn parse_loop_expr(c: &mut TokenCursor) -> ParseResult<ast::Expr> {
+    let c = &mut c.trace("loop_expr");
+
+    c.consume(Token::Loop)?;
+
+    let body = parse_block_expr(c)?;
+
+    Ok(ast::Expr {
+        kind: ast::ExprKind::Loop(Ptr::new(ast::Loop { body })),
+    })
+}
+
+fn parse_time_expr(c: &mut TokenCursor) -> ParseResult<ast::Expr> {
+    let c = &mut c.trace("time_expr");
+
+    c.consume(Token::Time)?;
+
+    let body = parse_block_expr(c)?;
+
+    Ok(ast::Expr {
+        kind: ast::ExprKind::Time(Ptr::new(ast::Time { body })),
+    })
+}
+
+fn parse_try_expr(c: &mut TokenCursor) -> ParseResult<ast::Expr> {
+    let c = &mut c.trace("try_expr");
+
+    c.consume(Token::Try)?;
+
+    let expr = parse_expr(c)?;
+
+    Ok(ast::Expr {
+        kind: ast::ExprKind::Try(Ptr::new(expr)),
+    })
+}
+
 fn parse_for_expr(c: &mut TokenCursor) -> ParseResult<ast::Expr> {
+    let c = &mut c.trace("for_expr");
+
     c.consume(Token::For)?;
 
     let id = parse_identifier(c)?;
@@ -621,6 +1268,8 @@ fn parse_for_expr(c: &mut TokenCursor) -> ParseResult<ast::Expr> {
 }
 
 fn parse_var_expr(c: &mut TokenCursor) -> ParseResult<ast::Expr> {
+    let c = &mut c.trace("var_expr");
+
     let id = parse_identifier(c)?;
 
     Ok(ast::Expr {
@@ -629,40 +1278,56 @@ fn parse_var_expr(c: &mut TokenCursor) -> ParseResult<ast::Expr> {
 }
 
 fn parse_bool_literal(c: &mut TokenCursor) -> ParseResult<ast::Expr> {
+    let c = &mut c.trace("bool_literal");
+
     Ok(ast::Expr {
         kind: ast::ExprKind::Literal(Ptr::new(ast::Literal {
             kind: ast::LiteralKind::Bool(match c.value() {
                 Some(Token::True) => true,
                 Some(Token::False) => false,
                 Some(t) => {
-                    return Err(ParseError::new(ParseErrorKind::Custom(format!(
+                    return Err(c.error_prev(ParseErrorKind::Custom(format!(
                         "not a boolean literal: '{t:?}'"
                     ))));
                 }
                 None => {
-                    return Err(ParseError::new(ParseErrorKind::UnexpectedEos(
-                        "boolean literal".to_owned(),
-                    )));
+                    return Err(
+                        c.error_prev(ParseErrorKind::UnexpectedEos("boolean literal".to_owned()))
+                    );
                 }
             }),
         })),
     })
 }
 
+fn parse_nil_literal(c: &mut TokenCursor) -> ParseResult<ast::Expr> {
+    let c = &mut c.trace("nil_literal");
+
+    c.consume(Token::Nil)?;
+
+    Ok(ast::Expr {
+        kind: ast::ExprKind::Literal(Ptr::new(ast::Literal {
+            kind: ast::LiteralKind::Nil,
+        })),
+    })
+}
+
 fn parse_number_literal(c: &mut TokenCursor) -> ParseResult<ast::Expr> {
+    let c = &mut c.trace("number_literal");
+
     Ok(ast::Expr {
         kind: ast::ExprKind::Literal(Ptr::new(ast::Literal {
             kind: ast::LiteralKind::Number(match c.value() {
-                Some(Token::Number(n)) => n,
+                Some(Token::Number(n, _)) => *n,
                 Some(n) => {
-                    return Err(ParseError::new(ParseErrorKind::Custom(format!(
-                        "not a number literal: '{n:?}'"
+                    return Err(c.error_prev(ParseErrorKind::Custom(format!(
+                        "not a number literal: '{n}'"
                     ))));
                 }
                 None => {
-                    return Err(ParseError::new(ParseErrorKind::UnexpectedEos(
-                        "number literal".to_owned(),
-                    )));
+                    return Err(
+                        c.error_prev(ParseErrorKind::UnexpectedEos("number literal".to_owned()))
+                    );
                 }
             }),
         })),
@@ -670,19 +1335,21 @@ fn parse_number_literal(c: &mut TokenCursor) -> ParseResult<ast::Expr> {
 }
 
 fn parse_string_literal(c: &mut TokenCursor) -> ParseResult<ast::Expr> {
+    let c = &mut c.trace("string_literal");
+
     Ok(ast::Expr {
         kind: ast::ExprKind::Literal(Ptr::new(ast::Literal {
             kind: ast::LiteralKind::String(match c.value() {
-                Some(Token::String(s)) => s,
+                Some(Token::String(s)) => s.clone(),
                 Some(s) => {
-                    return Err(ParseError::new(ParseErrorKind::Custom(format!(
+                    return Err(c.error_prev(ParseErrorKind::Custom(format!(
                         "not a string literal: '{s:?}'"
                     ))));
                 }
                 None => {
-                    return Err(ParseError::new(ParseErrorKind::UnexpectedEos(
-                        "string literal".to_owned(),
-                    )));
+                    return Err(
+                        c.error_prev(ParseErrorKind::UnexpectedEos("string literal".to_owned()))
+                    );
                 }
             }),
         })),
@@ -690,6 +1357,8 @@ fn parse_string_literal(c: &mut TokenCursor) -> ParseResult<ast::Expr> {
 }
 
 fn parse_group_expr(c: &mut TokenCursor) -> ParseResult<ast::Expr> {
+    let c = &mut c.trace("group_expr");
+
     c.consume(Token::LeftParen)?;
 
     let expr = parse_expr(c)?;
@@ -702,6 +1371,8 @@ fn parse_group_expr(c: &mut TokenCursor) -> ParseResult<ast::Expr> {
 }
 
 fn parse_block_expr(c: &mut TokenCursor) -> ParseResult<ast::Expr> {
+    let c = &mut c.trace("block_expr");
+
     let body = parse_block_stmt(c)?;
 
     Ok(ast::Expr {
@@ -709,7 +1380,7 @@ fn parse_block_expr(c: &mut TokenCursor) -> ParseResult<ast::Expr> {
     })
 }
 
-fn parse_unary_op(t: Option<Token>) -> ParseResult<ast::UnaryOp> {
+fn parse_unary_op(t: Option<&Token>) -> ParseResult<ast::UnaryOp> {
     Ok(match t {
         Some(Token::Bang) => ast::UnaryOp::Bang,
         Some(Token::Minus) => ast::UnaryOp::Minus,
@@ -729,7 +1400,7 @@ fn parse_unary_op(t: Option<Token>) -> ParseResult<ast::UnaryOp> {
     })
 }
 
-fn parse_binary_op(t: Option<Token>) -> ParseResult<ast::BinaryOp> {
+fn parse_binary_op(t: Option<&Token>) -> ParseResult<ast::BinaryOp> {
     Ok(match t {
         Some(Token::And) => ast::BinaryOp::And,
         Some(Token::Dot) => ast::BinaryOp::Dot,
@@ -745,6 +1416,7 @@ fn parse_binary_op(t: Option<Token>) -> ParseResult<ast::BinaryOp> {
         Some(Token::Plus) => ast::BinaryOp::Plus,
         Some(Token::Slash) => ast::BinaryOp::Divide,
         Some(Token::Star) => ast::BinaryOp::Multiply,
+        Some(Token::StarStar) => ast::BinaryOp::Power,
         Some(_) => {
             return Err(ParseError::new(ParseErrorKind::Custom(
                 "not a binary expression token".to_owned(),
@@ -759,49 +1431,225 @@ fn parse_binary_op(t: Option<Token>) -> ParseResult<ast::BinaryOp> {
 }
 
 fn parse_print_stmt(c: &mut TokenCursor) -> ParseResult<ast::Stmt> {
-    c.consume(Token::Print)?;
+    let c = &mut c.trace("print_stmt");
 
-    let expr = parse_expr(c)?;
+    let kind = match c.value() {
+        Some(Token::Println) => ast::PrintKind::Println,
+        _ => ast::PrintKind::Print,
+    };
+
+    let mut args = Vec::new();
+    if c.peek() != Some(&Token::SemiColon) {
+        loop {
+            args.push(parse_expr(c)?);
+
+            if !c.advance_if(Token::Comma) {
+                break;
+            }
 
-    c.consume_msg(Token::SemiColon, "after statement")?;
+            if c.peek() == Some(&Token::SemiColon) {
+                return Err(c.error(ParseErrorKind::Custom(
+                    "expected argument after ','".to_owned(),
+                )));
+            }
+        }
+    } else if kind == ast::PrintKind::Print {
+        return Err(c.error(ParseErrorKind::Custom(
+            "'print' requires at least one argument (use 'println' for an empty line)".to_owned(),
+        )));
+    }
+
+    parse_stmt_end(c)?;
 
     Ok(ast::Stmt {
-        kind: ast::StmtKind::Print(Ptr::new(ast::Print { expr })),
+        kind: ast::StmtKind::Print(Ptr::new(ast::Print { kind, args })),
+        span: None,
     })
 }
 
 fn parse_return_stmt(c: &mut TokenCursor) -> ParseResult<ast::Stmt> {
+    let c = &mut c.trace("return_stmt");
+
     c.consume(Token::Return)?;
 
     let expr = parse_expr(c)?;
 
-    c.consume_msg(Token::SemiColon, "after statement")?;
+    parse_stmt_end(c)?;
 
     Ok(ast::Stmt {
         kind: ast::StmtKind::Return(Ptr::new(ast::Return { expr })),
+        span: None,
     })
 }
 
 fn parse_assignment_stmt(lhs: ast::Expr, c: &mut TokenCursor) -> ParseResult<ast::Stmt> {
+    let c = &mut c.trace("assignment_stmt");
+
     c.consume(Token::Is)?;
 
     let rhs = parse_expr(c)?;
 
-    c.consume_msg(Token::SemiColon, "expected semicolon after statement")?;
+    parse_stmt_end(c)?;
 
     Ok(ast::Stmt {
         kind: ast::StmtKind::Assignment(Ptr::new(ast::Assignment { lhs, rhs })),
+        span: None,
+    })
+}
+
+/// Parse a connect (`->`) or disconnect (`-/>`) statement following its first stage. A chain uses a single arrow kind.
+fn parse_connect_stmt(sources: Vec<ast::Expr>, c: &mut TokenCursor) -> ParseResult<ast::Stmt> {
+    let c = &mut c.trace("connect_stmt");
+
+    let arrow = match c.peek() {
+        Some(Token::NotArrow) => Token::NotArrow,
+        _ => Token::Arrow,
+    };
+
+    let mut stages = vec![sources];
+
+    c.consume(arrow.clone())?;
+    loop {
+        stages.push(parse_connect_stage(c)?);
+
+        if !c.advance_if(arrow.clone()) {
+            break;
+        }
+    }
+
+    let buffer = match arrow {
+        Token::Arrow if is_buffer(c) => Some(parse_buffer(c)?),
+        _ => None,
+    };
+
+    parse_stmt_end(c)?;
+
+    let connect = Ptr::new(ast::Connect { stages, buffer });
+    Ok(ast::Stmt {
+        kind: match arrow {
+            Token::NotArrow => ast::StmtKind::Disconnect(connect),
+            _ => ast::StmtKind::Connect(connect),
+        },
+        span: None,
     })
 }
 
-fn parse_connect_stmt(source: ast::Expr, c: &mut TokenCursor) -> ParseResult<ast::Stmt> {
-    c.consume(Token::Arrow)?;
+/// Check if a '[' starts the buffering policy of a connect statement (`[capacity: 8]`), rather than an index.
+fn is_buffer(c: &TokenCursor) -> bool {
+    matches!(
+        (c.peek(), c.peek_nth(1), c.peek_nth(2)),
+        (
+            Some(Token::LeftBracket),
+            Some(Token::Identifier(_)),
+            Some(Token::Colon)
+        )
+    )
+}
+
+/// Parse the buffering policy of a connect statement: a list of options, like `[capacity: 8, overflow: drop_oldest]`.
+fn parse_buffer(c: &mut TokenCursor) -> ParseResult<ast::Buffer> {
+    let c = &mut c.trace("buffer");
+
+    c.consume(Token::LeftBracket)?;
+
+    let mut buffer = ast::Buffer::default();
+    let mut options = Vec::new();
+    loop {
+        let option = parse_identifier(c)?;
+        if !matches!(option.as_str(), "capacity" | "overflow") {
+            return Err(c.error_prev(ParseErrorKind::UnknownConnectOption(option)));
+        }
+        if options.contains(&option) {
+            return Err(c.error_prev(ParseErrorKind::Custom(format!(
+                "duplicate connection option '{option}'"
+            ))));
+        }
+        options.push(option);
+
+        c.consume_msg(Token::Colon, "expected ':' after connection option")?;
+
+        match option.as_str() {
+            "capacity" => buffer.capacity = Some(parse_expr(c)?),
+            _ => {
+                let policy = parse_identifier(c)?;
+                buffer.overflow = ast::Overflow::ALL
+                    .into_iter()
+                    .find(|o| o.to_string() == policy.as_str())
+                    .ok_or_else(|| c.error_prev(ParseErrorKind::UnknownOverflow(policy)))?;
+            }
+        }
+
+        if !c.advance_if(Token::Comma) {
+            break;
+        }
+    }
+
+    c.consume_msg(Token::RightBracket, "expected ']' after connection options")?;
+
+    Ok(buffer)
+}
+
+/// Contextual keyword of enum declarations (`enum Mode { .. }`), which is reserved from edition 2024 (except in
+///  declarations).
+const ENUM: &str = "enum";
+
+/// Contextual keyword of cast expressions (`x as String`), which is reserved from edition 2024 (except in casts).
+const AS: &str = "as";
 
-    let sink = parse_expr(c)?;
+/// Contextual keyword of the range step (`0..10 step 2`), which is not reserved elsewhere.
+const STEP: &str = "step";
 
-    c.consume_msg(Token::SemiColon, "after statement")?;
+/// Contextual keyword of the remove statement (`remove a;`), which is not reserved elsewhere.
+const REMOVE: &str = "remove";
+
+/// Contextual keyword of the force form of the remove statement (`remove force a;`).
+const FORCE: &str = "force";
+
+/// Check if a 'remove' identifier starts a remove statement, i.e. it is followed by another identifier.
+fn is_remove_stmt(c: &TokenCursor) -> bool {
+    matches!(c.peek_nth(1), Some(Token::Identifier(_)))
+}
+
+fn parse_remove_stmt(c: &mut TokenCursor) -> ParseResult<ast::Stmt> {
+    let c = &mut c.trace("remove_stmt");
+
+    c.advance(); // The 'remove' identifier.
+
+    let force = match (c.peek(), c.peek_nth(1)) {
+        (Some(Token::Identifier(id)), Some(Token::Identifier(_))) if id.as_str() == FORCE => {
+            c.advance();
+            true
+        }
+        _ => false,
+    };
+
+    let target = parse_expr(c)?;
+
+    parse_stmt_end(c)?;
 
     Ok(ast::Stmt {
-        kind: ast::StmtKind::Connect(Ptr::new(ast::Connect { source, sink })),
+        kind: ast::StmtKind::Remove(Ptr::new(ast::Remove { target, force })),
+        span: None,
     })
 }
+
+/// Parse a connection stage: a single endpoint, or a (non-empty) list of endpoints like `[b, c]`.
+fn parse_connect_stage(c: &mut TokenCursor) -> ParseResult<Vec<ast::Expr>> {
+    let c = &mut c.trace("connect_stage");
+
+    if !c.advance_if(Token::LeftBracket) {
+        return Ok(vec![parse_expr(c)?]);
+    }
+
+    let mut endpoints = vec![parse_expr(c)?];
+    while c.advance_if(Token::Comma) {
+        endpoints.push(parse_expr(c)?);
+    }
+
+    c.consume_msg(
+        Token::RightBracket,
+        "expected ']' after connection endpoints",
+    )?;
+
+    Ok(endpoints)
+}
@@ -1,16 +1,25 @@
 use crate::parse_error::*;
-use crate::token::{Token, Tokens};
-use std::{iter::Peekable, vec::IntoIter};
+use crate::span::Span;
+use crate::token::Token;
+use log::trace;
+use std::ops::{Deref, DerefMut};
+
+#[cfg(test)]
+use crate::symbol::Symbol;
 
 /// Cursor for tokens in a token collection.
+///
+/// The cursor borrows the tokens and only tracks a position, so peeking and consuming never copy tokens.
 #[derive(Debug)]
-pub struct TokenCursor {
-    iter: Peekable<IntoIter<Token>>,
-    curr: Option<Token>,
+pub struct TokenCursor<'a> {
+    tokens: &'a [Token],
+    spans: &'a [Span],
+    pos: usize,
+    depth: usize,
 }
 
-impl TokenCursor {
-    /// Create a new cursor from a collection of tokens.
+impl<'a> TokenCursor<'a> {
+    /// Create a new cursor for a collection of tokens.
     ///
     /// # Example
     ///
@@ -19,12 +28,22 @@ impl TokenCursor {
     /// use nexus_rs::token::Tokens;
     ///
     /// let t = Tokens::new();
-    /// let c = TokenCursor::new(t);
+    /// let c = TokenCursor::new(&t);
     /// ```
-    pub fn new(tokens: Tokens) -> Self {
-        let mut iter = tokens.into_iter().peekable();
-        let curr = iter.next();
-        TokenCursor { iter, curr }
+    pub fn new(tokens: &'a [Token]) -> Self {
+        Self::with_spans(tokens, &[])
+    }
+
+    /// Create a new cursor for a collection of tokens and their source spans.
+    ///
+    /// The spans are used to locate parse errors; they may be empty if unknown.
+    pub fn with_spans(tokens: &'a [Token], spans: &'a [Span]) -> Self {
+        TokenCursor {
+            tokens,
+            spans,
+            pos: 0,
+            depth: 0,
+        }
     }
 
     /// Take value and advance cursor.
@@ -36,13 +55,13 @@ impl TokenCursor {
     /// use nexus_rs::token::Token;
     ///
     /// let t = vec![Token::Let];
-    /// let mut c = TokenCursor::new(t);
+    /// let mut c = TokenCursor::new(&t);
     ///
-    /// assert_eq!(c.value(), Some(Token::Let));
+    /// assert_eq!(c.value(), Some(&Token::Let));
     /// assert_eq!(c.value(), None);
     /// ```
-    pub fn value(&mut self) -> Option<Token> {
-        let value = self.curr.take();
+    pub fn value(&mut self) -> Option<&'a Token> {
+        let value = self.peek();
         self.advance();
         value
     }
@@ -56,16 +75,16 @@ impl TokenCursor {
     /// use nexus_rs::token::Token;
     ///
     /// let t = vec![Token::Let, Token::Arrow];
-    /// let mut c = TokenCursor::new(t);
+    /// let mut c = TokenCursor::new(&t);
     ///
-    /// assert_eq!(c.value(), Some(Token::Let));
-    /// assert_eq!(c.peek(), Some(Token::Arrow));
+    /// assert_eq!(c.value(), Some(&Token::Let));
+    /// assert_eq!(c.peek(), Some(&Token::Arrow));
     /// ```
-    pub fn peek(&self) -> Option<Token> {
-        self.curr.clone()
+    pub fn peek(&self) -> Option<&'a Token> {
+        self.peek_nth(0)
     }
 
-    /// Peek one past the upcoming value (without advancing).
+    /// Peek the n-th value past the upcoming value (without advancing), `peek_nth(0)` being equal to `peek()`.
     ///
     /// # Example
     ///
@@ -74,14 +93,15 @@ impl TokenCursor {
     /// use nexus_rs::token::Token;
     ///
     /// let t = vec![Token::Let, Token::Arrow, Token::For];
-    /// let mut c = TokenCursor::new(t);
+    /// let mut c = TokenCursor::new(&t);
     ///
-    /// assert_eq!(c.value(), Some(Token::Let));
-    /// assert_eq!(c.peek(), Some(Token::Arrow));
-    /// assert_eq!(c.peek_next(), Some(Token::For));
+    /// assert_eq!(c.value(), Some(&Token::Let));
+    /// assert_eq!(c.peek_nth(0), Some(&Token::Arrow));
+    /// assert_eq!(c.peek_nth(1), Some(&Token::For));
+    /// assert_eq!(c.peek_nth(2), None);
     /// ```
-    pub fn peek_next(&self) -> Option<Token> {
-        self.iter.clone().next()
+    pub fn peek_nth(&self, n: usize) -> Option<&'a Token> {
+        self.tokens.get(self.pos + n)
     }
 
     /// Advance cursor.
@@ -93,14 +113,14 @@ impl TokenCursor {
     /// use nexus_rs::token::Token;
     ///
     /// let t = vec![Token::Let];
-    /// let mut c = TokenCursor::new(t);
+    /// let mut c = TokenCursor::new(&t);
     ///
-    /// assert_eq!(c.peek(), Some(Token::Let));
+    /// assert_eq!(c.peek(), Some(&Token::Let));
     /// c.advance();
     /// assert_eq!(c.peek(), None);
     /// ```
     pub fn advance(&mut self) {
-        self.curr = self.iter.next();
+        self.pos = (self.pos + 1).min(self.tokens.len());
     }
 
     /// Conditionally advance cursor (and return match result).
@@ -112,13 +132,13 @@ impl TokenCursor {
     /// use nexus_rs::token::Token;
     ///
     /// let t = vec![Token::Let, Token::Arrow];
-    /// let mut c = TokenCursor::new(t);
+    /// let mut c = TokenCursor::new(&t);
     ///
     /// assert_eq!(c.advance_if(Token::Let), true);
     /// assert_eq!(c.advance_if(Token::Let), false);
     /// ```
     pub fn advance_if(&mut self, match_token: Token) -> bool {
-        if self.peek() == Some(match_token) {
+        if self.peek() == Some(&match_token) {
             self.advance();
             true
         } else {
@@ -136,18 +156,18 @@ impl TokenCursor {
     /// use nexus_rs::token::Token;
     ///
     /// let t = vec![Token::Let, Token::Arrow, Token::Colon];
-    /// let mut c = TokenCursor::new(t);
+    /// let mut c = TokenCursor::new(&t);
     ///
     /// assert!(c.consume(Token::Let).is_ok());
     /// assert!(c.consume(Token::Arrow).is_ok());
     /// assert!(c.consume(Token::SemiColon).is_err());
     /// ```
     pub fn consume(&mut self, expected: Token) -> ParseResult<()> {
-        if self.curr == Some(expected.clone()) {
+        if self.peek() == Some(&expected) {
             self.advance();
             Ok(())
         } else {
-            Err(ParseError::new(ParseErrorKind::Expected(expected)))
+            Err(self.error(ParseErrorKind::Expected(expected)))
         }
     }
 
@@ -161,21 +181,18 @@ impl TokenCursor {
     /// use nexus_rs::token::Token;
     ///
     /// let t = vec![Token::Let, Token::Arrow, Token::Colon];
-    /// let mut c = TokenCursor::new(t);
+    /// let mut c = TokenCursor::new(&t);
     ///
     /// assert!(c.consume_msg(Token::Let, "for funzies").is_ok());
     /// assert!(c.consume_msg(Token::Arrow, "just because").is_ok());
     /// assert!(c.consume_msg(Token::SemiColon, "I like it").is_err());
     /// ```
     pub fn consume_msg(&mut self, expected: Token, reason: &str) -> ParseResult<()> {
-        if self.curr == Some(expected.clone()) {
+        if self.peek() == Some(&expected) {
             self.advance();
             Ok(())
         } else {
-            Err(ParseError::new(ParseErrorKind::ExpectedReason(
-                expected,
-                reason.to_owned(),
-            )))
+            Err(self.error(ParseErrorKind::ExpectedReason(expected, reason.to_owned())))
         }
     }
 
@@ -188,78 +205,266 @@ impl TokenCursor {
     /// use nexus_rs::token::Token;
     ///
     /// let t = vec![Token::Let];
-    /// let mut c = TokenCursor::new(t);
+    /// let mut c = TokenCursor::new(&t);
     ///
     /// assert!(!c.eos());
     /// c.advance();
     /// assert!(c.eos());
     /// ```
     pub fn eos(&self) -> bool {
-        self.curr.is_none()
+        self.pos >= self.tokens.len()
+    }
+
+    /// Save the cursor position, to be restored later with [`TokenCursor::rollback`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nexus_rs::token_cursor::TokenCursor;
+    /// use nexus_rs::token::Token;
+    ///
+    /// let t = vec![Token::Let, Token::Arrow];
+    /// let mut c = TokenCursor::new(&t);
+    ///
+    /// let checkpoint = c.checkpoint();
+    /// c.advance();
+    /// c.advance();
+    /// assert!(c.eos());
+    ///
+    /// c.rollback(checkpoint);
+    /// assert_eq!(c.peek(), Some(&Token::Let));
+    /// ```
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint(self.pos)
+    }
+
+    /// Restore the cursor position to a previously saved checkpoint.
+    pub fn rollback(&mut self, checkpoint: Checkpoint) {
+        self.pos = checkpoint.0;
+    }
+
+    /// Speculatively parse using a parse function.
+    ///
+    /// If the parse function succeeds, its result is returned and the cursor stays advanced. Otherwise, the cursor is
+    ///  rolled back to where it was before and `None` is returned, so another alternative can be tried.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nexus_rs::token_cursor::TokenCursor;
+    /// use nexus_rs::token::Token;
+    ///
+    /// let t = vec![Token::Let, Token::Arrow];
+    /// let mut c = TokenCursor::new(&t);
+    ///
+    /// assert!(c.speculate(|c| c.consume(Token::Let).and_then(|_| c.consume(Token::For))).is_none());
+    /// assert_eq!(c.peek(), Some(&Token::Let));
+    ///
+    /// assert!(c.speculate(|c| c.consume(Token::Let).and_then(|_| c.consume(Token::Arrow))).is_some());
+    /// assert!(c.eos());
+    /// ```
+    pub fn speculate<T>(&mut self, parse: impl FnOnce(&mut Self) -> ParseResult<T>) -> Option<T> {
+        let checkpoint = self.checkpoint();
+
+        match parse(self) {
+            Ok(result) => Some(result),
+            Err(_) => {
+                self.rollback(checkpoint);
+                None
+            }
+        }
+    }
+
+    /// Get the source span of the upcoming value (if known).
+    ///
+    /// At end-of-stream, this is the position directly after the last token.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nexus_rs::token_cursor::TokenCursor;
+    /// use nexus_rs::token::Token;
+    /// use nexus_rs::span::Span;
+    ///
+    /// let s = Span { file: 0, line: None, start: 0, end: 3 };
+    /// let t = vec![Token::Let];
+    /// let spans = vec![s];
+    /// let mut c = TokenCursor::with_spans(&t, &spans);
+    ///
+    /// assert_eq!(c.span(), Some(s));
+    /// c.advance();
+    /// assert_eq!(c.span(), Some(s.after()));
+    /// assert_eq!(c.prev_span(), Some(s));
+    /// ```
+    pub fn span(&self) -> Option<Span> {
+        if self.eos() {
+            self.spans.last().map(|s| s.after())
+        } else {
+            self.spans.get(self.pos).copied()
+        }
+    }
+
+    /// Get the source span of the previously consumed value (if known).
+    pub fn prev_span(&self) -> Option<Span> {
+        self.pos
+            .checked_sub(1)
+            .and_then(|p| self.spans.get(p))
+            .copied()
+    }
+
+    /// Create a parse error located at the upcoming value.
+    pub fn error(&self, kind: ParseErrorKind) -> ParseError {
+        ParseError::new(kind).with_span(self.span())
+    }
+
+    /// Create a parse error located at the previously consumed value.
+    pub fn error_prev(&self, kind: ParseErrorKind) -> ParseError {
+        ParseError::new(kind).with_span(self.prev_span())
+    }
+
+    /// Get the current grammar rule nesting depth (see [`TokenCursor::trace`]).
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Describe the cursor position for tracing: the token index, and its source location (if known).
+    fn position(&self) -> String {
+        match self.span() {
+            Some(Span {
+                line: Some(line),
+                start,
+                ..
+            }) => format!("#{} ({line}:{})", self.pos, start + 1),
+            _ => format!("#{}", self.pos),
+        }
+    }
+
+    /// Trace entry of a grammar rule, returning a guard that traces the rule exit when dropped.
+    ///
+    /// Tracing is done through the `log` facade at trace level, with target `nexus_rs::parser`. Each record shows the
+    ///  upcoming token and the cursor position (see [`TokenCursor::span`]), indented by the rule nesting depth.
+    /// The guard dereferences to the cursor, so it can be used in its place while parsing the rule.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nexus_rs::token_cursor::TokenCursor;
+    /// use nexus_rs::token::Token;
+    ///
+    /// let t = vec![Token::Let];
+    /// let mut c = TokenCursor::new(&t);
+    /// {
+    ///     let c = &mut c.trace("let");
+    ///     assert_eq!(c.value(), Some(&Token::Let));
+    /// }
+    /// assert!(c.eos());
+    /// ```
+    pub fn trace(&mut self, rule: &'static str) -> RuleTrace<'_, 'a> {
+        trace!(target: TRACE_TARGET, "{:indent$}> {rule} at {:?} {}", "", self.peek(), self.position(), indent = 2 * self.depth);
+        self.depth += 1;
+
+        RuleTrace { cursor: self, rule }
+    }
+}
+
+/// Saved cursor position (see [`TokenCursor::checkpoint`]).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Checkpoint(usize);
+
+/// Log target for parser rule tracing.
+pub const TRACE_TARGET: &str = "nexus_rs::parser";
+
+/// Guard tracing the exit of a grammar rule (see [`TokenCursor::trace`]).
+pub struct RuleTrace<'c, 'a> {
+    cursor: &'c mut TokenCursor<'a>,
+    rule: &'static str,
+}
+
+impl<'a> Deref for RuleTrace<'_, 'a> {
+    type Target = TokenCursor<'a>;
+
+    fn deref(&self) -> &Self::Target {
+        self.cursor
+    }
+}
+
+impl DerefMut for RuleTrace<'_, '_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.cursor
+    }
+}
+
+impl Drop for RuleTrace<'_, '_> {
+    fn drop(&mut self) {
+        self.cursor.depth -= 1;
+        trace!(target: TRACE_TARGET, "{:indent$}< {} at {:?} {}", "", self.rule, self.cursor.peek(), self.cursor.position(), indent = 2 * self.cursor.depth);
     }
 }
 
 #[test]
 fn new_test() {
     let t = vec![Token::Let];
-    let mut c = TokenCursor::new(t);
+    let mut c = TokenCursor::new(&t);
 
-    assert_eq!(c.value(), Some(Token::Let));
+    assert_eq!(c.value(), Some(&Token::Let));
     assert_eq!(c.value(), None);
 }
 
 #[test]
 fn value_test() {
     let t = vec![Token::Let, Token::Arrow];
-    let mut c = TokenCursor::new(t);
+    let mut c = TokenCursor::new(&t);
 
-    assert_eq!(c.value(), Some(Token::Let));
-    assert_eq!(c.value(), Some(Token::Arrow));
+    assert_eq!(c.value(), Some(&Token::Let));
+    assert_eq!(c.value(), Some(&Token::Arrow));
     assert_eq!(c.value(), None);
 }
 
 #[test]
 fn peek_test() {
     let t = vec![Token::Let, Token::Arrow];
-    let mut c = TokenCursor::new(t);
+    let mut c = TokenCursor::new(&t);
 
-    assert_eq!(c.value(), Some(Token::Let));
-    assert_eq!(c.peek(), Some(Token::Arrow));
-    assert_eq!(c.value(), Some(Token::Arrow));
+    assert_eq!(c.value(), Some(&Token::Let));
+    assert_eq!(c.peek(), Some(&Token::Arrow));
+    assert_eq!(c.value(), Some(&Token::Arrow));
     assert_eq!(c.peek(), None);
     assert_eq!(c.value(), None);
 }
 
 #[test]
-fn peek_next_test() {
+fn peek_nth_test() {
     let t = vec![Token::Let, Token::Arrow, Token::For];
-    let mut c = TokenCursor::new(t);
-
-    assert_eq!(c.value(), Some(Token::Let));
-    assert_eq!(c.peek_next(), Some(Token::For));
-    assert_eq!(c.value(), Some(Token::Arrow));
-    assert_eq!(c.peek_next(), None);
-    assert_eq!(c.value(), Some(Token::For));
+    let mut c = TokenCursor::new(&t);
+
+    assert_eq!(c.peek_nth(2), Some(&Token::For));
+    assert_eq!(c.value(), Some(&Token::Let));
+    assert_eq!(c.peek_nth(0), Some(&Token::Arrow));
+    assert_eq!(c.peek_nth(1), Some(&Token::For));
+    assert_eq!(c.value(), Some(&Token::Arrow));
+    assert_eq!(c.peek_nth(1), None);
+    assert_eq!(c.value(), Some(&Token::For));
+    assert_eq!(c.peek_nth(0), None);
 }
 
 #[test]
 fn advance_test() {
     let t = vec![
         Token::Let,
-        Token::Identifier("x".to_string()),
+        Token::Identifier(Symbol::intern("x")),
         Token::SemiColon,
     ];
-    let mut c = TokenCursor::new(t);
+    let mut c = TokenCursor::new(&t);
 
-    assert_eq!(c.peek(), Some(Token::Let));
+    assert_eq!(c.peek(), Some(&Token::Let));
 
     c.advance();
 
-    assert_eq!(c.peek(), Some(Token::Identifier("x".to_string())));
+    assert_eq!(c.peek(), Some(&Token::Identifier(Symbol::intern("x"))));
 
     c.advance();
 
-    assert_eq!(c.peek(), Some(Token::SemiColon));
+    assert_eq!(c.peek(), Some(&Token::SemiColon));
 
     c.advance();
 
@@ -269,16 +474,16 @@ fn advance_test() {
 #[test]
 fn advance_if_test() {
     let t = vec![Token::Let, Token::Arrow];
-    let mut c = TokenCursor::new(t);
+    let mut c = TokenCursor::new(&t);
 
-    assert_eq!(c.advance_if(Token::Let), true);
-    assert_eq!(c.advance_if(Token::Let), false);
+    assert!(c.advance_if(Token::Let));
+    assert!(!c.advance_if(Token::Let));
 }
 
 #[test]
 fn consume_test() {
     let t = vec![Token::Let, Token::Arrow, Token::Colon];
-    let mut c = TokenCursor::new(t);
+    let mut c = TokenCursor::new(&t);
 
     assert!(c.consume(Token::Let).is_ok());
     assert!(c.consume(Token::Arrow).is_ok());
@@ -288,7 +493,7 @@ fn consume_test() {
 #[test]
 fn consume_msg_test() {
     let t = vec![Token::Let, Token::Arrow, Token::Colon];
-    let mut c = TokenCursor::new(t);
+    let mut c = TokenCursor::new(&t);
 
     assert!(c.consume_msg(Token::Let, "expected 'let'").is_ok());
     assert!(c.consume_msg(Token::Arrow, "expected '->'").is_ok());
@@ -298,7 +503,7 @@ fn consume_msg_test() {
 #[test]
 fn eos_test() {
     let t = vec![Token::Let, Token::Arrow];
-    let mut c = TokenCursor::new(t);
+    let mut c = TokenCursor::new(&t);
 
     assert!(!c.eos());
     c.advance();
@@ -306,3 +511,111 @@ fn eos_test() {
     c.advance();
     assert!(c.eos());
 }
+
+#[test]
+fn checkpoint_test() {
+    let t = vec![Token::Let, Token::Arrow, Token::For];
+    let mut c = TokenCursor::new(&t);
+
+    let start = c.checkpoint();
+    c.advance();
+    let middle = c.checkpoint();
+    c.advance();
+    c.advance();
+    assert!(c.eos());
+
+    c.rollback(middle);
+    assert_eq!(c.peek(), Some(&Token::Arrow));
+    assert_eq!(c.prev_span(), None);
+
+    c.rollback(start);
+    assert_eq!(c.peek(), Some(&Token::Let));
+    assert_eq!(c.checkpoint(), start);
+}
+
+#[test]
+fn speculate_test() {
+    let t = vec![Token::Let, Token::Arrow, Token::For];
+    let mut c = TokenCursor::new(&t);
+
+    let result = c.speculate(|c| {
+        c.consume(Token::Let)?;
+        c.consume(Token::Arrow)?;
+        c.consume(Token::SemiColon)
+    });
+    assert!(result.is_none());
+    assert_eq!(c.peek(), Some(&Token::Let));
+
+    let result = c.speculate(|c| {
+        c.consume(Token::Let)?;
+        Ok(c.value().cloned())
+    });
+    assert_eq!(result, Some(Some(Token::Arrow)));
+    assert_eq!(c.peek(), Some(&Token::For));
+}
+
+#[test]
+fn span_test() {
+    let spans = vec![
+        Span {
+            file: 0,
+            line: Some(1),
+            start: 0,
+            end: 3,
+        },
+        Span {
+            file: 0,
+            line: Some(1),
+            start: 4,
+            end: 5,
+        },
+    ];
+    let t = vec![Token::Let, Token::Identifier(Symbol::intern("x"))];
+    let mut c = TokenCursor::with_spans(&t, &spans);
+
+    assert_eq!(c.prev_span(), None);
+    assert_eq!(c.span(), Some(spans[0]));
+    c.value();
+    assert_eq!(c.prev_span(), Some(spans[0]));
+    assert_eq!(c.span(), Some(spans[1]));
+    c.value();
+    assert_eq!(c.prev_span(), Some(spans[1]));
+    assert_eq!(c.span(), Some(spans[1].after()));
+    c.value();
+    assert_eq!(c.prev_span(), Some(spans[1]));
+
+    assert_eq!(
+        c.consume(Token::SemiColon).unwrap_err().span(),
+        Some(spans[1].after())
+    );
+}
+
+#[test]
+fn position_test() {
+    let t = vec![Token::Let, Token::SemiColon];
+    let spans = vec![
+        Span {
+            file: 0,
+            line: Some(2),
+            start: 0,
+            end: 3,
+        },
+        Span {
+            file: 0,
+            line: Some(2),
+            start: 3,
+            end: 4,
+        },
+    ];
+
+    let mut c = TokenCursor::with_spans(&t, &spans);
+    assert_eq!(c.position(), "#0 (2:1)");
+
+    c.advance();
+    assert_eq!(c.position(), "#1 (2:4)");
+
+    c.advance();
+    assert_eq!(c.position(), "#2 (2:5)");
+
+    assert_eq!(TokenCursor::new(&t).position(), "#0");
+}
@@ -0,0 +1,225 @@
+use crate::toml::{entries, parse_string, Entry};
+use std::{fs, io, path::PathBuf};
+use thiserror::Error;
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+/// Name of the REPL configuration file in the Nexus configuration directory.
+const CONFIG_FILENAME: &str = "repl.toml";
+
+/// Name of the REPL startup script in the Nexus configuration directory.
+const STARTUP_FILENAME: &str = "repl.nxs";
+
+/// Name of the REPL startup script in the home directory (used if there is none in the configuration directory).
+const RC_FILENAME: &str = ".nexusrc";
+
+/// Name of the REPL history file in the home directory.
+const HISTORY_FILENAME: &str = ".nexus_history";
+
+/// REPL configuration error representation.
+#[derive(Error, Debug)]
+pub enum ReplConfigError {
+    #[error("failed to read configuration file ({0})")]
+    Io(#[from] io::Error),
+
+    #[error("line {0}: expected 'key = value'")]
+    Syntax(usize),
+
+    #[error("line {0}: unknown key '{1}'")]
+    UnknownKey(usize, String),
+
+    #[error("line {0}: invalid value for '{1}'")]
+    InvalidValue(usize, String),
+}
+
+/// REPL configuration, read from `repl.toml` in the Nexus configuration directory.
+///
+/// The configuration file uses a (small) subset of TOML: one `key = value` pair per line, where values are
+///  quoted strings, unsigned integers or booleans. Comments start with '#'.
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::repl_config::ReplConfig;
+///
+/// let config = ReplConfig::from_toml("prompt = \"nxs> \"\nhistory_size = 50").unwrap();
+///
+/// assert_eq!(config.prompt, "nxs> ");
+/// assert_eq!(config.continuation_prompt, None);
+/// assert_eq!(config.history_size, 50);
+/// assert!(config.save_history);
+/// assert!(config.color);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReplConfig {
+    pub prompt: String,
+    pub continuation_prompt: Option<String>,
+    pub history_size: usize,
+    pub save_history: bool, // Persist the history across sessions.
+    pub color: bool,
+}
+
+impl Default for ReplConfig {
+    fn default() -> Self {
+        ReplConfig {
+            prompt: "nexus> ".to_owned(),
+            continuation_prompt: None,
+            history_size: 1000,
+            save_history: true,
+            color: true,
+        }
+    }
+}
+
+impl ReplConfig {
+    /// Get the Nexus configuration directory (e.g. `~/.config/nexus` on Linux).
+    pub fn config_dir() -> Option<PathBuf> {
+        dirs_next::config_dir().map(|d| d.join("nexus"))
+    }
+
+    /// Get the path to the REPL startup script, if it exists.
+    ///
+    /// The script in the configuration directory takes precedence over `~/.nexusrc`.
+    pub fn startup_script() -> Option<PathBuf> {
+        startup_script_in(Self::config_dir(), dirs_next::home_dir())
+    }
+
+    /// Get the path to the REPL history file (`~/.nexus_history`).
+    pub fn history_file() -> Option<PathBuf> {
+        history_file_in(dirs_next::home_dir())
+    }
+
+    /// Load the REPL configuration from the configuration directory.
+    ///
+    /// A missing configuration file is not an error, and results in the default configuration.
+    pub fn load() -> Result<Self, ReplConfigError> {
+        match Self::config_dir().map(|d| d.join(CONFIG_FILENAME)) {
+            Some(path) if path.is_file() => Self::from_toml(&fs::read_to_string(path)?),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    /// Parse a REPL configuration from a string. Keys that are not set keep their default value.
+    pub fn from_toml(input: &str) -> Result<Self, ReplConfigError> {
+        let mut config = Self::default();
+
+        for Entry { line, key, value } in entries(input).map_err(ReplConfigError::Syntax)? {
+            let invalid = || ReplConfigError::InvalidValue(line, key.clone());
+
+            match key.as_str() {
+                "prompt" => config.prompt = parse_string(value).ok_or_else(invalid)?,
+                "continuation_prompt" => {
+                    config.continuation_prompt = Some(parse_string(value).ok_or_else(invalid)?)
+                }
+                "history_size" => config.history_size = value.parse().map_err(|_| invalid())?,
+                "save_history" => config.save_history = value.parse().map_err(|_| invalid())?,
+                "color" => config.color = value.parse().map_err(|_| invalid())?,
+                _ => return Err(ReplConfigError::UnknownKey(line, key)),
+            }
+        }
+
+        Ok(config)
+    }
+}
+
+fn startup_script_in(config_dir: Option<PathBuf>, home_dir: Option<PathBuf>) -> Option<PathBuf> {
+    config_dir
+        .map(|d| d.join(STARTUP_FILENAME))
+        .into_iter()
+        .chain(home_dir.map(|d| d.join(RC_FILENAME)))
+        .find(|p| p.is_file())
+}
+
+fn history_file_in(home_dir: Option<PathBuf>) -> Option<PathBuf> {
+    home_dir.map(|d| d.join(HISTORY_FILENAME))
+}
+
+#[test]
+fn from_toml_test() {
+    let config = ReplConfig::from_toml(
+        r#"
+        # Nexus REPL configuration.
+        prompt = "nxs> " # With comment.
+        continuation_prompt = "...  "
+        history_size = 42
+        save_history = false
+        color = false
+        "#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        config,
+        ReplConfig {
+            prompt: "nxs> ".to_owned(),
+            continuation_prompt: Some("...  ".to_owned()),
+            history_size: 42,
+            save_history: false,
+            color: false,
+        }
+    );
+
+    assert_eq!(ReplConfig::from_toml("").unwrap(), ReplConfig::default());
+    assert_eq!(
+        ReplConfig::from_toml("prompt = \"# \"").unwrap().prompt,
+        "# ".to_owned()
+    );
+}
+
+#[test]
+fn history_file_test() {
+    assert_eq!(
+        history_file_in(Some(PathBuf::from("/home/nexus"))),
+        Some(PathBuf::from("/home/nexus/.nexus_history"))
+    );
+    assert_eq!(history_file_in(None), None);
+}
+
+#[test]
+fn startup_script_test() {
+    let dir = std::env::temp_dir().join(format!("nexus_repl_config_{}", std::process::id()));
+    let (config, home) = (dir.join("config"), dir.join("home"));
+    fs::create_dir_all(&config).unwrap();
+    fs::create_dir_all(&home).unwrap();
+
+    let script = || startup_script_in(Some(config.clone()), Some(home.clone()));
+
+    assert_eq!(script(), None);
+
+    fs::write(home.join(RC_FILENAME), "").unwrap();
+    assert_eq!(script(), Some(home.join(RC_FILENAME)));
+
+    fs::write(config.join(STARTUP_FILENAME), "").unwrap();
+    assert_eq!(script(), Some(config.join(STARTUP_FILENAME)));
+    assert_eq!(
+        startup_script_in(None, Some(home.clone())),
+        Some(home.join(RC_FILENAME))
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn from_toml_error_test() {
+    assert!(matches!(
+        ReplConfig::from_toml("prompt"),
+        Err(ReplConfigError::Syntax(1))
+    ));
+    assert!(matches!(
+        ReplConfig::from_toml("\nfoo = 1"),
+        Err(ReplConfigError::UnknownKey(2, _))
+    ));
+    assert!(matches!(
+        ReplConfig::from_toml("history_size = -1"),
+        Err(ReplConfigError::InvalidValue(1, _))
+    ));
+    assert!(matches!(
+        ReplConfig::from_toml("save_history = yes"),
+        Err(ReplConfigError::InvalidValue(1, _))
+    ));
+    assert!(matches!(
+        ReplConfig::from_toml("prompt = unquoted"),
+        Err(ReplConfigError::InvalidValue(1, _))
+    ));
+}
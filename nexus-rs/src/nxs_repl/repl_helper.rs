@@ -0,0 +1,269 @@
+use super::{
+    repl_highlight::highlight,
+    repl_prompt::{PromptState, ReplPrompt},
+};
+use crate::{
+    ast::{ExprKind, StmtKind, Stmts},
+    builtins::{BUILTINS, METHODS},
+    scanner,
+};
+use colored::Colorize;
+use rustyline::{
+    completion::Completer, highlight::Highlighter, hint::Hinter, validate::Validator, Context,
+    Helper,
+};
+use std::{borrow::Cow, cell::Cell};
+
+#[cfg(test)]
+use crate::{parser::Parser, scanner::Scanner, source_line::SourceLine};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+/// Line editor helper for the Nexus REPL, hooking into prompt rendering, syntax highlighting and tab-completion.
+pub struct ReplHelper {
+    prompt: ReplPrompt,
+    state: Cell<PromptState>, // State of the prompt being read, for highlighting.
+    bindings: Vec<&'static str>, // Names defined in the REPL session.
+    enums: Vec<(&'static str, Vec<&'static str>)>, // Enums defined in the REPL session, with their variants.
+}
+
+impl ReplHelper {
+    /// Create a new REPL helper for a prompt.
+    pub fn new(prompt: ReplPrompt) -> Self {
+        ReplHelper {
+            prompt,
+            state: Cell::new(PromptState::Ready),
+            bindings: Vec::new(),
+            enums: Vec::new(),
+        }
+    }
+
+    /// Get the prompt.
+    pub fn prompt(&self) -> &ReplPrompt {
+        &self.prompt
+    }
+
+    /// Get the state of the prompt being read.
+    pub fn state(&self) -> PromptState {
+        self.state.get()
+    }
+
+    /// Set the state of the prompt to read next (rendered with `ReplPrompt::render`).
+    pub fn set_state(&self, state: PromptState) {
+        self.state.set(state);
+    }
+
+    /// Update the names defined in the REPL session, offered for completion.
+    pub fn set_bindings(&mut self, session: &Stmts) {
+        self.bindings = bindings(session);
+        self.enums = enums(session);
+    }
+
+    /// Get the completions for the word before a position in a line, with the start position of that word.
+    ///
+    /// Keywords, built-in functions and session bindings are completed. After '.', the variants of an enum, or
+    ///  else the built-in functions on strings and ranges (called as methods) are completed.
+    pub fn completions(&self, line: &str, pos: usize) -> (usize, Vec<String>) {
+        let start = word_start(line, pos);
+        let prefix = &line[start..pos];
+
+        // A member follows a single '.' ('..' is the range operator).
+        let member_of = line[..start]
+            .strip_suffix('.')
+            .filter(|before| !before.ends_with('.'));
+
+        let mut candidates: Vec<String> = match member_of {
+            Some(before) => {
+                let target = &before[word_start(before, before.len())..];
+                match self.enums.iter().find(|(id, _)| *id == target) {
+                    Some((_, variants)) => variants.clone(),
+                    None => METHODS.to_vec(),
+                }
+            }
+            None if prefix.is_empty() => Vec::new(),
+            None => scanner::keywords()
+                .iter()
+                .map(|(k, _)| *k)
+                .chain(BUILTINS.iter().map(|b| b.name))
+                .chain(self.bindings.iter().copied())
+                .collect(),
+        }
+        .into_iter()
+        .filter(|name| name.starts_with(prefix))
+        .map(str::to_owned)
+        .collect();
+
+        candidates.sort_unstable();
+        candidates.dedup();
+
+        (start, candidates)
+    }
+}
+
+/// Get the start position of the word (identifier characters) before a position in a line.
+fn word_start(line: &str, pos: usize) -> usize {
+    line[..pos]
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| c.is_alphanumeric() || *c == '_')
+        .last()
+        .map_or(pos, |(i, _)| i)
+}
+
+/// Collect the names of the top-level declarations (functions, enums, constants and variables) in a REPL session.
+fn bindings(session: &Stmts) -> Vec<&'static str> {
+    session
+        .iter()
+        .filter_map(|stmt| match &stmt.kind {
+            StmtKind::FunctionDecl(f) => Some(f.id.as_str()),
+            StmtKind::EnumDecl(e) => Some(e.id.as_str()),
+            StmtKind::ConstDecl(c) => Some(c.id.as_str()),
+            StmtKind::VarDecl(v) => match &v.id.kind {
+                ExprKind::Var(var) => Some(var.id.as_str()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+/// Collect the enums (with their variants) declared at the top level of a REPL session.
+fn enums(session: &Stmts) -> Vec<(&'static str, Vec<&'static str>)> {
+    session
+        .iter()
+        .filter_map(|stmt| match &stmt.kind {
+            StmtKind::EnumDecl(e) => Some((
+                e.id.as_str(),
+                e.variants.iter().map(|v| v.as_str()).collect(),
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+impl Helper for ReplHelper {}
+
+impl Completer for ReplHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Self::Candidate>)> {
+        Ok(self.completions(line, pos))
+    }
+}
+
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
+
+impl Validator for ReplHelper {}
+
+impl Highlighter for ReplHelper {
+    fn highlight<'l>(&self, line: &'l str, pos: usize) -> Cow<'l, str> {
+        Cow::Owned(highlight(line, Some(pos)))
+    }
+
+    fn highlight_char(&self, line: &str, _pos: usize) -> bool {
+        // Refresh on every change (and cursor movement) for syntax highlighting and bracket matching.
+        !line.is_empty()
+    }
+
+    fn highlight_prompt<'b, 's: 'b, 'p: 'b>(
+        &'s self,
+        prompt: &'p str,
+        _default: bool,
+    ) -> Cow<'b, str> {
+        let marker = ReplPrompt::error_marker();
+
+        Cow::Owned(match (self.state.get(), prompt.strip_prefix(marker)) {
+            (PromptState::Ready, _) | (PromptState::Error, None) => prompt.bold().to_string(),
+            (PromptState::Continuation, _) => prompt.dimmed().to_string(),
+            (PromptState::Error, Some(rest)) => format!("{}{}", marker.red().bold(), rest.bold()),
+        })
+    }
+}
+
+#[test]
+fn completions_test() {
+    let line = SourceLine {
+        line:
+            "fn greet() {} let counter = 1; const COUNT: Number = 2; enum Mode { Idle, Run, Stop }"
+                .to_string(),
+        number: None,
+    };
+
+    let mut helper = ReplHelper::new(ReplPrompt::new("> ", None));
+    helper.set_bindings(&Parser::new(Scanner::new().scan(line).0).parse().unwrap());
+
+    let complete = |line: &str| helper.completions(line, line.len());
+
+    assert_eq!(complete("whi"), (0, vec!["while".to_owned()]));
+    assert_eq!(complete("let x = sq"), (8, vec!["sqrt".to_owned()]));
+    assert_eq!(
+        complete("print gr"),
+        (6, vec!["greet".to_owned(), "group".to_owned()])
+    );
+    assert_eq!(complete("x = cou"), (4, vec!["counter".to_owned()]));
+    assert_eq!(complete("CO"), (0, vec!["COUNT".to_owned()]));
+    assert_eq!(complete("x.cou"), (2, vec![]));
+    assert_eq!(
+        complete("name.t"),
+        (
+            5,
+            vec![
+                "to_lower".to_owned(),
+                "to_upper".to_owned(),
+                "trim".to_owned()
+            ]
+        )
+    );
+    assert_eq!(complete("\"hi\".tr"), (5, vec!["trim".to_owned()]));
+    assert_eq!(
+        complete("m = Mode."),
+        (
+            9,
+            vec!["Idle".to_owned(), "Run".to_owned(), "Stop".to_owned()]
+        )
+    );
+    assert_eq!(complete("Mode.R"), (5, vec!["Run".to_owned()]));
+    assert_eq!(complete("Mo"), (0, vec!["Mode".to_owned()]));
+    assert_eq!(complete("0..cou"), (3, vec!["counter".to_owned()]));
+    assert_eq!(complete("x = "), (4, vec![]));
+    assert_eq!(complete("ŮñĭçøƋɇ"), (0, vec![]));
+    assert_eq!(
+        helper.completions("whi x", 3),
+        (0, vec!["while".to_owned()])
+    );
+}
+
+#[test]
+fn state_test() {
+    colored::control::set_override(true);
+
+    let helper = ReplHelper::new(ReplPrompt::new("> ", None));
+    let prompt = helper.prompt().clone();
+
+    let highlight = |state| {
+        helper.set_state(state);
+        assert_eq!(helper.state(), state);
+        helper
+            .highlight_prompt(&prompt.render(state), true)
+            .into_owned()
+    };
+
+    // The continuation prompt equals the primary prompt, so the state cannot be told from the prompt text.
+    assert_eq!(highlight(PromptState::Ready), "> ".bold().to_string());
+    assert_eq!(
+        highlight(PromptState::Continuation),
+        "> ".dimmed().to_string()
+    );
+    assert_eq!(
+        highlight(PromptState::Error),
+        format!("{}{}", "✗ ".red().bold(), "> ".bold())
+    );
+}
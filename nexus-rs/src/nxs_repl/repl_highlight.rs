@@ -0,0 +1,237 @@
+use crate::{
+    scanner::{is_keyword, Scanner},
+    source_line::SourceLine,
+    token::Token,
+};
+use colored::Colorize;
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+/// Highlighting style of a piece of REPL input.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Style {
+    Plain,
+    Keyword,
+    Type,
+    Literal,
+    Comment,
+    Bracket,   // Bracket at the cursor, or its matching bracket.
+    Unmatched, // Bracket at the cursor without matching bracket.
+}
+
+/// Split a line of REPL input into styled pieces.
+///
+/// The line is scanned with the Nexus scanner, so highlighting follows the real grammar. If a (byte) cursor position
+///  is given, the bracket directly before or at the cursor is highlighted together with its matching bracket.
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::repl_highlight::{styles, Style};
+///
+/// assert_eq!(
+///     styles("let x = (1);", Some(9)),
+///     vec![
+///         (Style::Keyword, "let".to_owned()),
+///         (Style::Plain, " x = ".to_owned()),
+///         (Style::Bracket, "(".to_owned()),
+///         (Style::Literal, "1".to_owned()),
+///         (Style::Bracket, ")".to_owned()),
+///         (Style::Plain, ";".to_owned()),
+///     ]
+/// );
+/// ```
+pub fn styles(line: &str, cursor: Option<usize>) -> Vec<(Style, String)> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut styles = vec![Style::Plain; chars.len()];
+    let mut covered = vec![false; chars.len()];
+    let mut brackets = Vec::new();
+
+    let (tokens, spans, _) = Scanner::new().scan_with_spans(SourceLine {
+        line: line.to_owned(),
+        number: None,
+    });
+
+    for (token, span) in tokens.iter().zip(spans.iter()) {
+        let (start, end) = (span.start.min(chars.len()), span.end.min(chars.len()));
+        let text: String = chars[start..end].iter().collect();
+
+        let style = match token {
+            Token::Number(..) | Token::String(_) | Token::True | Token::False | Token::Nil => {
+                Style::Literal
+            }
+            Token::BoolId | Token::GroupId | Token::NodeId | Token::NumberId | Token::StringId => {
+                Style::Type
+            }
+            Token::DocComment(_) => Style::Comment,
+            _ if is_keyword(&text) => Style::Keyword,
+            _ => Style::Plain,
+        };
+
+        styles[start..end].fill(style);
+        covered[start..end].fill(true);
+
+        if let Some(open) = bracket(token) {
+            brackets.push((start, open));
+        }
+    }
+
+    highlight_comments(&chars, &covered, &mut styles);
+
+    if let Some(cursor) = cursor {
+        highlight_brackets(
+            line[..cursor.min(line.len())].chars().count(),
+            &brackets,
+            &mut styles,
+        );
+    }
+
+    let mut pieces: Vec<(Style, String)> = Vec::new();
+    for (c, style) in chars.into_iter().zip(styles) {
+        match pieces.last_mut() {
+            Some((s, text)) if *s == style => text.push(c),
+            _ => pieces.push((style, c.to_string())),
+        }
+    }
+
+    pieces
+}
+
+/// Highlight a line of REPL input with terminal colors (see [`styles`]).
+pub fn highlight(line: &str, cursor: Option<usize>) -> String {
+    styles(line, cursor)
+        .into_iter()
+        .map(|(style, text)| match style {
+            Style::Plain => text,
+            Style::Keyword => text.magenta().bold().to_string(),
+            Style::Type => text.cyan().to_string(),
+            Style::Literal => text.yellow().to_string(),
+            Style::Comment => text.dimmed().to_string(),
+            Style::Bracket => text.bold().underline().to_string(),
+            Style::Unmatched => text.red().bold().to_string(),
+        })
+        .collect()
+}
+
+/// Check if a token is a bracket, and whether it is an opening bracket.
+fn bracket(token: &Token) -> Option<bool> {
+    match token {
+        Token::LeftParen | Token::LeftBrace | Token::LeftBracket => Some(true),
+        Token::RightParen | Token::RightBrace | Token::RightBracket => Some(false),
+        _ => None,
+    }
+}
+
+/// Style the comments, i.e. the text not covered by any token starting with '//' or '/*'.
+fn highlight_comments(chars: &[char], covered: &[bool], styles: &mut [Style]) {
+    let mut i = 0;
+
+    while i + 1 < chars.len() {
+        if covered[i] || chars[i] != '/' || !matches!(chars[i + 1], '/' | '*') {
+            i += 1;
+            continue;
+        }
+
+        let end = if chars[i + 1] == '/' {
+            chars.len()
+        } else {
+            (i + 2..chars.len())
+                .find(|&j| chars[j - 1] == '*' && chars[j] == '/' && j > i + 2)
+                .map_or(chars.len(), |j| j + 1)
+        };
+
+        styles[i..end].fill(Style::Comment);
+        i = end;
+    }
+}
+
+/// Style the bracket directly before (or else at) the cursor, and its matching bracket.
+fn highlight_brackets(cursor: usize, brackets: &[(usize, bool)], styles: &mut [Style]) {
+    let Some(index) = [cursor.checked_sub(1), Some(cursor)]
+        .into_iter()
+        .flatten()
+        .find_map(|c| brackets.iter().position(|(i, _)| *i == c))
+    else {
+        return;
+    };
+
+    // Walk towards the matching bracket, keeping track of the nesting depth.
+    let (position, open) = brackets[index];
+    let mut depth = 0usize;
+    let candidates: Box<dyn Iterator<Item = &(usize, bool)>> = if open {
+        Box::new(brackets[index + 1..].iter())
+    } else {
+        Box::new(brackets[..index].iter().rev())
+    };
+
+    for (i, o) in candidates {
+        if *o == open {
+            depth += 1;
+        } else if depth > 0 {
+            depth -= 1;
+        } else {
+            styles[position] = Style::Bracket;
+            styles[*i] = Style::Bracket;
+            return;
+        }
+    }
+
+    styles[position] = Style::Unmatched;
+}
+
+#[test]
+fn styles_test() {
+    use Style::*;
+
+    let test = |line: &str, cursor: Option<usize>| {
+        styles(line, cursor)
+            .into_iter()
+            .map(|(style, text)| format!("{style:?}({text})"))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+
+    assert_eq!(
+        test(r#"const s: String = "fn"; // let"#, None),
+        "Keyword(const) Plain( s: ) Type(String) Plain( = ) Literal(\"fn\") Plain(; ) Comment(// let)"
+    );
+    assert_eq!(
+        test("x = 1 /* a */ / 2; /*/ open", None),
+        "Plain(x = ) Literal(1) Plain( ) Comment(/* a */) Plain( / ) Literal(2) Plain(; ) Comment(/*/ open)"
+    );
+    assert_eq!(
+        test("fn function() {}", None),
+        "Keyword(fn) Plain( function() {})"
+    );
+    assert_eq!(test("/// Doc.", None), "Comment(/// Doc.)");
+    assert_eq!(
+        test("print nil", None),
+        "Keyword(print) Plain( ) Literal(nil)"
+    );
+    assert_eq!(test("", Some(0)), "");
+    assert_eq!(
+        styles("ŮñĭçøƋɇ", Some(2)),
+        vec![(Plain, "ŮñĭçøƋɇ".to_owned())]
+    );
+}
+
+#[test]
+fn bracket_test() {
+    let test = |line: &str, cursor: usize| {
+        styles(line, Some(cursor))
+            .into_iter()
+            .filter(|(style, _)| matches!(style, Style::Bracket | Style::Unmatched))
+            .map(|(style, text)| format!("{style:?}({text})"))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+
+    assert_eq!(test("f((1), 2)", 1), "Bracket(() Bracket())");
+    assert_eq!(test("f((1), 2)", 9), "Bracket(() Bracket())");
+    assert_eq!(test("f((1), 2)", 3), "Bracket(() Bracket())");
+    assert_eq!(test("{ [1] ", 1), "Unmatched({)");
+    assert_eq!(test("x }", 3), "Unmatched(})");
+    assert_eq!(test("x = \"(\"", 6), "");
+    assert_eq!(test("x = 1", 2), "");
+}
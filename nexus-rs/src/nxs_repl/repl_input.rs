@@ -0,0 +1,160 @@
+use crate::scanner::Scanner;
+use crate::source_line::SourceLine;
+use crate::token::Token;
+
+/// Buffer collecting (possibly multiline) REPL input until it forms a complete unit.
+///
+/// Input is considered incomplete while it has unclosed brackets or an unterminated multiline comment.
+/// Pushed input may span multiple lines (e.g. bracketed paste input from the line editor), which are then
+///  buffered separately, so that the pasted text is handled as a single unit.
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::repl_input::InputBuffer;
+///
+/// let mut b = InputBuffer::new();
+///
+/// b.push("fn f() {".to_string());
+/// assert!(!b.is_complete());
+///
+/// b.push("}".to_string());
+/// assert!(b.is_complete());
+/// assert_eq!(b.take().len(), 2);
+/// assert!(b.is_empty());
+/// ```
+#[derive(Debug, Default)]
+pub struct InputBuffer {
+    lines: Vec<String>,
+}
+
+impl InputBuffer {
+    /// Create a new, empty input buffer.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add input, which may consist of multiple lines.
+    pub fn push(&mut self, input: String) {
+        if input.contains('\n') {
+            self.lines.extend(input.lines().map(|l| l.to_owned()));
+        } else {
+            self.lines.push(input);
+        }
+    }
+
+    /// Check if the buffer is empty.
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /// Clear the buffer.
+    pub fn clear(&mut self) {
+        self.lines.clear();
+    }
+
+    /// Check if the buffered input is complete.
+    ///
+    /// Input that fails to scan is considered complete, so the error can be reported right away.
+    pub fn is_complete(&self) -> bool {
+        let mut scanner = Scanner::new();
+        let mut depth = 0isize;
+
+        for line in &self.lines {
+            let (tokens, errors) = scanner.scan(SourceLine {
+                line: line.clone(),
+                number: None,
+            });
+
+            if !errors.is_empty() {
+                return true;
+            }
+
+            tokens.iter().for_each(|t| match t {
+                Token::LeftBrace | Token::LeftParen | Token::LeftBracket => depth += 1,
+                Token::RightBrace | Token::RightParen | Token::RightBracket => depth -= 1,
+                _ => (),
+            });
+        }
+
+        depth <= 0 && !scanner.in_comment()
+    }
+
+    /// Take the buffered input as source lines, leaving the buffer empty.
+    ///
+    /// Multiline input is numbered (starting from one), single line input is not.
+    pub fn take(&mut self) -> Vec<SourceLine> {
+        let numbered = self.lines.len() > 1;
+
+        self.lines
+            .drain(..)
+            .enumerate()
+            .map(|(n, line)| SourceLine {
+                line,
+                number: numbered.then_some(n + 1),
+            })
+            .collect()
+    }
+
+    /// Get the buffered input as a single string (e.g. for history storage).
+    pub fn text(&self) -> String {
+        self.lines.join("\n")
+    }
+}
+
+#[test]
+fn is_complete_test() {
+    let test = |lines: &[&str], expected: bool| {
+        let mut b = InputBuffer::new();
+        lines.iter().for_each(|l| b.push(l.to_string()));
+        assert_eq!(b.is_complete(), expected, "{lines:?}");
+    };
+
+    test(&[], true);
+    test(&["let x = 1;"], true);
+    test(&["fn f() {"], false);
+    test(&["fn f() {", "  print 1;"], false);
+    test(&["fn f() {", "  print 1;", "}"], true);
+    test(&["let x = (1 +"], false);
+    test(&["let x = (1 +", "2);"], true);
+    test(&["/* multiline"], false);
+    test(&["/* multiline", "comment */"], true);
+    test(&["let x = \"unterminated {"], true);
+    test(&["}"], true);
+}
+
+#[test]
+fn take_test() {
+    let mut b = InputBuffer::new();
+
+    b.push("x;".to_string());
+    let lines = b.take();
+    assert_eq!(lines.len(), 1);
+    assert_eq!(lines[0].number, None);
+
+    b.push("{".to_string());
+    b.push("}".to_string());
+    assert_eq!(b.text(), "{\n}");
+    let lines = b.take();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[1].number, Some(2));
+    assert!(b.is_empty());
+}
+
+#[test]
+fn push_multiline_test() {
+    let mut b = InputBuffer::new();
+
+    b.push("fn f() {\r\n  // Comment {\n  print 1;".to_string());
+    assert!(!b.is_complete());
+
+    b.push("}\nf();\n".to_string());
+    assert!(b.is_complete());
+
+    let lines = b.take();
+    assert_eq!(
+        lines.iter().map(|l| l.line.as_str()).collect::<Vec<_>>(),
+        vec!["fn f() {", "  // Comment {", "  print 1;", "}", "f();"]
+    );
+    assert_eq!(lines[4].number, Some(5));
+}
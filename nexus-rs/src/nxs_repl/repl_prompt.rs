@@ -0,0 +1,92 @@
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+/// Marker shown in front of the prompt after an input failed.
+const ERROR_MARKER: &str = "✗ ";
+
+/// REPL prompt state.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PromptState {
+    Ready,        // Waiting for new input.
+    Continuation, // Waiting for the continuation of incomplete input.
+    Error,        // Waiting for new input, after the previous input failed.
+}
+
+/// State-aware REPL prompt.
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::repl_prompt::{PromptState, ReplPrompt};
+///
+/// let p = ReplPrompt::new("nexus> ", None);
+///
+/// assert_eq!(p.render(PromptState::Ready), "nexus> ");
+/// assert_eq!(p.render(PromptState::Continuation), ".....> ");
+/// assert_eq!(p.render(PromptState::Error), "✗ nexus> ");
+/// ```
+#[derive(Clone, Debug)]
+pub struct ReplPrompt {
+    primary: String,
+    continuation: String,
+}
+
+impl ReplPrompt {
+    /// Create a new prompt.
+    ///
+    /// If no continuation prompt is given, it is derived from the primary prompt by replacing all characters up
+    ///  to the trailing '>' (or whitespace) with dots, so that continuation lines align with the first line.
+    pub fn new(primary: &str, continuation: Option<&str>) -> Self {
+        ReplPrompt {
+            primary: primary.to_owned(),
+            continuation: continuation
+                .map(|c| c.to_owned())
+                .unwrap_or_else(|| derive_continuation(primary)),
+        }
+    }
+
+    /// Render the prompt text for a specific state (without any styling).
+    pub fn render(&self, state: PromptState) -> String {
+        match state {
+            PromptState::Ready => self.primary.clone(),
+            PromptState::Continuation => self.continuation.clone(),
+            PromptState::Error => format!("{ERROR_MARKER}{}", self.primary),
+        }
+    }
+
+    /// Get the error marker part of a rendered prompt text.
+    pub fn error_marker() -> &'static str {
+        ERROR_MARKER
+    }
+}
+
+fn derive_continuation(primary: &str) -> String {
+    let tail = primary
+        .trim_end_matches(|c: char| c.is_whitespace() || c == '>')
+        .chars()
+        .count();
+
+    ".".repeat(tail) + &primary.chars().skip(tail).collect::<String>()
+}
+
+#[test]
+fn render_test() {
+    let p = ReplPrompt::new("> ", None);
+
+    assert_eq!(p.render(PromptState::Ready), "> ");
+    assert_eq!(p.render(PromptState::Continuation), "> ");
+    assert_eq!(p.render(PromptState::Error), "✗ > ");
+
+    let p = ReplPrompt::new("nxs>> ", Some("... "));
+
+    assert_eq!(p.render(PromptState::Ready), "nxs>> ");
+    assert_eq!(p.render(PromptState::Continuation), "... ");
+}
+
+#[test]
+fn derive_continuation_test() {
+    assert_eq!(derive_continuation("nexus> "), ".....> ");
+    assert_eq!(derive_continuation("ŴêïRD>"), ".....>");
+    assert_eq!(derive_continuation("$ "), ". ");
+    assert_eq!(derive_continuation(""), "");
+}
@@ -1,13 +1,19 @@
+use super::source_line::next_column;
 use std::{iter::Peekable, str::Chars};
 
 #[cfg(test)]
 use pretty_assertions::assert_eq;
 
 /// Cursor for characters in a string, providing direct value access and advanced peeking.
+///
+/// Besides the character index, the cursor tracks the byte offset and display column (see [`next_column`]) of the
+///  current character.
 #[derive(Debug)]
 pub struct Cursor<'a> {
     chars: Peekable<Chars<'a>>,
     index: usize,
+    byte_index: usize,
+    column: usize,
     value: Option<char>,
 }
 
@@ -35,6 +41,8 @@ impl<'a> Cursor<'a> {
         Cursor {
             chars: chars.peekable(),
             index: 0,
+            byte_index: 0,
+            column: 0,
             value,
         }
     }
@@ -78,8 +86,8 @@ impl<'a> Cursor<'a> {
     /// assert_eq!(c.value(), None);
     /// ```
     pub fn advance(&mut self) {
-        self.value = self.chars.next();
-        self.index_inc();
+        let previous = std::mem::replace(&mut self.value, self.chars.next());
+        self.index_inc(previous);
     }
 
     /// Advance the cursor by N positions, consuming the value at each increment.
@@ -102,20 +110,11 @@ impl<'a> Cursor<'a> {
     /// assert_eq!(c.value(), None);
     /// ```
     pub fn advance_by(&mut self, n: usize) {
-        match n {
-            0 => (),
-            _ => {
-                for _ in 0..(n - 1) {
-                    self.chars.next();
-                    self.index_inc();
-
-                    if self.chars.peek().is_none() {
-                        break;
-                    }
-                }
+        for _ in 0..n {
+            self.advance();
 
-                self.value = self.chars.next();
-                self.index_inc();
+            if self.eol() {
+                break;
             }
         }
     }
@@ -185,7 +184,7 @@ impl<'a> Cursor<'a> {
     /// assert_eq!(c.peek_while(|c| c.is_alphanumeric()), Some("abc123".to_string()));
     /// ```
     pub fn peek_while(&self, mut predicate: impl FnMut(char) -> bool) -> Option<String> {
-        let mut result = self.value.unwrap().to_string();
+        let mut result = self.value?.to_string();
 
         if !self.eol() {
             result += &self
@@ -220,6 +219,42 @@ impl<'a> Cursor<'a> {
         self.index
     }
 
+    /// Get the current byte offset of the cursor.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nexus_rs::cursor::Cursor;
+    ///
+    /// let s = "ŮñĭçøƋɇ".to_string();
+    /// let mut c = Cursor::new(&s);
+    ///
+    /// c.advance_by(3);
+    /// assert_eq!(c.index(), 3);
+    /// assert_eq!(c.byte_index(), 6);
+    /// ```
+    pub fn byte_index(&self) -> usize {
+        self.byte_index
+    }
+
+    /// Get the current display column of the cursor (see [`next_column`]).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nexus_rs::cursor::Cursor;
+    ///
+    /// let s = "\t漢字x".to_string();
+    /// let mut c = Cursor::new(&s);
+    ///
+    /// c.advance_by(3);
+    /// assert_eq!(c.value(), Some('x'));
+    /// assert_eq!(c.column(), 8);
+    /// ```
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
     /// Check if the cursor is at end-of-line (EOL).
     ///
     /// # Example
@@ -238,10 +273,12 @@ impl<'a> Cursor<'a> {
         self.value.is_none()
     }
 
-    /// Increment index.
-    fn index_inc(&mut self) {
-        if !self.eol() {
+    /// Increment indices, given the previous value.
+    fn index_inc(&mut self, previous: Option<char>) {
+        if let (false, Some(previous)) = (self.eol(), previous) {
             self.index += 1;
+            self.byte_index += previous.len_utf8();
+            self.column = next_column(self.column, previous);
         }
     }
 }
@@ -311,6 +348,33 @@ fn advance_by_test() {
     assert!(c.eol());
 }
 
+#[test]
+fn positions_test() {
+    let line = "a\tŮ漢 z".to_string();
+
+    let mut c = Cursor::new(&line);
+    let mut positions = vec![(c.index(), c.byte_index(), c.column())];
+
+    while !c.eol() {
+        c.advance();
+        positions.push((c.index(), c.byte_index(), c.column()));
+    }
+
+    assert_eq!(
+        positions,
+        [
+            (0, 0, 0),
+            (1, 1, 1),
+            (2, 2, 4),
+            (3, 4, 5),
+            (4, 7, 7),
+            (5, 8, 8),
+            (5, 8, 8)
+        ]
+    );
+    assert_eq!(&line[c.byte_index()..], "z");
+}
+
 #[test]
 fn peek_test() {
     let line = "abcdefg".to_string();
@@ -372,6 +436,9 @@ fn peek_while_test() {
 
     assert!(!c.eol());
     assert_eq!(c.value(), Some('d'));
+
+    let empty = String::new();
+    assert_eq!(Cursor::new(&empty).peek_while(is_word_char), None);
 }
 
 #[test]
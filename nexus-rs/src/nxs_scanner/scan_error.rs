@@ -1,5 +1,6 @@
 use super::cursor::Cursor;
 use super::source_line::SourceLine;
+use crate::span::Span;
 use std::fmt;
 use thiserror::Error;
 
@@ -22,29 +23,33 @@ pub enum ScanErrorKind {
     UnterminatedString,
 }
 
+impl ScanErrorKind {
+    /// Get the diagnostic code of the error (see [`diagnostics`](crate::diagnostics)).
+    pub fn code(&self) -> &'static str {
+        match self {
+            ScanErrorKind::MalformedString => "NXS0001",
+            ScanErrorKind::NumberParseError(_) => "NXS0002",
+            ScanErrorKind::WordParseError => "NXS0003",
+            ScanErrorKind::UnexpectedCharacter => "NXS0004",
+            ScanErrorKind::UnterminatedString => "NXS0005",
+        }
+    }
+}
+
 #[derive(Error, Debug)]
 pub struct ScanError {
     line: SourceLine,
     kind: ScanErrorKind,
     char_index: usize,
+    column: usize,
 }
 
 impl fmt::Display for ScanError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let line_number_str = self.line.number.map_or("".to_owned(), |n| n.to_string());
-        let prefix_fill = " ".repeat(line_number_str.len() + 2); // +2 for spaces.
-        let char_fill = " ".repeat(self.char_index);
-        f.write_fmt(format_args!(
-            "{}|\n {} | {}\n{}| {}{}\n{}| error: {}\n{}|",
-            prefix_fill,
-            line_number_str,
-            self.line.line,
-            prefix_fill,
-            char_fill,
-            "^",
-            prefix_fill,
-            self.kind,
-            prefix_fill,
+        f.write_str(&self.line.render_snippet(
+            self.char_index,
+            self.char_index + 1,
+            &format!("error[{}]: {}", self.kind.code(), self.kind),
         ))
     }
 }
@@ -55,6 +60,32 @@ impl ScanError {
             line,
             kind,
             char_index: cursor.index(),
+            column: cursor.column(),
         }
     }
+
+    /// Get the kind of error.
+    pub fn kind(&self) -> &ScanErrorKind {
+        &self.kind
+    }
+
+    /// Get the line number of the error (if any).
+    pub fn line(&self) -> Option<usize> {
+        self.line.number
+    }
+
+    /// Get the source span of the error (a single character).
+    pub fn span(&self) -> Span {
+        Span {
+            file: 0,
+            line: self.line.number,
+            start: self.char_index,
+            end: self.char_index + 1,
+        }
+    }
+
+    /// Get the display column of the error (zero-based, see [`next_column`](super::source_line::next_column)).
+    pub fn column(&self) -> usize {
+        self.column
+    }
 }
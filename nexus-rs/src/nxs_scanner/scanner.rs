@@ -1,9 +1,9 @@
 use super::cursor::Cursor;
 use super::scan_error::{ScanError, ScanErrorKind};
 use super::source_line::SourceLine;
+use crate::span::{Span, Spans};
+use crate::symbol::Symbol;
 use crate::token::{Token, Tokens};
-use lazy_static::lazy_static;
-use std::collections::HashMap;
 
 #[cfg(test)]
 use pretty_assertions::assert_eq;
@@ -13,41 +13,144 @@ use std::f64::consts::PI;
 
 /// Scanner for Nexus.
 ///
+/// Tokens are scanned by maximal munch: the longest character sequence forming a valid token is always taken,
+///  regardless of what follows. E.g. `..=` is always an inclusive range token (never `..` followed by `=`), and
+///  `form` is always an identifier (never the keyword `for` followed by `m`). Keywords are only recognized for
+///  complete words.
+///
 /// **Note**: at this moment, the scanner is *not* suitable for out-of-order parallel operation.
 /// Due to support for multiline comments in Nexus, line scans are non-commutative.
 pub struct Scanner {
-    comment_: bool, //<! Indicates multiline comment state.
+    comment_: bool,        //<! Indicates multiline comment state.
+    options_: ScanOptions, //<! Optional token output.
+}
+
+/// Optional scanner output, next to the tokens required for parsing.
+///
+/// These are useful for tooling that needs to know about the source code layout, e.g. a formatter that preserves
+///  blank lines.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ScanOptions {
+    pub newlines: bool, // Emit a `Token::Newline` at the end of each line (outside multiline comments).
+    pub eof: bool,      // Emit a final `Token::Eof` when finishing (see `Scanner::finish`).
+    pub trivia: bool,   // Emit `Token::Whitespace` for runs of whitespace.
 }
 
 impl Scanner {
     /// Construct a new scanner.
     pub fn new() -> Self {
-        Scanner { comment_: false }
+        Self::with_options(ScanOptions::default())
+    }
+
+    /// Construct a new scanner with optional token output.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nexus_rs::{scanner::*, source_line::SourceLine, token::Token};
+    ///
+    /// let mut s = Scanner::with_options(ScanOptions { newlines: true, eof: true, trivia: false });
+    ///
+    /// let (mut tokens, _) = s.scan(SourceLine { line: "x;".to_string(), number: Some(1) });
+    /// tokens.append(&mut s.finish());
+    ///
+    /// assert_eq!(tokens[2..], [Token::Newline, Token::Eof]);
+    /// ```
+    pub fn with_options(options: ScanOptions) -> Self {
+        Scanner {
+            comment_: false,
+            options_: options,
+        }
+    }
+
+    /// Finish scanning, returning the final tokens (only `Token::Eof`, if enabled).
+    pub fn finish(&mut self) -> Tokens {
+        if self.options_.eof {
+            vec![Token::Eof]
+        } else {
+            Tokens::new()
+        }
+    }
+
+    /// Check if the scanner is inside a multiline comment.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nexus_rs::{scanner::Scanner, source_line::SourceLine};
+    ///
+    /// let mut s = Scanner::new();
+    /// s.scan(SourceLine { line: "/* comment".to_string(), number: None });
+    /// assert!(s.in_comment());
+    /// ```
+    pub fn in_comment(&self) -> bool {
+        self.comment_
+    }
+
+    /// Scan a line of text and output the tokens found, together with the scanning errors.
+    ///
+    /// Scanning continues after an error: the offending character (or literal) is skipped, so all errors on a line
+    ///  are reported and the remaining tokens are still produced.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nexus_rs::{scanner::Scanner, source_line::SourceLine, symbol::Symbol, token::Token};
+    ///
+    /// let mut s = Scanner::new();
+    /// let (tokens, errors) = s.scan(SourceLine { line: "let x;".to_string(), number: None });
+    ///
+    /// assert!(errors.is_empty());
+    /// assert_eq!(tokens,
+    ///            vec![Token::Let,
+    ///                 Token::Identifier(Symbol::intern("x")),
+    ///                 Token::SemiColon]);
+    ///
+    /// let (tokens, errors) = s.scan(SourceLine { line: "let # x $;".to_string(), number: None });
+    ///
+    /// assert_eq!(errors.len(), 2);
+    /// assert_eq!(tokens.len(), 3);
+    /// ```
+    pub fn scan(&mut self, sline: SourceLine) -> (Tokens, Vec<ScanError>) {
+        let (tokens, _, errors) = self.scan_with_spans(sline);
+        (tokens, errors)
     }
 
-    /// Scan a line of text and output the tokens found, or a scanning error.
+    /// Scan a line of text and output the tokens found with their source spans, together with the scanning errors.
     ///
     /// # Example
     ///
     /// ```
-    /// use nexus_rs::{scanner::Scanner, source_line::SourceLine, token::Token};
+    /// use nexus_rs::{scanner::Scanner, source_line::SourceLine, span::Span};
     ///
     /// let mut s = Scanner::new();
-    /// if let Ok(tokens) = s.scan(SourceLine { line: "let x;".to_string(), number: None }) {
-    ///     assert_eq!(tokens,
-    ///                vec![Token::Let,
-    ///                     Token::Identifier("x".to_string()),
-    ///                     Token::SemiColon]);
-    /// }
+    /// let (tokens, spans, errors) = s.scan_with_spans(SourceLine { line: "let xy;".to_string(), number: Some(1) });
+    ///
+    /// assert!(errors.is_empty());
+    /// assert_eq!(tokens.len(), spans.len());
+    /// assert_eq!(spans[1], Span { file: 0, line: Some(1), start: 4, end: 6 });
     /// ```
-    pub fn scan(&mut self, sline: SourceLine) -> Result<Tokens, ScanError> {
+    pub fn scan_with_spans(&mut self, sline: SourceLine) -> (Tokens, Spans, Vec<ScanError>) {
         let mut tokens = Vec::new();
+        let mut spans = Spans::new();
+        let mut errors = Vec::new();
 
         let mut cursor = Cursor::new(&sline.line);
         while let Some(c) = cursor.value() {
+            let start = cursor.index();
+            let count = tokens.len();
+
             if !self.comment_ {
                 match c {
-                    ' ' | '\n' | '\r' | '\t' => (),
+                    c if is_whitespace(c) => {
+                        if self.options_.trivia {
+                            let ws = cursor
+                                .peek_while(is_whitespace)
+                                .expect("cursor at whitespace");
+                            cursor.advance_by(ws.chars().count() - 1);
+                            tokens.push(Token::Whitespace(ws));
+                        }
+                    }
                     '(' => tokens.push(Token::LeftParen),
                     ')' => tokens.push(Token::RightParen),
                     '{' => tokens.push(Token::LeftBrace),
@@ -57,19 +160,34 @@ impl Scanner {
                     ':' => tokens.push(Token::Colon),
                     ';' => tokens.push(Token::SemiColon),
                     '+' => tokens.push(Token::Plus),
-                    '-' => match cursor.peek() {
-                        Some('>') => {
+                    '-' => match (cursor.peek(), cursor.peek_nth(2)) {
+                        (Some('>'), _) => {
                             cursor.advance();
                             tokens.push(Token::Arrow);
                         }
+                        (Some('/'), Some('>')) => {
+                            cursor.advance_by(2);
+                            tokens.push(Token::NotArrow);
+                        }
                         _ => tokens.push(Token::Minus),
                     },
-                    '*' => tokens.push(Token::Star),
+                    '*' => match cursor.peek() {
+                        Some('*') => {
+                            cursor.advance();
+                            tokens.push(Token::StarStar);
+                        }
+                        _ => tokens.push(Token::Star),
+                    },
                     '\\' => tokens.push(Token::BackSlash),
                     '%' => tokens.push(Token::Percent),
                     ',' => tokens.push(Token::Comma),
-                    '.' => match cursor.peek() {
-                        Some('.') => {
+                    '?' => tokens.push(Token::Question),
+                    '.' => match (cursor.peek(), cursor.peek_nth(2)) {
+                        (Some('.'), Some('=')) => {
+                            cursor.advance_by(2);
+                            tokens.push(Token::RangeInclusive);
+                        }
+                        (Some('.'), _) => {
                             cursor.advance();
                             tokens.push(Token::Range);
                         }
@@ -83,7 +201,13 @@ impl Scanner {
                         }
                         _ => tokens.push(Token::Is),
                     },
-                    '|' => tokens.push(Token::Pipe),
+                    '|' => match cursor.peek() {
+                        Some('>') => {
+                            cursor.advance();
+                            tokens.push(Token::Pipeline);
+                        }
+                        _ => tokens.push(Token::Pipe),
+                    },
                     '>' => match cursor.peek() {
                         Some('=') => {
                             cursor.advance();
@@ -114,6 +238,23 @@ impl Scanner {
                         }
                     }
                     '/' => match cursor.peek() {
+                        // A doc comment starts with exactly three slashes ('////' is a regular comment).
+                        Some('/')
+                            if cursor.peek_nth(2) == Some('/')
+                                && cursor.peek_nth(3) != Some('/') =>
+                        {
+                            let text: String = sline.line.chars().skip(start + 3).collect();
+                            let text = text.strip_prefix(' ').unwrap_or(&text).trim_end();
+
+                            tokens.push(Token::DocComment(text.into()));
+                            spans.push(Span {
+                                file: 0,
+                                line: sline.number,
+                                start,
+                                end: sline.line.chars().count(),
+                            });
+                            break;
+                        }
                         Some('/') => break,
                         Some('*') => {
                             cursor.advance();
@@ -121,37 +262,65 @@ impl Scanner {
                         }
                         _ => tokens.push(Token::Slash),
                     },
-                    '"' => {
-                        tokens.push(Token::String(
-                            parse_string(&mut cursor)
-                                .map_err(|e| ScanError::new(sline.clone(), e, &cursor))?,
-                        ));
+                    // A shebang line is skipped as a comment (see `SourceLine::is_shebang`).
+                    '#' if start == 0 && sline.is_shebang() => break,
+                    '"' => match parse_string(&mut cursor) {
+                        Ok(string) => tokens.push(Token::String(string)),
+                        Err(e) => errors.push(ScanError::new(sline.clone(), e, &cursor)),
+                    },
+                    '0'..='9' => match parse_number(&mut cursor) {
+                        Ok(token) => tokens.push(token),
+                        Err(e) => errors.push(ScanError::new(sline.clone(), e, &cursor)),
+                    },
+                    // A raw identifier ('r#' prefix) can be any word, including a keyword.
+                    'r' if cursor.peek() == Some('#')
+                        && cursor.peek_nth(2).is_some_and(char::is_alphabetic) =>
+                    {
+                        match parse_raw_word(&mut cursor) {
+                            Ok(token) => tokens.push(token),
+                            Err(e) => errors.push(ScanError::new(sline.clone(), e, &cursor)),
+                        }
                     }
-                    '0'..='9' => tokens.push(Token::Number(
-                        parse_number(&mut cursor)
-                            .map_err(|e| ScanError::new(sline.clone(), e, &cursor))?,
+                    x if x.is_alphabetic() => match parse_word(&mut cursor) {
+                        Ok(token) => tokens.push(token),
+                        Err(e) => errors.push(ScanError::new(sline.clone(), e, &cursor)),
+                    },
+                    _ => errors.push(ScanError::new(
+                        sline.clone(),
+                        ScanErrorKind::UnexpectedCharacter,
+                        &cursor,
                     )),
-                    x if x.is_alphabetic() => tokens.push(
-                        parse_word(&mut cursor)
-                            .map_err(|e| ScanError::new(sline.clone(), e, &cursor))?,
-                    ),
-                    _ => {
-                        return Err(ScanError::new(
-                            sline.clone(),
-                            ScanErrorKind::UnexpectedCharacter,
-                            &cursor,
-                        ))
-                    }
                 }
             } else if (c == '*') && (cursor.peek() == Some('/')) {
                 cursor.advance();
                 self.comment_ = false;
             }
 
+            if tokens.len() > count {
+                spans.push(Span {
+                    file: 0,
+                    line: sline.number,
+                    start,
+                    end: cursor.index() + 1,
+                });
+            }
+
             cursor.advance();
         }
 
-        Ok(tokens)
+        if self.options_.newlines && !self.comment_ {
+            let end = sline.line.chars().count();
+
+            tokens.push(Token::Newline);
+            spans.push(Span {
+                file: 0,
+                line: sline.number,
+                start: end,
+                end: end + 1,
+            });
+        }
+
+        (tokens, spans, errors)
     }
 }
 
@@ -161,6 +330,10 @@ impl Default for Scanner {
     }
 }
 
+fn is_whitespace(c: char) -> bool {
+    matches!(c, ' ' | '\n' | '\r' | '\t')
+}
+
 fn parse_string(cursor: &mut Cursor) -> Result<String, ScanErrorKind> {
     let mut result = String::new();
     let mut escaped = false;
@@ -233,8 +406,11 @@ fn parse_string_test() {
     test(r#"\"quotes at the sides\""#);
 }
 
-fn parse_number(cursor: &mut Cursor) -> Result<f64, ScanErrorKind> {
-    let mut result = cursor.value().unwrap().to_string(); // Loads the first digit.
+fn parse_number(cursor: &mut Cursor) -> Result<Token, ScanErrorKind> {
+    let mut result = cursor
+        .value()
+        .ok_or_else(|| ScanErrorKind::NumberParseError(String::new()))?
+        .to_string(); // Loads the first digit.
 
     let mut found_dot = false;
     while let Some(c) = cursor.peek() {
@@ -257,16 +433,23 @@ fn parse_number(cursor: &mut Cursor) -> Result<f64, ScanErrorKind> {
         cursor.advance();
     }
 
-    result
-        .parse::<f64>()
-        .map_err(|e| ScanErrorKind::NumberParseError(e.to_string()))
+    match result.parse::<f64>() {
+        Ok(value) => Ok(Token::Number(value, result)),
+        Err(e) => Err(ScanErrorKind::NumberParseError(e.to_string())),
+    }
 }
 
 #[test]
 fn parse_number_test() {
     let test = |input: &str, expected: f64| {
         let mut cursor = Cursor::new(input);
-        assert!(parse_number(&mut cursor).unwrap() - expected < 0.001);
+        match parse_number(&mut cursor).unwrap() {
+            Token::Number(value, text) => {
+                assert!(value - expected < 0.001);
+                assert_eq!(text, input);
+            }
+            token => panic!("{token:?}"),
+        }
     };
 
     test("0", 0.0);
@@ -280,68 +463,144 @@ fn parse_number_test() {
     test("123.456", 123.456);
     test("123.456", 123.456);
     test("3.1415926535", PI);
+
+    // The literal text is kept as written, also beyond the precision of the value.
+    test("1.50", 1.5);
+    test("12345678901234567890", 1.2345678901234567e19);
 }
 
-type TokenMap = HashMap<&'static str, Token>;
+/// All reserved words with their tokens, in (byte-wise) alphabetical order: the single source of keywords for the
+///  scanner, the parser and tooling.
+const KEYWORDS: &[(&str, Token)] = &[
+    ("Group", Token::GroupId),
+    ("Node", Token::NodeId),
+    ("Number", Token::NumberId),
+    ("String", Token::StringId),
+    ("bool", Token::BoolId),
+    ("const", Token::Const),
+    ("defer", Token::Defer),
+    ("else", Token::Else),
+    ("false", Token::False),
+    ("fn", Token::Function),
+    ("for", Token::For),
+    ("group", Token::Group),
+    ("if", Token::If),
+    ("in", Token::In),
+    ("let", Token::Let),
+    ("loop", Token::Loop),
+    ("mut", Token::Mut),
+    ("nil", Token::Nil),
+    ("node", Token::Node),
+    ("print", Token::Print),
+    ("println", Token::Println),
+    ("return", Token::Return),
+    ("time", Token::Time),
+    ("true", Token::True),
+    ("try", Token::Try),
+    ("use", Token::Use),
+    ("while", Token::While),
+];
+
+/// Get all reserved words with their tokens, in (byte-wise) alphabetical order.
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::{keywords, token::Token};
+///
+/// assert!(keywords().contains(&("while", Token::While)));
+/// assert!(keywords().windows(2).all(|w| w[0].0 < w[1].0));
+/// ```
+pub fn keywords() -> &'static [(&'static str, Token)] {
+    KEYWORDS
+}
 
-/// Initialize a token map using 'key => value' notation.
-macro_rules! token_map {
-    ($($key:expr => $value:expr),+ $(,)?) => {
-        {
-            let mut map: TokenMap = HashMap::new();
-            $(map.insert($key, $value);)+
-            map
-        }
-    }
+/// Get the token of a reserved word (if it is one).
+fn keyword_token(word: &str) -> Option<&'static Token> {
+    KEYWORDS
+        .binary_search_by_key(&word, |(k, _)| k)
+        .ok()
+        .map(|i| &KEYWORDS[i].1)
 }
 
-fn parse_word(cursor: &mut Cursor) -> Result<Token, ScanErrorKind> {
-    lazy_static! {
-        static ref KEYWORDS: TokenMap = token_map! {
-            "Group"  => Token::GroupId,
-            "Node"   => Token::NodeId,
-            "Number" => Token::NumberId,
-            "String" => Token::StringId,
-            "bool"   => Token::BoolId,
-            "const"  => Token::Const,
-            "else"   => Token::Else,
-            "false"  => Token::False,
-            "fn"     => Token::Function,
-            "for"    => Token::For,
-            "group"  => Token::Group,
-            "if"     => Token::If,
-            "in"     => Token::In,
-            "let"    => Token::Let,
-            "mut"    => Token::Mut,
-            "node"   => Token::Node,
-            "print"  => Token::Print,
-            "return" => Token::Return,
-            "true"   => Token::True,
-            "use"    => Token::Use,
-            "while"  => Token::While,
-        };
+/// Check if a word is a reserved keyword (and thus cannot be used as identifier).
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::scanner::is_keyword;
+///
+/// assert!(is_keyword("fn"));
+/// assert!(!is_keyword("fun"));
+/// ```
+pub fn is_keyword(word: &str) -> bool {
+    keyword_token(word).is_some()
+}
+
+/// Get the keyword of a token (if it is a keyword token).
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::{scanner::keyword, token::Token};
+///
+/// assert_eq!(keyword(&Token::While), Some("while"));
+/// assert_eq!(keyword(&Token::Plus), None);
+/// ```
+pub fn keyword(token: &Token) -> Option<&'static str> {
+    KEYWORDS.iter().find(|(_, t)| t == token).map(|(k, _)| *k)
+}
+
+/// Format a name as it is written in source code: as raw identifier (e.g. `r#group`) if it is a keyword.
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::scanner::source_identifier;
+///
+/// assert_eq!(source_identifier("group"), "r#group");
+/// assert_eq!(source_identifier("groups"), "groups");
+/// ```
+pub fn source_identifier(name: &str) -> String {
+    match is_keyword(name) {
+        true => format!("r#{name}"),
+        false => name.to_owned(),
     }
+}
 
+fn scan_word(cursor: &mut Cursor) -> Result<String, ScanErrorKind> {
     match cursor.peek_while(|c| c.is_alphanumeric() || c == '_') {
         Some(word) => {
             cursor.advance_by(word.chars().count() - 1);
-            if let Some(token) = KEYWORDS.get(&word.as_str()) {
-                Ok(token.clone())
-            } else {
-                Ok(Token::Identifier(word))
-            }
+            Ok(word)
         }
         _ => Err(ScanErrorKind::WordParseError),
     }
 }
 
+fn parse_word(cursor: &mut Cursor) -> Result<Token, ScanErrorKind> {
+    let word = scan_word(cursor)?;
+
+    match keyword_token(&word) {
+        Some(token) => Ok(token.clone()),
+        None => Ok(Token::Identifier(Symbol::intern(&word))),
+    }
+}
+
+/// Parse a raw identifier (`r#` followed by a word), which is never a keyword.
+fn parse_raw_word(cursor: &mut Cursor) -> Result<Token, ScanErrorKind> {
+    cursor.advance_by(2);
+
+    scan_word(cursor).map(|word| Token::Identifier(Symbol::intern(&word)))
+}
+
 #[test]
 fn parse_word_identifier_test() {
     let test = |word: &str| {
         let mut cursor = Cursor::new(word);
         assert_eq!(
             parse_word(&mut cursor).unwrap(),
-            Token::Identifier(word.to_string())
+            Token::Identifier(Symbol::intern(word))
         );
     };
 
@@ -372,8 +631,267 @@ fn parse_word_keyword_test() {
     test("let", Token::Let);
     test("node", Token::Node);
     test("print", Token::Print);
+    test("println", Token::Println);
     test("return", Token::Return);
     test("true", Token::True);
     test("use", Token::Use);
+    test("loop", Token::Loop);
     test("while", Token::While);
+    test("time", Token::Time);
+    test("nil", Token::Nil);
+    test("try", Token::Try);
+    test("defer", Token::Defer);
+}
+
+#[test]
+fn keywords_test() {
+    // Keywords are looked up by binary search.
+    assert!(KEYWORDS.windows(2).all(|w| w[0].0 < w[1].0));
+
+    for (word, token) in keywords() {
+        assert_eq!(parse_word(&mut Cursor::new(word)).unwrap(), *token);
+        assert_eq!(keyword(token), Some(*word));
+        assert_eq!(token.lexeme(), Some(*word));
+    }
+}
+
+#[test]
+fn parse_raw_word_test() {
+    let test = |word: &str, expected: &str| {
+        let mut cursor = Cursor::new(word);
+        assert_eq!(
+            parse_raw_word(&mut cursor).unwrap(),
+            Token::Identifier(Symbol::intern(expected))
+        );
+    };
+
+    test("r#group", "group");
+    test("r#in", "in");
+    test("r#Node", "Node");
+    test("r#value", "value");
+
+    // A raw identifier is only recognized if a word follows the prefix.
+    let mut s = Scanner::new();
+    let (tokens, errors) = s.scan(SourceLine {
+        line: "r#for r #x r#1".to_string(),
+        number: None,
+    });
+    assert_eq!(
+        tokens[..3],
+        [
+            Token::Identifier(Symbol::intern("for")),
+            Token::Identifier(Symbol::intern("r")),
+            Token::Identifier(Symbol::intern("x"))
+        ]
+    );
+    assert_eq!(errors.len(), 2);
+}
+
+#[test]
+fn scan_with_spans_test() {
+    let mut s = Scanner::new();
+
+    let (tokens, spans, errors) = s.scan_with_spans(SourceLine {
+        line: r#"let ŮñĭçøƋɇ = "a b" + 3.14; // Comment"#.to_string(),
+        number: Some(42),
+    });
+
+    assert!(errors.is_empty());
+    assert_eq!(tokens.len(), 7);
+    assert_eq!(
+        spans
+            .iter()
+            .map(|s| (s.start, s.end))
+            .collect::<Vec<(usize, usize)>>(),
+        vec![
+            (0, 3),
+            (4, 11),
+            (12, 13),
+            (14, 19),
+            (20, 21),
+            (22, 26),
+            (26, 27)
+        ]
+    );
+    assert!(spans.iter().all(|s| s.line == Some(42)));
+}
+
+#[test]
+fn scan_doc_comment_test() {
+    let mut s = Scanner::new();
+
+    let (tokens, spans, errors) = s.scan_with_spans(SourceLine {
+        line: "///  Doc comment. ".to_string(),
+        number: Some(1),
+    });
+
+    assert!(errors.is_empty());
+    assert_eq!(tokens.len(), 1);
+    assert_eq!(tokens[0], Token::DocComment(" Doc comment.".into()));
+    assert_eq!((spans[0].start, spans[0].end), (0, 18));
+
+    // More than three slashes is a regular comment.
+    for line in ["//// Not a doc comment.", "// Comment.", "x // Comment."] {
+        let (tokens, _, _) = s.scan_with_spans(SourceLine {
+            line: line.to_string(),
+            number: None,
+        });
+        assert!(tokens.iter().all(|t| !matches!(t, Token::DocComment(_))));
+    }
+}
+
+#[test]
+fn scan_shebang_test() {
+    let scan = |line: &str, number| {
+        let mut s = Scanner::new();
+        let (tokens, errors) = s.scan(SourceLine {
+            line: line.to_string(),
+            number,
+        });
+        (tokens.len(), errors.len())
+    };
+
+    assert_eq!(scan("#!/usr/bin/env nexus", Some(1)), (0, 0));
+
+    // Only the start of the first line is a shebang.
+    assert_eq!(scan("#!/usr/bin/env nexus", Some(2)), (8, 1));
+    assert_eq!(scan(" #!/usr/bin/env nexus", Some(1)), (8, 1));
+}
+
+#[test]
+fn scan_options_test() {
+    let scan = |options: ScanOptions, lines: &[&str]| {
+        let mut s = Scanner::with_options(options);
+        let mut tokens = Tokens::new();
+
+        for line in lines {
+            let (mut t, errors) = s.scan(SourceLine {
+                line: line.to_string(),
+                number: None,
+            });
+            assert!(errors.is_empty());
+            tokens.append(&mut t);
+        }
+
+        tokens.append(&mut s.finish());
+        tokens
+    };
+
+    let lines = ["let x = 1; // Comment", "", "/* multi", "line */ x"];
+    let ws = |w: &str| Token::Whitespace(w.into());
+
+    assert_eq!(scan(ScanOptions::default(), &lines).len(), 6);
+    assert_eq!(
+        scan(
+            ScanOptions {
+                newlines: true,
+                eof: true,
+                trivia: false
+            },
+            &lines
+        ),
+        vec![
+            Token::Let,
+            Token::Identifier(Symbol::intern("x")),
+            Token::Is,
+            Token::Number(1.0, "1".into()),
+            Token::SemiColon,
+            Token::Newline,
+            Token::Newline,
+            Token::Identifier(Symbol::intern("x")),
+            Token::Newline,
+            Token::Eof
+        ]
+    );
+    assert_eq!(
+        scan(
+            ScanOptions {
+                newlines: false,
+                eof: false,
+                trivia: true
+            },
+            &["  let\tx  =1; ", "x"]
+        ),
+        vec![
+            ws("  "),
+            Token::Let,
+            ws("\t"),
+            Token::Identifier(Symbol::intern("x")),
+            ws("  "),
+            Token::Is,
+            Token::Number(1.0, "1".into()),
+            Token::SemiColon,
+            ws(" "),
+            Token::Identifier(Symbol::intern("x")),
+        ]
+    );
+}
+
+#[test]
+fn scan_options_spans_test() {
+    let mut s = Scanner::with_options(ScanOptions {
+        newlines: true,
+        eof: false,
+        trivia: true,
+    });
+
+    let (tokens, spans, errors) = s.scan_with_spans(SourceLine {
+        line: "a  b".to_string(),
+        number: Some(3),
+    });
+
+    assert!(errors.is_empty());
+    assert_eq!(tokens.len(), spans.len());
+    assert_eq!(
+        spans
+            .iter()
+            .map(|s| (s.start, s.end))
+            .collect::<Vec<(usize, usize)>>(),
+        vec![(0, 1), (1, 3), (3, 4), (4, 5)]
+    );
+}
+
+#[test]
+fn scan_recovery_test() {
+    let mut s = Scanner::new();
+
+    let (tokens, spans, errors) = s.scan_with_spans(SourceLine {
+        line: "let # x = @1; $".to_string(),
+        number: Some(1),
+    });
+
+    assert_eq!(
+        tokens,
+        vec![
+            Token::Let,
+            Token::Identifier(Symbol::intern("x")),
+            Token::Is,
+            Token::Number(1.0, "1".into()),
+            Token::SemiColon
+        ]
+    );
+    assert_eq!(tokens.len(), spans.len());
+    assert!(errors
+        .iter()
+        .all(|e| matches!(e.kind(), ScanErrorKind::UnexpectedCharacter)));
+    assert_eq!(
+        errors.iter().map(ScanError::column).collect::<Vec<_>>(),
+        vec![4, 10, 14]
+    );
+
+    // An unterminated string ends the line, but earlier tokens are kept.
+    let (tokens, errors) = s.scan(SourceLine {
+        line: "x = \"abc; y".to_string(),
+        number: None,
+    });
+
+    assert_eq!(
+        tokens,
+        vec![Token::Identifier(Symbol::intern("x")), Token::Is]
+    );
+    assert_eq!(errors.len(), 1);
+    assert!(matches!(
+        errors[0].kind(),
+        ScanErrorKind::UnterminatedString
+    ));
 }
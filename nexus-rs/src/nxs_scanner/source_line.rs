@@ -1,3 +1,5 @@
+use unicode_width::UnicodeWidthChar;
+
 /// Source line representation (string + line number) for use in the Nexus scanner.
 ///
 /// # Example
@@ -15,3 +17,225 @@ pub struct SourceLine {
     pub line: String,
     pub number: Option<usize>,
 }
+
+/// Tab stop width used for rendering source lines.
+pub const TAB_WIDTH: usize = 4;
+
+/// Maximum display width of rendered source lines, longer lines are cut around the underlined range.
+pub const MAX_SNIPPET_WIDTH: usize = 100;
+
+/// Byte order mark, which may start a UTF-8 encoded source file.
+pub const BOM: char = '\u{feff}';
+
+/// Interpreter directive, which may start the first line of an executable source file (e.g. `#!/usr/bin/env nexus`).
+pub const SHEBANG: &str = "#!";
+
+/// Get the display column following a character at the given display column.
+///
+/// Tabs advance to the next tab stop (see [`TAB_WIDTH`]), wide characters (e.g. CJK) take two columns, and control
+///  and combining characters take none.
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::source_line::next_column;
+///
+/// assert_eq!(next_column(0, 'x'), 1);
+/// assert_eq!(next_column(1, '\t'), 4);
+/// assert_eq!(next_column(0, '字'), 2);
+/// assert_eq!(next_column(0, '\u{301}'), 0);
+/// ```
+pub fn next_column(column: usize, c: char) -> usize {
+    match c {
+        '\t' => (column / TAB_WIDTH + 1) * TAB_WIDTH,
+        _ => column + c.width().unwrap_or(0),
+    }
+}
+
+impl SourceLine {
+    /// Check if this is a shebang line: the first line of a source file, starting with [`SHEBANG`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nexus_rs::source_line::SourceLine;
+    ///
+    /// let sl = |number| SourceLine { line: "#!/usr/bin/env nexus".to_string(), number };
+    ///
+    /// assert!(sl(Some(1)).is_shebang());
+    /// assert!(!sl(Some(2)).is_shebang());
+    /// assert!(!sl(None).is_shebang());
+    /// ```
+    pub fn is_shebang(&self) -> bool {
+        self.number == Some(1) && self.line.starts_with(SHEBANG)
+    }
+
+    /// Get the display column of a character index (see [`next_column`]).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nexus_rs::source_line::SourceLine;
+    ///
+    /// let sl = SourceLine { line: "\tx =\t1;".to_string(), number: None };
+    ///
+    /// assert_eq!(sl.column(1), 4);
+    /// assert_eq!(sl.column(5), 8);
+    ///
+    /// let sl = SourceLine { line: "漢字 = 1;".to_string(), number: None };
+    ///
+    /// assert_eq!(sl.column(3), 5);
+    /// ```
+    pub fn column(&self, index: usize) -> usize {
+        self.line.chars().take(index).fold(0, next_column)
+            + index.saturating_sub(self.line.chars().count()) // Beyond end-of-line.
+    }
+
+    /// Render a diagnostic snippet of this line, underlining a range of character indices.
+    ///
+    /// The underline is placed at display columns, so it lines up with wide characters. Tabs are expanded to spaces, so
+    ///  the underline lines up regardless of the tab width of the terminal/editor. Lines wider than
+    ///  [`MAX_SNIPPET_WIDTH`] are cut (marked by `...`) around the start of the underline.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nexus_rs::source_line::SourceLine;
+    ///
+    /// let sl = SourceLine { line: "let x = ;".to_string(), number: Some(7) };
+    ///
+    /// assert_eq!(sl.render_snippet(8, 9, "error: oops"),
+    ///            "   |\n 7 | let x = ;\n   |         ^\n   | error: oops\n   |");
+    /// ```
+    pub fn render_snippet(&self, start: usize, end: usize, message: &str) -> String {
+        let line_number_str = self.number.map_or("".to_owned(), |n| n.to_string());
+        let prefix_fill = " ".repeat(line_number_str.len() + 2); // +2 for spaces.
+        let (start, end) = (self.column(start), self.column(end.max(start)));
+        let (line, from, shift) = self.window(start);
+        let (start, end) = (
+            start - from + shift,
+            (end - from).min(MAX_SNIPPET_WIDTH) + shift,
+        );
+        let char_fill = " ".repeat(start);
+        let underline = "^".repeat(end.saturating_sub(start).max(1));
+        format!(
+            "{prefix_fill}|\n {line_number_str} | {line}\n{prefix_fill}| {char_fill}{underline}\n{prefix_fill}| {message}\n{prefix_fill}|",
+        )
+    }
+
+    /// Get the (expanded) line, cut to at most [`MAX_SNIPPET_WIDTH`] columns around a display column.
+    ///
+    /// Returns the line, the first display column shown and the display width of the cut marker in front (if any).
+    fn window(&self, column: usize) -> (String, usize, usize) {
+        const MARKER: &str = "...";
+
+        let expanded = self.expanded();
+        if expanded.chars().fold(0, next_column) <= MAX_SNIPPET_WIDTH {
+            return (expanded, 0, 0);
+        }
+
+        let from = column.saturating_sub(MAX_SNIPPET_WIDTH / 2);
+        let to = from + MAX_SNIPPET_WIDTH;
+
+        let mut result = String::new();
+        let mut cut_end = false;
+        let mut col = 0;
+
+        for c in expanded.chars() {
+            let next = next_column(col, c);
+            if col >= from && next <= to {
+                result.push(c);
+            } else if next > to {
+                cut_end = true;
+                break;
+            }
+            col = next;
+        }
+
+        let shift = if from > 0 { MARKER.len() } else { 0 };
+        let result = format!(
+            "{}{result}{}",
+            if from > 0 { MARKER } else { "" },
+            if cut_end { MARKER } else { "" }
+        );
+
+        (result, from, shift)
+    }
+
+    /// Get the line with tabs expanded to spaces.
+    fn expanded(&self) -> String {
+        let mut result = String::new();
+
+        for c in self.line.chars() {
+            match c {
+                '\t' => {
+                    let column = result.chars().fold(0, next_column);
+                    result.push_str(&" ".repeat(next_column(column, c) - column));
+                }
+                _ => result.push(c),
+            }
+        }
+
+        result
+    }
+}
+
+#[test]
+fn render_snippet_tabs_test() {
+    let sl = SourceLine {
+        line: "\tlet\tx = @;".to_string(),
+        number: Some(3),
+    };
+
+    assert_eq!(
+        sl.render_snippet(9, 10, "error: oops"),
+        "   |\n 3 |     let x = @;\n   |             ^\n   | error: oops\n   |"
+    );
+    assert_eq!(sl.column(12), 15);
+    assert_eq!(sl.column(14), 17);
+}
+
+#[test]
+fn render_snippet_wide_test() {
+    let sl = SourceLine {
+        line: "let ŮñĭçøƋɇ = 漢字 @;".to_string(),
+        number: Some(1),
+    };
+
+    assert_eq!(
+        sl.render_snippet(17, 18, "error: oops"),
+        "   |\n 1 | let ŮñĭçøƋɇ = 漢字 @;\n   |                    ^\n   | error: oops\n   |"
+    );
+    assert_eq!(
+        sl.render_snippet(14, 16, "error: oops"),
+        "   |\n 1 | let ŮñĭçøƋɇ = 漢字 @;\n   |               ^^^^\n   | error: oops\n   |"
+    );
+}
+
+#[test]
+fn render_snippet_long_line_test() {
+    let sl = SourceLine {
+        line: format!("let x = {}@{};", "(".repeat(200), ")".repeat(200)),
+        number: Some(1),
+    };
+
+    let snippet = sl.render_snippet(208, 209, "error: oops");
+    let lines = snippet.lines().collect::<Vec<_>>();
+
+    assert_eq!(
+        lines[1],
+        format!(" 1 | ...{}@{}...", "(".repeat(50), ")".repeat(49))
+    );
+    assert_eq!(lines[2], format!("   | {}^", " ".repeat(53)));
+
+    // Short lines are not cut, also when underlining beyond the maximum width.
+    let sl = SourceLine {
+        line: "x".to_string(),
+        number: None,
+    };
+
+    assert_eq!(
+        sl.render_snippet(0, 500, "error: oops").lines().nth(2),
+        Some(format!("  | {}", "^".repeat(MAX_SNIPPET_WIDTH)).as_str())
+    );
+}
@@ -0,0 +1,57 @@
+/// Source code span of a token: a range of character columns on a single source line.
+///
+/// The file index distinguishes the source files of a program, the main source file being file 0 and used files
+///  numbered in order of loading (see [`Loader`](crate::loader::Loader)).
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::span::Span;
+///
+/// let s = Span { file: 0, line: Some(3), start: 4, end: 7 };
+///
+/// assert_eq!(s.len(), 3);
+/// assert_eq!(s.after(), Span { file: 0, line: Some(3), start: 7, end: 8 });
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Span {
+    pub file: usize,
+    pub line: Option<usize>,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    /// Get the span length in characters.
+    pub fn len(&self) -> usize {
+        self.end.saturating_sub(self.start)
+    }
+
+    /// Check if the span is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the single-character span directly following this span.
+    pub fn after(&self) -> Span {
+        Span {
+            file: self.file,
+            line: self.line,
+            start: self.end,
+            end: self.end + 1,
+        }
+    }
+
+    /// Merge two spans on the same line into one span covering both.
+    pub fn merge(&self, other: &Span) -> Span {
+        Span {
+            file: self.file,
+            line: self.line,
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
+        }
+    }
+}
+
+/// Collection of spans.
+pub type Spans = Vec<Span>;
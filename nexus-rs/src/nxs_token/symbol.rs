@@ -0,0 +1,109 @@
+use lazy_static::lazy_static;
+use std::{collections::HashMap, fmt, sync::Mutex};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+/// Interned string, used for identifiers (literals and trivia are kept as strings).
+///
+/// Symbols are cheap to copy and compare (in constant time), the string value is stored only once in a global
+///  interner. Interned strings are never freed.
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::symbol::Symbol;
+///
+/// let a = Symbol::intern("foo");
+/// let b = Symbol::intern("foo");
+///
+/// assert_eq!(a, b);
+/// assert_ne!(a, Symbol::intern("bar"));
+/// assert_eq!(a.as_str(), "foo");
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Symbol(u32);
+
+impl Symbol {
+    /// Intern a string, returning its symbol.
+    pub fn intern(s: &str) -> Symbol {
+        INTERNER.lock().expect("poisoned interner").intern(s)
+    }
+
+    /// Get the interned string value.
+    pub fn as_str(&self) -> &'static str {
+        INTERNER.lock().expect("poisoned interner").get(*self)
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(s: &str) -> Self {
+        Symbol::intern(s)
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.as_str())
+    }
+}
+
+/// String interner, mapping strings to symbols and back.
+#[derive(Default)]
+struct Interner {
+    symbols: HashMap<&'static str, Symbol>,
+    strings: Vec<&'static str>,
+}
+
+impl Interner {
+    fn intern(&mut self, s: &str) -> Symbol {
+        if let Some(symbol) = self.symbols.get(s) {
+            return *symbol;
+        }
+
+        let symbol = Symbol(self.strings.len() as u32);
+        let s: &'static str = Box::leak(s.to_owned().into_boxed_str());
+
+        self.strings.push(s);
+        self.symbols.insert(s, symbol);
+
+        symbol
+    }
+
+    fn get(&self, symbol: Symbol) -> &'static str {
+        self.strings[symbol.0 as usize]
+    }
+}
+
+lazy_static! {
+    static ref INTERNER: Mutex<Interner> = Mutex::new(Interner::default());
+}
+
+#[test]
+fn intern_test() {
+    let mut i = Interner::default();
+
+    let a = i.intern("a");
+    let b = i.intern("ŮñĭçøƋɇ");
+
+    assert_eq!(i.intern("a"), a);
+    assert_eq!(i.intern("ŮñĭçøƋɇ"), b);
+    assert_ne!(a, b);
+    assert_eq!(i.get(a), "a");
+    assert_eq!(i.get(b), "ŮñĭçøƋɇ");
+    assert_eq!(i.strings.len(), 2);
+}
+
+#[test]
+fn symbol_format_test() {
+    let s = Symbol::intern("x y");
+
+    assert_eq!(format!("{s}"), "x y");
+    assert_eq!(format!("{s:?}"), "\"x y\"");
+}
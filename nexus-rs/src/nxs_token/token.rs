@@ -1,64 +1,237 @@
+use super::symbol::Symbol;
+use std::fmt;
+
 /// Scanning/lexing token representation used in the Nexus grammar.
 #[derive(Clone, Debug, PartialEq)]
 pub enum Token {
-    Empty,        // "Empty" token, used for stream preprocessing.
-    LeftParen,    // '('
-    RightParen,   // ')'
-    LeftBrace,    // '{'
-    RightBrace,   // '}'
-    LeftBracket,  // '['
-    RightBracket, // ']'
-    Colon,        // ':'
-    SemiColon,    // ';'
-    Amp,          // '&'
-    Plus,         // '+'
-    Minus,        // '-'
-    Arrow,        // '->'
-    Star,         // '*'
-    Slash,        // '/'
-    BackSlash,    // '\'
-    Percent,      // '%'
-    Comma,        // ','
-    Dot,          // '.'
-    Range,        // '..'
-    Underscore,   // '_'
-    Is,           // '='
-    Eq,           // '=='
-    Gt,           // '>'
-    GtEq,         // '>='
-    Lt,           // '<'
-    LtEq,         // '<='
-    Bang,         // '!'
-    NotEq,        // '!='
-    And,          // '&&'
-    Or,           // '||'
-    EmptyClosure, // '||'
-    Pipe,         // '|'
-    True,         // 'true'
-    False,        // 'false'
-    Const,        // 'const'
-    Let,          // 'let'
-    Mut,          // 'mut'
-    Function,     // 'fn'
-    If,           // 'if'
-    Else,         // 'else'
-    For,          // 'for'
-    In,           // 'in'
-    While,        // 'while'
-    Return,       // 'return'
-    Use,          // 'use'
-    BoolId,       // 'bool'
-    NodeId,       // 'Node'
-    GroupId,      // 'Group'
-    NumberId,     // 'Number'
-    StringId,     // 'String'
-    Print,        // 'print'
-    Node,         // 'node'
-    Group,        // 'group'
-    Number(f64),
-    Identifier(String),
+    Empty,               // "Empty" token, used for stream preprocessing.
+    LeftParen,           // '('
+    RightParen,          // ')'
+    LeftBrace,           // '{'
+    RightBrace,          // '}'
+    LeftBracket,         // '['
+    RightBracket,        // ']'
+    Colon,               // ':'
+    SemiColon,           // ';'
+    Amp,                 // '&'
+    Plus,                // '+'
+    Minus,               // '-'
+    Arrow,               // '->'
+    NotArrow,            // '-/>'
+    Star,                // '*'
+    StarStar,            // '**'
+    Slash,               // '/'
+    BackSlash,           // '\'
+    Percent,             // '%'
+    Comma,               // ','
+    Question,            // '?'
+    Dot,                 // '.'
+    Range,               // '..'
+    RangeInclusive,      // '..='
+    Underscore,          // '_'
+    Is,                  // '='
+    Eq,                  // '=='
+    Gt,                  // '>'
+    GtEq,                // '>='
+    Lt,                  // '<'
+    LtEq,                // '<='
+    Bang,                // '!'
+    NotEq,               // '!='
+    And,                 // '&&'
+    Or,                  // '||'
+    EmptyClosure,        // '||'
+    Pipe,                // '|'
+    Pipeline,            // '|>'
+    True,                // 'true'
+    False,               // 'false'
+    Nil,                 // 'nil'
+    Const,               // 'const'
+    Let,                 // 'let'
+    Mut,                 // 'mut'
+    Function,            // 'fn'
+    If,                  // 'if'
+    Else,                // 'else'
+    For,                 // 'for'
+    In,                  // 'in'
+    Loop,                // 'loop'
+    While,               // 'while'
+    Time,                // 'time'
+    Try,                 // 'try'
+    Return,              // 'return'
+    Defer,               // 'defer'
+    Use,                 // 'use'
+    BoolId,              // 'bool'
+    NodeId,              // 'Node'
+    GroupId,             // 'Group'
+    NumberId,            // 'Number'
+    StringId,            // 'String'
+    Print,               // 'print'
+    Println,             // 'println'
+    Node,                // 'node'
+    Group,               // 'group'
+    Number(f64, String), // Value and literal text, as written (e.g. '1.50').
+    Identifier(Symbol),
     String(String),
+    DocComment(String), // Documentation comment ('///'), without the slashes and the first space.
+    Newline,            // End of line (optional, see `ScanOptions`).
+    Eof,                // End of file (optional, see `ScanOptions`).
+    Whitespace(String), // Whitespace trivia (optional, see `ScanOptions`).
 }
 
 /// Collection of tokens.
 pub type Tokens = Vec<Token>;
+
+impl Token {
+    /// Get the binding power (precedence) of a binary operator token, a higher power binds stronger.
+    ///
+    /// Member access (`.`) is not included, as it binds stronger than any (unary) operator. All binary operators are
+    ///  left-associative, except for exponentiation (see [`Token::is_right_associative`]).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nexus_rs::token::Token;
+    ///
+    /// assert!(Token::Star.binding_power() > Token::Plus.binding_power());
+    /// assert_eq!(Token::Bang.binding_power(), None);
+    /// ```
+    pub fn binding_power(&self) -> Option<u8> {
+        Some(match self {
+            Token::Range | Token::RangeInclusive => 1,
+            Token::Or => 2,
+            Token::And => 3,
+            Token::Pipeline => 4,
+            Token::Eq | Token::NotEq => 5,
+            Token::Lt | Token::LtEq | Token::Gt | Token::GtEq => 6,
+            Token::Plus | Token::Minus => 7,
+            Token::Star | Token::Slash | Token::Percent => 8,
+            Token::StarStar => 9,
+            _ => return None,
+        })
+    }
+
+    /// Check if a token is a type name (e.g. `Number`).
+    pub fn is_type(&self) -> bool {
+        matches!(
+            self,
+            Token::BoolId | Token::GroupId | Token::NodeId | Token::NumberId | Token::StringId
+        )
+    }
+
+    /// Check if a binary operator token is right-associative, e.g. `2 ** 3 ** 2` is `2 ** (3 ** 2)`.
+    pub fn is_right_associative(&self) -> bool {
+        matches!(self, Token::StarStar)
+    }
+
+    /// Get the surface syntax of a token with a fixed lexeme (punctuation, operators and keywords).
+    ///
+    /// Tokens with a value (e.g. numbers and identifiers) or without source text (e.g. end of file) have no fixed
+    ///  lexeme, see the [`Display`](fmt::Display) implementation instead.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nexus_rs::{symbol::Symbol, token::Token};
+    ///
+    /// assert_eq!(Token::SemiColon.lexeme(), Some(";"));
+    /// assert_eq!(Token::Function.lexeme(), Some("fn"));
+    /// assert_eq!(Token::Number(1.0, "1.0".into()).lexeme(), None);
+    /// ```
+    pub fn lexeme(&self) -> Option<&'static str> {
+        Some(match self {
+            Token::LeftParen => "(",
+            Token::RightParen => ")",
+            Token::LeftBrace => "{",
+            Token::RightBrace => "}",
+            Token::LeftBracket => "[",
+            Token::RightBracket => "]",
+            Token::Colon => ":",
+            Token::SemiColon => ";",
+            Token::Amp => "&",
+            Token::Plus => "+",
+            Token::Minus => "-",
+            Token::Arrow => "->",
+            Token::NotArrow => "-/>",
+            Token::Star => "*",
+            Token::StarStar => "**",
+            Token::Slash => "/",
+            Token::BackSlash => "\\",
+            Token::Percent => "%",
+            Token::Comma => ",",
+            Token::Question => "?",
+            Token::Dot => ".",
+            Token::Range => "..",
+            Token::RangeInclusive => "..=",
+            Token::Underscore => "_",
+            Token::Is => "=",
+            Token::Eq => "==",
+            Token::Gt => ">",
+            Token::GtEq => ">=",
+            Token::Lt => "<",
+            Token::LtEq => "<=",
+            Token::Bang => "!",
+            Token::NotEq => "!=",
+            Token::And => "&&",
+            Token::Or => "||",
+            Token::EmptyClosure => "||",
+            Token::Pipe => "|",
+            Token::Pipeline => "|>",
+            Token::True => "true",
+            Token::False => "false",
+            Token::Nil => "nil",
+            Token::Const => "const",
+            Token::Let => "let",
+            Token::Mut => "mut",
+            Token::Function => "fn",
+            Token::If => "if",
+            Token::Else => "else",
+            Token::For => "for",
+            Token::In => "in",
+            Token::Loop => "loop",
+            Token::While => "while",
+            Token::Time => "time",
+            Token::Try => "try",
+            Token::Return => "return",
+            Token::Defer => "defer",
+            Token::Use => "use",
+            Token::BoolId => "bool",
+            Token::NodeId => "Node",
+            Token::GroupId => "Group",
+            Token::NumberId => "Number",
+            Token::StringId => "String",
+            Token::Print => "print",
+            Token::Println => "println",
+            Token::Node => "node",
+            Token::Group => "group",
+            _ => return None,
+        })
+    }
+}
+
+/// Format a token as it is written in source code, e.g. `;` for [`Token::SemiColon`].
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::{symbol::Symbol, token::Token};
+///
+/// assert_eq!(Token::RangeInclusive.to_string(), "..=");
+/// assert_eq!(Token::Identifier(Symbol::intern("x")).to_string(), "x");
+/// assert_eq!(Token::Number(1.5, "1.50".into()).to_string(), "1.50");
+/// assert_eq!(Token::String("a\"b".into()).to_string(), "\"a\\\"b\"");
+/// ```
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Number(_, text) => f.write_str(text),
+            Token::Identifier(id) => f.write_str(id.as_str()),
+            Token::String(s) => write!(f, "\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\"")),
+            Token::DocComment(text) => write!(f, "/// {text}"),
+            Token::Whitespace(ws) => f.write_str(ws),
+            Token::Empty => Ok(()),
+            Token::Newline => f.write_str("end of line"),
+            Token::Eof => f.write_str("end of file"),
+            t => f.write_str(t.lexeme().expect("token with a fixed lexeme")),
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use crate::{driver, trace_logger::TraceLogger};
+use clap::ValueEnum;
+use log::LevelFilter;
+use std::{
+    io::{self, IsTerminal},
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+#[cfg(test)]
+use clap::Parser;
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+/// Colored output choice.
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum ColorChoice {
+    /// Color if both standard output and standard error are terminals (and 'NO_COLOR' is not set).
+    #[default]
+    Auto,
+
+    /// Always color.
+    Always,
+
+    /// Never color.
+    Never,
+}
+
+/// Output verbosity, in increasing order.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+    Quiet, // Only errors.
+    #[default]
+    Normal,
+    Verbose, // Progress information.
+    Debug,   // Progress information with details (e.g. timing).
+}
+
+/// Global command-line options, shared by all binaries (flattened into the arguments of each binary).
+///
+/// # Example
+///
+/// ```
+/// use clap::Parser;
+/// use nexus_rs::cli::{ColorChoice, GlobalArgs, Verbosity};
+///
+/// #[derive(Parser)]
+/// struct Args {
+///     #[command(flatten)]
+///     global: GlobalArgs,
+/// }
+///
+/// let args = Args::parse_from(["test", "--color", "never", "-vv"]);
+///
+/// assert_eq!(args.global.color, ColorChoice::Never);
+/// assert_eq!(args.global.verbosity(), Verbosity::Debug);
+/// ```
+#[derive(clap::Args, Debug)]
+pub struct GlobalArgs {
+    /// Colored output.
+    #[arg(long, value_enum, value_name = "WHEN", default_value_t = ColorChoice::Auto, global = true)]
+    pub color: ColorChoice,
+
+    /// Only report errors (no warnings, notes or progress information).
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    pub quiet: bool,
+
+    /// Report progress information to stderr ('-vv' adds details, e.g. timing).
+    #[arg(short, long, action = clap::ArgAction::Count, global = true)]
+    pub verbose: u8,
+}
+
+/// Verbosity set by [`GlobalArgs::init`].
+static VERBOSITY: AtomicU8 = AtomicU8::new(Verbosity::Normal as u8);
+
+impl GlobalArgs {
+    /// Get the verbosity.
+    pub fn verbosity(&self) -> Verbosity {
+        match (self.quiet, self.verbose) {
+            (true, _) => Verbosity::Quiet,
+            (false, 0) => Verbosity::Normal,
+            (false, 1) => Verbosity::Verbose,
+            (false, _) => Verbosity::Debug,
+        }
+    }
+
+    /// Apply the options: set the color output and verbosity, and install a logger for the progress information and
+    ///  (optionally) a developer trace target (e.g. parser tracing). Call this once, at the start of a binary.
+    pub fn init(&self, trace: Option<&'static str>) {
+        let color = match self.color {
+            ColorChoice::Auto => {
+                std::env::var_os("NO_COLOR").is_none()
+                    && io::stdout().is_terminal()
+                    && io::stderr().is_terminal()
+            }
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        };
+        colored::control::set_override(color);
+
+        let verbosity = self.verbosity();
+        VERBOSITY.store(verbosity as u8, Ordering::Relaxed);
+
+        let mut filters: Vec<_> = trace.into_iter().map(|t| (t, LevelFilter::Trace)).collect();
+        match verbosity {
+            Verbosity::Verbose => filters.push((driver::LOG_TARGET, LevelFilter::Info)),
+            Verbosity::Debug => filters.push((driver::LOG_TARGET, LevelFilter::Debug)),
+            _ => (),
+        }
+
+        if !filters.is_empty() {
+            TraceLogger::init_with_filters(filters).expect("failed to install logger");
+        }
+    }
+}
+
+/// Get the verbosity set by [`GlobalArgs::init`] (normal if not set).
+pub fn verbosity() -> Verbosity {
+    match VERBOSITY.load(Ordering::Relaxed) {
+        0 => Verbosity::Quiet,
+        2 => Verbosity::Verbose,
+        3 => Verbosity::Debug,
+        _ => Verbosity::Normal,
+    }
+}
+
+/// Check if only errors should be reported (see `--quiet`).
+pub fn is_quiet() -> bool {
+    verbosity() == Verbosity::Quiet
+}
+
+#[cfg(test)]
+#[derive(Parser)]
+struct TestArgs {
+    #[command(flatten)]
+    global: GlobalArgs,
+}
+
+#[test]
+fn verbosity_test() {
+    let verbosity = |args: &[&str]| {
+        TestArgs::try_parse_from(std::iter::once("test").chain(args.iter().copied()))
+            .map(|a| a.global.verbosity())
+            .ok()
+    };
+
+    assert_eq!(verbosity(&[]), Some(Verbosity::Normal));
+    assert_eq!(verbosity(&["--quiet"]), Some(Verbosity::Quiet));
+    assert_eq!(verbosity(&["-v"]), Some(Verbosity::Verbose));
+    assert_eq!(verbosity(&["-vvv"]), Some(Verbosity::Debug));
+    assert_eq!(verbosity(&["-q", "-v"]), None);
+
+    assert_eq!(
+        TestArgs::parse_from(["test"]).global.color,
+        ColorChoice::Auto
+    );
+    assert!(TestArgs::try_parse_from(["test", "--color", "sometimes"]).is_err());
+}
@@ -1,14 +1,21 @@
+use crate::source_line::BOM;
 use std::{
     fs::File,
-    io::{BufRead, BufReader, Lines, Result},
+    io::{self, BufRead, BufReader, Result},
     path::Path,
 };
 
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
 /// Iterable wrapper around a buffered file reader.
 ///
 /// The main difference to using the buffered file reader directly is that each line reading iteration results in a
-///  `String` value directly, rather than a `Option<Result<String>>` (which is the case for iterating over `BufReader`).
-/// A failing line read will result in a panic (don't use in production!).
+///  line without line ending, lossily decoded as UTF-8 (invalid sequences are replaced by U+FFFD, the replacement
+///  character). A leading UTF-8 byte order mark is stripped. Read errors are passed on, after which the iteration
+///  ends.
+///
+/// The filename `-` reads from the standard input.
 ///
 /// # Example
 ///
@@ -18,11 +25,11 @@ use std::{
 /// let file = FileReader::try_new("example.txt").unwrap();
 ///
 /// for line in file {
-///   println!("{line}");
+///   println!("{}", line.unwrap());
 /// }
 /// ```
 pub struct FileReader {
-    reader: BufReader<File>,
+    reader: Box<dyn BufRead>,
 }
 
 impl FileReader {
@@ -30,42 +37,112 @@ impl FileReader {
     where
         P: AsRef<Path>,
     {
-        match File::open(&filename) {
-            Ok(file) => Ok(FileReader {
-                reader: BufReader::new(file),
-            }),
-            Err(error) => Err(error),
+        if filename.as_ref() == Path::new("-") {
+            return Ok(FileReader {
+                reader: Box::new(BufReader::new(io::stdin())),
+            });
         }
+
+        Ok(FileReader {
+            reader: Box::new(BufReader::new(File::open(&filename)?)),
+        })
     }
 }
 
 impl IntoIterator for FileReader {
-    type Item = String;
+    type Item = Result<String>;
     type IntoIter = FileReaderIterator;
 
     fn into_iter(self) -> Self::IntoIter {
         FileReaderIterator {
-            lines: self.reader.lines(),
+            reader: self.reader,
+            first: true,
+            failed: false,
         }
     }
 }
 
 /// Iterator for [FileReader].
 pub struct FileReaderIterator {
-    lines: Lines<BufReader<File>>,
+    reader: Box<dyn BufRead>,
+    first: bool,
+    failed: bool,
 }
 
 impl Iterator for FileReaderIterator {
-    type Item = String;
+    type Item = Result<String>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if let Some(result) = self.lines.next() {
-            let line = result.unwrap_or_else(|e| {
-                panic!("failed to read line ({e})");
-            });
-            Some(line)
-        } else {
-            None
+        if self.failed {
+            return None;
+        }
+
+        let mut buffer = Vec::new();
+
+        match self.reader.read_until(b'\n', &mut buffer) {
+            Ok(0) => None,
+            Ok(_) => {
+                if buffer.ends_with(b"\n") {
+                    buffer.pop();
+                    if buffer.ends_with(b"\r") {
+                        buffer.pop();
+                    }
+                }
+
+                let line = String::from_utf8_lossy(&buffer);
+                let line = match std::mem::replace(&mut self.first, false) {
+                    true => line.strip_prefix(BOM).unwrap_or(&line),
+                    false => &line,
+                };
+
+                Some(Ok(line.to_owned()))
+            }
+            Err(e) => {
+                self.failed = true;
+                Some(Err(e))
+            }
         }
     }
 }
+
+#[test]
+fn invalid_utf8_test() {
+    let path = std::env::temp_dir().join(format!("nexus_filereader_{}.nxs", std::process::id()));
+    std::fs::write(&path, b"let x = 1;\r\nprint \"\xff\xfe\";\nlast").unwrap();
+
+    let lines = FileReader::try_new(&path)
+        .unwrap()
+        .into_iter()
+        .collect::<Result<Vec<_>>>()
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(lines, ["let x = 1;", "print \"\u{fffd}\u{fffd}\";", "last"]);
+}
+
+#[test]
+fn bom_test() {
+    let path =
+        std::env::temp_dir().join(format!("nexus_filereader_bom_{}.nxs", std::process::id()));
+    std::fs::write(&path, "\u{feff}let x = 1;\r\n\u{feff}").unwrap();
+
+    let lines = FileReader::try_new(&path)
+        .unwrap()
+        .into_iter()
+        .collect::<Result<Vec<_>>>()
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(lines, ["let x = 1;", "\u{feff}"]);
+}
+
+#[test]
+fn read_error_test() {
+    // Reading a directory fails on the first read (rather than on opening).
+    let mut lines = FileReader::try_new(std::env::temp_dir())
+        .unwrap()
+        .into_iter();
+
+    assert!(lines.next().unwrap().is_err());
+    assert!(lines.next().is_none());
+}
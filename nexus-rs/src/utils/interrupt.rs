@@ -0,0 +1,37 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, OnceLock,
+};
+
+/// Interrupt flag, set by the SIGINT handler.
+static INTERRUPT: OnceLock<Arc<AtomicBool>> = OnceLock::new();
+
+/// Install a SIGINT (Ctrl-C) handler, returning the flag it sets (see
+///  [`Interpreter::with_interrupt`](crate::interpreter::Interpreter::with_interrupt)).
+///
+/// The interpreter clears the flag when it stops the program. A second Ctrl-C before that (e.g. while the program is
+///  blocked reading input) exits the process immediately, with [`INTERRUPTED`](crate::driver::INTERRUPTED) as exit
+///  code.
+pub fn install() -> Arc<AtomicBool> {
+    let flag = INTERRUPT.get_or_init(|| {
+        // SAFETY: the handler only accesses an initialized atomic flag, and exits, which are async-signal-safe.
+        unsafe {
+            libc::signal(
+                libc::SIGINT,
+                on_interrupt as extern "C" fn(libc::c_int) as libc::sighandler_t,
+            );
+        }
+        Arc::new(AtomicBool::new(false))
+    });
+
+    flag.clone()
+}
+
+extern "C" fn on_interrupt(_: libc::c_int) {
+    if let Some(flag) = INTERRUPT.get() {
+        if flag.swap(true, Ordering::SeqCst) {
+            // SAFETY: `_exit` is async-signal-safe, and ends the process without running any handlers.
+            unsafe { libc::_exit(crate::driver::INTERRUPTED) };
+        }
+    }
+}
@@ -0,0 +1,469 @@
+use std::{fmt, iter::Peekable, str::Chars};
+use thiserror::Error;
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+/// Maximum nesting depth of arrays and objects, to limit the recursion of the parser.
+const MAX_DEPTH: usize = 128;
+
+/// JSON parsing error representation.
+#[derive(Error, Debug, PartialEq)]
+pub enum JsonError {
+    #[error("unexpected end of input")]
+    UnexpectedEnd,
+
+    #[error("unexpected character '{0}'")]
+    UnexpectedCharacter(char),
+
+    #[error("invalid number '{0}'")]
+    InvalidNumber(String),
+
+    #[error("invalid escape sequence")]
+    InvalidEscape,
+
+    #[error("trailing characters after value")]
+    TrailingCharacters,
+
+    #[error("nesting exceeds the maximum depth of {MAX_DEPTH}")]
+    TooDeep,
+}
+
+/// JSON value representation.
+///
+/// Object members are kept in insertion order, so that serialized output is stable.
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::json::Json;
+///
+/// let value = Json::parse(r#"{"name": "Test", "ids": [1, 2]}"#).unwrap();
+///
+/// assert_eq!(value.get("name").and_then(Json::as_str), Some("Test"));
+/// assert_eq!(value.to_string(), r#"{"name":"Test","ids":[1,2]}"#);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// Parse a JSON value from a string.
+    pub fn parse(input: &str) -> Result<Json, JsonError> {
+        let mut chars = input.chars().peekable();
+
+        let value = parse_value(&mut chars, 0)?;
+
+        skip_whitespace(&mut chars);
+        if chars.peek().is_some() {
+            return Err(JsonError::TrailingCharacters);
+        }
+
+        Ok(value)
+    }
+
+    /// Get an object member value by key.
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(members) => members.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    /// Get the boolean value (if any).
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Json::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// Get the number value (if any).
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Json::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// Get the number value as an unsigned integer (if any).
+    pub fn as_usize(&self) -> Option<usize> {
+        self.as_f64()
+            .filter(|n| n.fract() == 0.0 && *n >= 0.0)
+            .map(|n| n as usize)
+    }
+
+    /// Get the string value (if any).
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Get the array elements (if any).
+    pub fn as_array(&self) -> Option<&Vec<Json>> {
+        match self {
+            Json::Array(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Check if the value is null.
+    pub fn is_null(&self) -> bool {
+        matches!(self, Json::Null)
+    }
+
+    /// Render the value as indented (two spaces per level) multiline text.
+    pub fn pretty(&self) -> String {
+        let mut result = String::new();
+        write_pretty(self, 0, &mut result);
+        result
+    }
+}
+
+impl From<&str> for Json {
+    fn from(s: &str) -> Self {
+        Json::String(s.to_owned())
+    }
+}
+
+impl From<String> for Json {
+    fn from(s: String) -> Self {
+        Json::String(s)
+    }
+}
+
+impl From<bool> for Json {
+    fn from(b: bool) -> Self {
+        Json::Bool(b)
+    }
+}
+
+impl From<f64> for Json {
+    fn from(n: f64) -> Self {
+        Json::Number(n)
+    }
+}
+
+impl From<usize> for Json {
+    fn from(n: usize) -> Self {
+        Json::Number(n as f64)
+    }
+}
+
+impl<T> From<Option<T>> for Json
+where
+    T: Into<Json>,
+{
+    fn from(o: Option<T>) -> Self {
+        o.map_or(Json::Null, |v| v.into())
+    }
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(b) => write!(f, "{b}"),
+            Json::Number(n) => write_number(*n, f),
+            Json::String(s) => write_string(s, f),
+            Json::Array(a) => {
+                write!(f, "[")?;
+                for (i, v) in a.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{v}")?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(o) => {
+                write!(f, "{{")?;
+                for (i, (k, v)) in o.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_string(k, f)?;
+                    write!(f, ":{v}")?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+fn write_number(n: f64, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if n.is_finite() {
+        write!(f, "{n}")
+    } else {
+        write!(f, "null") // JSON has no representation for NaN/infinity.
+    }
+}
+
+fn write_string(s: &str, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{c}")?,
+        }
+    }
+    write!(f, "\"")
+}
+
+fn write_pretty(value: &Json, level: usize, out: &mut String) {
+    let indent = |level: usize| "  ".repeat(level);
+
+    match value {
+        Json::Array(a) if !a.is_empty() => {
+            out.push_str("[\n");
+            for (i, v) in a.iter().enumerate() {
+                out.push_str(&indent(level + 1));
+                write_pretty(v, level + 1, out);
+                out.push_str(if i + 1 < a.len() { ",\n" } else { "\n" });
+            }
+            out.push_str(&indent(level));
+            out.push(']');
+        }
+        Json::Object(o) if !o.is_empty() => {
+            out.push_str("{\n");
+            for (i, (k, v)) in o.iter().enumerate() {
+                out.push_str(&format!(
+                    "{}{}: ",
+                    indent(level + 1),
+                    Json::from(k.as_str())
+                ));
+                write_pretty(v, level + 1, out);
+                out.push_str(if i + 1 < o.len() { ",\n" } else { "\n" });
+            }
+            out.push_str(&indent(level));
+            out.push('}');
+        }
+        v => out.push_str(&v.to_string()),
+    }
+}
+
+type JsonChars<'a> = Peekable<Chars<'a>>;
+
+fn skip_whitespace(chars: &mut JsonChars) {
+    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+}
+
+fn expect(chars: &mut JsonChars, expected: char) -> Result<(), JsonError> {
+    match chars.next() {
+        Some(c) if c == expected => Ok(()),
+        Some(c) => Err(JsonError::UnexpectedCharacter(c)),
+        None => Err(JsonError::UnexpectedEnd),
+    }
+}
+
+fn expect_word(chars: &mut JsonChars, word: &str, value: Json) -> Result<Json, JsonError> {
+    for c in word.chars() {
+        expect(chars, c)?;
+    }
+
+    Ok(value)
+}
+
+fn parse_value(chars: &mut JsonChars, depth: usize) -> Result<Json, JsonError> {
+    skip_whitespace(chars);
+
+    match chars.peek() {
+        Some('n') => expect_word(chars, "null", Json::Null),
+        Some('t') => expect_word(chars, "true", Json::Bool(true)),
+        Some('f') => expect_word(chars, "false", Json::Bool(false)),
+        Some('"') => Ok(Json::String(parse_string(chars)?)),
+        Some('[' | '{') if depth == MAX_DEPTH => Err(JsonError::TooDeep),
+        Some('[') => parse_array(chars, depth + 1),
+        Some('{') => parse_object(chars, depth + 1),
+        Some(c) if *c == '-' || c.is_ascii_digit() => parse_number(chars),
+        Some(c) => Err(JsonError::UnexpectedCharacter(*c)),
+        None => Err(JsonError::UnexpectedEnd),
+    }
+}
+
+fn parse_number(chars: &mut JsonChars) -> Result<Json, JsonError> {
+    let mut number = String::new();
+    while let Some(c) = chars.next_if(|c| c.is_ascii_digit() || "+-.eE".contains(*c)) {
+        number.push(c);
+    }
+
+    number
+        .parse::<f64>()
+        .map(Json::Number)
+        .map_err(|_| JsonError::InvalidNumber(number))
+}
+
+fn parse_string(chars: &mut JsonChars) -> Result<String, JsonError> {
+    expect(chars, '"')?;
+
+    let mut result = String::new();
+    loop {
+        match chars.next() {
+            Some('"') => break,
+            Some('\\') => result.push(match chars.next() {
+                Some('"') => '"',
+                Some('\\') => '\\',
+                Some('/') => '/',
+                Some('b') => '\u{8}',
+                Some('f') => '\u{c}',
+                Some('n') => '\n',
+                Some('r') => '\r',
+                Some('t') => '\t',
+                Some('u') => {
+                    let code: String = chars.by_ref().take(4).collect();
+                    u32::from_str_radix(&code, 16)
+                        .ok()
+                        .and_then(char::from_u32)
+                        .ok_or(JsonError::InvalidEscape)?
+                }
+                _ => return Err(JsonError::InvalidEscape),
+            }),
+            Some(c) => result.push(c),
+            None => return Err(JsonError::UnexpectedEnd),
+        }
+    }
+
+    Ok(result)
+}
+
+fn parse_array(chars: &mut JsonChars, depth: usize) -> Result<Json, JsonError> {
+    expect(chars, '[')?;
+
+    let mut result = Vec::new();
+
+    skip_whitespace(chars);
+    if chars.next_if_eq(&']').is_some() {
+        return Ok(Json::Array(result));
+    }
+
+    loop {
+        result.push(parse_value(chars, depth)?);
+
+        skip_whitespace(chars);
+        match chars.next() {
+            Some(',') => (),
+            Some(']') => break,
+            Some(c) => return Err(JsonError::UnexpectedCharacter(c)),
+            None => return Err(JsonError::UnexpectedEnd),
+        }
+    }
+
+    Ok(Json::Array(result))
+}
+
+fn parse_object(chars: &mut JsonChars, depth: usize) -> Result<Json, JsonError> {
+    expect(chars, '{')?;
+
+    let mut result = Vec::new();
+
+    skip_whitespace(chars);
+    if chars.next_if_eq(&'}').is_some() {
+        return Ok(Json::Object(result));
+    }
+
+    loop {
+        skip_whitespace(chars);
+        let key = parse_string(chars)?;
+
+        skip_whitespace(chars);
+        expect(chars, ':')?;
+
+        result.push((key, parse_value(chars, depth)?));
+
+        skip_whitespace(chars);
+        match chars.next() {
+            Some(',') => (),
+            Some('}') => break,
+            Some(c) => return Err(JsonError::UnexpectedCharacter(c)),
+            None => return Err(JsonError::UnexpectedEnd),
+        }
+    }
+
+    Ok(Json::Object(result))
+}
+
+#[test]
+fn parse_test() {
+    assert_eq!(Json::parse("null"), Ok(Json::Null));
+    assert_eq!(Json::parse(" true "), Ok(Json::Bool(true)));
+    assert_eq!(Json::parse("false"), Ok(Json::Bool(false)));
+    assert_eq!(Json::parse("-12.5e1"), Ok(Json::Number(-125.0)));
+    assert_eq!(
+        Json::parse(r#""a\"b\\c\nA""#),
+        Ok(Json::String("a\"b\\c\nA".to_owned()))
+    );
+    assert_eq!(
+        Json::parse("[1, [], {}]"),
+        Ok(Json::Array(vec![
+            Json::Number(1.0),
+            Json::Array(vec![]),
+            Json::Object(vec![])
+        ]))
+    );
+    assert_eq!(
+        Json::parse(r#"{"a": 1, "b": null}"#),
+        Ok(Json::Object(vec![
+            ("a".to_owned(), Json::Number(1.0)),
+            ("b".to_owned(), Json::Null)
+        ]))
+    );
+}
+
+#[test]
+fn parse_error_test() {
+    assert_eq!(Json::parse(""), Err(JsonError::UnexpectedEnd));
+    assert_eq!(Json::parse("[1,"), Err(JsonError::UnexpectedEnd));
+    assert_eq!(Json::parse("nul"), Err(JsonError::UnexpectedEnd));
+    assert_eq!(
+        Json::parse("[1 2]"),
+        Err(JsonError::UnexpectedCharacter('2'))
+    );
+    assert_eq!(Json::parse("1 2"), Err(JsonError::TrailingCharacters));
+    assert_eq!(Json::parse(r#""\x""#), Err(JsonError::InvalidEscape));
+}
+
+#[test]
+fn depth_test() {
+    let nested = |depth: usize| "[".repeat(depth) + &"]".repeat(depth);
+
+    assert!(Json::parse(&nested(MAX_DEPTH)).is_ok());
+    assert_eq!(Json::parse(&nested(MAX_DEPTH + 1)), Err(JsonError::TooDeep));
+    assert_eq!(
+        Json::parse(&r#"{"a":"#.repeat(MAX_DEPTH + 1)),
+        Err(JsonError::TooDeep)
+    );
+    assert_eq!(Json::parse(&"[".repeat(200_000)), Err(JsonError::TooDeep));
+}
+
+#[test]
+fn roundtrip_test() {
+    let text =
+        r#"{"name":"Ŵêïrd \"name\"","values":[1,2.5,-3],"nested":{"flag":true,"none":null}}"#;
+    let value = Json::parse(text).unwrap();
+
+    assert_eq!(value.to_string(), text);
+    assert_eq!(Json::parse(&value.pretty()).unwrap(), value);
+}
+
+#[test]
+fn pretty_test() {
+    let value = Json::parse(r#"{"a":[1,2],"b":{},"c":[]}"#).unwrap();
+
+    assert_eq!(
+        value.pretty(),
+        "{\n  \"a\": [\n    1,\n    2\n  ],\n  \"b\": {},\n  \"c\": []\n}"
+    );
+}
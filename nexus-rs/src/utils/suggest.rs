@@ -0,0 +1,81 @@
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+/// Compute the edit (Levenshtein) distance between two strings, counted in characters.
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::suggest::edit_distance;
+///
+/// assert_eq!(edit_distance("whle", "while"), 1);
+/// assert_eq!(edit_distance("kitten", "sitting"), 3);
+/// ```
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = (diagonal + usize::from(ca != *cb))
+                .min(above + 1)
+                .min(row[j] + 1);
+            diagonal = above;
+        }
+    }
+
+    row[b.len()]
+}
+
+/// Find the candidate closest to a (misspelled) name, for a "did you mean" suggestion.
+///
+/// Only candidates within an edit distance of a third of the name length (at least one) are considered. Ties are
+///  resolved by taking the first candidate found.
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::suggest::suggest;
+///
+/// assert_eq!(suggest("Numbr", ["String", "Number", "bool"]), Some("Number"));
+/// assert_eq!(suggest("x", ["String", "Number", "bool"]), None);
+/// ```
+pub fn suggest<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    let max = (name.chars().count() / 3).max(1);
+
+    candidates
+        .into_iter()
+        .filter(|c| *c != name)
+        .map(|c| (edit_distance(name, c), c))
+        .filter(|(distance, _)| *distance <= max)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, c)| c)
+}
+
+#[test]
+fn edit_distance_test() {
+    assert_eq!(edit_distance("", ""), 0);
+    assert_eq!(edit_distance("abc", ""), 3);
+    assert_eq!(edit_distance("", "abc"), 3);
+    assert_eq!(edit_distance("abc", "abc"), 0);
+    assert_eq!(edit_distance("abc", "acb"), 2);
+    assert_eq!(edit_distance("flaw", "lawn"), 2);
+    assert_eq!(edit_distance("ŮñĭçøƋɇ", "ŮñĭçøƋ"), 1);
+}
+
+#[test]
+fn suggest_test() {
+    let names = ["counter", "count", "total", "print_all"];
+
+    assert_eq!(suggest("coutner", names), Some("counter"));
+    assert_eq!(suggest("cont", names), Some("count"));
+    assert_eq!(suggest("totl", names), Some("total"));
+    assert_eq!(suggest("print_al", names), Some("print_all"));
+    assert_eq!(suggest("count", names), None);
+    assert_eq!(suggest("xyz", names), None);
+    assert_eq!(suggest("a", []), None);
+}
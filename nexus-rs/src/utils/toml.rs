@@ -0,0 +1,198 @@
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+/// Key/value entry of a TOML file.
+#[derive(Debug, PartialEq)]
+pub struct Entry<'a> {
+    pub line: usize,    // Line number, starting from 1.
+    pub key: String,    // Key, prefixed with the table name (if any), e.g. `dependencies.lib`.
+    pub value: &'a str, // Raw value, without trailing comment.
+}
+
+/// Split the input into key/value entries.
+///
+/// Only a (small) subset of TOML is supported: one `key = value` pair per line, and table headers (e.g.
+///  `[dependencies]`) prefixing the keys that follow with the table name, like dotted keys. Empty lines and comments
+///  (starting with '#') are skipped. On error, the number of the offending line is returned.
+///
+/// # Example
+///
+/// ```
+/// use nexus_rs::toml::{entries, parse_string_array};
+///
+/// let entries = entries("# Project.\n[project]\nsources = [\"src\", \"lib\"] # Comment.").unwrap();
+///
+/// assert_eq!(entries[0].key, "project.sources");
+/// assert_eq!(parse_string_array(entries[0].value), Some(vec!["src".to_owned(), "lib".to_owned()]));
+/// ```
+pub fn entries(input: &str) -> Result<Vec<Entry<'_>>, usize> {
+    let mut entries = Vec::new();
+    let mut table = None;
+
+    for (number, line) in input.lines().enumerate() {
+        let line = strip_comment(line.trim());
+
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            match name.trim() {
+                "" => return Err(number + 1),
+                name => table = Some(name),
+            }
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once('=') else {
+            return Err(number + 1);
+        };
+
+        entries.push(Entry {
+            line: number + 1,
+            key: match table {
+                Some(table) => format!("{table}.{}", key.trim()),
+                None => key.trim().to_owned(),
+            },
+            value: value.trim(),
+        });
+    }
+
+    Ok(entries)
+}
+
+/// Strip a trailing comment from a value (not taking into account '#' characters inside strings).
+fn strip_comment(value: &str) -> &str {
+    let mut quoted = false;
+
+    for (i, c) in value.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '#' if !quoted => return value[..i].trim(),
+            _ => (),
+        }
+    }
+
+    value
+}
+
+/// Parse a quoted string value.
+pub fn parse_string(value: &str) -> Option<String> {
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .filter(|v| !v.contains('"'))
+        .map(|v| v.to_owned())
+}
+
+/// Parse an inline table (e.g. `{ path = "lib", version = "1.0" }`) into its keys and raw values.
+pub fn parse_inline_table(value: &str) -> Option<Vec<(String, &str)>> {
+    let items = value.strip_prefix('{')?.strip_suffix('}')?.trim();
+
+    if items.is_empty() {
+        return Some(Vec::new());
+    }
+
+    split_quoted(items, ',')
+        .into_iter()
+        .map(|item| {
+            let (key, value) = item.split_once('=')?;
+            Some((key.trim().to_owned(), value.trim()))
+        })
+        .collect()
+}
+
+/// Split a string at a separator, not taking into account separators inside strings.
+fn split_quoted(input: &str, separator: char) -> Vec<&str> {
+    let (mut parts, mut start, mut quoted) = (Vec::new(), 0, false);
+
+    for (i, c) in input.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c == separator && !quoted => {
+                parts.push(&input[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => (),
+        }
+    }
+
+    parts.push(&input[start..]);
+    parts
+}
+
+/// Parse an array of quoted strings (e.g. `["a", "b"]`), allowing a trailing comma.
+pub fn parse_string_array(value: &str) -> Option<Vec<String>> {
+    let items = value.strip_prefix('[')?.strip_suffix(']')?.trim();
+    let items = items.strip_suffix(',').unwrap_or(items);
+
+    if items.trim().is_empty() {
+        return Some(Vec::new());
+    }
+
+    split_quoted(items, ',')
+        .into_iter()
+        .map(|v| parse_string(v.trim()))
+        .collect()
+}
+
+#[test]
+fn entries_test() {
+    let input = r#"
+        # Comment.
+        a = "x # y" # Comment.
+        [table] # Comment.
+        b=1
+        "#;
+
+    assert_eq!(
+        entries(input),
+        Ok(vec![
+            Entry {
+                line: 3,
+                key: "a".to_owned(),
+                value: "\"x # y\""
+            },
+            Entry {
+                line: 5,
+                key: "table.b".to_owned(),
+                value: "1"
+            },
+        ])
+    );
+
+    assert_eq!(entries("\n\nfoo"), Err(3));
+    assert_eq!(entries("[]"), Err(1));
+    assert_eq!(entries(""), Ok(vec![]));
+}
+
+#[test]
+fn parse_test() {
+    assert_eq!(parse_string("\"a b\""), Some("a b".to_owned()));
+    assert_eq!(parse_string("a"), None);
+    assert_eq!(parse_string("\"a\" \"b\""), None);
+
+    assert_eq!(
+        parse_string_array(r#"[ "a", "b", ]"#),
+        Some(vec!["a".to_owned(), "b".to_owned()])
+    );
+    assert_eq!(parse_string_array("[]"), Some(vec![]));
+    assert_eq!(parse_string_array("[\"a\" \"b\"]"), None);
+    assert_eq!(parse_string_array("[a]"), None);
+    assert_eq!(parse_string_array("\"a\""), None);
+    assert_eq!(
+        parse_string_array(r#"["a,b"]"#),
+        Some(vec!["a,b".to_owned()])
+    );
+
+    assert_eq!(
+        parse_inline_table(r#"{ path = "a, b", version = "1" }"#),
+        Some(vec![
+            ("path".to_owned(), "\"a, b\""),
+            ("version".to_owned(), "\"1\"")
+        ])
+    );
+    assert_eq!(parse_inline_table("{}"), Some(vec![]));
+    assert_eq!(parse_inline_table("{ path }"), None);
+    assert_eq!(parse_inline_table("\"path\""), None);
+}
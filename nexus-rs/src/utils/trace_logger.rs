@@ -0,0 +1,59 @@
+use log::{LevelFilter, Log, Metadata, Record, SetLoggerError};
+
+/// Minimal logger, writing records for specific log targets (prefixes) to stderr.
+///
+/// Used for developer tracing output (e.g. parser rule tracing) and verbose output (see [`cli`](crate::cli)), without
+///  pulling in a full logging framework.
+///
+/// # Example
+///
+/// ```no_run
+/// use nexus_rs::trace_logger::TraceLogger;
+///
+/// TraceLogger::init("nexus_rs::parser").unwrap();
+/// ```
+pub struct TraceLogger {
+    filters: Vec<(&'static str, LevelFilter)>, // Log targets with their maximum level.
+}
+
+impl TraceLogger {
+    /// Install a trace logger for a log target as the global logger.
+    ///
+    /// This fails if a global logger was installed already.
+    pub fn init(target: &'static str) -> Result<(), SetLoggerError> {
+        Self::init_with_filters(vec![(target, LevelFilter::Trace)])
+    }
+
+    /// Install a logger for log targets, each with a maximum level, as the global logger.
+    ///
+    /// This fails if a global logger was installed already.
+    pub fn init_with_filters(
+        filters: Vec<(&'static str, LevelFilter)>,
+    ) -> Result<(), SetLoggerError> {
+        let max = filters
+            .iter()
+            .map(|(_, l)| *l)
+            .max()
+            .unwrap_or(LevelFilter::Off);
+
+        log::set_logger(Box::leak(Box::new(TraceLogger { filters })))?;
+        log::set_max_level(max);
+        Ok(())
+    }
+}
+
+impl Log for TraceLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.filters.iter().any(|(target, level)| {
+            metadata.target().starts_with(target) && metadata.level() <= *level
+        })
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            eprintln!("[{}] {}", record.target(), record.args());
+        }
+    }
+
+    fn flush(&self) {}
+}
@@ -0,0 +1,183 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+#[cfg(test)]
+use pretty_assertions::assert_eq;
+
+/// Default interval between checks for file changes.
+pub const DEFAULT_POLL: Duration = Duration::from_millis(100);
+
+/// Default time without further changes before a change is reported.
+pub const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Polling file watcher, detecting changes by file modification time.
+///
+/// A file that appears or disappears also counts as a change. Changes are debounced: editors often write a file in
+///  multiple steps (e.g. truncate, write, rename), so a change is only reported after the files have been unchanged
+///  for a while.
+///
+/// # Example
+///
+/// ```no_run
+/// use nexus_rs::watcher::Watcher;
+///
+/// let mut watcher = Watcher::new(["main.nxs".into(), "lib.nxs".into()]);
+///
+/// loop {
+///     watcher.wait();
+///     println!("changed!");
+/// }
+/// ```
+pub struct Watcher {
+    files: Vec<(PathBuf, Option<SystemTime>)>, // Watched files, with their last seen modification time.
+    created: SystemTime,
+    poll: Duration,
+    debounce: Duration,
+}
+
+impl Watcher {
+    /// Create a new watcher for the given files, with the current state of the files as reference.
+    pub fn new(paths: impl IntoIterator<Item = PathBuf>) -> Self {
+        Watcher {
+            files: paths
+                .into_iter()
+                .map(|p| (p.clone(), modified(&p)))
+                .collect(),
+            created: SystemTime::now(),
+            poll: DEFAULT_POLL,
+            debounce: DEFAULT_DEBOUNCE,
+        }
+    }
+
+    /// Set the poll interval and debounce time.
+    pub fn with_timing(mut self, poll: Duration, debounce: Duration) -> Self {
+        self.poll = poll;
+        self.debounce = debounce;
+        self
+    }
+
+    /// Watch additional files (e.g. discovered while running). A file modified after creating the watcher counts as
+    ///  changed, so changes made while the files were discovered are not missed.
+    pub fn add(&mut self, paths: impl IntoIterator<Item = PathBuf>) {
+        for path in paths {
+            if self.files.iter().all(|(p, _)| *p != path) {
+                // Without a reference time, an existing file is reported as changed on the next check.
+                let time = modified(&path).filter(|&t| t <= self.created);
+                self.files.push((path, time));
+            }
+        }
+    }
+
+    /// Get the watched files.
+    pub fn paths(&self) -> impl Iterator<Item = &PathBuf> {
+        self.files.iter().map(|(p, _)| p)
+    }
+
+    /// Check if any of the files changed since the last check (or since creating the watcher).
+    pub fn changed(&mut self) -> bool {
+        let mut changed = false;
+
+        for (path, time) in &mut self.files {
+            let current = modified(path);
+            if current != *time {
+                *time = current;
+                changed = true;
+            }
+        }
+
+        changed
+    }
+
+    /// Block until any of the files changed, and then until the files are unchanged for the debounce time.
+    pub fn wait(&mut self) {
+        while !self.changed() {
+            thread::sleep(self.poll);
+        }
+
+        loop {
+            thread::sleep(self.debounce);
+            if !self.changed() {
+                break;
+            }
+        }
+    }
+}
+
+/// Get the modification time of a file, or `None` if it does not exist (or cannot be accessed).
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+#[test]
+fn changed_test() {
+    let dir = std::env::temp_dir().join(format!("nexus_watcher_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    let (main, lib) = (dir.join("main.nxs"), dir.join("lib.nxs"));
+    fs::write(&main, "use \"lib.nxs\";").unwrap();
+    let _ = fs::remove_file(&lib);
+
+    let mut watcher = Watcher::new([main.clone(), lib.clone()]);
+    assert_eq!(watcher.paths().count(), 2);
+    assert!(!watcher.changed());
+
+    // Created file.
+    fs::write(&lib, "let x = 1;").unwrap();
+    assert!(watcher.changed());
+    assert!(!watcher.changed());
+
+    // Modified file (the time is set explicitly, as the file system time resolution may be coarse).
+    let file = fs::File::options().write(true).open(&main).unwrap();
+    file.set_modified(SystemTime::UNIX_EPOCH).unwrap();
+    assert!(watcher.changed());
+    assert!(!watcher.changed());
+
+    // Removed file.
+    fs::remove_file(&lib).unwrap();
+    assert!(watcher.changed());
+
+    // Added files: unchanged since creating the watcher, and modified since.
+    let mut watcher = Watcher::new([lib.clone()]);
+    let file = fs::File::options().write(true).open(&main).unwrap();
+    file.set_modified(SystemTime::UNIX_EPOCH).unwrap();
+    watcher.add([main.clone(), lib.clone()]);
+    assert_eq!(watcher.paths().count(), 2);
+    assert!(!watcher.changed());
+
+    fs::write(&lib, "let y = 2;").unwrap();
+    let file = fs::File::options().write(true).open(&lib).unwrap();
+    file.set_modified(SystemTime::now() + Duration::from_secs(60))
+        .unwrap();
+    let mut watcher = Watcher::new([]);
+    watcher.add([lib.clone()]);
+    assert!(watcher.changed());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn wait_test() {
+    let path = std::env::temp_dir().join(format!("nexus_watcher_wait_{}.nxs", std::process::id()));
+    let _ = fs::remove_file(&path);
+
+    let mut watcher = Watcher::new([path.clone()])
+        .with_timing(Duration::from_millis(5), Duration::from_millis(20));
+
+    let writer = {
+        let path = path.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            fs::write(&path, "let x = 1;").unwrap();
+        })
+    };
+
+    watcher.wait();
+    writer.join().unwrap();
+    assert!(!watcher.changed());
+
+    fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,69 @@
+//! Guard of the stable API (see `nexus_rs::prelude`): changes breaking this test require a major version bump.
+
+use nexus_rs::prelude::*;
+
+/// Check the prelude item signatures used for embedding Nexus.
+#[test]
+fn prelude_test() {
+    // Driver pipeline.
+    let mut output = Vec::new();
+    let options = DriverOptions {
+        stop_after: Stage::Run,
+        edition: Some(Edition::LATEST),
+        limits: Limits::default(),
+        numeric_mode: NumericMode::Ieee,
+        clock: Clock::System,
+        seed: None,
+        interrupt: None,
+        args: vec!["arg".to_owned()],
+        deny_io: true,
+        lints: LintLevels::default(),
+        search_dirs: Vec::new(),
+        packages: Default::default(),
+    };
+    let session: Session =
+        Driver::with_output(options, &mut output).run_source("main", "print 1 + 1;");
+    assert!(!session.has_errors());
+    assert_eq!(session.value, Some(Value::Unit));
+
+    let session =
+        Driver::with_output(DriverOptions::default(), Vec::new()).run_source("main", "x;");
+    let diagnostic: &Diagnostic = &session.diagnostics[0];
+    assert!(matches!(
+        diagnostic,
+        Diagnostic::Runtime(RuntimeError::UndefinedVariable(..), Some(_))
+    ));
+    assert_eq!(diagnostic.stage(), Stage::Run);
+
+    // Front-end.
+    let (tokens, errors): (Tokens, Vec<ScanError>) = Scanner::with_options(ScanOptions::default())
+        .scan(SourceLine {
+            line: "1 + 2;".to_owned(),
+            number: Some(1),
+        });
+    assert!(errors.is_empty());
+    assert_eq!(tokens[1], Token::Plus);
+
+    let ast: ast::Stmts = Parser::new(tokens).parse().unwrap();
+    let _: Result<ast::Stmts, FrontError> = parse_source("1 + 2;");
+
+    // Interpreter.
+    let limits = Limits {
+        max_steps: Some(100),
+        ..Default::default()
+    };
+    assert_eq!(
+        Interpreter::with_output(std::io::sink())
+            .with_limits(limits)
+            .run(&ast),
+        Ok(Value::Number(3.0))
+    );
+    assert!(!Limit::Steps(1).to_string().is_empty());
+
+    // Errors and positions.
+    let _: fn(&ParseError) -> &ParseErrorKind = ParseError::kind;
+    let _: fn(&ScanError) -> (Option<usize>, usize) = |e| (e.line(), e.column());
+    let _ = ScanErrorKind::UnexpectedCharacter;
+    let _: Option<Span> = None::<Spans>.and_then(|s| s.first().copied());
+    assert_eq!(Symbol::intern("a").as_str(), "a");
+}
@@ -0,0 +1,444 @@
+//! Property-based fuzzing of the scanner/parser pipeline.
+//!
+//! Random inputs (byte strings, token soups, grammar-derived programs and mutations thereof) are fed into the scanner
+//!  and parser, asserting that they never panic. Failing inputs are shrunk to a minimal crashing input, which should be
+//!  added to `REGRESSIONS` after fixing the crash.
+//!
+//! Grammar-derived programs are also executed differentially: a program and its minified forms must behave the same
+//!  in the interpreter. Additional execution engines are to be checked against the tree-walking interpreter likewise.
+//!
+//! The number of cases per property and the random seed can be set for longer fuzzing runs, e.g.:
+//!
+//! ```text
+//! NEXUS_FUZZ_CASES=1000000 NEXUS_FUZZ_SEED=42 cargo test --release --test fuzz_tests
+//! ```
+
+use nexus_rs::{
+    ast::Stmts,
+    front::parse_source,
+    interpreter::Interpreter,
+    limits::Limits,
+    minify::{minify, MinifyOptions},
+    parser::Parser,
+    scanner::Scanner,
+    source_line::SourceLine,
+    span::Spans,
+    token::Tokens,
+};
+use std::{env, panic};
+
+/// Minimal crashing inputs found by fuzzing, kept as regression tests.
+const REGRESSIONS: &[&str] = &[];
+
+/// Default number of cases per property.
+const DEFAULT_CASES: usize = 2000;
+
+/// Small, deterministic pseudo-random number generator (xorshift64*).
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Rng(seed.max(1))
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.below(items.len())]
+    }
+
+    fn chance(&mut self, percent: usize) -> bool {
+        self.below(100) < percent
+    }
+}
+
+fn cases() -> usize {
+    env::var("NEXUS_FUZZ_CASES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_CASES)
+}
+
+fn seed() -> u64 {
+    env::var("NEXUS_FUZZ_SEED")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0x006e_6578_7573)
+}
+
+/// Run the scanner and parser on a source text, returning the panic message (if any).
+fn run_pipeline(code: &str) -> Result<(), String> {
+    panic::catch_unwind(|| {
+        let mut scanner = Scanner::new();
+        let mut tokens = Tokens::new();
+        let mut spans = Spans::new();
+
+        for (number, line) in code.lines().enumerate() {
+            let (mut t, mut s, errors) = scanner.scan_with_spans(SourceLine {
+                line: line.to_string(),
+                number: Some(number + 1),
+            });
+            tokens.append(&mut t);
+            spans.append(&mut s);
+
+            for e in errors {
+                let _ = e.to_string();
+            }
+        }
+
+        if let Err(e) = Parser::with_spans(tokens, spans).parse() {
+            let _ = e.render(&[]);
+        }
+    })
+    .map_err(|e| {
+        e.downcast_ref::<String>()
+            .cloned()
+            .or_else(|| e.downcast_ref::<&str>().map(|s| s.to_string()))
+            .unwrap_or_default()
+    })
+}
+
+/// Shrink a failing input to a (locally) minimal input that still fails, by removing ever smaller chunks.
+fn shrink(input: &str, fails: impl Fn(&str) -> bool) -> String {
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+
+    let mut chars = input.chars().collect::<Vec<_>>();
+    let mut chunk = chars.len() / 2;
+
+    while chunk > 0 {
+        let mut start = 0;
+        let mut shrunk = false;
+
+        while start + chunk <= chars.len() {
+            let candidate = [&chars[..start], &chars[start + chunk..]].concat();
+            if fails(&candidate.iter().collect::<String>()) {
+                chars = candidate;
+                shrunk = true;
+            } else {
+                start += chunk;
+            }
+        }
+
+        if !shrunk {
+            chunk /= 2;
+        }
+    }
+
+    panic::set_hook(hook);
+    chars.into_iter().collect()
+}
+
+/// Check that the pipeline does not panic for any generated input.
+fn check_no_panic(mut generate: impl FnMut(&mut Rng) -> String) {
+    let mut rng = Rng::new(seed());
+
+    for _ in 0..cases() {
+        let input = generate(&mut rng);
+
+        if let Err(message) = run_pipeline(&input) {
+            let minimal = shrink(&input, |s| run_pipeline(s).is_err());
+            panic!("pipeline panicked ({message}), minimal crashing input: {minimal:?}");
+        }
+    }
+}
+
+const TOKENS: &[&str] = &[
+    "(", ")", "{", "}", "[", "]", ":", ";", "+", "-", "->", "*", "\\", "%", ",", "?", ".", "..",
+    "..=", "_", "=", "==", "|", "||", ">", ">=", "<", "<=", "!", "!=", "&", "&&", "/", "//", "/*",
+    "*/", "\"", "\"str\"", "0", "42", "3.14", "1.", "x", "y1", "Group", "Node", "Number", "String",
+    "bool", "const", "defer", "else", "false", "fn", "for", "group", "if", "in", "let", "loop",
+    "mut", "nil", "node", "print", "println", "return", "time", "true", "try", "use", "while", "é",
+    "\t", "\n",
+];
+
+/// Identifiers, including built-in function names and `main` (which must be kept when minifying with renaming).
+const IDS: &[&str] = &["a", "b", "x1", "foo", "ŮñĭçøƋɇ", "abs", "len", "pi", "main"];
+
+/// Grammar-derived random program generator.
+struct Generator<'r> {
+    rng: &'r mut Rng,
+    depth: usize,
+}
+
+impl Generator<'_> {
+    fn program(&mut self) -> String {
+        (0..self.rng.below(8))
+            .map(|_| self.decl())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn decl(&mut self) -> String {
+        match self.rng.below(11) {
+            0 => format!("const {}: Number = {};", self.id(), self.rng.below(100)),
+            1 => format!(
+                "fn {}({}) -> Number {}",
+                self.id(),
+                ["", "p: Number", "p: Number, q: String"][self.rng.below(3)],
+                self.block()
+            ),
+            2 => format!(
+                "let {}{}{};",
+                if self.rng.chance(30) { "mut " } else { "" },
+                self.id(),
+                [
+                    String::new(),
+                    format!(" = {}", self.expr()),
+                    " : bool".to_owned()
+                ][self.rng.below(3)]
+            ),
+            3 => format!("use \"{}.nxs\";", self.id()),
+            4 => format!("print {};", self.args()),
+            5 => format!(
+                "println{};",
+                if self.rng.chance(50) {
+                    format!(" {}", self.args())
+                } else {
+                    String::new()
+                }
+            ),
+            6 => format!("{} = {};", self.id(), self.expr()),
+            7 => format!("{}.{} -> {};", self.id(), self.id(), self.id()),
+            8 => format!("return {};", self.expr()),
+            9 => format!("defer {}", self.block()),
+            _ => format!("{};", self.expr()),
+        }
+    }
+
+    fn block(&mut self) -> String {
+        if self.depth > 3 {
+            return "{}".to_owned();
+        }
+
+        self.depth += 1;
+        let body = (0..self.rng.below(3))
+            .map(|_| self.decl())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let tail = if self.rng.chance(50) {
+            format!(" {}", self.term())
+        } else {
+            String::new()
+        };
+        self.depth -= 1;
+
+        format!("{{ {body}{tail} }}")
+    }
+
+    fn args(&mut self) -> String {
+        (0..1 + self.rng.below(3))
+            .map(|_| self.expr())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn expr(&mut self) -> String {
+        if self.depth > 3 {
+            return self.term();
+        }
+
+        self.depth += 1;
+        let result = match self.rng.below(8) {
+            0 => {
+                let op = self.rng.pick(&[
+                    "+", "-", "*", "**", "/", "%", "==", "!=", "<", "<=", ">", ">=", "&&", "||",
+                ]);
+
+                // Comparisons do not chain, so the right-hand side of a comparison is grouped.
+                match op {
+                    "<" | "<=" | ">" | ">=" => format!("{} {op} ({})", self.term(), self.expr()),
+                    _ => format!("{} {op} {}", self.term(), self.expr()),
+                }
+            }
+            1 => format!(
+                "{}{}",
+                self.rng.pick(&["!", "-", "+", "node ", "group ", "try "]),
+                self.expr()
+            ),
+            2 => format!("({})", self.expr()),
+            3 => format!("if {} {} else {}", self.term(), self.block(), self.block()),
+            4 => format!("while {} {}", self.term(), self.block()),
+            5 => format!("for {} in {} {}", self.id(), self.range(), self.block()),
+            6 => self.block(),
+            _ => self.term(),
+        };
+        self.depth -= 1;
+
+        result
+    }
+
+    fn term(&mut self) -> String {
+        match self.rng.below(6) {
+            0 => self.rng.below(1000).to_string(),
+            1 => format!("{}.{}", self.rng.below(100), self.rng.below(100)),
+            2 => format!(
+                "\"{}\"",
+                self.rng
+                    .pick(&["", "text", "with \\\"quotes\\\"", "ünïcode"])
+            ),
+            3 => self.rng.pick(&["true", "false", "nil"]).to_owned(),
+            4 => format!(
+                "{}({}){}",
+                self.id(),
+                if self.rng.chance(50) {
+                    self.args()
+                } else {
+                    String::new()
+                },
+                if self.rng.chance(20) { "?" } else { "" }
+            ),
+            _ => self.id(),
+        }
+    }
+
+    /// Generate a range, counting down (with a negative step) if the literal bounds are reversed.
+    fn range(&mut self) -> String {
+        let (start, end) = (self.range_delimiter(), self.range_delimiter());
+        let op = self.rng.pick(&["..", "..="]);
+
+        match (start.parse::<u64>(), end.parse::<u64>()) {
+            (Ok(s), Ok(e)) if s > e => format!("{start}{op}{end} step -1"),
+            _ => format!("{start}{op}{end}"),
+        }
+    }
+
+    fn range_delimiter(&mut self) -> String {
+        match self.rng.below(3) {
+            0 => self.rng.below(100).to_string(),
+            1 => format!("({})", self.expr()),
+            _ => self.id(),
+        }
+    }
+
+    fn id(&mut self) -> String {
+        self.rng.pick(IDS).to_owned()
+    }
+}
+
+fn generate_program(rng: &mut Rng) -> String {
+    Generator { rng, depth: 0 }.program()
+}
+
+/// Random byte strings (lossily converted to UTF-8).
+#[test]
+fn fuzz_bytes_test() {
+    check_no_panic(|rng| {
+        let bytes = (0..rng.below(64))
+            .map(|_| rng.next() as u8)
+            .collect::<Vec<_>>();
+        String::from_utf8_lossy(&bytes).into_owned()
+    });
+}
+
+/// Random sequences of (partial) tokens.
+#[test]
+fn fuzz_token_soup_test() {
+    check_no_panic(|rng| {
+        (0..rng.below(32))
+            .map(|_| format!("{}{}", rng.pick(TOKENS), rng.pick(&["", "", " ", "\n"])))
+            .collect()
+    });
+}
+
+/// Grammar-derived programs, which must be valid and minify to valid (canonical) source code.
+#[test]
+fn fuzz_grammar_test() {
+    let mut rng = Rng::new(seed());
+
+    for _ in 0..cases() {
+        let code = generate_program(&mut rng);
+
+        let ast = parse_source(&code)
+            .unwrap_or_else(|e| panic!("generated program is invalid ({e}):\n{code}"));
+
+        for options in [MinifyOptions::default(), MinifyOptions { rename: true }] {
+            let min = minify(&ast, options);
+            let reparsed = parse_source(&min)
+                .unwrap_or_else(|e| panic!("minified program is invalid ({e}):\n{min}"));
+
+            assert_eq!(minify(&reparsed, options), min, "{code}");
+        }
+    }
+}
+
+/// Execute a program and its `main` function (if any), returning the results and output. Errors are only compared by
+///  kind, as renaming changes the identifiers in error messages.
+fn execute(ast: &Stmts) -> (String, String) {
+    let limits = Limits {
+        max_steps: Some(10_000),
+        ..Default::default()
+    };
+
+    let format = |result: Result<_, _>| match result {
+        Ok(value) => format!("{value:?}"),
+        Err(e) => format!("{:?}", std::mem::discriminant(&e)),
+    };
+
+    let mut output = Vec::new();
+    let mut interpreter = Interpreter::with_output(&mut output).with_limits(limits);
+    let mut result = format(interpreter.run(ast));
+    if !result.starts_with("Discriminant") {
+        result += &format!(", main: {:?}", interpreter.run_main().map(format));
+    }
+    drop(interpreter);
+
+    (result, String::from_utf8_lossy(&output).into_owned())
+}
+
+/// Grammar-derived programs and their minified forms, which must behave the same when executed.
+#[test]
+fn fuzz_differential_test() {
+    let mut rng = Rng::new(seed());
+
+    for _ in 0..cases() {
+        let code = generate_program(&mut rng);
+        let ast = parse_source(&code).unwrap();
+        let expected = execute(&ast);
+
+        for options in [MinifyOptions::default(), MinifyOptions { rename: true }] {
+            let min = minify(&ast, options);
+            let reparsed = parse_source(&min).unwrap();
+
+            assert_eq!(execute(&reparsed), expected, "{code}\n\nminified:\n{min}");
+        }
+    }
+}
+
+/// Grammar-derived programs with random character-level mutations.
+#[test]
+fn fuzz_mutation_test() {
+    check_no_panic(|rng| {
+        let mut chars = generate_program(rng).chars().collect::<Vec<_>>();
+
+        for _ in 0..1 + rng.below(4) {
+            let at = rng.below(chars.len() + 1);
+            match rng.below(3) {
+                0 if at < chars.len() => {
+                    chars.remove(at);
+                }
+                1 => chars.insert(at, rng.pick(TOKENS).chars().next().unwrap_or(' ')),
+                _ => chars.truncate(at),
+            }
+        }
+
+        chars.into_iter().collect()
+    });
+}
+
+/// Minimal crashing inputs found earlier.
+#[test]
+fn fuzz_regressions_test() {
+    for input in REGRESSIONS {
+        assert_eq!(run_pipeline(input), Ok(()), "{input:?}");
+    }
+}
@@ -1,6 +1,19 @@
 use std::{ffi::OsStr, fs, path::Path};
 
-use nexus_rs::{parser::Parser, scanner::Scanner, source_line::SourceLine};
+use nexus_rs::{
+    ast::{Expr, ExprKind, StmtKind},
+    ast_dump::{dump, DumpFormat},
+    driver::{Driver, DriverOptions, Stage},
+    front::{parse_source, FrontError},
+    interpreter::Interpreter,
+    minify::{minify, MinifyOptions},
+    parse_error::ParseErrorKind,
+    parser::Parser,
+    scanner::{ScanOptions, Scanner},
+    source_line::SourceLine,
+};
+
+mod snapshot;
 
 const CODE_PATH: &str = "tests/test_code/";
 
@@ -10,26 +23,774 @@ fn parser_test() {
     for entry in fs::read_dir(CODE_PATH).unwrap_or_else(|e| panic!("{e}")) {
         let filename = entry.expect("invalid directory entry").file_name();
         if Path::new(&filename).extension().and_then(OsStr::to_str) == Some("nxs") {
-            let code = fs::read_to_string(CODE_PATH.to_owned() + filename.to_str().unwrap())
-                .unwrap_or_else(|e| panic!("{e}"))
-                .trim()
-                .to_owned();
-
             println!("Parsing {filename:?}..");
 
-            let mut scanner = Scanner::new();
-            let mut parser = Parser::new(
-                scanner
+            let path = CODE_PATH.to_owned() + filename.to_str().unwrap();
+            let code = fs::read_to_string(&path).unwrap_or_else(|e| panic!("{e}"));
+
+            let session = Driver::new(DriverOptions {
+                stop_after: Stage::Parse,
+                ..Default::default()
+            })
+            .run_source(&path, &code);
+
+            for diagnostic in &session.diagnostics {
+                println!("{}", session.render(diagnostic));
+            }
+
+            assert!(!session.has_errors());
+
+            println!("..done");
+        }
+    }
+}
+
+/// Compare the AST of each Nexus source file in `CODE_PATH` against its `.ast` snapshot (S-expression dump).
+#[test]
+fn parser_snapshot_test() {
+    for entry in fs::read_dir(CODE_PATH).unwrap_or_else(|e| panic!("{e}")) {
+        let path = entry.expect("invalid directory entry").path();
+        if path.extension().and_then(OsStr::to_str) == Some("nxs") {
+            let code = fs::read_to_string(&path).unwrap_or_else(|e| panic!("{e}"));
+            let ast = parse_source(&code).unwrap_or_else(|e| panic!("{path:?}: {e}"));
+
+            snapshot::assert_snapshot(&path.with_extension("ast"), &dump(&ast, DumpFormat::Sexp));
+        }
+    }
+}
+
+/// Check the source spans attached to parse errors.
+#[test]
+fn parser_error_span_test() {
+    let test = |code: &str, expected: (usize, usize)| {
+        let (tokens, spans, _) = Scanner::new().scan_with_spans(SourceLine {
+            line: code.to_string(),
+            number: Some(1),
+        });
+
+        let e = Parser::with_spans(tokens, spans).parse().unwrap_err();
+        let span = e.span().expect("missing error span");
+
+        assert_eq!(
+            (span.line, span.start, span.end),
+            (Some(1), expected.0, expected.1),
+            "{code}"
+        );
+    };
+
+    test("let = 3;", (4, 5));
+    test("let x = 1", (9, 10));
+    test("print (1;", (8, 9));
+    test("fn for() {}", (3, 6));
+    test("x = 1 + 2..3;", (9, 11));
+    test("a = || b;", (4, 6));
+    test("{ print 1;", (10, 11));
+    test("0.. = 10;", (1, 5));
+    test("1 + 2 = 3;", (0, 5));
+}
+
+/// Check that a pipeline only accepts a function name or call as target.
+#[test]
+fn pipeline_target_test() {
+    let test = |code: &str| {
+        let e = Parser::new(
+            Scanner::new()
+                .scan(SourceLine {
+                    line: code.to_string(),
+                    number: None,
+                })
+                .0,
+        )
+        .parse()
+        .unwrap_err();
+        assert!(
+            matches!(e.kind(), ParseErrorKind::PipelineTarget),
+            "{code}: {e}"
+        );
+    };
+
+    test("x |> 1;");
+    test("x |> f + 1;");
+    test("x |> f == y;");
+    test("x |> (f);");
+    test("x |> f |> \"g\";");
+}
+
+#[test]
+fn connect_buffer_test() {
+    let test = |code: &str| {
+        Parser::new(
+            Scanner::new()
+                .scan(SourceLine {
+                    line: code.to_string(),
+                    number: None,
+                })
+                .0,
+        )
+        .parse()
+        .map_err(|e| (e.kind().code(), e.to_string()))
+    };
+
+    assert!(test("a -> b [capacity: 1, overflow: block];").is_ok());
+    assert!(test("a -> b [capacity: x[0]];").is_ok());
+    assert!(matches!(test("a -> b [size: 1];"), Err(("NXS0118", _))));
+    assert!(matches!(
+        test("a -> b [overflow: drop];"),
+        Err(("NXS0119", _))
+    ));
+    assert!(matches!(
+        test("a -> b [capacity: 1, capacity: 2];"),
+        Err(("NXS0100", _))
+    ));
+
+    // Disconnecting has no buffering policy.
+    assert!(test("a -/> b [capacity: 1];").is_err());
+}
+
+/// Check the source spans attached to statements: from the first token up to the last token on the same line.
+#[test]
+fn stmt_span_test() {
+    let ast = parse_source("let x = 1;\n  print x;\nfn f() {\n  x;\n}\ndefer x = 2;").unwrap();
+    let span = |stmt: &nexus_rs::ast::Stmt| stmt.span.map(|s| (s.line.unwrap(), s.start, s.end));
+
+    assert_eq!(span(&ast[0]), Some((1, 0, 10)));
+    assert_eq!(span(&ast[1]), Some((2, 2, 10)));
+    assert_eq!(span(&ast[2]), Some((3, 0, 2)));
+    assert_eq!(span(&ast[3]), Some((6, 0, 12)));
+
+    let StmtKind::FunctionDecl(f) = &ast[2].kind else {
+        panic!("expected a function declaration");
+    };
+    let StmtKind::Block(body) = &f.body.kind else {
+        panic!("expected a block");
+    };
+    assert_eq!(span(&body[0]), Some((4, 2, 4)));
+
+    let StmtKind::Defer(deferred) = &ast[3].kind else {
+        panic!("expected a deferred statement");
+    };
+    assert_eq!(span(deferred), Some((6, 6, 12)));
+}
+
+/// Check that exclusive and inclusive ranges are parsed into the right range kind.
+#[test]
+fn range_kind_test() {
+    let test = |code: &str, expected: &str| {
+        let (tokens, _) = Scanner::new().scan(SourceLine {
+            line: code.to_string(),
+            number: None,
+        });
+
+        let ast = Parser::new(tokens).parse().unwrap();
+        assert!(ast[0].to_string().contains(expected), "{code}");
+    };
+
+    test("0..10;", " .. ");
+    test("0..=10;", " ..= ");
+    test("a..=(b + 1);", " ..= ");
+}
+
+/// Check that a range operator separated from '=' is not silently parsed as an inclusive range.
+#[test]
+fn split_range_inclusive_test() {
+    let test = |code: &str| {
+        let (tokens, _) = Scanner::new().scan(SourceLine {
+            line: code.to_string(),
+            number: None,
+        });
+
+        let e = Parser::new(tokens).parse().unwrap_err();
+        assert!(
+            matches!(e.kind(), ParseErrorKind::SplitRangeInclusive),
+            "{code}: {e}"
+        );
+    };
+
+    test("0.. = 10;");
+    test("x = a.. =b;");
+    test("for i in 0.. = 3 { print i; }");
+}
+
+/// Check that references are only accepted as variable initializer, referring to a variable or member.
+#[test]
+fn reference_test() {
+    let test = |code: &str| {
+        let (tokens, _) = Scanner::new().scan(SourceLine {
+            line: code.to_string(),
+            number: None,
+        });
+
+        Parser::new(tokens)
+            .parse()
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    };
+
+    assert!(test("let r = &x;").is_ok());
+    assert!(test("let mut r = &x.y.z;").is_ok());
+
+    for code in [
+        "let r = &1;",
+        "let r = &(x);",
+        "let r = &f();",
+        "let r = &x + 1;",
+    ] {
+        assert!(
+            test(code)
+                .unwrap_err()
+                .contains("must refer to a variable or member"),
+            "{code}"
+        );
+    }
+
+    for code in ["let r = 1 + &x;", "f(&x);", "&x;", "x = &y;"] {
+        assert!(
+            test(code)
+                .unwrap_err()
+                .contains("only allowed as variable initializer"),
+            "{code}"
+        );
+    }
+}
+
+/// Check that broken programs are rejected with an error instead of aborting the parser.
+#[test]
+fn broken_program_test() {
+    let test = |code: &str| {
+        let result = std::panic::catch_unwind(|| parse_source(code).map(|_| ()));
+        assert!(result.is_ok(), "parser panicked on {code:?}");
+        result.unwrap()
+    };
+
+    for code in [
+        "{",
+        "}",
+        "{ let x = 1;",
+        "let x = (1 + 2;",
+        "let = 1;",
+        "let x",
+        "const x: = 1;",
+        "fn (",
+        "fn f(x: Number -> Number { x }",
+        "fn f() -> { }",
+        "f(1, 2",
+        "print;",
+        "if { }",
+        "if true { } else",
+        "while",
+        "loop",
+        "loop x { }",
+        "for x in { }",
+        "for in 0..2 { }",
+        "return",
+        "defer",
+        "defer let x = 1;",
+        "try",
+        "?;",
+        "x = = 1;",
+        "1 ..= ;",
+        ".;",
+    ] {
+        assert!(test(code).is_err(), "{code:?} parsed without error");
+    }
+
+    // Every truncation of a valid program either parses or fails, but never panics.
+    for entry in fs::read_dir(CODE_PATH).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().and_then(OsStr::to_str) == Some("nxs") {
+            let code = fs::read_to_string(&path).unwrap();
+            for (i, _) in code.char_indices() {
+                let _ = test(&code[..i]);
+            }
+        }
+    }
+}
+
+/// Check the diagnostics for statements missing their terminating semicolon.
+#[test]
+fn missing_semicolon_test() {
+    let test = |lines: &[&str], missing: bool, expected: (usize, usize, usize)| {
+        let mut scanner = Scanner::new();
+        let (mut tokens, mut spans) = (Vec::new(), Vec::new());
+
+        for (n, line) in lines.iter().enumerate() {
+            let (mut t, mut s, _) = scanner.scan_with_spans(SourceLine {
+                line: line.to_string(),
+                number: Some(n + 1),
+            });
+            tokens.append(&mut t);
+            spans.append(&mut s);
+        }
+
+        let e = Parser::with_spans(tokens, spans).parse().unwrap_err();
+        let span = e.span().expect("missing error span");
+
+        assert_eq!(
+            matches!(e.kind(), ParseErrorKind::MissingSemiColon),
+            missing,
+            "{lines:?}: {e}"
+        );
+        assert_eq!(
+            (span.line, span.start, span.end),
+            (Some(expected.0), expected.1, expected.2),
+            "{lines:?}"
+        );
+    };
+
+    test(&["let x = 1", "let y = 2;"], true, (1, 9, 10));
+    test(&["let x = 1", "y = 2;"], true, (1, 9, 10));
+    test(&["x = 1 let y = 2;"], true, (1, 5, 6));
+    test(&["fn f() { return 1 }"], true, (1, 17, 18));
+    test(&["print 1"], true, (1, 7, 8));
+    test(&["print 1 2;"], false, (1, 8, 9));
+    test(&["x -> y z;"], false, (1, 7, 8));
+}
+
+/// Check the diagnostic for an operand following an expression, on the same line.
+#[test]
+fn expected_operator_test() {
+    for code in [
+        "let x = 1 2;",
+        "print a b;",
+        "return 1 (2);",
+        "let x = a + b c;",
+    ] {
+        let e = parse_source(code).unwrap_err();
+        assert!(
+            matches!(&e, FrontError::Parse(p) if matches!(p.kind(), ParseErrorKind::ExpectedOperator)),
+            "{code}: {e}"
+        );
+    }
+}
+
+/// Render an expression fully parenthesized, to check operator precedence and associativity.
+fn parenthesize(expr: &Expr) -> String {
+    match &expr.kind {
+        ExprKind::Binary(b) => format!(
+            "({} {:?} {})",
+            parenthesize(&b.lhs),
+            b.op,
+            parenthesize(&b.rhs)
+        ),
+        ExprKind::Unary(u) => format!("({:?} {})", u.op, parenthesize(&u.expr)),
+        ExprKind::Range(r) => format!(
+            "({} {} {})",
+            parenthesize(&r.start),
+            r.kind,
+            parenthesize(&r.end)
+        ),
+        ExprKind::Group(e) => parenthesize(e),
+        ExprKind::Var(v) => v.id.to_string(),
+        _ => "?".to_owned(),
+    }
+}
+
+/// Check that only statements can be deferred.
+#[test]
+fn defer_test() {
+    assert!(parse_source("defer print 1; defer { let x = 1; } defer x = f(); defer g();").is_ok());
+
+    for code in [
+        "defer let x = 1;",
+        "defer return;",
+        "defer defer f();",
+        "defer fn f() {}",
+    ] {
+        assert!(
+            matches!(
+                parse_source(code),
+                Err(FrontError::Parse(e)) if matches!(e.kind(), ParseErrorKind::DeferTarget)
+            ),
+            "{code}"
+        );
+    }
+}
+
+/// Check operator precedence and associativity of binary expressions.
+#[test]
+fn operator_precedence_test() {
+    let test = |code: &str, expected: &str| {
+        let ast = parse_source(&format!("{code};")).unwrap();
+        let StmtKind::Expr(expr) = &ast[0].kind else {
+            panic!("{code}: not an expression statement");
+        };
+
+        assert_eq!(parenthesize(expr), expected, "{code}");
+    };
+
+    test("a + b * c", "(a Plus (b Multiply c))");
+    test("a * b + c", "((a Multiply b) Plus c)");
+    test("a - b - c", "((a Subtract b) Subtract c)");
+    test("a / b % c * d", "(((a Divide b) Remainder c) Multiply d)");
+    test("a < b + c == d", "((a Lt (b Plus c)) Eq d)");
+    test("a || b && c || d", "((a Or (b And c)) Or d)");
+    test("a == b && c != d", "((a Eq b) And (c NotEq d))");
+    test("a.b.c * d", "(((a Dot b) Dot c) Multiply d)");
+    test("(a + b) * c", "((a Plus b) Multiply c)");
+    test("a..(b + c)", "(a .. (b Plus c))");
+    test("a ** b ** c", "(a Power (b Power c))");
+    test(
+        "a * b ** c ** d * e",
+        "((a Multiply (b Power (c Power d))) Multiply e)",
+    );
+    test("-a * b", "(Minus (a Multiply b))"); // Unary operators apply to the full expression that follows.
+}
+
+/// Check print statement argument parsing and validation.
+#[test]
+fn print_stmt_test() {
+    let parse = |code: &str| {
+        let (tokens, _) = Scanner::new().scan(SourceLine {
+            line: code.to_string(),
+            number: None,
+        });
+
+        Parser::new(tokens).parse()
+    };
+
+    let test = |code: &str, expected: &str| {
+        assert_eq!(parse(code).unwrap()[0].to_string(), expected, "{code}");
+    };
+
+    test("println;", "PrintStmt { Ptr -> Println {  } }");
+    test(
+        "print 1;",
+        "PrintStmt { Ptr -> Print { LiteralExpr { Ptr -> Number { 1 } } } }",
+    );
+    test(
+        "println 1, x;",
+        "PrintStmt { Ptr -> Println { LiteralExpr { Ptr -> Number { 1 } }, VarExpr { Ptr -> Var { x } } } }",
+    );
+
+    assert!(parse("print;").is_err());
+    assert!(parse("print 1,;").is_err());
+    assert!(parse("println 1 2;").is_err());
+}
+
+/// Check that optional layout tokens (newlines, EOF, whitespace) do not affect parsing.
+#[test]
+fn layout_tokens_test() {
+    let lines = [
+        "fn f(a: Number) {",
+        "  let x = a || true;",
+        "  x",
+        "}",
+        "f(2);",
+    ];
+
+    let parse = |options: ScanOptions| {
+        let mut scanner = Scanner::with_options(options);
+        let mut tokens = Vec::new();
+
+        for line in lines {
+            tokens.append(
+                &mut scanner
                     .scan(SourceLine {
-                        line: code,
+                        line: line.to_string(),
                         number: None,
                     })
-                    .unwrap(),
+                    .0,
             );
+        }
+        tokens.append(&mut scanner.finish());
 
-            assert!(parser.parse().is_ok()); // TODO: Display error.
+        Parser::new(tokens)
+            .parse()
+            .unwrap()
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>()
+    };
 
-            println!("..done");
+    assert_eq!(
+        parse(ScanOptions::default()),
+        parse(ScanOptions {
+            newlines: true,
+            eof: true,
+            trivia: true
+        })
+    );
+}
+
+/// Check that minified source code of all test code parses into the same AST, and minifies to itself.
+#[test]
+fn minify_roundtrip_test() {
+    for entry in fs::read_dir(CODE_PATH).unwrap_or_else(|e| panic!("{e}")) {
+        let path = entry.expect("invalid directory entry").path();
+        if path.extension().and_then(OsStr::to_str) == Some("nxs") {
+            let code = fs::read_to_string(&path).unwrap_or_else(|e| panic!("{e}"));
+            let ast = parse_source(&code).unwrap_or_else(|e| panic!("{path:?}: {e}"));
+
+            for options in [MinifyOptions::default(), MinifyOptions { rename: true }] {
+                let min = minify(&ast, options);
+                let reparsed = parse_source(&min).unwrap_or_else(|e| panic!("{min}: {e}"));
+
+                assert_eq!(minify(&reparsed, options), min, "{path:?}");
+                if !options.rename {
+                    assert_eq!(reparsed.to_string(), ast.to_string(), "{path:?}");
+                }
+            }
         }
     }
 }
+
+/// Check that deeply nested input results in a parse error instead of a stack overflow.
+#[test]
+fn nesting_limit_test() {
+    let test = |code: String| {
+        let (tokens, _) = Scanner::new().scan(SourceLine {
+            line: code,
+            number: None,
+        });
+
+        Parser::new(tokens).parse().map(|_| ())
+    };
+
+    assert!(test(format!("let x = {}1{};", "(".repeat(50), ")".repeat(50))).is_ok());
+    assert!(test(format!("{}{}", "{".repeat(50), "}".repeat(50))).is_ok());
+
+    for code in [
+        format!("let x = {}1{};", "(".repeat(10000), ")".repeat(10000)),
+        format!("{}{}", "{".repeat(10000), "}".repeat(10000)),
+        format!("print {}1;", "-".repeat(10000)),
+        "(".repeat(10000),
+        format!("print 1{};", " + 1".repeat(10000)),
+        format!("print 1{};", " ** 1".repeat(10000)),
+        format!("print 1{};", " |> abs".repeat(10000)),
+    ] {
+        assert!(matches!(
+            test(code).unwrap_err().kind(),
+            ParseErrorKind::NestingTooDeep(_)
+        ));
+    }
+}
+
+/// Check that deeply nested input results in a clean diagnostic through the whole pipeline.
+#[test]
+fn nesting_limit_diagnostic_test() {
+    let code = format!("let x = {}1{};", "(".repeat(10000), ")".repeat(10000));
+    let session = Driver::new(DriverOptions::default()).run_source("deep.nxs", &code);
+
+    assert_eq!(session.diagnostics.len(), 1);
+
+    let rendered = session.render(&session.diagnostics[0]);
+    assert!(rendered.contains("nesting too deep"), "{rendered}");
+    assert!(rendered.lines().all(|l| l.len() < 120), "{rendered}");
+}
+
+/// Check that the deepest accepted nesting of each construct is handled by all AST consumers on a small stack.
+#[test]
+fn nesting_limit_stack_test() {
+    let constructs: [fn(usize) -> String; 7] = [
+        |n| format!("let x = {}1{};", "(".repeat(n), ")".repeat(n)),
+        |n| format!("{}{}", "{".repeat(n), "}".repeat(n)),
+        |n| format!("print {}1;", "-".repeat(n)),
+        |n| format!("{}1{};", "if true { ".repeat(n), " }".repeat(n)),
+        |n| {
+            format!(
+                "fn f(x: Number) -> Number {{ x }} {}1{};",
+                "f(".repeat(n),
+                ")".repeat(n)
+            )
+        },
+        |n| format!("print 1{};", " + 1".repeat(n)),
+        |n| format!("print 1{};", " ** 1".repeat(n)),
+    ];
+
+    std::thread::Builder::new()
+        .stack_size(2 * 1024 * 1024)
+        .spawn(move || {
+            for construct in constructs {
+                let depth = (1..)
+                    .find(|n| parse_source(&construct(n + 1)).is_err())
+                    .unwrap();
+                let ast = parse_source(&construct(depth)).unwrap();
+
+                let _ = ast.to_string();
+                let _ = minify(&ast, MinifyOptions::default());
+                let _ = Interpreter::with_output(std::io::sink()).run(&ast);
+            }
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+}
+
+/// Check node declarations: ports are unique, and a node expression is not mistaken for a declaration.
+#[test]
+fn node_decl_test() {
+    let e = parse_source("node Gain { in x: Number; out x: Number; }").unwrap_err();
+    assert!(
+        matches!(&e, FrontError::Parse(p) if matches!(p.kind(), ParseErrorKind::DuplicatePort(_))),
+        "{e}"
+    );
+
+    assert!(parse_source("node Gain { x: Number; }").is_err());
+    assert!(parse_source("node Gain { in x: Number }").is_err());
+
+    let ast = parse_source("node Gain { out y: Number; }\nnode g;").unwrap();
+    assert!(matches!(ast[0].kind, StmtKind::NodeDecl(_)));
+    assert!(matches!(ast[1].kind, StmtKind::Expr(_)));
+}
+
+/// Check that keywords can be used as names with the raw identifier prefix.
+#[test]
+fn raw_identifier_test() {
+    let mut output = Vec::new();
+    let ast = parse_source("let r#group = 2; for r#in in 0..r#group { print r#in; };").unwrap();
+    Interpreter::with_output(&mut output).run(&ast).unwrap();
+    assert_eq!(output, b"01");
+
+    // The minified source code keeps the prefix, to parse again.
+    assert_eq!(
+        minify(&ast, MinifyOptions::default()),
+        "let r#group=2;for r#in in 0..r#group{print r#in;};"
+    );
+
+    let test = |code: &str, note: &str| match parse_source(code) {
+        Err(FrontError::Parse(e)) => {
+            assert!(
+                matches!(e.kind(), ParseErrorKind::KeywordAsIdentifier(_)),
+                "{code}"
+            );
+            assert_eq!(e.notes(), [note], "{code}");
+        }
+        result => panic!("{code}: {result:?}"),
+    };
+
+    test("let group = 1;", "use the raw identifier 'r#group' as name");
+    test(
+        "for in in 0..3 {};",
+        "use the raw identifier 'r#in' as name",
+    );
+    test("fn Node() {}", "use the raw identifier 'r#Node' as name");
+}
+
+/// Check that only variables, members and indexed values can be assigned to.
+#[test]
+fn assignment_target_test() {
+    for code in [
+        "x = 1;",
+        "x.y.z = 1;",
+        "f().y = 1;",
+        "s[0] = 1;",
+        "s[0][1..2] = 1;",
+    ] {
+        assert!(parse_source(code).is_ok(), "{code}");
+    }
+
+    for code in [
+        "1 + 2 = 3;",
+        "1 = 2;",
+        "f() = 1;",
+        "x.f() = 1;",
+        "-x = 1;",
+        "(x) = 1;",
+    ] {
+        assert!(
+            matches!(parse_source(code), Err(FrontError::Parse(e)) if matches!(e.kind(), ParseErrorKind::InvalidAssignmentTarget)),
+            "{code}"
+        );
+    }
+}
+
+/// Check the targeted errors for chained comparisons and assignments in conditions.
+#[test]
+fn comparison_misuse_test() {
+    let kind = |code: &str| match parse_source(code) {
+        Err(FrontError::Parse(e)) => Some(e.kind().code()),
+        _ => None,
+    };
+
+    assert_eq!(kind("a < b < c;"), Some("NXS0121"));
+    assert_eq!(kind("a + 1 >= b <= c;"), Some("NXS0121"));
+    assert_eq!(kind("x == a < b > c;"), Some("NXS0121"));
+    assert_eq!(kind("(a < b) < c;"), None);
+    assert_eq!(kind("a < b == c < d;"), None);
+    assert_eq!(kind("a < b && b < c;"), None);
+
+    assert_eq!(kind("if x = 1 { };"), Some("NXS0122"));
+    assert_eq!(kind("while x = 1 { };"), Some("NXS0122"));
+    assert_eq!(kind("if x == 1 { };"), None);
+}
+
+/// Check that literal ranges counting up from a greater start are rejected, unless they count down with a step.
+#[test]
+fn range_step_test() {
+    let kind = |code: &str| match parse_source(code) {
+        Err(FrontError::Parse(e)) => Some(e.kind().code()),
+        _ => None,
+    };
+
+    assert_eq!(kind("for i in 10..0 { };"), Some("NXS0123"));
+    assert_eq!(kind("1..=0.5;"), Some("NXS0123"));
+    assert_eq!(kind("10..0 step -1;"), None);
+    assert_eq!(kind("n..0;"), None);
+    assert_eq!(kind("0..0;"), None);
+    assert_eq!(kind("0..10 step n + 1;"), Some("NXS0107"));
+    assert_eq!(kind("0..10 step !n;"), Some("NXS0107"));
+    assert_eq!(kind("0..10 step;"), Some("NXS0107"));
+
+    match parse_source("for i in 3..=1 { };") {
+        Err(FrontError::Parse(e)) => assert_eq!(
+            e.notes(),
+            ["count down with a negative step, like '3..=1 step -1'"]
+        ),
+        result => panic!("{result:?}"),
+    }
+}
+
+/// Check that casts are parsed in any edition (though 'as' is reserved from edition 2024), to value types only.
+#[test]
+fn cast_test() {
+    let kind = |code: &str| match parse_source(code) {
+        Err(FrontError::Parse(e)) => Some(e.kind().code()),
+        _ => None,
+    };
+
+    assert_eq!(kind("x as String;"), None);
+    assert_eq!(kind("// edition: 2024\nx as String;"), None);
+    assert_eq!(kind("// edition: 2024\nlet as = 1;"), Some("NXS0110"));
+    assert_eq!(kind("let as = 1; as as bool;"), None);
+    assert_eq!(kind("x as Node;"), Some("NXS0124"));
+    assert_eq!(kind("x as Strin;"), Some("NXS0115"));
+
+    // A cast binds stronger than binary operators.
+    let ast = parse_source("1 + x as Number;").unwrap();
+    assert_eq!(
+        dump(&ast, DumpFormat::Sexp),
+        "(ExprStmt (Binary Plus (Number 1) (Cast Number (Var x))))"
+    );
+}
+
+/// Check enum declarations: variants are unique, and 'enum' is allowed in declarations from edition 2024.
+#[test]
+fn enum_decl_test() {
+    let kind = |code: &str| match parse_source(code) {
+        Err(FrontError::Parse(e)) => Some(e.kind().code()),
+        _ => None,
+    };
+
+    assert_eq!(kind("enum Mode { Idle, Run, }"), None);
+    assert_eq!(kind("enum Mode { Idle, Idle }"), Some("NXS0125"));
+    assert_eq!(kind("enum Mode { Idle Run }"), Some("NXS0102"));
+    assert_eq!(kind("// edition: 2024\nenum Mode { Idle }"), None);
+    assert_eq!(kind("// edition: 2024\nlet enum = 1;"), Some("NXS0110"));
+
+    // Without a declaration, 'enum' is an identifier in the first edition.
+    let ast = parse_source("let enum = 1; enum;").unwrap();
+    assert!(matches!(ast[1].kind, StmtKind::Expr(_)));
+}
+
+/// Check that parse errors show tokens as written in source code.
+#[test]
+fn token_message_test() {
+    let test = |code: &str, message: &str| match parse_source(code) {
+        Err(FrontError::Parse(e)) => assert_eq!(e.kind().to_string(), message, "{code}"),
+        result => panic!("{code}: {result:?}"),
+    };
+
+    test("fn f( {}", "unexpected token '{'");
+    test("let fn = 1;", "keyword 'fn' cannot be used as identifier");
+    test("print (1;", "expected ')'");
+}
@@ -1,4 +1,4 @@
-use nexus_rs::{scanner::Scanner, source_line::SourceLine, token::Token};
+use nexus_rs::{scanner::Scanner, source_line::SourceLine, symbol::Symbol, token::Token};
 
 #[cfg(test)]
 use pretty_assertions::assert_eq;
@@ -8,16 +8,17 @@ fn token_test() {
     let test = |input: &str, expected: Token| {
         let mut s = Scanner::new();
 
-        match s.scan(SourceLine {
+        let (tokens, errors) = s.scan(SourceLine {
             line: input.to_string(),
             number: None,
-        }) {
-            Ok(tokens) => {
-                assert_eq!(tokens.len(), 1);
-                assert_eq!(tokens.into_iter().next().unwrap(), expected);
-            }
-            Err(e) => assert!(false, "error: {}", e),
+        });
+
+        if let Some(e) = errors.first() {
+            panic!("error: {e}");
         }
+
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(tokens.into_iter().next().unwrap(), expected);
     };
 
     test("(", Token::LeftParen);
@@ -32,13 +33,17 @@ fn token_test() {
     test("+", Token::Plus);
     test("-", Token::Minus);
     test("->", Token::Arrow);
+    test("-/>", Token::NotArrow);
     test("*", Token::Star);
+    test("**", Token::StarStar);
     test("/", Token::Slash);
     test("\\", Token::BackSlash);
     test("%", Token::Percent);
+    test("?", Token::Question);
     test(",", Token::Comma);
     test(".", Token::Dot);
     test("..", Token::Range);
+    test("..=", Token::RangeInclusive);
     test("_", Token::Underscore);
     test("=", Token::Is);
     test("==", Token::Eq);
@@ -52,6 +57,7 @@ fn token_test() {
     //test("||", Token::Or);           // NOTE: Selected in token stream post-processing.
     //test("||", Token::EmptyClosure); // NOTE: Selected in token stream post-processing.
     test("|", Token::Pipe);
+    test("|>", Token::Pipeline);
     test("true", Token::True);
     test("false", Token::False);
     test("const", Token::Const);
@@ -63,6 +69,11 @@ fn token_test() {
     test("for", Token::For);
     test("in", Token::In);
     test("while", Token::While);
+    test("loop", Token::Loop);
+    test("time", Token::Time);
+    test("nil", Token::Nil);
+    test("try", Token::Try);
+    test("defer", Token::Defer);
     test("return", Token::Return);
     test("use", Token::Use);
     test("bool", Token::BoolId);
@@ -71,10 +82,77 @@ fn token_test() {
     test("Number", Token::NumberId);
     test("String", Token::StringId);
     test("print", Token::Print);
+    test("println", Token::Println);
     test("node", Token::Node);
     test("group", Token::Group);
 
-    test("2.8539", Token::Number(2.8539f64));
-    test("top_id", Token::Identifier("top_id".to_string()));
-    test("\"Hi\"", Token::String("Hi".to_string()));
+    test("2.8539", Token::Number(2.8539f64, "2.8539".into()));
+    test("top_id", Token::Identifier(Symbol::intern("top_id")));
+    test("\"Hi\"", Token::String("Hi".into()));
+}
+
+#[test]
+fn maximal_munch_test() {
+    let test = |input: &str, expected: Vec<Token>| {
+        let mut s = Scanner::new();
+
+        let (tokens, errors) = s.scan(SourceLine {
+            line: input.to_string(),
+            number: None,
+        });
+
+        if let Some(e) = errors.first() {
+            panic!("error: {e}");
+        }
+
+        assert_eq!(tokens, expected, "{input}");
+    };
+
+    let id = |i: &str| Token::Identifier(Symbol::intern(i));
+
+    // Keywords are only recognized for complete words:
+    test("form", vec![id("form")]);
+    test("for m", vec![Token::For, id("m")]);
+    test("fn_", vec![id("fn_")]);
+    test("in2", vec![id("in2")]);
+    test("letx", vec![id("letx")]);
+    test("Numbers", vec![id("Numbers")]);
+    test("truefalse", vec![id("truefalse")]);
+
+    // Ranges:
+    test("..=", vec![Token::RangeInclusive]);
+    test(".. =", vec![Token::Range, Token::Is]);
+    test("..==", vec![Token::RangeInclusive, Token::Is]);
+    test("...", vec![Token::Range, Token::Dot]);
+    test(
+        "0..=10",
+        vec![
+            Token::Number(0.0, "0".into()),
+            Token::RangeInclusive,
+            Token::Number(10.0, "10".into()),
+        ],
+    );
+    test(
+        "0..10",
+        vec![
+            Token::Number(0.0, "0".into()),
+            Token::Range,
+            Token::Number(10.0, "10".into()),
+        ],
+    );
+    test("a..=b", vec![id("a"), Token::RangeInclusive, id("b")]);
+
+    // Operators:
+    test("===", vec![Token::Eq, Token::Is]);
+    test("<==", vec![Token::LtEq, Token::Is]);
+    test(">==", vec![Token::GtEq, Token::Is]);
+    test("!==", vec![Token::NotEq, Token::Is]);
+    test("&&&", vec![Token::And, Token::Amp]);
+    test("->>", vec![Token::Arrow, Token::Gt]);
+    test("-->", vec![Token::Minus, Token::Arrow]);
+    test("-/>>", vec![Token::NotArrow, Token::Gt]);
+    test("-/ >", vec![Token::Minus, Token::Slash, Token::Gt]);
+    test("***", vec![Token::StarStar, Token::Star]);
+    test("||>", vec![Token::Pipe, Token::Pipeline]);
+    test("|>>", vec![Token::Pipeline, Token::Gt]);
 }
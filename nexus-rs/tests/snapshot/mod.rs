@@ -0,0 +1,33 @@
+//! Golden-file (snapshot) test harness.
+//!
+//! Run the tests with `UPDATE_SNAPSHOTS=1` to (re)generate the snapshot files instead of comparing against them.
+
+use pretty_assertions::assert_eq;
+use std::{env, fs, path::Path};
+
+/// Environment variable to set for regenerating snapshots.
+pub const UPDATE_ENV: &str = "UPDATE_SNAPSHOTS";
+
+/// Check whether snapshots are to be regenerated.
+pub fn updating() -> bool {
+    env::var(UPDATE_ENV).is_ok_and(|v| !v.is_empty() && v != "0")
+}
+
+/// Compare output against the snapshot file at the given path, or write it when updating snapshots.
+pub fn assert_snapshot(path: &Path, actual: &str) {
+    let actual = format!("{}\n", actual.trim_end());
+
+    if updating() {
+        fs::write(path, &actual).unwrap_or_else(|e| panic!("{path:?}: {e}"));
+        return;
+    }
+
+    let expected = fs::read_to_string(path).unwrap_or_else(|e| {
+        panic!("{path:?}: {e} (run with {UPDATE_ENV}=1 to create the snapshot)")
+    });
+
+    assert_eq!(
+        expected, actual,
+        "{path:?} is out of date (run with {UPDATE_ENV}=1 to update the snapshot)"
+    );
+}